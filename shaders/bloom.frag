@@ -0,0 +1,41 @@
+#version 450
+
+layout (location = 0) in vec2 coord;
+
+layout (location = 0) out vec4 fragColor;
+
+layout (set = 0, binding = 0) uniform sampler2D source_color;
+
+layout (push_constant) uniform PushConstants {
+	// The offset in pixels between neighboring taps, along the axis being blurred
+	vec2 direction;
+
+	// The brightness above which particles glow. The second pass blurs the already-thresholded first, so uses zero
+	float threshold;
+} push;
+
+// Gather parameters. Taps are spaced apart and sampled between pixels, widening the glow at little cost.
+const int tapRadius = 8;
+const float sigma = 4.0;
+
+// Keep only the color above the threshold, preserving its hue.
+vec3 brightPass(vec3 color) {
+	const float brightness = max(color.r, max(color.g, color.b));
+	return color * max(brightness - push.threshold, 0.0) / max(brightness, 0.0001);
+}
+
+void main() {
+	const vec2 texelSize = 1.0 / vec2(textureSize(source_color, 0));
+	const vec2 uv = gl_FragCoord.xy * texelSize;
+
+	// Separable Gaussian blur along a single axis.
+	vec3 result = vec3(0.0);
+	float totalWeight = 0.0;
+	for(int i = -tapRadius; i <= tapRadius; i++) {
+		const float weight = exp(-0.5 * float(i*i) / (sigma*sigma));
+		result += weight * brightPass(texture(source_color, uv + float(i) * push.direction * texelSize).rgb);
+		totalWeight += weight;
+	}
+
+	fragColor = vec4(result / totalWeight, 1.0);
+}
@@ -0,0 +1,106 @@
+#version 450
+
+layout (location = 0) in vec2 coord;
+
+layout (location = 0) out vec4 fragColor;
+layout (location = 1) out float sceneDepth;
+layout (location = 2) out vec4 trailColor;
+
+layout (set = 0, binding = 0) uniform sampler2D particle_color;
+layout (set = 0, binding = 1) uniform sampler2D particle_depth;
+layout (set = 0, binding = 2) uniform sampler2D previous_trails;
+layout (set = 0, binding = 3) uniform sampler2D bloom_color;
+
+layout (push_constant) uniform PushConstants {
+	float aperture;
+	float focus_distance;
+
+	// The fraction of the previous frame's trails which remains, where zero disables the effect
+	float trail_decay;
+
+	// The brightness of the glow around the brightest particles, where zero disables the effect
+	float bloom_intensity;
+} push;
+
+const float pi = 3.14159265358;
+
+// Distances must match those used in `particles.vert`
+const float far = 8.0;
+const float near = 0.03125;
+
+// Gather parameters. The maximum blur radius is in pixels.
+const float maxBlurRadius = 16.0;
+const uint sampleCount = 48;
+const float goldenAngle = 2.39996323;
+
+// Calculate inverse of projection on z coordinate.
+float linearDepth(float depth) {
+	const float farNearDiff = far - near;
+	return (-2.0*far*near) / ((depth - (far + near)/farNearDiff)*farNearDiff);
+}
+
+// Blend the fresh particles over the faded trails of previous frames, keeping the result as the next frame's trails,
+// then add the glow. The glow is kept out of the trails so that it is not accumulated over frames.
+void writeParticles(vec3 fresh, ivec2 pixel) {
+	vec3 color = fresh;
+	if(push.trail_decay > 0.0) {
+		color = max(color, push.trail_decay * texelFetch(previous_trails, pixel, 0).rgb);
+	}
+	trailColor = vec4(color, 1.0);
+	if(push.bloom_intensity > 0.0) {
+		color += push.bloom_intensity * texelFetch(bloom_color, pixel, 0).rgb;
+	}
+	fragColor = vec4(color, 1.0);
+}
+
+// The radius in pixels of the circle of confusion for a particle at the given distance.
+float circleOfConfusion(float dist, float viewHeight) {
+	return min(push.aperture * viewHeight * abs(dist - push.focus_distance) / dist, maxBlurRadius);
+}
+
+void main() {
+	const ivec2 size = textureSize(particle_color, 0);
+	const ivec2 pixel = ivec2(gl_FragCoord.xy);
+	const vec3 center = texelFetch(particle_color, pixel, 0).rgb;
+
+	// Forward the nearest particle depth for the fractal pass, which is unaffected by the blur.
+	const float centerDepth = texelFetch(particle_depth, pixel, 0).x;
+	sceneDepth = centerDepth;
+
+	// Pass particles through unchanged when the effect is disabled.
+	if(push.aperture <= 0.0) {
+		writeParticles(center, pixel);
+		return;
+	}
+
+	const float viewHeight = float(size.y);
+	vec3 result = vec3(0.0);
+
+	// Keep in-focus particles sharp, and spread the energy of blurred particles over their circle of confusion.
+	// A depth of 1.0 is the clear value, where no particle was drawn.
+	if(centerDepth < 1.0) {
+		const float coc = circleOfConfusion(linearDepth(centerDepth), viewHeight);
+		result += coc < 1.0 ? center : center / (pi*coc*coc);
+	}
+
+	// Gather the blurred particles whose circle of confusion covers this pixel.
+	// Each sample of the spiral represents an equal area of the maximum blur disk.
+	const float sampleArea = maxBlurRadius*maxBlurRadius / float(sampleCount);
+	for(uint i = 0; i < sampleCount; i++) {
+		const float radius = sqrt((float(i) + 0.5) / float(sampleCount)) * maxBlurRadius;
+		const float theta = float(i) * goldenAngle;
+		const ivec2 samplePixel = clamp(pixel + ivec2(round(radius * vec2(cos(theta), sin(theta)))), ivec2(0), size - 1);
+
+		const float sampleDepth = texelFetch(particle_depth, samplePixel, 0).x;
+		if(sampleDepth >= 1.0) {
+			continue;
+		}
+		const float coc = circleOfConfusion(linearDepth(sampleDepth), viewHeight);
+		if(coc < 1.0 || radius > coc) {
+			continue;
+		}
+		result += texelFetch(particle_color, samplePixel, 0).rgb * sampleArea / (coc*coc);
+	}
+
+	writeParticles(result, pixel);
+}
@@ -0,0 +1,27 @@
+#version 450
+
+layout (location = 0) in vec2 coord;
+
+layout (location = 0) out float minDepth;
+
+#ifdef SINGLE_SAMPLE
+layout (input_attachment_index = 0, set = 0, binding = 0) uniform subpassInput particle_depth;
+#else
+layout (input_attachment_index = 0, set = 0, binding = 0) uniform subpassInputMS particle_depth;
+#endif
+
+void main() {
+#ifdef SINGLE_SAMPLE
+	// Without multisampling, the depth only needs copying to an image later passes may sample directly.
+	minDepth = subpassLoad(particle_depth).x;
+#else
+	// Resolve the multisampled particle depth to the nearest sample, so later passes may sample it directly.
+	// The engine compiles a variant for each supported count of samples per pixel
+	const int sampleCount = SAMPLE_COUNT;
+	float depth = subpassLoad(particle_depth, 0).x;
+	for(int i = 1; i < sampleCount; i++) {
+		depth = min(subpassLoad(particle_depth, i).x, depth);
+	}
+	minDepth = depth;
+#endif
+}
@@ -0,0 +1,428 @@
+// The distance estimators of the fractals, shared by the fractal shader and the compute pass placing particles on its surface.
+// The including shader must declare the `push` constants with the audio state and `time` the fractal is animated by,
+// and the `runtime` constants with the selected fractal and its tunables
+
+mat3 buildRot3(vec3 u, float theta) {
+	float c = cos(theta);
+	float cC = 1.0 - c;
+	float s = sin(theta);
+	float sC = 1.0 - s;
+	return mat3(
+		c+u.x*u.x*cC, u.y*u.x*cC+u.z*s, u.z*u.x*cC-u.y*s,
+		u.x*u.y*cC-u.z*s, c+u.y*u.y*cC, u.z*u.y*cC+u.x*s,
+		u.x*u.z*cC+u.y*s, u.y*u.z*cC-u.x*s, c+u.z*u.z*cC
+	);
+}
+
+vec4 multiplyQuaternions(vec4 q, vec4 r) {
+	return vec4(cross(q.xyz, r.xyz) + q.xyz*r.w + q.w*r.xyz, q.w*r.w - dot(q.xyz, r.xyz));
+}
+
+vec3 safe_normalize(vec3 t) {
+	if(length(t) < 0.000001) {
+		return vec3(1.0, 0.0, 0.0);
+	}
+	return normalize(t);
+}
+vec4 safe_normalize(vec4 t) {
+	if(length(t) < 0.000001) {
+		return vec4(0.0, 0.0, 0.0, 1.0);
+	}
+	return normalize(t);
+}
+
+float boundReflect(float x, float b) {
+	float r = mod(x + b, 4.0*b);
+	if(r < 2.0*b) {
+		return r - b;
+	} else {
+		return 3.0*b - r;
+	}
+}
+
+mat3 frame_constant_mandelbulb_colorRotato;
+mat3 frame_constant_klein_colorRotato;
+mat3 frame_constant_sierpinski_rotato1;
+mat3 frame_constant_sierpinski_rotato2;
+mat3 frame_constant_quaternion_colorRotato;
+vec4 frame_constant_quaternion_q;
+vec4 frame_constant_quaternion_c;
+mat3 frame_constant_menger_rotato;
+mat3 frame_constant_tetrahedron_rotato;
+
+vec4 orbitTrap;
+
+#ifdef USER_FRACTAL
+// The distance estimator of the `user_fractal` file, included when the app compiles the fractal shader at runtime.
+// It must define `float userDistanceEstimator(vec3 t)`, and may write the orbit trap to color the surface.
+// Its tunables are read from the runtime constants like those of the built-in fractals
+#include "user_fractal.glsl"
+#endif
+
+float distanceEstimator(vec3 t) {
+	orbitTrap = vec4(1.0, 1.0, 1.0, 1.0);
+
+	// Mandelbox.
+	if(runtime.distance_estimator_id == 1) {
+		int maxIterations = int(runtime.fractal_iterations);
+		const float reScale = 4.8;
+		t *= reScale;
+		vec3 s = t;
+		const float mandelboxScale = 0.25*cos(0.075 * push.time) - runtime.fractal_fold_scale;
+		float DEfactor = 1.0;
+		float r2 = 1.0;
+		const float maxR2 = runtime.fractal_bailout*runtime.fractal_bailout;
+		const float BVR = sqrt(maxR2);
+		for (int i = 0; i < maxIterations; i++) {
+			if(s.x>1.0){s.x=2.0-s.x;}else if(s.x<-1.0){s.x=-2.0-s.x;}
+			if(s.y>1.0){s.y=2.0-s.y;}else if(s.y<-1.0){s.y=-2.0-s.y;}
+			if(s.z>1.0){s.z=2.0-s.z;}else if(s.z<-1.0){s.z=-2.0-s.z;}
+
+			r2 = dot(s, s);
+			if (r2 < 0.25) {
+				s *= 4.0;
+				DEfactor *= 4.0;
+			} else if(r2 < 1.0) {
+				s /= r2;
+				DEfactor /= r2;
+			}
+
+			orbitTrap.x = min(orbitTrap.x, length(s/BVR - push.reactive_bass.xyz)/1.25);
+			orbitTrap.y = min(orbitTrap.y, length(s/BVR - push.reactive_mids.xyz)/1.25);
+			orbitTrap.z = min(orbitTrap.z, length(s/BVR - push.reactive_high.xyz)/1.25);
+
+			s = s*mandelboxScale + t;
+			DEfactor = DEfactor*abs(mandelboxScale) + 1.0;
+		
+			if(r2 > maxR2) break;
+		}
+		return (length(s)-BVR)/abs(DEfactor) / reScale;
+	}
+	// Mandelbulb.
+	else if(runtime.distance_estimator_id == 2) {
+		int maxIterations = int(runtime.fractal_iterations);
+		const float reScale = 1.85;
+		t *= reScale;
+		vec3 s = t;
+		float power = 9. + 2.0*boundReflect(0.0375*push.time + 1.0, 1.0);
+		float dr = 1.0;
+		float r = 0.0;
+
+		for(int i = 0; i < maxIterations; i++) {
+			r = length(s);
+			const float b = runtime.fractal_bailout;
+			if (r > b) break;
+
+			float theta = acos(s.z/r);
+			float phi = atan(s.y, s.x);
+			dr = pow(r, power-1.0)*power*dr + 1.0;
+
+			r = pow(r, power);
+			theta *= power;
+			phi *= power;
+
+			s = r*vec3(sin(theta)*cos(phi), sin(theta)*sin(phi), cos(theta));
+			s += t;
+
+			orbitTrap.xyz = min(orbitTrap.xyz, abs((s - (push.reactive_high.xyz + push.reactive_bass.xyz)/2.0) * frame_constant_mandelbulb_colorRotato)/1.25);
+		}
+		return min(0.5*log(r)*r/dr, 3.5) / reScale;
+	}
+	// Klein-inspired.
+	else if(runtime.distance_estimator_id == 3) {
+		int maxIterations = int(runtime.fractal_iterations);
+		const float reScale = 0.8;
+		t = reScale*t;
+		vec3 s = t;
+
+		float anim = 1.275 + 0.085*sin(0.2*push.time);
+		float scale = 1.0;
+		float theta = 0.1 * push.time;
+		float ct = cos(theta);
+		float st = sin(theta);
+		mat2 rotato = mat2(ct, st, -st, ct);
+
+		for(int i = 0; i < maxIterations; i++) {
+			if (i == 2) {
+				s.xy *= rotato;
+			}
+
+			s = -1.0 + 2.0*fract(0.5*s + 0.5);
+
+			float r2 = dot(s,s);
+		
+			float k = anim/r2;
+			s *= k;
+			scale *= k;
+
+			orbitTrap.xyz = min(orbitTrap.xyz, abs((s - (push.reactive_high.xyz + push.reactive_bass.xyz)/2.0) * frame_constant_klein_colorRotato));
+		}
+	
+		return max((0.25*abs(s.z)/scale)/reScale, length(t/reScale)-0.62);
+	}
+	// Menger Sponge.
+	else if(runtime.distance_estimator_id == 4) {
+		int maxIterations = int(runtime.fractal_iterations);
+
+		const float reScale = 1.32;
+		t *= reScale;
+		vec3 s = t;
+
+		s = s + 0.5; //center it by changing position and scale
+		float xx=abs(s.x-0.5)-0.5, yy=abs(s.y-0.5)-0.5, zz=abs(s.z-0.5)-0.5;
+		float d1=max(xx,max(yy,zz)); //distance to the box
+		float d=d1; //current computed distance
+		float p=1.0;
+		float mengerScale = runtime.fractal_fold_scale;
+		float halfScale = mengerScale / 2.0;
+
+		orbitTrap.xyz = abs(vec3(xx/1.2, yy/1.2, zz/1.2));
+
+		float theta = 0.575*sin(0.055*push.time);
+		mat3 rotato = buildRot3(safe_normalize(cross(push.smooth_bass.xyz, push.smooth_mids.xyz)), theta);
+
+		for (int i = 0; i < maxIterations; i++) {
+			p *= mengerScale;
+			float xa = mod(s.x*p, mengerScale);
+			float ya = mod(s.y*p, mengerScale);
+			float za = mod(s.z*p, mengerScale);
+
+			float xx=0.5-abs(xa-halfScale), yy=0.5-abs(ya-halfScale), zz=0.5-abs(za-halfScale);
+			d1=min(max(xx,zz),min(max(xx,yy),max(yy,zz))) / p; //distance inside the 3 axis-aligned square tubes
+
+			d=max(d,d1); //intersection
+
+			vec3 q = vec3(xx, yy, zz);
+			orbitTrap.xyz = max(orbitTrap.xyz, abs(vec3(dot(q, push.reactive_bass.xyz), dot(q, push.reactive_mids.xyz), dot(q, push.reactive_high.xyz))));
+
+			const vec3 halfVec = vec3(0.5);
+			s = (s - halfVec)*rotato + halfVec;
+		}
+		return d/reScale;
+	}
+	// Sierpiński-inspired.
+	else if(runtime.distance_estimator_id == 5) {
+		int maxIterations = int(runtime.fractal_iterations);
+		const float scale = runtime.fractal_fold_scale;
+		const float reScale = 1.375;
+
+		t *= reScale;
+		vec3 s = t;
+		const vec3 center = vec3(sqrt(0.5), sqrt(0.3), sqrt(0.2));
+		float r2 = dot(s, s);
+		float DEfactor = 1.0;
+
+		for(int i = 0; i < maxIterations && r2 < runtime.fractal_bailout*runtime.fractal_bailout; i++) {
+			s *= frame_constant_sierpinski_rotato1;
+
+			if(s.x+s.y<0.0){float x1=-s.y;s.y=-s.x;s.x=x1;}
+			if(s.x+s.z<0.0){float x1=-s.z;s.z=-s.x;s.x=x1;}
+			if(s.y+s.z<0.0){float y1=-s.z;s.z=-s.y;s.y=y1;}
+
+			s *= frame_constant_sierpinski_rotato2;
+
+			s = scale*s - (scale - 1.0)*center;
+			r2 = dot(s, s);
+
+			orbitTrap.x = min(orbitTrap.x, length(s - push.reactive_bass.xyz)/2.0);
+			orbitTrap.y = min(orbitTrap.y, length(s - push.reactive_mids.xyz)/2.0);
+			orbitTrap.z = min(orbitTrap.z, length(s - push.reactive_high.xyz)/2.0);
+
+			DEfactor *= scale;
+		}
+		return (sqrt(r2) - 2.0) / DEfactor / reScale;
+	}
+	// Quaternion Julia.
+	else if(runtime.distance_estimator_id == 6) {
+		int maxIterations = int(runtime.fractal_iterations);
+		const float reScale = 1.85;
+		t *= reScale;
+		float power = 4.0 + sin(0.025*push.time);
+
+		// Store the running derivative as a quaternion.
+		float dr = 1.0;
+		float r = 0.0;
+
+		// Create a quaternion from the position.
+		vec4 s = vec4(0.0, t);
+		vec4 s_0 = s;
+
+		for(int i = 0; i < maxIterations; i++) {
+			r = length(s);
+			const float b = runtime.fractal_bailout;
+			if (r > b) break;
+
+			//*/ Julia-style.
+			// Get the derivative.
+			dr = power*pow(r, power-1.0)*dr;
+			// s = q*s^p + c; using quaternion algebra.
+			float phi = acos(s.w/r);
+			s = pow(r, power)*vec4(sin(power*phi)*s.xyz/r, cos(power*phi));
+			s = multiplyQuaternions(frame_constant_quaternion_q, s) + frame_constant_quaternion_c;
+			/*/
+			// Mandelbrot-style.
+			// Get the derivative.
+			dr = 2*r*dr;
+			// s = s^2 + s_0; using quaternion algebra.
+			s = multiplyQuaternions(s, s) + s_0; //*/
+
+			orbitTrap.xyz = min(orbitTrap.xyz, abs((s.xyz - (push.reactive_high.xyz + push.reactive_bass.xyz)/2.0) * frame_constant_quaternion_colorRotato)/3.5);
+		}
+		orbitTrap.xyz = sqrt(sqrt(orbitTrap.xyz));
+		return 0.6 * min(log(r)*r/dr, 3.5) / reScale;
+	}
+
+	// Mandelbox, with the folds of the classic formula.
+	else if(runtime.distance_estimator_id == 7) {
+		int maxIterations = int(runtime.fractal_iterations);
+		const float reScale = 4.8;
+		t *= reScale;
+		vec3 s = t;
+		const float mandelboxScale = -runtime.fractal_fold_scale - 0.2*boundReflect(0.04*push.time, 1.0);
+		const float foldLimit = 1.0;
+		const float fixedR2 = 1.0;
+		const float minR2 = 0.25 + 0.1*sin(0.06*push.time);
+		const float BVR = 4.0;
+		float DEfactor = 1.0;
+		for(int i = 0; i < maxIterations; i++) {
+			s = 2.0*clamp(s, -foldLimit, foldLimit) - s;
+
+			float r2 = dot(s, s);
+			if(r2 < minR2) {
+				s *= fixedR2/minR2;
+				DEfactor *= fixedR2/minR2;
+			} else if(r2 < fixedR2) {
+				s *= fixedR2/r2;
+				DEfactor *= fixedR2/r2;
+			}
+
+			orbitTrap.x = min(orbitTrap.x, length(s/BVR - push.reactive_bass.xyz)/1.25);
+			orbitTrap.y = min(orbitTrap.y, length(s/BVR - push.reactive_mids.xyz)/1.25);
+			orbitTrap.z = min(orbitTrap.z, length(s/BVR - push.reactive_high.xyz)/1.25);
+
+			s = mandelboxScale*s + t;
+			DEfactor = DEfactor*abs(mandelboxScale) + 1.0;
+		}
+		return length(s)/abs(DEfactor) / reScale;
+	}
+	// Menger sponge, folded into itself about the axes and gently twisted between iterations.
+	else if(runtime.distance_estimator_id == 8) {
+		int maxIterations = int(runtime.fractal_iterations);
+		const float reScale = 1.6;
+		t *= reScale;
+		vec3 s = t;
+		const float mengerScale = runtime.fractal_fold_scale;
+		float DEfactor = 1.0;
+		for(int i = 0; i < maxIterations; i++) {
+			s = abs(s);
+			if(s.x < s.y) {s.xy = s.yx;}
+			if(s.x < s.z) {s.xz = s.zx;}
+			if(s.y < s.z) {s.yz = s.zy;}
+
+			s = mengerScale*s - (mengerScale - 1.0);
+			if(s.z < -0.5*(mengerScale - 1.0)) {
+				s.z += mengerScale - 1.0;
+			}
+			s *= frame_constant_menger_rotato;
+			DEfactor *= mengerScale;
+
+			orbitTrap.x = min(orbitTrap.x, length(s/mengerScale - push.reactive_bass.xyz)/2.0);
+			orbitTrap.y = min(orbitTrap.y, length(s/mengerScale - push.reactive_mids.xyz)/2.0);
+			orbitTrap.z = min(orbitTrap.z, length(s/mengerScale - push.reactive_high.xyz)/2.0);
+		}
+		vec3 box = abs(s) - vec3(1.0);
+		return (min(max(box.x, max(box.y, box.z)), 0.0) + length(max(box, 0.0))) / DEfactor / reScale;
+	}
+	// Sierpiński tetrahedron, folded across the planes of symmetry of a tetrahedron.
+	else if(runtime.distance_estimator_id == 9) {
+		int maxIterations = int(runtime.fractal_iterations);
+		const float scale = runtime.fractal_fold_scale;
+		const float reScale = 1.9;
+		t *= reScale;
+		vec3 s = t;
+		float r2 = dot(s, s);
+		float DEfactor = 1.0;
+		for(int i = 0; i < maxIterations && r2 < runtime.fractal_bailout*runtime.fractal_bailout; i++) {
+			if(s.x + s.y < 0.0) {s.xy = -s.yx;}
+			if(s.x + s.z < 0.0) {s.xz = -s.zx;}
+			if(s.y + s.z < 0.0) {s.yz = -s.zy;}
+
+			s = scale*s - (scale - 1.0);
+			s *= frame_constant_tetrahedron_rotato;
+			r2 = dot(s, s);
+			DEfactor *= scale;
+
+			orbitTrap.x = min(orbitTrap.x, length(s - push.reactive_bass.xyz)/2.0);
+			orbitTrap.y = min(orbitTrap.y, length(s - push.reactive_mids.xyz)/2.0);
+			orbitTrap.z = min(orbitTrap.z, length(s - push.reactive_high.xyz)/2.0);
+		}
+		return sqrt(r2) / DEfactor / reScale;
+	}
+	// Pseudo-Kleinian, the limit set of sphere inversions within a box, bounded by a sphere.
+	else if(runtime.distance_estimator_id == 10) {
+		int maxIterations = int(runtime.fractal_iterations);
+		const float reScale = 2.2;
+		t *= reScale;
+		vec3 s = t;
+		const vec3 boxSize = vec3(0.92436, 0.90756, 0.92436);
+		const float inversionR2 = 1.0 + 0.06*sin(0.1*push.time);
+		const float thickness = 0.02;
+		float DEfactor = 1.0;
+		for(int i = 0; i < maxIterations; i++) {
+			s = 2.0*clamp(s, -boxSize, boxSize) - s;
+
+			float r2 = dot(s, s);
+			float k = max(inversionR2/r2, 1.0);
+			s *= k;
+			DEfactor *= k;
+
+			orbitTrap.x = min(orbitTrap.x, length(s - push.reactive_bass.xyz)/2.0);
+			orbitTrap.y = min(orbitTrap.y, length(s - push.reactive_mids.xyz)/2.0);
+			orbitTrap.z = min(orbitTrap.z, length(s - push.reactive_high.xyz)/2.0);
+		}
+		float rxy = length(s.xy);
+		float d = max(rxy - thickness, abs(rxy*s.z)/length(s)) / DEfactor;
+		return max(d, length(t) - 1.6) / reScale;
+	}
+#ifdef USER_FRACTAL
+	// User-provided.
+	else if(runtime.distance_estimator_id == 11) {
+		return userDistanceEstimator(t);
+	}
+#endif
+
+	// If no fractal is selected, then escape to infinity.
+	return 1024.0;
+}
+
+void setFrameConstants() {
+	// Mandelbulb.
+	if(runtime.distance_estimator_id == 2) {
+		frame_constant_mandelbulb_colorRotato = buildRot3(safe_normalize(push.smooth_mids.xyz), 0.325*push.time);
+	}
+	// Klein-inspired.
+	else if (runtime.distance_estimator_id == 3) {
+		frame_constant_klein_colorRotato = buildRot3(safe_normalize(push.smooth_mids.xyz), 0.15*push.time);
+	}
+	// Sierpiński-inspired.
+	else if (runtime.distance_estimator_id == 5) {
+		float theta = 0.08*push.time;
+		frame_constant_sierpinski_rotato1 = buildRot3(safe_normalize(push.smooth_high.xyz), theta);
+		theta = 0.22*sin(0.25*push.time);
+		frame_constant_sierpinski_rotato2 = buildRot3(safe_normalize(push.smooth_mids.xyz), theta);
+	}
+	// Quaternion Julia.
+	else if (runtime.distance_estimator_id == 6) {
+		frame_constant_quaternion_colorRotato = buildRot3(safe_normalize(push.smooth_mids.xyz), 0.1*push.time);
+		frame_constant_quaternion_q = safe_normalize(multiplyQuaternions(multiplyQuaternions(push.smooth_high, push.smooth_bass), push.smooth_mids));
+		frame_constant_quaternion_c = 0.615*multiplyQuaternions(frame_constant_quaternion_q, safe_normalize(multiplyQuaternions(multiplyQuaternions(push.smooth_bass, push.smooth_mids), push.smooth_high)));
+	}
+	// Menger sponge.
+	else if (runtime.distance_estimator_id == 8) {
+		frame_constant_menger_rotato = buildRot3(safe_normalize(cross(push.smooth_bass.xyz, push.smooth_high.xyz)), 0.12*sin(0.05*push.time));
+	}
+	// Sierpiński tetrahedron.
+	else if (runtime.distance_estimator_id == 9) {
+		frame_constant_tetrahedron_rotato = buildRot3(safe_normalize(push.smooth_mids.xyz), 0.18*sin(0.07*push.time));
+	}
+}
@@ -0,0 +1,12 @@
+#version 450
+
+layout (location = 0) in vec2 coord;
+
+layout (location = 0) out vec4 fragColor;
+
+layout (input_attachment_index = 0, set = 0, binding = 0) uniform subpassInput particle_color;
+
+// Where no fractal is drawn, the particles are passed through the fractal subpass unchanged.
+void main() {
+	fragColor = vec4(subpassLoad(particle_color).rgb, 1.0);
+}
@@ -2,8 +2,54 @@
 
 layout (location = 0) in vec4 outColor;
 
+// Unused for now, available to temporal effects such as motion blur
+layout (location = 1) in vec2 outMotion;
+
+#ifdef INSTANCED_SPRITES
+layout (location = 2) in vec2 outSpriteCoord;
+layout (location = 3) flat in uint outSpriteShape;
+
+// User-supplied image drawn by the `texture` sprite shape, or a single white texel
+layout (binding = 7) uniform sampler2D sprite_texture;
+
+// Sprite shapes, in the order of `SpriteShape`
+const uint SHAPE_SQUARE = 0;
+const uint SHAPE_CIRCLE = 1;
+const uint SHAPE_GLOW = 2;
+const uint SHAPE_STAR = 3;
+const uint SHAPE_TEXTURE = 4;
+#endif
+
 layout (location = 0) out vec4 fragColor;
 
 void main() {
-    fragColor = outColor;
-}
\ No newline at end of file
+    vec3 color = outColor.rgb;
+    float alpha = outColor.a;
+
+#ifdef INSTANCED_SPRITES
+    // Determine how much of this fragment the sprite's shape covers
+    vec2 p = outSpriteCoord;
+    float r = length(p);
+    float coverage = 1.0;
+    if (outSpriteShape == SHAPE_CIRCLE) {
+        float edge = fwidth(r);
+        coverage = 1.0 - smoothstep(1.0 - edge, 1.0, r);
+    } else if (outSpriteShape == SHAPE_GLOW) {
+        float falloff = max(1.0 - r, 0.0);
+        coverage = falloff * falloff;
+    } else if (outSpriteShape == SHAPE_STAR) {
+        coverage = clamp(max(1.0 - r, 0.0) * 0.03 / (abs(p.x * p.y) + 0.03), 0.0, 1.0);
+    } else if (outSpriteShape == SHAPE_TEXTURE) {
+        vec4 texel = texture(sprite_texture, 0.5 * p + 0.5);
+        color *= texel.rgb;
+        coverage = texel.a;
+    }
+    if (coverage < 0.01) {
+        discard;
+    }
+    alpha *= coverage;
+#endif
+
+    // Output premultiplied alpha so that blended sprites fade smoothly at their edges
+    fragColor = vec4(color * alpha, alpha);
+}
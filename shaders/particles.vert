@@ -1,10 +1,41 @@
 #version 450
 
+#ifdef INSTANCED_SPRITES
+// Sprites are drawn as one instance per particle, pulling the particle from its storage buffer.
+// The four vertices of each instance are the corners of its quad
+struct Particle {
+	vec4 pos;
+	vec4 vel;
+};
+layout (binding = 5) buffer readonly ParticleBuffer {
+	Particle particles[];
+};
+#ifdef SORTED_SPRITES
+// Translucent sprites are drawn in the order of the particles sorted back-to-front
+layout (binding = 6) buffer readonly SortedIndexBuffer {
+	uint sorted_indices[];
+};
+#define PARTICLE_INDEX sorted_indices[gl_InstanceIndex]
+#else
+#define PARTICLE_INDEX gl_InstanceIndex
+#endif
+#else
 layout (location = 0) in vec4 pos;
 layout (location = 1) in vec4 vel;
+#define PARTICLE_INDEX gl_VertexIndex
+#endif
 
 layout (location = 0) out vec4 outColor;
 
+// Screen-space motion of the particle since the previous frame, in normalized device coordinates
+layout (location = 1) out vec2 outMotion;
+
+#ifdef INSTANCED_SPRITES
+// The position within the sprite's quad, from -1 to 1 along each axis, and the shape drawn within it
+layout (location = 2) out vec2 outSpriteCoord;
+layout (location = 3) flat out uint outSpriteShape;
+#endif
+
 layout (binding = 0) uniform ParticleColorScheme {
 	vec4 indexConst[4];
     vec4 speedConst[4];
@@ -34,13 +65,70 @@ layout (binding = 2) uniform RuntimeConstants {
 	// TODO: Move this to ConfigConstants
 	bool render_particles;
 	uint distance_estimator_id;
+	uint coloring_mode;
+
+	// Color-grade constant
+	float hue_rotation;
+
+	// Display constant
+	float point_scale;
+
+	// Sidechain-pump constant
+	float fractal_brightness;
+
+	// Window constant, in pixels
+	float viewport_height;
+
+	// Color-management constants. Surfaces with sRGB output encode the linear values written to them.
+	// Colors left of the split, in normalized device coordinates, are written unconverted
+	bool srgb_output;
+	float unmanaged_split;
+
+	// Fractal constants, the tunables of the selected fractal
+	uint fractal_iterations;
+	float fractal_bailout;
+	float fractal_fold_scale;
+	float fractal_color_exponent;
+
+	// Particle constant, how particles are kept within the unit square or cube
+	uint particle_containment;
+
+	// Particle constant, the fraction of particles respawned on the fractal's surface each second
+	float surface_emission;
 } runtime;
 
+// Custom constants from the `[live_uniforms]` config table, in alphabetical order of their names.
+// The n-th is `live.values[n / 4][n % 4]`. They are re-uploaded whenever the config file is saved
+layout (binding = 3) uniform LiveUniforms {
+	vec4 values[4];
+} live;
+
+// Each particle's position before the latest compute pass.
+layout (binding = 4) buffer readonly PreviousPositionBuffer {
+	vec3 previous_positions[];
+};
+
 layout (push_constant) uniform PushConstants {
 	vec4 quaternion;
+
+	// The position of the camera, at the fractal camera's position scaled to the particles' orbit
+	vec4 camera_position;
+
+	// Recent kicks, as the position of the big boomer with the strength in `w`, and the seconds since each.
+	// Unused echoes have zero strength
+	vec4 kick_echoes[4];
+	vec4 kick_ages;
+
 	float time;
 	bool alternate_colors;
 	bool use_third_dimension;
+	float opacity;
+
+	// The alpha of each particle, which only translucent particles blend by
+	float alpha;
+
+	// The shape drawn within each sprite's quad, in the order of `SpriteShape`
+	uint sprite_shape;
 } push;
 
 // Define constants for perspective rendering
@@ -64,34 +152,73 @@ vec3 rotateByQuaternion(vec3 v, vec4 q) {
 	return v + temp+temp;
 }
 
+// Calculate screen position based on desired perspective.
+vec4 projectParticle(vec3 p) {
+	if(push.use_third_dimension) {
+		return createPerspective(runtime.aspect_ratio) * vec4(rotateByQuaternion(p - push.camera_position.xyz, push.quaternion), 1.0);
+	}
+	return vec4(p.xy, 0.0, 1.0);
+}
+
+// Encode a linear color value as sRGB.
+vec3 linearToSrgb(vec3 col) {
+	return mix(12.92*col, 1.055*pow(col, vec3(1.0/2.4)) - 0.055, greaterThan(col, vec3(0.0031308)));
+}
+
+// Rotate a color about the gray axis, shifting its hue while preserving its brightness.
+vec3 rotateHue(vec3 col, float angle) {
+	const vec3 k = vec3(0.57735026919);
+	float c = cos(angle);
+	return col*c + cross(k, col)*sin(angle) + k*dot(k, col)*(1.0 - c);
+}
+
 void main() {
+#ifdef INSTANCED_SPRITES
+	vec4 pos = particles[PARTICLE_INDEX].pos;
+	vec4 vel = particles[PARTICLE_INDEX].vel;
+	outSpriteShape = push.sprite_shape;
+#endif
 	float speed = min(length(vel.xyz), config.max_speed);
 
+	float pointSize = config.point_size * runtime.point_scale;
 	if(config.hide_stationary_particles) {
 		const float minSpeedRatioVisible = 0.021;
 		const float normalizeSpeedVisibility = 1.0 / (1.0 - minSpeedRatioVisible);
 		float visibility = (speed/config.max_speed - minSpeedRatioVisible) * normalizeSpeedVisibility;
 		if(visibility > 0) {
-			gl_PointSize = config.point_size * pow(visibility, 0.15);
+			pointSize *= pow(visibility, 0.15);
 		} else {
+#ifndef INSTANCED_SPRITES
 			gl_PointSize = 0.0;
+#endif
 			gl_Position = vec4(1.0);
 			outColor = vec4(0.0);
+			outMotion = vec2(0.0);
 			return;
 		}
-	} else {
-		gl_PointSize = config.point_size;
 	}
 
-	// Calculate screen position based on desired perspective.
-	if(push.use_third_dimension) {
-		vec4 temp = createPerspective(runtime.aspect_ratio) * vec4(rotateByQuaternion(pos.xyz, push.quaternion) - vec3(0.0, 0.0, 1.75), 1.0);
-		gl_Position = temp;
-	} else {
-		gl_Position = vec4(pos.xy, 0.0, 1.0);
+	gl_Position = projectParticle(pos.xyz);
+
+	// Both positions are projected by the current camera, so only the particle's own motion is measured.
+	vec4 previousPosition = projectParticle(previous_positions[PARTICLE_INDEX]);
+	outMotion = gl_Position.xy/gl_Position.w - previousPosition.xy/previousPosition.w;
+
+#ifdef INSTANCED_SPRITES
+	// Expand the particle to a quad `pointSize` pixels wide, turned to face along its motion on screen.
+	vec2 corner = vec2(gl_VertexIndex & 1, gl_VertexIndex >> 1) * 2.0 - 1.0;
+	outSpriteCoord = corner;
+	vec2 motion = outMotion * vec2(runtime.aspect_ratio, 1.0);
+	if(dot(motion, motion) > 1e-12) {
+		vec2 dir = normalize(motion);
+		corner = mat2(dir.x, dir.y, -dir.y, dir.x) * corner;
 	}
+	gl_Position.xy += corner * vec2(1.0 / runtime.aspect_ratio, 1.0) * (pointSize / runtime.viewport_height) * gl_Position.w;
+#else
+	gl_PointSize = pointSize;
+#endif
 
-	float t = fract(float(gl_VertexIndex)/config.particle_count + 0.045*push.time);
+	float t = fract(float(PARTICLE_INDEX)/config.particle_count + 0.045*push.time);
 	vec3 indexColor;
 	{
 		vec3 indexStart;
@@ -162,6 +289,35 @@ void main() {
 		speedColor = mix(speedStart, speedEnd, speedScale);
 	}
 
+	// Brighten particles on the rings expanding from recent kicks.
+	const float kickEchoSpeed = 0.65;
+	const float kickEchoWidth = 0.035;
+	const float kickEchoDecay = 1.4;
+	float echo = 0.0;
+	for(int i = 0; i < 4; i++) {
+		const float strength = push.kick_echoes[i].w;
+		if(strength <= 0.0) {
+			continue;
+		}
+
+		// Measure 2D distances in screen proportions so that rings are circular.
+		float dist;
+		if(push.use_third_dimension) {
+			dist = distance(pos.xyz, push.kick_echoes[i].xyz);
+		} else {
+			dist = length((pos.xy - push.kick_echoes[i].xy) * vec2(runtime.aspect_ratio, 1.0));
+		}
+		const float age = push.kick_ages[i];
+		const float offset = (dist - kickEchoSpeed*age) / kickEchoWidth;
+		echo += strength * exp(-offset*offset - kickEchoDecay*age);
+	}
+
 	//outColor = vec4(mix(speedColor, indexColor, pow(max(speed - maxSpeed/100.0, 0.0)/maxSpeed, 0.35)), 1.0);
-	outColor = vec4(speedColor, 1.0);
+	vec3 color = push.opacity * max(rotateHue(speedColor + echo*mix(indexColor, vec3(1.0), 0.5), runtime.hue_rotation), vec3(0.0));
+
+	// Scheme colors are uploaded as linear values for sRGB output, so unmanaged particles are encoded to write them unconverted.
+	if(runtime.srgb_output && gl_Position.x/gl_Position.w < runtime.unmanaged_split) {
+		color = linearToSrgb(color);
+	}
+	outColor = vec4(color, push.alpha);
 }
\ No newline at end of file
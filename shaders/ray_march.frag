@@ -5,7 +5,7 @@ layout (location = 0) in vec2 coord;
 layout (location = 0) out vec4 fragColor;
 
 layout (input_attachment_index = 0, set = 0, binding = 0) uniform subpassInput particle_color;
-layout (input_attachment_index = 1, set = 0, binding = 1) uniform subpassInputMS particle_depth;
+layout (input_attachment_index = 1, set = 0, binding = 1) uniform subpassInput particle_depth;
 
 layout (set = 0, binding = 2) uniform ConfigConstants {
 	// Particle constants
@@ -30,11 +30,50 @@ layout (set = 0, binding = 3) uniform RuntimeConstants {
 	// Fractal constants
 	bool render_particles;
 	uint distance_estimator_id;
+	uint coloring_mode;
+
+	// Color-grade constant
+	float hue_rotation;
+
+	// Display constant
+	float point_scale;
+
+	// Sidechain-pump constant
+	float fractal_brightness;
+
+	// Window constant, in pixels
+	float viewport_height;
+
+	// Color-management constants. Surfaces with sRGB output encode the linear values written to them.
+	// Colors left of the split, in normalized device coordinates, are written unconverted
+	bool srgb_output;
+	float unmanaged_split;
+
+	// Fractal constants, the tunables of the selected fractal
+	uint fractal_iterations;
+	float fractal_bailout;
+	float fractal_fold_scale;
+	float fractal_color_exponent;
+
+	// Particle constant, how particles are kept within the unit square or cube
+	uint particle_containment;
+
+	// Particle constant, the fraction of particles respawned on the fractal's surface each second
+	float surface_emission;
 } runtime;
 
+// Custom constants from the `[live_uniforms]` config table, in alphabetical order of their names.
+// The n-th is `live.values[n / 4][n % 4]`. They are re-uploaded whenever the config file is saved
+layout (set = 0, binding = 4) uniform LiveUniforms {
+	vec4 values[4];
+} live;
+
 layout (push_constant) uniform PushConstants {
 	vec4 quaternion;
 
+	// The position of the camera, on its orbit about the origin unless flown freely
+	vec4 camera_position;
+
 	vec4 reactive_bass;
     vec4 reactive_mids;
     vec4 reactive_high;
@@ -46,7 +85,18 @@ layout (push_constant) uniform PushConstants {
 	float time;
 	
 	float kaleidoscope;
+
+	// The kaleidoscope's number of mirrored segments, the angle its folded view has turned by,
+	// and the angle from the right of the view to the center of its first segment
+	uint kaleidoscope_segments;
+	float kaleidoscope_rotation;
+	float kaleidoscope_axis;
+
 	float orbit_distance;
+
+	// Sub-pixel offset of the view, in screen-coordinates, for temporal anti-aliasing
+	float jitter_x;
+	float jitter_y;
 } push;
 
 const float pi = 3.14159265358;
@@ -57,40 +107,11 @@ const vec3 dirX = vec3(1.0, 0.0, 0.0);
 const vec3 dirY = vec3(0.0, 1.0, 0.0);
 const vec3 dirZ = vec3(0.0, 0.0, 1.0);
 
-mat3 buildRot3(vec3 u, float theta) {
-	float c = cos(theta);
-	float cC = 1.0 - c;
-	float s = sin(theta);
-	float sC = 1.0 - s;
-	return mat3(
-		c+u.x*u.x*cC, u.y*u.x*cC+u.z*s, u.z*u.x*cC-u.y*s,
-		u.x*u.y*cC-u.z*s, c+u.y*u.y*cC, u.z*u.y*cC+u.x*s,
-		u.x*u.z*cC+u.y*s, u.y*u.z*cC-u.x*s, c+u.z*u.z*cC
-	);
-}
-
 vec3 rotateByQuaternion(vec3 v, vec4 q) {
 	vec3 temp = cross(q.xyz, cross(q.xyz, v) + q.w * v);
 	return v + temp+temp;
 }
 
-vec4 multiplyQuaternions(vec4 q, vec4 r) {
-	return vec4(cross(q.xyz, r.xyz) + q.xyz*r.w + q.w*r.xyz, q.w*r.w - dot(q.xyz, r.xyz));
-}
-
-vec3 safe_normalize(vec3 t) {
-	if(length(t) < 0.000001) {
-		return vec3(1.0, 0.0, 0.0);
-	}
-	return normalize(t);
-}
-vec4 safe_normalize(vec4 t) {
-	if(length(t) < 0.000001) {
-		return vec4(0.0, 0.0, 0.0, 1.0);
-	}
-	return normalize(t);
-}
-
 float getAngle(vec2 s) {
 	float theta = 0.0;
 	if(s.y < 0.0) {
@@ -111,274 +132,74 @@ float getAngle(vec2 s) {
 	}
 }
 
-float boundReflect(float x, float b) {
-	float r = mod(x + b, 4.0*b);
-	if(r < 2.0*b) {
-		return r - b;
-	} else {
-		return 3.0*b - r;
+#include "distance_estimator.glsl"
+
+const float maxBrightness = 1.6;
+const float maxBrightnessR2 = maxBrightness*maxBrightness;
+vec3 scaleColor(float distanceRatio, float iterationRatio, vec3 col) {
+	col *= pow(1.0 - distanceRatio, 1.2) * pow(1.0 - iterationRatio, runtime.fractal_color_exponent);
+	if(dot(col, col) > maxBrightnessR2) {
+		col = maxBrightness*normalize(col);
 	}
+	col = min(vec3(1.0), col);
+	return col;
 }
 
-mat3 frame_constant_mandelbulb_colorRotato;
-mat3 frame_constant_klein_colorRotato;
-mat3 frame_constant_sierpinski_rotato1;
-mat3 frame_constant_sierpinski_rotato2;
-mat3 frame_constant_quaternion_colorRotato;
-vec4 frame_constant_quaternion_q;
-vec4 frame_constant_quaternion_c;
-
-vec4 orbitTrap;
-float distanceEstimator(vec3 t) {
-	orbitTrap = vec4(1.0, 1.0, 1.0, 1.0);
-
-	// Mandelbox.
-	if(runtime.distance_estimator_id == 1) {
-		const int maxIterations = 5;
-		const float reScale = 4.8;
-		t *= reScale;
-		vec3 s = t;
-		const float mandelboxScale = 0.25*cos(0.075 * push.time) - 2.1;
-		float DEfactor = 1.0;
-		float r2 = 1.0;
-		const float maxR2 = 12.0;
-		const float BVR = sqrt(maxR2);
-		for (int i = 0; i < maxIterations; i++) {
-			if(s.x>1.0){s.x=2.0-s.x;}else if(s.x<-1.0){s.x=-2.0-s.x;}
-			if(s.y>1.0){s.y=2.0-s.y;}else if(s.y<-1.0){s.y=-2.0-s.y;}
-			if(s.z>1.0){s.z=2.0-s.z;}else if(s.z<-1.0){s.z=-2.0-s.z;}
-
-			r2 = dot(s, s);
-			if (r2 < 0.25) {
-				s *= 4.0;
-				DEfactor *= 4.0;
-			} else if(r2 < 1.0) {
-				s /= r2;
-				DEfactor /= r2;
-			}
-
-			orbitTrap.x = min(orbitTrap.x, length(s/BVR - push.reactive_bass.xyz)/1.25);
-			orbitTrap.y = min(orbitTrap.y, length(s/BVR - push.reactive_mids.xyz)/1.25);
-			orbitTrap.z = min(orbitTrap.z, length(s/BVR - push.reactive_high.xyz)/1.25);
-
-			s = s*mandelboxScale + t;
-			DEfactor = DEfactor*abs(mandelboxScale) + 1.0;
-		
-			if(r2 > maxR2) break;
-		}
-		return (length(s)-BVR)/abs(DEfactor) / reScale;
-	}
-	// Mandelbulb.
-	else if(runtime.distance_estimator_id == 2) {
-		const int maxIterations = 3;
-		const float reScale = 1.85;
-		t *= reScale;
-		vec3 s = t;
-		float power = 9. + 2.0*boundReflect(0.0375*push.time + 1.0, 1.0);
-		float dr = 1.0;
-		float r = 0.0;
-
-		for(int i = 0; i < maxIterations; i++) {
-			r = length(s);
-			const float b = 1.5;
-			if (r > b) break;
-
-			float theta = acos(s.z/r);
-			float phi = atan(s.y, s.x);
-			dr = pow(r, power-1.0)*power*dr + 1.0;
-
-			r = pow(r, power);
-			theta *= power;
-			phi *= power;
-
-			s = r*vec3(sin(theta)*cos(phi), sin(theta)*sin(phi), cos(theta));
-			s += t;
-
-			orbitTrap.xyz = min(orbitTrap.xyz, abs((s - (push.reactive_high.xyz + push.reactive_bass.xyz)/2.0) * frame_constant_mandelbulb_colorRotato)/1.25);
-		}
-		return min(0.5*log(r)*r/dr, 3.5) / reScale;
-	}
-	// Klein-inspired.
-	else if(runtime.distance_estimator_id == 3) {
-		const int maxIterations = 3;
-		const float reScale = 0.8;
-		t = reScale*t;
-		vec3 s = t;
-
-		float anim = 1.275 + 0.085*sin(0.2*push.time);
-		float scale = 1.0;
-		float theta = 0.1 * push.time;
-		float ct = cos(theta);
-		float st = sin(theta);
-		mat2 rotato = mat2(ct, st, -st, ct);
-
-		for(int i = 0; i < maxIterations; i++) {
-			if (i == 2) {
-				s.xy *= rotato;
-			}
+// Cosine gradient palette with phases offset per channel.
+vec3 palette(float t) {
+	return vec3(0.55) + 0.45*cos(tau*(t + vec3(0.0, 0.33, 0.67)));
+}
 
-			s = -1.0 + 2.0*fract(0.5*s + 0.5);
+// Estimate the surface normal with central differences of the distance estimator.
+vec3 surfaceNormal(vec3 position) {
+	const vec2 h = vec2(4.0*epsilon, 0.0);
+	return safe_normalize(vec3(
+		distanceEstimator(position + h.xyy) - distanceEstimator(position - h.xyy),
+		distanceEstimator(position + h.yxy) - distanceEstimator(position - h.yxy),
+		distanceEstimator(position + h.yyx) - distanceEstimator(position - h.yyx)));
+}
 
-			float r2 = dot(s,s);
-		
-			float k = anim/r2;
-			s *= k;
-			scale *= k;
+// Color the surface hit at the given position according to the selected coloring mode.
+vec3 surfaceColor(vec3 position, float iterationRatio) {
+	// The orbit trap of the hit is copied first since estimating the normal overwrites it.
+	vec3 trap = orbitTrap.xyz;
 
-			orbitTrap.xyz = min(orbitTrap.xyz, abs((s - (push.reactive_high.xyz + push.reactive_bass.xyz)/2.0) * frame_constant_klein_colorRotato));
-		}
-	
-		return max((0.25*abs(s.z)/scale)/reScale, length(t/reScale)-0.62);
+	// Orbit-trap palette.
+	if(runtime.coloring_mode == 1) {
+		return 1.35*palette(dot(trap, vec3(0.6, 0.3, 0.1)) + 0.02*push.time);
 	}
-	// Menger Sponge.
-	else if(runtime.distance_estimator_id == 4) {
-		const int maxIterations = 4;
-
-		const float reScale = 1.32;
-		t *= reScale;
-		vec3 s = t;
-
-		s = s + 0.5; //center it by changing position and scale
-		float xx=abs(s.x-0.5)-0.5, yy=abs(s.y-0.5)-0.5, zz=abs(s.z-0.5)-0.5;
-		float d1=max(xx,max(yy,zz)); //distance to the box
-		float d=d1; //current computed distance
-		float p=1.0;
-		float mengerScale = 3.0;
-		float halfScale = mengerScale / 2.0;
-
-		orbitTrap.xyz = abs(vec3(xx/1.2, yy/1.2, zz/1.2));
-
-		float theta = 0.575*sin(0.055*push.time);
-		mat3 rotato = buildRot3(safe_normalize(cross(push.smooth_bass.xyz, push.smooth_mids.xyz)), theta);
-
-		for (int i = 0; i < maxIterations; i++) {
-			p *= mengerScale;
-			float xa = mod(s.x*p, mengerScale);
-			float ya = mod(s.y*p, mengerScale);
-			float za = mod(s.z*p, mengerScale);
-
-			float xx=0.5-abs(xa-halfScale), yy=0.5-abs(ya-halfScale), zz=0.5-abs(za-halfScale);
-			d1=min(max(xx,zz),min(max(xx,yy),max(yy,zz))) / p; //distance inside the 3 axis-aligned square tubes
-
-			d=max(d,d1); //intersection
-
-			vec3 q = vec3(xx, yy, zz);
-			orbitTrap.xyz = max(orbitTrap.xyz, abs(vec3(dot(q, push.reactive_bass.xyz), dot(q, push.reactive_mids.xyz), dot(q, push.reactive_high.xyz))));
-
-			const vec3 halfVec = vec3(0.5);
-			s = (s - halfVec)*rotato + halfVec;
-		}
-		return d/reScale;
+	// Surface normal.
+	else if(runtime.coloring_mode == 2) {
+		vec3 normal = rotateByQuaternion(surfaceNormal(position), vec4(-push.quaternion.xyz, push.quaternion.w));
+		return 1.25*(0.5 + 0.5*normal);
 	}
-	// Sierpiński-inspired.
-	else if(runtime.distance_estimator_id == 5) {
-		const int maxIterations = 8;
-		const float scale = 2.0;
-		const float reScale = 1.375;
-
-		t *= reScale;
-		vec3 s = t;
-		const vec3 center = vec3(sqrt(0.5), sqrt(0.3), sqrt(0.2));
-		float r2 = dot(s, s);
-		float DEfactor = 1.0;
-
-		for(int i = 0; i < maxIterations && r2 < 1000.0; i++) {
-			s *= frame_constant_sierpinski_rotato1;
-
-			if(s.x+s.y<0.0){float x1=-s.y;s.y=-s.x;s.x=x1;}
-			if(s.x+s.z<0.0){float x1=-s.z;s.z=-s.x;s.x=x1;}
-			if(s.y+s.z<0.0){float y1=-s.z;s.z=-s.y;s.y=y1;}
-
-			s *= frame_constant_sierpinski_rotato2;
-
-			s = scale*s - (scale - 1.0)*center;
-			r2 = dot(s, s);
-
-			orbitTrap.x = min(orbitTrap.x, length(s - push.reactive_bass.xyz)/2.0);
-			orbitTrap.y = min(orbitTrap.y, length(s - push.reactive_mids.xyz)/2.0);
-			orbitTrap.z = min(orbitTrap.z, length(s - push.reactive_high.xyz)/2.0);
-
-			DEfactor *= scale;
-		}
-		return (sqrt(r2) - 2.0) / DEfactor / reScale;
+	// Iteration count, brightened to offset the darkening of distant steps by `scaleColor`.
+	else if(runtime.coloring_mode == 3) {
+		return 2.5*palette(4.0*iterationRatio);
 	}
-	// Quaternion Julia.
-	else if(runtime.distance_estimator_id == 6) {
-		const int maxIterations = 6;
-		const float reScale = 1.85;
-		t *= reScale;
-		float power = 4.0 + sin(0.025*push.time);
-
-		// Store the running derivative as a quaternion.
-		float dr = 1.0;
-		float r = 0.0;
-
-		// Create a quaternion from the position.
-		vec4 s = vec4(0.0, t);
-		vec4 s_0 = s;
-
-		for(int i = 0; i < maxIterations; i++) {
-			r = length(s);
-			const float b = 1.5;
-			if (r > b) break;
-
-			//*/ Julia-style.
-			// Get the derivative.
-			dr = power*pow(r, power-1.0)*dr;
-			// s = q*s^p + c; using quaternion algebra.
-			float phi = acos(s.w/r);
-			s = pow(r, power)*vec4(sin(power*phi)*s.xyz/r, cos(power*phi));
-			s = multiplyQuaternions(frame_constant_quaternion_q, s) + frame_constant_quaternion_c;
-			/*/
-			// Mandelbrot-style.
-			// Get the derivative.
-			dr = 2*r*dr;
-			// s = s^2 + s_0; using quaternion algebra.
-			s = multiplyQuaternions(s, s) + s_0; //*/
-
-			orbitTrap.xyz = min(orbitTrap.xyz, abs((s.xyz - (push.reactive_high.xyz + push.reactive_bass.xyz)/2.0) * frame_constant_quaternion_colorRotato)/3.5);
-		}
-		orbitTrap.xyz = sqrt(sqrt(orbitTrap.xyz));
-		return 0.6 * min(log(r)*r/dr, 3.5) / reScale;
+	// Band reactive.
+	else if(runtime.coloring_mode == 4) {
+		vec3 col = trap.x*abs(push.smooth_bass.xyz) + trap.y*abs(push.smooth_mids.xyz) + trap.z*abs(push.smooth_high.xyz);
+		return 1.5*safe_normalize(col)*(0.35 + length(push.reactive_bass.xyz + push.reactive_mids.xyz + push.reactive_high.xyz));
 	}
 
-	// If no fractal is selected, then escape to infinity.
-	return 1024.0;
+	// Classic, the coloring designed alongside each fractal.
+	return trap;
 }
 
-void setFrameConstants() {
-	// Mandelbulb.
-	if(runtime.distance_estimator_id == 2) {
-		frame_constant_mandelbulb_colorRotato = buildRot3(safe_normalize(push.smooth_mids.xyz), 0.325*push.time);
-	}
-	// Klein-inspired.
-	else if (runtime.distance_estimator_id == 3) {
-		frame_constant_klein_colorRotato = buildRot3(safe_normalize(push.smooth_mids.xyz), 0.15*push.time);
-	}
-	// Sierpiński-inspired.
-	else if (runtime.distance_estimator_id == 5) {
-		float theta = 0.08*push.time;
-		frame_constant_sierpinski_rotato1 = buildRot3(safe_normalize(push.smooth_high.xyz), theta);
-		theta = 0.22*sin(0.25*push.time);
-		frame_constant_sierpinski_rotato2 = buildRot3(safe_normalize(push.smooth_mids.xyz), theta);
-	}
-	// Quaternion Julia.
-	else if (runtime.distance_estimator_id == 6) {
-		frame_constant_quaternion_colorRotato = buildRot3(safe_normalize(push.smooth_mids.xyz), 0.1*push.time);
-		frame_constant_quaternion_q = safe_normalize(multiplyQuaternions(multiplyQuaternions(push.smooth_high, push.smooth_bass), push.smooth_mids));
-		frame_constant_quaternion_c = 0.615*multiplyQuaternions(frame_constant_quaternion_q, safe_normalize(multiplyQuaternions(multiplyQuaternions(push.smooth_bass, push.smooth_mids), push.smooth_high)));
-	}
+// Convert between linear and sRGB-encoded color values.
+vec3 linearToSrgb(vec3 col) {
+	return mix(12.92*col, 1.055*pow(col, vec3(1.0/2.4)) - 0.055, greaterThan(col, vec3(0.0031308)));
+}
+vec3 srgbToLinear(vec3 col) {
+	return mix(col/12.92, pow((col + 0.055)/1.055, vec3(2.4)), greaterThan(col, vec3(0.04045)));
 }
 
-const float maxBrightness = 1.6;
-const float maxBrightnessR2 = maxBrightness*maxBrightness;
-vec3 scaleColor(float distanceRatio, float iterationRatio, vec3 col) {
-	col *= pow(1.0 - distanceRatio, 1.2) * pow(1.0 - iterationRatio, runtime.distance_estimator_id != 6 ? 2.75 : 1.75);
-	if(dot(col, col) > maxBrightnessR2) {
-		col = maxBrightness*normalize(col);
-	}
-	col = min(vec3(1.0), col);
-	return col;
+// Rotate a color about the gray axis, shifting its hue while preserving its brightness.
+vec3 rotateHue(vec3 col, float angle) {
+	const vec3 k = vec3(0.57735026919);
+	float c = cos(angle);
+	return col*c + cross(k, col)*sin(angle) + k*dot(k, col)*(1.0 - c);
 }
 
 vec3 castRay(vec3 position, vec3 direction, float fovX, float fovY, out float travel) {
@@ -402,7 +223,8 @@ vec3 castRay(vec3 position, vec3 direction, float fovX, float fovY, out float tr
 
 		if(dist <= hitDistance) {
 			float smoothIter = float(i) - (dist - hitDistance)/(dist - lastDistance);
-			return scaleColor(travel/maxDistance, smoothIter/float(maxIterations), orbitTrap.xyz);
+			float iterationRatio = smoothIter/float(maxIterations);
+			return scaleColor(travel/maxDistance, iterationRatio, surfaceColor(position, iterationRatio));
 		}
 
 		lastDistance = dist;
@@ -429,32 +251,40 @@ void main(void) {
 	const float fovY = tan(config.vertical_fov);
 	float fovX = runtime.aspect_ratio * fovY;
 
-	// Adjust the screen-coordinates by the kaleidoscope angle.
-	float kaleidoTheta = boundReflect(getAngle(coord), push.kaleidoscope*(pi/6.0 - tau) + tau);
-	vec2 newCoord = length(coord)*vec2(cos(kaleidoTheta), sin(kaleidoTheta));
+	// Adjust the screen-coordinates by the jitter and kaleidoscope angle.
+	// The fold is applied on the view plane so that its mirrors stay evenly spaced at any aspect ratio.
+	// Segments are mirrored about the axis, and the folded view turns only as far as the kaleidoscope is enabled
+	vec2 viewCoord = (coord + vec2(push.jitter_x, push.jitter_y)) * vec2(fovX, fovY);
+	float kaleidoAngle = getAngle(viewCoord) - push.kaleidoscope_axis;
+	float kaleidoTheta = push.kaleidoscope_axis + push.kaleidoscope*push.kaleidoscope_rotation
+		+ boundReflect(kaleidoAngle, push.kaleidoscope*(pi/float(push.kaleidoscope_segments) - tau) + tau);
+	vec2 newCoord = length(viewCoord)*vec2(cos(kaleidoTheta), sin(kaleidoTheta));
 
-	// Calculate the camera position and view direction given the camera quaternion and screen coordinates.
-	vec3 position = rotateByQuaternion(vec3(0.0, 0.0, push.orbit_distance), push.quaternion);
-	vec3 direction = rotateByQuaternion(normalize(vec3(newCoord.x*fovX, newCoord.y*fovY, -1.0)), push.quaternion);
+	// Calculate the view direction given the camera quaternion and screen coordinates.
+	vec3 position = push.camera_position.xyz;
+	vec3 direction = rotateByQuaternion(normalize(vec3(newCoord, -1.0)), push.quaternion);
 
 	float travel;
-	vec3 tFragColor = castRay(position, direction, fovX, fovY, travel);
+	vec3 tFragColor = runtime.fractal_brightness * max(rotateHue(castRay(position, direction, fovX, fovY, travel), runtime.hue_rotation), vec3(0.0));
 
 	vec3 particle = subpassLoad(particle_color).rgb;
 
+	// The fractal is shaded in sRGB, so managed particles are encoded to match before compositing, and the result is decoded.
+	// Unmanaged particles were already encoded, so the result is written unconverted
+	const bool managed = runtime.srgb_output && coord.x >= runtime.unmanaged_split;
+	if(managed) {
+		particle = linearToSrgb(particle);
+	}
+
 	// Distances must match those used in `particles.vert`
 	const float far = 8.0;
 	const float near = 0.03125;
 
-	const uint sampleCount = 8; // Must be kept up to date with MSAA sample count used in `src/`
-	float minDepth = subpassLoad(particle_depth, 0).x;
-	for(int i = 1; i < sampleCount; i++) {
-		minDepth = min(subpassLoad(particle_depth, i).x, minDepth);
-	}
+	float minDepth = subpassLoad(particle_depth).x; // Already resolved to the nearest sample by `depth_resolve.frag`
 	const float farNearDiff = far - near;
 	minDepth = (-2.0*far*near) / ((minDepth - (far + near)/farNearDiff)*farNearDiff); // Calcualte inverse of projection on z coordinate
 
-	minDepth = minDepth*sqrt(1.0 + newCoord.x*fovX*newCoord.x*fovX + newCoord.y*fovY*newCoord.y*fovY); // Use screen space to determine 3D distance
+	minDepth = minDepth*sqrt(1.0 + dot(newCoord, newCoord)); // Use screen space to determine 3D distance
 
 	// Scale the distance to match the distance used by the particle subpass.
 	travel *= 1.75/push.orbit_distance;
@@ -469,5 +299,8 @@ void main(void) {
 		}
 	}
 
+	if(managed) {
+		tFragColor = srgbToLinear(tFragColor);
+	}
 	fragColor = vec4(tFragColor, 1.0);
 }
@@ -0,0 +1,96 @@
+#version 450
+
+layout (location = 0) in vec2 coord;
+
+layout (location = 0) out vec4 fragColor;
+#ifndef PASSTHROUGH
+layout (location = 1) out vec4 historyColor;
+#endif
+
+layout (set = 0, binding = 0) uniform sampler2D current_color;
+#ifndef PASSTHROUGH
+layout (set = 0, binding = 1) uniform sampler2D history_color;
+#endif
+
+layout (push_constant) uniform PushConstants {
+	vec4 quaternion;
+	vec4 previous_quaternion;
+
+	// The offset and extent in pixels of the viewport the fractal is rendered to
+	vec4 viewport;
+
+	float orbit_distance;
+	float fov_x;
+	float fov_y;
+
+	// The weight of the reprojected history, where zero disables the effect
+	float history_weight;
+
+	// The brightness of the output, which a blackout fades to zero. The history is kept at full brightness
+	float fade;
+
+	// Whether to repeat the previous frame, which the history holds, rather than show the current one
+	uint freeze;
+} push;
+
+vec3 rotateByQuaternion(vec3 v, vec4 q) {
+	vec3 temp = cross(q.xyz, cross(q.xyz, v) + q.w * v);
+	return v + temp+temp;
+}
+
+void main() {
+	const ivec2 size = textureSize(current_color, 0);
+	const ivec2 pixel = ivec2(gl_FragCoord.xy);
+	const vec3 current = texelFetch(current_color, pixel, 0).rgb;
+
+#ifdef PASSTHROUGH
+	// Without a history to blend or keep, the current frame is only faded
+	fragColor = vec4(push.fade*current, 1.0);
+#else
+	if(push.freeze != 0) {
+		const vec3 held = texelFetch(history_color, pixel, 0).rgb;
+		fragColor = vec4(push.fade*held, 1.0);
+		historyColor = vec4(held, 1.0);
+		return;
+	}
+
+	// Only the fractal's region of the view is reprojected, which is all of the content unless the screen is split
+	const vec2 viewCoord = 2.0*(gl_FragCoord.xy - push.viewport.xy)/push.viewport.zw - 1.0;
+	if(push.history_weight <= 0.0 || any(greaterThan(abs(viewCoord), vec2(1.0)))) {
+		fragColor = vec4(push.fade*current, 1.0);
+		historyColor = vec4(current, 1.0);
+		return;
+	}
+
+	// Bound the history by the colors surrounding this pixel to reject stale samples.
+	vec3 minColor = current;
+	vec3 maxColor = current;
+	for(int y = -1; y <= 1; y++) {
+		for(int x = -1; x <= 1; x++) {
+			const vec3 neighbor = texelFetch(current_color, clamp(pixel + ivec2(x, y), ivec2(0), size - 1), 0).rgb;
+			minColor = min(minColor, neighbor);
+			maxColor = max(maxColor, neighbor);
+		}
+	}
+
+	// Reproject this pixel into the previous frame, assuming the surface lies at the orbit distance where the fractal is centered.
+	// The camera orbits the origin, so the previous view is found by rotating by the change in camera orientation
+	const vec3 cameraPosition = vec3(0.0, 0.0, push.orbit_distance);
+	const vec3 direction = normalize(vec3(viewCoord.x*push.fov_x, viewCoord.y*push.fov_y, -1.0));
+	const vec3 worldPoint = rotateByQuaternion(cameraPosition + push.orbit_distance*direction, push.quaternion);
+	const vec4 inversePrevious = vec4(-push.previous_quaternion.xyz, push.previous_quaternion.w);
+	const vec3 previousView = rotateByQuaternion(worldPoint, inversePrevious) - cameraPosition;
+
+	vec3 result = current;
+	if(previousView.z < 0.0) {
+		const vec2 previousCoord = previousView.xy / (-previousView.z * vec2(push.fov_x, push.fov_y));
+		if(all(lessThanEqual(abs(previousCoord), vec2(1.0)))) {
+			const vec3 history = clamp(texture(history_color, (push.viewport.xy + (0.5*previousCoord + 0.5)*push.viewport.zw) / vec2(size)).rgb, minColor, maxColor);
+			result = mix(current, history, push.history_weight);
+		}
+	}
+
+	fragColor = vec4(push.fade*result, 1.0);
+	historyColor = vec4(result, 1.0);
+#endif
+}
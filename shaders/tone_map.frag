@@ -0,0 +1,55 @@
+#version 450
+
+layout (location = 0) in vec2 coord;
+
+layout (location = 0) out vec4 fragColor;
+
+layout (input_attachment_index = 0, set = 0, binding = 0) uniform subpassInput hdr_color;
+
+layout (push_constant) uniform PushConstants {
+	// The multiplier of the color before it is mapped to the display
+	float exposure;
+
+	// The operator mapping unbounded colors to the display, where zero clips them as before
+	uint operator_id;
+
+	// Whether colors are encoded as sRGB rather than linear, so must be decoded before they are mapped
+	bool srgb_encoded;
+} push;
+
+vec3 linearToSrgb(vec3 col) {
+	return mix(12.92*col, 1.055*pow(col, vec3(1.0/2.4)) - 0.055, greaterThan(col, vec3(0.0031308)));
+}
+vec3 srgbToLinear(vec3 col) {
+	return mix(col/12.92, pow((col + 0.055)/1.055, vec3(2.4)), greaterThan(col, vec3(0.04045)));
+}
+
+// Narkowicz's fit of the ACES filmic curve.
+vec3 aces(vec3 col) {
+	return clamp((col*(2.51*col + 0.03)) / (col*(2.43*col + 0.59) + 0.14), 0.0, 1.0);
+}
+
+void main() {
+	vec3 color = max(subpassLoad(hdr_color).rgb, vec3(0.0));
+	if(push.srgb_encoded) {
+		color = srgbToLinear(color);
+	}
+
+	color *= push.exposure;
+	switch(push.operator_id) {
+		case 1:
+			color = color / (1.0 + color); // Reinhard
+			break;
+		case 2:
+			color = aces(color);
+			break;
+		default:
+			color = min(color, vec3(1.0));
+			break;
+	}
+
+	if(push.srgb_encoded) {
+		color = linearToSrgb(color);
+	}
+	fragColor = vec4(color, 1.0);
+}
@@ -0,0 +1,118 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::io::Write;
+use std::net::UdpSocket;
+
+use crate::app_config::{self, AppConfig};
+use crate::audio;
+
+// Where the analyzed audio states are published to.
+#[derive(Clone, PartialEq)]
+pub enum PublishTarget {
+    Stdout,
+    Udp(String), // The `HOST:PORT` address to send datagrams to
+}
+
+impl std::str::FromStr for PublishTarget {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        if s == "stdout" {
+            Ok(Self::Stdout)
+        } else if let Some(address) = s.strip_prefix("udp:") {
+            Ok(Self::Udp(String::from(address)))
+        } else {
+            anyhow::bail!("Unknown publish target `{s}`, expected `stdout` or `udp:HOST:PORT`")
+        }
+    }
+}
+
+// Run only the audio capture and analysis, publishing each state as JSON without creating a window.
+// Over UDP each state is a single datagram, for use with `--audio-source udp:PORT` on another machine.
+// On stdout each state is a single line, so every logged line is printed to stderr instead.
+// The analysis is configured by the config file as when visualizing, and the source defaults to its `capture_mode`.
+// Returns once the audio source stops, or once the reader of stdout closes it
+pub fn run(
+    source_kind: Option<audio::SourceKind>,
+    target: &PublishTarget,
+    config_path: &str,
+) -> anyhow::Result<()> {
+    let socket = match target {
+        PublishTarget::Stdout => {
+            crate::crash::log_to_stderr();
+            None
+        }
+        PublishTarget::Udp(address) => {
+            let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+            socket.connect(address.as_str())?;
+            log!("Publishing audio states to UDP address {address}");
+            Some(socket)
+        }
+    };
+
+    // The config is only read once any messages are redirected away from the published states.
+    // Analysis parameters are then fixed since there is no overlay to tune them. Whether the attractors follow the
+    // stereo channels is left to each receiver, as the notes of both channels are always published
+    let app_config = match app_config::parse_file(config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            log!("Failed to process config file `{config_path}`: {e:?}");
+            AppConfig::default()
+        }
+    };
+    let source_kind = source_kind.unwrap_or_else(|| app_config.capture_mode.source_kind());
+    let analysis_config = audio::new_shared_analysis_config(app_config.launch_analysis());
+    let (tx, rx) = crossbeam_channel::bounded(crate::MAX_MESSAGE_BUFFER_COUNT);
+    let source = audio::create_source_and_send(source_kind, tx, analysis_config, None)?;
+    log!("Audio source: {}", source.description());
+
+    let mut stdout = std::io::stdout().lock();
+    let mut send_failed = false;
+    for state in rx {
+        let json = serde_json::to_vec(&state)?;
+        match &socket {
+            // Datagrams may be refused while the receiver is not yet listening, which is not fatal.
+            Some(socket) => match socket.send(&json) {
+                Ok(_) => send_failed = false,
+                Err(e) => {
+                    if !send_failed {
+                        log!("Failed to send audio state, will keep trying: {e:?}");
+                    }
+                    send_failed = true;
+                }
+            },
+            None => {
+                let result = stdout
+                    .write_all(&json)
+                    .and_then(|()| stdout.write_all(b"\n"))
+                    .and_then(|()| stdout.flush());
+                match result {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
+                        log!("The reader of the audio states closed stdout, exiting");
+                        return Ok(());
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+    }
+
+    log!("The audio source stopped sending states, exiting");
+    Ok(())
+}
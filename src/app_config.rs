@@ -16,11 +16,22 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+use std::collections::BTreeMap;
 use std::num::NonZeroUsize;
 
 use bytemuck::{Pod, Zeroable};
 use css_color_parser::Color as CssColor;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{
+    AnalysisConfig, BandConfig, GainControl, KickConfig, SourceKind, Stem, StemChannel,
+    BUILTIN_PRESETS, DEFAULT_BASS_ATTRACTORS, DEFAULT_GAIN_CONTROL, DEFAULT_HIGH_ATTRACTORS,
+    DEFAULT_MIDS_ATTRACTORS, MAX_BASS_ATTRACTORS, MAX_HIGH_ATTRACTORS, MAX_MIDS_ATTRACTORS,
+};
+use crate::fractals;
+use crate::input::{Action, Chord};
+use crate::my_math::{Quaternion, Vector3};
+use crate::parameters::{self, ParameterValues, PARAMETERS};
 
 #[repr(C)]
 #[derive(Copy, Clone, Default, Zeroable, Pod)]
@@ -29,94 +40,1781 @@ pub struct Scheme {
     pub speed: [[f32; 4]; 4],
 }
 
-#[derive(Deserialize)]
-#[serde(untagged)]
-enum CustomSchemeColor {
-    ColorString(String),
-    ColorStringVal(String, f32),
-    Vec4(Vec<f32>),
+impl Scheme {
+    // Interpolate each color and threshold of the schemes, where `t` of zero is entirely `a`.
+    pub fn lerp(a: &Self, b: &Self, t: f32) -> Self {
+        let mix = |x: &[[f32; 4]; 4], y: &[[f32; 4]; 4]| {
+            std::array::from_fn(|i| std::array::from_fn(|j| x[i][j] + t * (y[i][j] - x[i][j])))
+        };
+        Self {
+            index: mix(&a.index, &b.index),
+            speed: mix(&a.speed, &b.speed),
+        }
+    }
+
+    // Convert the sRGB colors of the scheme to linear values, leaving the thresholds in `w` unchanged.
+    #[must_use]
+    pub fn to_linear(&self) -> Self {
+        let to_linear = |c: f32| {
+            if c > 0.04045 {
+                ((c + 0.055) / 1.055).powf(2.4)
+            } else {
+                c / 12.92
+            }
+        };
+        let convert =
+            |x: &[[f32; 4]; 4]| x.map(|[r, g, b, w]| [to_linear(r), to_linear(g), to_linear(b), w]);
+        Self {
+            index: convert(&self.index),
+            speed: convert(&self.speed),
+        }
+    }
+}
+
+// A color scheme to reach at a time, in seconds since launch.
+#[derive(Clone, Copy, PartialEq)]
+pub struct ColorKeyframe {
+    pub time: f32,
+    pub scheme_index: usize,
+}
+
+// How the progress along a camera path accelerates over its duration.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    #[default]
+    EaseInOut,
+}
+impl Easing {
+    // Map the fraction of the duration elapsed to the fraction of the path travelled, both in [0, 1].
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => t * (2. - t),
+            Self::EaseInOut => t * t * (3. - 2. * t),
+        }
+    }
+}
+
+// A control point of a camera path, as a rotation of the fractal camera and a scale of its orbit distance.
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraKeyframe {
+    pub axis: [f32; 3],
+    pub angle: f32, // Degrees to rotate about the axis
+    pub orbit: Option<f32>,
+}
+impl CameraKeyframe {
+    pub fn quaternion(&self) -> Quaternion {
+        let [x, y, z] = self.axis;
+        Quaternion::build(Vector3::new(x, y, z), self.angle.to_radians() / 2.)
+    }
+    pub fn orbit(&self) -> f32 {
+        self.orbit.unwrap_or(1.)
+    }
+}
+
+// A named camera animation, played along a Bezier curve from the current camera through its keyframes.
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraPath {
+    pub name: String,
+    pub duration: f32, // Seconds
+    #[serde(default)]
+    pub easing: Easing,
+    pub keyframes: Vec<CameraKeyframe>,
+}
+
+// A scene of the show, played in order of definition. Settings the scene leaves unset keep their current state.
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scene {
+    pub name: String,
+    pub duration: f32, // Seconds the scene is shown, beginning with its transition
+    pub transition: Option<f32>, // Seconds over which the scene is blended in
+    pub fractal: Option<u32>, // The distance estimator ID
+    pub color_scheme: Option<String>,
+    pub kaleidoscope: Option<bool>,
+    pub third_dimension: Option<bool>,
+    pub physics_mode: Option<PhysicsMode>,
+    pub camera_path: Option<String>, // The name of a camera path played as the scene begins
+}
+impl Scene {
+    pub fn transition(&self) -> f32 {
+        self.transition.unwrap_or(DEFAULT_SCENE_TRANSITION)
+    }
+}
+
+// An additional particle system, simulated and drawn alongside the main particles with its own buffers.
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParticleSystem {
+    pub name: String,
+    pub particle_count: NonZeroUsize,
+    pub color_scheme: Option<String>, // The name of the color scheme the system is drawn with, or else the first
+    #[serde(default)]
+    pub third_dimension: bool,
+    #[serde(default)]
+    pub jello: bool, // Whether the particles are fixed to their positions with spring tension
+    #[serde(default)]
+    pub physics_mode: PhysicsMode,
+    #[serde(default)]
+    pub hidden: bool, // Whether the system is neither simulated nor drawn
+}
+impl ParticleSystem {
+    // The index of the system's color scheme among the given names, or else of the first scheme.
+    pub fn scheme_index(&self, color_scheme_names: &[String]) -> usize {
+        self.color_scheme
+            .as_ref()
+            .and_then(|scheme| color_scheme_names.iter().position(|name| name == scheme))
+            .unwrap_or_default()
+    }
+}
+
+// Settings for the optional particle-particle repulsion pass.
+#[derive(Clone, Copy, PartialEq)]
+pub struct ParticleRepulsion {
+    pub enabled: bool,
+    pub strength: f32,
+    pub radius: f32,
+}
+
+// Settings for the depth-of-field effect applied to 3D particles.
+#[derive(Clone, Copy, PartialEq)]
+pub struct DepthOfField {
+    pub enabled: bool,
+    pub aperture: f32,
+    pub focus_distance: f32,
+    pub track_big_boomer: bool,
+}
+
+// Settings for the glow around the brightest particles.
+#[derive(Clone, Copy, PartialEq)]
+pub struct ParticleBloom {
+    pub enabled: bool,
+    pub threshold: f32, // The brightness, in [0, 1], above which particles glow
+    pub intensity: f32, // The brightness of the glow
+}
+
+// The operator mapping the unbounded colors of the particles and fractal to the range of the display.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToneMapOperator {
+    #[default]
+    None, // Clip colors beyond the display's range, as without tone mapping
+    Reinhard, // Compress bright colors smoothly toward white
+    Aces, // The filmic curve of the Academy Color Encoding System, with more contrast than Reinhard
+}
+impl ToneMapOperator {
+    pub const ALL: [Self; 3] = [Self::None, Self::Reinhard, Self::Aces];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Reinhard => "Reinhard",
+            Self::Aces => "ACES",
+        }
+    }
+}
+
+// Settings for mapping the rendered colors to the display.
+#[derive(Clone, Copy, PartialEq)]
+pub struct ToneMapping {
+    pub operator: ToneMapOperator,
+    pub exposure: f32, // The multiplier of colors before they are mapped
+}
+
+// Settings for the trails particles leave, fading out from the positions of previous frames.
+#[derive(Clone, Copy, PartialEq)]
+pub struct ParticleTrails {
+    pub enabled: bool,
+    pub length: f32, // Seconds for a trail to fade to about a third of its brightness
+}
+
+// Settings for the mirrors of the kaleidoscope, which fold the fractal into segments about the center of the view.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Kaleidoscope {
+    pub segments: u32,
+    pub spin: f32, // Radians per second the folded view turns at, scaled by the volume
+    pub mirror_axis: f32, // Degrees from the right of the view to the center of the first segment
+}
+
+// How content is fit to displays which are not square.
+#[derive(Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AspectFit {
+    #[default]
+    Native, // Fill the window, keeping content proportional with its width bound to the aspect ratio
+    Letterbox, // Render content into a centered square with black bars filling the remainder
+    Anamorphic, // Stretch square content to fill the window, spreading particles evenly across it
+}
+
+// Which of the particles and the fractal are shown, and how they share the view.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayLayout {
+    #[default]
+    Both, // Particles composited over the fractal
+    FractalOnly,   // The fractal alone, without simulating particles
+    ParticlesOnly, // The particles alone, skipping the ray-marching of the fractal
+    SplitScreen,   // Particles on the left half of the view and the fractal on the right
+}
+impl DisplayLayout {
+    pub const ALL: [Self; 4] = [
+        Self::Both,
+        Self::FractalOnly,
+        Self::ParticlesOnly,
+        Self::SplitScreen,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Both => "Particles and fractal",
+            Self::FractalOnly => "Fractal only",
+            Self::ParticlesOnly => "Particles only",
+            Self::SplitScreen => "Split-screen",
+        }
+    }
+
+    // The layout after this one, wrapping back to the first.
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|l| *l == self)
+            .unwrap_or_default();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+// The shape of each particle drawn as a sprite, within the quad it is expanded to.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpriteShape {
+    #[default]
+    Square, // The entire quad, as points are drawn
+    Circle,  // A disc with a smoothed edge
+    Glow,    // A soft falloff from the center
+    Star,    // A four-pointed star, turned with the quad to follow the particle's motion
+    Texture, // The `sprite_texture` image, tinted by the particle's color
+}
+
+// How each particle is combined with the particles drawn behind it.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParticleBlending {
+    #[default]
+    Opaque, // The nearest particle covers any behind it
+    Alpha, // Particles are translucent, sorted back-to-front while 3D so that they blend correctly
+}
+
+// Which kind of device audio is captured from at launch, unless `--audio-source` is given.
+#[derive(Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureMode {
+    #[default]
+    Loopback, // The audio playing out of the default output device
+    Microphone, // The default input device, such as a microphone picking up live instruments
+}
+
+impl CaptureMode {
+    pub fn source_kind(self) -> SourceKind {
+        match self {
+            Self::Loopback => SourceKind::Loopback,
+            Self::Microphone => SourceKind::Microphone,
+        }
+    }
+}
+
+// Where the clock of the color keyframes comes from.
+#[derive(Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyframeClockSource {
+    #[default]
+    Launch, // Seconds since launch
+    Mpd,   // The elapsed time of the song playing in MPD
+    Mpris, // The position of the track playing in an MPRIS player, such as Spotify on Linux
+}
+
+// How the clock of the color keyframes follows an external player, if at all.
+#[derive(Clone, PartialEq)]
+pub struct KeyframeClock {
+    pub source: KeyframeClockSource,
+    pub mpd_address: String,          // The `HOST:PORT` address of MPD
+    pub mpris_player: Option<String>, // The name of the MPRIS player, defaulting to the first found
+}
+
+// How the surface of the fractal is colored, independent of which fractal is selected.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColoringMode {
+    #[default]
+    Classic, // The coloring designed alongside each fractal
+    OrbitTrap, // A cosine palette indexed by the orbit trap
+    Normal,    // The direction of the surface normal
+    Iteration, // The number of ray-marching steps taken to reach the surface
+    Reactive,  // A blend of the audio-reactive band directions, weighted by the orbit trap
+}
+impl ColoringMode {
+    // Every mode, ordered by the value the fractal shader uses to identify each.
+    pub const ALL: [Self; 5] = [
+        Self::Classic,
+        Self::OrbitTrap,
+        Self::Normal,
+        Self::Iteration,
+        Self::Reactive,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Classic => "Classic",
+            Self::OrbitTrap => "Orbit-trap palette",
+            Self::Normal => "Surface normal",
+            Self::Iteration => "Iteration count",
+            Self::Reactive => "Band reactive",
+        }
+    }
+
+    // The mode after this one, wrapping back to the first.
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|m| *m == self)
+            .unwrap_or_default();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+// The global force acting on every particle alongside the audio-driven attractors.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhysicsMode {
+    #[default]
+    Attractors, // Only the attractors and springs act on the particles
+    Gravity,   // A constant pull toward the bottom of the screen
+    Vortex,    // A swirl about the center of the view
+    FlowField, // A slowly evolving curl-noise current
+}
+impl PhysicsMode {
+    // Every mode, ordered by the value the particle compute shader uses to identify each.
+    pub const ALL: [Self; 4] = [
+        Self::Attractors,
+        Self::Gravity,
+        Self::Vortex,
+        Self::FlowField,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Attractors => "Attractors",
+            Self::Gravity => "Gravity",
+            Self::Vortex => "Vortex",
+            Self::FlowField => "Flow field",
+        }
+    }
+
+    // The mode after this one, wrapping back to the first.
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|m| *m == self)
+            .unwrap_or_default();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+// How particles are kept within the unit square, or cube in 3D, when they reach its edges.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParticleContainment {
+    None, // Particles fly freely beyond the edges
+    #[default]
+    Bounce, // Particles are reflected back inside, losing a little speed
+    Wrap, // Particles leaving through one edge re-enter through the opposite
+}
+impl ParticleContainment {
+    // Every mode, ordered by the value the particle compute shader uses to identify each.
+    pub const ALL: [Self; 3] = [Self::None, Self::Bounce, Self::Wrap];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Bounce => "Bounce",
+            Self::Wrap => "Wrap",
+        }
+    }
+}
+
+// How colors, which are all specified in sRGB, are written to surfaces which encode to sRGB themselves.
+// Surfaces without sRGB encoding display colors as they are written, so are unaffected
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorManagement {
+    Linear, // Colors are written unconverted, which appear washed-out once encoded
+    #[default]
+    Srgb, // Colors are converted to linear values, so they appear as specified and are blended correctly
+    Compare, // Linear on the left half of the view and sRGB on the right, to help choose between them
+}
+impl ColorManagement {
+    pub const ALL: [Self; 3] = [Self::Linear, Self::Srgb, Self::Compare];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Linear => "Linear",
+            Self::Srgb => "sRGB-correct",
+            Self::Compare => "Side-by-side",
+        }
+    }
+
+    // The horizontal position in normalized device coordinates, left of which colors are written unconverted.
+    pub fn unmanaged_split(self) -> f32 {
+        match self {
+            Self::Linear => 2.,
+            Self::Srgb => -2.,
+            Self::Compare => 0.,
+        }
+    }
+}
+
+// How rendered frames are presented to the display, trading tearing and power for latency.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PresentationMode {
+    #[default]
+    Vsync, // Frames wait for the display's refresh, which every device supports
+    Mailbox,   // The newest frame replaces any waiting for the refresh, without tearing
+    Immediate, // Frames are shown as soon as they are rendered, which may tear
+}
+impl PresentationMode {
+    pub const ALL: [Self; 3] = [Self::Vsync, Self::Mailbox, Self::Immediate];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Vsync => "Vsync",
+            Self::Mailbox => "Mailbox",
+            Self::Immediate => "Immediate",
+        }
+    }
+}
+
+// Whether the overlay windows are drawn light-on-dark or dark-on-light.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeMode {
+    #[default]
+    Dark,
+    Light,
+}
+
+// Appearance of the overlay windows, such as to match venue branding or be readable from a distance.
+#[derive(Clone, Copy, PartialEq)]
+pub struct OverlayTheme {
+    pub mode: ThemeMode,
+    pub accent: Option<[u8; 3]>, // Color of selections and highlights, or the theme's own if unset
+    pub font_scale: f32,
+}
+
+// Overrides of the monitor's scale factor, which otherwise scales the particles and overlay to the pixel density.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct DisplayScale {
+    pub point_size: Option<f32>, // Multiplies the configured point size
+    pub overlay: Option<f32>,    // The overlay's pixels per point
+}
+
+// Rates, per second, at which the visuals follow the audio. Larger rates are snappier, smaller are lazier.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Responsiveness {
+    pub volume: f32, // How quickly the volume, and so the animation speed, follows the latest
+    pub reactive: f32, // How quickly the reactive fractal colors follow the latest notes
+    pub smooth: f32, // How quickly the smooth fractal colors follow the reactive colors
+}
+
+// How many of the loudest notes of each band become particle attractors.
+#[derive(Clone, Copy, PartialEq)]
+pub struct AttractorCounts {
+    pub bass: usize, // Big boomers
+    pub mids: usize, // Curl attractors
+    pub high: usize, // Attractors
+}
+
+// A multiplier of the force of each band's particle attractors.
+#[derive(Clone, Copy, PartialEq)]
+pub struct AttractorStrengths {
+    pub bass: f32, // Big boomers
+    pub mids: f32, // Curl attractors
+    pub high: f32, // Attractors
+}
+
+// The parameters of each frequency band, replacing those of the launch preset, such as once tuned in the overlay.
+// The attractor counts of the bands are unused, since they are configured separately
+#[derive(Clone, Copy, PartialEq)]
+pub struct BandOverrides {
+    pub bass: BandConfig,
+    pub mids: BandConfig,
+    pub high: BandConfig,
+}
+
+impl BandOverrides {
+    // The bands of the given analysis.
+    pub fn of(analysis: &AnalysisConfig) -> Self {
+        Self {
+            bass: analysis.bass,
+            mids: analysis.mids,
+            high: analysis.high,
+        }
+    }
+
+    // Use these bands in place of those of the given analysis, keeping its attractor counts.
+    pub fn apply(self, analysis: AnalysisConfig) -> AnalysisConfig {
+        let counts = analysis.attractor_counts();
+        AnalysisConfig {
+            bass: self.bass,
+            mids: self.mids,
+            high: self.high,
+            ..analysis
+        }
+        .with_attractor_counts(counts)
+    }
+}
+
+// A named set of audio-analysis parameters, either built in or defined in the config file.
+// The attractor counts of a preset are unused, since they are configured separately
+#[derive(Clone, PartialEq)]
+pub struct AnalysisPreset {
+    pub name: String,
+    pub analysis: AnalysisConfig,
+    pub builtin: bool,
+}
+
+// Settings for tinting the visuals by the musical key detected in the audio.
+#[derive(Clone, Copy, PartialEq)]
+pub struct KeyHue {
+    pub enabled: bool,
+    pub response: f32, // Rate, per second, at which the hue follows a change of key
+    pub hues: [f32; 12], // Hue rotation in degrees for the major key of each pitch class, beginning from C
+}
+
+// Thresholds for automatically balancing particles against the fractal by the character of the music.
+#[derive(Clone, Copy, PartialEq)]
+pub struct AutoBalance {
+    pub enabled: bool,
+    pub quiet_ratio: f32, // Recent loudness, relative to the last half-minute, below which a passage is quiet
+    pub ambient_kick_rate: f32, // Kicks per second at or below which a quiet passage is ambient
+    pub percussive_kick_rate: f32, // Kicks per second at or above which a passage is percussive
+    pub response: f32,    // Rate, per second, of the transitions between balances
+}
+
+// Settings for automatically switching to the next color scheme with the music.
+#[derive(Clone, Copy, PartialEq)]
+pub struct AutoCycle {
+    pub enabled: bool,
+    pub kicks: u32,          // Kicks between switches, or zero to not switch on kicks
+    pub energy_ratio: f32, // Recent loudness, relative to the last half-minute, above which the music is high-energy
+    pub energy_seconds: f32, // Seconds of sustained high energy between switches, or zero to not switch on energy
+}
+
+// Shape of the sidechain-style pump, which ducks the fractal on each kick and then releases it.
+#[derive(Clone, Copy, PartialEq)]
+pub struct SidechainPump {
+    pub enabled: bool,
+    pub brightness: f32, // Fraction of the fractal's brightness ducked at the peak
+    pub orbit: f32,      // Fraction of the fractal's orbit distance ducked at the peak
+    pub attack: f32,     // Seconds to reach the peak after a kick
+    pub release: f32,    // Seconds to return from the peak
+    pub release_curve: f32, // Exponent of the release, where values above one recover quickly at first
+}
+
+// The pair of color schemes blended by the crossfade fader, and where the fader is controlled from.
+#[derive(Clone, Copy, PartialEq)]
+pub struct SchemeCrossfade {
+    pub scheme_a: usize,       // Index of the scheme displayed with the fader at zero
+    pub scheme_b: usize,       // Index of the scheme displayed with the fader at one
+    pub osc_port: Option<u16>, // UDP port receiving the fader position as OSC messages
+}
+
+// How a target of the intensity macro follows the knob, as a multiplier of the target's own value.
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct IntensityCurve {
+    pub calm: f32,     // The multiplier at zero intensity
+    pub hyped: f32,    // The multiplier at full intensity
+    pub exponent: f32, // The shape of the curve between them, where one is linear
+}
+
+// The curve of each parameter the intensity macro scales.
+#[derive(Clone, Copy, PartialEq)]
+pub struct IntensityCurves {
+    pub audio_scale: IntensityCurve,
+    pub attractor_strength: IntensityCurve, // The strength of the audio-driven attractors and big boomer
+    pub kaleidoscope_max: IntensityCurve, // The extent the kaleidoscope reaches when fully toggled
+    pub angular_velocity_max: IntensityCurve, // The speed the kicks spin the camera at
+}
+
+// The multiplier of each parameter the intensity macro scales, at a single intensity.
+#[derive(Clone, Copy)]
+pub struct IntensityMultipliers {
+    pub audio_scale: f32,
+    pub attractor_strength: f32,
+    pub kaleidoscope_max: f32,
+    pub angular_velocity_max: f32,
+}
+
+// A single knob scaling several parameters at once, to calm or hype the entire visual.
+#[derive(Clone, Copy, PartialEq)]
+pub struct IntensityMacro {
+    pub intensity: f32, // In [0, 1], where the default curves leave every parameter unchanged at one half
+    pub curves: IntensityCurves,
+    pub osc_port: Option<u16>, // UDP port receiving the intensity as OSC messages
+}
+
+impl IntensityCurve {
+    pub fn multiplier(self, intensity: f32) -> f32 {
+        self.calm + (self.hyped - self.calm) * intensity.powf(self.exponent)
+    }
+}
+impl IntensityCurves {
+    pub fn multipliers(&self, intensity: f32) -> IntensityMultipliers {
+        IntensityMultipliers {
+            audio_scale: self.audio_scale.multiplier(intensity),
+            attractor_strength: self.attractor_strength.multiplier(intensity),
+            kaleidoscope_max: self.kaleidoscope_max.multiplier(intensity),
+            angular_velocity_max: self.angular_velocity_max.multiplier(intensity),
+        }
+    }
+}
+
+// An intro in which the particles begin arranged as a logo, then burst into the simulation.
+#[derive(Clone, PartialEq)]
+pub struct LogoIntro {
+    pub image: Option<String>, // Path of a PNG image whose opaque pixels the particles are arranged over
+    pub hold: f32,             // Seconds to hold the logo before bursting
+    pub ramp: f32,             // Seconds over which the audio reactivity ramps in after the burst
+}
+
+// Settings for the slots of saved looks, recalled with the number keys.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Snapshots {
+    pub persist: bool,       // Whether the saved looks are kept between launches
+    pub recall_seconds: f32, // Seconds over which a recalled look is blended in
+}
+
+// Settings for the screensaver-style scenes cycled through while no one is interacting with the app.
+#[derive(Clone, Copy, PartialEq)]
+pub struct AttractMode {
+    pub enabled: bool,
+    pub idle_seconds: f32,  // Seconds without input before the scenes begin
+    pub scene_seconds: f32, // Seconds each scene is shown
+    pub transition_seconds: f32, // Seconds over which each scene is blended in
+}
+
+// Settings for presenting directly to a display, instead of to the window.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct DirectDisplay {
+    pub enabled: bool,
+    pub display: u32, // The index of the display, as listed by `--list-displays`
+    pub mode: u32,    // The index of the display's mode
+}
+
+// Settings for publishing the rendered frames as a virtual webcam.
+#[derive(Clone, PartialEq)]
+pub struct VirtualCamera {
+    pub enabled: bool,
+    pub device: String, // The `v4l2loopback` video device to write frames to, only used on Linux
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+}
+
+// Settings for saving a frame periodically while a timelapse is running.
+#[derive(Clone, PartialEq)]
+pub struct Timelapse {
+    pub directory: Option<String>, // Where each session's folder is created, defaulting to the user's pictures
+    pub interval: f32,             // Seconds between saved frames
+    pub beats: Option<u32>,        // Kicks between saved frames, replacing the interval when set
+    pub width: u32,
+    pub height: u32,
+}
+
+// Settings for recording a short clip as an animated GIF.
+#[derive(Clone, PartialEq)]
+pub struct Clip {
+    pub seconds: f32, // The length of each clip
+    pub fps: u32,
+    pub width: u32, // Frames are downsampled to this width, keeping their aspect ratio
+}
+
+// The MIDI message which triggers a mapping, by its controller or note number.
+#[derive(Clone, Copy, PartialEq)]
+pub enum MidiInput {
+    Control(u8), // A knob, fader, or button sending control changes
+    Note(u8),    // A pad or key, triggering on each press
+}
+
+// What a MIDI mapping drives.
+#[derive(Clone, PartialEq)]
+pub enum MidiTarget {
+    // A runtime variable, as listed by the Variables console, or the kaleidoscope amount.
+    // Floats span the given range, defaulting to the variable's own, while booleans are on past the halfway point
+    Variable {
+        name: String,
+        range: Option<(f32, f32)>,
+    },
+    Action(Action), // Triggered as though by its keybinding
+}
+
+#[derive(Clone, PartialEq)]
+pub struct MidiMapping {
+    pub channel: Option<u8>, // In [1, 16], or any channel if omitted
+    pub input: MidiInput,
+    pub target: MidiTarget,
+}
+
+// Settings for receiving the knobs, faders, and pads of a MIDI controller.
+#[derive(Clone, Default, PartialEq)]
+pub struct Midi {
+    pub device: Option<String>, // Part of the name of the input port to open, defaulting to the first port
+    pub mappings: Vec<MidiMapping>,
+}
+
+// Destinations notified of notable events, such as kicks and changes of color scheme.
+#[derive(Clone, PartialEq)]
+pub struct EventHooks {
+    pub webhooks: Vec<String>, // `http://` URLs which each event is POSTed to as JSON
+    pub osc_targets: Vec<String>, // `HOST:PORT` addresses which each event is sent to as an OSC message
+    pub kick_threshold: f32,      // The least strength, in [0, 1], of a kick which is published
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum CustomSchemeColor {
+    ColorString(String),
+    ColorStringVal(String, f32),
+    Vec4(Vec<f32>),
+}
+
+#[derive(Deserialize, Serialize)]
+struct CustomScheme {
+    pub name: String,
+    pub speed: [CustomSchemeColor; 4],
+    pub index: [CustomSchemeColor; 4],
+}
+
+// Overrides of the parameters of a single frequency band, where any omitted are those of the base preset.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct CustomBand {
+    pub min_frequency: Option<f32>,
+    pub max_frequency: Option<f32>,
+    pub delta: Option<f32>,
+    pub min_volume: Option<f32>,
+    pub vol_freq_scale: Option<f32>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct CustomKick {
+    pub min_magnitude: Option<f32>,
+    pub history_ratio: Option<f32>,
+    pub min_interval: Option<f32>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct CustomAnalysisPreset {
+    pub name: String,
+    pub base: Option<String>, // The name of a previously defined preset to override, defaulting to the first
+    pub bass: Option<CustomBand>,
+    pub mids: Option<CustomBand>,
+    pub high: Option<CustomBand>,
+    pub kick: Option<CustomKick>,
+}
+
+// Overrides of the bands of the launch preset, where any omitted are those of the preset.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct CustomBands {
+    pub bass: Option<CustomBand>,
+    pub mids: Option<CustomBand>,
+    pub high: Option<CustomBand>,
+}
+
+// The `[audio]` table, grouping the overrides of the audio analysis.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct CustomAudio {
+    pub bands: Option<CustomBands>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct CustomStem {
+    pub name: String,
+    pub source: String, // Selected as with `--audio-source`
+    #[serde(default)]
+    pub channels: Vec<StemChannel>,
+}
+
+// Overrides of the curves of the intensity macro, where any omitted are the defaults.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct CustomIntensityCurves {
+    pub audio_scale: Option<IntensityCurve>,
+    pub attractor_strength: Option<IntensityCurve>,
+    pub kaleidoscope_max: Option<IntensityCurve>,
+    pub angular_velocity_max: Option<IntensityCurve>,
+}
+
+// Overrides of the tunables of a fractal, where any omitted are the fractal's built-in values.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct CustomTunables {
+    pub iterations: Option<u32>,
+    pub bailout: Option<f32>,
+    pub fold_scale: Option<f32>,
+    pub color_exponent: Option<f32>,
+}
+
+// A mapping of a MIDI control change or note to a runtime variable or an action.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct CustomMidiMapping {
+    pub channel: Option<u8>,
+    pub control: Option<u8>,
+    pub note: Option<u8>,
+    pub variable: Option<String>,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+    pub action: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct CustomKeyframe {
+    pub time: f32,
+    pub scheme: String, // The name of the color scheme
+}
+
+// The chords bound to an action, given either alone or as a list.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum CustomKeybinding {
+    Chord(String),
+    Chords(Vec<String>),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct TomlData {
+    pub launch_fullscreen: Option<bool>,
+    pub launch_help_visible: Option<bool>,
+    pub aspect_fit: Option<AspectFit>,
+    pub display_layout: Option<DisplayLayout>,
+    pub coloring_mode: Option<ColoringMode>,
+    pub physics_mode: Option<PhysicsMode>,
+    pub particle_containment: Option<ParticleContainment>,
+    pub color_management: Option<ColorManagement>,
+    pub present_mode: Option<PresentationMode>,
+    pub direct_display: Option<bool>,
+    pub direct_display_index: Option<u32>,
+    pub direct_display_mode: Option<u32>,
+    pub overlay_theme: Option<ThemeMode>,
+    pub overlay_accent: Option<String>,
+    pub overlay_font_scale: Option<f32>,
+    pub point_size_scale: Option<f32>,
+    pub overlay_pixels_per_point: Option<f32>,
+
+    pub particle_count: Option<NonZeroUsize>,
+    pub msaa_samples: Option<u32>,
+    pub hide_stationary_particles: Option<bool>,
+    pub disable_background: Option<bool>,
+    pub kick_echoes: Option<bool>,
+    pub stereo_placement: Option<f32>,
+    pub stereo_attractors: Option<bool>,
+    pub capture_mode: Option<CaptureMode>,
+    pub particle_sprites: Option<bool>,
+    pub sprite_shape: Option<SpriteShape>,
+    pub sprite_texture: Option<String>,
+    pub particle_blending: Option<ParticleBlending>,
+    pub particle_alpha: Option<f32>,
+    pub surface_emission: Option<bool>,
+    pub surface_emission_rate: Option<f32>,
+
+    pub particle_repulsion: Option<bool>,
+    pub repulsion_strength: Option<f32>,
+    pub repulsion_radius: Option<f32>,
+
+    pub depth_of_field: Option<bool>,
+    pub dof_aperture: Option<f32>,
+    pub dof_focus_distance: Option<f32>,
+    pub dof_track_big_boomer: Option<bool>,
+
+    pub particle_trails: Option<bool>,
+    pub trail_length: Option<f32>,
+
+    pub kaleidoscope_segments: Option<u32>,
+    pub kaleidoscope_spin: Option<f32>,
+    pub kaleidoscope_axis: Option<f32>,
+
+    pub bloom: Option<bool>,
+    pub bloom_threshold: Option<f32>,
+    pub bloom_intensity: Option<f32>,
+
+    pub tone_mapping: Option<ToneMapOperator>,
+    pub exposure: Option<f32>,
+
+    pub temporal_antialiasing: Option<bool>,
+    pub progressive_refinement: Option<bool>,
+
+    pub volume_response: Option<f32>,
+    pub reactive_response: Option<f32>,
+    pub smooth_response: Option<f32>,
+
+    pub bass_attractors: Option<usize>,
+    pub mids_attractors: Option<usize>,
+    pub high_attractors: Option<usize>,
+    pub bass_attractor_strength: Option<f32>,
+    pub mids_attractor_strength: Option<f32>,
+    pub high_attractor_strength: Option<f32>,
+
+    pub analysis_preset: Option<String>,
+    pub audio: Option<CustomAudio>,
+
+    pub gain_control: Option<bool>,
+    pub gain_target: Option<f32>,
+    pub gain_attack: Option<f32>,
+    pub gain_release: Option<f32>,
+
+    pub key_hue: Option<bool>,
+    pub key_hue_response: Option<f32>,
+    pub key_hues: Option<[f32; 12]>,
+
+    pub auto_balance: Option<bool>,
+    pub balance_quiet_ratio: Option<f32>,
+    pub balance_ambient_kick_rate: Option<f32>,
+    pub balance_percussive_kick_rate: Option<f32>,
+    pub balance_response: Option<f32>,
+
+    pub sidechain_pump: Option<bool>,
+    pub pump_brightness: Option<f32>,
+    pub pump_orbit: Option<f32>,
+    pub pump_attack: Option<f32>,
+    pub pump_release: Option<f32>,
+    pub pump_release_curve: Option<f32>,
+
+    pub scheme_transition: Option<f32>,
+
+    pub auto_cycle: Option<bool>,
+    pub cycle_kicks: Option<u32>,
+    pub cycle_energy_ratio: Option<f32>,
+    pub cycle_energy_seconds: Option<f32>,
+
+    pub crossfade_scheme_a: Option<String>,
+    pub crossfade_scheme_b: Option<String>,
+    pub crossfade_osc_port: Option<u16>,
+
+    pub intensity: Option<f32>,
+    pub intensity_osc_port: Option<u16>,
+    pub intensity_curves: Option<CustomIntensityCurves>,
+
+    pub performer_osc_port: Option<u16>,
+
+    pub midi_device: Option<String>,
+    #[serde(default)]
+    pub midi_mappings: Vec<CustomMidiMapping>,
+
+    pub persist_snapshots: Option<bool>,
+    pub snapshot_recall: Option<f32>,
+
+    pub attract_mode: Option<bool>,
+    pub attract_idle: Option<f32>,
+    pub attract_scene: Option<f32>,
+    pub attract_transition: Option<f32>,
+
+    pub intro_logo: Option<String>,
+    pub intro_hold: Option<f32>,
+    pub intro_ramp: Option<f32>,
+
+    pub virtual_camera: Option<bool>,
+    pub virtual_camera_device: Option<String>,
+    pub virtual_camera_width: Option<u32>,
+    pub virtual_camera_height: Option<u32>,
+    pub virtual_camera_fps: Option<u32>,
+
+    pub timelapse_directory: Option<String>,
+    pub timelapse_interval: Option<f32>,
+    pub timelapse_beats: Option<u32>,
+    pub timelapse_width: Option<u32>,
+    pub timelapse_height: Option<u32>,
+
+    pub clip_seconds: Option<f32>,
+    pub clip_fps: Option<u32>,
+    pub clip_width: Option<u32>,
+
+    #[serde(default)]
+    pub event_webhooks: Vec<String>,
+    #[serde(default)]
+    pub event_osc_targets: Vec<String>,
+    pub kick_event_threshold: Option<f32>,
+
+    pub keyframe_clock: Option<KeyframeClockSource>,
+    pub keyframe_mpd_address: Option<String>,
+    pub keyframe_mpris_player: Option<String>,
+
+    #[serde(default)]
+    pub analysis_presets: Vec<CustomAnalysisPreset>,
+
+    #[serde(default)]
+    pub stems: Vec<CustomStem>,
+
+    #[serde(default)]
+    pub color_schemes: Vec<CustomScheme>,
+
+    #[serde(default)]
+    pub color_keyframes: Vec<CustomKeyframe>,
+
+    #[serde(default)]
+    pub camera_paths: Vec<CameraPath>,
+
+    #[serde(default)]
+    pub particle_systems: Vec<ParticleSystem>,
+
+    #[serde(default)]
+    pub scenes: Vec<Scene>,
+
+    #[serde(default)]
+    pub live_uniforms: BTreeMap<String, f32>,
+    pub user_fractal: Option<String>,
+
+    #[serde(default)]
+    pub fractal_tunables: BTreeMap<String, CustomTunables>,
+
+    #[serde(default)]
+    pub keybindings: BTreeMap<String, CustomKeybinding>,
+}
+
+// Hardcoded default values. The defaults of shader parameters are declared in their registry
+const DEFAULT_HELP_VISIBLE: bool = true;
+const DEFAULT_PARTICLE_COUNT: usize = 1_250_000;
+const DEFAULT_MSAA_SAMPLES: u32 = 8;
+pub const MSAA_SAMPLE_CHOICES: [u32; 4] = [1, 2, 4, 8];
+
+// Conservative values used in safe mode, chosen to run on nearly any device.
+const SAFE_MODE_PARTICLE_COUNT: usize = 100_000;
+const DEFAULT_HIDE_STATIONARY_PARTICLES: bool = false;
+const DEFAULT_PARTICLE_ALPHA: f32 = 0.5;
+const DEFAULT_SURFACE_EMISSION_RATE: f32 = 0.5;
+const DEFAULT_REPULSION_STRENGTH: f32 = 8.;
+const DEFAULT_REPULSION_RADIUS: f32 = 0.01;
+const DEFAULT_DOF_APERTURE: f32 = 0.012;
+const DEFAULT_DOF_FOCUS_DISTANCE: f32 = 1.75; // The distance from the particle camera to the origin
+const DEFAULT_DOF_TRACK_BIG_BOOMER: bool = true;
+const DEFAULT_TRAIL_LENGTH: f32 = 0.3;
+const DEFAULT_KALEIDOSCOPE_SEGMENTS: u32 = 6;
+const DEFAULT_BLOOM_THRESHOLD: f32 = 0.5;
+const DEFAULT_BLOOM_INTENSITY: f32 = 1.;
+const DEFAULT_EXPOSURE: f32 = 1.;
+const DEFAULT_VOLUME_RESPONSE: f32 = 1.8;
+const DEFAULT_REACTIVE_RESPONSE: f32 = 0.36;
+const DEFAULT_SMOOTH_RESPONSE: f32 = 0.15;
+const DEFAULT_VIRTUAL_CAMERA_DEVICE: &str = "/dev/video10";
+const DEFAULT_VIRTUAL_CAMERA_WIDTH: u32 = 1280;
+const DEFAULT_VIRTUAL_CAMERA_HEIGHT: u32 = 720;
+const DEFAULT_VIRTUAL_CAMERA_FPS: u32 = 30;
+const DEFAULT_TIMELAPSE_INTERVAL: f32 = 10.;
+const DEFAULT_TIMELAPSE_WIDTH: u32 = 1920;
+const DEFAULT_TIMELAPSE_HEIGHT: u32 = 1080;
+const MIN_TIMELAPSE_INTERVAL: f32 = 0.1;
+const MAX_TIMELAPSE_INTERVAL: f32 = 3600.;
+const DEFAULT_CLIP_SECONDS: f32 = 10.;
+const DEFAULT_CLIP_FPS: u32 = 15;
+const DEFAULT_CLIP_WIDTH: u32 = 480;
+const MIN_CLIP_SECONDS: f32 = 1.;
+const MAX_CLIP_SECONDS: f32 = 60.;
+const MAX_CLIP_FPS: u32 = 50;
+const MIN_BAND_FREQUENCY: f32 = 20.;
+const MAX_BAND_FREQUENCY: f32 = 20_000.;
+const DEFAULT_KICK_EVENT_THRESHOLD: f32 = 0.5;
+const DEFAULT_MPD_ADDRESS: &str = "127.0.0.1:6600";
+const DEFAULT_KEY_HUE_RESPONSE: f32 = 0.25;
+const DEFAULT_OVERLAY_FONT_SCALE: f32 = 1.;
+const MAX_CAMERA_ORBIT: f32 = 4.;
+const DEFAULT_BALANCE_QUIET_RATIO: f32 = 0.6;
+const DEFAULT_BALANCE_AMBIENT_KICK_RATE: f32 = 0.1;
+const DEFAULT_BALANCE_PERCUSSIVE_KICK_RATE: f32 = 0.5;
+const DEFAULT_BALANCE_RESPONSE: f32 = 0.3;
+pub const MIN_GAIN_TARGET: f32 = -40.;
+pub const MAX_GAIN_TARGET: f32 = 0.;
+pub const MAX_GAIN_SECONDS: f32 = 30.;
+const DEFAULT_PUMP_BRIGHTNESS: f32 = 0.5;
+const DEFAULT_PUMP_ORBIT: f32 = 0.1;
+const DEFAULT_PUMP_ATTACK: f32 = 0.01;
+const DEFAULT_PUMP_RELEASE: f32 = 0.35;
+const DEFAULT_PUMP_RELEASE_CURVE: f32 = 2.;
+const DEFAULT_INTENSITY: f32 = 0.5;
+const DEFAULT_INTENSITY_CURVE: IntensityCurve = IntensityCurve {
+    calm: 0.5,
+    hyped: 1.5,
+    exponent: 1.,
+};
+const DEFAULT_INTRO_HOLD: f32 = 3.;
+const DEFAULT_STEREO_PLACEMENT: f32 = 0.5;
+const DEFAULT_INTRO_RAMP: f32 = 2.;
+const MAX_INTRO_SECONDS: f32 = 60.;
+const DEFAULT_PERSIST_SNAPSHOTS: bool = true;
+const DEFAULT_SNAPSHOT_RECALL: f32 = 1.5;
+const DEFAULT_SCHEME_TRANSITION: f32 = 0.5;
+const MAX_SCHEME_TRANSITION: f32 = 10.;
+const DEFAULT_CYCLE_KICKS: u32 = 32;
+const DEFAULT_CYCLE_ENERGY_RATIO: f32 = 1.3;
+const DEFAULT_CYCLE_ENERGY_SECONDS: f32 = 8.;
+const MAX_SNAPSHOT_RECALL: f32 = 60.;
+const DEFAULT_ATTRACT_IDLE: f32 = 120.;
+const DEFAULT_ATTRACT_SCENE: f32 = 30.;
+const DEFAULT_ATTRACT_TRANSITION: f32 = 3.;
+const MAX_ATTRACT_SECONDS: f32 = 3600.;
+const DEFAULT_SCENE_TRANSITION: f32 = 2.;
+
+// Keys are spread around the hue wheel by the circle of fifths, so closely related keys have similar tints.
+const DEFAULT_KEY_HUES: [f32; 12] = [
+    0., 210., 60., 270., 120., 330., 180., 30., 240., 90., 300., 150.,
+];
+
+// The largest permitted repulsion radius. Larger radii bin so many particles per cell that most neighbors are skipped.
+pub const MAX_REPULSION_RADIUS: f32 = 0.1;
+
+// The multiplier of each band's attractor force, and the largest permitted. Stronger attractors fling the particles beyond the scene.
+const DEFAULT_ATTRACTOR_STRENGTH: f32 = 1.;
+pub const MAX_ATTRACTOR_STRENGTH: f32 = 4.;
+
+// Limits on the depth-of-field parameters. Apertures beyond this blur most particles to the shader's maximum radius.
+pub const MAX_DOF_APERTURE: f32 = 0.05;
+pub const MIN_DOF_FOCUS_DISTANCE: f32 = 0.25;
+pub const MAX_DOF_FOCUS_DISTANCE: f32 = 4.;
+
+// Limits on the trail length. Shorter trails are imperceptible at common frame rates, while longer trails smear the entire view.
+pub const MIN_TRAIL_LENGTH: f32 = 0.02;
+pub const MAX_TRAIL_LENGTH: f32 = 3.;
+
+// The largest permitted surface-emission rate. Faster emission respawns particles before they can move from the surface.
+pub const MAX_SURFACE_EMISSION_RATE: f32 = 10.;
+
+// Limits on the kaleidoscope. More segments fold the view into slivers too thin to make out, and faster spins blur them.
+pub const MIN_KALEIDOSCOPE_SEGMENTS: u32 = 2;
+pub const MAX_KALEIDOSCOPE_SEGMENTS: u32 = 24;
+pub const MAX_KALEIDOSCOPE_SPIN: f32 = 2.;
+
+// The largest permitted bloom intensity. Brighter glows wash the particles out to white.
+pub const MAX_BLOOM_INTENSITY: f32 = 4.;
+
+// Limits on the exposure, beyond which the scene is nearly black or entirely washed out.
+pub const MIN_EXPOSURE: f32 = 0.1;
+pub const MAX_EXPOSURE: f32 = 8.;
+
+// Limits on the responsiveness rates. Near zero the visuals barely follow the audio, while the largest rates strobe with every note.
+pub const MIN_RESPONSE: f32 = 0.01;
+pub const MAX_VOLUME_RESPONSE: f32 = 12.;
+pub const MAX_REACTIVE_RESPONSE: f32 = 4.;
+pub const MAX_SMOOTH_RESPONSE: f32 = 2.;
+pub const MAX_KEY_HUE_RESPONSE: f32 = 2.;
+pub const MAX_BALANCE_RESPONSE: f32 = 2.;
+pub const MAX_CYCLE_KICKS: u32 = 128;
+pub const MIN_CYCLE_ENERGY_RATIO: f32 = 1.;
+pub const MAX_CYCLE_ENERGY_RATIO: f32 = 3.;
+pub const MAX_CYCLE_ENERGY_SECONDS: f32 = 60.;
+pub const MAX_PUMP_ORBIT: f32 = 0.5;
+pub const MAX_PUMP_ATTACK: f32 = 0.25;
+pub const MAX_PUMP_RELEASE: f32 = 2.;
+pub const MIN_PUMP_RELEASE_CURVE: f32 = 0.25;
+pub const MAX_PUMP_RELEASE_CURVE: f32 = 4.;
+
+// Limits on the scale of overlay text, from compact to legible on a projector across a room.
+pub const MIN_OVERLAY_FONT_SCALE: f32 = 0.5;
+const MAX_DISPLAY_SCALE: f32 = 8.;
+pub const MAX_OVERLAY_FONT_SCALE: f32 = 3.;
+pub const MAX_CAMERA_PATHS: usize = 9;
+pub const MAX_PARTICLE_SYSTEMS: usize = 4;
+pub const MAX_LIVE_UNIFORMS: usize = 16;
+
+#[derive(Clone)]
+pub struct AppConfig {
+    pub launch_fullscreen: bool,
+    pub launch_help_visible: bool,
+    pub aspect_fit: AspectFit,
+    pub display_layout: DisplayLayout,
+    pub coloring_mode: ColoringMode,
+    pub physics_mode: PhysicsMode,
+    pub particle_containment: ParticleContainment,
+    pub color_management: ColorManagement,
+    pub present_mode: PresentationMode,
+    pub direct_display: DirectDisplay,
+    pub overlay_theme: OverlayTheme,
+    pub display_scale: DisplayScale,
+
+    pub parameters: ParameterValues, // The presented values of the shader parameters
+    pub particle_count: usize,
+    pub msaa_samples: u32, // The samples per pixel when rendering particles, one of `MSAA_SAMPLE_CHOICES`
+    pub hide_stationary_particles: bool,
+    pub disable_background: bool,
+    pub kick_echoes: bool,
+    pub stereo_placement: f32, // How far, in [0, 1], attractors move toward where their band is heard in the stereo field
+    pub stereo_attractors: bool, // Whether the mids and high attractors follow the left and right channels, mirrored to their sides
+    pub capture_mode: CaptureMode,
+    pub particle_sprites: bool, // Whether particles are drawn as instanced quads rather than points
+    pub sprite_shape: SpriteShape,
+    pub sprite_texture: Option<String>, // A PNG image drawn by sprites of the `Texture` shape
+    pub particle_blending: ParticleBlending,
+    pub particle_alpha: f32, // The opacity of each particle, in (0, 1], when alpha-blended
+    pub surface_emission: bool, // Whether particles are respawned on the fractal's surface
+    pub surface_emission_rate: f32, // The fraction of particles respawned each second while emitting
+
+    pub particle_repulsion: ParticleRepulsion,
+
+    pub depth_of_field: DepthOfField,
+
+    pub particle_trails: ParticleTrails,
+
+    pub kaleidoscope: Kaleidoscope,
+
+    pub bloom: ParticleBloom,
+
+    pub tone_mapping: ToneMapping,
+
+    pub temporal_antialiasing: bool,
+    pub progressive_refinement: bool,
+
+    pub responsiveness: Responsiveness,
+
+    pub attractor_counts: AttractorCounts,
+    pub attractor_strengths: AttractorStrengths,
+
+    pub analysis_presets: Vec<AnalysisPreset>, // The built-in presets followed by any defined in the config file
+    pub analysis_preset: usize, // Index of the preset the audio is analyzed with at launch
+    pub bands: Option<BandOverrides>, // Replacing the bands of the launch preset, if any
+    pub gain_control: GainControl,
+
+    pub stems: Vec<Stem>, // Inputs analyzed separately, replacing the single audio source when any are defined
+
+    pub key_hue: KeyHue,
+
+    pub auto_balance: AutoBalance,
+
+    pub sidechain_pump: SidechainPump,
+
+    pub scheme_transition: f32, // Seconds over which switching color schemes blends between them
+
+    pub auto_cycle: AutoCycle,
+
+    pub scheme_crossfade: SchemeCrossfade,
+
+    pub intensity_macro: IntensityMacro,
+
+    // UDP port receiving the blackout and freeze switches as OSC messages
+    pub performer_osc_port: Option<u16>,
+
+    pub midi: Midi,
+
+    pub snapshots: Snapshots,
+
+    pub attract_mode: AttractMode,
+
+    pub logo_intro: LogoIntro,
+
+    pub virtual_camera: VirtualCamera,
+
+    pub timelapse: Timelapse,
+
+    pub clip: Clip,
+
+    pub event_hooks: EventHooks,
+
+    pub color_schemes: Vec<Scheme>,
+    pub color_scheme_names: Vec<String>,
+    pub color_keyframes: Vec<ColorKeyframe>, // Sorted by time
+    pub keyframe_clock: KeyframeClock,
+
+    pub camera_paths: Vec<CameraPath>, // Played by the keys SHIFT+1 through SHIFT+9, in order
+    pub particle_systems: Vec<ParticleSystem>, // Drawn alongside the main particles, in order
+
+    pub scenes: Vec<Scene>, // The show, played in order and looping back to the first scene
+
+    pub live_uniforms: BTreeMap<String, f32>, // Custom shader constants, uploaded in order of their names
+    pub user_fractal: Option<String>, // A GLSL file defining a custom distance estimator, compiled at runtime
+
+    pub fractal_tunables: [fractals::Tunables; fractals::FRACTAL_COUNT], // Indexed by distance estimator ID
+
+    pub keybindings: Vec<(Action, Vec<Chord>)>, // Actions whose default chords are replaced
+}
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            launch_fullscreen: bool::default(),
+            launch_help_visible: DEFAULT_HELP_VISIBLE,
+            aspect_fit: AspectFit::default(),
+            display_layout: DisplayLayout::default(),
+            coloring_mode: ColoringMode::default(),
+            physics_mode: PhysicsMode::default(),
+            particle_containment: ParticleContainment::default(),
+            color_management: ColorManagement::default(),
+            present_mode: PresentationMode::default(),
+            direct_display: DirectDisplay::default(),
+            overlay_theme: OverlayTheme::default(),
+            display_scale: DisplayScale::default(),
+
+            parameters: parameters::default_values(),
+            particle_count: DEFAULT_PARTICLE_COUNT,
+            msaa_samples: DEFAULT_MSAA_SAMPLES,
+            hide_stationary_particles: DEFAULT_HIDE_STATIONARY_PARTICLES,
+            disable_background: bool::default(),
+            kick_echoes: bool::default(),
+            stereo_placement: DEFAULT_STEREO_PLACEMENT,
+            stereo_attractors: bool::default(),
+            capture_mode: CaptureMode::default(),
+            particle_sprites: bool::default(),
+            sprite_shape: SpriteShape::default(),
+            sprite_texture: None,
+            particle_blending: ParticleBlending::default(),
+            particle_alpha: DEFAULT_PARTICLE_ALPHA,
+            surface_emission: bool::default(),
+            surface_emission_rate: DEFAULT_SURFACE_EMISSION_RATE,
+
+            particle_repulsion: ParticleRepulsion::default(),
+
+            depth_of_field: DepthOfField::default(),
+            particle_trails: ParticleTrails::default(),
+            kaleidoscope: Kaleidoscope::default(),
+            bloom: ParticleBloom::default(),
+            tone_mapping: ToneMapping::default(),
+
+            temporal_antialiasing: bool::default(),
+            progressive_refinement: bool::default(),
+
+            responsiveness: Responsiveness::default(),
+
+            attractor_counts: AttractorCounts::default(),
+            attractor_strengths: AttractorStrengths::default(),
+
+            analysis_presets: builtin_analysis_presets(),
+            analysis_preset: 0,
+            bands: None,
+            gain_control: DEFAULT_GAIN_CONTROL,
+
+            stems: Vec::new(),
+
+            key_hue: KeyHue::default(),
+
+            auto_balance: AutoBalance::default(),
+
+            sidechain_pump: SidechainPump::default(),
+            scheme_transition: DEFAULT_SCHEME_TRANSITION,
+            auto_cycle: AutoCycle::default(),
+            scheme_crossfade: SchemeCrossfade::default(),
+            intensity_macro: IntensityMacro::default(),
+            performer_osc_port: None,
+            midi: Midi::default(),
+            snapshots: Snapshots::default(),
+            attract_mode: AttractMode::default(),
+            logo_intro: LogoIntro::default(),
+
+            virtual_camera: VirtualCamera::default(),
+
+            timelapse: Timelapse::default(),
+
+            clip: Clip::default(),
+
+            event_hooks: EventHooks::default(),
+
+            color_schemes: COLOR_SCHEMES.to_vec(),
+            color_scheme_names: COLOR_SCHEME_NAMES.into_iter().map(String::from).collect(),
+            color_keyframes: Vec::new(),
+            keyframe_clock: KeyframeClock::default(),
+
+            camera_paths: Vec::new(),
+            particle_systems: Vec::new(),
+            scenes: Vec::new(),
+
+            live_uniforms: BTreeMap::new(),
+            user_fractal: None,
+
+            fractal_tunables: fractals::default_tunables(),
+
+            keybindings: Vec::new(),
+        }
+    }
+}
+
+impl AppConfig {
+    // Conservative settings for diagnosing crashes, which do not depend on any config file.
+    pub fn safe_mode() -> Self {
+        Self {
+            particle_count: SAFE_MODE_PARTICLE_COUNT,
+            msaa_samples: 1,
+            ..Self::default()
+        }
+    }
+
+    // The analysis to launch with, being the selected preset with the configured attractor counts, gain control, and bands.
+    pub fn launch_analysis(&self) -> AnalysisConfig {
+        let analysis = self.analysis_presets[self.analysis_preset]
+            .analysis
+            .with_attractor_counts(self.attractor_counts)
+            .with_gain_control(self.gain_control);
+        self.bands.map_or(analysis, |bands| bands.apply(analysis))
+    }
+}
+
+impl Default for ParticleRepulsion {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strength: DEFAULT_REPULSION_STRENGTH,
+            radius: DEFAULT_REPULSION_RADIUS,
+        }
+    }
+}
+
+impl Default for DepthOfField {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            aperture: DEFAULT_DOF_APERTURE,
+            focus_distance: DEFAULT_DOF_FOCUS_DISTANCE,
+            track_big_boomer: DEFAULT_DOF_TRACK_BIG_BOOMER,
+        }
+    }
 }
 
-#[derive(Deserialize)]
-struct CustomScheme {
-    pub name: String,
-    pub speed: [CustomSchemeColor; 4],
-    pub index: [CustomSchemeColor; 4],
+impl Default for ParticleTrails {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            length: DEFAULT_TRAIL_LENGTH,
+        }
+    }
 }
 
-#[derive(Deserialize)]
-#[serde(deny_unknown_fields)]
-struct TomlData {
-    pub launch_fullscreen: Option<bool>,
-    pub launch_help_visible: Option<bool>,
-
-    pub max_speed: Option<f32>,
-    pub spring_coefficient: Option<f32>,
-    pub particle_count: Option<NonZeroUsize>,
-    pub point_size: Option<f32>,
-    pub friction_scale: Option<f32>,
-    pub hide_stationary_particles: Option<bool>,
-    pub disable_background: Option<bool>,
+impl Default for Kaleidoscope {
+    fn default() -> Self {
+        Self {
+            segments: DEFAULT_KALEIDOSCOPE_SEGMENTS,
+            spin: 0.,
+            mirror_axis: 0.,
+        }
+    }
+}
 
-    pub audio_scale: Option<f32>,
+impl Default for ParticleBloom {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: DEFAULT_BLOOM_THRESHOLD,
+            intensity: DEFAULT_BLOOM_INTENSITY,
+        }
+    }
+}
 
-    pub vertical_fov: Option<f32>,
+impl Default for ToneMapping {
+    fn default() -> Self {
+        Self {
+            operator: ToneMapOperator::default(),
+            exposure: DEFAULT_EXPOSURE,
+        }
+    }
+}
 
-    #[serde(default)]
-    pub color_schemes: Vec<CustomScheme>,
+impl Default for Responsiveness {
+    fn default() -> Self {
+        Self {
+            volume: DEFAULT_VOLUME_RESPONSE,
+            reactive: DEFAULT_REACTIVE_RESPONSE,
+            smooth: DEFAULT_SMOOTH_RESPONSE,
+        }
+    }
 }
 
-// Hardcoded default values
-const DEFAULT_HELP_VISIBLE: bool = true;
-const DEFAULT_MAX_SPEED: f32 = 7.;
-const DEFAULT_PARTICLE_COUNT: usize = 1_250_000;
-const DEFAULT_SPRING_COEFFICIENT: f32 = 75.;
-const DEFAULT_PARTICLE_POINT_SIZE: f32 = 2.;
-const DEAFULT_FRICTION_SCALE: f32 = 1.;
-const DEFAULT_HIDE_STATIONARY_PARTICLES: bool = false;
-const DEFAULT_AUDIO_SCALE: f32 = -20.;
-const DEFAULT_VERTICAL_FOV: f32 = 72.; // 72 degrees of vertical FOV
+impl Default for AttractorCounts {
+    fn default() -> Self {
+        Self {
+            bass: DEFAULT_BASS_ATTRACTORS,
+            mids: DEFAULT_MIDS_ATTRACTORS,
+            high: DEFAULT_HIGH_ATTRACTORS,
+        }
+    }
+}
 
-#[derive(Clone)]
-pub struct AppConfig {
-    pub launch_fullscreen: bool,
-    pub launch_help_visible: bool,
+impl Default for AttractorStrengths {
+    fn default() -> Self {
+        Self {
+            bass: DEFAULT_ATTRACTOR_STRENGTH,
+            mids: DEFAULT_ATTRACTOR_STRENGTH,
+            high: DEFAULT_ATTRACTOR_STRENGTH,
+        }
+    }
+}
 
-    pub max_speed: f32,
-    pub spring_coefficient: f32,
-    pub particle_count: usize,
-    pub point_size: f32,
-    pub friction_scale: f32,
-    pub hide_stationary_particles: bool,
-    pub disable_background: bool,
+impl Default for OverlayTheme {
+    fn default() -> Self {
+        Self {
+            mode: ThemeMode::default(),
+            accent: None,
+            font_scale: DEFAULT_OVERLAY_FONT_SCALE,
+        }
+    }
+}
+impl Default for AutoBalance {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            quiet_ratio: DEFAULT_BALANCE_QUIET_RATIO,
+            ambient_kick_rate: DEFAULT_BALANCE_AMBIENT_KICK_RATE,
+            percussive_kick_rate: DEFAULT_BALANCE_PERCUSSIVE_KICK_RATE,
+            response: DEFAULT_BALANCE_RESPONSE,
+        }
+    }
+}
+impl Default for AutoCycle {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kicks: DEFAULT_CYCLE_KICKS,
+            energy_ratio: DEFAULT_CYCLE_ENERGY_RATIO,
+            energy_seconds: DEFAULT_CYCLE_ENERGY_SECONDS,
+        }
+    }
+}
+impl Default for SidechainPump {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            brightness: DEFAULT_PUMP_BRIGHTNESS,
+            orbit: DEFAULT_PUMP_ORBIT,
+            attack: DEFAULT_PUMP_ATTACK,
+            release: DEFAULT_PUMP_RELEASE,
+            release_curve: DEFAULT_PUMP_RELEASE_CURVE,
+        }
+    }
+}
+impl Default for SchemeCrossfade {
+    fn default() -> Self {
+        Self {
+            scheme_a: 0,
+            scheme_b: 1,
+            osc_port: None,
+        }
+    }
+}
+impl Default for IntensityMacro {
+    fn default() -> Self {
+        Self {
+            intensity: DEFAULT_INTENSITY,
+            curves: IntensityCurves {
+                audio_scale: DEFAULT_INTENSITY_CURVE,
+                attractor_strength: DEFAULT_INTENSITY_CURVE,
+                kaleidoscope_max: DEFAULT_INTENSITY_CURVE,
+                angular_velocity_max: DEFAULT_INTENSITY_CURVE,
+            },
+            osc_port: None,
+        }
+    }
+}
+impl Default for Snapshots {
+    fn default() -> Self {
+        Self {
+            persist: DEFAULT_PERSIST_SNAPSHOTS,
+            recall_seconds: DEFAULT_SNAPSHOT_RECALL,
+        }
+    }
+}
+impl Default for AttractMode {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_seconds: DEFAULT_ATTRACT_IDLE,
+            scene_seconds: DEFAULT_ATTRACT_SCENE,
+            transition_seconds: DEFAULT_ATTRACT_TRANSITION,
+        }
+    }
+}
+impl Default for LogoIntro {
+    fn default() -> Self {
+        Self {
+            image: None,
+            hold: DEFAULT_INTRO_HOLD,
+            ramp: DEFAULT_INTRO_RAMP,
+        }
+    }
+}
+impl Default for KeyHue {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            response: DEFAULT_KEY_HUE_RESPONSE,
+            hues: DEFAULT_KEY_HUES,
+        }
+    }
+}
+impl Default for EventHooks {
+    fn default() -> Self {
+        Self {
+            webhooks: Vec::new(),
+            osc_targets: Vec::new(),
+            kick_threshold: DEFAULT_KICK_EVENT_THRESHOLD,
+        }
+    }
+}
 
-    pub audio_scale: f32,
+impl Default for KeyframeClock {
+    fn default() -> Self {
+        Self {
+            source: KeyframeClockSource::default(),
+            mpd_address: String::from(DEFAULT_MPD_ADDRESS),
+            mpris_player: None,
+        }
+    }
+}
 
-    pub vertical_fov: f32,
+impl Default for VirtualCamera {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            device: String::from(DEFAULT_VIRTUAL_CAMERA_DEVICE),
+            width: DEFAULT_VIRTUAL_CAMERA_WIDTH,
+            height: DEFAULT_VIRTUAL_CAMERA_HEIGHT,
+            fps: DEFAULT_VIRTUAL_CAMERA_FPS,
+        }
+    }
+}
 
-    pub color_schemes: Vec<Scheme>,
-    pub color_scheme_names: Vec<String>,
+impl Default for Clip {
+    fn default() -> Self {
+        Self {
+            seconds: DEFAULT_CLIP_SECONDS,
+            fps: DEFAULT_CLIP_FPS,
+            width: DEFAULT_CLIP_WIDTH,
+        }
+    }
 }
-impl Default for AppConfig {
+
+impl Default for Timelapse {
     fn default() -> Self {
         Self {
-            launch_fullscreen: bool::default(),
-            launch_help_visible: DEFAULT_HELP_VISIBLE,
+            directory: None,
+            interval: DEFAULT_TIMELAPSE_INTERVAL,
+            beats: None,
+            width: DEFAULT_TIMELAPSE_WIDTH,
+            height: DEFAULT_TIMELAPSE_HEIGHT,
+        }
+    }
+}
 
-            max_speed: DEFAULT_MAX_SPEED,
-            spring_coefficient: DEFAULT_SPRING_COEFFICIENT,
-            particle_count: DEFAULT_PARTICLE_COUNT,
-            point_size: DEFAULT_PARTICLE_POINT_SIZE,
-            friction_scale: DEAFULT_FRICTION_SCALE,
-            hide_stationary_particles: DEFAULT_HIDE_STATIONARY_PARTICLES,
-            disable_background: bool::default(),
+// The analysis presets built into the app, in the order they are listed.
+fn builtin_analysis_presets() -> Vec<AnalysisPreset> {
+    BUILTIN_PRESETS
+        .iter()
+        .map(|&(name, analysis)| AnalysisPreset {
+            name: String::from(name),
+            analysis,
+            builtin: true,
+        })
+        .collect()
+}
 
-            audio_scale: DEFAULT_AUDIO_SCALE,
+// Ensure the parameters of an analysis preset describe sensible bands and kicks.
+fn validate_analysis(name: &str, analysis: &AnalysisConfig) -> anyhow::Result<()> {
+    // Comparisons which also reject NaN.
+    let positive = |x: f32| x > 0.;
+    let non_negative = |x: f32| x >= 0.;
+    for (band_name, band) in [
+        ("bass", &analysis.bass),
+        ("mids", &analysis.mids),
+        ("high", &analysis.high),
+    ] {
+        if !(MIN_BAND_FREQUENCY..=MAX_BAND_FREQUENCY).contains(&band.min_frequency)
+            || !(MIN_BAND_FREQUENCY..=MAX_BAND_FREQUENCY).contains(&band.max_frequency)
+            || band.min_frequency >= band.max_frequency
+        {
+            anyhow::bail!(
+                "The `{band_name}` band of analysis preset `{name}` must lie within [{MIN_BAND_FREQUENCY}, {MAX_BAND_FREQUENCY}] Hz with its minimum frequency below its maximum"
+            );
+        }
+        if !positive(band.delta) || !positive(band.vol_freq_scale) || !non_negative(band.min_volume)
+        {
+            anyhow::bail!(
+                "The `{band_name}` band of analysis preset `{name}` must have a positive `delta` and `vol_freq_scale`, and a non-negative `min_volume`"
+            );
+        }
+    }
+    let kick = &analysis.kick;
+    if !non_negative(kick.min_magnitude)
+        || !non_negative(kick.history_ratio)
+        || !non_negative(kick.min_interval)
+    {
+        anyhow::bail!("The `kick` thresholds of analysis preset `{name}` must be non-negative");
+    }
+    Ok(())
+}
 
-            vertical_fov: DEFAULT_VERTICAL_FOV,
+impl CustomBand {
+    fn apply(&self, base: BandConfig) -> BandConfig {
+        BandConfig {
+            min_frequency: self.min_frequency.unwrap_or(base.min_frequency),
+            max_frequency: self.max_frequency.unwrap_or(base.max_frequency),
+            delta: self.delta.unwrap_or(base.delta),
+            min_volume: self.min_volume.unwrap_or(base.min_volume),
+            vol_freq_scale: self.vol_freq_scale.unwrap_or(base.vol_freq_scale),
+            ..base
+        }
+    }
+}
+impl From<&BandConfig> for CustomBand {
+    fn from(band: &BandConfig) -> Self {
+        Self {
+            min_frequency: Some(band.min_frequency),
+            max_frequency: Some(band.max_frequency),
+            delta: Some(band.delta),
+            min_volume: Some(band.min_volume),
+            vol_freq_scale: Some(band.vol_freq_scale),
+        }
+    }
+}
 
-            color_schemes: COLOR_SCHEMES.to_vec(),
-            color_scheme_names: COLOR_SCHEME_NAMES.into_iter().map(String::from).collect(),
+impl CustomAnalysisPreset {
+    // Override the given analysis with any parameters of this preset.
+    fn apply(&self, base: AnalysisConfig) -> AnalysisConfig {
+        let band = |custom: &Option<CustomBand>, base: BandConfig| {
+            custom.as_ref().map_or(base, |custom| custom.apply(base))
+        };
+        let kick = self.kick.as_ref().map_or(base.kick, |kick| KickConfig {
+            min_magnitude: kick.min_magnitude.unwrap_or(base.kick.min_magnitude),
+            history_ratio: kick.history_ratio.unwrap_or(base.kick.history_ratio),
+            min_interval: kick.min_interval.unwrap_or(base.kick.min_interval),
+        });
+        AnalysisConfig {
+            bass: band(&self.bass, base.bass),
+            mids: band(&self.mids, base.mids),
+            high: band(&self.high, base.high),
+            kick,
+            ..base
+        }
+    }
+}
+// Presets are saved with every parameter, so they no longer depend on any base preset.
+impl From<&AnalysisPreset> for CustomAnalysisPreset {
+    fn from(preset: &AnalysisPreset) -> Self {
+        let analysis = &preset.analysis;
+        Self {
+            name: preset.name.clone(),
+            base: None,
+            bass: Some((&analysis.bass).into()),
+            mids: Some((&analysis.mids).into()),
+            high: Some((&analysis.high).into()),
+            kick: Some(CustomKick {
+                min_magnitude: Some(analysis.kick.min_magnitude),
+                history_ratio: Some(analysis.kick.history_ratio),
+                min_interval: Some(analysis.kick.min_interval),
+            }),
         }
     }
 }
@@ -172,8 +1870,458 @@ impl std::convert::From<&CustomScheme> for Scheme {
     }
 }
 
+// Write the given configuration to a TOML file that `parse_file` reads back to the same values.
+// The values of an existing file are replaced in place, so that its comments and layout are preserved.
+#[allow(clippy::cast_possible_truncation)]
+pub fn save_file(filepath: &str, config: &AppConfig) -> anyhow::Result<()> {
+    // Helper to write floats with the shortest decimal that round-trips through `f32`.
+    fn round_floats(value: &mut toml::Value) {
+        match value {
+            toml::Value::Float(f) => *f = (*f as f32).to_string().parse().unwrap_or(*f),
+            toml::Value::Array(array) => array.iter_mut().for_each(round_floats),
+            toml::Value::Table(table) => table.values_mut().for_each(round_floats),
+            _ => {}
+        }
+    }
+    fn vec4(color: [f32; 4]) -> CustomSchemeColor {
+        CustomSchemeColor::Vec4(color.to_vec())
+    }
+
+    let data = TomlData {
+        launch_fullscreen: Some(config.launch_fullscreen),
+        launch_help_visible: Some(config.launch_help_visible),
+        aspect_fit: Some(config.aspect_fit),
+        display_layout: Some(config.display_layout),
+        coloring_mode: Some(config.coloring_mode),
+        physics_mode: Some(config.physics_mode),
+        particle_containment: Some(config.particle_containment),
+        color_management: Some(config.color_management),
+        present_mode: Some(config.present_mode),
+        direct_display: Some(config.direct_display.enabled),
+        direct_display_index: Some(config.direct_display.display),
+        direct_display_mode: Some(config.direct_display.mode),
+        overlay_theme: Some(config.overlay_theme.mode),
+        overlay_accent: config
+            .overlay_theme
+            .accent
+            .map(|[r, g, b]| format!("#{r:02x}{g:02x}{b:02x}")),
+        overlay_font_scale: Some(config.overlay_theme.font_scale),
+        point_size_scale: config.display_scale.point_size,
+        overlay_pixels_per_point: config.display_scale.overlay,
+
+        particle_count: NonZeroUsize::new(config.particle_count),
+        msaa_samples: Some(config.msaa_samples),
+        hide_stationary_particles: Some(config.hide_stationary_particles),
+        disable_background: Some(config.disable_background),
+        kick_echoes: Some(config.kick_echoes),
+        stereo_placement: Some(config.stereo_placement),
+        stereo_attractors: Some(config.stereo_attractors),
+        capture_mode: Some(config.capture_mode),
+        particle_sprites: Some(config.particle_sprites),
+        sprite_shape: Some(config.sprite_shape),
+        sprite_texture: config.sprite_texture.clone(),
+        particle_blending: Some(config.particle_blending),
+        particle_alpha: Some(config.particle_alpha),
+        surface_emission: Some(config.surface_emission),
+        surface_emission_rate: Some(config.surface_emission_rate),
+
+        particle_repulsion: Some(config.particle_repulsion.enabled),
+        repulsion_strength: Some(config.particle_repulsion.strength),
+        repulsion_radius: Some(config.particle_repulsion.radius),
+
+        depth_of_field: Some(config.depth_of_field.enabled),
+        dof_aperture: Some(config.depth_of_field.aperture),
+        dof_focus_distance: Some(config.depth_of_field.focus_distance),
+        dof_track_big_boomer: Some(config.depth_of_field.track_big_boomer),
+
+        particle_trails: Some(config.particle_trails.enabled),
+        trail_length: Some(config.particle_trails.length),
+
+        kaleidoscope_segments: Some(config.kaleidoscope.segments),
+        kaleidoscope_spin: Some(config.kaleidoscope.spin),
+        kaleidoscope_axis: Some(config.kaleidoscope.mirror_axis),
+
+        bloom: Some(config.bloom.enabled),
+        bloom_threshold: Some(config.bloom.threshold),
+        bloom_intensity: Some(config.bloom.intensity),
+
+        tone_mapping: Some(config.tone_mapping.operator),
+        exposure: Some(config.tone_mapping.exposure),
+
+        temporal_antialiasing: Some(config.temporal_antialiasing),
+        progressive_refinement: Some(config.progressive_refinement),
+
+        volume_response: Some(config.responsiveness.volume),
+        reactive_response: Some(config.responsiveness.reactive),
+        smooth_response: Some(config.responsiveness.smooth),
+
+        bass_attractors: Some(config.attractor_counts.bass),
+        mids_attractors: Some(config.attractor_counts.mids),
+        high_attractors: Some(config.attractor_counts.high),
+        bass_attractor_strength: Some(config.attractor_strengths.bass),
+        mids_attractor_strength: Some(config.attractor_strengths.mids),
+        high_attractor_strength: Some(config.attractor_strengths.high),
+
+        analysis_preset: Some(config.analysis_presets[config.analysis_preset].name.clone()),
+        gain_control: Some(config.gain_control.enabled),
+        gain_target: Some(config.gain_control.target),
+        gain_attack: Some(config.gain_control.attack),
+        gain_release: Some(config.gain_control.release),
+        audio: config.bands.map(|bands| CustomAudio {
+            bands: Some(CustomBands {
+                bass: Some((&bands.bass).into()),
+                mids: Some((&bands.mids).into()),
+                high: Some((&bands.high).into()),
+            }),
+        }),
+
+        key_hue: Some(config.key_hue.enabled),
+        key_hue_response: Some(config.key_hue.response),
+        key_hues: Some(config.key_hue.hues),
+
+        auto_balance: Some(config.auto_balance.enabled),
+        balance_quiet_ratio: Some(config.auto_balance.quiet_ratio),
+        balance_ambient_kick_rate: Some(config.auto_balance.ambient_kick_rate),
+        balance_percussive_kick_rate: Some(config.auto_balance.percussive_kick_rate),
+        balance_response: Some(config.auto_balance.response),
+
+        sidechain_pump: Some(config.sidechain_pump.enabled),
+        pump_brightness: Some(config.sidechain_pump.brightness),
+        pump_orbit: Some(config.sidechain_pump.orbit),
+        pump_attack: Some(config.sidechain_pump.attack),
+        pump_release: Some(config.sidechain_pump.release),
+        pump_release_curve: Some(config.sidechain_pump.release_curve),
+
+        scheme_transition: Some(config.scheme_transition),
+
+        auto_cycle: Some(config.auto_cycle.enabled),
+        cycle_kicks: Some(config.auto_cycle.kicks),
+        cycle_energy_ratio: Some(config.auto_cycle.energy_ratio),
+        cycle_energy_seconds: Some(config.auto_cycle.energy_seconds),
+
+        crossfade_scheme_a: Some(
+            config.color_scheme_names[config.scheme_crossfade.scheme_a].clone(),
+        ),
+        crossfade_scheme_b: Some(
+            config.color_scheme_names[config.scheme_crossfade.scheme_b].clone(),
+        ),
+        crossfade_osc_port: config.scheme_crossfade.osc_port,
+
+        intensity: Some(config.intensity_macro.intensity),
+        intensity_osc_port: config.intensity_macro.osc_port,
+        intensity_curves: Some(CustomIntensityCurves {
+            audio_scale: Some(config.intensity_macro.curves.audio_scale),
+            attractor_strength: Some(config.intensity_macro.curves.attractor_strength),
+            kaleidoscope_max: Some(config.intensity_macro.curves.kaleidoscope_max),
+            angular_velocity_max: Some(config.intensity_macro.curves.angular_velocity_max),
+        }),
+
+        performer_osc_port: config.performer_osc_port,
+
+        midi_device: config.midi.device.clone(),
+        midi_mappings: config
+            .midi
+            .mappings
+            .iter()
+            .map(|mapping| {
+                let (control, note) = match mapping.input {
+                    MidiInput::Control(control) => (Some(control), None),
+                    MidiInput::Note(note) => (None, Some(note)),
+                };
+                let (variable, range, action) = match &mapping.target {
+                    MidiTarget::Variable { name, range } => (Some(name.clone()), *range, None),
+                    MidiTarget::Action(action) => (None, None, Some(action.config_name())),
+                };
+                CustomMidiMapping {
+                    channel: mapping.channel,
+                    control,
+                    note,
+                    variable,
+                    min: range.map(|(min, _)| min),
+                    max: range.map(|(_, max)| max),
+                    action,
+                }
+            })
+            .collect(),
+
+        persist_snapshots: Some(config.snapshots.persist),
+        snapshot_recall: Some(config.snapshots.recall_seconds),
+
+        attract_mode: Some(config.attract_mode.enabled),
+        attract_idle: Some(config.attract_mode.idle_seconds),
+        attract_scene: Some(config.attract_mode.scene_seconds),
+        attract_transition: Some(config.attract_mode.transition_seconds),
+
+        intro_logo: config.logo_intro.image.clone(),
+        intro_hold: Some(config.logo_intro.hold),
+        intro_ramp: Some(config.logo_intro.ramp),
+
+        virtual_camera: Some(config.virtual_camera.enabled),
+        virtual_camera_device: Some(config.virtual_camera.device.clone()),
+        virtual_camera_width: Some(config.virtual_camera.width),
+        virtual_camera_height: Some(config.virtual_camera.height),
+        virtual_camera_fps: Some(config.virtual_camera.fps),
+
+        timelapse_directory: config.timelapse.directory.clone(),
+        timelapse_interval: Some(config.timelapse.interval),
+        timelapse_beats: config.timelapse.beats,
+        timelapse_width: Some(config.timelapse.width),
+        timelapse_height: Some(config.timelapse.height),
+
+        clip_seconds: Some(config.clip.seconds),
+        clip_fps: Some(config.clip.fps),
+        clip_width: Some(config.clip.width),
+
+        event_webhooks: config.event_hooks.webhooks.clone(),
+        event_osc_targets: config.event_hooks.osc_targets.clone(),
+        kick_event_threshold: Some(config.event_hooks.kick_threshold),
+
+        keyframe_clock: Some(config.keyframe_clock.source),
+        keyframe_mpd_address: Some(config.keyframe_clock.mpd_address.clone()),
+        keyframe_mpris_player: config.keyframe_clock.mpris_player.clone(),
+
+        analysis_presets: config
+            .analysis_presets
+            .iter()
+            .filter(|preset| !preset.builtin)
+            .map(CustomAnalysisPreset::from)
+            .collect(),
+
+        stems: config
+            .stems
+            .iter()
+            .map(|stem| CustomStem {
+                name: stem.name.clone(),
+                source: stem.source.to_string(),
+                channels: stem.channels.clone(),
+            })
+            .collect(),
+
+        color_schemes: config
+            .color_schemes
+            .iter()
+            .zip(&config.color_scheme_names)
+            .map(|(scheme, name)| CustomScheme {
+                name: name.clone(),
+                speed: scheme.speed.map(vec4),
+                index: scheme.index.map(vec4),
+            })
+            .collect(),
+
+        color_keyframes: config
+            .color_keyframes
+            .iter()
+            .map(|keyframe| CustomKeyframe {
+                time: keyframe.time,
+                scheme: config.color_scheme_names[keyframe.scheme_index].clone(),
+            })
+            .collect(),
+
+        camera_paths: config.camera_paths.clone(),
+        particle_systems: config.particle_systems.clone(),
+        scenes: config.scenes.clone(),
+
+        live_uniforms: config.live_uniforms.clone(),
+        user_fractal: config.user_fractal.clone(),
+
+        // Only the fractals tuned away from their built-in values are written.
+        fractal_tunables: config
+            .fractal_tunables
+            .iter()
+            .zip(fractals::FRACTALS)
+            .enumerate()
+            .filter(|(_, (tunables, fractal))| **tunables != fractal.defaults)
+            .map(|(id, (tunables, _))| {
+                (
+                    id.to_string(),
+                    CustomTunables {
+                        iterations: Some(tunables.iterations),
+                        bailout: Some(tunables.bailout),
+                        fold_scale: Some(tunables.fold_scale),
+                        color_exponent: Some(tunables.color_exponent),
+                    },
+                )
+            })
+            .collect(),
+
+        keybindings: config
+            .keybindings
+            .iter()
+            .map(|(action, chords)| {
+                (
+                    action.config_name(),
+                    CustomKeybinding::Chords(chords.iter().map(ToString::to_string).collect()),
+                )
+            })
+            .collect(),
+    };
+
+    let mut value = toml::Value::try_from(data)?;
+    if let toml::Value::Table(table) = &mut value {
+        for (parameter, value) in PARAMETERS.iter().zip(config.parameters) {
+            table.insert(
+                String::from(parameter.key),
+                toml::Value::Float(f64::from(value)),
+            );
+        }
+    }
+    round_floats(&mut value);
+    let updated: toml_edit::Document = toml::to_string(&value)?.parse()?;
+
+    // An existing file which cannot be parsed is replaced entirely.
+    let mut document = std::fs::read_to_string(filepath)
+        .ok()
+        .and_then(|contents| contents.parse::<toml_edit::Document>().ok())
+        .unwrap_or_default();
+    merge_toml_tables(document.as_table_mut(), updated.as_table().clone());
+    std::fs::write(filepath, document.to_string())?;
+    Ok(())
+}
+
+// Replace the entries of an existing TOML table with those of the updated one, keeping the comments of every entry which remains.
+// Entries absent from the updated table are removed, and new entries are appended
+fn merge_toml_tables(existing: &mut toml_edit::Table, updated: toml_edit::Table) {
+    existing.retain(|key, _| updated.contains_key(key));
+    for (key, item) in updated {
+        match existing.get_mut(&key) {
+            Some(existing_item) => merge_toml_items(existing_item, item),
+            None => {
+                existing.insert(&key, item);
+            }
+        }
+    }
+}
+fn merge_toml_items(existing: &mut toml_edit::Item, updated: toml_edit::Item) {
+    match (existing, updated) {
+        (toml_edit::Item::Table(existing), toml_edit::Item::Table(updated)) => {
+            merge_toml_tables(existing, updated);
+        }
+        (toml_edit::Item::ArrayOfTables(existing), toml_edit::Item::ArrayOfTables(updated)) => {
+            // Tables are matched by their order, as when the color schemes are listed
+            let updated_count = updated.len();
+            let mut updated = updated.into_iter();
+            for (existing_table, updated_table) in existing.iter_mut().zip(updated.by_ref()) {
+                merge_toml_tables(existing_table, updated_table);
+            }
+            while existing.len() > updated_count {
+                existing.remove(existing.len() - 1);
+            }
+            for table in updated {
+                existing.push(table);
+            }
+        }
+        (toml_edit::Item::Value(existing), toml_edit::Item::Value(updated)) => {
+            merge_toml_values(existing, updated);
+        }
+        (existing, updated) => *existing = updated,
+    }
+}
+fn merge_toml_values(existing: &mut toml_edit::Value, updated: toml_edit::Value) {
+    // A color written as a CSS string is kept while it is still the color being saved
+    if let (Some(color), Some(updated_color)) = (toml_css_color(existing), toml_vec4(&updated)) {
+        if color == updated_color {
+            return;
+        }
+    }
+
+    match (existing, updated) {
+        (toml_edit::Value::Array(existing), toml_edit::Value::Array(updated)) => {
+            // Elements are matched by their order, keeping the comments and layout of each which remains
+            let updated_count = updated.len();
+            let mut updated = updated.into_iter();
+            for (existing_value, updated_value) in existing.iter_mut().zip(updated.by_ref()) {
+                merge_toml_values(existing_value, updated_value);
+            }
+            while existing.len() > updated_count {
+                existing.remove(existing.len() - 1);
+            }
+            for value in updated {
+                existing.push_formatted(value);
+            }
+        }
+        (toml_edit::Value::InlineTable(existing), toml_edit::Value::InlineTable(updated)) => {
+            existing.retain(|key, _| updated.contains_key(key));
+            for (key, value) in updated {
+                match existing.get_mut(&key) {
+                    Some(existing_value) => merge_toml_values(existing_value, value),
+                    None => {
+                        existing.insert(&key, value);
+                    }
+                }
+            }
+        }
+        (existing, updated) => {
+            // Any trailing comment belongs to the value's decor
+            let decor = existing.decor().clone();
+            *existing = updated;
+            *existing.decor_mut() = decor;
+        }
+    }
+}
+
+// The color of a CSS color string, or of a pair of one and an alpha, as a color scheme's colors may be written.
+#[allow(clippy::cast_possible_truncation)]
+fn toml_css_color(value: &toml_edit::Value) -> Option<[f32; 4]> {
+    let (css_color, alpha) = match value {
+        toml_edit::Value::String(css_color) => (css_color.value().as_str(), 1.),
+        toml_edit::Value::Array(pair) if pair.len() == 2 => {
+            (pair.get(0)?.as_str()?, toml_number(pair.get(1)?)? as f32)
+        }
+        _ => return None,
+    };
+    let c = css_color.parse::<CssColor>().ok()?;
+    Some([
+        f32::from(c.r) / 255.,
+        f32::from(c.g) / 255.,
+        f32::from(c.b) / 255.,
+        alpha,
+    ])
+}
+#[allow(clippy::cast_possible_truncation)]
+fn toml_vec4(value: &toml_edit::Value) -> Option<[f32; 4]> {
+    let array = value.as_array().filter(|array| array.len() == 4)?;
+    let mut vec4 = [0.; 4];
+    for (component, value) in vec4.iter_mut().zip(array.iter()) {
+        *component = toml_number(value)? as f32;
+    }
+    Some(vec4)
+}
+#[allow(clippy::cast_precision_loss)]
+fn toml_number(value: &toml_edit::Value) -> Option<f64> {
+    value
+        .as_float()
+        .or_else(|| value.as_integer().map(|i| i as f64))
+}
+
+// Remove the shader parameters from the parsed TOML table, using the defaults of any which are absent.
+fn take_parameters(table: &mut toml::Table) -> anyhow::Result<ParameterValues> {
+    let mut values = parameters::default_values();
+    for (parameter, value) in PARAMETERS.iter().zip(&mut values) {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+        let given = match table.remove(parameter.key) {
+            Some(toml::Value::Float(f)) => f as f32,
+            Some(toml::Value::Integer(i)) => i as f32,
+            Some(other) => {
+                anyhow::bail!("`{}` must be a number, was given: {other}", parameter.key)
+            }
+            None => continue,
+        };
+        if !parameter.limits.contains(given) {
+            anyhow::bail!(
+                "`{}` must be {}, was given: {given}",
+                parameter.key,
+                parameter.limits.describe()
+            );
+        }
+        *value = given;
+    }
+    Ok(values)
+}
+
 pub fn parse_file(filepath: &str) -> anyhow::Result<AppConfig> {
-    let config: TomlData = toml::from_str(&std::fs::read_to_string(filepath)?)?;
+    let mut table: toml::Table = toml::from_str(&std::fs::read_to_string(filepath)?)?;
+    let parameters = take_parameters(&mut table)?;
+    let config: TomlData = toml::Value::Table(table).try_into()?;
 
     let mut schemes: Vec<Scheme> = vec![];
     let mut scheme_names: Vec<String> = vec![];
@@ -197,68 +2345,1150 @@ pub fn parse_file(filepath: &str) -> anyhow::Result<AppConfig> {
         (schemes, scheme_names)
     };
 
-    let max_speed = match config.max_speed {
-        Some(max_speed) => {
-            if max_speed > 0. {
-                max_speed
-            } else {
+    // Resolve each keyframe to the index of its named scheme.
+    let mut color_keyframes = config
+        .color_keyframes
+        .iter()
+        .map(|keyframe| {
+            if keyframe.time.is_nan() || keyframe.time < 0. {
                 anyhow::bail!(
-                    "`max_speed` must be a positive number, was given: {}",
-                    max_speed
+                    "Color keyframe times must be non-negative, was given: {}",
+                    keyframe.time
                 );
             }
+            let Some(scheme_index) = color_scheme_names
+                .iter()
+                .position(|name| *name == keyframe.scheme)
+            else {
+                anyhow::bail!(
+                    "Color keyframe refers to unknown color scheme `{}`",
+                    keyframe.scheme
+                );
+            };
+            Ok(ColorKeyframe {
+                time: keyframe.time,
+                scheme_index,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    color_keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    let scheme_transition = config
+        .scheme_transition
+        .unwrap_or(DEFAULT_SCHEME_TRANSITION);
+    if !(0. ..=MAX_SCHEME_TRANSITION).contains(&scheme_transition) {
+        anyhow::bail!(
+            "`scheme_transition` must be in [0, {MAX_SCHEME_TRANSITION}], was given: {scheme_transition}"
+        );
+    }
+
+    let auto_cycle = {
+        let kicks = config.cycle_kicks.unwrap_or(DEFAULT_CYCLE_KICKS);
+        if kicks > MAX_CYCLE_KICKS {
+            anyhow::bail!("`cycle_kicks` must be in [0, {MAX_CYCLE_KICKS}], was given: {kicks}");
+        }
+        let energy_ratio = config
+            .cycle_energy_ratio
+            .unwrap_or(DEFAULT_CYCLE_ENERGY_RATIO);
+        if !(MIN_CYCLE_ENERGY_RATIO..=MAX_CYCLE_ENERGY_RATIO).contains(&energy_ratio) {
+            anyhow::bail!(
+                "`cycle_energy_ratio` must be in [{MIN_CYCLE_ENERGY_RATIO}, {MAX_CYCLE_ENERGY_RATIO}], was given: {energy_ratio}"
+            );
+        }
+        let energy_seconds = config
+            .cycle_energy_seconds
+            .unwrap_or(DEFAULT_CYCLE_ENERGY_SECONDS);
+        if !(0. ..=MAX_CYCLE_ENERGY_SECONDS).contains(&energy_seconds) {
+            anyhow::bail!(
+                "`cycle_energy_seconds` must be in [0, {MAX_CYCLE_ENERGY_SECONDS}], was given: {energy_seconds}"
+            );
+        }
+        AutoCycle {
+            enabled: config.auto_cycle.unwrap_or_default(),
+            kicks,
+            energy_ratio,
+            energy_seconds,
         }
-        None => DEFAULT_MAX_SPEED,
     };
 
-    let spring_coefficient = config
-        .spring_coefficient
-        .unwrap_or(DEFAULT_SPRING_COEFFICIENT);
+    // Resolve the crossfaded schemes, defaulting to the first two.
+    let crossfade_scheme = |key: &str, name: &Option<String>, default: usize| match name {
+        None => Ok(default.min(color_scheme_names.len() - 1)),
+        Some(name) => match color_scheme_names.iter().position(|n| n == name) {
+            Some(index) => Ok(index),
+            None => Err(anyhow::anyhow!(
+                "`{key}` refers to unknown color scheme `{name}`"
+            )),
+        },
+    };
+    let scheme_crossfade = SchemeCrossfade {
+        scheme_a: crossfade_scheme("crossfade_scheme_a", &config.crossfade_scheme_a, 0)?,
+        scheme_b: crossfade_scheme("crossfade_scheme_b", &config.crossfade_scheme_b, 1)?,
+        osc_port: config.crossfade_osc_port,
+    };
 
     let particle_count = config
         .particle_count
         .unwrap_or(unsafe { NonZeroUsize::new_unchecked(DEFAULT_PARTICLE_COUNT) })
         .get();
 
-    let point_size = config
-        .point_size
-        .unwrap_or(DEFAULT_PARTICLE_POINT_SIZE)
-        .clamp(0., 16.);
+    let msaa_samples = config.msaa_samples.unwrap_or(DEFAULT_MSAA_SAMPLES);
+    if !MSAA_SAMPLE_CHOICES.contains(&msaa_samples) {
+        anyhow::bail!(
+            "`msaa_samples` must be one of {MSAA_SAMPLE_CHOICES:?}, was given: {msaa_samples}"
+        );
+    }
+
+    let sprite_shape = config.sprite_shape.unwrap_or_default();
+    if sprite_shape == SpriteShape::Texture && config.sprite_texture.is_none() {
+        anyhow::bail!("`sprite_shape` of \"texture\" requires a `sprite_texture` image");
+    }
+
+    let particle_alpha = config.particle_alpha.unwrap_or(DEFAULT_PARTICLE_ALPHA);
+    if !(particle_alpha > 0. && particle_alpha <= 1.) {
+        anyhow::bail!("`particle_alpha` must be in (0, 1], was given: {particle_alpha}");
+    }
+
+    let surface_emission_rate = config
+        .surface_emission_rate
+        .unwrap_or(DEFAULT_SURFACE_EMISSION_RATE);
+    if !(surface_emission_rate > 0. && surface_emission_rate <= MAX_SURFACE_EMISSION_RATE) {
+        anyhow::bail!(
+            "`surface_emission_rate` must be in (0, {MAX_SURFACE_EMISSION_RATE}], was given: {surface_emission_rate}"
+        );
+    }
+
+    let particle_repulsion = {
+        let strength = config
+            .repulsion_strength
+            .unwrap_or(DEFAULT_REPULSION_STRENGTH);
+        if strength < 0. {
+            anyhow::bail!(
+                "`repulsion_strength` must be a non-negative number, was given: {}",
+                strength
+            );
+        }
+        let radius = match config.repulsion_radius {
+            Some(radius) => {
+                if radius > 0. {
+                    radius.min(MAX_REPULSION_RADIUS)
+                } else {
+                    anyhow::bail!(
+                        "`repulsion_radius` must be a positive number, was given: {}",
+                        radius
+                    );
+                }
+            }
+            None => DEFAULT_REPULSION_RADIUS,
+        };
+        ParticleRepulsion {
+            enabled: config.particle_repulsion.unwrap_or_default(),
+            strength,
+            radius,
+        }
+    };
+
+    let depth_of_field = {
+        let aperture = config.dof_aperture.unwrap_or(DEFAULT_DOF_APERTURE);
+        if aperture < 0. {
+            anyhow::bail!(
+                "`dof_aperture` must be a non-negative number, was given: {}",
+                aperture
+            );
+        }
+        DepthOfField {
+            enabled: config.depth_of_field.unwrap_or_default(),
+            aperture: aperture.min(MAX_DOF_APERTURE),
+            focus_distance: config
+                .dof_focus_distance
+                .unwrap_or(DEFAULT_DOF_FOCUS_DISTANCE)
+                .clamp(MIN_DOF_FOCUS_DISTANCE, MAX_DOF_FOCUS_DISTANCE),
+            track_big_boomer: config
+                .dof_track_big_boomer
+                .unwrap_or(DEFAULT_DOF_TRACK_BIG_BOOMER),
+        }
+    };
+
+    let particle_trails = {
+        let length = config.trail_length.unwrap_or(DEFAULT_TRAIL_LENGTH);
+        if length <= 0. {
+            anyhow::bail!(
+                "`trail_length` must be a positive number, was given: {}",
+                length
+            );
+        }
+        ParticleTrails {
+            enabled: config.particle_trails.unwrap_or_default(),
+            length: length.clamp(MIN_TRAIL_LENGTH, MAX_TRAIL_LENGTH),
+        }
+    };
+
+    let kaleidoscope = {
+        let segments = config
+            .kaleidoscope_segments
+            .unwrap_or(DEFAULT_KALEIDOSCOPE_SEGMENTS);
+        if !(MIN_KALEIDOSCOPE_SEGMENTS..=MAX_KALEIDOSCOPE_SEGMENTS).contains(&segments) {
+            anyhow::bail!(
+                "`kaleidoscope_segments` must be in [{MIN_KALEIDOSCOPE_SEGMENTS}, {MAX_KALEIDOSCOPE_SEGMENTS}], was given: {segments}"
+            );
+        }
+        let spin = config.kaleidoscope_spin.unwrap_or_default();
+        if spin.abs() > MAX_KALEIDOSCOPE_SPIN {
+            anyhow::bail!(
+                "`kaleidoscope_spin` must be in [-{MAX_KALEIDOSCOPE_SPIN}, {MAX_KALEIDOSCOPE_SPIN}], was given: {spin}"
+            );
+        }
+        Kaleidoscope {
+            segments,
+            spin,
+            mirror_axis: config
+                .kaleidoscope_axis
+                .unwrap_or_default()
+                .rem_euclid(360.),
+        }
+    };
+
+    let bloom = {
+        let threshold = config.bloom_threshold.unwrap_or(DEFAULT_BLOOM_THRESHOLD);
+        if !(0. ..=1.).contains(&threshold) {
+            anyhow::bail!("`bloom_threshold` must be in [0, 1], was given: {threshold}");
+        }
+        let intensity = config.bloom_intensity.unwrap_or(DEFAULT_BLOOM_INTENSITY);
+        if intensity < 0. {
+            anyhow::bail!(
+                "`bloom_intensity` must be a non-negative number, was given: {intensity}"
+            );
+        }
+        ParticleBloom {
+            enabled: config.bloom.unwrap_or_default(),
+            threshold,
+            intensity: intensity.min(MAX_BLOOM_INTENSITY),
+        }
+    };
+
+    let tone_mapping = {
+        let exposure = config.exposure.unwrap_or(DEFAULT_EXPOSURE);
+        if exposure <= 0. {
+            anyhow::bail!("`exposure` must be a positive number, was given: {exposure}");
+        }
+        ToneMapping {
+            operator: config.tone_mapping.unwrap_or_default(),
+            exposure: exposure.clamp(MIN_EXPOSURE, MAX_EXPOSURE),
+        }
+    };
+
+    let responsiveness = {
+        let response = |value: Option<f32>, name: &str, default: f32, max: f32| {
+            let value = value.unwrap_or(default);
+            if value <= 0. {
+                anyhow::bail!("`{name}` must be a positive number, was given: {value}");
+            }
+            Ok(value.clamp(MIN_RESPONSE, max))
+        };
+        Responsiveness {
+            volume: response(
+                config.volume_response,
+                "volume_response",
+                DEFAULT_VOLUME_RESPONSE,
+                MAX_VOLUME_RESPONSE,
+            )?,
+            reactive: response(
+                config.reactive_response,
+                "reactive_response",
+                DEFAULT_REACTIVE_RESPONSE,
+                MAX_REACTIVE_RESPONSE,
+            )?,
+            smooth: response(
+                config.smooth_response,
+                "smooth_response",
+                DEFAULT_SMOOTH_RESPONSE,
+                MAX_SMOOTH_RESPONSE,
+            )?,
+        }
+    };
+
+    let attractor_counts = {
+        let count = |value: Option<usize>, name: &str, default: usize, max: usize| {
+            let value = value.unwrap_or(default);
+            if value > max {
+                anyhow::bail!("`{name}` must be at most {max}, was given: {value}");
+            }
+            Ok(value)
+        };
+        AttractorCounts {
+            bass: count(
+                config.bass_attractors,
+                "bass_attractors",
+                DEFAULT_BASS_ATTRACTORS,
+                MAX_BASS_ATTRACTORS,
+            )?,
+            mids: count(
+                config.mids_attractors,
+                "mids_attractors",
+                DEFAULT_MIDS_ATTRACTORS,
+                MAX_MIDS_ATTRACTORS,
+            )?,
+            high: count(
+                config.high_attractors,
+                "high_attractors",
+                DEFAULT_HIGH_ATTRACTORS,
+                MAX_HIGH_ATTRACTORS,
+            )?,
+        }
+    };
+
+    let attractor_strengths = {
+        let strength = |value: Option<f32>, name: &str| {
+            let value = value.unwrap_or(DEFAULT_ATTRACTOR_STRENGTH);
+            if !(0. ..=MAX_ATTRACTOR_STRENGTH).contains(&value) {
+                anyhow::bail!(
+                    "`{name}` must be in [0, {MAX_ATTRACTOR_STRENGTH}], was given: {value}"
+                );
+            }
+            Ok(value)
+        };
+        AttractorStrengths {
+            bass: strength(config.bass_attractor_strength, "bass_attractor_strength")?,
+            mids: strength(config.mids_attractor_strength, "mids_attractor_strength")?,
+            high: strength(config.high_attractor_strength, "high_attractor_strength")?,
+        }
+    };
+
+    // Each custom preset overrides one defined before it, so presets may build upon each other.
+    let mut analysis_presets = builtin_analysis_presets();
+    for custom in config.analysis_presets {
+        if analysis_presets
+            .iter()
+            .any(|preset| preset.name == custom.name)
+        {
+            anyhow::bail!(
+                "Analysis preset `{}` is defined more than once",
+                custom.name
+            );
+        }
+        let base = match &custom.base {
+            Some(base) => {
+                let Some(preset) = analysis_presets.iter().find(|preset| &preset.name == base)
+                else {
+                    anyhow::bail!(
+                        "Analysis preset `{}` overrides the unknown preset `{base}`",
+                        custom.name
+                    );
+                };
+                preset.analysis
+            }
+            None => AnalysisConfig::default(),
+        };
+        let analysis = custom.apply(base);
+        validate_analysis(&custom.name, &analysis)?;
+        analysis_presets.push(AnalysisPreset {
+            name: custom.name,
+            analysis,
+            builtin: false,
+        });
+    }
+    let analysis_preset = match &config.analysis_preset {
+        Some(name) => analysis_presets
+            .iter()
+            .position(|preset| &preset.name == name)
+            .ok_or_else(|| {
+                anyhow::anyhow!("`analysis_preset` names the unknown preset `{name}`")
+            })?,
+        None => 0,
+    };
+    let gain_control = {
+        let target = config.gain_target.unwrap_or(DEFAULT_GAIN_CONTROL.target);
+        if !(MIN_GAIN_TARGET..=MAX_GAIN_TARGET).contains(&target) {
+            anyhow::bail!(
+                "`gain_target` must be in [{MIN_GAIN_TARGET}, {MAX_GAIN_TARGET}] dBFS, was given: {target}"
+            );
+        }
+        let attack = config.gain_attack.unwrap_or(DEFAULT_GAIN_CONTROL.attack);
+        let release = config.gain_release.unwrap_or(DEFAULT_GAIN_CONTROL.release);
+        for (name, seconds) in [("gain_attack", attack), ("gain_release", release)] {
+            if seconds <= 0. || seconds > MAX_GAIN_SECONDS {
+                anyhow::bail!(
+                    "`{name}` must be in (0, {MAX_GAIN_SECONDS}] seconds, was given: {seconds}"
+                );
+            }
+        }
+        GainControl {
+            enabled: config.gain_control.unwrap_or(DEFAULT_GAIN_CONTROL.enabled),
+            target,
+            attack,
+            release,
+        }
+    };
+    let bands = match config.audio.and_then(|audio| audio.bands) {
+        Some(custom) => {
+            let preset = &analysis_presets[analysis_preset];
+            let analysis = AnalysisConfig {
+                bass: custom.bass.as_ref().map_or(preset.analysis.bass, |band| {
+                    band.apply(preset.analysis.bass)
+                }),
+                mids: custom.mids.as_ref().map_or(preset.analysis.mids, |band| {
+                    band.apply(preset.analysis.mids)
+                }),
+                high: custom.high.as_ref().map_or(preset.analysis.high, |band| {
+                    band.apply(preset.analysis.high)
+                }),
+                ..preset.analysis
+            };
+            validate_analysis(&format!("{} with `[audio.bands]`", preset.name), &analysis)?;
+            Some(BandOverrides::of(&analysis))
+        }
+        None => None,
+    };
+
+    // Each channel of the visuals is driven by at most one stem.
+    let mut stems: Vec<Stem> = Vec::with_capacity(config.stems.len());
+    for custom in config.stems {
+        if stems.iter().any(|stem| stem.name == custom.name) {
+            anyhow::bail!("Stem `{}` is defined more than once", custom.name);
+        }
+        let source = custom
+            .source
+            .parse::<SourceKind>()
+            .map_err(|e| anyhow::anyhow!("Stem `{}` has an invalid source: {e}", custom.name))?;
+        for channel in &custom.channels {
+            if let Some(other) = stems.iter().find(|stem| stem.channels.contains(channel)) {
+                anyhow::bail!(
+                    "Stems `{}` and `{}` both drive the `{channel:?}` channel",
+                    other.name,
+                    custom.name
+                );
+            }
+        }
+        stems.push(Stem {
+            name: custom.name,
+            source,
+            channels: custom.channels,
+        });
+    }
+
+    let overlay_theme = {
+        let accent = match config.overlay_accent {
+            Some(accent) => {
+                let c = accent.parse::<CssColor>().map_err(|e| {
+                    anyhow::anyhow!("Invalid `overlay_accent` color `{accent}`: {e:?}")
+                })?;
+                Some([c.r, c.g, c.b])
+            }
+            None => None,
+        };
+        let font_scale = config
+            .overlay_font_scale
+            .unwrap_or(DEFAULT_OVERLAY_FONT_SCALE);
+        if !(MIN_OVERLAY_FONT_SCALE..=MAX_OVERLAY_FONT_SCALE).contains(&font_scale) {
+            anyhow::bail!(
+                "`overlay_font_scale` must be in [{MIN_OVERLAY_FONT_SCALE}, {MAX_OVERLAY_FONT_SCALE}], was given: {font_scale}"
+            );
+        }
+        OverlayTheme {
+            mode: config.overlay_theme.unwrap_or_default(),
+            accent,
+            font_scale,
+        }
+    };
+
+    let display_scale = {
+        for (key, scale) in [
+            ("point_size_scale", config.point_size_scale),
+            ("overlay_pixels_per_point", config.overlay_pixels_per_point),
+        ] {
+            if let Some(scale) = scale {
+                if !(scale > 0. && scale <= MAX_DISPLAY_SCALE) {
+                    anyhow::bail!(
+                        "`{key}` must be in (0, {MAX_DISPLAY_SCALE}], was given: {scale}"
+                    );
+                }
+            }
+        }
+        DisplayScale {
+            point_size: config.point_size_scale,
+            overlay: config.overlay_pixels_per_point,
+        }
+    };
+
+    let key_hue = {
+        let response = config.key_hue_response.unwrap_or(DEFAULT_KEY_HUE_RESPONSE);
+        if !(MIN_RESPONSE..=MAX_KEY_HUE_RESPONSE).contains(&response) {
+            anyhow::bail!(
+                "`key_hue_response` must be in [{MIN_RESPONSE}, {MAX_KEY_HUE_RESPONSE}], was given: {response}"
+            );
+        }
+        KeyHue {
+            enabled: config.key_hue.unwrap_or_default(),
+            response,
+            hues: config.key_hues.unwrap_or(DEFAULT_KEY_HUES),
+        }
+    };
+
+    let auto_balance = {
+        let quiet_ratio = config
+            .balance_quiet_ratio
+            .unwrap_or(DEFAULT_BALANCE_QUIET_RATIO);
+        if quiet_ratio <= 0. || quiet_ratio > 1. {
+            anyhow::bail!("`balance_quiet_ratio` must be in (0, 1], was given: {quiet_ratio}");
+        }
+        let ambient_kick_rate = config
+            .balance_ambient_kick_rate
+            .unwrap_or(DEFAULT_BALANCE_AMBIENT_KICK_RATE);
+        let percussive_kick_rate = config
+            .balance_percussive_kick_rate
+            .unwrap_or(DEFAULT_BALANCE_PERCUSSIVE_KICK_RATE);
+        if ambient_kick_rate < 0. || percussive_kick_rate <= ambient_kick_rate {
+            anyhow::bail!(
+                "The balance kick rates must satisfy 0 <= ambient < percussive, was given: {ambient_kick_rate} and {percussive_kick_rate}"
+            );
+        }
+        let response = config.balance_response.unwrap_or(DEFAULT_BALANCE_RESPONSE);
+        if !(MIN_RESPONSE..=MAX_BALANCE_RESPONSE).contains(&response) {
+            anyhow::bail!(
+                "`balance_response` must be in [{MIN_RESPONSE}, {MAX_BALANCE_RESPONSE}], was given: {response}"
+            );
+        }
+        AutoBalance {
+            enabled: config.auto_balance.unwrap_or_default(),
+            quiet_ratio,
+            ambient_kick_rate,
+            percussive_kick_rate,
+            response,
+        }
+    };
+
+    let sidechain_pump = {
+        let brightness = config.pump_brightness.unwrap_or(DEFAULT_PUMP_BRIGHTNESS);
+        if !(0. ..=1.).contains(&brightness) {
+            anyhow::bail!("`pump_brightness` must be in [0, 1], was given: {brightness}");
+        }
+        let orbit = config.pump_orbit.unwrap_or(DEFAULT_PUMP_ORBIT);
+        if !(0. ..=MAX_PUMP_ORBIT).contains(&orbit) {
+            anyhow::bail!("`pump_orbit` must be in [0, {MAX_PUMP_ORBIT}], was given: {orbit}");
+        }
+        let attack = config.pump_attack.unwrap_or(DEFAULT_PUMP_ATTACK);
+        if !(0. ..=MAX_PUMP_ATTACK).contains(&attack) {
+            anyhow::bail!("`pump_attack` must be in [0, {MAX_PUMP_ATTACK}], was given: {attack}");
+        }
+        let release = config.pump_release.unwrap_or(DEFAULT_PUMP_RELEASE);
+        if release <= 0. || release > MAX_PUMP_RELEASE {
+            anyhow::bail!(
+                "`pump_release` must be in (0, {MAX_PUMP_RELEASE}], was given: {release}"
+            );
+        }
+        let release_curve = config
+            .pump_release_curve
+            .unwrap_or(DEFAULT_PUMP_RELEASE_CURVE);
+        if !(MIN_PUMP_RELEASE_CURVE..=MAX_PUMP_RELEASE_CURVE).contains(&release_curve) {
+            anyhow::bail!(
+                "`pump_release_curve` must be in [{MIN_PUMP_RELEASE_CURVE}, {MAX_PUMP_RELEASE_CURVE}], was given: {release_curve}"
+            );
+        }
+        SidechainPump {
+            enabled: config.sidechain_pump.unwrap_or_default(),
+            brightness,
+            orbit,
+            attack,
+            release,
+            release_curve,
+        }
+    };
+
+    let intensity_macro = {
+        let intensity = config.intensity.unwrap_or(DEFAULT_INTENSITY);
+        if !(0. ..=1.).contains(&intensity) {
+            anyhow::bail!("`intensity` must be in [0, 1], was given: {intensity}");
+        }
+        let custom = config.intensity_curves.unwrap_or(CustomIntensityCurves {
+            audio_scale: None,
+            attractor_strength: None,
+            kaleidoscope_max: None,
+            angular_velocity_max: None,
+        });
+        let curve = |key: &str, curve: Option<IntensityCurve>| {
+            let curve = curve.unwrap_or(DEFAULT_INTENSITY_CURVE);
+            if curve.calm < 0. || curve.hyped < 0. || curve.exponent <= 0. {
+                anyhow::bail!(
+                    "`intensity_curves.{key}` must have non-negative multipliers and a positive exponent"
+                );
+            }
+            Ok(curve)
+        };
+        IntensityMacro {
+            intensity,
+            curves: IntensityCurves {
+                audio_scale: curve("audio_scale", custom.audio_scale)?,
+                attractor_strength: curve("attractor_strength", custom.attractor_strength)?,
+                kaleidoscope_max: curve("kaleidoscope_max", custom.kaleidoscope_max)?,
+                angular_velocity_max: curve("angular_velocity_max", custom.angular_velocity_max)?,
+            },
+            osc_port: config.intensity_osc_port,
+        }
+    };
+
+    let mut fractal_tunables = fractals::default_tunables();
+    for (key, custom) in config.fractal_tunables {
+        let tunables = key
+            .parse::<usize>()
+            .ok()
+            .filter(|id| (1..fractals::FRACTAL_COUNT).contains(id))
+            .and_then(|id| fractal_tunables.get_mut(id))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "`fractal_tunables.{key}` must name a fractal in [1, {}]",
+                    fractals::FRACTAL_COUNT - 1
+                )
+            })?;
+        let iterations = custom.iterations.unwrap_or(tunables.iterations);
+        if !(1..=fractals::MAX_ITERATIONS).contains(&iterations) {
+            anyhow::bail!(
+                "`fractal_tunables.{key}.iterations` must be in [1, {}], was given: {iterations}",
+                fractals::MAX_ITERATIONS
+            );
+        }
+        let bailout = custom.bailout.unwrap_or(tunables.bailout);
+        if !(bailout > 0. && bailout <= fractals::MAX_BAILOUT) {
+            anyhow::bail!(
+                "`fractal_tunables.{key}.bailout` must be in (0, {}], was given: {bailout}",
+                fractals::MAX_BAILOUT
+            );
+        }
+        let fold_scale = custom.fold_scale.unwrap_or(tunables.fold_scale);
+        if !(fold_scale > 0. && fold_scale <= fractals::MAX_FOLD_SCALE) {
+            anyhow::bail!(
+                "`fractal_tunables.{key}.fold_scale` must be in (0, {}], was given: {fold_scale}",
+                fractals::MAX_FOLD_SCALE
+            );
+        }
+        let color_exponent = custom.color_exponent.unwrap_or(tunables.color_exponent);
+        if !(0. ..=fractals::MAX_COLOR_EXPONENT).contains(&color_exponent) {
+            anyhow::bail!(
+                "`fractal_tunables.{key}.color_exponent` must be in [0, {}], was given: {color_exponent}",
+                fractals::MAX_COLOR_EXPONENT
+            );
+        }
+        *tunables = fractals::Tunables {
+            iterations,
+            bailout,
+            fold_scale,
+            color_exponent,
+        };
+    }
+
+    let snapshots = {
+        let recall_seconds = config.snapshot_recall.unwrap_or(DEFAULT_SNAPSHOT_RECALL);
+        if !(0. ..=MAX_SNAPSHOT_RECALL).contains(&recall_seconds) {
+            anyhow::bail!(
+                "`snapshot_recall` must be in [0, {MAX_SNAPSHOT_RECALL}], was given: {recall_seconds}"
+            );
+        }
+        Snapshots {
+            persist: config
+                .persist_snapshots
+                .unwrap_or(DEFAULT_PERSIST_SNAPSHOTS),
+            recall_seconds,
+        }
+    };
+
+    let attract_mode = {
+        let idle_seconds = config.attract_idle.unwrap_or(DEFAULT_ATTRACT_IDLE);
+        if !(idle_seconds > 0. && idle_seconds <= MAX_ATTRACT_SECONDS) {
+            anyhow::bail!(
+                "`attract_idle` must be in (0, {MAX_ATTRACT_SECONDS}], was given: {idle_seconds}"
+            );
+        }
+        let scene_seconds = config.attract_scene.unwrap_or(DEFAULT_ATTRACT_SCENE);
+        if !(scene_seconds > 0. && scene_seconds <= MAX_ATTRACT_SECONDS) {
+            anyhow::bail!(
+                "`attract_scene` must be in (0, {MAX_ATTRACT_SECONDS}], was given: {scene_seconds}"
+            );
+        }
+        let transition_seconds = config
+            .attract_transition
+            .unwrap_or(DEFAULT_ATTRACT_TRANSITION);
+        if !(0. ..=scene_seconds).contains(&transition_seconds) {
+            anyhow::bail!(
+                "`attract_transition` must be in [0, `attract_scene`], was given: {transition_seconds}"
+            );
+        }
+        AttractMode {
+            enabled: config.attract_mode.unwrap_or_default(),
+            idle_seconds,
+            scene_seconds,
+            transition_seconds,
+        }
+    };
+
+    let logo_intro = {
+        let hold = config.intro_hold.unwrap_or(DEFAULT_INTRO_HOLD);
+        if !(0. ..=MAX_INTRO_SECONDS).contains(&hold) {
+            anyhow::bail!("`intro_hold` must be in [0, {MAX_INTRO_SECONDS}], was given: {hold}");
+        }
+        let ramp = config.intro_ramp.unwrap_or(DEFAULT_INTRO_RAMP);
+        if !(0. ..=MAX_INTRO_SECONDS).contains(&ramp) {
+            anyhow::bail!("`intro_ramp` must be in [0, {MAX_INTRO_SECONDS}], was given: {ramp}");
+        }
+        LogoIntro {
+            image: config.intro_logo,
+            hold,
+            ramp,
+        }
+    };
+
+    let stereo_placement = config.stereo_placement.unwrap_or(DEFAULT_STEREO_PLACEMENT);
+    if !(0. ..=1.).contains(&stereo_placement) {
+        anyhow::bail!("`stereo_placement` must be in [0, 1], was given: {stereo_placement}");
+    }
+
+    let virtual_camera = {
+        let width = config
+            .virtual_camera_width
+            .unwrap_or(DEFAULT_VIRTUAL_CAMERA_WIDTH);
+        let height = config
+            .virtual_camera_height
+            .unwrap_or(DEFAULT_VIRTUAL_CAMERA_HEIGHT);
+        // Each pair of horizontal pixels shares its chroma, and some players require even heights as well
+        if width == 0 || height == 0 || width % 2 != 0 || height % 2 != 0 {
+            anyhow::bail!(
+                "The virtual camera resolution must be positive and even, was given: {width}x{height}"
+            );
+        }
+        let fps = config
+            .virtual_camera_fps
+            .unwrap_or(DEFAULT_VIRTUAL_CAMERA_FPS);
+        if fps == 0 {
+            anyhow::bail!("`virtual_camera_fps` must be a positive number");
+        }
+        VirtualCamera {
+            enabled: config.virtual_camera.unwrap_or_default(),
+            device: config
+                .virtual_camera_device
+                .unwrap_or_else(|| String::from(DEFAULT_VIRTUAL_CAMERA_DEVICE)),
+            width,
+            height,
+            fps,
+        }
+    };
+
+    let timelapse = {
+        let interval = config
+            .timelapse_interval
+            .unwrap_or(DEFAULT_TIMELAPSE_INTERVAL);
+        if !(MIN_TIMELAPSE_INTERVAL..=MAX_TIMELAPSE_INTERVAL).contains(&interval) {
+            anyhow::bail!(
+                "`timelapse_interval` must be in [{MIN_TIMELAPSE_INTERVAL}, {MAX_TIMELAPSE_INTERVAL}], was given: {interval}"
+            );
+        }
+        if config.timelapse_beats == Some(0) {
+            anyhow::bail!("`timelapse_beats` must be a positive number");
+        }
+        let width = config.timelapse_width.unwrap_or(DEFAULT_TIMELAPSE_WIDTH);
+        let height = config.timelapse_height.unwrap_or(DEFAULT_TIMELAPSE_HEIGHT);
+        if width == 0 || height == 0 {
+            anyhow::bail!("The timelapse resolution must be positive, was given: {width}x{height}");
+        }
+        Timelapse {
+            directory: config.timelapse_directory,
+            interval,
+            beats: config.timelapse_beats,
+            width,
+            height,
+        }
+    };
+
+    let clip = {
+        let seconds = config.clip_seconds.unwrap_or(DEFAULT_CLIP_SECONDS);
+        if !(MIN_CLIP_SECONDS..=MAX_CLIP_SECONDS).contains(&seconds) {
+            anyhow::bail!(
+                "`clip_seconds` must be in [{MIN_CLIP_SECONDS}, {MAX_CLIP_SECONDS}], was given: {seconds}"
+            );
+        }
+        let fps = config.clip_fps.unwrap_or(DEFAULT_CLIP_FPS);
+        if !(1..=MAX_CLIP_FPS).contains(&fps) {
+            anyhow::bail!("`clip_fps` must be in [1, {MAX_CLIP_FPS}], was given: {fps}");
+        }
+        let width = config.clip_width.unwrap_or(DEFAULT_CLIP_WIDTH);
+        if width == 0 {
+            anyhow::bail!("`clip_width` must be positive");
+        }
+        Clip {
+            seconds,
+            fps,
+            width,
+        }
+    };
 
-    let friction_scale = config.friction_scale.unwrap_or(DEAFULT_FRICTION_SCALE);
+    let event_hooks = {
+        let kick_threshold = config
+            .kick_event_threshold
+            .unwrap_or(DEFAULT_KICK_EVENT_THRESHOLD);
+        if !(0. ..=1.).contains(&kick_threshold) {
+            anyhow::bail!("`kick_event_threshold` must be in [0, 1], was given: {kick_threshold}");
+        }
+        if let Some(url) = config
+            .event_webhooks
+            .iter()
+            .find(|url| !url.starts_with("http://"))
+        {
+            anyhow::bail!("Event webhooks must be `http://` URLs, was given: {url}");
+        }
+        EventHooks {
+            webhooks: config.event_webhooks,
+            osc_targets: config.event_osc_targets,
+            kick_threshold,
+        }
+    };
 
-    let audio_scale = {
-        const DECIBEL_SCALE: f32 = std::f32::consts::LN_10 / 10.;
-        (DECIBEL_SCALE * config.audio_scale.unwrap_or(DEFAULT_AUDIO_SCALE)).exp()
+    let midi = Midi {
+        device: config.midi_device,
+        mappings: config
+            .midi_mappings
+            .into_iter()
+            .map(parse_midi_mapping)
+            .collect::<anyhow::Result<_>>()?,
     };
 
-    let vertical_fov = config
-        .vertical_fov
-        .unwrap_or(DEFAULT_VERTICAL_FOV)
-        .clamp(-180., 180.)
-        * std::f32::consts::PI
-        / 360.;
+    if config.camera_paths.len() > MAX_CAMERA_PATHS {
+        anyhow::bail!(
+            "At most {MAX_CAMERA_PATHS} camera paths may be defined, one for each of the keys CTRL+1 through CTRL+9"
+        );
+    }
+    for path in &config.camera_paths {
+        if !path.duration.is_finite() || path.duration <= 0. {
+            anyhow::bail!(
+                "Camera path `{}` must have a positive duration, was given: {}",
+                path.name,
+                path.duration
+            );
+        }
+        if path.keyframes.is_empty() {
+            anyhow::bail!(
+                "Camera path `{}` must have at least one keyframe",
+                path.name
+            );
+        }
+        for keyframe in &path.keyframes {
+            if keyframe.axis.iter().all(|c| *c == 0.) || !keyframe.angle.is_finite() {
+                anyhow::bail!(
+                    "Camera path `{}` keyframes must have a non-zero axis and a finite angle",
+                    path.name
+                );
+            }
+            let orbit = keyframe.orbit();
+            if !(orbit > 0. && orbit <= MAX_CAMERA_ORBIT) {
+                anyhow::bail!(
+                    "Camera path `{}` keyframe orbits must be in (0, {MAX_CAMERA_ORBIT}], was given: {orbit}",
+                    path.name
+                );
+            }
+        }
+    }
+
+    if config.particle_systems.len() > MAX_PARTICLE_SYSTEMS {
+        anyhow::bail!(
+            "At most {MAX_PARTICLE_SYSTEMS} additional particle systems may be defined, was given {}",
+            config.particle_systems.len()
+        );
+    }
+    for (i, system) in config.particle_systems.iter().enumerate() {
+        if config.particle_systems[..i]
+            .iter()
+            .any(|other| other.name == system.name)
+        {
+            anyhow::bail!(
+                "Particle system `{}` is defined more than once",
+                system.name
+            );
+        }
+        if let Some(scheme) = system
+            .color_scheme
+            .as_ref()
+            .filter(|scheme| !color_scheme_names.contains(scheme))
+        {
+            anyhow::bail!(
+                "Particle system `{}` refers to unknown color scheme `{scheme}`",
+                system.name
+            );
+        }
+    }
+
+    for scene in &config.scenes {
+        if !scene.duration.is_finite() || scene.duration <= 0. {
+            anyhow::bail!(
+                "Scene `{}` must have a positive duration, was given: {}",
+                scene.name,
+                scene.duration
+            );
+        }
+        let transition = scene.transition();
+        if !(0. ..=scene.duration).contains(&transition) {
+            anyhow::bail!(
+                "Scene `{}` must have a transition in [0, {}], the scene's duration, was given: {transition}",
+                scene.name,
+                scene.duration
+            );
+        }
+        if let Some(fractal) = scene
+            .fractal
+            .filter(|id| *id as usize >= fractals::FRACTAL_COUNT)
+        {
+            anyhow::bail!(
+                "Scene `{}` must select a fractal in [0, {}], was given: {fractal}",
+                scene.name,
+                fractals::FRACTAL_COUNT - 1
+            );
+        }
+        if let Some(scheme) = scene
+            .color_scheme
+            .as_ref()
+            .filter(|scheme| !color_scheme_names.contains(scheme))
+        {
+            anyhow::bail!(
+                "Scene `{}` refers to unknown color scheme `{scheme}`",
+                scene.name
+            );
+        }
+        if let Some(path) = scene
+            .camera_path
+            .as_ref()
+            .filter(|path| !config.camera_paths.iter().any(|p| p.name == **path))
+        {
+            anyhow::bail!(
+                "Scene `{}` refers to unknown camera path `{path}`",
+                scene.name
+            );
+        }
+    }
+
+    if config.live_uniforms.len() > MAX_LIVE_UNIFORMS {
+        anyhow::bail!(
+            "At most {MAX_LIVE_UNIFORMS} live uniforms may be defined, was given {}",
+            config.live_uniforms.len()
+        );
+    }
+    if let Some((name, value)) = config.live_uniforms.iter().find(|(_, v)| !v.is_finite()) {
+        anyhow::bail!("Live uniform `{name}` must be a finite number, was given: {value}");
+    }
+
+    // Each chord may be configured for at most one action.
+    let mut keybindings: Vec<(Action, Vec<Chord>)> = Vec::with_capacity(config.keybindings.len());
+    for (name, binding) in config.keybindings {
+        let action = Action::from_config_name(&name)
+            .ok_or_else(|| anyhow::anyhow!("`keybindings` names the unknown action `{name}`"))?;
+        let chords = match binding {
+            CustomKeybinding::Chord(chord) => vec![chord],
+            CustomKeybinding::Chords(chords) => chords,
+        };
+        let chords = chords
+            .iter()
+            .map(|chord| chord.parse::<Chord>())
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        for chord in &chords {
+            if let Some((other, _)) = keybindings.iter().find(|(_, bound)| bound.contains(chord)) {
+                anyhow::bail!(
+                    "`{chord}` is bound to both `{}` and `{name}`",
+                    other.config_name()
+                );
+            }
+        }
+        keybindings.push((action, chords));
+    }
 
     Ok(AppConfig {
         launch_fullscreen: config.launch_fullscreen.unwrap_or_default(),
         launch_help_visible: config.launch_help_visible.unwrap_or(DEFAULT_HELP_VISIBLE),
+        aspect_fit: config.aspect_fit.unwrap_or_default(),
+        display_layout: config.display_layout.unwrap_or_default(),
+        coloring_mode: config.coloring_mode.unwrap_or_default(),
+        physics_mode: config.physics_mode.unwrap_or_default(),
+        particle_containment: config.particle_containment.unwrap_or_default(),
+        color_management: config.color_management.unwrap_or_default(),
+        present_mode: config.present_mode.unwrap_or_default(),
+        direct_display: DirectDisplay {
+            enabled: config.direct_display.unwrap_or_default(),
+            display: config.direct_display_index.unwrap_or_default(),
+            mode: config.direct_display_mode.unwrap_or_default(),
+        },
+        overlay_theme,
+        display_scale,
 
-        max_speed,
+        parameters,
         particle_count,
-        spring_coefficient,
-        point_size,
-        friction_scale,
+        msaa_samples,
         hide_stationary_particles: config
             .hide_stationary_particles
             .unwrap_or(DEFAULT_HIDE_STATIONARY_PARTICLES),
         disable_background: config.disable_background.unwrap_or_default(),
+        kick_echoes: config.kick_echoes.unwrap_or_default(),
+        stereo_placement,
+        stereo_attractors: config.stereo_attractors.unwrap_or_default(),
+        capture_mode: config.capture_mode.unwrap_or_default(),
+        particle_sprites: config.particle_sprites.unwrap_or_default(),
+        sprite_shape,
+        sprite_texture: config.sprite_texture,
+        particle_blending: config.particle_blending.unwrap_or_default(),
+        particle_alpha,
+        surface_emission: config.surface_emission.unwrap_or_default(),
+        surface_emission_rate,
+
+        particle_repulsion,
+
+        depth_of_field,
+
+        particle_trails,
+
+        kaleidoscope,
+
+        bloom,
+
+        tone_mapping,
+
+        temporal_antialiasing: config.temporal_antialiasing.unwrap_or_default(),
+        progressive_refinement: config.progressive_refinement.unwrap_or_default(),
+
+        responsiveness,
+
+        attractor_counts,
+        attractor_strengths,
+
+        analysis_presets,
+        analysis_preset,
+        bands,
+        gain_control,
+
+        stems,
+
+        key_hue,
+
+        auto_balance,
+
+        sidechain_pump,
+
+        scheme_transition,
+
+        auto_cycle,
+
+        scheme_crossfade,
+
+        intensity_macro,
+
+        performer_osc_port: config.performer_osc_port,
+
+        midi,
+
+        snapshots,
+
+        attract_mode,
+
+        logo_intro,
+
+        virtual_camera,
+
+        timelapse,
 
-        audio_scale,
+        clip,
 
-        vertical_fov,
+        event_hooks,
 
         color_schemes,
         color_scheme_names,
+        color_keyframes,
+        keyframe_clock: KeyframeClock {
+            source: config.keyframe_clock.unwrap_or_default(),
+            mpd_address: config
+                .keyframe_mpd_address
+                .unwrap_or_else(|| String::from(DEFAULT_MPD_ADDRESS)),
+            mpris_player: config.keyframe_mpris_player,
+        },
+
+        camera_paths: config.camera_paths,
+        particle_systems: config.particle_systems,
+        scenes: config.scenes,
+
+        live_uniforms: config.live_uniforms,
+        user_fractal: config.user_fractal,
+
+        fractal_tunables,
+
+        keybindings,
+    })
+}
+
+// Validate a MIDI mapping, which must name exactly one control or note and one variable or action.
+fn parse_midi_mapping(mapping: CustomMidiMapping) -> anyhow::Result<MidiMapping> {
+    if let Some(channel) = mapping.channel {
+        if !(1..=16).contains(&channel) {
+            anyhow::bail!("MIDI mapping channels must be in [1, 16], was given: {channel}");
+        }
+    }
+    let input = match (mapping.control, mapping.note) {
+        (Some(control), None) => MidiInput::Control(control),
+        (None, Some(note)) => MidiInput::Note(note),
+        _ => anyhow::bail!("Each MIDI mapping must have exactly one of `control` or `note`"),
+    };
+    if let MidiInput::Control(number) | MidiInput::Note(number) = input {
+        if number > 127 {
+            anyhow::bail!("MIDI controls and notes must be in [0, 127], was given: {number}");
+        }
+    }
+
+    let target = match (mapping.variable, mapping.action) {
+        (Some(name), None) => {
+            let range = match (mapping.min, mapping.max) {
+                (None, None) => None,
+                (Some(min), Some(max)) if min.is_finite() && max.is_finite() => Some((min, max)),
+                _ => anyhow::bail!(
+                    "MIDI mapping of `{name}` must have both a finite `min` and `max`, or neither"
+                ),
+            };
+            MidiTarget::Variable { name, range }
+        }
+        (None, Some(name)) => {
+            if mapping.min.is_some() || mapping.max.is_some() {
+                anyhow::bail!("MIDI mapping of the action `{name}` may not have a `min` or `max`");
+            }
+            MidiTarget::Action(
+                Action::from_config_name(&name).ok_or_else(|| {
+                    anyhow::anyhow!("MIDI mapping names the unknown action `{name}`")
+                })?,
+            )
+        }
+        _ => anyhow::bail!("Each MIDI mapping must have exactly one of `variable` or `action`"),
+    };
+    if matches!(input, MidiInput::Note(_)) && matches!(target, MidiTarget::Variable { .. }) {
+        anyhow::bail!(
+            "MIDI notes may only trigger actions, since they have no position to set a variable to"
+        );
+    }
+
+    Ok(MidiMapping {
+        channel: mapping.channel,
+        input,
+        target,
+    })
+}
+
+// Find the scheme at the given time by interpolating between the surrounding keyframes, which must be sorted.
+// Before the first and after the last keyframe their schemes are held
+pub fn keyframed_scheme(
+    keyframes: &[ColorKeyframe],
+    schemes: &[Scheme],
+    time: f32,
+) -> Option<Scheme> {
+    let next = keyframes.iter().position(|keyframe| keyframe.time > time);
+    let (previous, next) = match next {
+        None => (keyframes.last()?, None),
+        Some(0) => (&keyframes[0], None),
+        Some(i) => (&keyframes[i - 1], Some(&keyframes[i])),
+    };
+    let from = &schemes[previous.scheme_index];
+    Some(match next {
+        // Ease in and out of each transition so that colors settle on the keyframed schemes.
+        Some(next) => {
+            let t = (time - previous.time) / (next.time - previous.time);
+            Scheme::lerp(from, &schemes[next.scheme_index], t * t * (3. - 2. * t))
+        }
+        None => *from,
     })
 }
 
@@ -322,5 +3552,21 @@ pub const MAGMA_CORE: Scheme = Scheme {
     ],
 };
 
+// A neutral grayscale ramp, as the starting point of a new scheme.
+pub const BLANK: Scheme = Scheme {
+    speed: [
+        [0.2, 0.2, 0.2, 0.5],
+        [0.45, 0.45, 0.45, 1.5],
+        [0.7, 0.7, 0.7, 3.],
+        [0.95, 0.95, 0.95, 0.],
+    ],
+    index: [
+        [0.2, 0.2, 0.2, 0.25],
+        [0.45, 0.45, 0.45, 0.5],
+        [0.7, 0.7, 0.7, 0.75],
+        [0.95, 0.95, 0.95, 1.],
+    ],
+};
+
 const COLOR_SCHEMES: [Scheme; 4] = [ORIGINAL, NORTHERN_LIGHTS, ARCTIC, MAGMA_CORE];
 const COLOR_SCHEME_NAMES: [&str; 4] = ["Classic", "Northern Lights", "Arctic", "Magma Core"];
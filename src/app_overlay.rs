@@ -17,9 +17,11 @@
 */
 
 use std::ops::RangeInclusive;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
 
-use egui::{ComboBox, ScrollArea, Slider, Ui};
+use egui::{Button, ComboBox, ScrollArea, Slider, Ui};
 use egui_winit_vulkano::{Gui, GuiConfig};
 use vulkano::command_buffer::SecondaryAutoCommandBuffer;
 use vulkano::device::Queue;
@@ -27,44 +29,262 @@ use vulkano::render_pass::Subpass;
 use vulkano::swapchain::{Surface, Swapchain};
 use winit::{event::WindowEvent, event_loop::EventLoop};
 
-use crate::app_config::{AppConfig, Scheme};
-use crate::engine::{ConfigConstants, Engine};
+use crate::app_config::{
+    AnalysisPreset, AppConfig, AttractorStrengths, AutoBalance, AutoCycle, BandOverrides,
+    CaptureMode, ColorManagement, ColoringMode, DepthOfField, IntensityCurves, IntensityMacro,
+    IntensityMultipliers, Kaleidoscope, KeyHue, OverlayTheme, ParticleBloom, ParticleContainment,
+    ParticleRepulsion, ParticleSystem, ParticleTrails, PhysicsMode, PresentationMode,
+    Responsiveness, Scheme, SchemeCrossfade, SidechainPump, ThemeMode, ToneMapOperator,
+    ToneMapping, BLANK, MAX_ATTRACTOR_STRENGTH, MAX_BALANCE_RESPONSE, MAX_BLOOM_INTENSITY,
+    MAX_CYCLE_ENERGY_RATIO, MAX_CYCLE_ENERGY_SECONDS, MAX_CYCLE_KICKS, MAX_DOF_APERTURE,
+    MAX_DOF_FOCUS_DISTANCE, MAX_EXPOSURE, MAX_GAIN_SECONDS, MAX_GAIN_TARGET,
+    MAX_KALEIDOSCOPE_SEGMENTS, MAX_KALEIDOSCOPE_SPIN, MAX_KEY_HUE_RESPONSE, MAX_OVERLAY_FONT_SCALE,
+    MAX_PUMP_ATTACK, MAX_PUMP_ORBIT, MAX_PUMP_RELEASE, MAX_PUMP_RELEASE_CURVE,
+    MAX_REACTIVE_RESPONSE, MAX_REPULSION_RADIUS, MAX_SMOOTH_RESPONSE, MAX_TRAIL_LENGTH,
+    MAX_VOLUME_RESPONSE, MIN_CYCLE_ENERGY_RATIO, MIN_DOF_FOCUS_DISTANCE, MIN_EXPOSURE,
+    MIN_GAIN_TARGET, MIN_KALEIDOSCOPE_SEGMENTS, MIN_OVERLAY_FONT_SCALE, MIN_PUMP_RELEASE_CURVE,
+    MIN_RESPONSE, MIN_TRAIL_LENGTH, MSAA_SAMPLE_CHOICES,
+};
+use crate::audio::{
+    list_devices, AnalysisConfig, AudioDevice, BandConfig, BeatInfo, KickMeter, Note,
+    SharedAnalysisConfig, SourceKind, State, StreamInfo, MAX_BASS_ATTRACTORS, MAX_HIGH_ATTRACTORS,
+    MAX_MIDS_ATTRACTORS, SPECTRUM_BINS, SPECTRUM_MAX_FREQUENCY, SPECTRUM_MIN_FREQUENCY,
+};
+use crate::clip::ClipStatus;
+use crate::cvars::{CVar, Registry, Storage, Writer};
+use crate::engine::{ConfigConstants, DrawData, Engine, GPU_PASS_NAMES};
+use crate::fractals::{self, Tunables, FRACTALS, FRACTAL_COUNT};
+use crate::input;
+use crate::metrics::{FrameHistory, Metrics};
+use crate::parameters::{
+    self, constants_from_presentable, constants_to_presentable, ParameterValues, PARAMETERS,
+};
+use crate::snapshots::Look;
 
 #[derive(Clone, Copy)]
-struct ConfigUiScheme {
+pub struct ConfigUiScheme {
     pub index_rgb: [[u8; 3]; 4],
     pub index_val: [f32; 4],
     pub speed_rgb: [[u8; 3]; 4],
     pub speed_val: [f32; 4],
 }
 
+// A change to the list of color schemes which the rest of the app must follow.
+#[derive(Clone, Copy)]
+pub enum ColorSchemeEdit {
+    Renamed,
+    Removed(usize), // The index of the removed scheme
+}
+
 pub struct AppOverlay {
     config_window: ConfigWindow,
     gui: Gui,
     help_visible: bool,
+    inspector_visible: bool,
+    timelapse_frames: Option<usize>, // The frames saved by the running timelapse, if any
+    clip_status: Option<ClipStatus>,
+    audio_warning: Option<&'static str>, // A problem with the audio source, shown until it is resolved
+    toast: Option<(String, Instant)>,    // A brief message and when it was shown, until it expires
+    tour: Option<Tour>,
+    crash_report: Option<PathBuf>, // A report of the previous run's crash, shown until dismissed
+    splash: Option<Splash>, // Shown over the visualization until it has initialized and faded in
+    variables: Registry<ConfigWindow>,
+    variables_console: VariablesConsole,
+    spectrum_panel: SpectrumPanel,
+}
+
+// Keybindings the onboarding tour demonstrates on behalf of the user.
+#[derive(Clone, Copy)]
+pub enum TourDemonstration {
+    ToggleKaleidoscope,
+    NextColorScheme,
+}
+
+// The steps of the onboarding tour, in order.
+#[derive(Clone, Copy, PartialEq)]
+enum TourStep {
+    Welcome,
+    Kaleidoscope,
+    ColorSchemes,
+    ConfigWindow,
+    Finished,
+}
+
+// State machine for the onboarding tour shown on first launch.
+struct Tour {
+    ended: bool,
+    step: TourStep,
+    last_demonstration: Instant,
+    kaleidoscope_toggles: u32,
+    requested_demonstration: Option<TourDemonstration>,
+}
+
+// Seconds between repeated demonstrations of the current step's keybinding.
+const TOUR_DEMONSTRATION_PERIOD: f32 = 3.;
+
+// The startup splash, covering the visualization while it initializes.
+struct Splash {
+    logo: egui::TextureHandle,
+    fade: Option<f32>, // Seconds since the visualization became ready, if it has
 }
 
+// Seconds the splash takes to fade into the visualization.
+const SPLASH_FADE_SECONDS: f32 = 0.75;
+
+// Width and height of the splash's logo, in points.
+const SPLASH_LOGO_SIZE: f32 = 128.;
+
+// Seconds a toast is shown before it expires.
+const TOAST_SECONDS: f32 = 6.;
+
+// The console listing every runtime variable, filtered by name or description.
+#[derive(Default)]
+struct VariablesConsole {
+    visible: bool,
+    filter: String,
+    command: String,
+    response: String, // The result of the last command
+}
+
+// The window showing the latest analyzed spectrum, the loudest notes of each band, and the kick meter.
+#[derive(Default)]
+struct SpectrumPanel {
+    visible: bool,
+    spectrum: [f32; SPECTRUM_BINS],
+    peak: f32, // The loudest bin recently received, decaying slowly so that quiet passages still fill the plot
+    bass_notes: Vec<Note>,
+    mids_notes: Vec<Note>,
+    high_notes: Vec<Note>,
+    kick_meter: KickMeter,
+    last_kick: Option<Instant>,
+}
+
+// The fraction of the spectrum's peak retained with each state received.
+const SPECTRUM_PEAK_DECAY: f32 = 0.98;
+
+// Seconds the kick meter stays lit after a kick.
+const KICK_FLASH_SECONDS: f32 = 0.15;
+
 struct ConfigWindow {
     color_schemes: Vec<ConfigUiScheme>,
-    init_color_schemes: Vec<ConfigUiScheme>,
+    init_color_schemes: Vec<Option<ConfigUiScheme>>, // The launch values of each scheme, if it existed at launch
     edit_scheme_index: usize,
+    renaming_scheme: Option<String>, // The new name being typed for the edited scheme, if any
+    color_scheme_edit: Option<ColorSchemeEdit>,
 
     config: ConfigConstants,
     init_config: ConfigConstants,
     visible: bool,
+
+    repulsion: ParticleRepulsion,
+    init_repulsion: ParticleRepulsion,
+
+    depth_of_field: DepthOfField,
+    init_depth_of_field: DepthOfField,
+
+    trails: ParticleTrails,
+    init_trails: ParticleTrails,
+
+    kaleidoscope: Kaleidoscope,
+    init_kaleidoscope: Kaleidoscope,
+
+    particle_systems: Vec<ParticleSystem>,
+    init_particle_systems: Vec<ParticleSystem>,
+
+    bloom: ParticleBloom,
+    init_bloom: ParticleBloom,
+
+    tone_mapping: ToneMapping,
+    init_tone_mapping: ToneMapping,
+
+    temporal_antialiasing: bool,
+    init_temporal_antialiasing: bool,
+
+    progressive_refinement: bool,
+    init_progressive_refinement: bool,
+
+    kick_echoes: bool,
+    init_kick_echoes: bool,
+
+    msaa_samples: u32, // Only applied at launch, so is changed here to be saved for the next
+    init_msaa_samples: u32,
+
+    fractal_tunables: [Tunables; FRACTAL_COUNT], // Indexed by distance estimator ID
+    init_fractal_tunables: [Tunables; FRACTAL_COUNT],
+
+    present_mode: PresentationMode,
+    init_present_mode: PresentationMode,
+    requested_present_mode: Option<PresentationMode>,
+
+    stereo_placement: f32,
+    init_stereo_placement: f32,
+    stereo_attractors: bool,
+    init_stereo_attractors: bool,
+    attractor_strengths: AttractorStrengths,
+    init_attractor_strengths: AttractorStrengths,
+
+    intensity: f32,
+    init_intensity: f32,
+    intensity_curves: IntensityCurves,
+
+    responsiveness: Responsiveness,
+    init_responsiveness: Responsiveness,
+
+    key_hue: KeyHue,
+    init_key_hue: KeyHue,
+
+    theme: OverlayTheme,
+    init_theme: OverlayTheme,
+
+    auto_balance: AutoBalance,
+    init_auto_balance: AutoBalance,
+
+    auto_cycle: AutoCycle,
+    init_auto_cycle: AutoCycle,
+
+    sidechain_pump: SidechainPump,
+    init_sidechain_pump: SidechainPump,
+
+    scheme_crossfade: SchemeCrossfade,
+    init_scheme_crossfade: SchemeCrossfade,
+    crossfade_position: f32,
+    requested_crossfade: bool,
+
+    requested_save: bool, // Whether the applied configuration should be written to the config file
+
+    analysis: AnalysisConfig,
+    init_analysis: AnalysisConfig,
+    shared_analysis: SharedAnalysisConfig,
+    analysis_presets: Vec<AnalysisPreset>,
+    analysis_preset: usize,
+    init_analysis_preset: usize,
+
+    audio_source: SourceKind,
+    requested_audio_source: Option<SourceKind>,
+
+    // The devices which may be captured, and the one selected in place of the default audio out, if any.
+    audio_devices: Vec<AudioDevice>,
+    audio_device: Option<AudioDevice>,
+    requested_audio_device: bool,
+
+    // The active audio source and its captured stream, shown while diagnosing the audio.
+    audio_description: String,
+    audio_stream: Option<StreamInfo>,
+    audio_beat: Option<BeatInfo>,
+
+    // Screen area of the window during the last draw, used for highlighting.
+    rect: Option<egui::Rect>,
 }
 
 const DEFAULT_VISIBILITY: bool = false;
 
-// Helper for viewing color schemes in the config UI.
-fn add_color_scheme(
-    ui: &mut Ui,
-    config_scheme: &mut ConfigUiScheme,
-    scheme: &mut Scheme,
-    displayed_scheme_index: &mut usize,
-    edit_scheme_index: usize,
-    engine: &mut Engine,
-) {
+// Limits of the vertical field-of-view, in degrees, when zooming.
+const MIN_ZOOM_FOV: f32 = 10.;
+const MAX_ZOOM_FOV: f32 = 150.;
+
+// Widgets for editing the colors and thresholds of a color scheme, returning whether any were changed.
+// Shared with the standalone config editor
+pub fn add_scheme_editor(ui: &mut Ui, config_scheme: &mut ConfigUiScheme) -> bool {
     // Helper to add rgb widgets and sliders associated with part of a color-scheme.
     fn add_scheme_element(
         ui: &mut Ui,
@@ -99,27 +319,40 @@ fn add_color_scheme(
     enforce_limits(&mut config_scheme.index_val, &mut changed);
     enforce_limits(&mut config_scheme.speed_val, &mut changed);
 
+    ui.heading("Index-Based Color Scheme");
+    egui::Grid::new("scheme_index_grid").show(ui, |ui| {
+        add_scheme_element(
+            ui,
+            &mut config_scheme.index_rgb,
+            &mut config_scheme.index_val,
+            0.0..=1.,
+            &mut changed,
+        );
+    });
+    ui.heading("Speed-Based Color Scheme");
+    egui::Grid::new("scheme_speed_grid").show(ui, |ui| {
+        add_scheme_element(
+            ui,
+            &mut config_scheme.speed_rgb,
+            &mut config_scheme.speed_val,
+            0.0..=10.,
+            &mut changed,
+        );
+    });
+    changed
+}
+
+// Helper for viewing color schemes in the config UI.
+fn add_color_scheme(
+    ui: &mut Ui,
+    config_scheme: &mut ConfigUiScheme,
+    scheme: &mut Scheme,
+    displayed_scheme_index: &mut usize,
+    edit_scheme_index: usize,
+    engine: &mut Engine,
+) {
     ScrollArea::vertical().max_height(350.).show(ui, |ui| {
-        ui.heading("Index-Based Color Scheme");
-        egui::Grid::new("scheme_index_grid").show(ui, |ui| {
-            add_scheme_element(
-                ui,
-                &mut config_scheme.index_rgb,
-                &mut config_scheme.index_val,
-                0.0..=1.,
-                &mut changed,
-            );
-        });
-        ui.heading("Speed-Based Color Scheme");
-        egui::Grid::new("scheme_speed_grid").show(ui, |ui| {
-            add_scheme_element(
-                ui,
-                &mut config_scheme.speed_rgb,
-                &mut config_scheme.speed_val,
-                0.0..=10.,
-                &mut changed,
-            );
-        });
+        let changed = add_scheme_editor(ui, config_scheme);
 
         if edit_scheme_index != *displayed_scheme_index
             && ui.button("Make this color scheme active").clicked()
@@ -137,26 +370,1037 @@ fn add_color_scheme(
     });
 }
 
-fn update_app_constants(engine: &mut Engine, config: ConfigConstants) {
-    let constants = constants_from_presentable(config);
+// Helper for creating, renaming, and deleting the color schemes in the config UI.
+// New schemes are appended so that the indices of the existing ones are unchanged
+fn add_scheme_list_controls(
+    ui: &mut Ui,
+    config_window: &mut ConfigWindow,
+    engine: &mut Engine,
+    color_scheme_names: &mut Vec<String>,
+    color_schemes: &mut Vec<Scheme>,
+    displayed_scheme_index: &mut usize,
+) {
+    let index = config_window.edit_scheme_index;
+    if let Some(name) = &mut config_window.renaming_scheme {
+        let mut finished = false;
+        ui.horizontal(|ui| {
+            let response = ui.text_edit_singleline(name);
+            // Schemes are saved and referenced by name, so each name must be unique.
+            let name = name.trim();
+            let valid = !name.is_empty()
+                && !color_scheme_names
+                    .iter()
+                    .enumerate()
+                    .any(|(i, n)| i != index && n == name);
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.add_enabled(valid, Button::new("Apply")).clicked() || (valid && submitted) {
+                color_scheme_names[index] = name.to_owned();
+                config_window.color_scheme_edit = Some(ColorSchemeEdit::Renamed);
+                finished = true;
+            }
+            finished |= ui.button("Cancel").clicked();
+        });
+        if finished {
+            config_window.renaming_scheme = None;
+        }
+        return;
+    }
+
+    ui.horizontal(|ui| {
+        let mut added = None;
+        if ui
+            .button("Duplicate")
+            .on_hover_text("Add a copy of the selected color scheme.")
+            .clicked()
+        {
+            let name = format!("{} copy", color_scheme_names[index]);
+            added = Some((name, config_window.color_schemes[index]));
+        }
+        if ui
+            .button("New")
+            .on_hover_text("Add a grayscale color scheme to start from.")
+            .clicked()
+        {
+            added = Some(("Untitled".to_owned(), BLANK.into()));
+        }
+        if let Some((name, config_scheme)) = added {
+            color_scheme_names.push(unique_scheme_name(color_scheme_names, &name));
+            color_schemes.push(config_scheme.into());
+            config_window.color_schemes.push(config_scheme);
+            config_window.init_color_schemes.push(None);
+            config_window.edit_scheme_index = color_schemes.len() - 1;
+        }
+
+        if ui.button("Rename").clicked() {
+            config_window.renaming_scheme = Some(color_scheme_names[index].clone());
+        }
+
+        // Always keep at least one scheme to display.
+        if ui
+            .add_enabled(color_schemes.len() > 1, Button::new("Delete"))
+            .on_hover_text(
+                "Remove the selected color scheme, along with any keyframes and snapshots of it.",
+            )
+            .clicked()
+        {
+            color_scheme_names.remove(index);
+            color_schemes.remove(index);
+            config_window.color_schemes.remove(index);
+            config_window.init_color_schemes.remove(index);
+
+            // Indices of the removed scheme move to its neighbor.
+            let count = color_schemes.len();
+            let displayed_removed = *displayed_scheme_index == index;
+            for i in [
+                &mut *displayed_scheme_index,
+                &mut config_window.edit_scheme_index,
+                &mut config_window.scheme_crossfade.scheme_a,
+                &mut config_window.scheme_crossfade.scheme_b,
+                &mut config_window.init_scheme_crossfade.scheme_a,
+                &mut config_window.init_scheme_crossfade.scheme_b,
+            ] {
+                if *i > index || *i == count {
+                    *i -= 1;
+                }
+            }
+            if displayed_removed {
+                engine.update_color_scheme(color_schemes[*displayed_scheme_index]);
+            }
+            config_window.color_scheme_edit = Some(ColorSchemeEdit::Removed(index));
+        }
+    });
+}
+
+// Helper to number the given name until it differs from every existing scheme name.
+fn unique_scheme_name(color_scheme_names: &[String], name: &str) -> String {
+    let mut unique = name.to_owned();
+    let mut n = 2;
+    while color_scheme_names.contains(&unique) {
+        unique = format!("{name} {n}");
+        n += 1;
+    }
+    unique
+}
+
+// Add a slider for each registered shader parameter, returning whether any were changed.
+// The constants are in their presented units. Shared with the standalone config editor
+pub fn add_parameter_sliders(ui: &mut Ui, config: &mut ConfigConstants) -> bool {
+    let mut changed = false;
+    for parameter in &PARAMETERS {
+        let value = (parameter.destination)(config);
+        changed |= ui
+            .add(Slider::new(value, parameter.slider_range.clone()).text(parameter.label))
+            .changed();
+    }
+    changed
+}
+
+// Helper for viewing and tuning the audio-analysis parameters in the config UI.
+fn add_audio_analysis(ui: &mut Ui, analysis: &mut AnalysisConfig) -> bool {
+    // Helper to add the widgets associated with a single frequency band.
+    fn add_band(
+        ui: &mut Ui,
+        name: &str,
+        band: &mut BandConfig,
+        max_attractors: usize,
+        changed: &mut bool,
+    ) {
+        const MIN_BAND_WIDTH: f32 = 10.;
+        ui.label(egui::RichText::new(name).strong());
+        ui.end_row();
+
+        ui.label("min frequency (Hz)");
+        *changed |= ui
+            .add(Slider::new(&mut band.min_frequency, 20.0..=20_000.).logarithmic(true))
+            .changed();
+        ui.end_row();
+        ui.label("max frequency (Hz)");
+        *changed |= ui
+            .add(Slider::new(&mut band.max_frequency, 20.0..=20_000.).logarithmic(true))
+            .changed();
+        ui.end_row();
+        ui.label("min volume");
+        *changed |= ui
+            .add(Slider::new(&mut band.min_volume, 0.0..=2.).logarithmic(true))
+            .changed();
+        ui.end_row();
+        ui.label("attractors");
+        *changed |= ui
+            .add(Slider::new(&mut band.attractors, 0..=max_attractors))
+            .changed();
+        ui.end_row();
+
+        // Ensure the band always contains at least a few frequency bins.
+        if band.max_frequency < band.min_frequency + MIN_BAND_WIDTH {
+            band.max_frequency = band.min_frequency + MIN_BAND_WIDTH;
+            *changed = true;
+        }
+    }
+
+    let mut changed = false;
+    egui::Grid::new("audio_analysis_grid").show(ui, |ui| {
+        add_band(
+            ui,
+            "Bass",
+            &mut analysis.bass,
+            MAX_BASS_ATTRACTORS,
+            &mut changed,
+        );
+        add_band(
+            ui,
+            "Mids",
+            &mut analysis.mids,
+            MAX_MIDS_ATTRACTORS,
+            &mut changed,
+        );
+        add_band(
+            ui,
+            "Highs",
+            &mut analysis.high,
+            MAX_HIGH_ATTRACTORS,
+            &mut changed,
+        );
+
+        let kick = &mut analysis.kick;
+        ui.label(egui::RichText::new("Kicks").strong());
+        ui.end_row();
+        ui.label("min magnitude");
+        changed |= ui
+            .add(Slider::new(&mut kick.min_magnitude, 0.0..=10.))
+            .changed();
+        ui.end_row();
+        ui.label("history ratio");
+        changed |= ui
+            .add(Slider::new(&mut kick.history_ratio, 0.0..=10.))
+            .on_hover_text("How many times louder than the recent bass a kick must be")
+            .changed();
+        ui.end_row();
+        ui.label("min interval (s)");
+        changed |= ui
+            .add(Slider::new(&mut kick.min_interval, 0.0..=5.))
+            .changed();
+        ui.end_row();
+
+        let gain = &mut analysis.gain;
+        ui.label(egui::RichText::new("Gain control").strong());
+        changed |= ui
+            .checkbox(&mut gain.enabled, "")
+            .on_hover_text("Normalize the loudness of the input toward the target level")
+            .changed();
+        ui.end_row();
+        ui.label("target (dBFS)");
+        changed |= ui
+            .add(Slider::new(
+                &mut gain.target,
+                MIN_GAIN_TARGET..=MAX_GAIN_TARGET,
+            ))
+            .changed();
+        ui.end_row();
+        ui.label("attack (s)");
+        changed |= ui
+            .add(Slider::new(&mut gain.attack, 0.01..=MAX_GAIN_SECONDS).logarithmic(true))
+            .on_hover_text("How quickly the gain falls when the input grows louder")
+            .changed();
+        ui.end_row();
+        ui.label("release (s)");
+        changed |= ui
+            .add(Slider::new(&mut gain.release, 0.01..=MAX_GAIN_SECONDS).logarithmic(true))
+            .on_hover_text("How quickly the gain rises when the input grows quieter")
+            .changed();
+        ui.end_row();
+    });
+    changed
+}
+
+// Atomically publish a new analysis snapshot for the audio thread to use.
+fn publish_analysis_config(shared: &SharedAnalysisConfig, analysis: AnalysisConfig) {
+    shared.store(Arc::new(analysis));
+}
+
+// Style the overlay windows with the given theme, scaling the default text sizes.
+fn apply_theme(ctx: &egui::Context, theme: OverlayTheme) {
+    let mut visuals = match theme.mode {
+        ThemeMode::Dark => egui::Visuals::dark(),
+        ThemeMode::Light => egui::Visuals::light(),
+    };
+    if let Some([r, g, b]) = theme.accent {
+        let accent = egui::Color32::from_rgb(r, g, b);
+        visuals.selection.bg_fill = accent;
+        visuals.hyperlink_color = accent;
+        visuals.widgets.hovered.bg_stroke.color = accent;
+        visuals.widgets.active.bg_stroke.color = accent;
+    }
+
+    let mut style = egui::Style {
+        visuals,
+        ..egui::Style::default()
+    };
+    for font in style.text_styles.values_mut() {
+        font.size *= theme.font_scale;
+    }
+    ctx.set_style(style);
+}
+
+// Widgets for the overlay theme, returning whether any were changed.
+fn add_theme_toggle(ui: &mut Ui, theme: &mut OverlayTheme) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        changed |= ui
+            .selectable_value(&mut theme.mode, ThemeMode::Dark, "Dark")
+            .changed();
+        changed |= ui
+            .selectable_value(&mut theme.mode, ThemeMode::Light, "Light")
+            .changed();
+
+        let mut custom_accent = theme.accent.is_some();
+        if ui.checkbox(&mut custom_accent, "Accent").changed() {
+            theme.accent = custom_accent.then_some([255, 136, 0]);
+            changed = true;
+        }
+        if let Some(accent) = &mut theme.accent {
+            changed |= ui.color_edit_button_srgb(accent).changed();
+        }
+    });
+
+    // Only apply the text scale once dragging ends, since rescaling moves the slider under the cursor.
+    let response = ui.add(
+        Slider::new(
+            &mut theme.font_scale,
+            MIN_OVERLAY_FONT_SCALE..=MAX_OVERLAY_FONT_SCALE,
+        )
+        .text("text scale"),
+    );
+    changed |= response.drag_released() || (response.changed() && !response.dragged());
+    changed
+}
+
+// The audio scale is applied through the intensity macro, so the edited value is that at the neutral intensity.
+fn update_app_constants(
+    engine: &mut Engine,
+    config: ConfigConstants,
+    intensity: IntensityMultipliers,
+) {
+    let mut constants = constants_from_presentable(config);
+    constants.audio_scale *= intensity.audio_scale;
     engine.update_app_constants(constants);
 }
 
+// Publish the new value of a runtime variable through the writer it requires.
+fn notify_writer(writer: Writer, config_window: &mut ConfigWindow, engine: &mut Engine) {
+    match writer {
+        Writer::AppConstants => update_app_constants(
+            engine,
+            config_window.config,
+            config_window
+                .intensity_curves
+                .multipliers(config_window.intensity),
+        ),
+        Writer::Analysis => {
+            publish_analysis_config(&config_window.shared_analysis, config_window.analysis);
+        }
+        Writer::Crossfade => config_window.requested_crossfade = true,
+        Writer::Frame => {}
+    }
+}
+
+// The registry of every tunable of the config window, named by their keys in the config file where they have one.
+// The shader parameters are registered in their presented units, as they are edited by the sliders
+#[allow(clippy::too_many_lines)]
+fn config_variables() -> Registry<ConfigWindow> {
+    // Helpers to register a float in a range, or a boolean, which is published by the given writer.
+    fn float(
+        name: &'static str,
+        description: &'static str,
+        writer: Writer,
+        range: RangeInclusive<f32>,
+        field: fn(&mut ConfigWindow) -> &mut f32,
+    ) -> CVar<ConfigWindow> {
+        CVar::new(
+            name,
+            description,
+            writer,
+            move |window: &mut ConfigWindow| Storage::Float(field(window), range.clone()),
+        )
+    }
+    fn boolean(
+        name: &'static str,
+        description: &'static str,
+        field: fn(&mut ConfigWindow) -> &mut bool,
+    ) -> CVar<ConfigWindow> {
+        CVar::new(
+            name,
+            description,
+            Writer::Frame,
+            move |window: &mut ConfigWindow| Storage::Bool(field(window)),
+        )
+    }
+    fn band(
+        variables: &mut Vec<CVar<ConfigWindow>>,
+        names: [&'static str; 3],
+        field: fn(&mut ConfigWindow) -> &mut BandConfig,
+    ) {
+        variables.extend([
+            CVar::new(
+                names[0],
+                "Lowest frequency of the band, in Hz",
+                Writer::Analysis,
+                move |window: &mut ConfigWindow| {
+                    Storage::Float(&mut field(window).min_frequency, 20.0..=20_000.)
+                },
+            ),
+            CVar::new(
+                names[1],
+                "Highest frequency of the band, in Hz",
+                Writer::Analysis,
+                move |window: &mut ConfigWindow| {
+                    Storage::Float(&mut field(window).max_frequency, 20.0..=20_000.)
+                },
+            ),
+            CVar::new(
+                names[2],
+                "Least volume of a note in the band which may become an attractor",
+                Writer::Analysis,
+                move |window: &mut ConfigWindow| {
+                    Storage::Float(&mut field(window).min_volume, 0.0..=2.)
+                },
+            ),
+        ]);
+    }
+
+    let mut variables: Vec<_> = PARAMETERS
+        .iter()
+        .map(|parameter| {
+            let destination = parameter.destination;
+            let range = parameter.slider_range.clone();
+            CVar::new(
+                parameter.key,
+                parameter.label,
+                Writer::AppConstants,
+                move |window: &mut ConfigWindow| {
+                    Storage::Float(destination(&mut window.config), range.clone())
+                },
+            )
+        })
+        .collect();
+
+    variables.extend([
+        float(
+            "intensity",
+            "Macro knob calming or hyping the entire visual",
+            Writer::AppConstants,
+            0.0..=1.,
+            |w| &mut w.intensity,
+        ),
+        CVar::new(
+            "hide_stationary_particles",
+            "Hide particles which are not moving",
+            Writer::AppConstants,
+            |w: &mut ConfigWindow| Storage::Flag(&mut w.config.hide_stationary_particles),
+        ),
+        CVar::new(
+            "disable_background",
+            "Ensure a black background in place of the fractal",
+            Writer::AppConstants,
+            |w: &mut ConfigWindow| Storage::Flag(&mut w.config.disable_background),
+        ),
+        boolean("kick_echoes", "Echo each kick as an expanding ring", |w| {
+            &mut w.kick_echoes
+        }),
+        float(
+            "stereo_placement",
+            "How far attractors move toward where their band is heard in the stereo field",
+            Writer::Frame,
+            0.0..=1.,
+            |w| &mut w.stereo_placement,
+        ),
+        boolean(
+            "stereo_attractors",
+            "Drive the mids and high attractors from the left and right channels, each on its own side",
+            |w| &mut w.stereo_attractors,
+        ),
+        float(
+            "bass_attractor_strength",
+            "Multiplier of the force of the big boomer",
+            Writer::Frame,
+            0.0..=MAX_ATTRACTOR_STRENGTH,
+            |w| &mut w.attractor_strengths.bass,
+        ),
+        float(
+            "mids_attractor_strength",
+            "Multiplier of the force of the curl attractors",
+            Writer::Frame,
+            0.0..=MAX_ATTRACTOR_STRENGTH,
+            |w| &mut w.attractor_strengths.mids,
+        ),
+        float(
+            "high_attractor_strength",
+            "Multiplier of the force of the attractors",
+            Writer::Frame,
+            0.0..=MAX_ATTRACTOR_STRENGTH,
+            |w| &mut w.attractor_strengths.high,
+        ),
+        boolean(
+            "temporal_antialiasing",
+            "Temporal anti-aliasing of the fractal",
+            |w| &mut w.temporal_antialiasing,
+        ),
+        boolean(
+            "progressive_refinement",
+            "Supersample the fractal while the scene is still",
+            |w| &mut w.progressive_refinement,
+        ),
+        boolean("particle_repulsion", "Repel neighboring particles", |w| {
+            &mut w.repulsion.enabled
+        }),
+        float(
+            "repulsion_strength",
+            "Strength of the repulsion between neighboring particles",
+            Writer::Frame,
+            0.0..=100.,
+            |w| &mut w.repulsion.strength,
+        ),
+        float(
+            "repulsion_radius",
+            "Distance within which particles repel each other",
+            Writer::Frame,
+            0.001..=MAX_REPULSION_RADIUS,
+            |w| &mut w.repulsion.radius,
+        ),
+        boolean("depth_of_field", "Blur 3D particles by depth", |w| {
+            &mut w.depth_of_field.enabled
+        }),
+        float(
+            "dof_aperture",
+            "Aperture of the depth-of-field blur",
+            Writer::Frame,
+            0.0..=MAX_DOF_APERTURE,
+            |w| &mut w.depth_of_field.aperture,
+        ),
+        float(
+            "dof_focus_distance",
+            "Distance in focus, unless focusing on the big boomer",
+            Writer::Frame,
+            MIN_DOF_FOCUS_DISTANCE..=MAX_DOF_FOCUS_DISTANCE,
+            |w| &mut w.depth_of_field.focus_distance,
+        ),
+        boolean("dof_track_big_boomer", "Focus on the big boomer", |w| {
+            &mut w.depth_of_field.track_big_boomer
+        }),
+        boolean("particle_trails", "Leave fading trails behind the particles", |w| {
+            &mut w.trails.enabled
+        }),
+        float(
+            "trail_length",
+            "Seconds for a trail to fade to about a third of its brightness",
+            Writer::Frame,
+            MIN_TRAIL_LENGTH..=MAX_TRAIL_LENGTH,
+            |w| &mut w.trails.length,
+        ),
+        boolean("bloom", "Glow around the brightest particles", |w| {
+            &mut w.bloom.enabled
+        }),
+        float(
+            "bloom_threshold",
+            "Brightness above which particles glow",
+            Writer::Frame,
+            0.0..=1.,
+            |w| &mut w.bloom.threshold,
+        ),
+        float(
+            "bloom_intensity",
+            "Brightness of the glow around the particles",
+            Writer::Frame,
+            0.0..=MAX_BLOOM_INTENSITY,
+            |w| &mut w.bloom.intensity,
+        ),
+        float(
+            "exposure",
+            "Multiplier of colors before they are mapped to the display",
+            Writer::Frame,
+            MIN_EXPOSURE..=MAX_EXPOSURE,
+            |w| &mut w.tone_mapping.exposure,
+        ),
+        float(
+            "volume_response",
+            "How quickly the volume, and so the animation speed, follows the latest",
+            Writer::Frame,
+            MIN_RESPONSE..=MAX_VOLUME_RESPONSE,
+            |w| &mut w.responsiveness.volume,
+        ),
+        float(
+            "reactive_response",
+            "How quickly the reactive fractal colors follow the latest notes",
+            Writer::Frame,
+            MIN_RESPONSE..=MAX_REACTIVE_RESPONSE,
+            |w| &mut w.responsiveness.reactive,
+        ),
+        float(
+            "smooth_response",
+            "How quickly the smooth fractal colors follow the reactive colors",
+            Writer::Frame,
+            MIN_RESPONSE..=MAX_SMOOTH_RESPONSE,
+            |w| &mut w.responsiveness.smooth,
+        ),
+        boolean(
+            "auto_balance",
+            "Balance the particles against the fractal by the character of the music",
+            |w| &mut w.auto_balance.enabled,
+        ),
+        float(
+            "balance_quiet_ratio",
+            "Volume, relative to the recent loudest, below which a passage is quiet",
+            Writer::Frame,
+            0.05..=1.,
+            |w| &mut w.auto_balance.quiet_ratio,
+        ),
+        float(
+            "balance_ambient_kick_rate",
+            "Kicks per second below which a passage is ambient",
+            Writer::Frame,
+            0.0..=1.25,
+            |w| &mut w.auto_balance.ambient_kick_rate,
+        ),
+        float(
+            "balance_percussive_kick_rate",
+            "Kicks per second above which a passage is percussive",
+            Writer::Frame,
+            0.0..=1.25,
+            |w| &mut w.auto_balance.percussive_kick_rate,
+        ),
+        float(
+            "balance_response",
+            "How quickly the balance transitions between rules",
+            Writer::Frame,
+            MIN_RESPONSE..=MAX_BALANCE_RESPONSE,
+            |w| &mut w.auto_balance.response,
+        ),
+        boolean(
+            "auto_cycle",
+            "Switch to the next color scheme with the music",
+            |w| &mut w.auto_cycle.enabled,
+        ),
+        float(
+            "cycle_energy_ratio",
+            "Recent loudness, relative to the last half-minute, above which the music is high-energy",
+            Writer::Frame,
+            MIN_CYCLE_ENERGY_RATIO..=MAX_CYCLE_ENERGY_RATIO,
+            |w| &mut w.auto_cycle.energy_ratio,
+        ),
+        float(
+            "cycle_energy_seconds",
+            "Seconds of sustained high energy between switches, or zero to not switch on energy",
+            Writer::Frame,
+            0.0..=MAX_CYCLE_ENERGY_SECONDS,
+            |w| &mut w.auto_cycle.energy_seconds,
+        ),
+        boolean("sidechain_pump", "Duck the fractal on each kick", |w| {
+            &mut w.sidechain_pump.enabled
+        }),
+        float(
+            "pump_brightness",
+            "Fraction of the fractal's brightness ducked at the peak",
+            Writer::Frame,
+            0.0..=1.,
+            |w| &mut w.sidechain_pump.brightness,
+        ),
+        float(
+            "pump_orbit",
+            "Fraction of the fractal's orbit distance ducked at the peak",
+            Writer::Frame,
+            0.0..=MAX_PUMP_ORBIT,
+            |w| &mut w.sidechain_pump.orbit,
+        ),
+        float(
+            "pump_attack",
+            "Seconds to reach the peak after a kick",
+            Writer::Frame,
+            0.0..=MAX_PUMP_ATTACK,
+            |w| &mut w.sidechain_pump.attack,
+        ),
+        float(
+            "pump_release",
+            "Seconds to return from the peak",
+            Writer::Frame,
+            0.01..=MAX_PUMP_RELEASE,
+            |w| &mut w.sidechain_pump.release,
+        ),
+        float(
+            "pump_release_curve",
+            "Exponent of the release, where values above one recover quickly at first",
+            Writer::Frame,
+            MIN_PUMP_RELEASE_CURVE..=MAX_PUMP_RELEASE_CURVE,
+            |w| &mut w.sidechain_pump.release_curve,
+        ),
+        float(
+            "crossfade_position",
+            "Position of the fader between the two crossfaded color schemes",
+            Writer::Crossfade,
+            0.0..=1.,
+            |w| &mut w.crossfade_position,
+        ),
+        boolean(
+            "key_hue",
+            "Rotate the hue by the detected musical key",
+            |w| &mut w.key_hue.enabled,
+        ),
+        float(
+            "key_hue_response",
+            "Rate, per second, at which the hue follows a change of key",
+            Writer::Frame,
+            MIN_RESPONSE..=MAX_KEY_HUE_RESPONSE,
+            |w| &mut w.key_hue.response,
+        ),
+    ]);
+
+    band(
+        &mut variables,
+        [
+            "bass_min_frequency",
+            "bass_max_frequency",
+            "bass_min_volume",
+        ],
+        |w| &mut w.analysis.bass,
+    );
+    band(
+        &mut variables,
+        [
+            "mids_min_frequency",
+            "mids_max_frequency",
+            "mids_min_volume",
+        ],
+        |w| &mut w.analysis.mids,
+    );
+    band(
+        &mut variables,
+        [
+            "high_min_frequency",
+            "high_max_frequency",
+            "high_min_volume",
+        ],
+        |w| &mut w.analysis.high,
+    );
+    variables.extend([
+        float(
+            "kick_min_magnitude",
+            "Least magnitude of the loudest bass note which may be a kick",
+            Writer::Analysis,
+            0.0..=10.,
+            |w| &mut w.analysis.kick.min_magnitude,
+        ),
+        float(
+            "kick_history_ratio",
+            "How many times louder than the recent bass a kick must be",
+            Writer::Analysis,
+            0.0..=10.,
+            |w| &mut w.analysis.kick.history_ratio,
+        ),
+        float(
+            "kick_min_interval",
+            "Seconds since the last kick before another may occur",
+            Writer::Analysis,
+            0.0..=5.,
+            |w| &mut w.analysis.kick.min_interval,
+        ),
+    ]);
+
+    Registry::new(variables)
+}
+
+// Define the layout and behavior of the console listing every runtime variable.
+// A variable is read by entering its name, or set by entering its name followed by the new value
+fn create_variables_ui(
+    gui: &mut Gui,
+    console: &mut VariablesConsole,
+    variables: &Registry<ConfigWindow>,
+    config_window: &mut ConfigWindow,
+    engine: &mut Engine,
+) {
+    let ctx = gui.context();
+    egui::Window::new("Variables")
+        .open(&mut console.visible)
+        .resizable(true)
+        .show(&ctx, |ui| {
+            let command = ui.add(
+                egui::TextEdit::singleline(&mut console.command)
+                    .hint_text("NAME [VALUE]")
+                    .desired_width(f32::INFINITY),
+            );
+            if command.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                let (response, writer) = variables.execute(config_window, &console.command);
+                if let Some(writer) = writer {
+                    notify_writer(writer, config_window, engine);
+                }
+                console.response = response;
+                console.command.clear();
+                command.request_focus();
+            }
+            if !console.response.is_empty() {
+                ui.monospace(console.response.as_str());
+            }
+            ui.separator();
+
+            ui.add(
+                egui::TextEdit::singleline(&mut console.filter)
+                    .hint_text("Filter")
+                    .desired_width(f32::INFINITY),
+            );
+            ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("variables_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for variable in variables.filter(&console.filter) {
+                            ui.monospace(variable.name)
+                                .on_hover_text(variable.description);
+                            let changed = match variable.storage(config_window) {
+                                Storage::Float(value, range) => {
+                                    ui.add(Slider::new(value, range)).changed()
+                                }
+                                Storage::Bool(value) => ui.checkbox(value, "").changed(),
+                                Storage::Flag(value) => {
+                                    let mut enabled = *value != 0;
+                                    let changed = ui.checkbox(&mut enabled, "").changed();
+                                    *value = u32::from(enabled);
+                                    changed
+                                }
+                            };
+                            if changed {
+                                notify_writer(variable.writer, config_window, engine);
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        });
+}
+
+// Add a grid describing the audio source, its stream, and how quickly its audio is analyzed.
+fn add_audio_device_info(
+    ui: &mut Ui,
+    description: &str,
+    stream: Option<&StreamInfo>,
+    beat: Option<BeatInfo>,
+    metrics: Metrics,
+) {
+    egui::Grid::new("audio_device_grid").show(ui, |ui| {
+        let mut rows = vec![("Source", description.to_owned())];
+        if let Some(stream) = stream {
+            let frames = |count: usize| {
+                if count == 0 {
+                    String::from("unavailable")
+                } else {
+                    format!("{count} frames")
+                }
+            };
+            rows.extend([
+                ("Device", stream.device_name.clone()),
+                ("Sample rate", format!("{} Hz", stream.sample_rate)),
+                ("Channels", stream.channel_count.to_string()),
+                ("Capture chunk size", frames(metrics.audio_chunk_frames)),
+                ("Analysis chunk size", frames(metrics.audio_analysis_size)),
+            ]);
+        }
+        rows.extend([
+            (
+                "Analysis updates per second",
+                format!("{:.1}", metrics.audio_update_hz),
+            ),
+            (
+                "Dropped audio chunks",
+                metrics.dropped_audio_chunks.to_string(),
+            ),
+            (
+                "Tempo",
+                beat.map_or_else(
+                    || String::from("unknown"),
+                    |beat| {
+                        format!(
+                            "{:.0} BPM ({:.0}% confidence)",
+                            beat.bpm,
+                            100. * beat.confidence
+                        )
+                    },
+                ),
+            ),
+        ]);
+        for (name, value) in rows {
+            ui.label(name);
+            ui.label(value);
+            ui.end_row();
+        }
+    });
+}
+
+// Helper to edit the tunables of the selected fractal, applying them as they change.
+fn add_fractal_tunables(
+    ui: &mut Ui,
+    engine: &mut Engine,
+    fractal_tunables: &mut [Tunables; FRACTAL_COUNT],
+) {
+    let id = engine.fractal_id();
+    let (Some(fractal), Some(tunables)) = (
+        fractals::fractal_info(id),
+        fractal_tunables.get_mut(id as usize),
+    ) else {
+        return;
+    };
+    if id == 0 {
+        ui.label("The empty fractal has no parameters.");
+        return;
+    }
+
+    ui.label(format!("Selected fractal: {}", fractal.name));
+    let previous = *tunables;
+    ui.add(Slider::new(&mut tunables.iterations, 1..=fractals::MAX_ITERATIONS).text("iterations"));
+    ui.add_enabled(
+        fractal.bailout,
+        Slider::new(&mut tunables.bailout, 0.5..=fractals::MAX_BAILOUT)
+            .logarithmic(true)
+            .text("bailout radius"),
+    )
+    .on_disabled_hover_text("This fractal does not escape by radius");
+    ui.add_enabled(
+        fractal.fold_scale,
+        Slider::new(&mut tunables.fold_scale, 1.0..=fractals::MAX_FOLD_SCALE).text("fold scale"),
+    )
+    .on_disabled_hover_text("This fractal does not fold space by a scale");
+    ui.add(
+        Slider::new(
+            &mut tunables.color_exponent,
+            0.0..=fractals::MAX_COLOR_EXPONENT,
+        )
+        .text("coloring exponent"),
+    )
+    .on_hover_text("How sharply the surface darkens with the steps taken to reach it");
+    if ui
+        .button("Built-in values")
+        .on_hover_text("Restore the values this fractal was designed with.")
+        .clicked()
+    {
+        *tunables = fractal.defaults;
+    }
+    if *tunables != previous {
+        engine.set_fractal_tunables(*tunables);
+    }
+}
+
+// Helper to manage the additional particle systems, whose particle counts are fixed at launch.
+fn add_particle_systems(
+    ui: &mut Ui,
+    engine: &mut Engine,
+    particle_systems: &mut [ParticleSystem],
+    color_scheme_names: &[String],
+    color_schemes: &[Scheme],
+) {
+    if particle_systems.is_empty() {
+        ui.label("No additional particle systems are defined in the config file.");
+        return;
+    }
+
+    for (i, system) in particle_systems.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.separator();
+            ui.label(format!(
+                "{}: {} particles",
+                system.name, system.particle_count
+            ));
+            let mut shown = !system.hidden;
+            if ui.checkbox(&mut shown, "Shown").changed() {
+                system.hidden = !shown;
+            }
+
+            let mut scheme_index = system.scheme_index(color_scheme_names);
+            let previous_scheme_index = scheme_index;
+            ComboBox::from_label("color scheme")
+                .selected_text(color_scheme_names[scheme_index].clone())
+                .show_ui(ui, |ui| {
+                    for (j, name) in color_scheme_names.iter().enumerate() {
+                        ui.selectable_value(&mut scheme_index, j, name.clone());
+                    }
+                });
+            if scheme_index != previous_scheme_index {
+                system.color_scheme = Some(color_scheme_names[scheme_index].clone());
+                engine.update_particle_system_scheme(i, color_schemes[scheme_index]);
+            }
+
+            ui.checkbox(&mut system.third_dimension, "3D projection");
+            ui.checkbox(&mut system.jello, "'Jello' spring tension");
+            ComboBox::from_label("physics")
+                .selected_text(system.physics_mode.name())
+                .show_ui(ui, |ui| {
+                    for mode in PhysicsMode::ALL {
+                        ui.selectable_value(&mut system.physics_mode, mode, mode.name());
+                    }
+                });
+        });
+    }
+}
+
+// Helper to graph the times of the recent frames, scaled to the slowest of them.
+#[allow(clippy::cast_precision_loss)]
+fn add_frame_time_graph(ui: &mut Ui, frame_times: &FrameHistory) {
+    const GRAPH_HEIGHT: f32 = 60.;
+    let max_ms = frame_times.max().max(1.);
+    ui.label(format!("Recent frame times, up to {max_ms:.1} ms"));
+
+    let (response, painter) = ui.allocate_painter(
+        egui::vec2(ui.available_width(), GRAPH_HEIGHT),
+        egui::Sense::hover(),
+    );
+    let rect = response.rect;
+    painter.rect_filled(rect, 0., ui.visuals().extreme_bg_color);
+    let last = frame_times.times().len().saturating_sub(1).max(1) as f32;
+    let points = frame_times
+        .times()
+        .enumerate()
+        .map(|(i, ms)| {
+            egui::pos2(
+                rect.left() + rect.width() * i as f32 / last,
+                rect.bottom() - rect.height() * ms / max_ms,
+            )
+        })
+        .collect();
+    painter.add(egui::Shape::line(
+        points,
+        egui::Stroke::new(1., ui.visuals().text_color()),
+    ));
+}
+
 // Define the layout and behavior of the config UI.
 fn create_config_ui(
     gui: &mut Gui,
     config_window: &mut ConfigWindow,
     engine: &mut Engine,
-    color_scheme_names: &[String],
-    color_schemes: &mut [Scheme],
+    color_scheme_names: &mut Vec<String>,
+    color_schemes: &mut Vec<Scheme>,
     displayed_scheme_index: &mut usize,
+    coloring_mode: &mut ColoringMode,
+    physics_mode: &mut PhysicsMode,
+    particle_containment: &mut ParticleContainment,
+    color_management: &mut ColorManagement,
+    metrics: Metrics,
+    frame_times: &FrameHistory,
 ) {
     let ctx = gui.context();
-    egui::Window::new("App Config")
+    let response = egui::Window::new("App Config")
         .open(&mut config_window.visible)
         .resizable(true)
         .show(&ctx, |ui| {
-            let mut data_changed = false;
+            // Quickly switch the overlay between themes, such as for readability on a projector.
+            if add_theme_toggle(ui, &mut config_window.theme) {
+                apply_theme(&ctx, config_window.theme);
+            }
+            ui.separator();
+
+            // The macro knob scaling several parameters at once.
+            let mut data_changed = ui
+                .add(Slider::new(&mut config_window.intensity, 0.0..=1.).text("intensity"))
+                .on_hover_text(
+                    "Calm or hype the entire visual, scaling the audio, attractors, kaleidoscope, and \
+                    camera spin together. Also ALT + scroll.",
+                )
+                .changed();
+            ui.separator();
+
             ComboBox::from_label("Selected Color Scheme")
                 .selected_text(color_scheme_names[config_window.edit_scheme_index].clone())
                 .show_ui(ui, |ui| {
@@ -164,6 +1408,14 @@ fn create_config_ui(
                         ui.selectable_value(&mut config_window.edit_scheme_index, i, name.clone());
                     }
                 });
+            add_scheme_list_controls(
+                ui,
+                config_window,
+                engine,
+                color_scheme_names,
+                color_schemes,
+                displayed_scheme_index,
+            );
             add_color_scheme(
                 ui,
                 &mut config_window.color_schemes[config_window.edit_scheme_index],
@@ -173,36 +1425,7 @@ fn create_config_ui(
                 engine,
             );
             ui.separator();
-            data_changed |= ui
-                .add(
-                    Slider::new(&mut config_window.config.audio_scale, -30.0..=5.)
-                        .text("audio scale (dB)"),
-                )
-                .changed();
-            data_changed |= ui
-                .add(Slider::new(&mut config_window.config.max_speed, 0.0..=10.).text("max speed"))
-                .changed();
-            data_changed |= ui
-                .add(Slider::new(&mut config_window.config.point_size, 0.0..=8.).text("point size"))
-                .changed();
-            data_changed |= ui
-                .add(
-                    Slider::new(&mut config_window.config.friction_scale, 0.0..=5.)
-                        .text("friction scale"),
-                )
-                .changed();
-            data_changed |= ui
-                .add(
-                    Slider::new(&mut config_window.config.spring_coefficient, 0.0..=200.)
-                        .text("spring coefficient"),
-                )
-                .changed();
-            data_changed |= ui
-                .add(
-                    Slider::new(&mut config_window.config.vertical_fov, 30.0..=105.)
-                        .text("vertical fov"),
-                )
-                .changed();
+            data_changed |= add_parameter_sliders(ui, &mut config_window.config);
 
             // Checkbox to toggle the hiding of stationary particles.
             let mut hide_stationary_particles = config_window.config.hide_stationary_particles > 0;
@@ -215,6 +1438,116 @@ fn create_config_ui(
                     u32::from(hide_stationary_particles);
             }
 
+            // Allow selecting the global force acting on the particles alongside the attractors.
+            ComboBox::from_label("Particle physics")
+                .selected_text(physics_mode.name())
+                .show_ui(ui, |ui| {
+                    for mode in PhysicsMode::ALL {
+                        ui.selectable_value(physics_mode, mode, mode.name());
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "The force acting on every particle alongside the audio-driven attractors. \
+                    Also the N key.",
+                );
+
+            // Allow selecting how particles are kept within the unit square or cube.
+            let previous_containment = *particle_containment;
+            ComboBox::from_label("Particle containment")
+                .selected_text(particle_containment.name())
+                .show_ui(ui, |ui| {
+                    for containment in ParticleContainment::ALL {
+                        ui.selectable_value(particle_containment, containment, containment.name());
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "Whether particles reaching the edges fly freely beyond them, bounce back inside, \
+                    or wrap around to the opposite edge.",
+                );
+            if *particle_containment != previous_containment {
+                engine.set_particle_containment(*particle_containment);
+            }
+
+            // Checkbox to toggle the rings expanding from each kick.
+            ui.checkbox(&mut config_window.kick_echoes, "Echo kicks as rings");
+
+            // Allow selecting the particles' antialiasing, which takes effect once saved and relaunched.
+            ComboBox::from_label("MSAA samples")
+                .selected_text(config_window.msaa_samples.to_string())
+                .show_ui(ui, |ui| {
+                    for samples in MSAA_SAMPLE_CHOICES {
+                        ui.selectable_value(
+                            &mut config_window.msaa_samples,
+                            samples,
+                            samples.to_string(),
+                        );
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "Samples per pixel when rendering particles. Applied at the next launch once \
+                    saved, falling back to fewer samples if the device does not support them.",
+                );
+
+            // Allow selecting how frames are presented, such as for lower latency on high-refresh displays.
+            let previous_present_mode = config_window.present_mode;
+            ComboBox::from_label("Presentation")
+                .selected_text(config_window.present_mode.name())
+                .show_ui(ui, |ui| {
+                    for mode in PresentationMode::ALL {
+                        ui.selectable_value(&mut config_window.present_mode, mode, mode.name());
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "Vsync waits for each refresh of the display. Mailbox shows the newest frame at each \
+                    refresh for lower latency, and Immediate shows frames as soon as they are rendered, \
+                    which may tear. Unsupported modes fall back to Vsync.",
+                );
+            if config_window.present_mode != previous_present_mode {
+                config_window.requested_present_mode = Some(config_window.present_mode);
+            }
+
+            // Allow selecting how the fractal surface is colored.
+            let previous_coloring_mode = *coloring_mode;
+            ComboBox::from_label("Fractal coloring")
+                .selected_text(coloring_mode.name())
+                .show_ui(ui, |ui| {
+                    for mode in ColoringMode::ALL {
+                        ui.selectable_value(coloring_mode, mode, mode.name());
+                    }
+                });
+            if *coloring_mode != previous_coloring_mode {
+                engine
+                    .runtime_constants_mut()
+                    .write()
+                    .unwrap()
+                    .coloring_mode = *coloring_mode as u32;
+            }
+
+            // Allow selecting how colors are written, which only matters on surfaces encoding to sRGB.
+            let previous_color_management = *color_management;
+            ui.add_enabled_ui(engine.srgb_surface(), |ui| {
+                ComboBox::from_label("Color management")
+                    .selected_text(color_management.name())
+                    .show_ui(ui, |ui| {
+                        for mode in ColorManagement::ALL {
+                            ui.selectable_value(color_management, mode, mode.name());
+                        }
+                    });
+            })
+            .response
+            .on_disabled_hover_text("The display surface does not encode to sRGB");
+            if *color_management != previous_color_management {
+                engine
+                    .runtime_constants_mut()
+                    .write()
+                    .unwrap()
+                    .unmanaged_split = color_management.unmanaged_split();
+            }
+
             // Allow a checkbox to toggle disabling the background.
             let mut disable_background = config_window.config.disable_background > 0;
             if ui
@@ -225,6 +1558,481 @@ fn create_config_ui(
                 config_window.config.disable_background = u32::from(disable_background);
             }
 
+            // Checkbox to toggle temporal anti-aliasing of the fractal.
+            ui.checkbox(
+                &mut config_window.temporal_antialiasing,
+                "Temporal anti-aliasing",
+            );
+
+            // Checkbox to toggle supersampling of the fractal while the scene is still.
+            ui.checkbox(
+                &mut config_window.progressive_refinement,
+                "Progressive refinement when still",
+            );
+
+            // Allow hand-tuning the selected fractal.
+            ui.collapsing("Fractal Parameters", |ui| {
+                add_fractal_tunables(ui, engine, &mut config_window.fractal_tunables);
+            });
+
+            // Allow shaping the kaleidoscope's mirrors, which apply while it is toggled on.
+            ui.collapsing("Kaleidoscope", |ui| {
+                let kaleidoscope = &mut config_window.kaleidoscope;
+                ui.add(
+                    Slider::new(
+                        &mut kaleidoscope.segments,
+                        MIN_KALEIDOSCOPE_SEGMENTS..=MAX_KALEIDOSCOPE_SEGMENTS,
+                    )
+                    .text("segments"),
+                );
+                ui.add(
+                    Slider::new(
+                        &mut kaleidoscope.spin,
+                        -MAX_KALEIDOSCOPE_SPIN..=MAX_KALEIDOSCOPE_SPIN,
+                    )
+                    .text("spin"),
+                )
+                .on_hover_text(
+                    "Radians per second the folded view turns at, faster with the volume.",
+                );
+                ui.add(
+                    Slider::new(&mut kaleidoscope.mirror_axis, 0.0..=360.)
+                        .suffix("°")
+                        .text("mirror axis"),
+                )
+                .on_hover_text(
+                    "Degrees from the right of the view to the center of the first segment.",
+                );
+            });
+
+            // Allow enabling and tuning the particle-particle repulsion pass.
+            ui.collapsing("Particle Repulsion", |ui| {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    "Warning: repulsion significantly increases the GPU cost of each frame.",
+                );
+                ui.checkbox(
+                    &mut config_window.repulsion.enabled,
+                    "Repel neighboring particles",
+                );
+                ui.add(
+                    Slider::new(&mut config_window.repulsion.strength, 0.0..=100.)
+                        .text("repulsion strength"),
+                );
+                ui.add(
+                    Slider::new(
+                        &mut config_window.repulsion.radius,
+                        0.001..=MAX_REPULSION_RADIUS,
+                    )
+                    .logarithmic(true)
+                    .text("repulsion radius"),
+                )
+                .on_hover_text("Larger radii are slower and consider fewer of the neighbors.");
+            });
+
+            // Allow enabling and focusing the depth-of-field blur on 3D particles.
+            ui.collapsing("Depth of Field", |ui| {
+                let depth_of_field = &mut config_window.depth_of_field;
+                ui.checkbox(&mut depth_of_field.enabled, "Blur 3D particles by depth");
+                ui.add(
+                    Slider::new(&mut depth_of_field.aperture, 0.0..=MAX_DOF_APERTURE)
+                        .text("aperture"),
+                );
+                ui.checkbox(
+                    &mut depth_of_field.track_big_boomer,
+                    "Focus on the big boomer",
+                );
+                ui.add_enabled(
+                    !depth_of_field.track_big_boomer,
+                    Slider::new(
+                        &mut depth_of_field.focus_distance,
+                        MIN_DOF_FOCUS_DISTANCE..=MAX_DOF_FOCUS_DISTANCE,
+                    )
+                    .text("focus distance"),
+                );
+            });
+
+            // Allow enabling and lengthening the trails particles leave behind.
+            ui.collapsing("Particle Trails", |ui| {
+                ui.checkbox(
+                    &mut config_window.trails.enabled,
+                    "Leave fading trails behind the particles",
+                );
+                ui.add(
+                    Slider::new(
+                        &mut config_window.trails.length,
+                        MIN_TRAIL_LENGTH..=MAX_TRAIL_LENGTH,
+                    )
+                    .logarithmic(true)
+                    .text("trail length"),
+                )
+                .on_hover_text("Seconds for a trail to fade to about a third of its brightness.");
+            });
+
+            // Allow showing and restyling the additional particle systems.
+            ui.collapsing("Particle Systems", |ui| {
+                add_particle_systems(
+                    ui,
+                    engine,
+                    &mut config_window.particle_systems,
+                    color_scheme_names,
+                    color_schemes,
+                );
+            });
+
+            // Allow enabling and tuning the glow around the brightest particles.
+            ui.collapsing("Bloom", |ui| {
+                let bloom = &mut config_window.bloom;
+                ui.checkbox(&mut bloom.enabled, "Glow around the brightest particles");
+                ui.add(Slider::new(&mut bloom.threshold, 0.0..=1.).text("threshold"));
+                ui.add(
+                    Slider::new(&mut bloom.intensity, 0.0..=MAX_BLOOM_INTENSITY).text("intensity"),
+                );
+            });
+
+            // Allow selecting how the rendered colors are mapped to the display.
+            ui.collapsing("Tone Mapping", |ui| {
+                let tone_mapping = &mut config_window.tone_mapping;
+                ComboBox::from_label("operator")
+                    .selected_text(tone_mapping.operator.name())
+                    .show_ui(ui, |ui| {
+                        for operator in ToneMapOperator::ALL {
+                            ui.selectable_value(
+                                &mut tone_mapping.operator,
+                                operator,
+                                operator.name(),
+                            );
+                        }
+                    });
+                ui.add(
+                    Slider::new(&mut tone_mapping.exposure, MIN_EXPOSURE..=MAX_EXPOSURE)
+                        .logarithmic(true)
+                        .text("exposure"),
+                );
+            });
+
+            // Allow tuning how quickly the visuals follow the audio.
+            ui.collapsing("Responsiveness", |ui| {
+                let responsiveness = &mut config_window.responsiveness;
+                let sliders = [
+                    (
+                        &mut responsiveness.volume,
+                        MAX_VOLUME_RESPONSE,
+                        "volume response",
+                    ),
+                    (
+                        &mut responsiveness.reactive,
+                        MAX_REACTIVE_RESPONSE,
+                        "reactive response",
+                    ),
+                    (
+                        &mut responsiveness.smooth,
+                        MAX_SMOOTH_RESPONSE,
+                        "smooth response",
+                    ),
+                ];
+                for (value, max, text) in sliders {
+                    ui.add(
+                        Slider::new(value, MIN_RESPONSE..=max)
+                            .logarithmic(true)
+                            .text(text),
+                    );
+                }
+                if ui
+                    .button("Reset to defaults")
+                    .on_hover_text("Restore the default rates at which the visuals follow audio.")
+                    .clicked()
+                {
+                    *responsiveness = Responsiveness::default();
+                }
+            });
+
+            // Allow balancing the particles against the fractal by the character of the music.
+            ui.collapsing("Automatic Balance", |ui| {
+                let auto_balance = &mut config_window.auto_balance;
+                ui.checkbox(&mut auto_balance.enabled, "Balance automatically")
+                    .on_hover_text(
+                        "Fade particles out during quiet ambient passages, \
+                        and bring them forward during percussive ones.",
+                    );
+                ui.add_enabled_ui(auto_balance.enabled, |ui| {
+                    ui.add(
+                        Slider::new(&mut auto_balance.quiet_ratio, 0.05..=1.).text("quiet ratio"),
+                    );
+                    ui.add(
+                        Slider::new(&mut auto_balance.ambient_kick_rate, 0.0..=1.25)
+                            .text("ambient kicks per second"),
+                    );
+                    ui.add(
+                        Slider::new(&mut auto_balance.percussive_kick_rate, 0.0..=1.25)
+                            .text("percussive kicks per second"),
+                    );
+                    ui.add(
+                        Slider::new(
+                            &mut auto_balance.response,
+                            MIN_RESPONSE..=MAX_BALANCE_RESPONSE,
+                        )
+                        .logarithmic(true)
+                        .text("transition response"),
+                    );
+                });
+                // Keep the rules distinguishable, as when parsing the config.
+                auto_balance.percussive_kick_rate = auto_balance
+                    .percussive_kick_rate
+                    .max(auto_balance.ambient_kick_rate + 0.01);
+            });
+
+            // Allow ducking the fractal on each kick, like a sidechain compressor.
+            ui.collapsing("Sidechain Pump", |ui| {
+                let pump = &mut config_window.sidechain_pump;
+                ui.checkbox(&mut pump.enabled, "Pump on kicks")
+                    .on_hover_text(
+                    "Momentarily duck the fractal's brightness and orbit distance on each kick, \
+                    then release them.",
+                );
+                ui.add_enabled_ui(pump.enabled, |ui| {
+                    ui.add(Slider::new(&mut pump.brightness, 0.0..=1.).text("brightness amount"));
+                    ui.add(Slider::new(&mut pump.orbit, 0.0..=MAX_PUMP_ORBIT).text("orbit amount"));
+                    ui.add(Slider::new(&mut pump.attack, 0.0..=MAX_PUMP_ATTACK).text("attack (s)"));
+                    ui.add(
+                        Slider::new(&mut pump.release, 0.01..=MAX_PUMP_RELEASE).text("release (s)"),
+                    );
+                    ui.add(
+                        Slider::new(
+                            &mut pump.release_curve,
+                            MIN_PUMP_RELEASE_CURVE..=MAX_PUMP_RELEASE_CURVE,
+                        )
+                        .logarithmic(true)
+                        .text("release curve"),
+                    );
+                });
+            });
+
+            // Allow riding a fader between two color schemes instead of switching between them.
+            ui.collapsing("Scheme Crossfade", |ui| {
+                let crossfade = &mut config_window.scheme_crossfade;
+                let previous = *crossfade;
+                for (label, index) in [
+                    ("Scheme A", &mut crossfade.scheme_a),
+                    ("Scheme B", &mut crossfade.scheme_b),
+                ] {
+                    ComboBox::from_label(label)
+                        .selected_text(color_scheme_names[*index].clone())
+                        .show_ui(ui, |ui| {
+                            for (i, name) in color_scheme_names.iter().enumerate() {
+                                ui.selectable_value(index, i, name.clone());
+                            }
+                        });
+                }
+                let fader = ui
+                    .add(
+                        Slider::new(&mut config_window.crossfade_position, 0.0..=1.)
+                            .text("position"),
+                    )
+                    .on_hover_text(
+                        "Blend between the two schemes. Switching schemes with Tab leaves the \
+                        crossfade until the fader is moved again.",
+                    );
+                if fader.changed() || *crossfade != previous {
+                    config_window.requested_crossfade = true;
+                }
+            });
+
+            // Allow the color schemes to switch themselves with the music.
+            ui.collapsing("Automatic Scheme Cycling", |ui| {
+                let auto_cycle = &mut config_window.auto_cycle;
+                ui.checkbox(&mut auto_cycle.enabled, "Cycle schemes automatically")
+                    .on_hover_text(
+                        "Switch to the next color scheme after a number of kicks, \
+                        or after a sustained high-energy passage. Either is disabled at zero.",
+                    );
+                ui.add_enabled_ui(auto_cycle.enabled, |ui| {
+                    ui.add(
+                        Slider::new(&mut auto_cycle.kicks, 0..=MAX_CYCLE_KICKS)
+                            .text("kicks per switch"),
+                    );
+                    ui.add(
+                        Slider::new(&mut auto_cycle.energy_seconds, 0.0..=MAX_CYCLE_ENERGY_SECONDS)
+                            .text("high-energy seconds per switch"),
+                    );
+                    ui.add(
+                        Slider::new(
+                            &mut auto_cycle.energy_ratio,
+                            MIN_CYCLE_ENERGY_RATIO..=MAX_CYCLE_ENERGY_RATIO,
+                        )
+                        .text("high-energy ratio"),
+                    );
+                });
+            });
+
+            // Allow tinting the visuals by the musical key of the audio.
+            ui.collapsing("Musical Key Tint", |ui| {
+                let key_hue = &mut config_window.key_hue;
+                ui.checkbox(&mut key_hue.enabled, "Rotate hue by detected key");
+                ui.add_enabled(
+                    key_hue.enabled,
+                    Slider::new(&mut key_hue.response, MIN_RESPONSE..=MAX_KEY_HUE_RESPONSE)
+                        .logarithmic(true)
+                        .text("hue response"),
+                );
+            });
+
+            // Allow selecting where audio is captured from.
+            ui.separator();
+            let previous_source = config_window.audio_source;
+            ComboBox::from_label("Audio source")
+                .selected_text(config_window.audio_source.name())
+                .show_ui(ui, |ui| {
+                    for kind in SourceKind::ALL {
+                        ui.selectable_value(&mut config_window.audio_source, kind, kind.name());
+                    }
+                });
+            if config_window.audio_source != previous_source {
+                config_window.requested_audio_source = Some(config_window.audio_source);
+            }
+
+            // Allow choosing the device captured, such as a particular microphone in place of the default.
+            let previous_device = config_window.audio_device.clone();
+            ui.horizontal(|ui| {
+                ComboBox::from_label("Audio device")
+                    .selected_text(
+                        config_window
+                            .audio_device
+                            .as_ref()
+                            .map_or_else(|| String::from("Default device"), ToString::to_string),
+                    )
+                    .show_ui(ui, |ui| {
+                        for device in &config_window.audio_devices {
+                            ui.selectable_value(
+                                &mut config_window.audio_device,
+                                Some(device.clone()),
+                                device.to_string(),
+                            );
+                        }
+                    });
+                if ui
+                    .button("Refresh")
+                    .on_hover_text("List the audio devices again, such as after connecting one.")
+                    .clicked()
+                {
+                    config_window.audio_devices = list_devices();
+                }
+            });
+            if config_window.audio_device != previous_device {
+                config_window.requested_audio_device = true;
+                config_window.audio_source = match &config_window.audio_device {
+                    Some(device) if device.is_input() => SourceKind::Microphone,
+                    _ => SourceKind::Loopback,
+                };
+            }
+            ui.add(
+                Slider::new(&mut config_window.stereo_placement, 0.0..=1.).text("stereo placement"),
+            )
+            .on_hover_text(
+                "Move each attractor toward where its band is heard in the stereo field. \
+                Elevation follows how out of phase the channels are.",
+            );
+            ui.checkbox(&mut config_window.stereo_attractors, "stereo attractors")
+                .on_hover_text(
+                    "Drive the first mids and high attractors from the left channel and the second from the right, \
+                    each mirrored to its own side, so that panning shifts the forces left and right.",
+                );
+
+            // Allow scaling the force of each band's attractors.
+            ui.collapsing("Attractor Strength", |ui| {
+                let strengths = &mut config_window.attractor_strengths;
+                for (strength, name) in [
+                    (&mut strengths.bass, "big boomer"),
+                    (&mut strengths.mids, "curl attractors"),
+                    (&mut strengths.high, "attractors"),
+                ] {
+                    ui.add(Slider::new(strength, 0.0..=MAX_ATTRACTOR_STRENGTH).text(name));
+                }
+            });
+
+            // Display what the audio source is capturing, for diagnosing audio which the visuals do not react to.
+            ui.collapsing("Audio Device", |ui| {
+                add_audio_device_info(
+                    ui,
+                    &config_window.audio_description,
+                    config_window.audio_stream.as_ref(),
+                    config_window.audio_beat,
+                    metrics,
+                );
+            });
+
+            // Allow tuning how incoming audio is analyzed.
+            ui.collapsing("Audio Analysis", |ui| {
+                // Switching presets keeps the attractor counts, which are configured separately.
+                let previous_preset = config_window.analysis_preset;
+                ComboBox::from_label("preset")
+                    .selected_text(
+                        config_window.analysis_presets[config_window.analysis_preset]
+                            .name
+                            .as_str(),
+                    )
+                    .show_ui(ui, |ui| {
+                        for (i, preset) in config_window.analysis_presets.iter().enumerate() {
+                            ui.selectable_value(
+                                &mut config_window.analysis_preset,
+                                i,
+                                preset.name.as_str(),
+                            );
+                        }
+                    });
+                let mut changed = config_window.analysis_preset != previous_preset;
+                if changed {
+                    config_window.analysis = config_window.analysis_presets
+                        [config_window.analysis_preset]
+                        .analysis
+                        .with_attractor_counts(config_window.analysis.attractor_counts())
+                        .with_gain_control(config_window.analysis.gain);
+                }
+
+                changed |= add_audio_analysis(ui, &mut config_window.analysis);
+                if changed {
+                    publish_analysis_config(&config_window.shared_analysis, config_window.analysis);
+                }
+            });
+
+            // Display the latest health metrics of the application.
+            ui.collapsing("Statistics", |ui| {
+                egui::Grid::new("statistics_grid").show(ui, |ui| {
+                    let gpu_ms = metrics
+                        .gpu_ms
+                        .map_or_else(|| String::from("unavailable"), |ms| format!("{ms:.2} ms"));
+                    let rows = [
+                        ("Frames per second", format!("{:.1}", metrics.fps)),
+                        ("CPU update time", format!("{:.2} ms", metrics.cpu_ms)),
+                        ("GPU frame time", gpu_ms),
+                        ("Particle count", metrics.particle_count.to_string()),
+                        (
+                            "Audio updates per second",
+                            format!("{:.1}", metrics.audio_update_hz),
+                        ),
+                        (
+                            "Dropped audio chunks",
+                            metrics.dropped_audio_chunks.to_string(),
+                        ),
+                    ];
+                    for (name, value) in rows {
+                        ui.label(name);
+                        ui.label(value);
+                        ui.end_row();
+                    }
+
+                    // Break the GPU time down by pass, where timestamps are supported.
+                    if let Some(gpu_pass_ms) = metrics.gpu_pass_ms {
+                        for (name, ms) in GPU_PASS_NAMES.iter().zip(gpu_pass_ms) {
+                            ui.label(format!("    {name}"));
+                            ui.label(format!("{ms:.2} ms"));
+                            ui.end_row();
+                        }
+                    }
+                });
+                add_frame_time_graph(ui, frame_times);
+            });
+
             // Separate between the `Reset` button and setting configuration values.
             ui.separator();
 
@@ -236,82 +2044,148 @@ fn create_config_ui(
                     .clicked()
                 {
                     config_window.config = config_window.init_config;
-                    config_window
+                    for (scheme, init) in config_window
                         .color_schemes
-                        .copy_from_slice(&config_window.init_color_schemes);
+                        .iter_mut()
+                        .zip(&config_window.init_color_schemes)
+                    {
+                        if let Some(init) = init {
+                            *scheme = *init;
+                        }
+                    }
 
-                    update_app_constants(engine, config_window.config);
+                    update_app_constants(
+                        engine,
+                        config_window.config,
+                        config_window
+                            .intensity_curves
+                            .multipliers(config_window.intensity),
+                    );
 
-                    let new_colors: Vec<_> = config_window
-                        .color_schemes
-                        .iter()
-                        .map(|cs| (*cs).into())
-                        .collect();
-                    color_schemes.copy_from_slice(&new_colors);
+                    for (scheme, cs) in color_schemes.iter_mut().zip(&config_window.color_schemes) {
+                        *scheme = (*cs).into();
+                    }
                     engine.update_color_scheme(color_schemes[*displayed_scheme_index]);
+
+                    config_window.repulsion = config_window.init_repulsion;
+                    config_window.depth_of_field = config_window.init_depth_of_field;
+                    config_window.trails = config_window.init_trails;
+                    config_window.kaleidoscope = config_window.init_kaleidoscope;
+                    config_window
+                        .particle_systems
+                        .clone_from(&config_window.init_particle_systems);
+                    for (i, system) in config_window.particle_systems.iter().enumerate() {
+                        engine.update_particle_system_scheme(
+                            i,
+                            color_schemes[system.scheme_index(color_scheme_names)],
+                        );
+                    }
+                    config_window.bloom = config_window.init_bloom;
+                    config_window.tone_mapping = config_window.init_tone_mapping;
+                    config_window.temporal_antialiasing = config_window.init_temporal_antialiasing;
+                    config_window.progressive_refinement =
+                        config_window.init_progressive_refinement;
+                    config_window.kick_echoes = config_window.init_kick_echoes;
+                    config_window.msaa_samples = config_window.init_msaa_samples;
+                    config_window.fractal_tunables = config_window.init_fractal_tunables;
+                    if let Some(tunables) = config_window
+                        .fractal_tunables
+                        .get(engine.fractal_id() as usize)
+                    {
+                        engine.set_fractal_tunables(*tunables);
+                    }
+                    if config_window.present_mode != config_window.init_present_mode {
+                        config_window.present_mode = config_window.init_present_mode;
+                        config_window.requested_present_mode = Some(config_window.present_mode);
+                    }
+                    config_window.stereo_placement = config_window.init_stereo_placement;
+                    config_window.stereo_attractors = config_window.init_stereo_attractors;
+                    config_window.attractor_strengths = config_window.init_attractor_strengths;
+                    config_window.intensity = config_window.init_intensity;
+                    config_window.responsiveness = config_window.init_responsiveness;
+                    config_window.key_hue = config_window.init_key_hue;
+                    config_window.auto_balance = config_window.init_auto_balance;
+                    config_window.auto_cycle = config_window.init_auto_cycle;
+                    config_window.sidechain_pump = config_window.init_sidechain_pump;
+                    config_window.scheme_crossfade = config_window.init_scheme_crossfade;
+                    config_window.theme = config_window.init_theme;
+                    apply_theme(&ctx, config_window.theme);
+
+                    config_window.analysis = config_window.init_analysis;
+                    config_window.analysis_preset = config_window.init_analysis_preset;
+                    publish_analysis_config(&config_window.shared_analysis, config_window.analysis);
+                }
+
+                // Allow user to keep the applied values for the next launch.
+                if ui
+                    .button("Save")
+                    .on_hover_text(
+                        "Write the applied configuration, including the color schemes, to the config file. \
+                        Comments in the file are preserved. Also CTRL+S.",
+                    )
+                    .clicked()
+                {
+                    config_window.requested_save = true;
                 }
             });
 
             if data_changed {
-                update_app_constants(engine, config_window.config);
+                update_app_constants(
+                    engine,
+                    config_window.config,
+                    config_window
+                        .intensity_curves
+                        .multipliers(config_window.intensity),
+                );
             }
         });
+    config_window.rect = response.map(|response| response.response.rect);
 }
 
-enum HelpWindowEntry {
-    Title(&'static str),
-    Item(&'static str, &'static str),
-    Empty(),
+// Define the layout of the notice of a crash during the previous run, returning whether it was dismissed.
+fn create_crash_report_ui(gui: &mut Gui, path: &std::path::Path) -> bool {
+    let ctx = gui.context();
+    let mut dismissed = false;
+    egui::Window::new("Previous Crash")
+        .collapsible(false)
+        .resizable(false)
+        .show(&ctx, |ui| {
+            ui.label("fractal_sugar crashed during its last run. A report was written to:");
+            ui.label(egui::RichText::new(path.display().to_string()).monospace());
+            ui.label(
+                "Please attach it when filing an issue, since it describes your GPU and the moments before the crash.",
+            );
+            ui.horizontal(|ui| {
+                if ui.button("Copy path").clicked() {
+                    ui.output_mut(|o| o.copied_text = path.display().to_string());
+                }
+                dismissed = ui.button("Dismiss").clicked();
+            });
+        });
+    dismissed
 }
 
-// Define the layout and behavior of the config UI.
+// Define the layout and behavior of the help UI, listing the current bindings of each action.
 fn create_help_ui(gui: &mut Gui, visible: &mut bool) {
-    use HelpWindowEntry::{Empty, Item, Title};
     let ctx = gui.context();
     egui::Window::new("Help")
         .open(visible)
         .resizable(true)
         .show(&ctx, |ui| {
             ScrollArea::vertical().show(ui, |ui| {
-                let controls_list = [
-                    Title("App-Window Management"),
-                    Item("F11", "Toggle window fullscreen"),
-                    Item("ESC", "If fullscreen, then enter windowed mode. Else, close the application"),
-                    #[cfg(all(not(debug_assertions), target_os = "windows"))]
-                    Item("ENTER", "Toggle the visibility of the output command prompt"),
-                    Empty(),
-                    Title("Overlay-Window Management"),
-                    Item("F1", "Toggle visibility of this Help window"),
-                    Item("C", "Toggle visibility of the App Config window"),
-                    Empty(),
-                    Title("Audio"),
-                    Item("R", "Toggle the application's responsiveness to system audio"),
-                    Empty(),
-                    Title("Visuals"),
-                    Item("SPACE", "Toggle kaleidoscope effect on fractals"),
-                    Item("J", "Toggle 'jello' effect on particles (i.e., the fixing of particles to a position with spring tension)"),
-                    Item("P", "Toggle the rendering and updating of particles"),
-                    Item("H", "Toggles whether to hide stationary particles"),
-                    Item("CAPS", "Toggle negative-color effect for particles"),
-                    Item("D", "Toggle between 2D and 3D projections of the particles"),
-                    Item("TAB", "Cycle through particle color schemes. *Requires that all overlay windows are closed*"),
-                    Item("0", "Select the 'empty' fractal"),
-                    Item("1-6", "Select the fractal corresponding to the respective key"),
-                    Item("MOUSE-BTTN", "Holding the primary or secondary mouse button applies a repulsive or attractive force, respectively, at the cursor's position"),
-                    Item("MOUSE-SCRL", "Scrolling up or down changes the strength of the cursor's applied force"),
-                ];
                 egui::Grid::new("scheme_index_grid").show(ui, |ui| {
-                    for entry in controls_list {
-                        match entry {
-                            Empty() => {}
-                            Item(key, desc) => {
-                                ui.vertical_centered(|ui| ui.label(egui::RichText::new(key).monospace().strong()));
-                                ui.label(desc);
-                            }
-                            Title(title) => {
-                                ui.separator();
-                                ui.heading(title);
-                            }
+                    for (category, entries) in input::help_entries() {
+                        ui.separator();
+                        ui.heading(category.title());
+                        ui.end_row();
+
+                        for entry in entries {
+                            ui.vertical_centered(|ui| {
+                                ui.label(egui::RichText::new(entry.keys).monospace().strong())
+                                    .on_hover_text(entry.name)
+                            });
+                            ui.label(entry.description);
+                            ui.end_row();
                         }
                         ui.end_row();
                     }
@@ -320,6 +2194,432 @@ fn create_help_ui(gui: &mut Gui, visible: &mut bool) {
         });
 }
 
+// Show a small indicator in the corner while a timelapse is running, with the number of frames saved.
+fn create_timelapse_indicator_ui(gui: &mut Gui, frames: usize) {
+    egui::Area::new("timelapse_indicator")
+        .anchor(egui::Align2::RIGHT_TOP, [-12., 12.])
+        .interactable(false)
+        .show(&gui.context(), |ui| {
+            ui.label(
+                egui::RichText::new(format!("\u{25CF} TIMELAPSE ({frames} frames)"))
+                    .color(egui::Color32::RED)
+                    .strong(),
+            );
+        });
+}
+
+// Beneath any timelapse indicator, show the progress of a clip.
+fn create_clip_indicator_ui(gui: &mut Gui, status: ClipStatus) {
+    let text = match status {
+        ClipStatus::Recording { seconds } => format!("\u{25CF} CLIP ({seconds:.1}s)"),
+        ClipStatus::Encoding => "ENCODING CLIP...".to_owned(),
+    };
+    egui::Area::new("clip_indicator")
+        .anchor(egui::Align2::RIGHT_TOP, [-12., 36.])
+        .interactable(false)
+        .show(&gui.context(), |ui| {
+            ui.label(egui::RichText::new(text).color(egui::Color32::RED).strong());
+        });
+}
+
+// Show the logo on a clear background across the whole window, becoming transparent as the splash fades.
+fn create_splash_ui(gui: &mut Gui, splash: &Splash) {
+    let opacity = 1. - splash.fade.unwrap_or_default() / SPLASH_FADE_SECONDS;
+    let ctx = gui.context();
+    let painter = ctx.layer_painter(egui::LayerId::background());
+    let screen = ctx.screen_rect();
+    painter.rect_filled(screen, 0., egui::Color32::BLACK.gamma_multiply(opacity));
+
+    let logo = egui::Rect::from_center_size(screen.center(), egui::Vec2::splat(SPLASH_LOGO_SIZE));
+    painter.image(
+        splash.logo.id(),
+        logo,
+        egui::Rect::from_min_max(egui::pos2(0., 0.), egui::pos2(1., 1.)),
+        egui::Color32::WHITE.gamma_multiply(opacity),
+    );
+    if splash.fade.is_none() {
+        painter.text(
+            logo.center_bottom() + egui::vec2(0., 16.),
+            egui::Align2::CENTER_TOP,
+            "Loading particles\u{2026}",
+            egui::FontId::proportional(16.),
+            egui::Color32::GRAY,
+        );
+    }
+}
+
+// Show a warning along the top of the window while the audio source has a problem.
+fn create_audio_warning_ui(gui: &mut Gui, warning: &str) {
+    egui::Area::new("audio_warning")
+        .anchor(egui::Align2::CENTER_TOP, [0., 12.])
+        .interactable(false)
+        .show(&gui.context(), |ui| {
+            ui.label(
+                egui::RichText::new(format!("\u{26A0} {warning}"))
+                    .color(egui::Color32::YELLOW)
+                    .strong(),
+            );
+        });
+}
+
+// Show a brief message along the bottom of the window, such as a failure the user should know of.
+fn create_toast_ui(gui: &mut Gui, message: &str) {
+    egui::Area::new("toast")
+        .anchor(egui::Align2::CENTER_BOTTOM, [0., -24.])
+        .interactable(false)
+        .show(&gui.context(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label(message);
+            });
+        });
+}
+
+// Define the layout of the spectrum window, plotting the spectrum on a log-frequency axis beneath the loudest notes.
+#[allow(clippy::cast_precision_loss)]
+fn create_spectrum_ui(gui: &mut Gui, panel: &mut SpectrumPanel, analysis: &AnalysisConfig) {
+    let ctx = gui.context();
+    egui::Window::new("Spectrum")
+        .open(&mut panel.visible)
+        .resizable(false)
+        .show(&ctx, |ui| {
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(360., 140.), egui::Sense::hover());
+            let painter = ui.painter_at(rect);
+            painter.rect_filled(rect, 2., egui::Color32::from_gray(16));
+
+            // Each bin is spaced evenly, since the bins are themselves log-spaced.
+            let bin_width = rect.width() / SPECTRUM_BINS as f32;
+            let peak = panel.peak.max(f32::EPSILON);
+            for (i, magnitude) in panel.spectrum.iter().enumerate() {
+                let height = (magnitude / peak).min(1.) * rect.height();
+                let left = rect.left() + i as f32 * bin_width;
+                painter.rect_filled(
+                    egui::Rect::from_min_max(
+                        egui::pos2(left + 1., rect.bottom() - height),
+                        egui::pos2(left + bin_width - 1., rect.bottom()),
+                    ),
+                    0.,
+                    egui::Color32::from_gray(150),
+                );
+            }
+
+            // Mark the loudest notes of each band at their frequencies, sized by their magnitude.
+            let ratio = (SPECTRUM_MAX_FREQUENCY / SPECTRUM_MIN_FREQUENCY).ln();
+            let x_of = |hertz: f32| {
+                let t = (hertz.max(SPECTRUM_MIN_FREQUENCY) / SPECTRUM_MIN_FREQUENCY).ln() / ratio;
+                rect.left() + t.min(1.) * rect.width()
+            };
+            let bands: [(&BandConfig, &[Note], egui::Color32); 3] = [
+                (&analysis.bass, &panel.bass_notes, egui::Color32::RED),
+                (&analysis.mids, &panel.mids_notes, egui::Color32::GREEN),
+                (&analysis.high, &panel.high_notes, egui::Color32::LIGHT_BLUE),
+            ];
+            for (band, notes, color) in bands {
+                for note in notes.iter().filter(|note| note.mag > 0.) {
+                    let hertz =
+                        band.min_frequency + note.freq * (band.max_frequency - band.min_frequency);
+                    let x = x_of(hertz);
+                    painter.line_segment(
+                        [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+                        egui::Stroke::new(1., color),
+                    );
+                    painter.circle_filled(
+                        egui::pos2(x, rect.top() + 6.),
+                        (2. + note.mag.sqrt()).min(6.),
+                        color,
+                    );
+                }
+            }
+            ui.horizontal(|ui| {
+                ui.colored_label(egui::Color32::RED, "Bass");
+                ui.colored_label(egui::Color32::GREEN, "Mids");
+                ui.colored_label(egui::Color32::LIGHT_BLUE, "Highs");
+                ui.label(format!(
+                    "{SPECTRUM_MIN_FREQUENCY:.0} Hz \u{2013} {SPECTRUM_MAX_FREQUENCY:.0} Hz"
+                ));
+            });
+
+            // Show how near the loudest bass note is to the threshold of a kick, which sits midway along the bar.
+            // The bar is lit briefly after each kick
+            let KickMeter {
+                magnitude,
+                threshold,
+            } = panel.kick_meter;
+            let kicked = panel
+                .last_kick
+                .is_some_and(|kick| kick.elapsed().as_secs_f32() < KICK_FLASH_SECONDS);
+            ui.add(
+                egui::ProgressBar::new((magnitude / threshold.max(f32::EPSILON) / 2.).min(1.))
+                    .fill(if kicked {
+                        egui::Color32::YELLOW
+                    } else {
+                        egui::Color32::DARK_GRAY
+                    })
+                    .text(format!("Kick: {magnitude:.2} of {threshold:.2}")),
+            );
+        });
+}
+
+// Helpers for formatting push-constant values in the inspector UI.
+fn format_floats(values: &[f32]) -> String {
+    let formatted: Vec<_> = values.iter().map(|v| format!("{v:>9.5}")).collect();
+    format!("({})", formatted.join(", "))
+}
+fn format_bool(value: u32) -> String {
+    String::from(if value == 0 { "false" } else { "true" })
+}
+
+// Define the layout of the frame inspector, showing the exact push constants submitted this frame.
+fn create_inspector_ui(gui: &mut Gui, visible: &mut bool, draw_data: &DrawData) {
+    // Helper to add a titled grid of named values.
+    fn add_section(ui: &mut Ui, title: &str, rows: &[(&str, String)]) {
+        ui.separator();
+        ui.heading(title);
+        egui::Grid::new(title).striped(true).show(ui, |ui| {
+            for (name, value) in rows {
+                ui.label(*name);
+                ui.label(egui::RichText::new(value).monospace());
+                ui.end_row();
+            }
+        });
+    }
+
+    let ctx = gui.context();
+    egui::Window::new("Frame Inspector")
+        .open(visible)
+        .resizable(true)
+        .show(&ctx, |ui| {
+            ui.label("The simulation is paused. Press `.` to advance exactly one frame.");
+            ScrollArea::vertical().show(ui, |ui| {
+                if let (Some((compute, vertex)), Some(attractors)) = (
+                    draw_data.particle_data.first().and_then(Option::as_ref),
+                    &draw_data.particle_attractors,
+                ) {
+                    // Only the counted attractors of each array are felt by the particles
+                    let counted = |slots: &[[f32; 4]], count: u32| {
+                        slots
+                            .iter()
+                            .take(count as usize)
+                            .map(|slot| format_floats(slot))
+                            .collect::<Vec<_>>()
+                    };
+                    let mut compute_rows = Vec::new();
+                    for big_boomer in counted(&attractors.big_boomers, attractors.big_boomer_count)
+                    {
+                        compute_rows.push(("big_boomer", big_boomer));
+                    }
+                    for curl in
+                        counted(&attractors.curl_attractors, attractors.curl_attractor_count)
+                    {
+                        compute_rows.push(("curl_attractor", curl));
+                    }
+                    for attractor in counted(&attractors.attractors, attractors.attractor_count) {
+                        compute_rows.push(("attractor", attractor));
+                    }
+                    compute_rows.extend([
+                        ("time", format_floats(&[compute.time])),
+                        ("delta_time", format_floats(&[compute.delta_time])),
+                        ("aspect_ratio", format_floats(&[compute.aspect_ratio])),
+                        ("fix_particles", format_bool(compute.fix_particles)),
+                        ("inertia", format_floats(&compute.inertia)),
+                        (
+                            "use_third_dimension",
+                            format_bool(compute.use_third_dimension),
+                        ),
+                        ("physics_mode", compute.physics_mode.to_string()),
+                    ]);
+                    add_section(ui, "Particle Compute", &compute_rows);
+
+                    add_section(
+                        ui,
+                        "Particle Vertex",
+                        &[
+                            ("quaternion", format_floats(&vertex.quaternion)),
+                            ("camera_position", format_floats(&vertex.camera_position)),
+                            ("kick_ages", format_floats(&vertex.kick_ages)),
+                            ("time", format_floats(&[vertex.time])),
+                            ("alternate_colors", format_bool(vertex.alternate_colors)),
+                            (
+                                "use_third_dimension",
+                                format_bool(vertex.use_third_dimension),
+                            ),
+                            ("opacity", format_floats(&[vertex.opacity])),
+                            ("alpha", format_floats(&[vertex.alpha])),
+                            ("sprite_shape", vertex.sprite_shape.to_string()),
+                        ],
+                    );
+                } else {
+                    ui.separator();
+                    ui.label("Particles are disabled, no particle push constants are submitted.");
+                }
+
+                let fractal = &draw_data.fractal_data;
+                add_section(
+                    ui,
+                    "Fractal",
+                    &[
+                        ("quaternion", format_floats(&fractal.quaternion)),
+                        ("camera_position", format_floats(&fractal.camera_position)),
+                        ("reactive_bass", format_floats(&fractal.reactive_bass)),
+                        ("reactive_mids", format_floats(&fractal.reactive_mids)),
+                        ("reactive_high", format_floats(&fractal.reactive_high)),
+                        ("smooth_bass", format_floats(&fractal.smooth_bass)),
+                        ("smooth_mids", format_floats(&fractal.smooth_mids)),
+                        ("smooth_high", format_floats(&fractal.smooth_high)),
+                        ("time", format_floats(&[fractal.time])),
+                        ("kaleidoscope", format_floats(&[fractal.kaleidoscope])),
+                        ("orbit_distance", format_floats(&[fractal.orbit_distance])),
+                        (
+                            "jitter",
+                            format_floats(&[fractal.jitter_x, fractal.jitter_y]),
+                        ),
+                    ],
+                );
+            });
+        });
+}
+
+impl Tour {
+    fn new() -> Self {
+        Self {
+            ended: false,
+            step: TourStep::Welcome,
+            last_demonstration: Instant::now(),
+            kaleidoscope_toggles: 0,
+            requested_demonstration: None,
+        }
+    }
+
+    // Request the keybinding demonstration of the current step, if any.
+    fn demonstrate(&mut self) {
+        self.last_demonstration = Instant::now();
+        self.requested_demonstration = match self.step {
+            TourStep::Kaleidoscope => {
+                self.kaleidoscope_toggles += 1;
+                Some(TourDemonstration::ToggleKaleidoscope)
+            }
+            TourStep::ColorSchemes => Some(TourDemonstration::NextColorScheme),
+            TourStep::Welcome | TourStep::ConfigWindow | TourStep::Finished => None,
+        };
+    }
+
+    // Leave the current step, undoing any demonstration which should not outlast it.
+    fn leave_step(&mut self, config_window: &mut ConfigWindow) {
+        match self.step {
+            TourStep::Kaleidoscope => {
+                if self.kaleidoscope_toggles % 2 == 1 {
+                    self.requested_demonstration = Some(TourDemonstration::ToggleKaleidoscope);
+                }
+            }
+            TourStep::ConfigWindow => config_window.visible = false,
+            TourStep::Welcome | TourStep::ColorSchemes | TourStep::Finished => {}
+        }
+    }
+
+    // Enter the given step, demonstrating its keybinding immediately.
+    fn enter_step(&mut self, step: TourStep, config_window: &mut ConfigWindow) {
+        self.step = step;
+        if step == TourStep::ConfigWindow {
+            config_window.visible = true;
+        }
+
+        // Do not override a pending request to undo the previous step.
+        if self.requested_demonstration.is_none() {
+            self.demonstrate();
+        } else {
+            self.last_demonstration = Instant::now();
+        }
+    }
+}
+
+// Define the layout of the onboarding tour. Returns whether the tour has ended.
+fn create_tour_ui(gui: &mut Gui, tour: &mut Tour, config_window: &mut ConfigWindow) -> bool {
+    // Periodically repeat the demonstration so the effect of the keybinding stays apparent.
+    if tour.requested_demonstration.is_none()
+        && tour.last_demonstration.elapsed().as_secs_f32() > TOUR_DEMONSTRATION_PERIOD
+    {
+        tour.demonstrate();
+    }
+
+    let (title, description) = match tour.step {
+        TourStep::Welcome => (
+            "Welcome to fractal_sugar!",
+            "fractal_sugar visualizes the audio playing on your computer. This short tour demonstrates a few of the controls.",
+        ),
+        TourStep::Kaleidoscope => (
+            "Kaleidoscope (SPACE)",
+            "Pressing SPACE toggles a kaleidoscope effect on the fractal. The tour is toggling it for you now.",
+        ),
+        TourStep::ColorSchemes => (
+            "Color schemes (TAB)",
+            "Pressing TAB cycles through the particle color schemes. The tour is cycling them for you now.",
+        ),
+        TourStep::ConfigWindow => (
+            "App Config window (C)",
+            "Pressing C shows the highlighted App Config window, where color schemes, particle physics, and audio analysis can be tuned live.",
+        ),
+        TourStep::Finished => (
+            "That's it!",
+            "Press F1 at any time to see every control in the Help window.",
+        ),
+    };
+
+    let ctx = gui.context();
+    let mut next = false;
+    let mut skip = false;
+    egui::Window::new("Tour")
+        .title_bar(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_BOTTOM, [0., -32.])
+        .show(&ctx, |ui| {
+            ui.heading(title);
+            ui.label(description);
+            ui.separator();
+            ui.horizontal(|ui| {
+                let next_label = if tour.step == TourStep::Finished {
+                    "Done"
+                } else {
+                    "Next"
+                };
+                next = ui.button(next_label).clicked();
+                if tour.step != TourStep::Finished {
+                    skip = ui.button("Skip").clicked();
+                }
+            });
+        });
+
+    // Point out the config window while it is the subject of the tour.
+    if tour.step == TourStep::ConfigWindow {
+        if let Some(rect) = config_window.rect {
+            ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                egui::Id::new("tour_highlight"),
+            ))
+            .rect_stroke(
+                rect.expand(4.),
+                6.,
+                egui::Stroke::new(3., egui::Color32::YELLOW),
+            );
+        }
+    }
+
+    if skip {
+        tour.leave_step(config_window);
+        return true;
+    }
+    if next {
+        tour.leave_step(config_window);
+        let next_step = match tour.step {
+            TourStep::Welcome => TourStep::Kaleidoscope,
+            TourStep::Kaleidoscope => TourStep::ColorSchemes,
+            TourStep::ColorSchemes => TourStep::ConfigWindow,
+            TourStep::ConfigWindow => TourStep::Finished,
+            TourStep::Finished => return true,
+        };
+        tour.enter_step(next_step, config_window);
+    }
+    false
+}
+
 impl AppOverlay {
     pub fn new(
         surface: Arc<Surface>,
@@ -328,6 +2628,11 @@ impl AppOverlay {
         event_loop: &EventLoop<()>,
         subpass: Subpass,
         app_config: &AppConfig,
+        shared_analysis: SharedAnalysisConfig,
+        audio_source: SourceKind,
+        show_tour: bool,
+        crash_report: Option<PathBuf>,
+        logo: &ico::IconImage,
     ) -> Self {
         let gui = Gui::new_with_subpass(
             event_loop,
@@ -336,11 +2641,29 @@ impl AppOverlay {
             subpass,
             swapchain.image_format(),
             GuiConfig {
+                allow_srgb_render_target: crate::engine::core::is_srgb_format(
+                    swapchain.image_format(),
+                ),
                 is_overlay: true,
                 ..Default::default()
             },
         );
 
+        apply_theme(&gui.context(), app_config.overlay_theme);
+
+        // Upload the logo for the splash shown while starting up.
+        let splash = Splash {
+            logo: gui.context().load_texture(
+                "splash_logo",
+                egui::ColorImage::from_rgba_unmultiplied(
+                    [logo.width() as usize, logo.height() as usize],
+                    logo.rgba_data(),
+                ),
+                egui::TextureOptions::LINEAR,
+            ),
+            fade: None,
+        };
+
         let initial_config = constants_to_presentable(app_config.into());
         let initial_colors: Vec<ConfigUiScheme> = app_config
             .color_schemes
@@ -348,22 +2671,151 @@ impl AppOverlay {
             .map(|cs| (*cs).into())
             .collect();
 
+        let initial_analysis = **shared_analysis.load();
         let config_window = ConfigWindow {
-            color_schemes: initial_colors.clone(),
-            init_color_schemes: initial_colors,
+            init_color_schemes: initial_colors.iter().copied().map(Some).collect(),
+            color_schemes: initial_colors,
             edit_scheme_index: 0,
+            renaming_scheme: None,
+            color_scheme_edit: None,
             config: initial_config,
             init_config: initial_config,
             visible: DEFAULT_VISIBILITY,
+
+            repulsion: app_config.particle_repulsion,
+            init_repulsion: app_config.particle_repulsion,
+
+            depth_of_field: app_config.depth_of_field,
+            init_depth_of_field: app_config.depth_of_field,
+
+            trails: app_config.particle_trails,
+            init_trails: app_config.particle_trails,
+
+            kaleidoscope: app_config.kaleidoscope,
+            init_kaleidoscope: app_config.kaleidoscope,
+
+            particle_systems: app_config.particle_systems.clone(),
+            init_particle_systems: app_config.particle_systems.clone(),
+
+            bloom: app_config.bloom,
+            init_bloom: app_config.bloom,
+
+            tone_mapping: app_config.tone_mapping,
+            init_tone_mapping: app_config.tone_mapping,
+
+            temporal_antialiasing: app_config.temporal_antialiasing,
+            init_temporal_antialiasing: app_config.temporal_antialiasing,
+
+            progressive_refinement: app_config.progressive_refinement,
+            init_progressive_refinement: app_config.progressive_refinement,
+
+            kick_echoes: app_config.kick_echoes,
+            init_kick_echoes: app_config.kick_echoes,
+            msaa_samples: app_config.msaa_samples,
+            init_msaa_samples: app_config.msaa_samples,
+            fractal_tunables: app_config.fractal_tunables,
+            init_fractal_tunables: app_config.fractal_tunables,
+            present_mode: app_config.present_mode,
+            init_present_mode: app_config.present_mode,
+            requested_present_mode: None,
+
+            stereo_placement: app_config.stereo_placement,
+            init_stereo_placement: app_config.stereo_placement,
+            stereo_attractors: app_config.stereo_attractors,
+            init_stereo_attractors: app_config.stereo_attractors,
+            attractor_strengths: app_config.attractor_strengths,
+            init_attractor_strengths: app_config.attractor_strengths,
+
+            intensity: app_config.intensity_macro.intensity,
+            init_intensity: app_config.intensity_macro.intensity,
+            intensity_curves: app_config.intensity_macro.curves,
+
+            responsiveness: app_config.responsiveness,
+            init_responsiveness: app_config.responsiveness,
+
+            key_hue: app_config.key_hue,
+            init_key_hue: app_config.key_hue,
+
+            theme: app_config.overlay_theme,
+            init_theme: app_config.overlay_theme,
+
+            auto_balance: app_config.auto_balance,
+            init_auto_balance: app_config.auto_balance,
+            auto_cycle: app_config.auto_cycle,
+            init_auto_cycle: app_config.auto_cycle,
+
+            sidechain_pump: app_config.sidechain_pump,
+            init_sidechain_pump: app_config.sidechain_pump,
+
+            scheme_crossfade: app_config.scheme_crossfade,
+            init_scheme_crossfade: app_config.scheme_crossfade,
+            crossfade_position: 0.,
+            requested_crossfade: false,
+
+            requested_save: false,
+
+            analysis: initial_analysis,
+            init_analysis: initial_analysis,
+            shared_analysis,
+            analysis_presets: app_config.analysis_presets.clone(),
+            analysis_preset: app_config.analysis_preset,
+            init_analysis_preset: app_config.analysis_preset,
+
+            audio_source,
+            requested_audio_source: None,
+
+            audio_devices: list_devices(),
+            audio_device: None,
+            requested_audio_device: false,
+
+            audio_description: String::new(),
+            audio_stream: None,
+            audio_beat: None,
+
+            rect: None,
         };
 
         Self {
             config_window,
             gui,
-            help_visible: app_config.launch_help_visible,
+            // The tour introduces the Help window itself when it completes.
+            help_visible: app_config.launch_help_visible && !show_tour,
+            inspector_visible: false,
+            timelapse_frames: None,
+            clip_status: None,
+            audio_warning: None,
+            toast: None,
+            tour: show_tour.then(Tour::new),
+            crash_report,
+            splash: Some(splash),
+            variables: config_variables(),
+            variables_console: VariablesConsole::default(),
+            spectrum_panel: SpectrumPanel::default(),
+        }
+    }
+
+    // Advance the startup splash, beginning to fade it out once the visualization is ready.
+    pub fn update_splash(&mut self, ready: bool, delta_time: f32) {
+        let Some(splash) = &mut self.splash else {
+            return;
+        };
+        match &mut splash.fade {
+            Some(fade) => {
+                *fade += delta_time;
+                if *fade >= SPLASH_FADE_SECONDS {
+                    self.splash = None;
+                }
+            }
+            None if ready => splash.fade = Some(0.),
+            None => {}
         }
     }
 
+    // Set the physical pixels per point of the overlay, such as to follow the monitor's scale factor.
+    pub fn set_pixels_per_point(&mut self, pixels_per_point: f32) {
+        self.gui.egui_winit.set_pixels_per_point(pixels_per_point);
+    }
+
     pub fn handle_input(&mut self, event: &WindowEvent) -> bool {
         // Handle UI events.
         self.gui.update(event)
@@ -373,17 +2825,45 @@ impl AppOverlay {
     pub fn draw(
         &mut self,
         engine: &mut Engine,
-        color_scheme_names: &[String],
-        color_schemes: &mut [Scheme],
+        color_scheme_names: &mut Vec<String>,
+        color_schemes: &mut Vec<Scheme>,
         displayed_scheme_index: &mut usize,
+        coloring_mode: &mut ColoringMode,
+        physics_mode: &mut PhysicsMode,
+        particle_containment: &mut ParticleContainment,
+        color_management: &mut ColorManagement,
+        draw_data: &DrawData,
+        metrics: Metrics,
+        frame_times: &FrameHistory,
     ) -> Option<Arc<SecondaryAutoCommandBuffer>> {
+        // Expire any toast which has been shown for long enough.
+        if self
+            .toast
+            .as_ref()
+            .is_some_and(|(_, shown)| shown.elapsed().as_secs_f32() >= TOAST_SECONDS)
+        {
+            self.toast = None;
+        }
+
         // Quick escape the render if window is not visible.
-        if !self.visible() {
+        if !self.visible()
+            && self.timelapse_frames.is_none()
+            && self.clip_status.is_none()
+            && self.audio_warning.is_none()
+            && self.toast.is_none()
+            && self.splash.is_none()
+        {
             return None;
         }
 
         // Setup UI layout.
+        let mut tour_ended = false;
         self.gui.immediate_ui(|gui| {
+            // Cover the visualization with the splash, beneath any other windows.
+            if let Some(splash) = &self.splash {
+                create_splash_ui(gui, splash);
+            }
+
             // Draw config window.
             create_config_ui(
                 gui,
@@ -392,15 +2872,76 @@ impl AppOverlay {
                 color_scheme_names,
                 color_schemes,
                 displayed_scheme_index,
+                coloring_mode,
+                physics_mode,
+                particle_containment,
+                color_management,
+                metrics,
+                frame_times,
             );
 
             // Draw help window.
             create_help_ui(gui, &mut self.help_visible);
+
+            // Draw the console of runtime variables.
+            create_variables_ui(
+                gui,
+                &mut self.variables_console,
+                &self.variables,
+                &mut self.config_window,
+                engine,
+            );
+
+            // Draw the live spectrum of the audio.
+            create_spectrum_ui(gui, &mut self.spectrum_panel, &self.config_window.analysis);
+
+            // Draw frame inspector window.
+            create_inspector_ui(gui, &mut self.inspector_visible, draw_data);
+
+            // Indicate that a timelapse is running.
+            if let Some(frames) = self.timelapse_frames {
+                create_timelapse_indicator_ui(gui, frames);
+            }
+
+            // Indicate that a clip is recording or encoding.
+            if let Some(status) = self.clip_status {
+                create_clip_indicator_ui(gui, status);
+            }
+
+            // Warn of any problem with the audio source.
+            if let Some(warning) = self.audio_warning {
+                create_audio_warning_ui(gui, warning);
+            }
+
+            // Show any recent toast.
+            if let Some((message, _)) = &self.toast {
+                create_toast_ui(gui, message);
+            }
+
+            // Point to the report of any crash during the previous run.
+            if let Some(path) = &self.crash_report {
+                if create_crash_report_ui(gui, path) {
+                    self.crash_report = None;
+                }
+            }
+
+            // Draw the onboarding tour.
+            if let Some(tour) = self.tour.as_mut().filter(|tour| !tour.ended) {
+                tour.ended = create_tour_ui(gui, tour, &mut self.config_window);
+                tour_ended = tour.ended;
+            }
         });
 
+        // Persist that the tour has been seen, then introduce the Help window.
+        if tour_ended {
+            crate::persistence::mark_tour_completed();
+            self.help_visible = true;
+        }
+
+        // The swapchain's extent is that of the display while presenting to one instead of the window.
         Some(
             self.gui
-                .draw_on_subpass_image(engine.window().inner_size().into()),
+                .draw_on_subpass_image(engine.swapchain().image_extent()),
         )
     }
 
@@ -410,31 +2951,322 @@ impl AppOverlay {
     pub fn toggle_config(&mut self) {
         self.config_window.visible = !self.config_window.visible;
     }
+    pub fn toggle_variables(&mut self) {
+        self.variables_console.visible = !self.variables_console.visible;
+    }
+    pub fn toggle_spectrum(&mut self) {
+        self.spectrum_panel.visible = !self.spectrum_panel.visible;
+    }
+    pub fn set_inspector_visible(&mut self, visible: bool) {
+        self.inspector_visible = visible;
+    }
+    pub fn set_timelapse_frames(&mut self, frames: Option<usize>) {
+        self.timelapse_frames = frames;
+    }
+    pub fn set_clip_status(&mut self, status: Option<ClipStatus>) {
+        self.clip_status = status;
+    }
+    pub fn set_audio_warning(&mut self, warning: Option<&'static str>) {
+        self.audio_warning = warning;
+    }
+    pub fn show_toast(&mut self, message: String) {
+        self.toast = Some((message, Instant::now()));
+    }
+    pub fn set_audio_stream(&mut self, description: String, stream: Option<StreamInfo>) {
+        self.config_window.audio_description = description;
+        self.config_window.audio_stream = stream;
+    }
+    pub fn set_audio_beat(&mut self, beat: Option<BeatInfo>) {
+        self.config_window.audio_beat = beat;
+    }
+    pub fn set_audio_spectrum(&mut self, state: &State) {
+        let panel = &mut self.spectrum_panel;
+        let loudest = state.spectrum.iter().copied().fold(0., f32::max);
+        panel.peak = (panel.peak * SPECTRUM_PEAK_DECAY).max(loudest);
+        panel.spectrum = state.spectrum;
+        panel.bass_notes = std::iter::once(state.bass_note)
+            .chain(state.extra_bass_notes.iter().copied())
+            .collect();
+        panel.mids_notes.clone_from(&state.mids_notes);
+        panel.high_notes.clone_from(&state.high_notes);
+        panel.kick_meter = state.kick_meter;
+        if state.kick_angular_velocity.is_some() {
+            panel.last_kick = Some(Instant::now());
+        }
+    }
     pub fn toggle_hide_stationary_particles(&mut self, engine: &mut Engine) {
         self.config_window.config.hide_stationary_particles =
             1 - self.config_window.config.hide_stationary_particles;
-        update_app_constants(engine, self.config_window.config);
+        update_app_constants(
+            engine,
+            self.config_window.config,
+            self.intensity_multipliers(),
+        );
     }
-    pub fn visible(&self) -> bool {
-        self.help_visible || self.config_window.visible
+    // Take the final demonstration request before discarding a finished tour.
+    pub fn take_tour_demonstration(&mut self) -> Option<TourDemonstration> {
+        let tour = self.tour.as_mut()?;
+        let demonstration = tour.requested_demonstration.take();
+        if tour.ended {
+            self.tour = None;
+        }
+        demonstration
     }
-}
+    pub fn particle_repulsion(&self) -> ParticleRepulsion {
+        self.config_window.repulsion
+    }
+    pub fn depth_of_field(&self) -> DepthOfField {
+        self.config_window.depth_of_field
+    }
+    pub fn particle_trails(&self) -> ParticleTrails {
+        self.config_window.trails
+    }
+    pub fn particle_systems(&self) -> &[ParticleSystem] {
+        &self.config_window.particle_systems
+    }
+    pub fn kaleidoscope(&self) -> Kaleidoscope {
+        self.config_window.kaleidoscope
+    }
+    // Fold the kaleidoscope into `step` more segments, or fewer if negative, within its limits, returning the new count.
+    pub fn step_kaleidoscope_segments(&mut self, step: i32) -> u32 {
+        let kaleidoscope = &mut self.config_window.kaleidoscope;
+        kaleidoscope.segments = kaleidoscope
+            .segments
+            .saturating_add_signed(step)
+            .clamp(MIN_KALEIDOSCOPE_SEGMENTS, MAX_KALEIDOSCOPE_SEGMENTS);
+        kaleidoscope.segments
+    }
+    pub fn bloom(&self) -> ParticleBloom {
+        self.config_window.bloom
+    }
+    pub fn tone_mapping(&self) -> ToneMapping {
+        self.config_window.tone_mapping
+    }
+    pub fn temporal_antialiasing(&self) -> bool {
+        self.config_window.temporal_antialiasing
+    }
+    pub fn progressive_refinement(&self) -> bool {
+        self.config_window.progressive_refinement
+    }
+    pub fn kick_echoes(&self) -> bool {
+        self.config_window.kick_echoes
+    }
+    pub fn stereo_placement(&self) -> f32 {
+        self.config_window.stereo_placement
+    }
+    pub fn stereo_attractors(&self) -> bool {
+        self.config_window.stereo_attractors
+    }
+    pub fn attractor_strengths(&self) -> AttractorStrengths {
+        self.config_window.attractor_strengths
+    }
+    pub fn responsiveness(&self) -> Responsiveness {
+        self.config_window.responsiveness
+    }
+    pub fn key_hue(&self) -> KeyHue {
+        self.config_window.key_hue
+    }
+    pub fn auto_balance(&self) -> AutoBalance {
+        self.config_window.auto_balance
+    }
+    pub fn auto_cycle(&self) -> AutoCycle {
+        self.config_window.auto_cycle
+    }
+    pub fn sidechain_pump(&self) -> SidechainPump {
+        self.config_window.sidechain_pump
+    }
+    pub fn scheme_crossfade(&self) -> SchemeCrossfade {
+        self.config_window.scheme_crossfade
+    }
+    // Whether a runtime variable of the given name exists.
+    pub fn has_variable(&self, name: &str) -> bool {
+        self.variables.find(name).is_some()
+    }
+    // Set a runtime variable to the position, in [0, 1], of an external controller's knob or fader.
+    // Floats span the given range, defaulting to the variable's own, while booleans are on past the halfway point
+    pub fn set_variable_position(
+        &mut self,
+        engine: &mut Engine,
+        name: &str,
+        position: f32,
+        range: Option<(f32, f32)>,
+    ) {
+        let Some(variable) = self.variables.find(name) else {
+            return;
+        };
+        match variable.storage(&mut self.config_window) {
+            Storage::Float(value, limits) => {
+                let (min, max) = range.unwrap_or((*limits.start(), *limits.end()));
+                *value = (min + position * (max - min)).clamp(*limits.start(), *limits.end());
+            }
+            Storage::Bool(value) => *value = position >= 0.5,
+            Storage::Flag(value) => *value = u32::from(position >= 0.5),
+        }
+        notify_writer(variable.writer, &mut self.config_window, engine);
+    }
+    // Move the displayed crossfade fader, such as when an external controller moves it.
+    pub fn set_crossfade_position(&mut self, position: f32) {
+        self.config_window.crossfade_position = position;
+    }
+    pub fn intensity(&self) -> f32 {
+        self.config_window.intensity
+    }
+    pub fn intensity_multipliers(&self) -> IntensityMultipliers {
+        self.config_window
+            .intensity_curves
+            .multipliers(self.config_window.intensity)
+    }
+    // Set the intensity macro, such as from an external controller, and apply it to the constants.
+    pub fn set_intensity(&mut self, engine: &mut Engine, intensity: f32) {
+        self.config_window.intensity = intensity.clamp(0., 1.);
+        update_app_constants(
+            engine,
+            self.config_window.config,
+            self.intensity_multipliers(),
+        );
+    }
+    // The presented values of the shader parameters applied through this overlay.
+    pub fn parameters(&self) -> ParameterValues {
+        parameters::read_constants(&constants_from_presentable(self.config_window.config))
+    }
+    // Apply the settings of a look to the constants, such as while blending into a recalled snapshot.
+    pub fn set_look(&mut self, engine: &mut Engine, look: &Look) {
+        let mut constants = constants_from_presentable(self.config_window.config);
+        parameters::write_constants(&look.parameters, &mut constants);
+        self.config_window.config = constants_to_presentable(constants);
+        self.config_window.intensity = look.intensity.clamp(0., 1.);
+        self.config_window.key_hue.enabled = look.key_hue;
+        self.config_window.sidechain_pump.enabled = look.sidechain_pump;
+        update_app_constants(
+            engine,
+            self.config_window.config,
+            self.intensity_multipliers(),
+        );
+    }
+    // Zoom the view by scaling the vertical field-of-view.
+    pub fn zoom(&mut self, engine: &mut Engine, scale: f32) {
+        let fov = &mut self.config_window.config.vertical_fov;
+        *fov = (*fov * scale).clamp(MIN_ZOOM_FOV, MAX_ZOOM_FOV);
+        update_app_constants(
+            engine,
+            self.config_window.config,
+            self.intensity_multipliers(),
+        );
+    }
+    // The bands as tuned in the overlay, if they differ from those of the selected preset.
+    fn tuned_bands(&self) -> Option<BandOverrides> {
+        let analysis = &self.config_window.analysis;
+        let preset = self.config_window.analysis_presets[self.config_window.analysis_preset]
+            .analysis
+            .with_attractor_counts(analysis.attractor_counts());
+        let bands = BandOverrides::of(analysis);
+        (bands != BandOverrides::of(&preset)).then_some(bands)
+    }
+    // Combine the values currently applied through this overlay with the remaining launch configuration.
+    pub fn applied_config(&self, launch_config: &AppConfig, color_schemes: &[Scheme]) -> AppConfig {
+        let constants = constants_from_presentable(self.config_window.config);
+        AppConfig {
+            parameters: parameters::read_constants(&constants),
+            hide_stationary_particles: constants.hide_stationary_particles != 0,
+            disable_background: constants.disable_background != 0,
+            kick_echoes: self.config_window.kick_echoes,
+            msaa_samples: self.config_window.msaa_samples,
+            fractal_tunables: self.config_window.fractal_tunables,
+            present_mode: self.config_window.present_mode,
+            stereo_placement: self.config_window.stereo_placement,
+            stereo_attractors: self.config_window.stereo_attractors,
+            capture_mode: match self.config_window.audio_source {
+                SourceKind::Loopback => CaptureMode::Loopback,
+                SourceKind::Microphone => CaptureMode::Microphone,
+                _ => launch_config.capture_mode,
+            },
+
+            particle_repulsion: self.config_window.repulsion,
+
+            depth_of_field: self.config_window.depth_of_field,
+
+            particle_trails: self.config_window.trails,
+
+            kaleidoscope: self.config_window.kaleidoscope,
+
+            particle_systems: self.config_window.particle_systems.clone(),
+
+            bloom: self.config_window.bloom,
+
+            tone_mapping: self.config_window.tone_mapping,
+
+            temporal_antialiasing: self.config_window.temporal_antialiasing,
+            progressive_refinement: self.config_window.progressive_refinement,
+
+            responsiveness: self.config_window.responsiveness,
 
-const DECIBEL_SCALE: f32 = std::f32::consts::LN_10 / 10.;
+            key_hue: self.config_window.key_hue,
 
-// Helpers for converting between presentation and internal units of measure.
-fn constants_to_presentable(app_constants: ConfigConstants) -> ConfigConstants {
-    ConfigConstants {
-        audio_scale: app_constants.audio_scale.ln() / DECIBEL_SCALE,
-        vertical_fov: app_constants.vertical_fov * 360. / std::f32::consts::PI,
-        ..app_constants
+            overlay_theme: self.config_window.theme,
+
+            auto_balance: self.config_window.auto_balance,
+            auto_cycle: self.config_window.auto_cycle,
+            sidechain_pump: self.config_window.sidechain_pump,
+            scheme_crossfade: self.config_window.scheme_crossfade,
+
+            intensity_macro: IntensityMacro {
+                intensity: self.config_window.intensity,
+                ..launch_config.intensity_macro
+            },
+
+            attractor_counts: self.config_window.analysis.attractor_counts(),
+            attractor_strengths: self.config_window.attractor_strengths,
+            analysis_preset: self.config_window.analysis_preset,
+            bands: self.tuned_bands(),
+            gain_control: self.config_window.analysis.gain,
+
+            color_schemes: color_schemes.to_vec(),
+            ..launch_config.clone()
+        }
     }
-}
-fn constants_from_presentable(app_constants: ConfigConstants) -> ConfigConstants {
-    ConfigConstants {
-        audio_scale: (DECIBEL_SCALE * app_constants.audio_scale).exp(),
-        vertical_fov: app_constants.vertical_fov * std::f32::consts::PI / 360.,
-        ..app_constants
+    // Show the audio source selected outside of the overlay, such as by the capture mode key.
+    pub fn set_audio_source(&mut self, source_kind: SourceKind) {
+        self.config_window.audio_source = source_kind;
+    }
+    pub fn take_requested_audio_source(&mut self) -> Option<SourceKind> {
+        self.config_window.requested_audio_source.take()
+    }
+    pub fn take_requested_present_mode(&mut self) -> Option<PresentationMode> {
+        self.config_window.requested_present_mode.take()
+    }
+    // Get the audio device if one was selected through the overlay.
+    pub fn take_requested_audio_device(&mut self) -> Option<AudioDevice> {
+        std::mem::take(&mut self.config_window.requested_audio_device)
+            .then(|| self.config_window.audio_device.clone())
+            .flatten()
+    }
+    // Get the crossfade position if the fader or its schemes were changed through the overlay.
+    pub fn take_requested_crossfade(&mut self) -> Option<f32> {
+        std::mem::take(&mut self.config_window.requested_crossfade)
+            .then_some(self.config_window.crossfade_position)
+    }
+    // Whether saving the configuration was requested through the overlay.
+    pub fn take_requested_save(&mut self) -> bool {
+        std::mem::take(&mut self.config_window.requested_save)
+    }
+    pub fn take_color_scheme_edit(&mut self) -> Option<ColorSchemeEdit> {
+        self.config_window.color_scheme_edit.take()
+    }
+    // The tunables applied to the given fractal, as edited through the overlay.
+    pub fn fractal_tunables(&self, id: u32) -> Tunables {
+        self.config_window
+            .fractal_tunables
+            .get(id as usize)
+            .map_or(FRACTALS[0].defaults, |tunables| *tunables)
+    }
+    pub fn visible(&self) -> bool {
+        self.help_visible
+            || self.config_window.visible
+            || self.variables_console.visible
+            || self.spectrum_panel.visible
+            || self.inspector_visible
+            || self.tour.as_ref().is_some_and(|tour| !tour.ended)
+            || self.crash_report.is_some()
     }
 }
 
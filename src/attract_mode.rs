@@ -0,0 +1,102 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use winit::event::WindowEvent;
+
+use crate::app_config;
+use crate::fractals::FRACTAL_COUNT;
+use crate::snapshots::Look;
+
+// The built-in fractals are cycled through, skipping the empty fractal and the optional user fractal.
+const FIRST_FRACTAL: u32 = 1;
+const LAST_FRACTAL: u32 = FRACTAL_COUNT as u32 - 2;
+
+// Counts the seconds without interaction, then schedules the scenes shown until the next interaction.
+#[derive(Default)]
+pub struct AttractMode {
+    idle_time: f32,      // Seconds since the latest interaction
+    scene_time: f32,     // Seconds since the latest scene began, while attracting
+    scenes: Option<u32>, // Scenes shown since attracting began, or none while the user is present
+}
+
+impl AttractMode {
+    // Whether the scenes are being cycled through.
+    pub fn active(&self) -> bool {
+        self.scenes.is_some()
+    }
+
+    // Restart the count of idle seconds, returning whether this stopped the scenes from being cycled through.
+    pub fn interact(&mut self) -> bool {
+        self.idle_time = 0.;
+        self.scene_time = 0.;
+        self.scenes.take().is_some()
+    }
+
+    // Advance the timers, returning the look of the next scene once the current one has been shown long enough.
+    // Each scene moves on to the next fractal and color scheme, and toggles the kaleidoscope on every other scene
+    pub fn update(
+        &mut self,
+        config: &app_config::AttractMode,
+        current: Look,
+        scheme_count: usize,
+        delta_time: f32,
+    ) -> Option<Look> {
+        if !config.enabled {
+            self.interact();
+            return None;
+        }
+
+        self.idle_time += delta_time;
+        let scenes = match self.scenes {
+            Some(scenes) => {
+                self.scene_time += delta_time;
+                if self.scene_time < config.scene_seconds {
+                    return None;
+                }
+                scenes
+            }
+            None if self.idle_time >= config.idle_seconds => 0,
+            None => return None,
+        };
+        self.scene_time = 0.;
+        self.scenes = Some(scenes + 1);
+
+        let fractal = if (FIRST_FRACTAL..LAST_FRACTAL).contains(&current.fractal) {
+            current.fractal + 1
+        } else {
+            FIRST_FRACTAL
+        };
+        Some(Look {
+            color_scheme: (current.color_scheme + 1) % scheme_count,
+            fractal,
+            kaleidoscope: current.kaleidoscope ^ (scenes % 2 == 1),
+            ..current
+        })
+    }
+}
+
+// Whether the window event is the user interacting with the app, rather than the window changing by itself.
+pub fn is_interaction(event: &WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::KeyboardInput { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::CursorMoved { .. }
+    )
+}
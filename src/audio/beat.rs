@@ -0,0 +1,260 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use super::AudioChunkHelper;
+
+// Range of frequencies whose spectral flux is considered, spanning kicks through hi-hats.
+const MIN_FREQUENCY: f32 = 30.;
+const MAX_FREQUENCY: f32 = 8_000.;
+
+// Compression applied to magnitudes before differencing, so that quiet transients count alongside loud ones.
+const LOG_COMPRESSION: f32 = 10.;
+
+// Seconds of flux over which the adaptive onset threshold is estimated.
+const THRESHOLD_SECONDS: f32 = 0.5;
+
+// Standard deviations above the recent mean flux which the flux must exceed to be an onset.
+const THRESHOLD_DEVIATIONS: f32 = 1.5;
+
+// Seconds after an onset before another may be detected.
+const MIN_ONSET_INTERVAL: f32 = 0.1;
+
+// Seconds of onset strength kept for estimating the tempo.
+const TEMPO_WINDOW_SECONDS: f32 = 6.;
+
+// Range of tempos which may be estimated.
+const MIN_BPM: f32 = 60.;
+const MAX_BPM: f32 = 200.;
+
+// Center and width, in octaves, of the preference for common tempos, resolving ambiguity between half and double tempo.
+const PRIOR_BPM: f32 = 120.;
+const PRIOR_OCTAVES: f32 = 1.;
+
+// Confidence below which no tempo is reported.
+const MIN_CONFIDENCE: f32 = 0.1;
+
+// Seconds over which the reported tempo follows the latest estimate.
+const TEMPO_SMOOTHING_SECONDS: f32 = 1.;
+
+// Fraction of a beat within which an onset is considered on the beat, and how far each such onset pulls the phase toward it.
+const PHASE_TOLERANCE: f32 = 0.2;
+const PHASE_CORRECTION: f32 = 0.3;
+
+// Consecutive onsets off the beat after which the phase is restarted from the latest onset.
+const MAX_MISSED_ONSETS: usize = 8;
+
+// The estimated tempo of the audio and where the latest chunk falls within the beat.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct BeatInfo {
+    pub bpm: f32,
+    pub confidence: f32, // In [0, 1], how strongly the onsets repeat at the tempo
+    pub phase: f32,      // In [0, 1), how far through the beat the latest chunk is
+    pub onset: bool,     // Whether an onset was detected in the latest chunk
+}
+
+// Onset detection by spectral flux with an adaptive threshold, and tempo estimation by autocorrelating the onset strengths.
+pub struct BeatTracker {
+    interval: f32, // Seconds between analyzed chunks
+    time: f32,     // Seconds of audio analyzed so far
+
+    previous_spectrum: Vec<f32>,
+    recent_flux: VecDeque<f32>,
+    onset_strengths: VecDeque<f32>,
+    last_onset: f32,
+
+    period: Option<f32>, // Smoothed seconds per beat
+    beat_time: f32,      // The time of some beat, from which the phase is measured
+    missed_onsets: usize,
+}
+
+impl BeatTracker {
+    // Create a tracker for chunks analyzed the given number of seconds apart.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn new(interval: f32) -> Self {
+        let capacity = |seconds: f32| (seconds / interval).ceil() as usize;
+        Self {
+            interval,
+            time: 0.,
+            previous_spectrum: Vec::new(),
+            recent_flux: VecDeque::with_capacity(capacity(THRESHOLD_SECONDS)),
+            onset_strengths: VecDeque::with_capacity(capacity(TEMPO_WINDOW_SECONDS)),
+            last_onset: f32::MIN,
+            period: None,
+            beat_time: 0.,
+            missed_onsets: 0,
+        }
+    }
+
+    // Detect whether the latest chunk begins an onset, then update the tempo and phase estimates.
+    // Returns `None` until the onsets repeat regularly enough for a tempo to be estimated
+    pub fn update(&mut self, audio_chunk: &AudioChunkHelper) -> Option<BeatInfo> {
+        self.time += self.interval;
+        let flux = self.spectral_flux(audio_chunk);
+
+        // Compare the flux against the mean and deviation of the recent flux.
+        let (mean, deviation) = mean_and_deviation(&self.recent_flux);
+        if self.recent_flux.len() == self.recent_flux.capacity() {
+            self.recent_flux.pop_front();
+        }
+        self.recent_flux.push_back(flux);
+        let onset = flux > mean + THRESHOLD_DEVIATIONS * deviation
+            && flux > 0.
+            && self.time - self.last_onset > MIN_ONSET_INTERVAL;
+        if onset {
+            self.last_onset = self.time;
+        }
+
+        // Only the flux rising above the recent mean contributes to the tempo.
+        if self.onset_strengths.len() == self.onset_strengths.capacity() {
+            self.onset_strengths.pop_front();
+        }
+        self.onset_strengths.push_back((flux - mean).max(0.));
+
+        let (period, confidence) = self.estimate_period()?;
+        let smooth = 1. - (-self.interval / TEMPO_SMOOTHING_SECONDS).exp();
+        let period = match self.period {
+            Some(current) => current + smooth * (period - current),
+            None => {
+                self.beat_time = self.last_onset.max(0.);
+                period
+            }
+        };
+        self.period = Some(period);
+
+        // Pull the phase toward onsets which fall near the beat, restarting it when they consistently do not.
+        let beats = (self.time - self.beat_time) / period;
+        if onset {
+            let offset = beats - beats.round();
+            if offset.abs() < PHASE_TOLERANCE {
+                self.beat_time += PHASE_CORRECTION * offset * period;
+                self.missed_onsets = 0;
+            } else {
+                self.missed_onsets += 1;
+                if self.missed_onsets >= MAX_MISSED_ONSETS {
+                    self.beat_time = self.time;
+                    self.missed_onsets = 0;
+                }
+            }
+        }
+
+        Some(BeatInfo {
+            bpm: 60. / period,
+            confidence,
+            phase: ((self.time - self.beat_time) / period).rem_euclid(1.),
+            onset,
+        })
+    }
+
+    // Sum the increases in compressed magnitude of each frequency bin since the previous chunk.
+    fn spectral_flux(&mut self, audio_chunk: &AudioChunkHelper) -> f32 {
+        let start_index = super::hertz_to_index(
+            MIN_FREQUENCY,
+            audio_chunk.size,
+            audio_chunk.frequency_resolution,
+        );
+        let end_index = super::hertz_to_index(
+            MAX_FREQUENCY,
+            audio_chunk.size,
+            audio_chunk.frequency_resolution,
+        )
+        .min(audio_chunk.size / 2);
+        let spectrum = audio_chunk.complex[start_index.max(1)..end_index]
+            .iter()
+            .map(|c| (1. + LOG_COMPRESSION * audio_chunk.scale * c.norm()).ln());
+
+        let mut flux = 0.;
+        if self.previous_spectrum.len() == end_index - start_index.max(1) {
+            for (previous, magnitude) in self.previous_spectrum.iter_mut().zip(spectrum) {
+                flux += (magnitude - *previous).max(0.);
+                *previous = magnitude;
+            }
+        } else {
+            self.previous_spectrum = spectrum.collect();
+        }
+        flux
+    }
+
+    // Find the beat period, in seconds, at which the onset strengths best correlate with themselves.
+    // The confidence is the correlation at that period relative to the strengths' own energy
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn estimate_period(&self) -> Option<(f32, f32)> {
+        let strengths = &self.onset_strengths;
+        let min_lag = (60. / MAX_BPM / self.interval).floor().max(1.) as usize;
+        let max_lag = (60. / MIN_BPM / self.interval).ceil() as usize;
+        if strengths.len() < 2 * max_lag {
+            return None;
+        }
+
+        // Normalize each lag by its count of overlapping pairs, so that longer lags are not penalized.
+        let correlation = |lag: usize| {
+            let pairs = strengths.len() - lag;
+            let sum: f32 = (0..pairs).map(|i| strengths[i] * strengths[i + lag]).sum();
+            sum / pairs as f32
+        };
+        let energy = correlation(0);
+        if energy <= f32::EPSILON {
+            return None;
+        }
+
+        let correlations: Vec<f32> = (min_lag - 1..=max_lag + 1).map(correlation).collect();
+        let prior = |lag: f32| {
+            let octaves = (60. / (lag * self.interval) / PRIOR_BPM).log2() / PRIOR_OCTAVES;
+            (-0.5 * octaves * octaves).exp()
+        };
+        let (best, _) = (1..correlations.len() - 1)
+            .map(|i| (i, correlations[i] * prior((min_lag + i - 1) as f32)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+
+        // Refine the lag between chunks by fitting a parabola through the peak and its neighbors.
+        let (before, peak, after) = (
+            correlations[best - 1],
+            correlations[best],
+            correlations[best + 1],
+        );
+        let curvature = before - 2. * peak + after;
+        let refinement = if curvature < 0. {
+            (0.5 * (before - after) / curvature).clamp(-0.5, 0.5)
+        } else {
+            0.
+        };
+        let lag = (min_lag + best - 1) as f32 + refinement;
+
+        let confidence = (peak / energy).clamp(0., 1.);
+        (confidence >= MIN_CONFIDENCE).then_some((lag * self.interval, confidence))
+    }
+}
+
+// The mean and standard deviation of the given values, or zeros if there are none.
+#[allow(clippy::cast_precision_loss)]
+fn mean_and_deviation(values: &VecDeque<f32>) -> (f32, f32) {
+    if values.is_empty() {
+        return (0., 0.);
+    }
+    let count = values.len() as f32;
+    let mean = values.iter().sum::<f32>() / count;
+    let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>() / count;
+    (mean, variance.sqrt())
+}
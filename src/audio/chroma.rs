@@ -0,0 +1,155 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use serde::{Deserialize, Serialize};
+
+use super::AudioChunkHelper;
+
+// Range of frequencies whose pitch classes contribute to the chroma, from C2 to roughly C8.
+const MIN_FREQUENCY: f32 = 65.4;
+const MAX_FREQUENCY: f32 = 4_200.;
+
+// Seconds over which the chroma is accumulated, so the key follows the harmony instead of single notes.
+const WINDOW_SECONDS: f32 = 8.;
+
+// Average chroma energy per chunk below which the window is considered silent and no key is estimated.
+const MIN_ENERGY: f32 = 0.5;
+
+// Krumhansl-Kessler key profiles, beginning from the tonic.
+const MAJOR_PROFILE: [f32; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+const MINOR_PROFILE: [f32; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+const PITCH_CLASS_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+// A musical key, as the pitch class of its tonic (where C is zero) and its mode.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MusicalKey {
+    pub tonic: usize,
+    pub minor: bool,
+}
+
+impl MusicalKey {
+    // The pitch class of the major key sharing this key's notes.
+    pub fn relative_major(self) -> usize {
+        (self.tonic + if self.minor { 3 } else { 0 }) % 12
+    }
+}
+
+impl std::fmt::Display for MusicalKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mode = if self.minor { "minor" } else { "major" };
+        write!(f, "{} {mode}", PITCH_CLASS_NAMES[self.tonic % 12])
+    }
+}
+
+// Rolling pitch-class analysis for estimating the dominant key of the incoming audio.
+#[derive(Default)]
+pub struct ChromaTracker {
+    chroma: [f32; 12],
+    weight: f32, // Decayed count of accumulated chunks, for averaging over the window
+}
+
+impl ChromaTracker {
+    // Accumulate the pitch classes of the latest chunk, decaying older chunks, and estimate the current key.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    pub fn update(
+        &mut self,
+        audio_chunk: &AudioChunkHelper,
+        sample_rate: f32,
+    ) -> Option<MusicalKey> {
+        let decay = (-(audio_chunk.size as f32 / sample_rate) / WINDOW_SECONDS).exp();
+        for class in &mut self.chroma {
+            *class *= decay;
+        }
+        self.weight = self.weight * decay + 1.;
+
+        // Fold each frequency bin into the pitch class nearest its frequency.
+        let start_index = super::hertz_to_index(
+            MIN_FREQUENCY,
+            audio_chunk.size,
+            audio_chunk.frequency_resolution,
+        );
+        let end_index = super::hertz_to_index(
+            MAX_FREQUENCY,
+            audio_chunk.size,
+            audio_chunk.frequency_resolution,
+        );
+        for i in start_index.max(1)..end_index {
+            let frequency = i as f32 * audio_chunk.frequency_resolution;
+            let midi_note = (12. * (frequency / 440.).log2()).round() as i32 + 69;
+            let v = audio_chunk.scale * audio_chunk.complex[i].norm();
+            self.chroma[midi_note.rem_euclid(12) as usize] += v;
+        }
+
+        let energy: f32 = self.chroma.iter().sum();
+        if energy / self.weight < MIN_ENERGY {
+            return None;
+        }
+        Some(self.estimate_key())
+    }
+
+    // Find the key whose profile correlates best with the accumulated chroma.
+    fn estimate_key(&self) -> MusicalKey {
+        let mut best = (
+            MusicalKey {
+                tonic: 0,
+                minor: false,
+            },
+            f32::MIN,
+        );
+        for tonic in 0..12 {
+            for (profile, minor) in [(&MAJOR_PROFILE, false), (&MINOR_PROFILE, true)] {
+                let score = correlation(&self.chroma, profile, tonic);
+                if score > best.1 {
+                    best = (MusicalKey { tonic, minor }, score);
+                }
+            }
+        }
+        best.0
+    }
+}
+
+// Pearson correlation between the chroma and a key profile rotated to begin at the given tonic.
+#[allow(clippy::cast_precision_loss)]
+fn correlation(chroma: &[f32; 12], profile: &[f32; 12], tonic: usize) -> f32 {
+    let mean_chroma = chroma.iter().sum::<f32>() / 12.;
+    let mean_profile = profile.iter().sum::<f32>() / 12.;
+    let (mut covariance, mut variance_chroma, mut variance_profile) = (0., 0., 0.);
+    for (i, p) in profile.iter().enumerate() {
+        let c = chroma[(tonic + i) % 12] - mean_chroma;
+        let p = p - mean_profile;
+        covariance += c * p;
+        variance_chroma += c * c;
+        variance_profile += p * p;
+    }
+    covariance
+        / (variance_chroma * variance_profile)
+            .sqrt()
+            .max(f32::EPSILON)
+}
@@ -0,0 +1,205 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::Sender;
+use rustfft::num_complex::Complex;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as DecodeError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use super::{AudioSource, SharedAnalysisConfig, State};
+
+// Seconds of decoded audio sent for processing at once.
+const CHUNK_SECONDS: f64 = 0.01;
+
+// How often the finished thread checks whether it should stop.
+const IDLE_TIMEOUT: Duration = Duration::from_millis(100);
+
+// Audio source decoding an audio file and sending its samples for processing at the rate they would play.
+pub struct FileSource {
+    path: PathBuf,
+    running: Arc<AtomicBool>,
+}
+
+// Decode the samples of an audio file as stereo pairs, duplicating any mono channel, stopping after `seconds` if given.
+// The format is detected from the contents, using the extension as a hint. Channels beyond the first two are ignored
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+pub fn decode(path: &Path, seconds: Option<f32>) -> anyhow::Result<(u32, Vec<(f32, f32)>)> {
+    let stream = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+    let mut format = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )?
+        .format;
+
+    let track = format
+        .default_track()
+        .ok_or_else(|| anyhow::anyhow!("`{}` contains no audio track", path.display()))?;
+    let track_id = track.id;
+    let sample_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or_else(|| anyhow::anyhow!("`{}` has an unknown sample rate", path.display()))?;
+    let mut decoder =
+        symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+    let frame_limit = seconds.map(|seconds| (seconds * sample_rate as f32).ceil() as usize);
+    let mut samples = Vec::new();
+    loop {
+        if frame_limit.is_some_and(|limit| samples.len() >= limit) {
+            break;
+        }
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(DecodeError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        // A corrupt packet only skips its own samples.
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(DecodeError::DecodeError(e)) => {
+                log!("Skipping undecodable audio in `{}`: {e}", path.display());
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let spec = *decoded.spec();
+        let channels = spec.channels.count();
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend(
+            buffer
+                .samples()
+                .chunks_exact(channels)
+                .map(|frame| (frame[0], frame[channels.min(2) - 1])),
+        );
+    }
+    if let Some(limit) = frame_limit {
+        samples.truncate(limit);
+    }
+    Ok((sample_rate, samples))
+}
+
+impl FileSource {
+    // Spawn a thread which decodes the file, then sends its samples for processing in real-time from the beginning.
+    pub fn new(path: &Path, tx: Sender<State>, analysis_config: SharedAnalysisConfig) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+        let thread_path = path.to_path_buf();
+        std::thread::spawn(move || {
+            // Hold the state channel open once finished, so that the end of the file is not mistaken for a failed source.
+            let idle = || {
+                while thread_running.load(Ordering::Relaxed) {
+                    std::thread::sleep(IDLE_TIMEOUT);
+                }
+            };
+            let (sample_rate, samples) = match decode(&thread_path, None) {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    log!(
+                        "Failed to decode the audio file `{}`: {e:?}",
+                        thread_path.display()
+                    );
+                    idle();
+                    return;
+                }
+            };
+
+            #[allow(clippy::cast_precision_loss)]
+            let tx_acc =
+                super::spawn_audio_processing_thread(sample_rate as f32, tx, analysis_config);
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let chunk_size = (CHUNK_SECONDS * f64::from(sample_rate)).ceil() as usize;
+            #[allow(clippy::cast_precision_loss)]
+            let chunk_duration =
+                Duration::from_secs_f64(chunk_size as f64 / f64::from(sample_rate));
+            let mut next_deadline = Instant::now();
+            for chunk in samples.chunks(chunk_size) {
+                if !thread_running.load(Ordering::Relaxed) {
+                    return;
+                }
+                let chunk: Vec<Complex<f32>> = chunk
+                    .iter()
+                    .map(|(left, right)| super::stereo::pack(*left, *right))
+                    .collect();
+
+                // End the thread when the processing thread has disconnected.
+                if !super::send_chunk(&tx_acc, chunk) {
+                    return;
+                }
+
+                // Pace the decoded samples to match real-time playback.
+                next_deadline += chunk_duration;
+                let now = Instant::now();
+                if next_deadline > now {
+                    std::thread::sleep(next_deadline - now);
+                } else {
+                    next_deadline = now;
+                }
+            }
+            log!("Finished the audio file `{}`", thread_path.display());
+            idle();
+        });
+
+        Self {
+            path: path.to_path_buf(),
+            running,
+        }
+    }
+}
+
+impl AudioSource for FileSource {
+    fn description(&self) -> String {
+        format!("File: {}", self.path.display())
+    }
+
+    fn pause(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Drop for FileSource {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,87 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use rustfft::num_complex::Complex;
+
+// Settings for automatically normalizing the loudness of the input before it is analyzed.
+// Loudness is the RMS level of both channels, so that quiet streams and loud masters drive the visuals alike
+#[derive(Clone, Copy, PartialEq)]
+pub struct GainControl {
+    pub enabled: bool,
+    pub target: f32,  // The level, in dBFS, the input is normalized toward
+    pub attack: f32,  // Seconds for the estimated level to rise most of the way to a louder input
+    pub release: f32, // Seconds for the estimated level to fall most of the way to a quieter input
+}
+
+pub const DEFAULT_GAIN_CONTROL: GainControl = GainControl {
+    enabled: false,
+    target: -20.,
+    attack: 0.5,
+    release: 5.,
+};
+
+// The greatest gain applied, in decibels, so that near-silence is not amplified into noise.
+const MAX_GAIN_DB: f32 = 30.;
+
+// Levels below this, in dBFS, are treated as silence, holding the gain until the input resumes.
+const SILENCE_DB: f32 = -70.;
+
+fn db_to_amplitude(db: f32) -> f32 {
+    10_f32.powf(db / 20.)
+}
+
+// Tracks a rolling estimate of the input's loudness, scaling each buffer toward the target level.
+#[derive(Default)]
+pub struct GainTracker {
+    mean_square: Option<f32>, // The estimated mean square of the recent samples, once any have been heard
+}
+
+impl GainTracker {
+    // Update the estimated level with the given samples, which span the given seconds, then scale them in place.
+    // Samples are packed from both channels, so each holds the left channel in its real part and the right in its imaginary
+    #[allow(clippy::cast_precision_loss)]
+    pub fn apply(&mut self, samples: &mut [Complex<f32>], seconds: f32, config: &GainControl) {
+        if !config.enabled || samples.is_empty() {
+            return;
+        }
+
+        // Follow the loudness with separate time constants for rising and falling levels.
+        let mean_square =
+            samples.iter().map(Complex::norm_sqr).sum::<f32>() / (2 * samples.len()) as f32;
+        if mean_square >= db_to_amplitude(SILENCE_DB).powi(2) {
+            self.mean_square = Some(self.mean_square.map_or(mean_square, |previous| {
+                let time = if mean_square > previous {
+                    config.attack
+                } else {
+                    config.release
+                };
+                let rate = 1. - (-seconds / time.max(f32::EPSILON)).exp();
+                previous + rate * (mean_square - previous)
+            }));
+        }
+        let Some(level) = self.mean_square else {
+            return;
+        };
+
+        let gain =
+            (db_to_amplitude(config.target) / level.sqrt()).min(db_to_amplitude(MAX_GAIN_DB));
+        for sample in samples {
+            *sample *= gain;
+        }
+    }
+}
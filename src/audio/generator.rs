@@ -0,0 +1,197 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::Sender;
+use rustfft::num_complex::Complex;
+
+use super::{AudioSource, SharedAnalysisConfig, State};
+
+// Generated signals are produced at a fixed rate, independent of any audio device.
+pub const SAMPLE_RATE: u32 = 48_000;
+const CHUNK_SIZE: usize = 480;
+const AMPLITUDE: f32 = 0.25;
+
+// Sine sweep parameters.
+const SWEEP_START_HERTZ: f32 = 30.;
+const SWEEP_END_HERTZ: f32 = 16_000.;
+const SWEEP_SECONDS: f32 = 12.;
+
+// Impulse train parameters, roughly the beat of a 120 BPM song.
+const IMPULSE_PERIOD_SECONDS: f32 = 0.5;
+
+// The test signals available to the generator.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SignalKind {
+    SineSweep,
+    PinkNoise,
+    ImpulseTrain,
+}
+
+// Audio source which synthesizes a test signal on its own thread.
+pub struct GeneratorSource {
+    signal: SignalKind,
+    running: Arc<AtomicBool>,
+}
+
+// Per-signal state needed to produce consecutive samples.
+struct SignalState {
+    sample_index: u64,
+    phase: f32,
+    rng: u32,
+    pink: [f32; 7],
+}
+
+impl SignalState {
+    fn new() -> Self {
+        Self {
+            sample_index: 0,
+            phase: 0.,
+            rng: 0x9E37_79B9,
+            pink: [0.; 7],
+        }
+    }
+
+    // Simple xorshift generator mapped to [-1, 1], sufficient for test noise.
+    fn white_noise(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        (self.rng as f32 / u32::MAX as f32) * 2. - 1.
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn next_sample(&mut self, signal: SignalKind) -> f32 {
+        let sample_rate = SAMPLE_RATE as f32;
+        let t = self.sample_index as f32 / sample_rate;
+        self.sample_index += 1;
+
+        match signal {
+            // Exponential sweep so that each octave receives equal time.
+            SignalKind::SineSweep => {
+                let progress = (t % SWEEP_SECONDS) / SWEEP_SECONDS;
+                let freq = SWEEP_START_HERTZ * (SWEEP_END_HERTZ / SWEEP_START_HERTZ).powf(progress);
+                self.phase = (self.phase + std::f32::consts::TAU * freq / sample_rate)
+                    % std::f32::consts::TAU;
+                AMPLITUDE * self.phase.sin()
+            }
+
+            // Paul Kellet's refined pink-noise filter applied to white noise.
+            SignalKind::PinkNoise => {
+                let white = self.white_noise();
+                let b = &mut self.pink;
+                b[0] = 0.998_86 * b[0] + white * 0.055_517_9;
+                b[1] = 0.993_32 * b[1] + white * 0.075_075_9;
+                b[2] = 0.969 * b[2] + white * 0.153_852;
+                b[3] = 0.8665 * b[3] + white * 0.310_485_6;
+                b[4] = 0.55 * b[4] + white * 0.532_952_2;
+                b[5] = -0.7616 * b[5] - white * 0.016_898;
+                let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+                b[6] = white * 0.115_926;
+                AMPLITUDE * 0.11 * pink
+            }
+
+            // A single full-scale sample at a fixed period.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            SignalKind::ImpulseTrain => {
+                let period = (IMPULSE_PERIOD_SECONDS * sample_rate) as u64;
+                if (self.sample_index - 1) % period == 0 {
+                    1.
+                } else {
+                    0.
+                }
+            }
+        }
+    }
+}
+
+// The samples of the given signal from its beginning, without pacing, such as for analyzing offline.
+pub fn samples(signal: SignalKind) -> impl Iterator<Item = f32> {
+    let mut state = SignalState::new();
+    std::iter::repeat_with(move || state.next_sample(signal))
+}
+
+impl GeneratorSource {
+    // Spawn a thread which synthesizes the requested signal in real-time and sends it for processing.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn new(
+        signal: SignalKind,
+        tx: Sender<State>,
+        analysis_config: SharedAnalysisConfig,
+    ) -> Self {
+        let tx_acc = super::spawn_audio_processing_thread(SAMPLE_RATE as f32, tx, analysis_config);
+
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+        std::thread::spawn(move || {
+            let chunk_duration =
+                Duration::from_secs_f64(CHUNK_SIZE as f64 / f64::from(SAMPLE_RATE));
+            let mut state = SignalState::new();
+            let mut next_deadline = Instant::now();
+
+            while thread_running.load(Ordering::Relaxed) {
+                let chunk: Vec<Complex<f32>> = (0..CHUNK_SIZE)
+                    .map(|_| {
+                        let sample = state.next_sample(signal);
+                        super::stereo::pack(sample, sample)
+                    })
+                    .collect();
+
+                // End the thread when the processing thread has disconnected.
+                if !super::send_chunk(&tx_acc, chunk) {
+                    return;
+                }
+
+                // Pace the generated samples to match real-time playback.
+                next_deadline += chunk_duration;
+                let now = Instant::now();
+                if next_deadline > now {
+                    std::thread::sleep(next_deadline - now);
+                } else {
+                    next_deadline = now;
+                }
+            }
+        });
+
+        Self { signal, running }
+    }
+}
+
+impl AudioSource for GeneratorSource {
+    fn description(&self) -> String {
+        let name = match self.signal {
+            SignalKind::SineSweep => "Sine sweep",
+            SignalKind::PinkNoise => "Pink noise",
+            SignalKind::ImpulseTrain => "Impulse train",
+        };
+        format!("Generator: {name}")
+    }
+
+    fn pause(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Drop for GeneratorSource {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
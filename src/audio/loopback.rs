@@ -0,0 +1,541 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::time::{Duration, Instant};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{
+    Device, FromSample, Sample, SampleFormat, SizedSample, StreamConfig, SupportedStreamConfig,
+    SupportedStreamConfigRange,
+};
+use crossbeam_channel::{bounded, Receiver, Sender};
+use rustfft::num_complex::Complex;
+
+use super::{AudioSource, SharedAnalysisConfig, SourceStatus, State, StreamInfo};
+
+// Seconds of consecutive samples of exactly zero before the stream is reported silent.
+// Even quiet passages carry noise or dither, while protected and exclusive-mode streams deliver exact zeros to loopback
+const SILENT_SECONDS: f32 = 8.;
+
+// How often a source following the system's default device checks whether the default has changed.
+const DEFAULT_DEVICE_POLL: Duration = Duration::from_secs(1);
+
+// Whether a device plays audio out, and so is captured by loopback, or records audio in, such as a microphone.
+#[derive(Clone, Copy, PartialEq)]
+pub enum DeviceDirection {
+    Output,
+    Input,
+}
+
+// An audio device of the default host which may be captured from.
+#[derive(Clone, PartialEq)]
+pub struct AudioDevice {
+    pub name: String,
+    pub direction: DeviceDirection,
+}
+
+impl AudioDevice {
+    pub fn is_input(&self) -> bool {
+        self.direction == DeviceDirection::Input
+    }
+}
+
+impl std::fmt::Display for AudioDevice {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.direction {
+            DeviceDirection::Output => write!(f, "{} (out)", self.name),
+            DeviceDirection::Input => write!(f, "{} (in)", self.name),
+        }
+    }
+}
+
+// Audio source capturing an audio device, by default the system's default audio-out device.
+pub struct LoopbackSource {
+    device: Device,
+    device_name: String,
+    direction: DeviceDirection,
+    audio_config: SupportedStreamConfig,
+    stream: cpal::Stream,
+    status_rx: Receiver<SourceStatus>,
+
+    // Needed to restart processing when capture is retried with another stream config
+    tx: Sender<State>,
+    analysis_config: SharedAnalysisConfig,
+
+    // Stream configs not yet tried, in the order to retry capture with while the stream is silent
+    alternate_configs: Vec<SupportedStreamConfig>,
+
+    // Whether the device was chosen as the system's default, and so is replaced when the default changes
+    follows_default: bool,
+    last_default_check: Instant,
+    paused: bool,
+}
+
+// Tracks whether the capture callback receives only exact silence, reporting each change over the status channel.
+struct SilenceDetector {
+    zero_frames: usize,
+    threshold: usize,
+    reported: Option<SourceStatus>,
+    tx: Sender<SourceStatus>,
+}
+
+impl SilenceDetector {
+    fn update(&mut self, data: &[f32], frames: usize) {
+        let status = if data.iter().any(|&sample| sample != 0.) {
+            self.zero_frames = 0;
+            SourceStatus::Audible
+        } else {
+            self.zero_frames += frames;
+            if self.zero_frames < self.threshold {
+                return;
+            }
+            SourceStatus::Silent
+        };
+
+        // A full channel is retried with the next chunk.
+        if self.reported != Some(status) && self.tx.try_send(status).is_ok() {
+            self.reported = Some(status);
+        }
+    }
+}
+
+// Whether capture can convert samples of the given format to floats for processing.
+pub fn is_supported_format(format: SampleFormat) -> bool {
+    matches!(
+        format,
+        SampleFormat::F32
+            | SampleFormat::F64
+            | SampleFormat::I8
+            | SampleFormat::I16
+            | SampleFormat::I32
+            | SampleFormat::U8
+            | SampleFormat::U16
+            | SampleFormat::U32
+    )
+}
+
+// Build an input stream delivering samples of type `T`, converted to floats in [-1, 1] before being handed to `process`.
+// Errors, such as the device being unplugged, report the stream lost rather than ending the app
+fn build_converting_stream<T>(
+    device: &Device,
+    stream_config: &StreamConfig,
+    status_tx: Sender<SourceStatus>,
+    mut process: impl FnMut(&[f32]) + Send + 'static,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    // The buffer is reused between callbacks so that capture does not allocate once warmed up
+    let mut converted = Vec::new();
+    device.build_input_stream(
+        stream_config,
+        move |data: &[T], _| {
+            converted.clear();
+            converted.extend(data.iter().map(|sample| sample.to_sample::<f32>()));
+            process(&converted);
+        },
+        move |e| {
+            log!("Error on audio input stream: {e:?}");
+
+            // A full channel already holds statuses which are yet to be polled, so this is retried on the next error.
+            let _ = status_tx.try_send(SourceStatus::Lost);
+        },
+        None,
+    )
+}
+
+// Create a new audio stream from the given device.
+// The retrieved data is then sent across the given channel to be processed.
+// Channels of input devices are separate inputs rather than a stereo mix, so they are averaged and kept centered
+fn transfer_loopback_chunks_for_processing(
+    device: &Device,
+    direction: DeviceDirection,
+    audio_config: &SupportedStreamConfig,
+    tx_acc: Sender<Vec<Complex<f32>>>,
+    status_tx: Sender<SourceStatus>,
+) -> anyhow::Result<cpal::Stream> {
+    // Store channel constants for use in callback
+    let channel_count = audio_config.channels() as usize;
+    let channel_count_f32 = channel_count as f32;
+    let mut silence = SilenceDetector {
+        zero_frames: 0,
+        threshold: (SILENT_SECONDS * audio_config.sample_rate().0 as f32) as usize,
+        reported: None,
+        tx: status_tx.clone(),
+    };
+
+    // Pass small audio-chunks to be processed in batches
+    let process = move |data: &[f32]| {
+        // Account for audio-channel packing of samples
+        let size = data.len() / channel_count;
+
+        // Short-circuit when there is no data
+        if size == 0 {
+            return;
+        }
+        silence.update(data, size);
+
+        // Map data to mutable complex array.
+        // This allows us to transfer ownership to processing thread and more easily use
+        let complex: Vec<Complex<f32>> = {
+            // Collect samples in groups equal in size to the audio-channel count.
+            // Any channels beyond the front pair are shared equally, so the mid of the pair is the average of all channels
+            (0..size)
+                .map(|i: usize| {
+                    let frame = &data[channel_count * i..channel_count * (i + 1)];
+                    match frame {
+                        [mono] => super::stereo::pack(*mono, *mono),
+                        _ if direction == DeviceDirection::Input => {
+                            let mono = frame.iter().sum::<f32>() / channel_count_f32;
+                            super::stereo::pack(mono, mono)
+                        }
+                        [left, right, rest @ ..] => {
+                            let shared: f32 = rest.iter().sum();
+                            super::stereo::pack(
+                                (2. * left + shared) / channel_count_f32,
+                                (2. * right + shared) / channel_count_f32,
+                            )
+                        }
+                        [] => unreachable!("Streams have at least one channel"),
+                    }
+                })
+                .collect()
+        };
+
+        // Send new audio data to audio processing thread
+        if !super::send_chunk(&tx_acc, complex) {
+            log!("Audio-processor receiver disconnected..");
+        }
+    };
+
+    // Create loopback stream, converting integer PCM formats to floats
+    let stream_config = audio_config.config();
+    let stream = match audio_config.sample_format() {
+        SampleFormat::F32 => {
+            build_converting_stream::<f32>(device, &stream_config, status_tx, process)
+        }
+        SampleFormat::F64 => {
+            build_converting_stream::<f64>(device, &stream_config, status_tx, process)
+        }
+        SampleFormat::I8 => {
+            build_converting_stream::<i8>(device, &stream_config, status_tx, process)
+        }
+        SampleFormat::I16 => {
+            build_converting_stream::<i16>(device, &stream_config, status_tx, process)
+        }
+        SampleFormat::I32 => {
+            build_converting_stream::<i32>(device, &stream_config, status_tx, process)
+        }
+        SampleFormat::U8 => {
+            build_converting_stream::<u8>(device, &stream_config, status_tx, process)
+        }
+        SampleFormat::U16 => {
+            build_converting_stream::<u16>(device, &stream_config, status_tx, process)
+        }
+        SampleFormat::U32 => {
+            build_converting_stream::<u32>(device, &stream_config, status_tx, process)
+        }
+        format => anyhow::bail!("Unsupported audio sample format {format:?}"),
+    }
+    .map_err(|e| anyhow::anyhow!("Error capturing audio stream: {e:?}"))?;
+
+    // Ensure loopback capture starts
+    stream
+        .play()
+        .map_err(|e| anyhow::anyhow!("Failed to initiate loopback stream: {e:?}"))?;
+    Ok(stream)
+}
+
+// Begin processing and capturing with the given stream config, returning the stream and its status channel.
+fn start_stream(
+    device: &Device,
+    direction: DeviceDirection,
+    audio_config: &SupportedStreamConfig,
+    tx: Sender<State>,
+    analysis_config: SharedAnalysisConfig,
+) -> anyhow::Result<(cpal::Stream, Receiver<SourceStatus>)> {
+    // Store stream details we are intersted in
+    let sample_rate = audio_config.sample_rate().0 as f32;
+    let tx_acc = super::spawn_audio_processing_thread(sample_rate, tx, analysis_config);
+
+    // The processing thread ends with the stream, including when it fails to be created
+    let (status_tx, status_rx) = bounded(4);
+    let stream = transfer_loopback_chunks_for_processing(
+        device,
+        direction,
+        audio_config,
+        tx_acc,
+        status_tx,
+    )?;
+    Ok((stream, status_rx))
+}
+
+// The device's other supported configs at their highest sample rates, to retry capture with when the default is silent.
+fn alternate_configs(
+    device: &Device,
+    direction: DeviceDirection,
+    default: &SupportedStreamConfig,
+) -> Vec<SupportedStreamConfig> {
+    let configs: Vec<_> = match direction {
+        DeviceDirection::Output => device.supported_output_configs().map(Iterator::collect),
+        DeviceDirection::Input => device.supported_input_configs().map(Iterator::collect),
+    }
+    .unwrap_or_default();
+    let mut alternates: Vec<_> = configs
+        .into_iter()
+        .filter(|config| is_supported_format(config.sample_format()))
+        .map(SupportedStreamConfigRange::with_max_sample_rate)
+        .filter(|config| config != default)
+        .collect();
+    alternates.dedup();
+    alternates
+}
+
+// Find the default audio-out device of the default host, along with its name and default stream config.
+pub fn default_output() -> anyhow::Result<(Device, String, SupportedStreamConfig)> {
+    // Create CPAL default instance
+    let audio_host = cpal::default_host();
+
+    // Get the default audio out device
+    let default_audio_out = audio_host
+        .default_output_device()
+        .ok_or_else(|| anyhow::anyhow!("There must be at least one output device"))?;
+    let device_name = default_audio_out
+        .name()
+        .unwrap_or_else(|_| String::from("Unnamed device"));
+
+    // Use the device's default format, whose samples are converted to floats as they are captured
+    let audio_config = default_audio_out
+        .default_output_config()
+        .map_err(|e| anyhow::anyhow!("Could not find default audio format: {e:?}"))?;
+    Ok((default_audio_out, device_name, audio_config))
+}
+
+// The default audio-in device of the default host, such as a microphone, if there is one.
+pub fn default_input() -> Option<AudioDevice> {
+    let device = cpal::default_host().default_input_device();
+    let name = device.and_then(|device| device.name().ok());
+    if name.is_none() {
+        log!("There is no default audio input device");
+    }
+    name.map(|name| AudioDevice {
+        name,
+        direction: DeviceDirection::Input,
+    })
+}
+
+// The name of the default host's default device in the given direction, if there is one.
+fn default_device_name(direction: DeviceDirection) -> Option<String> {
+    let host = cpal::default_host();
+    match direction {
+        DeviceDirection::Output => host.default_output_device(),
+        DeviceDirection::Input => host.default_input_device(),
+    }?
+    .name()
+    .ok()
+}
+
+// The devices of the default host in the given direction.
+fn host_devices(direction: DeviceDirection) -> anyhow::Result<Vec<Device>> {
+    let host = cpal::default_host();
+    let devices = match direction {
+        DeviceDirection::Output => host.output_devices().map(Iterator::collect),
+        DeviceDirection::Input => host.input_devices().map(Iterator::collect),
+    };
+    devices.map_err(|e| anyhow::anyhow!("Failed to list audio devices: {e:?}"))
+}
+
+// List every output and input device of the default host, in that order.
+pub fn list_devices() -> Vec<AudioDevice> {
+    let mut devices = Vec::new();
+    for direction in [DeviceDirection::Output, DeviceDirection::Input] {
+        match host_devices(direction) {
+            Ok(found) => devices.extend(found.iter().filter_map(|device| {
+                Some(AudioDevice {
+                    name: device.name().ok()?,
+                    direction,
+                })
+            })),
+            Err(e) => log!("{e:?}"),
+        }
+    }
+    devices
+}
+
+// Find the named device of the default host, along with its default stream config.
+fn find_device(selected: &AudioDevice) -> anyhow::Result<(Device, SupportedStreamConfig)> {
+    let device = host_devices(selected.direction)?
+        .into_iter()
+        .find(|device| device.name().is_ok_and(|name| name == selected.name))
+        .ok_or_else(|| anyhow::anyhow!("There is no audio device named {:?}", selected.name))?;
+
+    let audio_config = match selected.direction {
+        DeviceDirection::Output => device.default_output_config(),
+        DeviceDirection::Input => device.default_input_config(),
+    }
+    .map_err(|e| anyhow::anyhow!("Could not find default audio format: {e:?}"))?;
+    Ok((device, audio_config))
+}
+
+impl LoopbackSource {
+    // Determine the selected device, or else the default audio-out device, and send the processed audio stream
+    // back to caller through the given asynchronous channel.
+    // A selected device which cannot be found or captured falls back to the default, which is then followed as it changes
+    pub fn new(
+        tx: Sender<State>,
+        analysis_config: SharedAnalysisConfig,
+        selected: Option<&AudioDevice>,
+        follows_default: bool,
+    ) -> anyhow::Result<Self> {
+        let capture =
+            |device: Device, selected: AudioDevice, audio_config: SupportedStreamConfig| {
+                log!("Default config of the audio device: {audio_config:?}");
+                let (stream, status_rx) = start_stream(
+                    &device,
+                    selected.direction,
+                    &audio_config,
+                    tx.clone(),
+                    analysis_config.clone(),
+                )?;
+                anyhow::Ok((device, selected, audio_config, stream, status_rx))
+            };
+        let selected_capture = selected.and_then(|selected| {
+            find_device(selected)
+                .and_then(|(device, audio_config)| capture(device, selected.clone(), audio_config))
+                .map_err(|e| log!("Capturing the default audio out instead: {e:?}"))
+                .ok()
+        });
+
+        // Create loopback capture stream, failing if the app cannot capture audio-out either
+        let (follows_default, (device, selected, audio_config, stream, status_rx)) =
+            match selected_capture {
+                Some(capture) => (follows_default, capture),
+                None => {
+                    let (default_audio_out, name, audio_config) = default_output()?;
+                    log!("Default audio out: {name:?}");
+                    let selected = AudioDevice {
+                        name,
+                        direction: DeviceDirection::Output,
+                    };
+                    (true, capture(default_audio_out, selected, audio_config)?)
+                }
+            };
+        let alternate_configs = alternate_configs(&device, selected.direction, &audio_config);
+
+        Ok(Self {
+            device,
+            device_name: selected.name,
+            direction: selected.direction,
+            audio_config,
+            stream,
+            status_rx,
+            tx,
+            analysis_config,
+            alternate_configs,
+            follows_default,
+            last_default_check: Instant::now(),
+            paused: false,
+        })
+    }
+
+    // Whether the system's default device has changed since it was captured, or there is no longer a default.
+    fn default_changed(&mut self) -> bool {
+        if !self.follows_default || self.last_default_check.elapsed() < DEFAULT_DEVICE_POLL {
+            return false;
+        }
+        self.last_default_check = Instant::now();
+        match default_device_name(self.direction) {
+            Some(name) if name == self.device_name => false,
+            Some(name) => {
+                log!("The default audio device changed to {name:?}");
+                true
+            }
+            None => {
+                log!("There is no longer a default audio device");
+                true
+            }
+        }
+    }
+
+    // Restart capture with the next untried stream config, since some configs only receive silence.
+    fn retry_alternate_config(&mut self) {
+        while !self.alternate_configs.is_empty() {
+            let audio_config = self.alternate_configs.remove(0);
+            log!("Loopback is receiving only silence, retrying with config: {audio_config:?}");
+            match start_stream(
+                &self.device,
+                self.direction,
+                &audio_config,
+                self.tx.clone(),
+                self.analysis_config.clone(),
+            ) {
+                Ok((stream, status_rx)) => {
+                    self.audio_config = audio_config;
+                    self.stream = stream;
+                    self.status_rx = status_rx;
+                    return;
+                }
+                Err(e) => log!("Failed to capture with alternate config: {e:?}"),
+            }
+        }
+    }
+}
+
+impl AudioSource for LoopbackSource {
+    fn description(&self) -> String {
+        match self.direction {
+            DeviceDirection::Output => format!("Loopback: {}", self.device_name),
+            DeviceDirection::Input => format!("Input: {}", self.device_name),
+        }
+    }
+
+    fn pause(&mut self) {
+        self.paused = true;
+        if let Err(e) = self.stream.pause() {
+            log!("Failed to pause loopback stream: {e:?}");
+        }
+    }
+
+    // A lost stream outweighs any other status received with it, since nothing follows until it is recreated.
+    fn poll_status(&mut self) -> Option<SourceStatus> {
+        if self.paused {
+            return None;
+        }
+        if self.default_changed() {
+            return Some(SourceStatus::Lost);
+        }
+        let statuses: Vec<_> = self.status_rx.try_iter().collect();
+        if statuses.contains(&SourceStatus::Lost) {
+            return Some(SourceStatus::Lost);
+        }
+        let status = statuses.last().copied()?;
+        if status == SourceStatus::Silent {
+            self.retry_alternate_config();
+        }
+        Some(status)
+    }
+
+    fn stream_info(&self) -> Option<StreamInfo> {
+        Some(StreamInfo {
+            device_name: self.device_name.clone(),
+            sample_rate: self.audio_config.sample_rate().0,
+            channel_count: self.audio_config.channels(),
+        })
+    }
+}
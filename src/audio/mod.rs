@@ -0,0 +1,996 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use arc_swap::ArcSwap;
+use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
+use rustfft::{num_complex::Complex, FftPlanner};
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+
+use crate::app_config::AttractorCounts;
+use crate::my_math::{Vector2, Vector3, Vector4};
+use crate::space_filling_curves;
+use crate::space_filling_curves::{cube::curve_to_cube_n, square::curve_to_square_n};
+
+mod beat;
+mod chroma;
+pub mod file_source;
+mod gain;
+pub mod generator;
+mod loopback;
+mod network;
+mod presets;
+mod session;
+mod stems;
+mod stereo;
+
+pub use beat::BeatInfo;
+pub use chroma::MusicalKey;
+pub use gain::{GainControl, DEFAULT_GAIN_CONTROL};
+pub use generator::SignalKind;
+pub use loopback::{default_output, is_supported_format, list_devices, AudioDevice};
+pub use presets::BUILTIN_PRESETS;
+pub use session::SessionRecorder;
+pub use stems::{Stem, StemChannel, StemSources};
+pub use stereo::{place_toward, Direction};
+
+// The number of log-spaced bins in the coarse spectrum sent with each state, for display.
+pub const SPECTRUM_BINS: usize = 32;
+
+// The range of frequencies in hertz covered by the coarse spectrum.
+pub const SPECTRUM_MIN_FREQUENCY: f32 = 30.;
+pub const SPECTRUM_MAX_FREQUENCY: f32 = 12_000.;
+
+// Set some constants for scaling frequencies to sound/appear more linear.
+pub const BASS_POW: f32 = 0.84;
+pub const MIDS_POW: f32 = 0.75;
+pub const HIGH_POW: f32 = 0.445;
+
+const BASS_KICK: f32 = 0.05;
+const PREVIOUS_BASS_COUNT: usize = 16;
+
+// The most notes of each band which may become particle attractors. Keep in sync with the attractor arrays of `particles.comp`
+pub const MAX_BASS_ATTRACTORS: usize = 4;
+pub const MAX_MIDS_ATTRACTORS: usize = 8;
+pub const MAX_HIGH_ATTRACTORS: usize = 8;
+
+// How many notes of each band become particle attractors unless configured otherwise.
+pub const DEFAULT_BASS_ATTRACTORS: usize = 1;
+pub const DEFAULT_MIDS_ATTRACTORS: usize = 2;
+pub const DEFAULT_HIGH_ATTRACTORS: usize = 2;
+
+// Experimentally determined to be the maximum number of bass frequency buckets.
+const MAX_BASS_BUCKET_COUNT: usize = 11;
+
+// Samples sent to the processing thread at once when analyzing offline.
+const OFFLINE_CHUNK_SIZE: usize = 4800;
+
+// Tunable parameters used when analyzing a single frequency band.
+#[derive(Clone, Copy, PartialEq)]
+pub struct BandConfig {
+    pub min_frequency: f32,
+    pub max_frequency: f32,
+    pub delta: f32,
+    pub min_volume: f32,
+    pub vol_freq_scale: f32,
+    pub attractors: usize, // How many of the loudest notes become attractors, at most the band's maximum
+}
+
+// Tunable thresholds for detecting kicks from the loudest bass note.
+#[derive(Clone, Copy, PartialEq)]
+pub struct KickConfig {
+    pub min_magnitude: f32, // The least magnitude of the loudest bass note which may be a kick
+    pub history_ratio: f32, // How many times louder than the recent bass history a kick must be
+    pub min_interval: f32,  // Seconds since the last kick before another may occur
+}
+
+// A snapshot of all runtime-tunable audio-analysis parameters.
+#[derive(Clone, Copy, PartialEq)]
+pub struct AnalysisConfig {
+    pub bass: BandConfig,
+    pub mids: BandConfig,
+    pub high: BandConfig,
+    pub kick: KickConfig,
+    pub gain: GainControl,
+}
+
+// Lock-free handle shared between the UI and audio threads.
+// The UI publishes whole new snapshots and the audio thread loads the latest once per analysis.
+pub type SharedAnalysisConfig = Arc<ArcSwap<AnalysisConfig>>;
+
+pub fn new_shared_analysis_config(config: AnalysisConfig) -> SharedAnalysisConfig {
+    Arc::new(ArcSwap::from_pointee(config))
+}
+
+// The kinds of audio input which can feed the analysis pipeline.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SourceKind {
+    Loopback,
+    Microphone, // Capture the default audio-in device, to react to live instruments
+    Generator(SignalKind),
+    Network(u16), // Receive analyzed states published by another instance to this UDP port
+}
+
+// A file played back in place of any live audio source.
+#[derive(Clone, PartialEq)]
+pub enum FileInput {
+    Audio(PathBuf),   // An audio file, decoded and analyzed as it plays
+    Session(PathBuf), // A session recorded with `--record`, whose states were already analyzed
+}
+
+// A running producer of audio samples for the analysis pipeline.
+pub trait AudioSource {
+    // Human-readable description of where the samples come from.
+    fn description(&self) -> String;
+
+    // Stop delivering samples. Sources are recreated to resume.
+    fn pause(&mut self);
+
+    // The latest change in the status of the source, if any. Polled each frame, giving sources a chance to recover.
+    fn poll_status(&mut self) -> Option<SourceStatus> {
+        None
+    }
+
+    // Details of the stream being captured, for sources capturing from a device.
+    fn stream_info(&self) -> Option<StreamInfo> {
+        None
+    }
+}
+
+// The stream a source captures from a device, for diagnosing a source which does not react to the audio.
+#[derive(Clone)]
+pub struct StreamInfo {
+    pub device_name: String,
+    pub sample_rate: u32,
+    pub channel_count: u16,
+}
+
+// Changes in the health of an audio source, reported by its capture callback over the source's status channel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SourceStatus {
+    Audible,
+    Silent, // Only samples of exactly zero have been delivered for a sustained period
+    Lost,   // The stream failed or its device changed, so the source must be recreated
+}
+
+impl SourceStatus {
+    // The warning to show while the source has this status, if any.
+    pub fn warning(self) -> Option<&'static str> {
+        match self {
+            Self::Audible => None,
+            Self::Silent => Some("Loopback receiving silence \u{2014} check exclusive mode/DRM"),
+            Self::Lost => Some("Audio device lost \u{2014} reconnecting"),
+        }
+    }
+}
+
+// A source which never delivers samples, used to launch without capturing audio.
+// It holds the sender so that the channel remains open for the lifetime of the source
+pub struct SilentSource {
+    _tx: Sender<State>,
+    unavailable: bool, // Whether it stands in for a source which could not be created
+    lost: bool,        // Whether it is yet to report itself lost, so that the source is retried
+}
+
+impl SilentSource {
+    pub fn new(tx: Sender<State>) -> Self {
+        Self {
+            _tx: tx,
+            unavailable: false,
+            lost: false,
+        }
+    }
+
+    // Stand in for a source which could not be created, such as while no audio device is plugged in.
+    pub fn unavailable(tx: Sender<State>) -> Self {
+        Self {
+            _tx: tx,
+            unavailable: true,
+            lost: true,
+        }
+    }
+}
+
+impl AudioSource for SilentSource {
+    fn description(&self) -> String {
+        String::from(if self.unavailable {
+            "Unavailable"
+        } else {
+            "None"
+        })
+    }
+
+    fn pause(&mut self) {
+        self.lost = false;
+    }
+
+    fn poll_status(&mut self) -> Option<SourceStatus> {
+        std::mem::take(&mut self.lost).then_some(SourceStatus::Lost)
+    }
+}
+
+// Create the requested audio source and begin sending its analyzed state over the given channel.
+// Loopback sources capture the given device if it is an output, and otherwise the default audio out.
+// Microphone sources likewise capture the given device if it is an input, and otherwise the default audio in
+pub fn create_source_and_send(
+    kind: SourceKind,
+    tx: Sender<State>,
+    analysis_config: SharedAnalysisConfig,
+    device: Option<&AudioDevice>,
+) -> anyhow::Result<Box<dyn AudioSource>> {
+    Ok(match kind {
+        SourceKind::Loopback => {
+            let selected = device.filter(|device| !device.is_input());
+            Box::new(loopback::LoopbackSource::new(
+                tx,
+                analysis_config,
+                selected,
+                selected.is_none(),
+            )?)
+        }
+        SourceKind::Microphone => {
+            let selected = device.filter(|device| device.is_input()).cloned();
+            let follows_default = selected.is_none();
+            let microphone = selected.or_else(loopback::default_input);
+            Box::new(loopback::LoopbackSource::new(
+                tx,
+                analysis_config,
+                microphone.as_ref(),
+                follows_default,
+            )?)
+        }
+        SourceKind::Generator(signal) => {
+            Box::new(generator::GeneratorSource::new(signal, tx, analysis_config))
+        }
+        SourceKind::Network(port) => Box::new(network::NetworkSource::new(port, tx)),
+    })
+}
+
+// Create the source playing back the given file and begin sending its analyzed state over the given channel.
+pub fn create_file_source_and_send(
+    input: &FileInput,
+    tx: Sender<State>,
+    analysis_config: SharedAnalysisConfig,
+) -> Box<dyn AudioSource> {
+    match input {
+        FileInput::Audio(path) => Box::new(file_source::FileSource::new(path, tx, analysis_config)),
+        FileInput::Session(path) => Box::new(session::ReplaySource::new(path, tx)),
+    }
+}
+
+// Simple type to represent a single note with a normalized frequency and a strength.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Note {
+    pub freq: f32,
+    pub mag: f32,
+}
+impl Note {
+    pub const fn new(freq: f32, mag: f32) -> Self {
+        Self { freq, mag }
+    }
+}
+
+// Audio state to pass to UI thread, or to another instance over the network
+#[derive(Default, Serialize, Deserialize)]
+pub struct State {
+    pub volume: f32,
+
+    // Notes for each instrument range (bass/mids/high).
+    // Allow caller to determine mapping notes to space
+    // Each band sends one note per configured attractor, though the loudest bass note is always sent, silenced without a big boomer
+    pub bass_note: Note,
+    #[serde(default)]
+    pub extra_bass_notes: Vec<Note>, // The quieter bass notes, each an additional big boomer
+    pub mids_notes: Vec<Note>,
+    pub high_notes: Vec<Note>,
+
+    // 3D (Fractals)
+    pub kick_angular_velocity: Option<Vector4>,
+    pub reactive_bass: Vector3,
+    pub reactive_mids: Vector3,
+    pub reactive_high: Vector3,
+
+    // The dominant key over the last several seconds, if the audio is loud enough to estimate one
+    #[serde(default)]
+    pub key: Option<MusicalKey>,
+
+    // The estimated tempo and beat phase, once onsets repeat regularly enough to estimate one
+    #[serde(default)]
+    pub beat: Option<BeatInfo>,
+
+    // The apparent direction of each band in the stereo field
+    #[serde(default)]
+    pub bass_direction: Direction,
+    #[serde(default)]
+    pub mids_direction: Direction,
+    #[serde(default)]
+    pub high_direction: Direction,
+
+    // The average magnitude within each log-spaced bin of the spectrum, from lowest to highest frequency
+    #[serde(default)]
+    pub spectrum: [f32; SPECTRUM_BINS],
+
+    // How near the loudest bass note came to triggering a kick
+    #[serde(default)]
+    pub kick_meter: KickMeter,
+
+    // The loudest mids and high notes of the left channel and then the right, each channel given half of the band's attractors.
+    // With an odd attractor count, the loudest note of both channels together lies between them
+    #[serde(default)]
+    pub mids_channel_notes: Vec<Note>,
+    #[serde(default)]
+    pub high_channel_notes: Vec<Note>,
+}
+
+// The loudest bass note's magnitude beside the magnitude it must exceed to trigger a kick.
+// A kick may still be withheld while the minimum interval since the last has not yet passed
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct KickMeter {
+    pub magnitude: f32,
+    pub threshold: f32,
+}
+
+// Type to retrieve results from `analyze_frequency_range` helper
+struct FrequencyAnalysis {
+    pub loudest: SmallVec<[Note; 2]>,
+    pub total_volume: f32,
+}
+
+// Type to retrieve results from `analyze_audio_frequencies` helper
+struct SpectrumAnalysis {
+    pub bass_analysis: FrequencyAnalysis,
+    pub current_bass: SmallVec<[f32; MAX_BASS_BUCKET_COUNT]>,
+    pub mids_analysis: FrequencyAnalysis,
+    pub high_analysis: FrequencyAnalysis,
+}
+
+// Type for storing state and history of bass notes
+struct BassHistoryAndState {
+    pub kick_angular_velocity: Option<Vector4>,
+    pub last_kick: Instant,
+    pub previous_bass_index: usize,
+    pub previous_bass: [Option<SmallVec<[f32; MAX_BASS_BUCKET_COUNT]>>; PREVIOUS_BASS_COUNT],
+}
+
+// Type to help with passing re-used information in `analyze_audio_frequencies` helper
+struct AudioChunkHelper<'a> {
+    complex: &'a [Complex<f32>],
+    size: usize,
+    scale: f32,
+    frequency_resolution: f32,
+}
+
+// Convert note analysis to 4D vector containing position and note strength.
+pub fn map_note_to_square(note: Note, pow: f32) -> Vector4 {
+    let Vector2 { x, y } = 0.95 * curve_to_square_n(note.freq.powf(pow), 5);
+    Vector4::new(x, y, 0., note.mag)
+}
+pub fn map_note_to_cube(note: Note, pow: f32) -> Vector4 {
+    let Vector3 { x, y, z, .. } = 0.9 * map_freq_to_cube(note.freq, pow);
+    Vector4::new(x, y, z, note.mag)
+}
+
+// Mirror the attractors of the left channel's notes onto the left side, and those of the right onto the right.
+pub fn mirror_to_sides(attractors: &[Vector4]) -> Vec<Vector4> {
+    let per_channel = attractors.len() / 2;
+    attractors
+        .iter()
+        .enumerate()
+        .map(|(i, attractor)| {
+            let x = if i < per_channel {
+                -attractor.x.abs()
+            } else if i >= attractors.len() - per_channel {
+                attractor.x.abs()
+            } else {
+                attractor.x // Following both channels, it favors neither side
+            };
+            Vector4::new(x, attractor.y, attractor.z, attractor.w)
+        })
+        .collect()
+}
+
+// Count of sample chunks dropped because the processing thread could not keep up.
+static DROPPED_CHUNK_COUNT: AtomicU64 = AtomicU64::new(0);
+
+// The total number of sample chunks which have been dropped since launch.
+pub fn dropped_chunk_count() -> u64 {
+    DROPPED_CHUNK_COUNT.load(Ordering::Relaxed)
+}
+
+// The frames in the latest chunk delivered by a source, and the samples analyzed with each update.
+static LATEST_CHUNK_FRAMES: AtomicUsize = AtomicUsize::new(0);
+static ANALYSIS_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+pub fn latest_chunk_frames() -> usize {
+    LATEST_CHUNK_FRAMES.load(Ordering::Relaxed)
+}
+pub fn analysis_size() -> usize {
+    ANALYSIS_SIZE.load(Ordering::Relaxed)
+}
+
+// Send a chunk of samples for processing without blocking the source, dropping it if the channel is full.
+// Returns `false` if the processing thread has disconnected.
+fn send_chunk(tx_acc: &Sender<Vec<Complex<f32>>>, chunk: Vec<Complex<f32>>) -> bool {
+    LATEST_CHUNK_FRAMES.store(chunk.len(), Ordering::Relaxed);
+    match tx_acc.try_send(chunk) {
+        Ok(()) => true,
+        Err(TrySendError::Full(_)) => {
+            DROPPED_CHUNK_COUNT.fetch_add(1, Ordering::Relaxed);
+            true
+        }
+        Err(TrySendError::Disconnected(_)) => false,
+    }
+}
+
+// The number of samples analyzed for each state. Use a fixed power-of-two for best performance
+fn analysis_size_for(sample_rate: f32) -> usize {
+    if sample_rate > 48_000. {
+        4096
+    } else {
+        2048
+    }
+}
+
+// Seconds of audio analyzed for each state at the given sample rate, which is also the time between states.
+#[allow(clippy::cast_precision_loss)]
+pub fn analysis_interval(sample_rate: u32) -> f32 {
+    analysis_size_for(sample_rate as f32) as f32 / sample_rate as f32
+}
+
+// Analyze the given stereo samples as fast as possible rather than in real-time, returning every state in order.
+pub fn analyze_offline(
+    sample_rate: u32,
+    samples: impl Iterator<Item = (f32, f32)>,
+    analysis_config: SharedAnalysisConfig,
+) -> Vec<State> {
+    // Chunks are never dropped, so the states must be collected without bound.
+    let (tx, rx) = crossbeam_channel::unbounded();
+    #[allow(clippy::cast_precision_loss)]
+    let tx_acc = spawn_audio_processing_thread(sample_rate as f32, tx, analysis_config);
+
+    let mut samples = samples
+        .map(|(left, right)| stereo::pack(left, right))
+        .peekable();
+    while samples.peek().is_some() {
+        let chunk = samples.by_ref().take(OFFLINE_CHUNK_SIZE).collect();
+        if tx_acc.send(chunk).is_err() {
+            break;
+        }
+    }
+
+    // The processing thread ends once it has analyzed every complete chunk, closing the channel.
+    drop(tx_acc);
+    rx.iter().collect()
+}
+
+// Create a new thread for retrieving and processing audio chunks. Results are sent over channel.
+// Returns the channel that sources must send their sample chunks to, each sample packed from both channels by `stereo::pack`.
+fn spawn_audio_processing_thread(
+    sample_rate: f32,
+    tx: Sender<State>,
+    analysis_config: SharedAnalysisConfig,
+) -> Sender<Vec<Complex<f32>>> {
+    // Create an accumulator channel to compose enough bytes for a reasonable FFT
+    let (tx_acc, rx_acc) = bounded(4);
+
+    std::thread::spawn(move || {
+        if let Err(e) = process_audio_chunks(sample_rate, &rx_acc, &tx, &analysis_config) {
+            log!("Stopped processing audio: {e:?}");
+        }
+    });
+
+    tx_acc
+}
+
+// Analyze each full buffer of the sample chunks received, sending the resulting state over the given channel.
+// Ends once the source stops sending chunks, or fails once the state can no longer be delivered
+fn process_audio_chunks(
+    sample_rate: f32,
+    rx_acc: &Receiver<Vec<Complex<f32>>>,
+    tx: &Sender<State>,
+    analysis_config: &SharedAnalysisConfig,
+) -> anyhow::Result<()> {
+    // Calculate some processing constants outside loop
+    let size = analysis_size_for(sample_rate);
+    let size_float = size as f32; // Size of the sample buffer as floating point
+    let scale = 1. / size_float.sqrt(); // Rescale elements by 1/sqrt(n)
+    let frequency_resolution = sample_rate / size_float; // Hertz per frequency bin after applying FFT
+    ANALYSIS_SIZE.store(size, Ordering::Relaxed);
+
+    // Store audio in a resizable array before processing, with some extra space to try to avoid heap allocations
+    let mut audio_storage_buffer: Vec<Complex<f32>> = Vec::with_capacity(size + 1024);
+
+    // Create factory and FFT once based on size
+    let mut planner = FftPlanner::<f32>::new();
+    let fft = planner.plan_fft_forward(size);
+
+    // Keep track of state that we don't want UI to need to calculate
+    let mut bass_state = BassHistoryAndState::default();
+    let mut chroma = chroma::ChromaTracker::default();
+    let mut beat_tracker = beat::BeatTracker::new(size_float / sample_rate);
+    let mut stereo = stereo::StereoSpectrum::default();
+    let mut gain = gain::GainTracker::default();
+
+    loop {
+        // Append incoming audio data until we have sufficient samples
+        while audio_storage_buffer.len() < size {
+            let Ok(mut d) = rx_acc.recv() else {
+                return Ok(());
+            };
+            audio_storage_buffer.append(&mut d);
+        }
+        let complex = &mut audio_storage_buffer[0..size];
+
+        // Retrieve the latest analysis parameters published by the UI thread
+        let config = **analysis_config.load();
+
+        // Normalize the loudness of the input, then perform FFT on data in-place and separate the channels
+        gain.apply(complex, size_float / sample_rate, &config.gain);
+        fft.process(complex);
+        stereo.split(complex);
+
+        // Estimate where each band is heard from
+        let band_direction = |band: &BandConfig| {
+            stereo.direction(
+                hertz_to_index(band.min_frequency, size, frequency_resolution)
+                    ..hertz_to_index(band.max_frequency, size, frequency_resolution),
+            )
+        };
+
+        // Analyze each frequency ranges
+        let audio_chunk = AudioChunkHelper {
+            complex,
+            size,
+            scale,
+            frequency_resolution,
+        };
+        let SpectrumAnalysis {
+            bass_analysis,
+            current_bass,
+            mids_analysis,
+            high_analysis,
+        } = analyze_audio_frequencies(&audio_chunk, &config);
+
+        // Find the loudest notes heard in each channel, for attractors following the left and right separately.
+        // Each channel is given half of the band's attractors, and any attractor left over follows the mix of both
+        let (left, right) = stereo.channels();
+        let channel_notes = |band: &BandConfig, mix: &FrequencyAnalysis| -> Vec<Note> {
+            let per_channel = band.attractors / 2;
+            let [left, right]: [Vec<Note>; 2] = [left, right].map(|spectrum| {
+                if per_channel == 0 {
+                    return Vec::new();
+                }
+                let chunk = AudioChunkHelper {
+                    complex: spectrum,
+                    size: spectrum.len(),
+                    scale,
+                    frequency_resolution,
+                };
+                let analysis = analyze_frequency_range(
+                    band.min_frequency..band.max_frequency,
+                    per_channel,
+                    band.delta,
+                    band.min_volume,
+                    band.vol_freq_scale,
+                    &chunk,
+                );
+                (0..per_channel)
+                    .map(|i| analysis.loudest.get(i).copied().unwrap_or_default())
+                    .collect()
+            });
+            let both = (band.attractors % 2 == 1)
+                .then(|| mix.loudest.first().copied().unwrap_or_default());
+            left.into_iter().chain(both).chain(right).collect()
+        };
+        let mids_channel_notes = channel_notes(&config.mids, &mids_analysis);
+        let high_channel_notes = channel_notes(&config.high, &high_analysis);
+
+        // Estimate the musical key from the pitch classes heard over the rolling window
+        let key = chroma.update(&audio_chunk, sample_rate);
+
+        // Detect onsets by spectral flux and track the tempo they repeat at
+        let beat = beat_tracker.update(&audio_chunk);
+
+        // Get total volume from all (relevant) frequencies
+        let volume =
+            bass_analysis.total_volume + mids_analysis.total_volume + high_analysis.total_volume;
+
+        // Update bass state and history
+        let kick_meter =
+            update_bass_history(&mut bass_state, &bass_analysis, current_bass, &config.kick);
+
+        // Send updated state to UI thread
+        let bass_notes = attractor_notes(&bass_analysis, &config.bass);
+        let (bass_note, extra_bass_notes) = match bass_notes.split_first() {
+            Some((loudest, rest)) => (*loudest, rest.to_vec()),
+            None => (Note::default(), Vec::new()),
+        };
+        tx.send(State {
+            volume,
+
+            bass_note,
+            extra_bass_notes,
+            mids_notes: attractor_notes(&mids_analysis, &config.mids),
+            high_notes: attractor_notes(&high_analysis, &config.high),
+
+            kick_angular_velocity: bass_state.kick_angular_velocity.take(),
+            reactive_bass: map_freq_to_cube(bass_analysis.loudest[0].freq, BASS_POW),
+            reactive_mids: map_freq_to_cube(mids_analysis.loudest[0].freq, MIDS_POW),
+            reactive_high: map_freq_to_cube(high_analysis.loudest[0].freq, HIGH_POW),
+
+            key,
+            beat,
+
+            bass_direction: band_direction(&config.bass),
+            mids_direction: band_direction(&config.mids),
+            high_direction: band_direction(&config.high),
+
+            spectrum: downsample_spectrum(&audio_chunk),
+            kick_meter,
+
+            mids_channel_notes,
+            high_channel_notes,
+        })
+        .map_err(|_| anyhow::anyhow!("The UI thread's receiver disconnected"))?;
+
+        // Copy elements with index >= `size` to the start of array since they haven't been used yet
+        audio_storage_buffer.copy_within(size.., 0);
+        audio_storage_buffer.truncate(audio_storage_buffer.len() - size);
+    } // end unconditional `loop`
+}
+
+// Convert normalized frequency to position in cube
+fn map_freq_to_cube(freq: f32, pow: f32) -> Vector3 {
+    curve_to_cube_n(freq.powf(pow), 6)
+}
+
+// Helper function for converting frequency in range [0, 1] to
+#[allow(clippy::cast_sign_loss)]
+fn normalized_frequency_to_index(f: f32, size: usize) -> usize {
+    let max = size - 1;
+    max.min(((max as f32) * f).round() as usize)
+}
+
+// Helper function for converting frequency in Hertz to buffer index
+#[allow(clippy::cast_sign_loss)]
+fn hertz_to_index(f: f32, size: usize, frequency_resolution: f32) -> usize {
+    (size - 1).min((f / frequency_resolution).round() as usize)
+}
+
+// Create helper closure for determining the loudest frequency bin(s) within a frequency range
+fn analyze_frequency_range(
+    frequency_range: std::ops::Range<f32>,
+    count: usize,
+    mut delta: f32,
+    min_volume: f32,
+    vol_freq_scale: f32,
+    audio_chunk: &AudioChunkHelper,
+) -> FrequencyAnalysis {
+    let start_index = hertz_to_index(
+        frequency_range.start,
+        audio_chunk.size,
+        audio_chunk.frequency_resolution,
+    );
+    let end_index = hertz_to_index(
+        frequency_range.end,
+        audio_chunk.size,
+        audio_chunk.frequency_resolution,
+    );
+    let len = end_index.saturating_sub(start_index);
+    let len_float = len as f32;
+    delta /= 2.; // Allow caller to specify total width, even though we use distance from center
+
+    // Create sorted array of notes in this frequency range
+    let mut total_volume = 0.;
+    let mut sorted: Vec<Note> = (0..len)
+        .map(|i| {
+            let frac = i as f32 / len_float;
+            let v = audio_chunk.scale * audio_chunk.complex[start_index + i].norm();
+            total_volume += v;
+            Note::new(frac, f32::powf(vol_freq_scale, frac) * v)
+        })
+        .collect();
+
+    let mut loudest = SmallVec::with_capacity(count);
+    while !sorted.is_empty() && loudest.len() < count {
+        sorted.sort_unstable_by(|x, y| {
+            y.mag
+                .partial_cmp(&x.mag)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let Note { freq, mag } = sorted[0];
+        let remaining: Vec<Note> = sorted
+            .into_iter()
+            .filter(|x| (freq - x.freq).abs() > delta)
+            .collect();
+
+        // Update the strongest and the remaining lists. Reject values too quiet
+        loudest.push(Note::new(freq, if mag >= min_volume { mag } else { 0. }));
+        sorted = remaining;
+    }
+
+    // Calling code assumes the requested number of notes will be returned.
+    // Tunable ranges may be too narrow to contain enough notes, so pad with silence.
+    loudest.resize(count, Note::default());
+
+    FrequencyAnalysis {
+        loudest,
+        total_volume,
+    }
+}
+
+// Average the magnitudes of the spectrum within each of its log-spaced bins, scaled as the bands are.
+#[allow(clippy::cast_precision_loss)]
+fn downsample_spectrum(audio_chunk: &AudioChunkHelper) -> [f32; SPECTRUM_BINS] {
+    let ratio = SPECTRUM_MAX_FREQUENCY / SPECTRUM_MIN_FREQUENCY;
+    let edge = |i: usize| {
+        hertz_to_index(
+            SPECTRUM_MIN_FREQUENCY * ratio.powf(i as f32 / SPECTRUM_BINS as f32),
+            audio_chunk.size,
+            audio_chunk.frequency_resolution,
+        )
+    };
+    std::array::from_fn(|i| {
+        let start = edge(i);
+        let end = edge(i + 1).max(start + 1);
+        let total: f32 = audio_chunk.complex[start..end]
+            .iter()
+            .map(|c| c.norm())
+            .sum();
+        audio_chunk.scale * total / (end - start) as f32
+    })
+}
+
+// Select the loudest notes of a band, one for each of its configured attractors.
+fn attractor_notes(analysis: &FrequencyAnalysis, band: &BandConfig) -> Vec<Note> {
+    (0..band.attractors)
+        .map(|i| analysis.loudest.get(i).copied().unwrap_or_default())
+        .collect()
+}
+
+// Given an audio chunk, determine information about bass, mids, and highs
+fn analyze_audio_frequencies(
+    audio_chunk: &AudioChunkHelper,
+    config: &AnalysisConfig,
+) -> SpectrumAnalysis {
+    let (bass_analysis, current_bass) = {
+        let band = &config.bass;
+        // The loudest bass note is always needed to detect kicks, even without a bass attractor
+        let analysis = analyze_frequency_range(
+            band.min_frequency..band.max_frequency,
+            band.attractors.max(1),
+            band.delta,
+            band.min_volume,
+            band.vol_freq_scale,
+            audio_chunk,
+        );
+
+        // Do extra analysis for bass notes.
+        let current_bass = {
+            let start_index = hertz_to_index(
+                band.min_frequency,
+                audio_chunk.size,
+                audio_chunk.frequency_resolution,
+            );
+            let end_index = hertz_to_index(
+                band.max_frequency,
+                audio_chunk.size,
+                audio_chunk.frequency_resolution,
+            );
+
+            // Get number of frequency buckets in the bass range.
+            let len = end_index.saturating_sub(start_index);
+            let len_f32 = len as f32;
+
+            // Get the volume of each frequency bin.
+            (0..len)
+                .map(|i| {
+                    let frac = i as f32 / len_f32;
+                    let v = audio_chunk.scale * audio_chunk.complex[start_index + i].norm();
+                    f32::powf(band.vol_freq_scale, frac) * v
+                })
+                .collect()
+        };
+
+        (analysis, current_bass)
+    };
+    let mids_analysis = {
+        let band = &config.mids;
+        analyze_frequency_range(
+            band.min_frequency..band.max_frequency,
+            band.attractors.max(1),
+            band.delta,
+            band.min_volume,
+            band.vol_freq_scale,
+            audio_chunk,
+        )
+    };
+    let high_analysis = {
+        let band = &config.high;
+        analyze_frequency_range(
+            band.min_frequency..band.max_frequency,
+            band.attractors.max(1),
+            band.delta,
+            band.min_volume,
+            band.vol_freq_scale,
+            audio_chunk,
+        )
+    };
+
+    SpectrumAnalysis {
+        bass_analysis,
+        current_bass,
+        mids_analysis,
+        high_analysis,
+    }
+}
+
+// Update the state and history of bass notes given the latest bass analysis
+fn update_bass_history(
+    bass_state: &mut BassHistoryAndState,
+    bass_analysis: &FrequencyAnalysis,
+    current_bass: SmallVec<[f32; MAX_BASS_BUCKET_COUNT]>,
+    kick: &KickConfig,
+) -> KickMeter {
+    // Use analysis of bass notes to determine if a kick should occur
+    let magnitude = bass_analysis.loudest[0].mag;
+    let kick_elapsed = bass_state.last_kick.elapsed().as_secs_f32();
+    let avg_prev_bass: f32 = bass_state.previous_bass.iter().fold(0., |acc, x| {
+        acc + match x {
+            Some(v) => {
+                let l = v.len();
+                if l > 0 {
+                    v[normalized_frequency_to_index(bass_analysis.loudest[0].freq, l)]
+                } else {
+                    0.
+                }
+            }
+            None => 0.,
+        }
+    }) / (bass_state.previous_bass.len() as f32);
+
+    // The loudest bass note must exceed each of these magnitudes, the first relaxing the longer since the last kick
+    let threshold = (8. / kick_elapsed)
+        .min(4.)
+        .max(kick.min_magnitude)
+        .max(kick.history_ratio * avg_prev_bass);
+    if kick_elapsed > kick.min_interval && magnitude > threshold {
+        let v = space_filling_curves::cube::curve_to_cube_n(
+            bass_analysis.loudest[0].freq.powf(BASS_POW),
+            6,
+        );
+        bass_state.kick_angular_velocity = Some(Vector4::new(
+            v.x,
+            v.y,
+            v.z,
+            BASS_KICK * bass_analysis.total_volume.sqrt(),
+        ));
+        bass_state.last_kick = Instant::now();
+    }
+
+    // Update bass history
+    bass_state.previous_bass[bass_state.previous_bass_index] = Some(current_bass);
+    bass_state.previous_bass_index =
+        (bass_state.previous_bass_index + 1) % bass_state.previous_bass.len();
+
+    KickMeter {
+        magnitude,
+        threshold,
+    }
+}
+
+impl Default for BassHistoryAndState {
+    fn default() -> Self {
+        Self {
+            kick_angular_velocity: None,
+            last_kick: Instant::now(),
+            previous_bass_index: 0,
+            previous_bass: Default::default(),
+        }
+    }
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        BUILTIN_PRESETS[0].1
+    }
+}
+
+impl AnalysisConfig {
+    // Use this analysis with the given number of attractors for each band.
+    pub fn with_attractor_counts(mut self, counts: AttractorCounts) -> Self {
+        self.bass.attractors = counts.bass;
+        self.mids.attractors = counts.mids;
+        self.high.attractors = counts.high;
+        self
+    }
+    // Use this analysis with the given gain control, which is configured separately from the presets.
+    pub fn with_gain_control(mut self, gain: GainControl) -> Self {
+        self.gain = gain;
+        self
+    }
+    pub fn attractor_counts(&self) -> AttractorCounts {
+        AttractorCounts {
+            bass: self.bass.attractors,
+            mids: self.mids.attractors,
+            high: self.high.attractors,
+        }
+    }
+}
+
+impl SourceKind {
+    pub const ALL: [Self; 5] = [
+        Self::Loopback,
+        Self::Microphone,
+        Self::Generator(SignalKind::SineSweep),
+        Self::Generator(SignalKind::PinkNoise),
+        Self::Generator(SignalKind::ImpulseTrain),
+    ];
+
+    // The name used to select this source from the command line and display it in the overlay.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Loopback => "loopback",
+            Self::Microphone => "microphone",
+            Self::Generator(SignalKind::SineSweep) => "sine-sweep",
+            Self::Generator(SignalKind::PinkNoise) => "pink-noise",
+            Self::Generator(SignalKind::ImpulseTrain) => "impulse-train",
+            Self::Network(_) => "udp",
+        }
+    }
+}
+
+// Format the source as it is selected from the command line, including the port of network sources.
+impl std::fmt::Display for SourceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Network(port) => write!(f, "udp:{port}"),
+            kind => f.write_str(kind.name()),
+        }
+    }
+}
+
+impl std::str::FromStr for SourceKind {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        // Network sources are selected with the port to listen on, e.g. `udp:9321`.
+        if let Some(port) = s.strip_prefix("udp:") {
+            return port
+                .parse()
+                .map(Self::Network)
+                .map_err(|e| anyhow::anyhow!("Invalid UDP port `{port}`: {e}"));
+        }
+
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|kind| kind.name()).collect();
+                anyhow::anyhow!(
+                    "Unknown audio source `{s}`, expected one of: {}, udp:PORT",
+                    names.join(", ")
+                )
+            })
+    }
+}
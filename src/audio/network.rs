@@ -0,0 +1,110 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crossbeam_channel::{Sender, TrySendError};
+
+use super::{AudioSource, State};
+
+// How often the receiving thread checks whether it should stop.
+const RECEIVE_TIMEOUT: Duration = Duration::from_millis(100);
+
+// A previous source may still be releasing the port, so binding is retried for a short while.
+const BIND_ATTEMPTS: u32 = 10;
+
+// Large enough for any single JSON-encoded state.
+const MAX_DATAGRAM_SIZE: usize = 4096;
+
+// Audio source receiving states analyzed by an `--analysis-only` instance, one JSON datagram each.
+pub struct NetworkSource {
+    port: u16,
+    running: Arc<AtomicBool>,
+}
+
+// Bind the UDP port on all interfaces, retrying while a previous socket is released.
+fn bind_socket(port: u16) -> Option<UdpSocket> {
+    for attempt in 1..=BIND_ATTEMPTS {
+        match UdpSocket::bind(("0.0.0.0", port)) {
+            Ok(socket) => return Some(socket),
+            Err(e) if attempt == BIND_ATTEMPTS => {
+                log!("Failed to listen for audio states on UDP port {port}: {e:?}");
+            }
+            Err(_) => std::thread::sleep(RECEIVE_TIMEOUT),
+        }
+    }
+    None
+}
+
+impl NetworkSource {
+    // Spawn a thread which forwards each received state to the given channel.
+    pub fn new(port: u16, tx: Sender<State>) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+        std::thread::spawn(move || {
+            let Some(socket) = bind_socket(port) else {
+                return;
+            };
+            if let Err(e) = socket.set_read_timeout(Some(RECEIVE_TIMEOUT)) {
+                log!("Failed to set the UDP read timeout: {e:?}");
+                return;
+            }
+
+            let mut buffer = [0; MAX_DATAGRAM_SIZE];
+            while thread_running.load(Ordering::Relaxed) {
+                // Timeouts only give the loop a chance to check whether it should stop.
+                let Ok(size) = socket.recv(&mut buffer) else {
+                    continue;
+                };
+                let state = match serde_json::from_slice::<State>(&buffer[..size]) {
+                    Ok(state) => state,
+                    Err(e) => {
+                        log!("Ignoring malformed audio state datagram: {e}");
+                        continue;
+                    }
+                };
+
+                // States are already analyzed, so a full channel simply skips the latest.
+                if let Err(TrySendError::Disconnected(_)) = tx.try_send(state) {
+                    return;
+                }
+            }
+        });
+
+        Self { port, running }
+    }
+}
+
+impl AudioSource for NetworkSource {
+    fn description(&self) -> String {
+        format!("Network: UDP port {}", self.port)
+    }
+
+    fn pause(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Drop for NetworkSource {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,106 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{
+    AnalysisConfig, BandConfig, KickConfig, DEFAULT_BASS_ATTRACTORS, DEFAULT_GAIN_CONTROL,
+    DEFAULT_HIGH_ATTRACTORS, DEFAULT_MIDS_ATTRACTORS,
+};
+
+// Helper for defining a band of a preset, which leaves the attractor count to be chosen separately.
+const fn band(
+    min_frequency: f32,
+    max_frequency: f32,
+    delta: f32,
+    min_volume: f32,
+    vol_freq_scale: f32,
+    attractors: usize,
+) -> BandConfig {
+    BandConfig {
+        min_frequency,
+        max_frequency,
+        delta,
+        min_volume,
+        vol_freq_scale,
+        attractors,
+    }
+}
+
+// The built-in analysis presets, each tuned for a broad genre of audio. The first is the default.
+// Attractor counts and gain control are replaced by those configured when a preset is applied
+pub const BUILTIN_PRESETS: [(&str, AnalysisConfig); 4] = [
+    // Strong, regular kicks with plenty of sub-bass
+    (
+        "electronic",
+        AnalysisConfig {
+            bass: band(30., 250., 1., 0.2, 1.825, DEFAULT_BASS_ATTRACTORS),
+            mids: band(250., 1_800., 0.1, 0.025, 3., DEFAULT_MIDS_ATTRACTORS),
+            high: band(1_800., 16_000., 0.1, 0.005, 8., DEFAULT_HIGH_ATTRACTORS),
+            kick: KickConfig {
+                min_magnitude: 1.25,
+                history_ratio: 3.,
+                min_interval: 0.8,
+            },
+            gain: DEFAULT_GAIN_CONTROL,
+        },
+    ),
+    // Punchier, faster drums over dense guitars, so kicks may come quicker with less contrast
+    (
+        "rock",
+        AnalysisConfig {
+            bass: band(40., 300., 1., 0.25, 1.825, DEFAULT_BASS_ATTRACTORS),
+            mids: band(300., 2_500., 0.1, 0.04, 3., DEFAULT_MIDS_ATTRACTORS),
+            high: band(2_500., 14_000., 0.1, 0.01, 8., DEFAULT_HIGH_ATTRACTORS),
+            kick: KickConfig {
+                min_magnitude: 1.5,
+                history_ratio: 2.5,
+                min_interval: 0.35,
+            },
+            gain: DEFAULT_GAIN_CONTROL,
+        },
+    ),
+    // Wide dynamics with few percussive hits, so quiet notes still register but kicks must stand out
+    (
+        "classical",
+        AnalysisConfig {
+            bass: band(30., 200., 1., 0.1, 1.825, DEFAULT_BASS_ATTRACTORS),
+            mids: band(200., 2_000., 0.05, 0.012, 3., DEFAULT_MIDS_ATTRACTORS),
+            high: band(2_000., 12_000., 0.05, 0.0025, 8., DEFAULT_HIGH_ATTRACTORS),
+            kick: KickConfig {
+                min_magnitude: 2.,
+                history_ratio: 4.,
+                min_interval: 2.,
+            },
+            gain: DEFAULT_GAIN_CONTROL,
+        },
+    ),
+    // Speech, whose energy lies within the telephone band, with only plosives resembling kicks
+    (
+        "podcast",
+        AnalysisConfig {
+            bass: band(80., 300., 1., 0.15, 1.825, DEFAULT_BASS_ATTRACTORS),
+            mids: band(300., 3_400., 0.1, 0.02, 3., DEFAULT_MIDS_ATTRACTORS),
+            high: band(3_400., 8_000., 0.1, 0.005, 8., DEFAULT_HIGH_ATTRACTORS),
+            kick: KickConfig {
+                min_magnitude: 3.,
+                history_ratio: 5.,
+                min_interval: 3.,
+            },
+            gain: DEFAULT_GAIN_CONTROL,
+        },
+    ),
+];
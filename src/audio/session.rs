@@ -0,0 +1,181 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::Sender;
+
+use super::{AudioSource, State};
+
+// Identifies a recorded session file and the version of its layout.
+const MAGIC: &[u8; 8] = b"FSSESS01";
+
+// Records the audio states applied to the visuals, each framed by its time and length.
+// The states themselves are JSON so that sessions recorded before a field was added may still be replayed
+pub struct SessionRecorder {
+    writer: BufWriter<File>,
+    start: Instant,
+    path: PathBuf,
+    failed: bool,
+}
+
+impl SessionRecorder {
+    // Create the session file, replacing any existing file at the path.
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        log!("Recording the audio session to `{}`", path.display());
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+            path: path.to_path_buf(),
+            failed: false,
+        })
+    }
+
+    // Append the state with the seconds since recording began. Recording stops after the first failed write.
+    pub fn record(&mut self, state: &State) {
+        if self.failed {
+            return;
+        }
+        let time = self.start.elapsed().as_secs_f32();
+        if let Err(e) = write_record(&mut self.writer, time, state) {
+            log!(
+                "Stopped recording the audio session to `{}`: {e:?}",
+                self.path.display()
+            );
+            self.failed = true;
+        }
+    }
+
+    // Write any buffered states to the file.
+    pub fn finish(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            log!(
+                "Failed to finish the audio session `{}`: {e:?}",
+                self.path.display()
+            );
+        }
+    }
+}
+
+// Write a single record: the time as an `f32`, the length of the state as a `u32`, then the state, all little-endian.
+fn write_record(writer: &mut impl Write, time: f32, state: &State) -> anyhow::Result<()> {
+    let json = serde_json::to_vec(state)?;
+    let length = u32::try_from(json.len())?;
+    writer.write_all(&time.to_le_bytes())?;
+    writer.write_all(&length.to_le_bytes())?;
+    writer.write_all(&json)?;
+    Ok(())
+}
+
+// Read every state of a recorded session with the seconds since recording began.
+// A record cut short, such as by the recording app being killed, ends the session rather than failing it
+pub fn read_session(path: &Path) -> anyhow::Result<Vec<(f32, State)>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut magic = [0; MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        anyhow::bail!(
+            "`{}` is not a recorded fractal_sugar session",
+            path.display()
+        );
+    }
+
+    let mut records = Vec::new();
+    let mut header = [0; 8];
+    while reader.read_exact(&mut header).is_ok() {
+        let time = f32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let mut json = vec![0; length as usize];
+        if reader.read_exact(&mut json).is_err() {
+            break;
+        }
+        records.push((time, serde_json::from_slice(&json)?));
+    }
+    Ok(records)
+}
+
+// Audio source replaying a recorded session, sending each state at the time it was recorded.
+pub struct ReplaySource {
+    path: PathBuf,
+    running: Arc<AtomicBool>,
+}
+
+impl ReplaySource {
+    // Read the session and spawn a thread sending its states to the given channel from the beginning.
+    pub fn new(path: &Path, tx: Sender<State>) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let records = match read_session(path) {
+            Ok(records) => records,
+            Err(e) => {
+                log!(
+                    "Failed to read the audio session `{}`: {e:?}",
+                    path.display()
+                );
+                Vec::new()
+            }
+        };
+
+        let thread_running = running.clone();
+        std::thread::spawn(move || {
+            let start = Instant::now();
+            for (time, state) in records {
+                let due = start + Duration::from_secs_f32(time.max(0.));
+                if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                    std::thread::sleep(wait);
+                }
+                if !thread_running.load(Ordering::Relaxed) || tx.send(state).is_err() {
+                    return;
+                }
+            }
+            log!("Finished replaying the audio session");
+
+            // Hold the channel open so that the end of the session is not mistaken for a failed source.
+            while thread_running.load(Ordering::Relaxed) {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        });
+
+        Self {
+            path: path.to_path_buf(),
+            running,
+        }
+    }
+}
+
+impl AudioSource for ReplaySource {
+    fn description(&self) -> String {
+        format!("Replay: {}", self.path.display())
+    }
+
+    fn pause(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Drop for ReplaySource {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,194 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crossbeam_channel::{bounded, Receiver, Select, Sender, TrySendError};
+use serde::{Deserialize, Serialize};
+
+use super::{AudioSource, SharedAnalysisConfig, SourceKind, SourceStatus, State};
+use crate::my_math::Vector4;
+
+// The parts of the visuals which a stem may drive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StemChannel {
+    Kicks,        // The kicks which spin the fractal and pulse the visuals, and the tempo they keep
+    BigBoomer,    // The bass note, which places the big boomer
+    Attractors,   // The mids and high notes, which place the particle attractors
+    FractalColor, // The reactive colors of the fractal and the estimated key
+}
+
+// An input analyzed separately from the others, such as the drums of a mix, driving only its channels of the visuals.
+#[derive(Clone, PartialEq)]
+pub struct Stem {
+    pub name: String,
+    pub source: SourceKind,
+    pub channels: Vec<StemChannel>,
+}
+
+// Audio source analyzing each stem with its own pipeline, merging their states into one.
+pub struct StemSources {
+    sources: Vec<(String, Box<dyn AudioSource>)>,
+}
+
+// Combine the latest state of each stem, taking each channel from the stem driving it.
+// The volume and spectrum are the totals of the stems, since together they make up the mix
+fn merge(latest: &[State], channels: &[Vec<StemChannel>], kick: Option<Vector4>) -> State {
+    let mut merged = State {
+        volume: latest.iter().map(|state| state.volume).sum(),
+        kick_angular_velocity: kick,
+        spectrum: std::array::from_fn(|i| latest.iter().map(|state| state.spectrum[i]).sum()),
+        ..State::default()
+    };
+    for (state, channels) in latest.iter().zip(channels) {
+        for channel in channels {
+            match channel {
+                StemChannel::Kicks => {
+                    merged.beat = state.beat;
+                    merged.kick_meter = state.kick_meter;
+                }
+                StemChannel::BigBoomer => {
+                    merged.bass_note = state.bass_note;
+                    merged.extra_bass_notes = state.extra_bass_notes.clone();
+                    merged.bass_direction = state.bass_direction;
+                }
+                StemChannel::Attractors => {
+                    merged.mids_notes = state.mids_notes.clone();
+                    merged.high_notes = state.high_notes.clone();
+                    merged.mids_channel_notes = state.mids_channel_notes.clone();
+                    merged.high_channel_notes = state.high_channel_notes.clone();
+                    merged.mids_direction = state.mids_direction;
+                    merged.high_direction = state.high_direction;
+                }
+                StemChannel::FractalColor => {
+                    merged.reactive_bass = state.reactive_bass;
+                    merged.reactive_mids = state.reactive_mids;
+                    merged.reactive_high = state.reactive_high;
+                    merged.key = state.key;
+                }
+            }
+        }
+    }
+    merged
+}
+
+// Spawn the thread merging the states of the stems, sending a merged state with each state of the leading stem.
+// The leading stem is the first still sending, so that the merged states arrive as often as those of a single source
+fn spawn_merge_thread(
+    receivers: Vec<Receiver<State>>,
+    channels: Vec<Vec<StemChannel>>,
+    tx: Sender<State>,
+) {
+    std::thread::spawn(move || {
+        let mut latest: Vec<State> = receivers.iter().map(|_| State::default()).collect();
+        let mut connected = vec![true; receivers.len()];
+        let mut select = Select::new();
+        for rx in &receivers {
+            select.recv(rx);
+        }
+
+        // Kicks are momentary, so are held until the next merged state rather than overwritten.
+        let mut pending_kick = None;
+        loop {
+            let operation = select.select();
+            let index = operation.index();
+            let Ok(state) = operation.recv(&receivers[index]) else {
+                select.remove(index);
+                connected[index] = false;
+                if connected.iter().any(|c| *c) {
+                    continue;
+                }
+                return;
+            };
+
+            if channels[index].contains(&StemChannel::Kicks) {
+                pending_kick = pending_kick.or(state.kick_angular_velocity);
+            }
+            latest[index] = state;
+            if connected.iter().position(|c| *c) != Some(index) {
+                continue;
+            }
+
+            let merged = merge(&latest, &channels, pending_kick.take());
+            if let Err(TrySendError::Disconnected(_)) = tx.try_send(merged) {
+                return;
+            }
+        }
+    });
+}
+
+impl StemSources {
+    // Create the source of each stem and begin sending their merged state over the given channel.
+    // Fails if the source of any stem cannot be created
+    pub fn new(
+        stems: &[Stem],
+        tx: Sender<State>,
+        analysis_config: &SharedAnalysisConfig,
+    ) -> anyhow::Result<Self> {
+        let mut receivers = Vec::with_capacity(stems.len());
+        let sources = stems
+            .iter()
+            .map(|stem| {
+                let (stem_tx, stem_rx) = bounded(crate::MAX_MESSAGE_BUFFER_COUNT);
+                receivers.push(stem_rx);
+                let source = super::create_source_and_send(
+                    stem.source,
+                    stem_tx,
+                    analysis_config.clone(),
+                    None,
+                )
+                .map_err(|e| e.context(format!("Failed to create the stem {:?}", stem.name)))?;
+                anyhow::Ok((stem.name.clone(), source))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let channels = stems.iter().map(|stem| stem.channels.clone()).collect();
+        spawn_merge_thread(receivers, channels, tx);
+
+        Ok(Self { sources })
+    }
+}
+
+impl AudioSource for StemSources {
+    fn description(&self) -> String {
+        let stems: Vec<_> = self
+            .sources
+            .iter()
+            .map(|(name, source)| format!("{name} ({})", source.description()))
+            .collect();
+        format!("Stems: {}", stems.join(", "))
+    }
+
+    fn pause(&mut self) {
+        for (_, source) in &mut self.sources {
+            source.pause();
+        }
+    }
+
+    // Every stem is polled so that each may recover, reporting the latest change of any.
+    // Any lost stem is reported over the statuses of the others, so that all of the stems are recreated.
+    fn poll_status(&mut self) -> Option<SourceStatus> {
+        let statuses: Vec<_> = self
+            .sources
+            .iter_mut()
+            .filter_map(|(_, source)| source.poll_status())
+            .collect();
+        if statuses.contains(&SourceStatus::Lost) {
+            return Some(SourceStatus::Lost);
+        }
+        statuses.last().copied()
+    }
+}
@@ -0,0 +1,120 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::f32::consts::PI;
+use std::ops::Range;
+
+use rustfft::num_complex::Complex;
+use serde::{Deserialize, Serialize};
+
+use crate::my_math::Vector4;
+
+// Bands quieter than this in both channels are considered centered.
+const MIN_POWER: f32 = 1e-6;
+
+// Apparent direction of a band, estimated from the differences between the left and right channels.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Direction {
+    pub pan: f32,       // From -1 when only in the left channel to 1 when only in the right
+    pub elevation: f32, // From 0 when the channels are in phase to 1 when they are opposed, which sounds wide and above
+}
+
+// Combine the channels of a frame of samples into one complex sample, the left as the real part and the right as the imaginary.
+// A single FFT of such samples can be separated into the spectra of both channels
+pub fn pack(left: f32, right: f32) -> Complex<f32> {
+    Complex::new(left, right)
+}
+
+// The left and right spectra of the latest chunk, up to the Nyquist frequency.
+#[derive(Default)]
+pub struct StereoSpectrum {
+    left: Vec<Complex<f32>>,
+    right: Vec<Complex<f32>>,
+}
+
+impl StereoSpectrum {
+    // Separate the spectra of both channels from the FFT of packed samples.
+    // The FFT is then replaced by the spectrum of the mid channel, which the rest of the analysis expects
+    pub fn split(&mut self, complex: &mut [Complex<f32>]) {
+        let size = complex.len();
+        let half = size / 2 + 1;
+        self.left.clear();
+        self.right.clear();
+        for k in 0..half {
+            // The spectrum of a real signal is conjugate-symmetric, which separates the two packed signals.
+            let z = complex[k];
+            let z_mirror = complex[(size - k) % size].conj();
+            let left = 0.5 * (z + z_mirror);
+            let right = Complex::new(0., -0.5) * (z - z_mirror);
+            self.left.push(left);
+            self.right.push(right);
+        }
+        for k in 0..half {
+            complex[k] = 0.5 * (self.left[k] + self.right[k]);
+        }
+    }
+
+    // The spectra of the left and right channels, up to the Nyquist frequency.
+    pub fn channels(&self) -> (&[Complex<f32>], &[Complex<f32>]) {
+        (&self.left, &self.right)
+    }
+
+    // Estimate the direction of the frequency bins in the given range from their level and phase differences.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn direction(&self, bins: Range<usize>) -> Direction {
+        let bins = bins.start.min(self.left.len())..bins.end.min(self.left.len());
+        let mut left_power = 0.;
+        let mut right_power = 0.;
+        let mut phase_weight = 0.;
+        let mut phase_sum = 0.;
+        for (left, right) in self.left[bins.clone()].iter().zip(&self.right[bins]) {
+            left_power += left.norm_sqr();
+            right_power += right.norm_sqr();
+
+            // Weight each bin's phase difference by how loud it is in both channels.
+            let weight = left.norm() * right.norm();
+            phase_weight += weight;
+            phase_sum += weight * (left * right.conj()).arg().abs();
+        }
+
+        let total_power = left_power + right_power;
+        if total_power < MIN_POWER {
+            return Direction::default();
+        }
+        Direction {
+            pan: (right_power - left_power) / total_power,
+            elevation: if phase_weight > 0. {
+                phase_sum / (phase_weight * PI)
+            } else {
+                0.
+            },
+        }
+    }
+}
+
+// Move an attractor toward the side and elevation its band is heard from, by the given amount in [0, 1].
+// Both the square and cube mappings span [-1, 1] along each axis, where negative `y` is up
+pub fn place_toward(attractor: Vector4, direction: Direction, amount: f32) -> Vector4 {
+    let toward = |v: f32, target: f32, weight: f32| v + amount * weight * (target - v);
+    Vector4::new(
+        toward(attractor.x, direction.pan.signum(), direction.pan.abs()),
+        toward(attractor.y, -1., direction.elevation),
+        attractor.z,
+        attractor.w,
+    )
+}
@@ -0,0 +1,78 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::app_config::AutoCycle;
+use crate::my_math::helpers::interpolate_floats;
+
+// Seconds over which the recent and long-term loudness are averaged.
+// The music is high-energy while its recent loudness is well above that of the surrounding music
+const RECENT_VOLUME_SECONDS: f32 = 2.;
+const LONG_VOLUME_SECONDS: f32 = 30.;
+
+// Keeps silence from appearing quiet relative to an equally silent history.
+const MIN_LONG_VOLUME: f32 = 0.01;
+
+// Counts the kicks and the sustained energy of the music towards the next automatic switch of color scheme.
+#[derive(Default)]
+pub struct SchemeCycler {
+    recent_volume: f32,
+    long_volume: f32,
+    kicks: u32,          // Kicks since the latest switch
+    energetic_time: f32, // Seconds the music has been high-energy without pause, since the latest switch
+}
+
+impl SchemeCycler {
+    // Count a kick detected by the audio thread towards the next switch.
+    pub fn record_kick(&mut self) {
+        self.kicks += 1;
+    }
+
+    // Accumulate the latest statistics, returning whether the next color scheme should be switched to.
+    // While disabled, nothing is counted towards a switch
+    pub fn update(&mut self, config: &AutoCycle, volume: f32, delta_time: f32) -> bool {
+        interpolate_floats(
+            &mut self.recent_volume,
+            volume,
+            -delta_time / RECENT_VOLUME_SECONDS,
+        );
+        interpolate_floats(
+            &mut self.long_volume,
+            volume,
+            -delta_time / LONG_VOLUME_SECONDS,
+        );
+
+        if !config.enabled {
+            self.kicks = 0;
+            self.energetic_time = 0.;
+            return false;
+        }
+        if self.recent_volume > config.energy_ratio * self.long_volume.max(MIN_LONG_VOLUME) {
+            self.energetic_time += delta_time;
+        } else {
+            self.energetic_time = 0.;
+        }
+
+        let switch = (config.kicks > 0 && self.kicks >= config.kicks)
+            || (config.energy_seconds > 0. && self.energetic_time >= config.energy_seconds);
+        if switch {
+            self.kicks = 0;
+            self.energetic_time = 0.;
+        }
+        switch
+    }
+}
@@ -0,0 +1,127 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::app_config::AutoBalance;
+use crate::my_math::helpers::interpolate_floats;
+
+// Seconds over which the recent and long-term loudness are averaged.
+// A passage is quiet when its recent loudness is well below that of the surrounding music
+const RECENT_VOLUME_SECONDS: f32 = 4.;
+const LONG_VOLUME_SECONDS: f32 = 30.;
+
+// Seconds over which the rate of kicks is averaged.
+const KICK_RATE_SECONDS: f32 = 8.;
+
+// Keeps silence from appearing loud relative to an equally silent history.
+const MIN_LONG_VOLUME: f32 = 0.01;
+
+// The character of the music over the last several seconds, each with its own balance of particles and fractal.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Rule {
+    Ambient,    // Sustained quiet without kicks, where the fractal takes the foreground
+    Neutral,    // Neither, leaving the visuals as configured
+    Percussive, // Frequent kicks, where dense particles are brought forward
+}
+
+impl Rule {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Ambient => "ambient",
+            Self::Neutral => "neutral",
+            Self::Percussive => "percussive",
+        }
+    }
+
+    // The particle opacity and the scale of the fractal's orbit distance applied by this rule.
+    // Smaller orbits bring the fractal closer, making it more prominent
+    fn targets(self) -> (f32, f32) {
+        match self {
+            Self::Ambient => (0.2, 0.8),
+            Self::Neutral => (1., 1.),
+            Self::Percussive => (1., 1.15),
+        }
+    }
+}
+
+// Long-window statistics of the audio, and the balance of the visuals they currently call for.
+pub struct Balancer {
+    recent_volume: f32,
+    long_volume: f32,
+    kick_rate: f32, // Kicks per second
+    pending_kicks: u32,
+    pub rule: Rule,
+    pub particle_opacity: f32,
+    pub orbit_scale: f32,
+}
+
+impl Balancer {
+    // Count a kick detected by the audio thread towards the kick rate.
+    pub fn record_kick(&mut self) {
+        self.pending_kicks += 1;
+    }
+
+    // Accumulate the latest statistics, select the rule they match, and transition towards its balance.
+    // While disabled, the balance returns to that configured by the user
+    #[allow(clippy::cast_precision_loss)]
+    pub fn update(&mut self, config: &AutoBalance, volume: f32, delta_time: f32) {
+        interpolate_floats(
+            &mut self.recent_volume,
+            volume,
+            -delta_time / RECENT_VOLUME_SECONDS,
+        );
+        interpolate_floats(
+            &mut self.long_volume,
+            volume,
+            -delta_time / LONG_VOLUME_SECONDS,
+        );
+        self.kick_rate = self.kick_rate * (-delta_time / KICK_RATE_SECONDS).exp()
+            + self.pending_kicks as f32 / KICK_RATE_SECONDS;
+        self.pending_kicks = 0;
+
+        self.rule = if !config.enabled {
+            Rule::Neutral
+        } else if self.kick_rate >= config.percussive_kick_rate {
+            Rule::Percussive
+        } else if self.kick_rate <= config.ambient_kick_rate
+            && self.recent_volume < config.quiet_ratio * self.long_volume.max(MIN_LONG_VOLUME)
+        {
+            Rule::Ambient
+        } else {
+            Rule::Neutral
+        };
+
+        let (particle_opacity, orbit_scale) = self.rule.targets();
+        let scale = -delta_time * config.response;
+        interpolate_floats(&mut self.particle_opacity, particle_opacity, scale);
+        interpolate_floats(&mut self.orbit_scale, orbit_scale, scale);
+    }
+}
+
+impl Default for Balancer {
+    fn default() -> Self {
+        Self {
+            recent_volume: 0.,
+            long_volume: 0.,
+            kick_rate: 0.,
+            pending_kicks: 0,
+            rule: Rule::Neutral,
+            particle_opacity: 1.,
+            orbit_scale: 1.,
+        }
+    }
+}
@@ -0,0 +1,132 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::app_config::CameraPath;
+use crate::my_math::Quaternion;
+
+// Seconds over which the audio-driven rotation and the orbit distance return after a path ends.
+const BLEND_DURATION: f32 = 1.5;
+
+enum Phase {
+    Idle,
+    Playing {
+        path: CameraPath,
+        start: Quaternion,
+        start_orbit: f32,
+        elapsed: f32,
+    },
+    BlendingOut {
+        orbit: f32, // The orbit scale at the end of the path
+        elapsed: f32,
+    },
+}
+
+// State machine animating the fractal camera along configured paths.
+// While a path plays the camera follows it exclusively, afterwards the audio-driven rotation blends back in
+pub struct CameraAnimation {
+    phase: Phase,
+
+    // The scale of the fractal's orbit distance, which is one unless animated.
+    pub orbit_scale: f32,
+
+    // The weight of the audio-driven rotation, from zero while playing a path to one when idle.
+    pub audio_weight: f32,
+}
+
+impl Default for CameraAnimation {
+    fn default() -> Self {
+        Self {
+            phase: Phase::Idle,
+            orbit_scale: 1.,
+            audio_weight: 1.,
+        }
+    }
+}
+
+// Evaluate the Bezier curve through the given control points by De Casteljau's algorithm.
+// Rotations are interpolated spherically, so the camera follows the curve on the sphere of orientations
+fn bezier(mut points: Vec<(Quaternion, f32)>, t: f32) -> (Quaternion, f32) {
+    while points.len() > 1 {
+        points = points
+            .windows(2)
+            .map(|pair| {
+                let ((q_a, orbit_a), (q_b, orbit_b)) = (pair[0], pair[1]);
+                (
+                    Quaternion::slerp(q_a, q_b, t),
+                    orbit_a + t * (orbit_b - orbit_a),
+                )
+            })
+            .collect();
+    }
+    points[0]
+}
+
+impl CameraAnimation {
+    // Begin playing the path from the camera's current orientation, interrupting any path already playing.
+    pub fn play(&mut self, path: &CameraPath, camera: Quaternion) {
+        self.phase = Phase::Playing {
+            path: path.clone(),
+            start: camera,
+            start_orbit: self.orbit_scale,
+            elapsed: 0.,
+        };
+        self.audio_weight = 0.;
+    }
+
+    // Advance the animation, moving the camera along any playing path.
+    pub fn update(&mut self, delta_time: f32, camera: &mut Quaternion) {
+        match &mut self.phase {
+            Phase::Idle => {}
+            Phase::Playing {
+                path,
+                start,
+                start_orbit,
+                elapsed,
+            } => {
+                *elapsed += delta_time;
+                let t = path.easing.apply((*elapsed / path.duration).min(1.));
+
+                // The current camera is the first control point, so the path begins without a jump.
+                let points = std::iter::once((*start, *start_orbit))
+                    .chain(
+                        path.keyframes
+                            .iter()
+                            .map(|keyframe| (keyframe.quaternion(), keyframe.orbit())),
+                    )
+                    .collect();
+                let (rotation, orbit) = bezier(points, t);
+                *camera = rotation;
+                self.orbit_scale = orbit;
+
+                if *elapsed >= path.duration {
+                    self.phase = Phase::BlendingOut { orbit, elapsed: 0. };
+                }
+            }
+            Phase::BlendingOut { orbit, elapsed } => {
+                *elapsed += delta_time;
+                let t = (*elapsed / BLEND_DURATION).min(1.);
+                let smooth = t * t * (3. - 2. * t);
+                self.audio_weight = smooth;
+                self.orbit_scale = *orbit + smooth * (1. - *orbit);
+                if t >= 1. {
+                    self.phase = Phase::Idle;
+                }
+            }
+        }
+    }
+}
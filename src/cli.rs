@@ -0,0 +1,264 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::path::PathBuf;
+
+use crate::analysis_only::PublishTarget;
+use crate::audio::{FileInput, SourceKind};
+use crate::export::{self, ExportOptions};
+use crate::poster::{self, PosterOptions};
+
+const DEFAULT_CONFIG_PATH: &str = "app_config.toml";
+
+pub const USAGE: &str = "Usage: fractal_sugar [OPTIONS] [CONFIG_FILE]
+       fractal_sugar doctor [CONFIG_FILE]
+
+Commands:
+  doctor  Check the Vulkan driver, GPUs, audio devices, and config file, printing hints for any failures
+
+Arguments:
+  [CONFIG_FILE]  The TOML app configuration file. The default path is 'app_config.toml'
+
+Options:
+  --audio-source <SOURCE>    Select the audio source to visualize: loopback, microphone, sine-sweep, pink-noise, impulse-train, udp:PORT
+  --metrics-port <PORT>      Serve application metrics as JSON on the given localhost port
+  --analysis-only <TARGET>   Only analyze audio, publishing the results as JSON to: stdout, udp:HOST:PORT
+  --list-displays            List the displays and modes available for direct output, then exit
+  --safe-mode                Launch with conservative settings and no audio capture, ignoring the config file
+  --edit-config              Only open an editor for the config file, which does not require Vulkan
+  --poster <SECONDS>         Render a single still frame at the given time of a session to `--out`, then exit
+  --out <FILE>               The PNG file a poster is saved to
+  --export <DIRECTORY>       Render every frame of a session to numbered PNGs in the directory, then exit
+  --fps <RATE>               The frame rate of an export. The default is 60
+  --duration <SECONDS>       The length of an export. The default is the length of `--audio-file`
+  --audio-file <FILE>        The audio file a poster or export analyzes, instead of a test signal of `--audio-source`
+  --size <WIDTHxHEIGHT>      The resolution of a poster or export. The default is 3840x2160
+  --record <FILE>            Record the audio states driving the visuals to a session file
+  --replay <FILE>            Replay a recorded session file in place of any audio source
+  --input <FILE>             Visualize an audio file (WAV, FLAC, MP3, ...) as it plays, in place of any audio source";
+
+// The parsed command-line arguments.
+pub struct Arguments {
+    pub config_path: String,
+    pub audio_source: Option<SourceKind>, // Overrides the configured capture mode
+    pub metrics_port: Option<u16>,
+    pub analysis_only: Option<PublishTarget>,
+    pub list_displays: bool,
+    pub safe_mode: bool,
+    pub edit_config: bool,
+    pub doctor: bool,
+    pub poster: Option<PosterOptions>,
+    pub export: Option<ExportOptions>,
+    pub record: Option<PathBuf>,
+    pub file_input: Option<FileInput>, // Played back in place of any audio source
+}
+
+// Parse a resolution such as `3840x2160`.
+fn parse_size(value: &str) -> anyhow::Result<[u32; 2]> {
+    let invalid = || anyhow::anyhow!("Invalid `--size` value `{value}`, expected `WIDTHxHEIGHT`");
+    let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+    match (width.parse(), height.parse()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok([width, height]),
+        _ => Err(invalid()),
+    }
+}
+
+// Parse a finite number greater than zero, such as a frame rate.
+fn parse_positive(option: &str, value: &str) -> anyhow::Result<f32> {
+    match value.parse::<f32>() {
+        Ok(number) if number.is_finite() && number > 0. => Ok(number),
+        Ok(_) => anyhow::bail!("`{option}` must be a positive number"),
+        Err(e) => anyhow::bail!("Invalid `{option}` value `{value}`: {e}"),
+    }
+}
+
+// Parse the given command-line arguments, excluding the executable path.
+pub fn parse(args: impl IntoIterator<Item = String>) -> anyhow::Result<Arguments> {
+    let mut config_path = None;
+    let mut audio_source = None;
+    let mut metrics_port = None;
+    let mut analysis_only = None;
+    let mut list_displays = false;
+    let mut safe_mode = false;
+    let mut edit_config = false;
+    let mut poster_time = None;
+    let mut out = None;
+    let mut audio_file = None;
+    let mut size = None;
+    let mut export_directory = None;
+    let mut frame_rate = None;
+    let mut duration = None;
+    let mut record = None;
+    let mut file_input = None;
+
+    // The `doctor` command is only recognized as the first argument, before any options
+    let mut args = args.into_iter().peekable();
+    let doctor = args.next_if(|arg| arg == "doctor").is_some();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--audio-source" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("`--audio-source` requires a value"))?;
+                audio_source = Some(value.parse()?);
+            }
+            "--metrics-port" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("`--metrics-port` requires a value"))?;
+                metrics_port = Some(value.parse().map_err(|e| {
+                    anyhow::anyhow!("Invalid `--metrics-port` value `{value}`: {e}")
+                })?);
+            }
+            "--analysis-only" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("`--analysis-only` requires a value"))?;
+                analysis_only = Some(value.parse()?);
+            }
+            "--poster" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("`--poster` requires a value"))?;
+                let time: f32 = value
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("Invalid `--poster` value `{value}`: {e}"))?;
+                if !time.is_finite() || time < 0. {
+                    anyhow::bail!("`--poster` must be a non-negative number of seconds");
+                }
+                poster_time = Some(time);
+            }
+            "--out" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("`--out` requires a value"))?;
+                out = Some(value.into());
+            }
+            "--audio-file" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("`--audio-file` requires a value"))?;
+                audio_file = Some(value.into());
+            }
+            "--export" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("`--export` requires a value"))?;
+                export_directory = Some(value.into());
+            }
+            "--fps" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("`--fps` requires a value"))?;
+                frame_rate = Some(parse_positive("--fps", &value)?);
+            }
+            "--duration" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("`--duration` requires a value"))?;
+                duration = Some(parse_positive("--duration", &value)?);
+            }
+            "--size" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("`--size` requires a value"))?;
+                size = Some(parse_size(&value)?);
+            }
+            "--record" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("`--record` requires a value"))?;
+                record = Some(value.into());
+            }
+            "--replay" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("`--replay` requires a value"))?;
+                if file_input.is_some() {
+                    anyhow::bail!("`--replay` and `--input` may not be combined");
+                }
+                file_input = Some(FileInput::Session(value.into()));
+            }
+            "--input" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("`--input` requires a value"))?;
+                if file_input.is_some() {
+                    anyhow::bail!("`--replay` and `--input` may not be combined");
+                }
+                file_input = Some(FileInput::Audio(value.into()));
+            }
+            "--list-displays" => list_displays = true,
+            "--safe-mode" => safe_mode = true,
+            "--edit-config" => edit_config = true,
+            _ if arg.starts_with("--") => anyhow::bail!("Unknown option `{arg}`"),
+            _ => {
+                if config_path.is_some() {
+                    anyhow::bail!("fractal_sugar accepts at most one configuration file");
+                }
+                config_path = Some(arg);
+            }
+        }
+    }
+
+    // The other poster and export options only apply when rendering one of them.
+    if poster_time.is_some() && export_directory.is_some() {
+        anyhow::bail!("`--poster` and `--export` may not be combined");
+    }
+    let poster = match poster_time {
+        Some(time) => Some(PosterOptions {
+            time,
+            out: out.ok_or_else(|| anyhow::anyhow!("`--poster` requires `--out`"))?,
+            audio_file: audio_file.take(),
+            size: size.unwrap_or(poster::DEFAULT_SIZE),
+        }),
+        None if out.is_some() => anyhow::bail!("`--out` only applies with `--poster`"),
+        None => None,
+    };
+    let export = match export_directory {
+        Some(directory) => Some(ExportOptions {
+            directory,
+            frame_rate: frame_rate.unwrap_or(export::DEFAULT_FRAME_RATE),
+            duration,
+            audio_file: audio_file.take(),
+            size: size.unwrap_or(poster::DEFAULT_SIZE),
+        }),
+        None if frame_rate.is_some() || duration.is_some() => {
+            anyhow::bail!("`--fps` and `--duration` only apply with `--export`")
+        }
+        None if poster.is_none() && (audio_file.is_some() || size.is_some()) => {
+            anyhow::bail!("`--audio-file` and `--size` only apply with `--poster` or `--export`")
+        }
+        None => None,
+    };
+
+    Ok(Arguments {
+        config_path: config_path.unwrap_or_else(|| String::from(DEFAULT_CONFIG_PATH)),
+        audio_source,
+        metrics_port,
+        analysis_only,
+        list_displays,
+        safe_mode,
+        edit_config,
+        doctor,
+        poster,
+        export,
+        record,
+        file_input,
+    })
+}
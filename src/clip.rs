@@ -0,0 +1,215 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::app_config;
+
+// What a clip recorder is doing, as shown by the overlay.
+#[derive(Clone, Copy)]
+pub enum ClipStatus {
+    Recording { seconds: f32 },
+    Encoding,
+}
+
+// The frames of a clip, downsampled as they are captured so that seconds of them can be kept in memory.
+struct Frames {
+    extent: [u32; 2],
+    rgba: Vec<Vec<u8>>,
+}
+
+// Records a short clip of downsampled frames into memory, then encodes them as an animated GIF on a separate thread.
+pub struct ClipRecorder {
+    path: PathBuf,
+    duration: Duration,
+    interval: Duration,
+    fps: u32,
+    capture_extent: [u32; 2],
+    started: Instant,
+    last_frame: Option<Instant>,
+    awaiting_capture: bool,
+    frames: Frames,
+    encoder: Option<JoinHandle<()>>,
+}
+
+impl ClipRecorder {
+    // Start recording frames captured at the given size, downsampled to the configured width.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn start(
+        config: &app_config::Clip,
+        directory: Option<&str>,
+        capture_extent: [u32; 2],
+    ) -> anyhow::Result<Self> {
+        let parent = match directory {
+            Some(directory) => PathBuf::from(directory),
+            None => crate::persistence::pictures_directory().ok_or_else(|| {
+                anyhow::anyhow!("There is no pictures directory, set `timelapse_directory`")
+            })?,
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+
+        // GIF frames are limited to 16-bit dimensions.
+        let [capture_width, capture_height] = capture_extent;
+        let width = config.width.min(capture_width).min(u32::from(u16::MAX));
+        let height = (u64::from(capture_height) * u64::from(width) / u64::from(capture_width))
+            .clamp(1, u64::from(u16::MAX)) as u32;
+
+        log!("Recording a {}s clip", config.seconds);
+        Ok(Self {
+            path: parent.join(format!("clip-{timestamp}.gif")),
+            duration: Duration::from_secs_f32(config.seconds),
+            interval: Duration::from_secs_f32(1. / config.fps as f32),
+            fps: config.fps,
+            capture_extent,
+            started: Instant::now(),
+            last_frame: None,
+            awaiting_capture: false,
+            frames: Frames {
+                extent: [width, height],
+                rgba: Vec::new(),
+            },
+            encoder: None,
+        })
+    }
+
+    // Whether the next rendered frame should be captured for the clip.
+    pub fn frame_due(&mut self) -> bool {
+        if self.encoder.is_some() {
+            return false;
+        }
+        let due = self
+            .last_frame
+            .map_or(true, |last_frame| last_frame.elapsed() >= self.interval);
+        if due {
+            self.last_frame = Some(Instant::now());
+            self.awaiting_capture = true;
+        }
+        due
+    }
+
+    // Keep a captured frame, given as tightly packed RGBA rows, if the clip requested it.
+    // The clip is encoded once its duration has been recorded.
+    pub fn save_frame(&mut self, rgba: &[u8]) {
+        if !std::mem::take(&mut self.awaiting_capture) {
+            return;
+        }
+        self.frames
+            .rgba
+            .push(downsample(rgba, self.capture_extent, self.frames.extent));
+        if self.started.elapsed() >= self.duration {
+            self.stop();
+        }
+    }
+
+    // Stop recording and encode the frames recorded so far.
+    pub fn stop(&mut self) {
+        if self.encoder.is_some() {
+            return;
+        }
+        let frames = std::mem::replace(
+            &mut self.frames,
+            Frames {
+                extent: self.frames.extent,
+                rgba: Vec::new(),
+            },
+        );
+        let (path, fps) = (self.path.clone(), self.fps);
+        log!("Encoding a clip of {} frames", frames.rgba.len());
+        self.encoder = Some(std::thread::spawn(move || {
+            let saved = path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .map_err(anyhow::Error::from)
+                .and_then(|()| write_gif(&path, frames, fps));
+            match saved {
+                Ok(()) => log!("Saved a clip to `{}`", path.display()),
+                Err(e) => log!("Failed to save clip `{}`: {e:?}", path.display()),
+            }
+        }));
+    }
+
+    pub fn status(&self) -> ClipStatus {
+        if self.encoder.is_some() {
+            ClipStatus::Encoding
+        } else {
+            ClipStatus::Recording {
+                seconds: self.started.elapsed().as_secs_f32(),
+            }
+        }
+    }
+
+    // Whether the clip has been encoded and saved, or failed to be.
+    pub fn finished(&self) -> bool {
+        self.encoder.as_ref().is_some_and(JoinHandle::is_finished)
+    }
+}
+
+// Scale the frame down to the given size, no larger than the frame, averaging the source pixels covered by each output pixel.
+#[allow(clippy::cast_possible_truncation)]
+fn downsample(
+    rgba: &[u8],
+    [width, height]: [u32; 2],
+    [out_width, out_height]: [u32; 2],
+) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let (out_width, out_height) = (out_width as usize, out_height as usize);
+    let mut out = Vec::with_capacity(out_width * out_height * 4);
+    for y in 0..out_height {
+        let (y0, y1) = (y * height / out_height, (y + 1) * height / out_height);
+        for x in 0..out_width {
+            let (x0, x1) = (x * width / out_width, (x + 1) * width / out_width);
+            let mut sum = [0u32; 4];
+            for row in y0..y1 {
+                for pixel in rgba[(row * width + x0) * 4..(row * width + x1) * 4].chunks_exact(4) {
+                    for (sum, channel) in sum.iter_mut().zip(pixel) {
+                        *sum += u32::from(*channel);
+                    }
+                }
+            }
+            let count = ((y1 - y0) * (x1 - x0)) as u32;
+            out.extend(sum.map(|sum| (sum / count) as u8));
+        }
+    }
+    out
+}
+
+// Write the frames as an endlessly looping, opaque GIF.
+#[allow(clippy::cast_possible_truncation)]
+fn write_gif(path: &Path, frames: Frames, fps: u32) -> anyhow::Result<()> {
+    let [width, height] = frames.extent.map(|x| x as u16);
+    let mut encoder = gif::Encoder::new(BufWriter::new(File::create(path)?), width, height, &[])?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+
+    // GIF delays are in hundredths of a second.
+    let delay = (100 / fps).max(1) as u16;
+    for mut rgba in frames.rgba {
+        for pixel in rgba.chunks_exact_mut(4) {
+            pixel[3] = u8::MAX;
+        }
+        let mut frame = gif::Frame::from_rgba_speed(width, height, &mut rgba, 10);
+        frame.delay = delay;
+        encoder.write_frame(&frame)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,236 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use egui::{Color32, ComboBox, ScrollArea, Ui};
+
+use crate::app_config::{self, AppConfig};
+use crate::app_overlay::{add_parameter_sliders, add_scheme_editor, ConfigUiScheme};
+use crate::engine::ConfigConstants;
+use crate::parameters::{self, constants_from_presentable, constants_to_presentable};
+
+// The number of bands drawn for each gradient preview.
+const PREVIEW_STEPS: usize = 96;
+
+// Editor of the config file which only needs a basic OpenGL context, for preparing configs away from the performance machine.
+struct ConfigEditor {
+    config_path: String,
+    launch_config: AppConfig,
+    config: ConfigConstants, // The constants to edit, in their presented units
+    color_schemes: Vec<ConfigUiScheme>,
+    edit_scheme_index: usize,
+    status: Result<String, String>, // The outcome of the last load, save, or validation
+}
+
+// Launch a window for editing and validating the config file at the given path, returning once it is closed.
+pub fn run(config_path: &str) -> anyhow::Result<()> {
+    let editor = ConfigEditor::load(String::from(config_path));
+    eframe::run_native(
+        "fractal_sugar config editor",
+        eframe::NativeOptions::default(),
+        Box::new(|_| Box::new(editor)),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to run the config editor: {e}"))
+}
+
+impl ConfigEditor {
+    // Begin editing the config file, falling back to the defaults if it is missing or invalid.
+    fn load(config_path: String) -> Self {
+        let (launch_config, status) = match app_config::parse_file(&config_path) {
+            Ok(config) => (config, Ok(format!("Loaded `{config_path}`"))),
+            Err(e) => (
+                AppConfig::default(),
+                Err(format!(
+                    "Failed to load `{config_path}`, editing the defaults: {e:?}"
+                )),
+            ),
+        };
+        Self {
+            config: constants_to_presentable((&launch_config).into()),
+            color_schemes: launch_config
+                .color_schemes
+                .iter()
+                .map(|scheme| (*scheme).into())
+                .collect(),
+            edit_scheme_index: 0,
+            config_path,
+            launch_config,
+            status,
+        }
+    }
+
+    // The config described by the editor's current values.
+    fn edited_config(&self) -> AppConfig {
+        let constants = constants_from_presentable(self.config);
+        AppConfig {
+            parameters: parameters::read_constants(&constants),
+            hide_stationary_particles: constants.hide_stationary_particles != 0,
+            disable_background: constants.disable_background != 0,
+            color_schemes: self.color_schemes.iter().map(|s| (*s).into()).collect(),
+            ..self.launch_config.clone()
+        }
+    }
+
+    // Write the edited config, then validate the written file exactly as a launch would.
+    fn save(&mut self) {
+        let path = self.config_path.as_str();
+        self.status = match app_config::save_file(path, &self.edited_config()) {
+            Ok(()) => match app_config::parse_file(path) {
+                Ok(_) => Ok(format!("Saved and validated `{path}`")),
+                Err(e) => Err(format!("Saved `{path}`, but it failed to validate: {e:?}")),
+            },
+            Err(e) => Err(format!("Failed to save `{path}`: {e:?}")),
+        };
+    }
+
+    // Validate the file on disk, which may have been edited by hand, without changing it.
+    fn validate(&mut self) {
+        let path = self.config_path.as_str();
+        self.status = match app_config::parse_file(path) {
+            Ok(_) => Ok(format!("`{path}` is valid")),
+            Err(e) => Err(format!("`{path}` is invalid: {e:?}")),
+        };
+    }
+}
+
+impl eframe::App for ConfigEditor {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::TopBottomPanel::bottom("status_panel").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    self.save();
+                }
+                if ui
+                    .button("Validate")
+                    .on_hover_text("Check the file on disk without saving.")
+                    .clicked()
+                {
+                    self.validate();
+                }
+                if ui
+                    .button("Reload")
+                    .on_hover_text("Discard any edits and load the file on disk.")
+                    .clicked()
+                {
+                    *self = Self::load(self.config_path.clone());
+                }
+            });
+            match &self.status {
+                Ok(message) => ui.label(message.as_str()),
+                Err(message) => ui.colored_label(Color32::LIGHT_RED, message.as_str()),
+            };
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ScrollArea::vertical().show(ui, |ui| {
+                ui.heading("Color Schemes");
+                let names = &self.launch_config.color_scheme_names;
+                ComboBox::from_label("Selected Color Scheme")
+                    .selected_text(names[self.edit_scheme_index].clone())
+                    .show_ui(ui, |ui| {
+                        for (i, name) in names.iter().enumerate() {
+                            ui.selectable_value(&mut self.edit_scheme_index, i, name.clone());
+                        }
+                    });
+                let max_speed = self.config.max_speed;
+                let scheme = &mut self.color_schemes[self.edit_scheme_index];
+                add_scheme_editor(ui, scheme);
+                add_scheme_preview(ui, scheme, max_speed);
+
+                ui.separator();
+                ui.heading("Parameters");
+                add_parameter_sliders(ui, &mut self.config);
+                let mut hide_stationary_particles = self.config.hide_stationary_particles > 0;
+                ui.checkbox(&mut hide_stationary_particles, "Hide stationary particles");
+                self.config.hide_stationary_particles = u32::from(hide_stationary_particles);
+                let mut disable_background = self.config.disable_background > 0;
+                ui.checkbox(&mut disable_background, "Ensure black background");
+                self.config.disable_background = u32::from(disable_background);
+            });
+        });
+    }
+}
+
+// Preview the gradients of a color scheme, as particles are colored by their index and by their speed.
+// The colors of the slowest particles blend from their index color, which is approximated as black here
+fn add_scheme_preview(ui: &mut Ui, scheme: &ConfigUiScheme, max_speed: f32) {
+    ui.label("Index-based gradient");
+    add_gradient(ui, |t| {
+        sample_gradient(
+            scheme.index_rgb[3],
+            &scheme.index_rgb,
+            &scheme.index_val,
+            1.,
+            t,
+        )
+    });
+    ui.label("Speed-based gradient, from rest to the max speed");
+    add_gradient(ui, |t| {
+        sample_gradient(
+            [0; 3],
+            &scheme.speed_rgb,
+            &scheme.speed_val,
+            max_speed,
+            t * max_speed,
+        )
+    });
+}
+
+// Draw a horizontal bar sampling the given gradient across [0, 1].
+#[allow(clippy::cast_precision_loss)]
+fn add_gradient(ui: &mut Ui, sample: impl Fn(f32) -> Color32) {
+    let (rect, _) =
+        ui.allocate_exact_size(egui::vec2(ui.available_width(), 18.), egui::Sense::hover());
+    let width = rect.width() / PREVIEW_STEPS as f32;
+    for i in 0..PREVIEW_STEPS {
+        let band = egui::Rect::from_min_size(
+            egui::pos2(rect.left() + i as f32 * width, rect.top()),
+            egui::vec2(width + 0.5, rect.height()), // Overlap neighbors to avoid seams
+        );
+        let t = (i as f32 + 0.5) / PREVIEW_STEPS as f32;
+        ui.painter().rect_filled(band, 0., sample(t));
+    }
+}
+
+// Sample a gradient the way `particles.vert` does, blending from `start` to the first color at the first threshold
+// and reaching the final color at `end`.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn sample_gradient(
+    start: [u8; 3],
+    rgb: &[[u8; 3]; 4],
+    vals: &[f32; 4],
+    end: f32,
+    x: f32,
+) -> Color32 {
+    let i = vals[..3].iter().position(|&v| x < v).unwrap_or(3);
+    let (from, low) = if i == 0 {
+        (start, 0.)
+    } else {
+        (rgb[i - 1], vals[i - 1])
+    };
+    let high = if i == 3 { end } else { vals[i] };
+    let scale = if high > low {
+        ((x - low) / (high - low)).clamp(0., 1.)
+    } else {
+        1.
+    };
+    let [r, g, b] = std::array::from_fn(|c| {
+        let (a, b) = (f32::from(from[c]), f32::from(rgb[i][c]));
+        (a + scale * (b - a)).round() as u8
+    });
+    Color32::from_rgb(r, g, b)
+}
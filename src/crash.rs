@@ -0,0 +1,154 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CRASH_REPORT_DIRECTORY: &str = "crash_reports";
+const PENDING_REPORT_FILE: &str = "pending_crash_report";
+
+// The number of recent log lines kept for crash reports.
+const LOG_CAPACITY: usize = 100;
+
+// Print a line to stdout, like `println!`, while keeping it for any crash report.
+// Lines go to stderr instead once `log_to_stderr` is called
+macro_rules! log {
+    ($($arg:tt)*) => {
+        $crate::crash::record_line(format!($($arg)*))
+    };
+}
+
+// Whether lines are printed to stderr, keeping stdout free for data such as the analysis-only mode's states.
+static LOG_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+// The most recent lines logged by the app, oldest first.
+static RECENT_LINES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+// Details of the running app which help to reproduce a crash.
+static CONTEXT: Mutex<Context> = Mutex::new(Context {
+    config_summary: String::new(),
+    gpu_name: String::new(),
+});
+
+struct Context {
+    config_summary: String,
+    gpu_name: String,
+}
+
+// Print the line and append it to the ring buffer of recent lines.
+pub fn record_line(line: String) {
+    print_line(&line);
+    let mut lines = RECENT_LINES.lock().unwrap_or_else(PoisonError::into_inner);
+    if lines.len() == LOG_CAPACITY {
+        lines.pop_front();
+    }
+    lines.push_back(line);
+}
+
+// Print every later line to stderr rather than stdout.
+pub fn log_to_stderr() {
+    LOG_TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+fn print_line(line: &str) {
+    if LOG_TO_STDERR.load(Ordering::Relaxed) {
+        eprintln!("{line}");
+    } else {
+        println!("{line}");
+    }
+}
+
+// Describe the applied configuration in any crash report.
+pub fn set_config_summary(summary: String) {
+    CONTEXT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .config_summary = summary;
+}
+
+// Name the selected GPU in any crash report.
+pub fn set_gpu_name(name: String) {
+    CONTEXT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .gpu_name = name;
+}
+
+// Write a report of the panic to the state directory and remember it to be shown on the next launch.
+// Called from the panic hook, so locks are only tried in case the panic occurred while one was held
+pub fn write_report(info: &dyn std::fmt::Display) {
+    let Some(state_dir) = crate::persistence::state_directory() else {
+        return;
+    };
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let report = format_report(info, timestamp);
+
+    let dir = state_dir.join(CRASH_REPORT_DIRECTORY);
+    let path = dir.join(format!("crash-{timestamp}.txt"));
+    let result = std::fs::create_dir_all(&dir)
+        .and_then(|()| std::fs::write(&path, report))
+        .and_then(|()| {
+            std::fs::write(
+                state_dir.join(PENDING_REPORT_FILE),
+                path.to_string_lossy().as_bytes(),
+            )
+        });
+    match result {
+        Ok(()) => print_line(&format!(
+            "A crash report was written to `{}`",
+            path.display()
+        )),
+        Err(e) => print_line(&format!("Failed to write a crash report: {e:?}")),
+    }
+}
+
+// Take the path of a crash report written by the previous run, if it has not yet been shown.
+pub fn take_pending_report() -> Option<PathBuf> {
+    let pending = crate::persistence::state_directory()?.join(PENDING_REPORT_FILE);
+    let path = std::fs::read_to_string(&pending).ok()?;
+    if let Err(e) = std::fs::remove_file(&pending) {
+        log!("Failed to clear the pending crash report: {e:?}");
+    }
+    Some(PathBuf::from(path))
+}
+
+fn format_report(info: &dyn std::fmt::Display, timestamp: u64) -> String {
+    let (gpu_name, config_summary) = match CONTEXT.try_lock() {
+        Ok(context) => (context.gpu_name.clone(), context.config_summary.clone()),
+        Err(_) => (String::from("unavailable"), String::from("unavailable")),
+    };
+    let recent_lines = match RECENT_LINES.try_lock() {
+        Ok(lines) => lines
+            .iter()
+            .fold(String::new(), |acc, line| acc + line + "\n"),
+        Err(_) => String::from("unavailable\n"),
+    };
+    format!(
+        "fractal_sugar {}\nOS: {} ({})\nTime: {timestamp} seconds since the Unix epoch\nThread: {}\nGPU: {gpu_name}\nConfig: {config_summary}\n\n{info}\n\nBacktrace:\n{}\n\nRecent log:\n{recent_lines}",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        std::thread::current().name().unwrap_or("unnamed"),
+        std::backtrace::Backtrace::force_capture(),
+    )
+}
@@ -0,0 +1,145 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crossbeam_channel::{Receiver, TrySendError};
+
+use crate::events::OSC_ADDRESS_PREFIX;
+
+// How often the receiving thread checks whether it should stop.
+const RECEIVE_TIMEOUT: Duration = Duration::from_millis(100);
+
+// Fader positions waiting to be applied. Controllers send many per second, and only the latest matters.
+const MAX_QUEUED_POSITIONS: usize = 64;
+
+// Large enough for any single OSC message from a controller.
+const MAX_DATAGRAM_SIZE: usize = 1024;
+
+// The names following the OSC address prefix of messages which move each fader.
+pub const CROSSFADE_ADDRESS: &str = "crossfade";
+pub const INTENSITY_ADDRESS: &str = "intensity";
+pub const BLACKOUT_ADDRESS: &str = "blackout";
+pub const FREEZE_ADDRESS: &str = "freeze";
+
+// Receives the positions of several named controls from an external controller as OSC messages over a single UDP port.
+pub struct OscControls {
+    rx: Receiver<(usize, f32)>, // The index of the control's name, and its position
+    running: Arc<AtomicBool>,
+}
+
+// Receives the position of a fader, such as the scheme crossfade, from an external controller as OSC messages over UDP.
+pub struct OscFader {
+    controls: OscControls,
+}
+
+// Read an OSC string from the start of the buffer, returning it and the remainder after its padding.
+fn read_osc_string(buffer: &[u8]) -> Option<(&str, &[u8])> {
+    let end = buffer.iter().position(|&b| b == 0)?;
+    let s = std::str::from_utf8(&buffer[..end]).ok()?;
+    let padded = (end / 4 + 1) * 4;
+    Some((s, buffer.get(padded..)?))
+}
+
+// Decode the control position from an OSC message, with the index of the address it is addressed to, if any.
+// Either a float or an int argument is accepted, since controllers differ in what they send
+fn decode_control_message(buffer: &[u8], addresses: &[String]) -> Option<(usize, f32)> {
+    let (message_address, rest) = read_osc_string(buffer)?;
+    let index = addresses
+        .iter()
+        .position(|address| address == message_address)?;
+    let (type_tags, rest) = read_osc_string(rest)?;
+    let argument: [u8; 4] = rest.get(..4)?.try_into().ok()?;
+
+    #[allow(clippy::cast_precision_loss)]
+    let position = match type_tags.strip_prefix(',')?.chars().next()? {
+        'f' => f32::from_be_bytes(argument),
+        'i' => i32::from_be_bytes(argument) as f32,
+        _ => return None,
+    };
+    position
+        .is_finite()
+        .then(|| (index, position.clamp(0., 1.)))
+}
+
+impl OscControls {
+    // Spawn a thread listening on the given UDP port for messages addressed to any of the named controls.
+    pub fn new(port: u16, names: &[&str]) -> anyhow::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        socket.set_read_timeout(Some(RECEIVE_TIMEOUT))?;
+
+        let addresses: Vec<String> = names
+            .iter()
+            .map(|name| format!("{OSC_ADDRESS_PREFIX}{name}"))
+            .collect();
+        let (tx, rx) = crossbeam_channel::bounded(MAX_QUEUED_POSITIONS);
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+        std::thread::spawn(move || {
+            let mut buffer = [0; MAX_DATAGRAM_SIZE];
+            while thread_running.load(Ordering::Relaxed) {
+                // Timeouts only give the loop a chance to check whether it should stop.
+                let Ok(size) = socket.recv(&mut buffer) else {
+                    continue;
+                };
+                let Some(control) = decode_control_message(&buffer[..size], &addresses) else {
+                    continue;
+                };
+
+                // A full queue means the frames are behind, and later positions will follow anyway.
+                if let Err(TrySendError::Disconnected(_)) = tx.try_send(control) {
+                    return;
+                }
+            }
+        });
+
+        log!(
+            "Listening for the {} controls on UDP port {port}",
+            names.join(", ")
+        );
+        Ok(Self { rx, running })
+    }
+
+    // Get the positions received since the last call, in order, with the index of each control's name.
+    pub fn take(&self) -> impl Iterator<Item = (usize, f32)> + '_ {
+        self.rx.try_iter()
+    }
+}
+
+impl OscFader {
+    // Spawn a thread listening on the given UDP port for messages addressed to the named fader.
+    pub fn new(port: u16, name: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            controls: OscControls::new(port, &[name])?,
+        })
+    }
+
+    // Get the latest position received since the last call, if any.
+    pub fn take(&self) -> Option<f32> {
+        self.controls.take().last().map(|(_, position)| position)
+    }
+}
+
+impl Drop for OscControls {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,174 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::ops::RangeInclusive;
+
+// The typed storage of a runtime variable, borrowed from the state it tunes.
+pub enum Storage<'a> {
+    Float(&'a mut f32, RangeInclusive<f32>), // Along with the values the variable may be set to
+    Bool(&'a mut bool),
+    Flag(&'a mut u32), // A boolean stored as an integer, as it is given to the shaders
+}
+
+// The value of a runtime variable, as read or written through the registry.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Value {
+    Float(f32),
+    Bool(bool),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Float(value) => write!(f, "{value}"),
+            Self::Bool(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+// Which writer must publish a variable's new value before it takes effect.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Writer {
+    AppConstants, // The constants given to the shaders
+    Analysis,     // The analysis config shared with the audio thread
+    Crossfade,    // The blend of the crossfaded color schemes
+    Frame,        // Nothing, since the variable is read every frame
+}
+
+// A named runtime variable, tuning some field of the state `T`.
+pub struct CVar<T> {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub writer: Writer,
+    storage: Box<dyn Fn(&mut T) -> Storage<'_>>,
+}
+
+impl<T> CVar<T> {
+    pub fn new(
+        name: &'static str,
+        description: &'static str,
+        writer: Writer,
+        storage: impl Fn(&mut T) -> Storage<'_> + 'static,
+    ) -> Self {
+        Self {
+            name,
+            description,
+            writer,
+            storage: Box::new(storage),
+        }
+    }
+
+    // Borrow the variable's storage within the state.
+    pub fn storage<'a>(&self, state: &'a mut T) -> Storage<'a> {
+        (self.storage)(state)
+    }
+
+    pub fn get(&self, state: &mut T) -> Value {
+        match self.storage(state) {
+            Storage::Float(value, _) => Value::Float(*value),
+            Storage::Bool(value) => Value::Bool(*value),
+            Storage::Flag(value) => Value::Bool(*value != 0),
+        }
+    }
+
+    // Set the variable, which must be given a value of its own type and within its range.
+    pub fn set(&self, state: &mut T, value: Value) -> anyhow::Result<()> {
+        match (self.storage(state), value) {
+            (Storage::Float(storage, range), Value::Float(value)) => {
+                if !range.contains(&value) {
+                    anyhow::bail!(
+                        "`{}` must be in [{}, {}], was given: {value}",
+                        self.name,
+                        range.start(),
+                        range.end()
+                    );
+                }
+                *storage = value;
+            }
+            (Storage::Bool(storage), Value::Bool(value)) => *storage = value,
+            (Storage::Flag(storage), Value::Bool(value)) => *storage = u32::from(value),
+            (Storage::Float(..), Value::Bool(_)) => {
+                anyhow::bail!("`{}` must be a number", self.name)
+            }
+            (Storage::Bool(_) | Storage::Flag(_), Value::Float(_)) => {
+                anyhow::bail!("`{}` must be `true` or `false`", self.name)
+            }
+        }
+        Ok(())
+    }
+
+    // Parse the text as a value of the variable's type, then set it.
+    pub fn set_str(&self, state: &mut T, text: &str) -> anyhow::Result<()> {
+        let value = match self.storage(state) {
+            Storage::Float(..) => Value::Float(
+                text.parse()
+                    .map_err(|_| anyhow::anyhow!("`{}` must be a number", self.name))?,
+            ),
+            Storage::Bool(_) | Storage::Flag(_) => Value::Bool(
+                text.parse()
+                    .map_err(|_| anyhow::anyhow!("`{}` must be `true` or `false`", self.name))?,
+            ),
+        };
+        self.set(state, value)
+    }
+}
+
+// The registry of every runtime variable tuning the state `T`, in the order they are listed.
+pub struct Registry<T> {
+    variables: Vec<CVar<T>>,
+}
+
+impl<T> Registry<T> {
+    pub fn new(variables: Vec<CVar<T>>) -> Self {
+        Self { variables }
+    }
+
+    pub fn find(&self, name: &str) -> Option<&CVar<T>> {
+        self.variables.iter().find(|variable| variable.name == name)
+    }
+
+    // The variables whose name or description contains the filter, ignoring case.
+    pub fn filter<'a>(&'a self, filter: &str) -> impl Iterator<Item = &'a CVar<T>> {
+        let filter = filter.trim().to_lowercase();
+        self.variables.iter().filter(move |variable| {
+            variable.name.contains(&filter) || variable.description.to_lowercase().contains(&filter)
+        })
+    }
+
+    // Run a console command, either `NAME` to read a variable or `NAME VALUE` to set it.
+    // Returns the text to show in response, along with the writer to notify of any change
+    pub fn execute(&self, state: &mut T, command: &str) -> (String, Option<Writer>) {
+        let mut words = command.split_whitespace();
+        let Some(name) = words.next() else {
+            return (String::new(), None);
+        };
+        let Some(variable) = self.find(name) else {
+            return (format!("Unknown variable `{name}`"), None);
+        };
+        match words.next() {
+            None => (format!("{name} = {}", variable.get(state)), None),
+            Some(text) => match variable.set_str(state, text) {
+                Ok(()) => (
+                    format!("{name} = {}", variable.get(state)),
+                    Some(variable.writer),
+                ),
+                Err(e) => (e.to_string(), None),
+            },
+        }
+    }
+}
@@ -0,0 +1,293 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use cpal::traits::{DeviceTrait, HostTrait};
+use vulkano::device::physical::PhysicalDevice;
+use vulkano::device::QueueFlags;
+use vulkano::format::{Format, FormatFeatures};
+use vulkano::instance::{Instance, InstanceCreateInfo};
+
+use crate::app_config;
+use crate::engine::core;
+
+// Counts the checks which failed, printing each result as it is made.
+#[derive(Default)]
+struct Report {
+    failures: u32,
+}
+
+impl Report {
+    fn pass(&self, message: &str) {
+        log!("[PASS] {message}");
+    }
+    fn warn(&self, message: &str, hint: &str) {
+        log!("[WARN] {message}\n       Hint: {hint}");
+    }
+    fn fail(&mut self, message: &str, hint: &str) {
+        self.failures += 1;
+        log!("[FAIL] {message}\n       Hint: {hint}");
+    }
+}
+
+// Check each piece of the environment the app depends on, printing pass/fail results with hints for fixing failures.
+// Nothing is launched, so this works where the app itself would crash. Returns whether all checks passed
+pub fn run(config_path: &str) -> bool {
+    let mut report = Report::default();
+
+    // Parse the config first, since it determines which features the GPU must support.
+    let msaa_samples = check_config(&mut report, config_path);
+    check_vulkan(&mut report, msaa_samples);
+    check_audio(&mut report);
+
+    if report.failures == 0 {
+        log!("All checks passed");
+    } else {
+        log!("{} check(s) failed", report.failures);
+    }
+    report.failures == 0
+}
+
+// Parse the config file, returning the MSAA samples it requests.
+fn check_config(report: &mut Report, config_path: &str) -> u32 {
+    if !std::path::Path::new(config_path).exists() {
+        report.warn(
+            &format!("Config file `{config_path}` does not exist, the defaults will be used"),
+            "A config file is written with the defaults when the app first saves its settings",
+        );
+        return app_config::AppConfig::default().msaa_samples;
+    }
+    match app_config::parse_file(config_path) {
+        Ok(config) => {
+            report.pass(&format!("Parsed config file `{config_path}`"));
+            config.msaa_samples
+        }
+        Err(e) => {
+            report.fail(
+                &format!("Failed to parse config file `{config_path}`: {e}"),
+                "Fix the reported key, or run `fractal_sugar --edit-config` to edit it with validation",
+            );
+            app_config::AppConfig::default().msaa_samples
+        }
+    }
+}
+
+// Check the Vulkan driver and list each device's support for the features the renderer uses.
+fn check_vulkan(report: &mut Report, msaa_samples: u32) {
+    let library = match vulkano::VulkanLibrary::new() {
+        Ok(library) => library,
+        Err(e) => {
+            report.fail(
+                &format!("Failed to load the Vulkan library: {e}"),
+                "Install the Vulkan driver for your GPU, e.g. `mesa-vulkan-drivers` on Linux or the latest vendor driver on Windows",
+            );
+            return;
+        }
+    };
+    report.pass(&format!("Loaded Vulkan {}", library.api_version()));
+
+    // A windowless instance is enough to inspect devices.
+    let instance = match Instance::new(library, InstanceCreateInfo::default()) {
+        Ok(instance) => instance,
+        Err(e) => {
+            report.fail(
+                &format!("Failed to create a Vulkan instance: {e}"),
+                "The installed driver may be incomplete, try reinstalling it",
+            );
+            return;
+        }
+    };
+    let devices: Vec<_> = match instance.enumerate_physical_devices() {
+        Ok(devices) => devices.collect(),
+        Err(e) => {
+            report.fail(
+                &format!("Failed to enumerate physical devices: {e}"),
+                "The installed driver may be incomplete, try reinstalling it",
+            );
+            return;
+        }
+    };
+
+    // Check each device the same way, then report which one the app would select.
+    let compatible: Vec<_> = devices
+        .iter()
+        .filter(|physical_device| check_device(report, physical_device, msaa_samples))
+        .collect();
+    match compatible
+        .into_iter()
+        .min_by_key(|p| core::device_type_rank(p.properties().device_type))
+    {
+        Some(physical_device) => report.pass(&format!(
+            "The app would use device `{}`",
+            physical_device.properties().device_name
+        )),
+        None => report.fail(
+            "No compatible GPU was found",
+            "Update the GPU driver, or install a software renderer such as `lavapipe` to run without a GPU",
+        ),
+    }
+}
+
+// Check and describe a single device, returning whether it meets the app's requirements.
+fn check_device(report: &mut Report, physical_device: &PhysicalDevice, msaa_samples: u32) -> bool {
+    let properties = physical_device.properties();
+    let name = &properties.device_name;
+    log!(
+        "Device: {name} (Type: {:?}, Vulkan {})",
+        properties.device_type,
+        physical_device.api_version()
+    );
+
+    let mut compatible = true;
+    if physical_device
+        .supported_extensions()
+        .contains(&core::required_device_extensions())
+    {
+        report.pass(&format!("`{name}` supports swapchains"));
+    } else {
+        report.fail(
+            &format!("`{name}` does not support swapchains (VK_KHR_swapchain)"),
+            "This device cannot present to a window, use another GPU or update its driver",
+        );
+        compatible = false;
+    }
+    if physical_device
+        .queue_family_properties()
+        .iter()
+        .any(|q| q.queue_flags.contains(QueueFlags::GRAPHICS))
+    {
+        report.pass(&format!("`{name}` has a graphics queue"));
+    } else {
+        report.fail(
+            &format!("`{name}` has no graphics queue"),
+            "This device is compute-only, use another GPU",
+        );
+        compatible = false;
+    }
+
+    // Particles are drawn with the configured MSAA samples to both a color and a depth attachment.
+    let sample_counts =
+        properties.framebuffer_color_sample_counts & properties.framebuffer_depth_sample_counts;
+    let supported: Vec<String> = sample_counts
+        .into_iter()
+        .map(|samples| (samples as u32).to_string())
+        .collect();
+    let samples_supported = sample_counts
+        .into_iter()
+        .any(|samples| samples as u32 == msaa_samples);
+    if samples_supported {
+        report.pass(&format!(
+            "`{name}` supports {msaa_samples} MSAA samples (supported: {})",
+            supported.join(", ")
+        ));
+    } else {
+        report.fail(
+            &format!(
+                "`{name}` does not support {msaa_samples} MSAA samples (supported: {})",
+                supported.join(", ")
+            ),
+            "Set `msaa_samples = 1` in the config file",
+        );
+        compatible = false;
+    }
+
+    // The swapchain only needs one of the desired formats, while each attachment format is required.
+    let supports = |format: Format, features: FormatFeatures| {
+        physical_device
+            .format_properties(format)
+            .is_ok_and(|p| p.optimal_tiling_features.contains(features))
+    };
+    let swapchain_formats: Vec<String> = core::DESIRED_SWAPCHAIN_FORMATS
+        .into_iter()
+        .filter(|&format| supports(format, FormatFeatures::COLOR_ATTACHMENT))
+        .map(|format| format!("{format:?}"))
+        .collect();
+    if swapchain_formats.is_empty() {
+        report.fail(
+            &format!("`{name}` supports none of the color formats the app renders to"),
+            "Update the GPU driver",
+        );
+        compatible = false;
+    } else {
+        report.pass(&format!(
+            "`{name}` supports color formats {}",
+            swapchain_formats.join(", ")
+        ));
+    }
+    let attachment_formats = [
+        (Format::D16_UNORM, FormatFeatures::DEPTH_STENCIL_ATTACHMENT),
+        (
+            Format::R32_SFLOAT,
+            FormatFeatures::COLOR_ATTACHMENT | FormatFeatures::SAMPLED_IMAGE,
+        ),
+        (
+            Format::R16G16B16A16_SFLOAT,
+            FormatFeatures::COLOR_ATTACHMENT | FormatFeatures::SAMPLED_IMAGE,
+        ),
+    ];
+    for (format, features) in attachment_formats {
+        if !supports(format, features) {
+            report.fail(
+                &format!("`{name}` does not support the attachment format {format:?}"),
+                "Update the GPU driver",
+            );
+            compatible = false;
+        }
+    }
+    compatible
+}
+
+// Check the audio host and its default devices, which loopback capture depends on.
+fn check_audio(report: &mut Report) {
+    let host = cpal::default_host();
+    report.pass(&format!("Audio host: {}", host.id().name()));
+
+    match crate::audio::default_output() {
+        Ok((_, device_name, config)) => {
+            report.pass(&format!("Default audio out: {device_name}"));
+            if crate::audio::is_supported_format(config.sample_format()) {
+                report.pass(&format!("Default audio-out config: {config:?}"));
+            } else {
+                report.fail(
+                    &format!(
+                        "The default audio out uses {:?} samples, which loopback capture cannot convert",
+                        config.sample_format()
+                    ),
+                    "Change the device's default format in the system sound settings",
+                );
+            }
+        }
+        Err(e) => report.fail(
+            &format!("No default audio out: {e}"),
+            "Connect or enable an output device, or use `--audio-source` to visualize a test signal",
+        ),
+    }
+
+    // Only a warning, since the audio in is only captured in microphone mode.
+    match host.default_input_device() {
+        Some(device) => report.pass(&format!(
+            "Default audio in: {}",
+            device
+                .name()
+                .unwrap_or_else(|_| String::from("Unnamed device"))
+        )),
+        None => report.warn(
+            "No default audio in",
+            "Connect a microphone to use the `microphone` capture mode. Loopback capture of the audio out is unaffected",
+        ),
+    }
+}
@@ -0,0 +1,165 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::sync::Arc;
+
+use vulkano::buffer::{Buffer, BufferCreateInfo, BufferUsage, Subbuffer};
+use vulkano::command_buffer::{
+    AutoCommandBufferBuilder, BlitImageInfo, CopyImageToBufferInfo, ImageBlit,
+    PrimaryAutoCommandBuffer,
+};
+use vulkano::format::Format;
+use vulkano::image::sampler::Filter;
+use vulkano::image::{Image, ImageCreateInfo, ImageUsage};
+use vulkano::memory::allocator::{AllocationCreateInfo, MemoryTypeFilter, StandardMemoryAllocator};
+use vulkano::pipeline::graphics::viewport::Viewport;
+
+// Reads finished frames back from the GPU, scaled to a fixed output resolution.
+// Frames are copied from the history of the temporal-resolve pass, so they never include the GUI
+pub struct FrameCapture {
+    image: Arc<Image>,
+    buffer: Subbuffer<[u8]>,
+    extent: [u32; 2],
+    requested: bool,
+    pending: bool,
+}
+
+impl FrameCapture {
+    // Create the images and host-readable buffer for capturing frames of the given size.
+    // Frames from sRGB sources are captured to an sRGB image, so that the blit keeps their encoding
+    pub fn new(
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+        extent: [u32; 2],
+        srgb: bool,
+    ) -> Self {
+        let image = Image::new(
+            memory_allocator.clone(),
+            ImageCreateInfo {
+                format: if srgb {
+                    Format::R8G8B8A8_SRGB
+                } else {
+                    Format::R8G8B8A8_UNORM
+                },
+                extent: [extent[0], extent[1], 1],
+                usage: ImageUsage::TRANSFER_DST | ImageUsage::TRANSFER_SRC,
+                ..Default::default()
+            },
+            AllocationCreateInfo::default(),
+        )
+        .expect("Failed to create frame-capture image");
+
+        let buffer = Buffer::new_slice::<u8>(
+            memory_allocator.clone(),
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: MemoryTypeFilter::PREFER_HOST
+                    | MemoryTypeFilter::HOST_RANDOM_ACCESS,
+                ..Default::default()
+            },
+            u64::from(extent[0]) * u64::from(extent[1]) * 4,
+        )
+        .expect("Failed to create frame-capture buffer");
+
+        Self {
+            image,
+            buffer,
+            extent,
+            requested: false,
+            pending: false,
+        }
+    }
+
+    // Capture the next rendered frame.
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    // Whether the next recorded frame is to be captured.
+    pub fn requested(&self) -> bool {
+        self.requested
+    }
+
+    // Record the commands to scale the viewport of the source image into the capture buffer, if a capture was requested.
+    // The viewport is cropped about its center to match the aspect ratio of the output
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn record(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        source: Arc<Image>,
+        viewport: &Viewport,
+    ) {
+        if !self.requested {
+            return;
+        }
+        self.requested = false;
+
+        let [source_width, source_height] = viewport.extent.map(|x| x as u32);
+        let [width, height] = self.extent;
+        let (crop_width, crop_height) = if u64::from(source_width) * u64::from(height)
+            > u64::from(source_height) * u64::from(width)
+        {
+            (source_height * width / height, source_height)
+        } else {
+            (source_width, source_width * height / width)
+        };
+        let x = viewport.offset[0] as u32 + (source_width - crop_width) / 2;
+        let y = viewport.offset[1] as u32 + (source_height - crop_height) / 2;
+
+        builder
+            .blit_image(BlitImageInfo {
+                regions: [ImageBlit {
+                    src_subresource: source.subresource_layers(),
+                    src_offsets: [[x, y, 0], [x + crop_width, y + crop_height, 1]],
+                    dst_subresource: self.image.subresource_layers(),
+                    dst_offsets: [[0, 0, 0], [width, height, 1]],
+                    ..Default::default()
+                }]
+                .into(),
+                filter: Filter::Linear,
+                ..BlitImageInfo::images(source, self.image.clone())
+            })
+            .unwrap()
+            .copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(
+                self.image.clone(),
+                self.buffer.clone(),
+            ))
+            .unwrap();
+        self.pending = true;
+    }
+
+    // Pass the pixels of the last captured frame, as tightly packed RGBA rows, to the given function.
+    // Must only be called once the frame's commands have completed
+    pub fn read(&mut self, f: impl FnOnce(&[u8])) {
+        if !self.pending {
+            return;
+        }
+        self.pending = false;
+
+        match self.buffer.read() {
+            Ok(pixels) => f(&pixels),
+            Err(e) => log!("Failed to read captured frame: {e:?}"),
+        }
+    }
+
+    pub fn extent(&self) -> [u32; 2] {
+        self.extent
+    }
+}
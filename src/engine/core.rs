@@ -41,6 +41,8 @@ pub struct EngineSwapchain {
     fences: SmallVec<[Option<Box<dyn GpuFuture>>; MAX_EXPECTED_FRAMES_IN_FLIGHT]>,
     images: Vec<Arc<Image>>,
     present_index: Option<u32>,
+    present_mode: PresentMode, // The mode used from the next recreation
+    present_modes: Vec<PresentMode>, // The modes the surface supports
     swapchain: Arc<Swapchain>,
 }
 
@@ -66,6 +68,41 @@ impl WindowSurface for Surface {
     }
 }
 
+// The swapchain color-formats the app can present with, in order of preference.
+// Formats which encode to sRGB are preferred, so that colors may be blended as linear values
+pub const DESIRED_SWAPCHAIN_FORMATS: [Format; 6] = [
+    Format::B8G8R8A8_SRGB,
+    Format::R8G8B8A8_SRGB,
+    Format::B8G8R8A8_SNORM,
+    Format::R8G8B8A8_SNORM,
+    Format::B8G8R8A8_UNORM,
+    Format::R8G8B8A8_UNORM,
+];
+
+// Whether the format encodes the linear values written to it as sRGB.
+pub fn is_srgb_format(format: Format) -> bool {
+    matches!(format, Format::B8G8R8A8_SRGB | Format::R8G8B8A8_SRGB)
+}
+
+// The device extensions any physical device must support to be used.
+pub fn required_device_extensions() -> DeviceExtensions {
+    DeviceExtensions {
+        khr_swapchain: true, // Require support for a swapchain
+        ..DeviceExtensions::empty()
+    }
+}
+
+// Preference from most dedicated graphics hardware to least. Lower ranks are preferred
+pub fn device_type_rank(device_type: PhysicalDeviceType) -> u32 {
+    match device_type {
+        PhysicalDeviceType::DiscreteGpu => 0,
+        PhysicalDeviceType::IntegratedGpu => 1,
+        PhysicalDeviceType::VirtualGpu => 2,
+        PhysicalDeviceType::Cpu => 3,
+        _ => 4,
+    }
+}
+
 // Select the best physical device for performing Vulkan operations
 fn select_best_physical_device(
     instance: &Arc<Instance>,
@@ -92,56 +129,96 @@ fn select_best_physical_device(
                 .map(|q| (p, q as u32))
         })
         // Preference from most dedicated graphics hardware to least
-        .min_by_key(|(p, _)| match p.properties().device_type {
-            PhysicalDeviceType::DiscreteGpu => 0,
-            PhysicalDeviceType::IntegratedGpu => 1,
-            PhysicalDeviceType::VirtualGpu => 2,
-            PhysicalDeviceType::Cpu => 3,
-            _ => 4,
-        })
+        .min_by_key(|(p, _)| device_type_rank(p.properties().device_type))
         .expect("Could not find a compatible GPU")
 }
 
-// Retrieve resources best suited for graphical Vulkan operations
+// Find a queue family dedicated to transfers, which can upload buffers alongside the graphics queue.
+fn dedicated_transfer_family(physical_device: &PhysicalDevice) -> Option<u32> {
+    physical_device
+        .queue_family_properties()
+        .iter()
+        .position(|q| {
+            q.queue_flags.contains(QueueFlags::TRANSFER)
+                && !q
+                    .queue_flags
+                    .intersects(QueueFlags::GRAPHICS | QueueFlags::COMPUTE)
+        })
+        .map(|i| i as u32)
+}
+
+// The most samples per pixel, not above the requested count, which the device supports for the particle color and depth.
+pub fn supported_msaa_samples(physical_device: &PhysicalDevice, requested: u32) -> u32 {
+    let properties = physical_device.properties();
+    let sample_counts =
+        properties.framebuffer_color_sample_counts & properties.framebuffer_depth_sample_counts;
+    let samples = sample_counts
+        .into_iter()
+        .map(|samples| samples as u32)
+        .filter(|&samples| samples <= requested)
+        .max()
+        .unwrap_or(1);
+    if samples != requested {
+        log!("The device does not support {requested} MSAA samples, falling back to {samples}");
+    }
+    samples
+}
+
+// Retrieve resources best suited for graphical Vulkan operations.
+// The last queue is for uploads, which is the graphics queue when the device has no dedicated transfer queue
 pub fn select_hardware(
     instance: &Arc<Instance>,
     surface: &Arc<Surface>,
-) -> (Arc<PhysicalDevice>, Arc<Device>, Arc<Queue>) {
+) -> (Arc<PhysicalDevice>, Arc<Device>, Arc<Queue>, Arc<Queue>) {
     // Perform non-trivial search for optimal GPU and corresponding queue family
-    let device_extensions = DeviceExtensions {
-        khr_swapchain: true, // Require support for a swapchain
-        ..DeviceExtensions::empty()
-    };
+    let device_extensions = required_device_extensions();
     let (physical_device, queue_family_index) =
         select_best_physical_device(instance, surface, &device_extensions);
 
-    // Pretty-print which GPU was selected
-    println!(
+    // Pretty-print which GPU was selected, and remember it for any crash report
+    crate::crash::set_gpu_name(physical_device.properties().device_name.clone());
+    log!(
         "Device: {} (Type: {:?})",
         physical_device.properties().device_name,
         physical_device.properties().device_type
     );
 
     // Create a logical Vulkan device object
+    let transfer_family_index = dedicated_transfer_family(&physical_device);
     let (device, mut queues) = Device::new(
         physical_device.clone(),
         DeviceCreateInfo {
             // Here we pass the desired queue families that we want to use
-            queue_create_infos: vec![QueueCreateInfo {
-                queue_family_index,
-                ..Default::default()
-            }],
+            queue_create_infos: std::iter::once(queue_family_index)
+                .chain(transfer_family_index)
+                .map(|queue_family_index| QueueCreateInfo {
+                    queue_family_index,
+                    ..Default::default()
+                })
+                .collect(),
             enabled_extensions: device_extensions,
             ..Default::default()
         },
     )
     .expect("Failed to create device");
 
-    // Retrieve first device queue
+    // Retrieve first device queue, then any transfer queue
     let queue = queues.next().unwrap();
+    let transfer_queue = queues.next().unwrap_or_else(|| queue.clone());
 
     // Return new objects
-    (physical_device, device, queue)
+    (physical_device, device, queue, transfer_queue)
+}
+
+// The desired present mode if the surface supports it, or else FIFO.
+fn supported_present_mode(present_modes: &[PresentMode], desired: PresentMode) -> PresentMode {
+    if present_modes.contains(&desired) {
+        desired
+    } else {
+        // The Vulkano spec requires FIFO to be supported.
+        log!("Fallback to default present mode, FIFO");
+        PresentMode::Fifo
+    }
 }
 
 impl EngineSwapchain {
@@ -149,6 +226,7 @@ impl EngineSwapchain {
         physical_device: &Arc<PhysicalDevice>,
         device: &Arc<Device>,
         surface: Arc<Surface>,
+        dimensions: PhysicalSize<u32>,
         desired_present_mode: PresentMode,
     ) -> Self {
         // Determine what features our surface can support.
@@ -157,25 +235,18 @@ impl EngineSwapchain {
             .expect("Failed to get surface capabilities");
 
         // Determine the properties of surface (on this physical device).
-        let dimensions = surface.window().inner_size();
         let composite_alpha = surface_capabilities
             .supported_composite_alpha
             .into_iter()
             .next()
             .unwrap();
         let image_format = {
-            let desired_formats = [
-                Format::B8G8R8A8_SNORM,
-                Format::R8G8B8A8_SNORM,
-                Format::B8G8R8A8_UNORM,
-                Format::R8G8B8A8_UNORM,
-            ];
             physical_device
                 .surface_formats(&surface, SurfaceInfo::default())
                 .unwrap()
                 .into_iter()
                 .find_map(|(format, _)| {
-                    if desired_formats.contains(&format) {
+                    if DESIRED_SWAPCHAIN_FORMATS.contains(&format) {
                         Some(format)
                     } else {
                         None
@@ -183,22 +254,14 @@ impl EngineSwapchain {
                 })
                 .expect("Failed to find suitable surface format")
         };
-        println!("Image color-format {image_format:?}");
+        log!("Image color-format {image_format:?}");
 
         // Get preferred present mode with fallback to FIFO (which any Vulkan instance must support)
-        let present_mode = {
-            if physical_device
-                .surface_present_modes(&surface, SurfaceInfo::default())
-                .unwrap()
-                .any(|p| p == desired_present_mode)
-            {
-                desired_present_mode
-            } else {
-                // The Vulkano spec requires FIFO to be supported.
-                println!("Fallback to default present mode, FIFO");
-                PresentMode::Fifo
-            }
-        };
+        let present_modes: Vec<PresentMode> = physical_device
+            .surface_present_modes(&surface, SurfaceInfo::default())
+            .unwrap()
+            .collect();
+        let present_mode = supported_present_mode(&present_modes, desired_present_mode);
 
         // Attempt to create one more image buffer than the minimum required, but constrained by the optional maximum count.
         let image_count = {
@@ -239,14 +302,22 @@ impl EngineSwapchain {
             swapchain,
             images,
             present_index: None,
+            present_mode,
+            present_modes,
         }
     }
 
+    // Select the present mode of the swapchain, which takes effect when it is next recreated.
+    pub fn set_present_mode(&mut self, desired_present_mode: PresentMode) {
+        self.present_mode = supported_present_mode(&self.present_modes, desired_present_mode);
+    }
+
     // Recreate swapchain using new dimensions
     pub fn recreate(&mut self, new_dimensions: PhysicalSize<u32>) -> RecreateSwapchainResult {
         // Create new swapchain with desired dimensions
         let recreate_swapchain = self.swapchain.recreate(SwapchainCreateInfo {
             image_extent: new_dimensions.into(),
+            present_mode: self.present_mode,
             ..self.swapchain.create_info()
         });
         match recreate_swapchain {
@@ -0,0 +1,129 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::sync::Arc;
+
+use vulkano::instance::{Instance, InstanceCreateInfo, InstanceExtensions};
+use vulkano::swapchain::display::{Display, DisplayPlane};
+use vulkano::swapchain::Surface;
+
+use crate::app_config::DirectDisplay;
+
+// Create a surface presenting to the configured display and mode, along with the extent of the mode.
+// Displays are numbered across every GPU in the order they are listed by `print_displays`
+pub fn create_surface(
+    instance: &Arc<Instance>,
+    direct_display: DirectDisplay,
+) -> anyhow::Result<(Arc<Surface>, [u32; 2])> {
+    if !instance.enabled_extensions().khr_display {
+        anyhow::bail!("The Vulkan driver does not support direct display output (VK_KHR_display)");
+    }
+
+    let display = instance
+        .enumerate_physical_devices()?
+        .flat_map(Display::enumerate)
+        .nth(direct_display.display as usize)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "There is no display {} available for direct output",
+                direct_display.display
+            )
+        })?;
+    let mode = display
+        .display_modes()
+        .nth(direct_display.mode as usize)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Display {} has no mode {}",
+                direct_display.display,
+                direct_display.mode
+            )
+        })?;
+
+    // Any plane which may be shown on the display will do, as only a single layer is presented.
+    let plane = DisplayPlane::enumerate(display.physical_device().clone())
+        .find(|plane| plane.supports(&display))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Display {} has no plane to present to",
+                direct_display.display
+            )
+        })?;
+
+    let surface = Surface::from_display_plane(&mode, &plane)?;
+    Ok((surface, mode.visible_region()))
+}
+
+// Print the displays and modes each GPU can drive directly, through the Vulkan driver.
+// This is the information needed to choose the monitor and mode for direct output to a dedicated display
+pub fn print_displays() {
+    let library =
+        vulkano::VulkanLibrary::new().expect("Could not determine Vulkan library to use.");
+    if !library.supported_extensions().khr_display {
+        log!("The Vulkan driver does not support direct display output (VK_KHR_display)");
+        return;
+    }
+    let instance = Instance::new(
+        library,
+        InstanceCreateInfo {
+            enabled_extensions: InstanceExtensions {
+                khr_surface: true,
+                khr_display: true,
+                ..InstanceExtensions::empty()
+            },
+            ..Default::default()
+        },
+    )
+    .expect("Failed to create Vulkan instance");
+
+    // Displays are numbered across every device, matching `direct_display_index`.
+    let mut display_index = 0;
+    for physical_device in instance
+        .enumerate_physical_devices()
+        .expect("Failed to enumerate physical devices")
+    {
+        log!("Device: {}", physical_device.properties().device_name);
+
+        // Displays already driven by a window system are typically not reported.
+        let planes: Vec<_> = DisplayPlane::enumerate(physical_device.clone()).collect();
+        let displays: Vec<_> = Display::enumerate(physical_device).collect();
+        if displays.is_empty() {
+            log!("  No displays are available for direct output");
+        }
+        for display in &displays {
+            let [width, height] = display.physical_resolution();
+            let plane_count = planes
+                .iter()
+                .filter(|plane| plane.supports(display))
+                .count();
+            log!(
+                "  Display {display_index}: {} ({width}x{height}, {plane_count} planes)",
+                display.name()
+            );
+            for (mode_index, mode) in display.display_modes().enumerate() {
+                let [width, height] = mode.visible_region();
+
+                // Refresh rates are reported in millihertz.
+                #[allow(clippy::cast_precision_loss)]
+                let refresh_rate = mode.refresh_rate() as f32 / 1000.;
+                log!("    Mode {mode_index}: {width}x{height} @ {refresh_rate:.2} Hz");
+            }
+            display_index += 1;
+        }
+    }
+}
@@ -28,14 +28,14 @@ use vulkano::command_buffer::SecondaryAutoCommandBuffer;
 use vulkano::descriptor_set::allocator::{
     StandardDescriptorSetAllocator, StandardDescriptorSetAllocatorCreateInfo,
 };
-use vulkano::descriptor_set::PersistentDescriptorSet;
 use vulkano::device::{Device, Queue};
 use vulkano::image::view::ImageView;
-use vulkano::image::{Image, ImageCreateInfo, ImageUsage};
+use vulkano::image::{Image, ImageUsage, SampleCount};
 use vulkano::instance::{Instance, InstanceCreateInfo};
-use vulkano::memory::allocator::{AllocationCreateInfo, MemoryTypeFilter, StandardMemoryAllocator};
+use vulkano::memory::allocator::{MemoryTypeFilter, StandardMemoryAllocator};
+use vulkano::pipeline::cache::PipelineCache;
 use vulkano::pipeline::graphics::viewport::Viewport;
-use vulkano::pipeline::{ComputePipeline, GraphicsPipeline};
+use vulkano::pipeline::GraphicsPipeline;
 use vulkano::render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass};
 use vulkano::swapchain::{PresentMode, Surface, Swapchain};
 use vulkano::sync::GpuFuture;
@@ -44,30 +44,78 @@ use winit::dpi::{LogicalSize, PhysicalSize};
 use winit::event_loop::EventLoop;
 use winit::window::{Fullscreen, Icon, Window, WindowBuilder};
 
+mod capture;
 pub mod core;
+pub mod display;
 mod object;
 pub mod pipeline;
+mod render_targets;
 pub mod renderer;
+mod timing;
+mod user_fractal;
 mod vertex;
 
 use self::core::{EngineSwapchain, RecreateSwapchainResult, WindowSurface};
-use crate::app_config::{AppConfig, Scheme};
+use self::render_targets::RenderTargetCache;
+use crate::app_config::{
+    AppConfig, AspectFit, DisplayLayout, ParticleBloom, ParticleContainment, ParticleRepulsion,
+    PresentationMode, Scheme,
+};
+use crate::fractals::Tunables;
+use crate::my_math::Vector2;
+use object::{Bloom, DepthOfField, DirectPass, Fractal, Particles, TemporalResolve, ToneMap};
 pub use object::{
-    ConfigConstants, FractalPushConstants, ParticleComputePushConstants,
-    ParticleVertexPushConstants, RuntimeConstants,
+    ConfigConstants, DepthOfFieldPushConstants, FractalPushConstants, FractalSurfacePushConstants,
+    LiveUniforms, ParticleAttractors, ParticleComputePushConstants, ParticleVertexPushConstants,
+    RuntimeConstants, TemporalResolvePushConstants, ToneMapPushConstants,
 };
-use object::{Fractal, Particles};
+pub use timing::{GPU_PASS_COUNT, GPU_PASS_NAMES};
 
 const DEFAULT_WIDTH: u32 = 800;
 const DEFAULT_HEIGHT: u32 = 450;
 const DEBUG_VULKAN: bool = false;
 
+// The format of the attachments holding unbounded colors, which are mapped to the display by the tone-mapping pass.
+// Also used to accumulate colors over several frames, where higher precision avoids banding
+const HDR_FORMAT: vulkano::format::Format = vulkano::format::Format::R16G16B16A16_SFLOAT;
+
 pub struct DrawData {
-    pub particle_data: Option<(
-        object::ParticleComputePushConstants,
-        object::ParticleVertexPushConstants,
-    )>,
+    // The push constants of each particle system, in the order of `Engine::particles`, where hidden systems have none
+    pub particle_data: Vec<
+        Option<(
+            object::ParticleComputePushConstants,
+            object::ParticleVertexPushConstants,
+        )>,
+    >,
+    pub particle_attractors: Option<object::ParticleAttractors>, // Felt by every shown particle system
     pub fractal_data: object::FractalPushConstants,
+    pub fractal_surface: Option<object::FractalSurfacePushConstants>, // Respawns particles on the fractal while emitting
+    pub particle_repulsion: ParticleRepulsion,
+    pub bloom: ParticleBloom,
+    pub depth_of_field: object::DepthOfFieldPushConstants,
+    pub tone_map: object::ToneMapPushConstants,
+    pub temporal_resolve: object::TemporalResolvePushConstants,
+}
+
+impl DrawData {
+    // Whether any pass samples the rendered particles, requiring them to be stored between render passes.
+    // Otherwise the particles are drawn straight into the fractal by the direct render pass
+    fn post_processes_particles(&self) -> bool {
+        self.depth_of_field.aperture > 0.
+            || self.depth_of_field.trail_decay > 0.
+            || self.bloom.enabled
+    }
+}
+
+// Framebuffers used to render to a single swapchain image
+#[derive(Clone)]
+pub struct FrameTargets {
+    particles: Arc<Framebuffer>,
+    bloom: [Arc<Framebuffer>; 2], // Blurred horizontally, then vertically
+    app: Arc<Framebuffer>,
+    direct: Arc<Framebuffer>, // Shares the attachments of the particle and app framebuffers
+    present: Arc<Framebuffer>,
+    present_passthrough: Arc<Framebuffer>, // Shares the attachments of the present framebuffer
 }
 
 pub struct Allocators {
@@ -81,43 +129,62 @@ pub struct Engine {
     allocators: Allocators,
     app_constants: Subbuffer<ConfigConstants>,
     runtime_constants: Subbuffer<RuntimeConstants>,
+    live_uniforms: Subbuffer<LiveUniforms>,
 
+    aspect_fit: AspectFit,
+    bloom: Bloom,
+    bloom_render_pass: Arc<RenderPass>,
+    color_scheme: Scheme, // The displayed scheme, in sRGB
+    depth_of_field: DepthOfField,
     device: Arc<Device>,
+    direct: DirectPass,
+    direct_render_pass: Arc<RenderPass>,
+    display_extent: Option<PhysicalSize<u32>>, // The extent of the display mode presented to, if not the window
+    display_layout: DisplayLayout,
     fractal: Fractal,
-    framebuffers: Vec<Arc<Framebuffer>>,
-    particles: Particles,
+    frame_capture: Option<capture::FrameCapture>,
+    frame_targets: Vec<FrameTargets>,
+    gpu_timer: Option<timing::GpuTimer>,
+    particle_render_pass: Arc<RenderPass>,
+    particle_samples: u32,
+    particles: Vec<Particles>, // The main particles, followed by each additional particle system
+    pipeline_cache: Arc<PipelineCache>,
+    present_passthrough_render_pass: Arc<RenderPass>,
+    present_render_pass: Arc<RenderPass>,
+    previous_frame_index: Option<usize>,
+    previous_frame_post_processed: bool, // Whether the previous frame wrote the particle trails
+    previous_frame_resolved: bool,       // Whether the previous frame wrote the history
     queue: Arc<Queue>,
     render_pass: Arc<RenderPass>,
+    render_targets: RenderTargetCache,
+    srgb_surface: bool, // Whether the surface encodes to sRGB, so that colors must be written as linear values
     surface: Arc<Surface>,
     swapchain: EngineSwapchain,
+    temporal_resolve: TemporalResolve,
+    tone_map: ToneMap,
+    user_fractal: Option<user_fractal::UserFractal>,
     viewport: Viewport,
 }
 
 impl Engine {
+    // Initialization is phased so that the window may be shown without waiting on every particle.
+    // The window, device, and pipelines are created here, while the particle buffers are filled in the background.
+    // Particles must not be drawn until `poll_particles_ready` reports they are ready
     pub fn new(
         event_loop: &EventLoop<()>,
         app_config: &AppConfig,
         runtime_constants: crate::RuntimeConstants,
         icon: Option<Icon>,
+        hidden_size: Option<[u32; 2]>,
     ) -> Self {
-        // Create the window! Set some basic properties and construct the result.
-        let window = WindowBuilder::new()
-            .with_inner_size(LogicalSize::new(DEFAULT_WIDTH, DEFAULT_HEIGHT))
-            .with_title("fractal_sugar")
-            .with_window_icon(icon)
-            .with_fullscreen(if app_config.launch_fullscreen {
-                Some(Fullscreen::Borderless(None))
-            } else {
-                None
-            })
-            .build(event_loop)
-            .expect("Failed to create window");
-
         // Create instance with extensions required for windowing (and optional debugging layers).
+        let direct_display = app_config.direct_display.enabled && hidden_size.is_none();
         let instance = {
             let library =
                 vulkano::VulkanLibrary::new().expect("Could not determine Vulkan library to use.");
-            let enabled_extensions = Surface::required_extensions(event_loop);
+            let mut enabled_extensions = Surface::required_extensions(event_loop);
+            enabled_extensions.khr_display =
+                direct_display && library.supported_extensions().khr_display;
             Instance::new(
                 library,
                 InstanceCreateInfo {
@@ -133,10 +200,54 @@ impl Engine {
             .expect("Failed to create Vulkan instance")
         };
 
+        // Frames may be presented directly to a display instead of the window, when one is configured and available.
+        let display_surface = if direct_display {
+            match display::create_surface(&instance, app_config.direct_display) {
+                Ok(display_surface) => Some(display_surface),
+                Err(e) => {
+                    log!("Failed to present directly to a display, using the window instead: {e}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Create the window! Set some basic properties and construct the result.
+        // Frames rendered only to be saved are drawn to a hidden window of exactly the output's size.
+        // While presenting to a display, the window is hidden as well, and is kept for its events and the overlay
+        let hidden_size = hidden_size.or(display_surface.as_ref().map(|(_, extent)| *extent));
+        let builder = WindowBuilder::new()
+            .with_title("fractal_sugar")
+            .with_window_icon(icon);
+        let builder = match hidden_size {
+            Some([width, height]) => builder
+                .with_inner_size(PhysicalSize::new(width, height))
+                .with_resizable(false)
+                .with_visible(false),
+            None => builder
+                .with_inner_size(LogicalSize::new(DEFAULT_WIDTH, DEFAULT_HEIGHT))
+                .with_fullscreen(if app_config.launch_fullscreen {
+                    Some(Fullscreen::Borderless(None))
+                } else {
+                    None
+                }),
+        };
+        let window = builder.build(event_loop).expect("Failed to create window");
+        let display_extent = display_surface
+            .as_ref()
+            .map(|(_, [width, height])| PhysicalSize::new(*width, *height));
+        let dimensions = display_extent.unwrap_or_else(|| window.inner_size());
+
         let surface = Surface::from_window(instance.clone(), window.into()).unwrap();
+        let present_surface = match display_surface {
+            Some((display_surface, _)) => display_surface,
+            None => surface.clone(),
+        };
 
         // Fetch device resources based on what is available to the system
-        let (physical_device, device, queue) = core::select_hardware(&instance, &surface);
+        let (physical_device, device, queue, transfer_queue) =
+            core::select_hardware(&instance, &present_surface);
 
         // Create a memory allocator for VRAM management
         let allocators = Allocators::new_default(&device);
@@ -145,10 +256,12 @@ impl Engine {
         let engine_swapchain = EngineSwapchain::new(
             &physical_device,
             &device,
-            surface.clone(),
-            PresentMode::Fifo,
+            present_surface,
+            dimensions,
+            vulkan_present_mode(app_config.present_mode),
         );
         let image_format = engine_swapchain.swapchain().image_format();
+        let srgb_surface = core::is_srgb_format(image_format);
 
         // Before creating descriptor sets and other buffers, allocate app-constants buffer
         let config_constants = {
@@ -163,15 +276,20 @@ impl Engine {
             buffer
         };
 
+        let particle_samples =
+            core::supported_msaa_samples(&physical_device, app_config.msaa_samples);
+        let particle_render_pass = create_particle_render_pass(&device, particle_samples);
+        let bloom_render_pass = create_bloom_render_pass(&device);
         let render_pass = create_app_render_pass(&device, image_format);
+        let present_render_pass = create_present_render_pass(&device, image_format, true);
+        let present_passthrough_render_pass =
+            create_present_render_pass(&device, image_format, false);
+        let direct_render_pass = create_direct_render_pass(&device, particle_samples, image_format);
 
         // Define our 2D viewspace (with normalized depth)
-        let dimensions = surface.window().inner_size();
-        let viewport = Viewport {
-            offset: [0., 0.],
-            extent: dimensions.into(),
-            depth_range: 0.0..=1.,
-        };
+        let viewport = content_viewport(dimensions, app_config.aspect_fit);
+        let (particle_viewport, fractal_viewport) =
+            layout_viewports(&viewport, app_config.display_layout);
 
         let runtime_constants = {
             let buffer = allocators
@@ -181,45 +299,169 @@ impl Engine {
             *buffer
                 .write()
                 .expect("Initialization of runtime constants failed") = runtime_constants
-                .to_engine_constants(dimensions.width as f32 / dimensions.height as f32);
+                .to_engine_constants(
+                    content_aspect_ratio(&fractal_viewport, app_config.aspect_fit),
+                    viewport.extent[1],
+                    srgb_surface,
+                );
             buffer
         };
 
-        // Create our "objects"™️
-        let fractal = Fractal::new(&device, &render_pass, viewport.clone());
-        let particles = Particles::new(
-            &allocators,
-            &queue,
+        let live_uniforms = {
+            let buffer = allocators
+                .uniform_buffer
+                .allocate_sized::<LiveUniforms>()
+                .expect("Allocation of live uniforms buffer failed");
+            *buffer
+                .write()
+                .expect("Initialization of live uniforms failed") =
+                crate::live_uniforms::pack(&app_config.live_uniforms);
+            buffer
+        };
+
+        // Reuse the pipelines compiled by previous launches where possible
+        let pipeline_cache = pipeline::load_cache(&device);
+
+        // Create our "objects"™️, preferring any shaders a distribution provides
+        let shader_directory = object::external_shader_directory();
+        let fractal = Fractal::new(
+            &device,
+            &pipeline_cache,
+            &render_pass,
+            fractal_viewport,
+            particle_viewport.clone(),
+            shader_directory.as_deref(),
+        );
+        let depth_of_field = DepthOfField::new(
+            &device,
+            &pipeline_cache,
+            &particle_render_pass,
             &render_pass,
             viewport.clone(),
-            app_config,
-            config_constants.clone(),
-            runtime_constants.clone(),
+        );
+        let tone_map = ToneMap::new(&device, &pipeline_cache, &render_pass, viewport.clone());
+        let bloom = Bloom::new(
+            &device,
+            &pipeline_cache,
+            &bloom_render_pass,
+            viewport.clone(),
+        );
+        let temporal_resolve = TemporalResolve::new(
+            &device,
+            &pipeline_cache,
+            &present_render_pass,
+            &present_passthrough_render_pass,
+            viewport.clone(),
+        );
+        let particle_systems = std::iter::once((app_config.particle_count, 0)).chain(
+            app_config.particle_systems.iter().map(|system| {
+                (
+                    system.particle_count.get(),
+                    system.scheme_index(&app_config.color_scheme_names),
+                )
+            }),
+        );
+        let particles = particle_systems
+            .map(|(particle_count, scheme_index)| {
+                let mut particles = Particles::new(
+                    &allocators,
+                    &queue,
+                    &transfer_queue,
+                    &pipeline_cache,
+                    &particle_render_pass,
+                    particle_viewport.clone(),
+                    app_config,
+                    particle_count,
+                    config_constants.clone(),
+                    runtime_constants.clone(),
+                    live_uniforms.clone(),
+                    shader_directory.as_deref(),
+                );
+                let scheme = app_config.color_schemes[scheme_index];
+                particles.update_color_scheme(if srgb_surface {
+                    scheme.to_linear()
+                } else {
+                    scheme
+                });
+                particles
+            })
+            .collect::<Vec<_>>();
+        let direct = DirectPass::new(
+            &device,
+            &pipeline_cache,
+            &direct_render_pass,
+            &particles,
+            &depth_of_field,
+            &fractal,
+            &tone_map,
+            &viewport,
+            app_config.display_layout,
         );
 
-        // Create a framebuffer to store results of render pass
-        let framebuffers = create_framebuffers(
+        // Create framebuffers to store results of the render passes
+        let mut render_targets = RenderTargetCache::default();
+        let frame_targets = create_frame_targets(
             &allocators.memory,
+            &mut render_targets,
+            &particle_render_pass,
+            &bloom_render_pass,
             &render_pass,
+            &present_render_pass,
+            &present_passthrough_render_pass,
+            &direct_render_pass,
             dimensions.into(),
             engine_swapchain.images(),
             image_format,
         );
 
+        // Measure GPU frame times when the queue supports timestamps
+        let gpu_timer = timing::GpuTimer::new(&queue);
+        if gpu_timer.is_none() {
+            log!("GPU timestamps are not supported, GPU frame times will not be reported");
+        }
+
         // Construct new Engine
         Self {
             allocators,
             app_constants: config_constants,
             runtime_constants,
+            live_uniforms,
 
+            aspect_fit: app_config.aspect_fit,
+            bloom,
+            bloom_render_pass,
+            color_scheme: app_config.color_schemes[0],
+            depth_of_field,
             device,
+            direct,
+            direct_render_pass,
+            display_extent,
+            display_layout: app_config.display_layout,
             fractal,
-            framebuffers,
+            frame_capture: None,
+            frame_targets,
+            gpu_timer,
+            particle_render_pass,
+            particle_samples,
             particles,
+            pipeline_cache,
+            present_passthrough_render_pass,
+            present_render_pass,
+            previous_frame_index: None,
+            previous_frame_post_processed: false,
+            previous_frame_resolved: false,
             queue,
             render_pass,
+            render_targets,
+            srgb_surface,
             surface,
             swapchain: engine_swapchain,
+            temporal_resolve,
+            tone_map,
+            user_fractal: app_config
+                .user_fractal
+                .as_deref()
+                .map(|path| user_fractal::UserFractal::new(path, shader_directory)),
             viewport,
         }
     }
@@ -230,6 +472,8 @@ impl Engine {
         dimensions: PhysicalSize<u32>,
         window_resized: bool,
     ) -> RecreateSwapchainResult {
+        // A display is always presented to at the extent of its mode, regardless of the window.
+        let dimensions = self.display_extent.unwrap_or(dimensions);
         if dimensions.width == 0 || dimensions.height == 0 {
             // Empty window detected, skipping swapchain recreation.
             // Vulkan panics if either dimensions are zero, bail here instead
@@ -247,42 +491,150 @@ impl Engine {
             }
         }
 
-        // Framebuffer is tied to the swapchain images, must recreate as well
-        self.framebuffers = create_framebuffers(
+        // Framebuffer is tied to the swapchain images, must recreate as well.
+        // The previous framebuffers are released first so that their idle attachments may be reused
+        self.frame_targets.clear();
+        self.frame_targets = create_frame_targets(
             &self.allocators.memory,
+            &mut self.render_targets,
+            &self.particle_render_pass,
+            &self.bloom_render_pass,
             &self.render_pass,
+            &self.present_render_pass,
+            &self.present_passthrough_render_pass,
+            &self.direct_render_pass,
             dimensions.into(),
             self.swapchain.images(),
             self.swapchain.image_format(),
         );
+        self.render_targets.evict();
+
+        // The history of the previous frame is lost with its framebuffers
+        self.previous_frame_index = None;
 
         // If caller indicates a resize has prompted this call then adjust viewport and fixed-view pipeline
         if window_resized {
-            self.viewport.extent = dimensions.into();
+            self.viewport = content_viewport(dimensions, self.aspect_fit);
+            self.recreate_viewport_pipelines();
+        }
 
-            // Since pipeline specifies viewport is fixed, entire pipeline needs to be reconstructed to account for size change
-            self.particles.graphics_pipeline = pipeline::create_particle(
-                self.device.clone(),
-                &self.particles.vert_shader,
-                &self.particles.frag_shader,
-                Subpass::from(self.render_pass.clone(), 0).unwrap(),
-                self.viewport.clone(),
-            );
-            self.fractal.pipeline = pipeline::create_fractal(
+        // Recreated swapchain and necessary follow-up structures without error
+        RecreateSwapchainResult::Ok
+    }
+
+    // Since pipelines specify their viewport is fixed, each must be reconstructed whenever the viewports change.
+    fn recreate_viewport_pipelines(&mut self) {
+        let (particle_viewport, fractal_viewport) =
+            layout_viewports(&self.viewport, self.display_layout);
+        for particles in &mut self.particles {
+            let create_particle_pipeline = if particles.sprites {
+                pipeline::create_particle_sprites
+            } else {
+                pipeline::create_particle
+            };
+            particles.graphics_pipeline = create_particle_pipeline(
                 self.device.clone(),
-                &self.fractal.vert_shader,
-                &self.fractal.frag_shader,
-                Subpass::from(self.render_pass.clone(), 1).unwrap(),
-                self.viewport.clone(),
+                &self.pipeline_cache,
+                &particles.vert_shader,
+                &particles.frag_shader,
+                Subpass::from(self.particle_render_pass.clone(), 0).unwrap(),
+                particle_viewport.clone(),
+                particles.alpha_blended,
             );
-
-            // Update runtime constants to reflect new aspect ratio
-            self.runtime_constants.write().unwrap().aspect_ratio =
-                self.viewport.extent[0] / self.viewport.extent[1];
         }
+        self.depth_of_field.resolve_pipeline = pipeline::create_entire_view(
+            self.device.clone(),
+            &self.pipeline_cache,
+            &self.depth_of_field.vert_shader,
+            &self.depth_of_field.resolve_frag_shader,
+            Subpass::from(self.particle_render_pass.clone(), 1).unwrap(),
+            self.viewport.clone(),
+        );
+        self.bloom.pipeline = pipeline::create_entire_view(
+            self.device.clone(),
+            &self.pipeline_cache,
+            &self.bloom.vert_shader,
+            &self.bloom.frag_shader,
+            Subpass::from(self.bloom_render_pass.clone(), 0).unwrap(),
+            self.viewport.clone(),
+        );
+        self.depth_of_field.pipeline = pipeline::create_entire_view(
+            self.device.clone(),
+            &self.pipeline_cache,
+            &self.depth_of_field.vert_shader,
+            &self.depth_of_field.frag_shader,
+            Subpass::from(self.render_pass.clone(), 0).unwrap(),
+            self.viewport.clone(),
+        );
+        self.fractal.pipeline = pipeline::create_entire_view(
+            self.device.clone(),
+            &self.pipeline_cache,
+            &self.fractal.vert_shader,
+            &self.fractal.frag_shader,
+            Subpass::from(self.render_pass.clone(), 1).unwrap(),
+            fractal_viewport,
+        );
+        self.fractal.passthrough_pipeline = pipeline::create_entire_view(
+            self.device.clone(),
+            &self.pipeline_cache,
+            &self.fractal.vert_shader,
+            &self.fractal.passthrough_frag_shader,
+            Subpass::from(self.render_pass.clone(), 1).unwrap(),
+            particle_viewport,
+        );
+        self.tone_map.pipeline = pipeline::create_entire_view(
+            self.device.clone(),
+            &self.pipeline_cache,
+            &self.tone_map.vert_shader,
+            &self.tone_map.frag_shader,
+            Subpass::from(self.render_pass.clone(), 2).unwrap(),
+            self.viewport.clone(),
+        );
+        self.temporal_resolve.pipeline = pipeline::create_entire_view(
+            self.device.clone(),
+            &self.pipeline_cache,
+            &self.temporal_resolve.vert_shader,
+            &self.temporal_resolve.frag_shader,
+            Subpass::from(self.present_render_pass.clone(), 0).unwrap(),
+            self.viewport.clone(),
+        );
+        self.temporal_resolve.passthrough_pipeline = pipeline::create_entire_view(
+            self.device.clone(),
+            &self.pipeline_cache,
+            &self.temporal_resolve.vert_shader,
+            &self.temporal_resolve.passthrough_frag_shader,
+            Subpass::from(self.present_passthrough_render_pass.clone(), 0).unwrap(),
+            self.viewport.clone(),
+        );
+        self.direct = DirectPass::new(
+            &self.device,
+            &self.pipeline_cache,
+            &self.direct_render_pass,
+            &self.particles,
+            &self.depth_of_field,
+            &self.fractal,
+            &self.tone_map,
+            &self.viewport,
+            self.display_layout,
+        );
 
-        // Recreated swapchain and necessary follow-up structures without error
-        RecreateSwapchainResult::Ok
+        // Update runtime constants to reflect new aspect ratio and height
+        let aspect_ratio = self.content_aspect_ratio();
+        let mut runtime_constants = self.runtime_constants.write().unwrap();
+        runtime_constants.aspect_ratio = aspect_ratio;
+        runtime_constants.viewport_height = self.viewport.extent[1];
+    }
+
+    // Whether the frame must write the history, as the temporal resolve blends or holds it, or a capture copies it.
+    // Otherwise the frame is only faded by the temporal passthrough, and the history is discarded
+    fn resolves_history(&self, draw_data: &DrawData) -> bool {
+        let temporal_resolve = &draw_data.temporal_resolve;
+        temporal_resolve.history_weight > 0.
+            || temporal_resolve.freeze != 0
+            || self
+                .frame_capture
+                .as_ref()
+                .is_some_and(capture::FrameCapture::requested)
     }
 
     // Use given push constants and synchronization-primitives to render next frame in swapchain.
@@ -304,8 +656,32 @@ impl Engine {
 
         // Create a one-time-submit command buffer for this frame
         let colored_sugar_commands = {
-            let framebuffer = self.framebuffers[image_index as usize].clone();
-            renderer::create_render_commands(self, &framebuffer, draw_data, gui_command_buffer)
+            let image_index = image_index as usize;
+            let frame_targets = self.frame_targets[image_index].clone();
+
+            // Temporal anti-aliasing and particle trails accumulate onto the history written by the previous frame
+            let previous_targets = self
+                .previous_frame_index
+                .filter(|&index| index != image_index)
+                .map(|index| &self.frame_targets[index]);
+            let history = previous_targets
+                .filter(|_| self.previous_frame_resolved)
+                .map(|targets| targets.present.attachments()[1].clone());
+            let previous_trails = previous_targets
+                .filter(|_| self.previous_frame_post_processed)
+                .map(|targets| targets.app.attachments()[3].clone());
+            self.previous_frame_index = Some(image_index);
+            self.previous_frame_post_processed = draw_data.post_processes_particles();
+            self.previous_frame_resolved = self.resolves_history(draw_data);
+
+            renderer::create_render_commands(
+                self,
+                &frame_targets,
+                history,
+                previous_trails,
+                draw_data,
+                gui_command_buffer,
+            )
         };
 
         // Create synchronization future for rendering the current frame
@@ -319,11 +695,84 @@ impl Engine {
     }
 
     pub fn present(&mut self, future: Box<dyn GpuFuture>) -> bool {
-        self.swapchain.present(&self.queue, future)
+        let requires_recreate_swapchain = self.swapchain.present(&self.queue, future);
+
+        // Presenting waits for the frame to complete, so its timestamps should now be available
+        if let Some(gpu_timer) = &mut self.gpu_timer {
+            gpu_timer.read_results();
+        }
+        requires_recreate_swapchain
     }
 
+    // Begin reading frames back from the GPU at the given resolution.
+    pub fn enable_frame_capture(&mut self, extent: [u32; 2]) {
+        self.frame_capture = Some(capture::FrameCapture::new(
+            &self.allocators.memory,
+            extent,
+            self.srgb_surface,
+        ));
+    }
+
+    // The resolution frames are captured at, if frame capture is enabled.
+    pub fn frame_capture_extent(&self) -> Option<[u32; 2]> {
+        self.frame_capture
+            .as_ref()
+            .map(capture::FrameCapture::extent)
+    }
+
+    // Capture the next rendered frame, if frame capture is enabled.
+    pub fn request_frame_capture(&mut self) {
+        if let Some(frame_capture) = &mut self.frame_capture {
+            frame_capture.request();
+        }
+    }
+
+    // Pass the pixels of the last captured frame to the given function. Must be called after presenting the frame.
+    pub fn read_captured_frame(&mut self, f: impl FnOnce(&[u8])) {
+        if let Some(frame_capture) = &mut self.frame_capture {
+            frame_capture.read(f);
+        }
+    }
+
+    // Write the pipeline cache to disk, allowing the next launch to start faster.
+    pub fn save_pipeline_cache(&self) {
+        pipeline::save_cache(&self.pipeline_cache);
+    }
+
+    // Colors are specified in sRGB, so are converted to linear values for surfaces which encode them.
     pub fn update_color_scheme(&mut self, scheme: Scheme) {
-        self.particles.update_color_scheme(scheme);
+        self.color_scheme = scheme;
+        self.particles[0].update_color_scheme(if self.srgb_surface {
+            scheme.to_linear()
+        } else {
+            scheme
+        });
+    }
+
+    // Update the color scheme of the additional particle system at the given index, which is independent of the main scheme.
+    pub fn update_particle_system_scheme(&mut self, index: usize, scheme: Scheme) {
+        self.particles[index + 1].update_color_scheme(if self.srgb_surface {
+            scheme.to_linear()
+        } else {
+            scheme
+        });
+    }
+
+    // Select how frames are presented, which takes effect once the swapchain is recreated.
+    pub fn set_present_mode(&mut self, present_mode: PresentationMode) {
+        self.swapchain
+            .set_present_mode(vulkan_present_mode(present_mode));
+    }
+
+    // Select which of the particles and the fractal are shown, rebuilding the pipelines drawn to each half of the view.
+    // The history of the previous frame no longer matches the view, so is discarded
+    pub fn set_display_layout(&mut self, display_layout: DisplayLayout) {
+        if display_layout == self.display_layout {
+            return;
+        }
+        self.display_layout = display_layout;
+        self.recreate_viewport_pipelines();
+        self.previous_frame_index = None;
     }
 
     pub fn update_app_constants(&mut self, config_constants: ConfigConstants) {
@@ -333,15 +782,72 @@ impl Engine {
             .expect("Failed to update config constants") = config_constants;
     }
 
+    pub fn update_live_uniforms(&mut self, live_uniforms: LiveUniforms) {
+        *self
+            .live_uniforms
+            .write()
+            .expect("Failed to update live uniforms") = live_uniforms;
+    }
+
+    // Recompile the fractal shader whenever the `user_fractal` file is saved, replacing the fractal pipeline if it compiles.
+    pub fn poll_user_fractal(&mut self) {
+        let Some(frag_shader) = self
+            .user_fractal
+            .as_mut()
+            .and_then(|user_fractal| user_fractal.poll(&self.device))
+        else {
+            return;
+        };
+        self.fractal.frag_shader = frag_shader;
+        self.fractal.pipeline = pipeline::create_entire_view(
+            self.device.clone(),
+            &self.pipeline_cache,
+            &self.fractal.vert_shader,
+            &self.fractal.frag_shader,
+            Subpass::from(self.render_pass.clone(), 1).unwrap(),
+            self.fractal_viewport(),
+        );
+        self.direct.fractal_pipeline = pipeline::create_entire_view(
+            self.device.clone(),
+            &self.pipeline_cache,
+            &self.fractal.vert_shader,
+            &self.fractal.frag_shader,
+            Subpass::from(self.direct_render_pass.clone(), 2).unwrap(),
+            self.fractal_viewport(),
+        );
+    }
+
+    // Place the particles at rest at the given 2D positions. Only used before the first frame.
+    pub fn arrange_particles(&mut self, positions: &[Vector2]) {
+        self.particles[0].arrange(&self.allocators, &self.queue, positions);
+    }
+
+    // Return the particles to their initial positions at rest, such as to recover from a chaotic state.
+    pub fn reset_particles(&mut self) {
+        for particles in &mut self.particles {
+            particles.reset(&self.allocators.memory);
+        }
+    }
+
+    // Whether the particles have been initialized in the background and may be simulated.
+    // Polling also completes their initialization, so should happen once per frame until ready
+    pub fn poll_particles_ready(&mut self) -> bool {
+        self.particles.iter_mut().fold(true, |ready, particles| {
+            particles.poll_upload(&self.allocators, &self.queue) && ready
+        })
+    }
+    pub fn particles_ready(&self) -> bool {
+        self.particles.iter().all(Particles::uploaded)
+    }
+
     // Engine getters
     pub fn app_constants(&self) -> &Subbuffer<ConfigConstants> {
         &self.app_constants
     }
-    pub fn compute_descriptor_set(&self) -> &Arc<PersistentDescriptorSet> {
-        &self.particles.compute_descriptor_set
-    }
-    pub fn compute_pipeline(&self) -> &Arc<ComputePipeline> {
-        &self.particles.compute_pipeline
+    // The aspect ratio content is rendered with, which is square unless fitting natively.
+    // Split-screen halves share the aspect ratio, so the fractal's stands for both
+    pub fn content_aspect_ratio(&self) -> f32 {
+        content_aspect_ratio(&self.fractal_viewport(), self.aspect_fit)
     }
     pub fn descriptor_pool(&self) -> &StandardDescriptorSetAllocator {
         &self.allocators.descriptor_set
@@ -349,14 +855,16 @@ impl Engine {
     pub fn fractal_pipeline(&self) -> &Arc<GraphicsPipeline> {
         &self.fractal.pipeline
     }
-    pub fn gui_pass(&self) -> Subpass {
-        Subpass::from(self.render_pass.clone(), 2).unwrap()
+    // The GPU time of the latest measured frame, along with each of its passes, in milliseconds.
+    pub fn gpu_frame_ms(&self) -> Option<(f32, [f32; GPU_PASS_COUNT])> {
+        let gpu_timer = self.gpu_timer.as_ref()?;
+        gpu_timer.last_frame_ms().zip(gpu_timer.last_pass_ms())
     }
-    pub fn particle_descriptor_set(&self) -> &Arc<PersistentDescriptorSet> {
-        &self.particles.graphics_descriptor_set
+    pub fn gui_pass(&self) -> Subpass {
+        Subpass::from(self.present_render_pass.clone(), 1).unwrap()
     }
-    pub fn particle_pipeline(&self) -> &Arc<GraphicsPipeline> {
-        &self.particles.graphics_pipeline
+    pub fn color_scheme(&self) -> Scheme {
+        self.color_scheme
     }
     pub fn queue(&self) -> &Arc<Queue> {
         &self.queue
@@ -364,140 +872,360 @@ impl Engine {
     pub fn runtime_constants_mut(&mut self) -> &mut Subbuffer<RuntimeConstants> {
         &mut self.runtime_constants
     }
+
+    // The distance estimator ID of the displayed fractal.
+    pub fn fractal_id(&self) -> u32 {
+        self.runtime_constants
+            .read()
+            .expect("Failed to read the runtime constants")
+            .distance_estimator_id
+    }
+
+    // Apply the tunables of the displayed fractal.
+    pub fn set_fractal_tunables(&mut self, tunables: Tunables) {
+        let mut constants = self
+            .runtime_constants
+            .write()
+            .expect("Failed to update the fractal tunables");
+        constants.fractal_iterations = tunables.iterations;
+        constants.fractal_bailout = tunables.bailout;
+        constants.fractal_fold_scale = tunables.fold_scale;
+        constants.fractal_color_exponent = tunables.color_exponent;
+    }
+
+    // Set how particles are kept within the unit square or cube, from the next compute pass.
+    pub fn set_particle_containment(&mut self, containment: ParticleContainment) {
+        self.runtime_constants
+            .write()
+            .expect("Failed to update the particle containment")
+            .particle_containment = containment as u32;
+    }
+    // Set the fraction of particles respawned on the fractal's surface each second, from the next compute pass.
+    pub fn set_surface_emission(&mut self, surface_emission: f32) {
+        self.runtime_constants
+            .write()
+            .expect("Failed to update the surface emission")
+            .surface_emission = surface_emission;
+    }
+    pub fn srgb_surface(&self) -> bool {
+        self.srgb_surface
+    }
+    // The surface of the window, which the overlay is integrated with even while frames are presented to a display.
     pub fn surface(&self) -> &Arc<Surface> {
         &self.surface
     }
     pub fn swapchain(&self) -> &Arc<Swapchain> {
         self.swapchain.swapchain()
     }
+    // The number of particles across every particle system.
     pub fn particle_count(&self) -> u64 {
-        self.particles.vertex_buffers.vertex.len()
+        self.particles
+            .iter()
+            .map(|particles| particles.vertex_buffers.vertex.len())
+            .sum()
     }
     pub fn window(&self) -> &Window {
         self.surface.window()
     }
+    pub fn viewport(&self) -> &Viewport {
+        &self.viewport
+    }
+    pub fn display_layout(&self) -> DisplayLayout {
+        self.display_layout
+    }
+    // The region of the view the particles are drawn to, which is the left half when splitting the screen.
+    pub fn particle_viewport(&self) -> Viewport {
+        layout_viewports(&self.viewport, self.display_layout).0
+    }
+    // The region of the view the fractal is drawn to, which is the right half when splitting the screen.
+    pub fn fractal_viewport(&self) -> Viewport {
+        layout_viewports(&self.viewport, self.display_layout).1
+    }
 }
 
 // Helper for (re)creating framebuffers
-fn create_framebuffers(
+#[allow(clippy::too_many_arguments)]
+fn create_frame_targets(
     memory_allocator: &Arc<StandardMemoryAllocator>,
+    render_targets: &mut RenderTargetCache,
+    particle_render_pass: &Arc<RenderPass>,
+    bloom_render_pass: &Arc<RenderPass>,
     render_pass: &Arc<RenderPass>,
+    present_render_pass: &Arc<RenderPass>,
+    present_passthrough_render_pass: &Arc<RenderPass>,
+    direct_render_pass: &Arc<RenderPass>,
     dimensions: [u32; 2],
     images: &[Arc<Image>],
     image_format: vulkano::format::Format,
-) -> Vec<Arc<Framebuffer>> {
+) -> Vec<FrameTargets> {
     let dimensions = [dimensions[0], dimensions[1], 1];
+    let particle_samples = particle_render_pass.attachments()[0].samples;
+
+    // Helper for getting the view of an image attachment, reusing any idle attachment of the same size
+    render_targets.begin_generation();
+    let mut new_attachment = |format, samples, usage| {
+        render_targets.attachment(memory_allocator, format, dimensions, samples, usage)
+    };
+
     images
         .iter()
         .map(|image| {
             // To interact with image buffers or framebuffers from shaders we create a view defining how the image will be used.
-            // This view, which belongs to the swapchain, will be the destination (i.e. fractal) view
+            // This view, which belongs to the swapchain, will be the destination (i.e. anti-aliased fractal) view
             let view = ImageView::new_default(image.clone()).unwrap();
 
-            // Create image attachment for MSAA particles.
+            // Create image attachment for MSAA particles, which is unused without multisampling.
             // It is transient but cannot be used as an input
-            let msaa_view = ImageView::new_default(
-                Image::new(
-                    memory_allocator.clone(),
-                    ImageCreateInfo {
-                        format: image_format,
-                        extent: dimensions,
-                        samples: vulkano::image::SampleCount::Sample8,
-                        usage: ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSIENT_ATTACHMENT,
+            let msaa_view = new_attachment(
+                HDR_FORMAT,
+                particle_samples,
+                ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSIENT_ATTACHMENT,
+            );
+
+            // Create image attachment for resolved particles.
+            // It is sampled by the depth-of-field pass, which must read neighboring pixels, or is an input to the direct fractal
+            let particle_view = new_attachment(
+                HDR_FORMAT,
+                SampleCount::Sample1,
+                ImageUsage::COLOR_ATTACHMENT
+                    | ImageUsage::SAMPLED
+                    | ImageUsage::INPUT_ATTACHMENT
+                    | ImageUsage::TRANSFER_DST,
+            );
+
+            // Create an attachement for the particle's depth buffer
+            let particle_depth = new_attachment(
+                vulkano::format::Format::D16_UNORM,
+                particle_samples,
+                ImageUsage::DEPTH_STENCIL_ATTACHMENT
+                    | ImageUsage::INPUT_ATTACHMENT
+                    | ImageUsage::TRANSIENT_ATTACHMENT,
+            );
+
+            // Create an attachment for the nearest depth of each pixel's samples, also sampled by the depth-of-field pass
+            let particle_min_depth = new_attachment(
+                vulkano::format::Format::R32_SFLOAT,
+                SampleCount::Sample1,
+                ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED | ImageUsage::INPUT_ATTACHMENT,
+            );
+
+            // Create transient attachments for the post-processed particles, used as inputs to the fractal
+            let scene_color = new_attachment(
+                HDR_FORMAT,
+                SampleCount::Sample1,
+                ImageUsage::COLOR_ATTACHMENT
+                    | ImageUsage::INPUT_ATTACHMENT
+                    | ImageUsage::TRANSIENT_ATTACHMENT,
+            );
+            let scene_depth = new_attachment(
+                vulkano::format::Format::R32_SFLOAT,
+                SampleCount::Sample1,
+                ImageUsage::COLOR_ATTACHMENT
+                    | ImageUsage::INPUT_ATTACHMENT
+                    | ImageUsage::TRANSIENT_ATTACHMENT,
+            );
+
+            // Create a transient attachment for the fractal, used as an input to the tone-mapping pass
+            let fractal_color = new_attachment(
+                HDR_FORMAT,
+                SampleCount::Sample1,
+                ImageUsage::COLOR_ATTACHMENT
+                    | ImageUsage::INPUT_ATTACHMENT
+                    | ImageUsage::TRANSIENT_ATTACHMENT,
+            );
+
+            // Create an attachment for the tone-mapped fractal, sampled by the temporal-resolve pass
+            let display_color = new_attachment(
+                image_format,
+                SampleCount::Sample1,
+                ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED,
+            );
+
+            // Create an attachment for the accumulated history, sampled by the next frame's temporal-resolve pass
+            let history = new_attachment(
+                HDR_FORMAT,
+                SampleCount::Sample1,
+                ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED | ImageUsage::TRANSFER_SRC,
+            );
+
+            // Create attachments for the glow of the particles, blurred along each axis in turn.
+            // Each is sampled by the following pass, which gathers its neighboring pixels
+            let mut bloom_framebuffer = || {
+                let bloom_view = new_attachment(
+                    HDR_FORMAT,
+                    SampleCount::Sample1,
+                    ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED,
+                );
+                Framebuffer::new(
+                    bloom_render_pass.clone(),
+                    FramebufferCreateInfo {
+                        attachments: vec![bloom_view],
                         ..Default::default()
                     },
-                    AllocationCreateInfo::default(),
                 )
-                .unwrap(),
+                .unwrap()
+            };
+            let bloom = [bloom_framebuffer(), bloom_framebuffer()];
+
+            // Create an attachment for the particles and their fading trails, sampled by the next frame's depth-of-field pass
+            let particle_trails = new_attachment(
+                HDR_FORMAT,
+                SampleCount::Sample1,
+                ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED,
+            );
+
+            // Create framebuffers specifying underlying renderpasses and image attachments
+            let direct = Framebuffer::new(
+                direct_render_pass.clone(),
+                FramebufferCreateInfo {
+                    attachments: vec![
+                        msaa_view.clone(),
+                        particle_view.clone(),
+                        particle_depth.clone(),
+                        particle_min_depth.clone(),
+                        fractal_color.clone(),
+                        display_color.clone(),
+                    ],
+                    ..Default::default()
+                },
             )
             .unwrap();
-
-            // Create image attachment for resolved particles.
-            // It is transient and will be used as an input to a later pass
-            let particle_view = ImageView::new_default(
-                Image::new(
-                    memory_allocator.clone(),
-                    ImageCreateInfo {
-                        format: image_format,
-                        extent: dimensions,
-                        usage: ImageUsage::COLOR_ATTACHMENT
-                            | ImageUsage::INPUT_ATTACHMENT
-                            | ImageUsage::TRANSFER_DST,
+            FrameTargets {
+                particles: Framebuffer::new(
+                    particle_render_pass.clone(),
+                    FramebufferCreateInfo {
+                        attachments: vec![
+                            msaa_view,
+                            particle_view,
+                            particle_depth,
+                            particle_min_depth,
+                        ], // Must add specified attachments in order
                         ..Default::default()
                     },
-                    AllocationCreateInfo::default(),
                 )
                 .unwrap(),
-            )
-            .unwrap();
-
-            // Create an attachement for the particle's depth buffer
-            let particle_depth = ImageView::new_default(
-                Image::new(
-                    memory_allocator.clone(),
-                    ImageCreateInfo {
-                        format: vulkano::format::Format::D16_UNORM,
-                        extent: dimensions,
-                        samples: vulkano::image::SampleCount::Sample8,
-                        usage: ImageUsage::DEPTH_STENCIL_ATTACHMENT
-                            | ImageUsage::INPUT_ATTACHMENT
-                            | ImageUsage::TRANSIENT_ATTACHMENT,
+                bloom,
+                app: Framebuffer::new(
+                    render_pass.clone(),
+                    FramebufferCreateInfo {
+                        attachments: vec![
+                            scene_color,
+                            scene_depth,
+                            fractal_color,
+                            particle_trails,
+                            display_color,
+                        ],
                         ..Default::default()
                     },
-                    AllocationCreateInfo::default(),
                 )
                 .unwrap(),
-            )
-            .unwrap();
-
-            // Create framebuffer specifying underlying renderpass and image attachments
-            Framebuffer::new(
-                render_pass.clone(),
-                FramebufferCreateInfo {
-                    attachments: vec![msaa_view, particle_view, particle_depth, view], // Must add specified attachments in order
-                    ..Default::default()
-                },
-            )
-            .unwrap()
+                direct,
+                present: Framebuffer::new(
+                    present_render_pass.clone(),
+                    FramebufferCreateInfo {
+                        attachments: vec![view.clone(), history.clone()],
+                        ..Default::default()
+                    },
+                )
+                .unwrap(),
+                present_passthrough: Framebuffer::new(
+                    present_passthrough_render_pass.clone(),
+                    FramebufferCreateInfo {
+                        attachments: vec![view, history],
+                        ..Default::default()
+                    },
+                )
+                .unwrap(),
+            }
         })
         .collect()
 }
 
-// Helper for initializing the app render pass
-fn create_app_render_pass(
-    device: &Arc<Device>,
-    image_format: vulkano::format::Format,
-) -> Arc<RenderPass> {
+// Helper for initializing the particle render pass with the given number of MSAA samples
+fn create_particle_render_pass(device: &Arc<Device>, samples: u32) -> Arc<RenderPass> {
+    // Without multisampling, particles are drawn directly to the image sampled by the depth-of-field pass.
+    // The intermediary is kept so that both render passes share the layout of their framebuffers, but is unused
+    if samples == 1 {
+        return vulkano::ordered_passes_renderpass!(
+            device.clone(),
+            attachments: {
+                intermediary: {
+                    format: HDR_FORMAT,
+                    samples: 1,
+                    load_op: DontCare,
+                    store_op: DontCare,
+                },
+
+                particle_color: {
+                    format: HDR_FORMAT,
+                    samples: 1,
+                    load_op: Clear,
+                    store_op: Store, // Sampled by the depth-of-field pass
+                },
+
+                particle_depth: {
+                    format: vulkano::format::Format::D16_UNORM,
+                    samples: 1,
+                    load_op: Clear,
+                    store_op: DontCare,
+                },
+
+                particle_min_depth: {
+                    format: vulkano::format::Format::R32_SFLOAT,
+                    samples: 1,
+                    load_op: DontCare, // Every pixel is written by the depth-resolve pass
+                    store_op: Store, // Sampled by the depth-of-field pass
+                }
+            },
+            passes: [
+                // Particles pass
+                {
+                    color: [particle_color],
+                    depth_stencil: {particle_depth},
+                    input: [],
+                },
+
+                // Depth-resolve pass, copying the depth to an image which may be sampled
+                {
+                    color: [particle_min_depth],
+                    depth_stencil: {},
+                    input: [particle_depth]
+                }
+            ]
+        )
+        .unwrap();
+    }
+
     vulkano::ordered_passes_renderpass!(
         device.clone(),
         attachments: {
             // The first framebuffer attachment is the intermediary image
             intermediary: {
-                format: image_format,
-                samples: 8, // MSAA for smooth particles. Must be resolved to non-sampled image for presentation
+                format: HDR_FORMAT,
+                samples: samples, // MSAA for smooth particles. Must be resolved to non-sampled image for presentation
                 load_op: Clear,
                 store_op: DontCare,
             },
 
             particle_color: {
-                format: image_format, // Use swapchain's format since we are writing to its buffers
+                format: HDR_FORMAT, // Unbounded, so that bright particles are mapped rather than clipped
                 samples: 1,
                 load_op: DontCare, // Resolve does not need destination image to be cleared
-                store_op: DontCare,
+                store_op: Store, // Sampled by the depth-of-field pass
             },
 
             particle_depth: {
                 format: vulkano::format::Format::D16_UNORM,
-                samples: 8, // Must match sample count of color
+                samples: samples, // Must match sample count of color
                 load_op: Clear,
                 store_op: DontCare,
             },
 
-            fractal_color: {
-                format: image_format, // Use swapchain's format since we are writing to its buffers
-                samples: 1, // No MSAA necessary when rendering a single quad with shaders ;)
-                load_op: DontCare,
-                store_op: Store,
+            particle_min_depth: {
+                format: vulkano::format::Format::R32_SFLOAT,
+                samples: 1,
+                load_op: DontCare, // Every pixel is written by the depth-resolve pass
+                store_op: Store, // Sampled by the depth-of-field pass
             }
         },
         passes: [
@@ -514,33 +1242,419 @@ fn create_app_render_pass(
                 input: [],
             },
 
+            // Depth-resolve pass, keeping the nearest of each pixel's depth samples
+            {
+                color: [particle_min_depth],
+                depth_stencil: {},
+                input: [particle_depth]
+            }
+        ]
+    )
+    .unwrap()
+}
+
+// Helper for initializing the render pass blurring the glow of the particles, used once for each axis
+fn create_bloom_render_pass(device: &Arc<Device>) -> Arc<RenderPass> {
+    vulkano::single_pass_renderpass!(
+        device.clone(),
+        attachments: {
+            bloom: {
+                format: HDR_FORMAT,
+                samples: 1,
+                load_op: Clear, // Clear any bars outside of a letterboxed viewport, which the blur reaches
+                store_op: Store, // Sampled by the next blur, or by the depth-of-field pass
+            }
+        },
+        pass: {
+            color: [bloom],
+            depth_stencil: {},
+        }
+    )
+    .unwrap()
+}
+
+// The Vulkan present mode of each presentation mode.
+fn vulkan_present_mode(present_mode: PresentationMode) -> PresentMode {
+    match present_mode {
+        PresentationMode::Vsync => PresentMode::Fifo,
+        PresentationMode::Mailbox => PresentMode::Mailbox,
+        PresentationMode::Immediate => PresentMode::Immediate,
+    }
+}
+
+// Helper for initializing the app render pass
+fn create_app_render_pass(
+    device: &Arc<Device>,
+    image_format: vulkano::format::Format,
+) -> Arc<RenderPass> {
+    vulkano::ordered_passes_renderpass!(
+        device.clone(),
+        attachments: {
+            scene_color: {
+                format: HDR_FORMAT,
+                samples: 1,
+                load_op: DontCare, // Every pixel is written by the depth-of-field pass
+                store_op: DontCare,
+            },
+
+            scene_depth: {
+                format: vulkano::format::Format::R32_SFLOAT,
+                samples: 1,
+                load_op: DontCare,
+                store_op: DontCare,
+            },
+
+            fractal_color: {
+                format: HDR_FORMAT,
+                samples: 1, // No MSAA necessary when rendering a single quad with shaders ;)
+                load_op: DontCare,
+                store_op: DontCare,
+            },
+
+            particle_trails: {
+                format: HDR_FORMAT,
+                samples: 1,
+                load_op: DontCare, // Every pixel is written by the depth-of-field pass
+                store_op: Store, // Sampled by the next frame's depth-of-field pass
+            },
+
+            display_color: {
+                format: image_format,
+                samples: 1,
+                load_op: DontCare, // Every pixel is written by the tone-mapping pass
+                store_op: Store, // Sampled by the temporal-resolve pass
+            }
+        },
+        passes: [
+            // Depth-of-field pass, which also forwards the particle depth to the fractal and accumulates the particle trails
+            {
+                color: [scene_color, scene_depth, particle_trails],
+                depth_stencil: {},
+                input: []
+            },
+
             // Fractal pass
             {
                 color: [fractal_color],
                 depth_stencil: {},
-                input: [particle_color, particle_depth]
+                input: [scene_color, scene_depth]
+            },
+
+            // Tone-mapping pass, mapping the unbounded colors to the display
+            {
+                color: [display_color],
+                depth_stencil: {},
+                input: [fractal_color]
+            }
+        ]
+    )
+    .unwrap()
+}
+
+// Helper for initializing the render pass drawing the particles straight into the fractal, used while nothing post-processes them.
+// It shares the attachments of the particle and app render passes, so that the present pass reads the same display color
+fn create_direct_render_pass(
+    device: &Arc<Device>,
+    samples: u32,
+    image_format: vulkano::format::Format,
+) -> Arc<RenderPass> {
+    // Without multisampling, particles are drawn directly to the input of the fractal and the intermediary is unused
+    if samples == 1 {
+        return vulkano::ordered_passes_renderpass!(
+            device.clone(),
+            attachments: {
+                intermediary: {
+                    format: HDR_FORMAT,
+                    samples: 1,
+                    load_op: DontCare,
+                    store_op: DontCare,
+                },
+
+                particle_color: {
+                    format: HDR_FORMAT,
+                    samples: 1,
+                    load_op: Clear,
+                    store_op: DontCare, // Only read within the render pass
+                },
+
+                particle_depth: {
+                    format: vulkano::format::Format::D16_UNORM,
+                    samples: 1,
+                    load_op: Clear,
+                    store_op: DontCare,
+                },
+
+                particle_min_depth: {
+                    format: vulkano::format::Format::R32_SFLOAT,
+                    samples: 1,
+                    load_op: DontCare, // Every pixel is written by the depth-resolve pass
+                    store_op: DontCare,
+                },
+
+                fractal_color: {
+                    format: HDR_FORMAT,
+                    samples: 1,
+                    load_op: DontCare,
+                    store_op: DontCare,
+                },
+
+                display_color: {
+                    format: image_format,
+                    samples: 1,
+                    load_op: DontCare, // Every pixel is written by the tone-mapping pass
+                    store_op: Store, // Sampled by the temporal-resolve pass
+                }
+            },
+            passes: [
+                // Particles pass
+                {
+                    color: [particle_color],
+                    depth_stencil: {particle_depth},
+                    input: [],
+                },
+
+                // Depth-resolve pass
+                {
+                    color: [particle_min_depth],
+                    depth_stencil: {},
+                    input: [particle_depth]
+                },
+
+                // Fractal pass
+                {
+                    color: [fractal_color],
+                    depth_stencil: {},
+                    input: [particle_color, particle_min_depth]
+                },
+
+                // Tone-mapping pass
+                {
+                    color: [display_color],
+                    depth_stencil: {},
+                    input: [fractal_color]
+                }
+            ]
+        )
+        .unwrap();
+    }
+
+    vulkano::ordered_passes_renderpass!(
+        device.clone(),
+        attachments: {
+            intermediary: {
+                format: HDR_FORMAT,
+                samples: samples,
+                load_op: Clear,
+                store_op: DontCare,
+            },
+
+            particle_color: {
+                format: HDR_FORMAT,
+                samples: 1,
+                load_op: DontCare, // Resolve does not need destination image to be cleared
+                store_op: DontCare, // Only read within the render pass
+            },
+
+            particle_depth: {
+                format: vulkano::format::Format::D16_UNORM,
+                samples: samples, // Must match sample count of color
+                load_op: Clear,
+                store_op: DontCare,
+            },
+
+            particle_min_depth: {
+                format: vulkano::format::Format::R32_SFLOAT,
+                samples: 1,
+                load_op: DontCare, // Every pixel is written by the depth-resolve pass
+                store_op: DontCare,
+            },
+
+            fractal_color: {
+                format: HDR_FORMAT,
+                samples: 1,
+                load_op: DontCare,
+                store_op: DontCare,
+            },
+
+            display_color: {
+                format: image_format,
+                samples: 1,
+                load_op: DontCare, // Every pixel is written by the tone-mapping pass
+                store_op: Store, // Sampled by the temporal-resolve pass
+            }
+        },
+        passes: [
+            // Particles pass, resolved into the input of the fractal
+            {
+                color: [intermediary],
+                color_resolve: [particle_color],
+                depth_stencil: {particle_depth},
+                input: [],
+            },
+
+            // Depth-resolve pass, keeping the nearest of each pixel's depth samples
+            {
+                color: [particle_min_depth],
+                depth_stencil: {},
+                input: [particle_depth]
+            },
+
+            // Fractal pass
+            {
+                color: [fractal_color],
+                depth_stencil: {},
+                input: [particle_color, particle_min_depth]
+            },
+
+            // Tone-mapping pass
+            {
+                color: [display_color],
+                depth_stencil: {},
+                input: [fractal_color]
+            }
+        ]
+    )
+    .unwrap()
+}
+
+// Helper for initializing the render pass which presents the final image.
+// Without storing the history, the frame is passed through instead of resolved. Both share a layout, so the GUI may be drawn in either
+fn create_present_render_pass(
+    device: &Arc<Device>,
+    image_format: vulkano::format::Format,
+    store_history: bool,
+) -> Arc<RenderPass> {
+    if !store_history {
+        return vulkano::ordered_passes_renderpass!(
+            device.clone(),
+            attachments: {
+                resolved_color: {
+                    format: image_format,
+                    samples: 1,
+                    load_op: Clear, // Clear any bars outside of a letterboxed viewport
+                    store_op: Store,
+                },
+
+                history: {
+                    format: HDR_FORMAT,
+                    samples: 1,
+                    load_op: DontCare,
+                    store_op: DontCare, // Never written by the temporal passthrough
+                }
+            },
+            passes: [
+                // Temporal passthrough pass, fading the fractal
+                {
+                    color: [resolved_color, history],
+                    depth_stencil: {},
+                    input: []
+                },
+
+                // GUI pass
+                { color: [resolved_color], depth_stencil: {}, input: [] }
+            ]
+        )
+        .unwrap();
+    }
+
+    vulkano::ordered_passes_renderpass!(
+        device.clone(),
+        attachments: {
+            resolved_color: {
+                format: image_format, // Use swapchain's format since we are writing to its buffers
+                samples: 1,
+                load_op: Clear, // Clear any bars outside of a letterboxed viewport
+                store_op: Store,
+            },
+
+            history: {
+                format: HDR_FORMAT,
+                samples: 1,
+                load_op: DontCare,
+                store_op: Store, // Sampled by the next frame's temporal-resolve pass
+            }
+        },
+        passes: [
+            // Temporal-resolve pass, blending the fractal with the reprojected history
+            {
+                color: [resolved_color, history],
+                depth_stencil: {},
+                input: []
             },
 
             // GUI pass
-            { color: [fractal_color], depth_stencil: {}, input: [] }
+            { color: [resolved_color], depth_stencil: {}, input: [] }
         ]
     )
     .unwrap()
 }
 
+// The region of the window content is rendered to, which is a centered square when letterboxing.
+#[allow(clippy::cast_precision_loss)]
+fn content_viewport(dimensions: PhysicalSize<u32>, aspect_fit: AspectFit) -> Viewport {
+    let (offset, extent) = if aspect_fit == AspectFit::Letterbox {
+        let side = dimensions.width.min(dimensions.height);
+        (
+            [
+                ((dimensions.width - side) / 2) as f32,
+                ((dimensions.height - side) / 2) as f32,
+            ],
+            [side as f32; 2],
+        )
+    } else {
+        ([0., 0.], dimensions.into())
+    };
+    Viewport {
+        offset,
+        extent,
+        depth_range: 0.0..=1.,
+    }
+}
+
+// The regions of the content viewport the particles and the fractal are drawn to, respectively.
+// Splitting the screen gives any odd column to the fractal, so that together the halves cover the viewport
+fn layout_viewports(viewport: &Viewport, display_layout: DisplayLayout) -> (Viewport, Viewport) {
+    if display_layout != DisplayLayout::SplitScreen {
+        return (viewport.clone(), viewport.clone());
+    }
+    let half = (0.5 * viewport.extent[0]).floor();
+    let particles = Viewport {
+        extent: [half, viewport.extent[1]],
+        ..viewport.clone()
+    };
+    let fractal = Viewport {
+        offset: [viewport.offset[0] + half, viewport.offset[1]],
+        extent: [viewport.extent[0] - half, viewport.extent[1]],
+        ..viewport.clone()
+    };
+    (particles, fractal)
+}
+
+// Anamorphic content is stretched from a square, so only native content is bound to the viewport shape.
+fn content_aspect_ratio(viewport: &Viewport, aspect_fit: AspectFit) -> f32 {
+    match aspect_fit {
+        AspectFit::Native => viewport.extent[0] / viewport.extent[1],
+        AspectFit::Letterbox | AspectFit::Anamorphic => 1.,
+    }
+}
+
 impl From<&AppConfig> for ConfigConstants {
     fn from(config: &AppConfig) -> Self {
-        Self {
-            max_speed: config.max_speed,
+        let mut constants = Self {
             particle_count: config.particle_count as f32,
-            spring_coefficient: config.spring_coefficient,
-            friction_scale: config.friction_scale,
-            point_size: config.point_size,
             hide_stationary_particles: u32::from(config.hide_stationary_particles),
             disable_background: u32::from(config.disable_background),
-            audio_scale: config.audio_scale,
-            vertical_fov: config.vertical_fov,
-        }
+
+            // Written from the parameter registry below
+            max_speed: 0.,
+            spring_coefficient: 0.,
+            friction_scale: 0.,
+            point_size: 0.,
+            audio_scale: 0.,
+            vertical_fov: 0.,
+        };
+        crate::parameters::write_constants(&config.parameters, &mut constants);
+        constants
     }
 }
 
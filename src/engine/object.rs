@@ -16,32 +16,187 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::thread::JoinHandle;
 
+use smallvec::smallvec;
 use vulkano::buffer::{Buffer, BufferCreateInfo, BufferUsage, Subbuffer};
+use vulkano::command_buffer::allocator::{
+    StandardCommandBufferAllocator, StandardCommandBufferAllocatorCreateInfo,
+};
 use vulkano::command_buffer::{
-    AutoCommandBufferBuilder, CommandBufferUsage, CopyBufferInfo, PrimaryCommandBufferAbstract,
+    AutoCommandBufferBuilder, CommandBufferUsage, CopyBufferInfo, CopyBufferToImageInfo,
+    PrimaryCommandBufferAbstract,
 };
 use vulkano::descriptor_set::allocator::StandardDescriptorSetAllocator;
 use vulkano::descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet};
 use vulkano::device::{Device, Queue};
-use vulkano::memory::allocator::{AllocationCreateInfo, MemoryTypeFilter};
-use vulkano::pipeline::compute::ComputePipelineCreateInfo;
+use vulkano::format::Format;
+use vulkano::image::sampler::{Filter, Sampler, SamplerAddressMode, SamplerCreateInfo};
+use vulkano::image::view::ImageView;
+use vulkano::image::{Image, ImageCreateInfo, ImageUsage, SampleCount};
+use vulkano::memory::allocator::{AllocationCreateInfo, MemoryTypeFilter, StandardMemoryAllocator};
+use vulkano::pipeline::cache::PipelineCache;
 use vulkano::pipeline::graphics::viewport::Viewport;
-use vulkano::pipeline::layout::PipelineDescriptorSetLayoutCreateInfo;
-use vulkano::pipeline::{
-    ComputePipeline, GraphicsPipeline, Pipeline, PipelineLayout, PipelineShaderStageCreateInfo,
-};
+use vulkano::pipeline::{ComputePipeline, GraphicsPipeline, Pipeline};
 use vulkano::render_pass::{RenderPass, Subpass};
-use vulkano::shader::ShaderModule;
-use vulkano::sync::GpuFuture;
+use vulkano::shader::{ShaderModule, ShaderModuleCreateInfo};
+use vulkano::sync::{GpuFuture, Sharing};
+use vulkano::{Validated, VulkanError};
 
 use super::vertex::PointParticle;
 use super::{pipeline, Allocators};
-use crate::app_config::{AppConfig, Scheme};
+use crate::app_config::{
+    AppConfig, DisplayLayout, ParticleBlending, ParticleRepulsion, Scheme, SpriteShape,
+};
 use crate::my_math::{Vector2, Vector3};
 use crate::space_filling_curves;
 
+// The directory beside the executable from which a distribution may provide patched shaders, as GLSL.
+const EXTERNAL_SHADER_DIRECTORY: &str = "shaders";
+
+// The GLSL shared between shaders by inclusion, embedded so that shaders compiled at runtime may include it too.
+const SHARED_INCLUDES: [(&str, &str); 1] = [(
+    "distance_estimator.glsl",
+    include_str!("../../shaders/distance_estimator.glsl"),
+)];
+
+// A shader embedded as SPIR-V at build time, which the GLSL file of the same name in the external shader
+// directory replaces when present. Replacements must keep the interface of the embedded shader.
+// A replaced shared include also causes the shader to be compiled at runtime, so every shader including it agrees
+pub struct ShaderSource {
+    pub file_name: &'static str,
+    pub kind: shaderc::ShaderKind,
+    pub defines: &'static [&'static str],
+    pub includes: &'static [&'static str], // The names of the shared GLSL files it includes
+    pub glsl: &'static str, // The embedded shader's source, for compiling with replaced includes
+    pub embedded: fn(Arc<Device>) -> Result<Arc<ShaderModule>, Validated<VulkanError>>,
+}
+
+// Locate the external shader directory, if a distribution provides one.
+pub fn external_shader_directory() -> Option<PathBuf> {
+    let directory = std::env::current_exe()
+        .ok()?
+        .parent()?
+        .join(EXTERNAL_SHADER_DIRECTORY);
+    directory.is_dir().then_some(directory)
+}
+
+impl ShaderSource {
+    // Load the replacement from the given directory if it exists and compiles, otherwise the embedded shader.
+    // The embedded GLSL is compiled instead when only the shared includes are replaced
+    pub fn load(&self, device: &Arc<Device>, directory: Option<&Path>) -> Arc<ShaderModule> {
+        if let Some(directory) = directory {
+            let path = directory.join(self.file_name);
+            let replaced = path.is_file();
+            if replaced
+                || self
+                    .includes
+                    .iter()
+                    .any(|name| directory.join(name).is_file())
+            {
+                match self.compile(device, replaced.then_some(path.as_path()), directory) {
+                    Ok(module) => {
+                        log!("Loaded external shader {}", path.display());
+                        return module;
+                    }
+                    Err(e) => log!(
+                        "Failed to load external shader {}, using the embedded shader: {e:?}",
+                        path.display()
+                    ),
+                }
+            }
+        }
+        (self.embedded)(device.clone())
+            .unwrap_or_else(|e| panic!("Failed to load shader {}: {e:?}", self.file_name))
+    }
+
+    // Compile the GLSL at the given path, or else the embedded GLSL, to SPIR-V and create a module from it.
+    fn compile(
+        &self,
+        device: &Arc<Device>,
+        path: Option<&Path>,
+        directory: &Path,
+    ) -> anyhow::Result<Arc<ShaderModule>> {
+        let source = match path {
+            Some(path) => std::fs::read_to_string(path)?,
+            None => String::from(self.glsl),
+        };
+        let mut options = compile_options(Some(directory))?;
+        for define in self.defines {
+            options.add_macro_definition(define, None);
+        }
+        compile_glsl(device, &source, self.kind, self.file_name, &options)
+    }
+}
+
+// Options for compiling GLSL to SPIR-V for Vulkan at runtime, including shared GLSL from the given directory if present.
+pub fn compile_options<'a>(
+    directory: Option<&Path>,
+) -> anyhow::Result<shaderc::CompileOptions<'a>> {
+    let mut options = shaderc::CompileOptions::new()
+        .ok_or_else(|| anyhow::anyhow!("Failed to create the shader compile options"))?;
+    options.set_target_env(
+        shaderc::TargetEnv::Vulkan,
+        shaderc::EnvVersion::Vulkan1_0 as u32,
+    );
+    let directory = directory.map(Path::to_path_buf);
+    options.set_include_callback(move |name, _, _, _| {
+        resolve_shared_include(name, directory.as_deref())
+    });
+    Ok(options)
+}
+
+// Resolve an include of a shader compiled at runtime to the shared GLSL of the same name.
+// A file of that name in the given directory replaces the embedded GLSL
+pub fn resolve_shared_include(
+    name: &str,
+    directory: Option<&Path>,
+) -> shaderc::IncludeCallbackResult {
+    let (file_name, content) = SHARED_INCLUDES
+        .iter()
+        .find(|(file_name, _)| *file_name == name)
+        .ok_or_else(|| format!("Unknown include `{name}`"))?;
+    match directory
+        .map(|directory| directory.join(file_name))
+        .filter(|path| path.is_file())
+    {
+        Some(path) => std::fs::read_to_string(&path)
+            .map(|content| shaderc::ResolvedInclude {
+                resolved_name: path.display().to_string(),
+                content,
+            })
+            .map_err(|e| format!("Failed to read include `{}`: {e}", path.display())),
+        None => Ok(shaderc::ResolvedInclude {
+            resolved_name: String::from(*file_name),
+            content: String::from(*content),
+        }),
+    }
+}
+
+// Compile the given GLSL to SPIR-V and create a module from it.
+pub fn compile_glsl(
+    device: &Arc<Device>,
+    source: &str,
+    kind: shaderc::ShaderKind,
+    file_name: &str,
+    options: &shaderc::CompileOptions,
+) -> anyhow::Result<Arc<ShaderModule>> {
+    let compiler = shaderc::Compiler::new()
+        .ok_or_else(|| anyhow::anyhow!("Failed to create the shader compiler"))?;
+    let artifact = compiler.compile_into_spirv(source, kind, file_name, "main", Some(options))?;
+
+    // Safety: the code is SPIR-V which shaderc has just compiled for Vulkan.
+    let module = unsafe {
+        ShaderModule::new(
+            device.clone(),
+            ShaderModuleCreateInfo::new(artifact.as_binary()),
+        )
+    }?;
+    Ok(module)
+}
+
 // Create module for the particle's shader macros
 #[allow(
     clippy::expl_impl_clone_on_copy,
@@ -55,26 +210,181 @@ pub mod particle_shaders {
             path: "shaders/particles.frag",
         }
     }
+    pub mod sprite_fs {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "shaders/particles.frag",
+            define: [("INSTANCED_SPRITES", "")],
+        }
+    }
     pub mod vs {
         vulkano_shaders::shader! {
             ty: "vertex",
             path: "shaders/particles.vert",
         }
     }
+    pub mod sprite_vs {
+        vulkano_shaders::shader! {
+            ty: "vertex",
+            path: "shaders/particles.vert",
+            define: [("INSTANCED_SPRITES", "")],
+        }
+    }
+    pub mod sorted_sprite_vs {
+        vulkano_shaders::shader! {
+            ty: "vertex",
+            path: "shaders/particles.vert",
+            define: [("INSTANCED_SPRITES", ""), ("SORTED_SPRITES", "")],
+        }
+    }
     pub mod cs {
         vulkano_shaders::shader! {
             ty: "compute",
             path: "shaders/particles.comp",
         }
     }
+    pub mod surface_cs {
+        vulkano_shaders::shader! {
+            ty: "compute",
+            path: "shaders/fractal_surface.comp",
+        }
+    }
 }
 
+// The particle shaders, which a distribution may replace.
+const PARTICLE_FRAGMENT_SHADER: ShaderSource = ShaderSource {
+    file_name: "particles.frag",
+    kind: shaderc::ShaderKind::Fragment,
+    defines: &[],
+    includes: &[],
+    glsl: include_str!("../../shaders/particles.frag"),
+    embedded: particle_shaders::fs::load,
+};
+const PARTICLE_SPRITE_FRAGMENT_SHADER: ShaderSource = ShaderSource {
+    file_name: "particles.frag",
+    kind: shaderc::ShaderKind::Fragment,
+    defines: &["INSTANCED_SPRITES"],
+    includes: &[],
+    glsl: include_str!("../../shaders/particles.frag"),
+    embedded: particle_shaders::sprite_fs::load,
+};
+const PARTICLE_VERTEX_SHADER: ShaderSource = ShaderSource {
+    file_name: "particles.vert",
+    kind: shaderc::ShaderKind::Vertex,
+    defines: &[],
+    includes: &[],
+    glsl: include_str!("../../shaders/particles.vert"),
+    embedded: particle_shaders::vs::load,
+};
+const PARTICLE_SPRITE_VERTEX_SHADER: ShaderSource = ShaderSource {
+    file_name: "particles.vert",
+    kind: shaderc::ShaderKind::Vertex,
+    defines: &["INSTANCED_SPRITES"],
+    includes: &[],
+    glsl: include_str!("../../shaders/particles.vert"),
+    embedded: particle_shaders::sprite_vs::load,
+};
+const PARTICLE_SORTED_SPRITE_VERTEX_SHADER: ShaderSource = ShaderSource {
+    file_name: "particles.vert",
+    kind: shaderc::ShaderKind::Vertex,
+    defines: &["INSTANCED_SPRITES", "SORTED_SPRITES"],
+    includes: &[],
+    glsl: include_str!("../../shaders/particles.vert"),
+    embedded: particle_shaders::sorted_sprite_vs::load,
+};
+const PARTICLE_COMPUTE_SHADER: ShaderSource = ShaderSource {
+    file_name: "particles.comp",
+    kind: shaderc::ShaderKind::Compute,
+    defines: &[],
+    includes: &[],
+    glsl: include_str!("../../shaders/particles.comp"),
+    embedded: particle_shaders::cs::load,
+};
+const FRACTAL_SURFACE_COMPUTE_SHADER: ShaderSource = ShaderSource {
+    file_name: "fractal_surface.comp",
+    kind: shaderc::ShaderKind::Compute,
+    defines: &[],
+    includes: &["distance_estimator.glsl"],
+    glsl: include_str!("../../shaders/fractal_surface.comp"),
+    embedded: particle_shaders::surface_cs::load,
+};
+
 // Export push constant types to callers
 pub type ParticleComputePushConstants = particle_shaders::cs::PushConstants;
+pub type ParticleAttractors = particle_shaders::cs::Attractors;
 pub type ParticleVertexPushConstants = particle_shaders::vs::PushConstants;
+pub type FractalSurfacePushConstants = particle_shaders::surface_cs::PushConstants;
 pub type ConfigConstants = particle_shaders::vs::ConfigConstants;
 pub type RuntimeConstants = particle_shaders::vs::RuntimeConstants;
 
+// The custom constants of the `[live_uniforms]` config table, packed four to a vector as `vec4 values[4]`.
+pub type LiveUniforms = [[f32; 4]; 4];
+
+// The bindings of the live uniforms in the particle vertex and fractal shaders.
+const LIVE_UNIFORMS_PARTICLE_BINDING: u32 = 3;
+
+// The binding of the particle storage buffer, which only the sprite vertex shader reads.
+const SPRITE_PARTICLES_BINDING: u32 = 5;
+
+// The binding of the particle indices sorted back-to-front, which only the sorted sprite vertex shader reads.
+const SPRITE_SORTED_INDICES_BINDING: u32 = 6;
+
+// The binding of the texture drawn by the `texture` sprite shape, which only the sprite fragment shader reads.
+const SPRITE_TEXTURE_BINDING: u32 = 7;
+pub const LIVE_UNIFORMS_FRACTAL_BINDING: u32 = 4;
+
+// Create module for the particle-grid shader macros, used for particle-particle repulsion
+#[allow(clippy::expl_impl_clone_on_copy, clippy::needless_question_mark)]
+mod particle_grid_shaders {
+    pub mod count {
+        vulkano_shaders::shader! {
+            ty: "compute",
+            path: "shaders/particle_grid_count.comp",
+        }
+    }
+    pub mod scan {
+        vulkano_shaders::shader! {
+            ty: "compute",
+            path: "shaders/particle_grid_scan.comp",
+        }
+    }
+    pub mod scatter {
+        vulkano_shaders::shader! {
+            ty: "compute",
+            path: "shaders/particle_grid_scatter.comp",
+        }
+    }
+    pub mod repel {
+        vulkano_shaders::shader! {
+            ty: "compute",
+            path: "shaders/particle_repulsion.comp",
+        }
+    }
+}
+
+// All particle-grid shaders share the same push constant layout
+pub type ParticleGridPushConstants = particle_grid_shaders::repel::PushConstants;
+
+// Create module for the particle-sort shader macros, used to draw translucent particles back-to-front
+#[allow(clippy::expl_impl_clone_on_copy, clippy::needless_question_mark)]
+mod particle_sort_shaders {
+    pub mod keys {
+        vulkano_shaders::shader! {
+            ty: "compute",
+            path: "shaders/particle_sort_keys.comp",
+        }
+    }
+    pub mod sort {
+        vulkano_shaders::shader! {
+            ty: "compute",
+            path: "shaders/particle_sort.comp",
+        }
+    }
+}
+
+// Both particle-sort shaders share the same push constant layout
+pub type ParticleSortPushConstants = particle_sort_shaders::sort::PushConstants;
+
 // Create module for the fractal shader macros
 #[allow(clippy::expl_impl_clone_on_copy, clippy::needless_question_mark)]
 mod fractal_shaders {
@@ -90,220 +400,535 @@ mod fractal_shaders {
             path: "shaders/entire_view.vert",
         }
     }
+    pub mod particle_passthrough {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "shaders/particle_passthrough.frag",
+        }
+    }
 }
 
+// The fractal shaders, which a distribution may replace.
+pub const FRACTAL_FRAGMENT_SHADER: ShaderSource = ShaderSource {
+    file_name: "ray_march.frag",
+    kind: shaderc::ShaderKind::Fragment,
+    defines: &[],
+    includes: &["distance_estimator.glsl"],
+    glsl: include_str!("../../shaders/ray_march.frag"),
+    embedded: fractal_shaders::fs::load,
+};
+const FRACTAL_VERTEX_SHADER: ShaderSource = ShaderSource {
+    file_name: "entire_view.vert",
+    kind: shaderc::ShaderKind::Vertex,
+    defines: &[],
+    includes: &[],
+    glsl: include_str!("../../shaders/entire_view.vert"),
+    embedded: fractal_shaders::vs::load,
+};
+
 // Export Push Constant types to callers
 pub type FractalPushConstants = fractal_shaders::fs::PushConstants;
 
+// Create module for the particle post-processing shader macros
+#[allow(clippy::expl_impl_clone_on_copy, clippy::needless_question_mark)]
+mod post_process_shaders {
+    pub mod depth_resolve_2_samples {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "shaders/depth_resolve.frag",
+            define: [("SAMPLE_COUNT", "2")],
+        }
+    }
+    pub mod depth_resolve_4_samples {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "shaders/depth_resolve.frag",
+            define: [("SAMPLE_COUNT", "4")],
+        }
+    }
+    pub mod depth_resolve_8_samples {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "shaders/depth_resolve.frag",
+            define: [("SAMPLE_COUNT", "8")],
+        }
+    }
+    pub mod depth_resolve_single_sample {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "shaders/depth_resolve.frag",
+            define: [("SINGLE_SAMPLE", "")],
+        }
+    }
+    pub mod depth_of_field {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "shaders/depth_of_field.frag",
+        }
+    }
+    pub mod temporal_resolve {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "shaders/temporal_resolve.frag",
+        }
+    }
+    pub mod temporal_passthrough {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "shaders/temporal_resolve.frag",
+            define: [("PASSTHROUGH", "")],
+        }
+    }
+    pub mod bloom {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "shaders/bloom.frag",
+        }
+    }
+    pub mod tone_map {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "shaders/tone_map.frag",
+        }
+    }
+}
+
+// Export Push Constant types to callers
+pub type DepthOfFieldPushConstants = post_process_shaders::depth_of_field::PushConstants;
+pub type TemporalResolvePushConstants = post_process_shaders::temporal_resolve::PushConstants;
+pub type BloomPushConstants = post_process_shaders::bloom::PushConstants;
+pub type ToneMapPushConstants = post_process_shaders::tone_map::PushConstants;
+
 const SQUARE_FILLING_CURVE_DEPTH: usize = 6;
 const CUBE_FILLING_CURVE_DEPTH: usize = 4;
 
+// Limits on the resolution of the particle grid, chosen so that both layouts fit the same cell buffers
+const MAX_GRID_SIZE_2D: u32 = 512;
+const MAX_GRID_SIZE_3D: u32 = 64;
+const MAX_GRID_CELL_COUNT: u64 = 1 << 18;
+
+// Particles are updated and drawn in chunks of at most this many, so that very large counts are split into
+// several shorter dispatches and draws rather than one which may exceed device limits or time out
+const MAX_PARTICLES_PER_CHUNK: u64 = 1 << 20;
+pub const PARTICLE_WORK_GROUP_SIZE: u32 = 128; // Keep in sync with `local_size_x` of the particle compute shaders.
+
 // Helper for containing relevant particle data
+#[derive(Clone)]
 pub struct ParticleBuffersTriplet {
     pub vertex: Subbuffer<[PointParticle]>,
     pub fixed_square: Subbuffer<[Vector2]>,
     pub fixed_cube: Subbuffer<[Vector3]>,
+
+    // Each particle's position before the latest compute pass, giving the motion of particles between frames
+    pub previous_positions: Subbuffer<[Vector3]>,
+}
+
+// A contiguous range of particles, with a compute descriptor set binding only their slice of each buffer
+pub struct ParticleChunk {
+    pub first: u32,
+    pub count: u32,
+    pub compute_descriptor_set: Arc<PersistentDescriptorSet>,
+    pub surface_descriptor_set: Arc<PersistentDescriptorSet>,
+}
+
+// A compute stage of the particle grid or sort, with the descriptor set binding the buffers it uses
+pub struct GridStage {
+    pub pipeline: Arc<ComputePipeline>,
+    pub descriptor_set: Arc<PersistentDescriptorSet>,
+}
+
+// Uniform-grid binning of particles, allowing each particle to find its neighbors.
+// The grid is rebuilt every frame by counting, prefix-summing, and scattering particle indices by cell
+pub struct ParticleGrid {
+    pub cell_counts: Subbuffer<[u32]>,
+    pub count: GridStage,
+    pub scan: GridStage,
+    pub scatter: GridStage,
+    pub repel: GridStage,
 }
 
+// Bitonic sorting of the particles by their depth, so that translucent particles are drawn back-to-front.
+// The sorted length is padded to a power of two, with the padding sorted after every particle
+pub struct ParticleSort {
+    pub indices: Subbuffer<[u32]>, // Bound as the index buffer when drawing points
+    pub keys: GridStage,
+    pub sort: GridStage,
+    pub sorted_count: u32,
+}
+
+// Post-processing of the particles, which resolves their depth and optionally blurs them by distance
+pub struct DepthOfField {
+    pub frag_shader: Arc<ShaderModule>,
+    pub pipeline: Arc<GraphicsPipeline>,
+    pub resolve_frag_shader: Arc<ShaderModule>,
+    pub resolve_pipeline: Arc<GraphicsPipeline>,
+    pub sampler: Arc<Sampler>,
+    pub vert_shader: Arc<ShaderModule>,
+}
+// Glow around the brightest particles, blurring them horizontally then vertically before the depth-of-field pass adds the result
+pub struct Bloom {
+    pub frag_shader: Arc<ShaderModule>,
+    pub pipeline: Arc<GraphicsPipeline>,
+    pub sampler: Arc<Sampler>,
+    pub vert_shader: Arc<ShaderModule>,
+}
+// Mapping of the unbounded colors of the particles and fractal to the range of the display
+pub struct ToneMap {
+    pub frag_shader: Arc<ShaderModule>,
+    pub pipeline: Arc<GraphicsPipeline>,
+    pub vert_shader: Arc<ShaderModule>,
+}
+// Temporal anti-aliasing of the fractal, which blends each frame with the reprojected history of previous frames
+pub struct TemporalResolve {
+    pub frag_shader: Arc<ShaderModule>,
+    pub passthrough_frag_shader: Arc<ShaderModule>,
+    pub passthrough_pipeline: Arc<GraphicsPipeline>, // Presents the frame without reading or writing the history
+    pub pipeline: Arc<GraphicsPipeline>,
+    pub sampler: Arc<Sampler>,
+    pub vert_shader: Arc<ShaderModule>,
+}
 pub struct Fractal {
     pub frag_shader: Arc<ShaderModule>,
+    pub passthrough_frag_shader: Arc<ShaderModule>,
+    pub passthrough_pipeline: Arc<GraphicsPipeline>, // Draws the particles alone where the fractal is not shown
     pub pipeline: Arc<GraphicsPipeline>,
     pub vert_shader: Arc<ShaderModule>,
 }
+// Pipelines drawing the particles straight into the fractal within a single render pass, used while nothing post-processes them
+pub struct DirectPass {
+    pub fractal_pipeline: Arc<GraphicsPipeline>,
+    pub particle_pipelines: Vec<Arc<GraphicsPipeline>>, // In the order of `Engine::particles`
+    pub passthrough_pipeline: Arc<GraphicsPipeline>,
+    pub resolve_pipeline: Arc<GraphicsPipeline>,
+    pub tone_map_pipeline: Arc<GraphicsPipeline>,
+}
 pub struct Particles {
     pub scheme_buffer: Subbuffer<Scheme>,
-    pub compute_descriptor_set: Arc<PersistentDescriptorSet>,
+    pub chunks: Vec<ParticleChunk>,
     pub compute_pipeline: Arc<ComputePipeline>,
+    pub surface_pipeline: Arc<ComputePipeline>, // Respawns particles on the fractal's surface
     pub frag_shader: Arc<ShaderModule>,
     pub graphics_descriptor_set: Arc<PersistentDescriptorSet>,
     pub graphics_pipeline: Arc<GraphicsPipeline>,
+    pub sprites: bool, // Whether particles are drawn as instanced quads rather than points
+    pub alpha_blended: bool, // Whether particles are translucent, blending over those drawn before them
     pub vert_shader: Arc<ShaderModule>,
     pub vertex_buffers: ParticleBuffersTriplet,
-}
+    pub grid: Option<ParticleGrid>,
+    pub sort: Option<ParticleSort>,
 
-fn create_particle_buffers(
-    allocators: &Allocators,
-    queue: &Arc<Queue>,
-    app_config: &AppConfig,
-) -> ParticleBuffersTriplet {
-    let particle_count_f32 = app_config.particle_count as f32;
+    // The worker thread filling the particle buffers, which must not be used until it finishes
+    upload: Option<JoinHandle<Option<()>>>,
+    pending_arrangement: Option<Vec<Vector2>>, // An arrangement requested during the upload, applied after it
 
-    // Buffer usage for device-local storage buffers.
-    let storage_usage = BufferCreateInfo {
-        usage: BufferUsage::STORAGE_BUFFER | BufferUsage::TRANSFER_DST | BufferUsage::VERTEX_BUFFER,
-        ..Default::default()
-    };
+    // Initial particle vertices and previous positions to copy over the particles by the next frame's commands
+    pub pending_reset: Option<(Subbuffer<[PointParticle]>, Subbuffer<[Vector3]>)>,
+}
 
-    // A helper for creating device-local buffers from iterators.
-    fn device_local_buffer<T: bytemuck::Pod + std::marker::Send + std::marker::Sync>(
-        allocators: &Allocators,
-        queue: &Arc<Queue>,
-        storage_usage: BufferCreateInfo,
-        iter: impl ExactSizeIterator<Item = T>,
-    ) -> Option<Subbuffer<[T]>> {
-        // Buffer usage for temporary transfer buffers into device-local memory.
-        let temp_usage = BufferCreateInfo {
+// Helper for creating a host-visible buffer to copy from.
+fn temporary_buffer<T: bytemuck::Pod + Send + Sync>(
+    memory: &Arc<StandardMemoryAllocator>,
+    iter: impl ExactSizeIterator<Item = T>,
+) -> Option<Subbuffer<[T]>> {
+    Buffer::from_iter(
+        memory.clone(),
+        BufferCreateInfo {
             usage: BufferUsage::TRANSFER_SRC,
             ..Default::default()
-        };
-        // Memory type filter for temporary transfer buffers.
-        let temp_memory = AllocationCreateInfo {
+        },
+        AllocationCreateInfo {
             memory_type_filter: MemoryTypeFilter::HOST_SEQUENTIAL_WRITE
                 | MemoryTypeFilter::PREFER_HOST,
             ..Default::default()
-        };
-
-        // Memory type filter for device-local storage buffers.
-        let device_memory = AllocationCreateInfo {
-            // Specify this buffer will only be used by the device.
-            memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
-            ..Default::default()
-        };
+        },
+        iter,
+    )
+    .map_err(|err| log!("Failed to create temporary buffer: {err:?}"))
+    .ok()
+}
 
-        // Create temporary buffer from the input iterator.
-        let temporary_accessible_buffer =
-            Buffer::from_iter(allocators.memory.clone(), temp_usage, temp_memory, iter)
-                .map_err(|err| println!("Failed to create temporary buffer: {err:?}"))
-                .ok()?;
+// Allocate the device-local particle buffers, leaving their initial contents to `upload_particle_buffers`.
+// Buffers are shared with any dedicated transfer queue, so that no ownership transfer is needed after the upload
+fn create_particle_buffers(
+    allocators: &Allocators,
+    queue: &Queue,
+    transfer_queue: &Queue,
+    particle_count: usize,
+) -> ParticleBuffersTriplet {
+    let sharing = if queue.queue_family_index() == transfer_queue.queue_family_index() {
+        Sharing::Exclusive
+    } else {
+        Sharing::Concurrent(smallvec![
+            queue.queue_family_index(),
+            transfer_queue.queue_family_index()
+        ])
+    };
 
-        // Create a buffer in device-local memory with enough space.
-        let device_local_buffer = Buffer::new_slice::<T>(
+    // A helper for creating device-local storage buffers with room for every particle.
+    let device_local_buffer = |name: &str| {
+        Buffer::new_slice(
             allocators.memory.clone(),
-            storage_usage,
-            device_memory,
-            temporary_accessible_buffer.len() as vulkano::DeviceSize,
+            BufferCreateInfo {
+                sharing: sharing.clone(),
+                usage: BufferUsage::STORAGE_BUFFER
+                    | BufferUsage::TRANSFER_DST
+                    | BufferUsage::VERTEX_BUFFER,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                // Specify this buffer will only be used by the device.
+                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
+                ..Default::default()
+            },
+            particle_count as vulkano::DeviceSize,
         )
-        .map_err(|err| println!("Failed to create device-local buffer: {err:?}"))
-        .ok()?;
+        .unwrap_or_else(|err| panic!("Failed to create {name} buffer: {err:?}"))
+    };
 
-        // Create one-time command to copy between the buffers.
-        let mut cbb = AutoCommandBufferBuilder::primary(
-            &allocators.command_buffer,
-            queue.queue_family_index(),
-            CommandBufferUsage::OneTimeSubmit,
-        )
-        .unwrap();
-        cbb.copy_buffer(CopyBufferInfo::buffers(
-            temporary_accessible_buffer,
-            device_local_buffer.clone(),
-        ))
-        .map_err(|err| println!("Failed to create buffer-copy command: {err:?}"))
-        .ok()?;
-        let cb = cbb.build().unwrap();
+    ParticleBuffersTriplet {
+        vertex: device_local_buffer("vertex"),
+        fixed_square: device_local_buffer("2D-fixed-position"),
+        fixed_cube: device_local_buffer("3D-fixed-position"),
+        previous_positions: device_local_buffer("previous-position"),
+    }
+}
 
-        // Execute copy and wait for copy to complete before proceeding.
-        cb.execute(queue.clone())
-            .map_err(|err| println!("Failed to execute buffer-copy command: {err:?}"))
-            .ok()?
-            .then_signal_fence_and_flush()
-            .unwrap()
-            .wait(None /* timeout */)
-            .unwrap();
+// The position of the given particle along the square-filling curve over the screen.
+#[allow(clippy::cast_precision_loss)]
+fn square_position(i: usize, particle_count: usize) -> Vector2 {
+    space_filling_curves::square::curve_to_square_n(
+        i as f32 / particle_count as f32,
+        SQUARE_FILLING_CURVE_DEPTH,
+    )
+}
 
-        Some(device_local_buffer)
-    }
+// Create temporary buffers holding the initial particle vertices and their previous positions.
+// Particles begin at rest along the square-filling curve, so their previous positions are the same as their initial ones
+fn initial_particle_buffers(
+    memory: &Arc<StandardMemoryAllocator>,
+    particle_count: usize,
+) -> Option<(Subbuffer<[PointParticle]>, Subbuffer<[Vector3]>)> {
+    let initial_position = |i: usize| {
+        let Vector2 { x, y } = square_position(i, particle_count);
+        Vector3::new(x, y, 0.)
+    };
+    let vertex = temporary_buffer(
+        memory,
+        (0..particle_count).map(|i| PointParticle {
+            pos: initial_position(i),
+            vel: Vector3::default(),
+        }),
+    )?;
+    let previous_positions = temporary_buffer(memory, (0..particle_count).map(initial_position))?;
+    Some((vertex, previous_positions))
+}
+
+// Fill the particle buffers with their initial contents, waiting for the copies to complete.
+// Generating a million or more positions takes a moment, so this is run on a worker thread during startup
+fn upload_particle_buffers(
+    memory: &Arc<StandardMemoryAllocator>,
+    queue: &Arc<Queue>,
+    buffers: ParticleBuffersTriplet,
+) -> Option<()> {
+    let particle_count = buffers.vertex.len() as usize;
+    let particle_count_f32 = particle_count as f32;
 
     // Create position data by mapping particle index to screen using a space filling curve
-    let square_position_iter = (0..app_config.particle_count).map(|i| {
-        space_filling_curves::square::curve_to_square_n(
-            i as f32 / particle_count_f32,
-            SQUARE_FILLING_CURVE_DEPTH,
-        )
-    });
+    let fixed_square = temporary_buffer(
+        memory,
+        (0..particle_count).map(|i| square_position(i, particle_count)),
+    )?;
 
-    // Create immutable fixed-position buffer for 2D perspective
-    let fixed_square = device_local_buffer(
-        &allocators,
-        queue,
-        storage_usage.clone(),
-        square_position_iter,
-    )
-    .expect("Failed to create 2D-fixed-position buffer");
+    // Likewise for the 3D perspective, using a cube-filling curve
+    let fixed_cube = temporary_buffer(
+        memory,
+        (0..particle_count).map(|i| {
+            space_filling_curves::cube::curve_to_cube_n(
+                i as f32 / particle_count_f32,
+                CUBE_FILLING_CURVE_DEPTH,
+            )
+        }),
+    )?;
 
-    // Create position data by mapping particle index to screen using a space filling curve
-    let cube_position_iter = (0..app_config.particle_count).map(|i| {
-        space_filling_curves::cube::curve_to_cube_n(
-            i as f32 / particle_count_f32,
-            CUBE_FILLING_CURVE_DEPTH,
-        )
-    });
+    let (vertex, previous_positions) = initial_particle_buffers(memory, particle_count)?;
 
-    // Create immutable fixed-position buffer for 3D perspective
-    let fixed_cube = device_local_buffer(
-        &allocators,
-        queue,
-        storage_usage.clone(),
-        cube_position_iter,
+    // The engine's command buffer allocator belongs to the main thread, so the upload uses its own.
+    let command_buffer_allocator = StandardCommandBufferAllocator::new(
+        queue.device().clone(),
+        StandardCommandBufferAllocatorCreateInfo::default(),
+    );
+    let mut cbb = AutoCommandBufferBuilder::primary(
+        &command_buffer_allocator,
+        queue.queue_family_index(),
+        CommandBufferUsage::OneTimeSubmit,
     )
-    .expect("Failed to create 3D-fixed-position buffer");
+    .unwrap();
+    cbb.copy_buffer(CopyBufferInfo::buffers(vertex, buffers.vertex))
+        .and_then(|cbb| {
+            cbb.copy_buffer(CopyBufferInfo::buffers(fixed_square, buffers.fixed_square))
+        })
+        .and_then(|cbb| cbb.copy_buffer(CopyBufferInfo::buffers(fixed_cube, buffers.fixed_cube)))
+        .and_then(|cbb| {
+            cbb.copy_buffer(CopyBufferInfo::buffers(
+                previous_positions,
+                buffers.previous_positions,
+            ))
+        })
+        .map_err(|err| log!("Failed to create buffer-copy command: {err:?}"))
+        .ok()?;
 
-    // Create vertex data by re-calculating position
-    let vertex_iter = (0..app_config.particle_count).map(|i| PointParticle {
-        pos: {
-            let Vector2 { x, y } = space_filling_curves::square::curve_to_square_n(
-                i as f32 / particle_count_f32,
-                SQUARE_FILLING_CURVE_DEPTH,
-            );
-            Vector3::new(x, y, 0.)
+    // Execute copies and wait for them to complete before the buffers are used.
+    cbb.build()
+        .unwrap()
+        .execute(queue.clone())
+        .map_err(|err| log!("Failed to execute buffer-copy command: {err:?}"))
+        .ok()?
+        .then_signal_fence_and_flush()
+        .unwrap()
+        .wait(None /* timeout */)
+        .unwrap();
+    Some(())
+}
+
+// Upload the PNG image drawn by the `texture` sprite shape, waiting for the copy to complete.
+// Without an image, or if it fails to load, a single white texel is used so that sprites keep their color
+fn load_sprite_texture(
+    allocators: &Allocators,
+    queue: &Arc<Queue>,
+    path: Option<&str>,
+) -> Arc<ImageView> {
+    let (width, height, rgba) = path
+        .and_then(|path| {
+            std::fs::File::open(path)
+                .and_then(|file| ico::IconImage::read_png(std::io::BufReader::new(file)))
+                .map_err(|err| log!("Failed to load sprite texture {path}: {err:?}"))
+                .ok()
+        })
+        .map_or_else(
+            || (1, 1, vec![u8::MAX; 4]),
+            |image| (image.width(), image.height(), image.rgba_data().to_vec()),
+        );
+
+    let image = Image::new(
+        allocators.memory.clone(),
+        ImageCreateInfo {
+            format: Format::R8G8B8A8_SRGB,
+            extent: [width, height, 1],
+            usage: ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
+            ..Default::default()
         },
-        vel: Vector3::default(),
-    });
+        AllocationCreateInfo::default(),
+    )
+    .expect("Failed to create sprite texture");
 
-    // Create position buffer
-    let vertex = device_local_buffer(&allocators, queue, storage_usage.clone(), vertex_iter)
-        .expect("Failed to create 3D-fixed-position buffer");
+    let mut cbb = AutoCommandBufferBuilder::primary(
+        &allocators.command_buffer,
+        queue.queue_family_index(),
+        CommandBufferUsage::OneTimeSubmit,
+    )
+    .unwrap();
+    let pixels = temporary_buffer(&allocators.memory, rgba.into_iter())
+        .expect("Failed to create sprite texture buffer");
+    cbb.copy_buffer_to_image(CopyBufferToImageInfo::buffer_image(pixels, image.clone()))
+        .expect("Failed to create sprite texture copy command");
+    cbb.build()
+        .unwrap()
+        .execute(queue.clone())
+        .expect("Failed to execute sprite texture copy command")
+        .then_signal_fence_and_flush()
+        .unwrap()
+        .wait(None /* timeout */)
+        .unwrap();
+    ImageView::new_default(image).expect("Failed to create sprite texture view")
+}
 
-    ParticleBuffersTriplet {
-        vertex,
-        fixed_square,
-        fixed_cube,
-    }
+// The most particles the device can process with a single dispatch, limited by work-group counts and storage-buffer ranges.
+fn max_particles_per_dispatch(device: &Device) -> u64 {
+    let properties = device.physical_device().properties();
+    let by_group_count =
+        u64::from(properties.max_compute_work_group_count[0]) * u64::from(PARTICLE_WORK_GROUP_SIZE);
+    let by_buffer_range = u64::from(properties.max_storage_buffer_range)
+        / std::mem::size_of::<PointParticle>() as u64;
+    by_group_count.min(by_buffer_range)
+}
+
+// Choose the size of each particle chunk within the device's limits.
+// Chunks are kept a whole number of work groups, which also keeps each slice aligned to the storage-buffer offset alignment
+fn particles_per_chunk(device: &Device) -> u64 {
+    let group_size = u64::from(PARTICLE_WORK_GROUP_SIZE);
+    let size = MAX_PARTICLES_PER_CHUNK.min(max_particles_per_dispatch(device));
+    assert!(
+        size >= group_size,
+        "The device cannot dispatch even a single work group of particles"
+    );
+    size - size % group_size
 }
 
 impl Particles {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         allocators: &Allocators,
         queue: &Arc<Queue>,
+        transfer_queue: &Arc<Queue>,
+        pipeline_cache: &Arc<PipelineCache>,
         render_pass: &Arc<RenderPass>,
         viewport: Viewport,
         app_config: &AppConfig,
+        particle_count: usize,
         config_constants: Subbuffer<ConfigConstants>,
         runtime_constants: Subbuffer<RuntimeConstants>,
+        live_uniforms: Subbuffer<LiveUniforms>,
+        shader_directory: Option<&Path>,
     ) -> Self {
         // Load particle shaders
         let device = queue.device();
-        let frag_shader = particle_shaders::fs::load(device.clone())
-            .expect("Failed to load particle fragment shader");
-        let vert_shader = particle_shaders::vs::load(device.clone())
-            .expect("Failed to load particle vertex shader");
-        let comp_shader = particle_shaders::cs::load(device.clone())
-            .expect("Failed to load particle compute shader")
-            .entry_point("main")
-            .unwrap();
-
-        // Create compute pipeline for particles
-        let compute_stage = PipelineShaderStageCreateInfo::new(comp_shader);
-        let compute_layout = PipelineLayout::new(
-            device.clone(),
-            PipelineDescriptorSetLayoutCreateInfo::from_stages([&compute_stage])
-                .into_pipeline_layout_create_info(device.clone())
-                .unwrap(),
-        )
-        .unwrap();
-        let compute_pipeline = ComputePipeline::new(
-            device.clone(),
-            None, // comp_shader.entry_point("main").unwrap()
-            ComputePipelineCreateInfo::stage_layout(compute_stage, compute_layout),
-        )
-        .expect("Failed to create compute shader");
+        let sprites = app_config.particle_sprites;
+        let frag_shader = if sprites {
+            PARTICLE_SPRITE_FRAGMENT_SHADER
+        } else {
+            PARTICLE_FRAGMENT_SHADER
+        }
+        .load(device, shader_directory);
+
+        // Translucent particles are sorted back-to-front, which processes every particle in one dispatch
+        let alpha_blended = app_config.particle_blending == ParticleBlending::Alpha;
+        let sorted_count = (particle_count as u64)
+            .next_power_of_two()
+            .max(u64::from(PARTICLE_WORK_GROUP_SIZE));
+        let sorted = alpha_blended && sorted_count <= max_particles_per_dispatch(device);
+        if alpha_blended && !sorted {
+            log!("Translucent particles are drawn unsorted, {sorted_count} sorted particles exceed the device's limits for a single dispatch");
+        }
+
+        let vert_shader = match (sprites, sorted) {
+            (false, _) => PARTICLE_VERTEX_SHADER,
+            (true, false) => PARTICLE_SPRITE_VERTEX_SHADER,
+            (true, true) => PARTICLE_SORTED_SPRITE_VERTEX_SHADER,
+        }
+        .load(device, shader_directory);
+        let comp_shader = PARTICLE_COMPUTE_SHADER.load(device, shader_directory);
+
+        // Create compute pipelines for particles
+        let compute_pipeline =
+            pipeline::create_compute(device.clone(), pipeline_cache, &comp_shader);
+        let surface_shader = FRACTAL_SURFACE_COMPUTE_SHADER.load(device, shader_directory);
+        let surface_pipeline =
+            pipeline::create_compute(device.clone(), pipeline_cache, &surface_shader);
 
         // Create the almighty graphics pipelines
-        let graphics_pipeline = pipeline::create_particle(
+        let create_graphics_pipeline = if sprites {
+            pipeline::create_particle_sprites
+        } else {
+            pipeline::create_particle
+        };
+        let graphics_pipeline = create_graphics_pipeline(
             device.clone(),
+            pipeline_cache,
             &vert_shader,
             &frag_shader,
             Subpass::from(render_pass.clone(), 0).expect("Failed to create subpass"),
             viewport,
+            alpha_blended,
         );
 
         // Particle color schemes?!
@@ -314,36 +939,148 @@ impl Particles {
         *scheme_buffer
             .write()
             .expect("Failed to initialize color scheme buffer") = app_config.color_schemes[0];
+
+        // Create storage buffers for particle info, filling them in the background on the transfer queue
+        let vertex_buffers =
+            create_particle_buffers(allocators, queue, transfer_queue, particle_count);
+        let upload = {
+            let memory = allocators.memory.clone();
+            let transfer_queue = transfer_queue.clone();
+            let buffers = vertex_buffers.clone();
+            std::thread::spawn(move || upload_particle_buffers(&memory, &transfer_queue, buffers))
+        };
+
+        #[allow(clippy::cast_possible_truncation)]
+        let sort = sorted.then(|| {
+            ParticleSort::new(
+                allocators,
+                device,
+                pipeline_cache,
+                &vertex_buffers.vertex,
+                sorted_count as u32,
+            )
+        });
+
+        // Only the `texture` sprite shape samples the user's image, the others are given a single white texel
+        let sprite_texture = load_sprite_texture(
+            allocators,
+            queue,
+            app_config
+                .sprite_texture
+                .as_deref()
+                .filter(|_| sprites && app_config.sprite_shape == SpriteShape::Texture),
+        );
+        let sprite_sampler = Sampler::new(
+            device.clone(),
+            SamplerCreateInfo {
+                mag_filter: Filter::Linear,
+                min_filter: Filter::Linear,
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..Default::default()
+            },
+        )
+        .expect("Failed to create sprite texture sampler");
+
         let graphics_descriptor_set = Self::new_graphics_descriptor(
             &allocators.descriptor_set,
             &graphics_pipeline,
             scheme_buffer.clone(),
             config_constants.clone(),
-            runtime_constants,
+            runtime_constants.clone(),
+            live_uniforms,
+            &vertex_buffers,
+            sort.as_ref(),
+            (sprite_texture, sprite_sampler),
         );
 
-        // Create storage buffers for particle info
-        let vertex_buffers = create_particle_buffers(allocators, queue, app_config);
-
-        // Create a new descriptor set for binding particle storage buffers
+        // Create a descriptor set for binding each chunk's slice of the particle storage buffers
         // Required to access layout() method
-        let compute_descriptor_set = Self::new_compute_descriptor(
-            &allocators.descriptor_set,
-            &compute_pipeline,
-            &vertex_buffers,
-            config_constants,
-        );
+        let chunk_size = particles_per_chunk(device);
+        let particle_count = vertex_buffers.vertex.len();
+        #[allow(clippy::cast_possible_truncation)]
+        let chunks = (0..particle_count)
+            .step_by(chunk_size as usize)
+            .map(|first| {
+                let range = first..(first + chunk_size).min(particle_count);
+                ParticleChunk {
+                    first: first as u32,
+                    count: (range.end - range.start) as u32,
+                    compute_descriptor_set: Self::new_compute_descriptor(
+                        &allocators.descriptor_set,
+                        &compute_pipeline,
+                        &vertex_buffers,
+                        range.clone(),
+                        config_constants.clone(),
+                        runtime_constants.clone(),
+                    ),
+                    surface_descriptor_set: Self::new_surface_descriptor(
+                        &allocators.descriptor_set,
+                        &surface_pipeline,
+                        &vertex_buffers,
+                        range,
+                        runtime_constants.clone(),
+                    ),
+                }
+            })
+            .collect();
+
+        // Create the grid used to find neighboring particles.
+        // The grid processes every particle in one dispatch, so it is unavailable beyond the device's limits
+        let grid = if particle_count <= max_particles_per_dispatch(device) {
+            Some(ParticleGrid::new(
+                allocators,
+                device,
+                pipeline_cache,
+                &vertex_buffers.vertex,
+            ))
+        } else {
+            log!("Particle repulsion is disabled, {particle_count} particles exceed the device's limits for a single dispatch");
+            None
+        };
 
         Self {
             scheme_buffer,
-            compute_descriptor_set,
+            chunks,
             compute_pipeline,
+            surface_pipeline,
             frag_shader,
             graphics_descriptor_set,
             graphics_pipeline,
+            sprites,
+            alpha_blended,
             vert_shader,
             vertex_buffers,
+            grid,
+            sort,
+            upload: Some(upload),
+            pending_arrangement: None,
+            pending_reset: None,
+        }
+    }
+
+    // Whether the particle buffers are filled, finishing the upload if it has just completed.
+    pub fn poll_upload(&mut self, allocators: &Allocators, queue: &Arc<Queue>) -> bool {
+        if self
+            .upload
+            .as_ref()
+            .is_some_and(|upload| !upload.is_finished())
+        {
+            return false;
+        }
+        if let Some(upload) = self.upload.take() {
+            upload
+                .join()
+                .ok()
+                .flatten()
+                .expect("Failed to upload particle buffers");
+            if let Some(positions) = self.pending_arrangement.take() {
+                self.arrange(allocators, queue, &positions);
+            }
         }
+        true
+    }
+    pub fn uploaded(&self) -> bool {
+        self.upload.is_none()
     }
 
     // Update particle state when color scheme changes
@@ -351,31 +1088,135 @@ impl Particles {
         *self.scheme_buffer.write().expect("Update color buffer") = scheme;
     }
 
+    // Return the particles to their initial positions at rest, copied by the commands of the next frame.
+    // The upload already begins the particles there, so a reset during it only cancels any pending arrangement
+    pub fn reset(&mut self, memory: &Arc<StandardMemoryAllocator>) {
+        self.pending_arrangement = None;
+        if self.upload.is_none() {
+            self.pending_reset =
+                initial_particle_buffers(memory, self.vertex_buffers.vertex.len() as usize);
+        }
+    }
+
+    // Place the particles at rest at the given 2D positions, such as to begin arranged as an image.
+    // Waits for the copy to complete, so should only be used before any frames are in flight.
+    // The upload would overwrite the arrangement, so any requested during it is deferred until it is polled complete
+    pub fn arrange(&mut self, allocators: &Allocators, queue: &Arc<Queue>, positions: &[Vector2]) {
+        if self.upload.is_some() {
+            self.pending_arrangement = Some(positions.to_vec());
+            return;
+        }
+
+        let position_iter = positions
+            .iter()
+            .map(|&Vector2 { x, y }| Vector3::new(x, y, 0.));
+        let vertex_iter = position_iter.clone().map(|pos| PointParticle {
+            pos,
+            vel: Vector3::default(),
+        });
+        let (Some(temporary_vertices), Some(temporary_positions)) = (
+            temporary_buffer(&allocators.memory, vertex_iter),
+            temporary_buffer(&allocators.memory, position_iter),
+        ) else {
+            return;
+        };
+
+        let mut cbb = AutoCommandBufferBuilder::primary(
+            &allocators.command_buffer,
+            queue.queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .unwrap();
+        // At rest, the previous positions are the same as the current ones.
+        if let Err(err) = cbb
+            .copy_buffer(CopyBufferInfo::buffers(
+                temporary_vertices,
+                self.vertex_buffers.vertex.clone(),
+            ))
+            .and_then(|cbb| {
+                cbb.copy_buffer(CopyBufferInfo::buffers(
+                    temporary_positions,
+                    self.vertex_buffers.previous_positions.clone(),
+                ))
+            })
+        {
+            log!("Failed to create buffer-copy command: {err:?}");
+            return;
+        }
+        match cbb.build().unwrap().execute(queue.clone()) {
+            Ok(future) => future
+                .then_signal_fence_and_flush()
+                .unwrap()
+                .wait(None /* timeout */)
+                .unwrap(),
+            Err(err) => log!("Failed to execute buffer-copy command: {err:?}"),
+        }
+    }
+
     // Helpers for creating particle desciptor sets
+    #[allow(clippy::too_many_arguments)]
     fn new_graphics_descriptor(
         allocator: &StandardDescriptorSetAllocator,
         pipeline: &Arc<GraphicsPipeline>,
         scheme: Subbuffer<Scheme>,
         config_constants: Subbuffer<ConfigConstants>,
         runtime_constants: Subbuffer<RuntimeConstants>,
+        live_uniforms: Subbuffer<LiveUniforms>,
+        vertex_buffers: &ParticleBuffersTriplet,
+        sort: Option<&ParticleSort>,
+        (sprite_texture, sprite_sampler): (Arc<ImageView>, Arc<Sampler>),
     ) -> Arc<PersistentDescriptorSet> {
-        PersistentDescriptorSet::new(
-            allocator,
-            pipeline.layout().set_layouts().get(0).unwrap().clone(),
-            [
-                WriteDescriptorSet::buffer(0, scheme),
-                WriteDescriptorSet::buffer(1, config_constants),
-                WriteDescriptorSet::buffer(2, runtime_constants),
-            ],
-            [],
-        )
-        .expect("Failed to create particle graphics descriptor set")
+        let layout = pipeline.layout().set_layouts().get(0).unwrap().clone();
+        let mut writes = vec![
+            WriteDescriptorSet::buffer(0, scheme),
+            WriteDescriptorSet::buffer(1, config_constants),
+            WriteDescriptorSet::buffer(2, runtime_constants),
+            WriteDescriptorSet::buffer(4, vertex_buffers.previous_positions.clone()),
+        ];
+        if layout.bindings().contains_key(&SPRITE_PARTICLES_BINDING) {
+            writes.push(WriteDescriptorSet::buffer(
+                SPRITE_PARTICLES_BINDING,
+                vertex_buffers.vertex.clone(),
+            ));
+        }
+        if let Some(sort) = sort.filter(|_| {
+            layout
+                .bindings()
+                .contains_key(&SPRITE_SORTED_INDICES_BINDING)
+        }) {
+            writes.push(WriteDescriptorSet::buffer(
+                SPRITE_SORTED_INDICES_BINDING,
+                sort.indices.clone(),
+            ));
+        }
+        if layout.bindings().contains_key(&SPRITE_TEXTURE_BINDING) {
+            writes.push(WriteDescriptorSet::image_view_sampler(
+                SPRITE_TEXTURE_BINDING,
+                sprite_texture,
+                sprite_sampler,
+            ));
+        }
+
+        // Bindings unused by the shader are absent from its layout, as are the live uniforms until a shader reads them.
+        if layout
+            .bindings()
+            .contains_key(&LIVE_UNIFORMS_PARTICLE_BINDING)
+        {
+            writes.push(WriteDescriptorSet::buffer(
+                LIVE_UNIFORMS_PARTICLE_BINDING,
+                live_uniforms,
+            ));
+        }
+        PersistentDescriptorSet::new(allocator, layout, writes, [])
+            .expect("Failed to create particle graphics descriptor set")
     }
     fn new_compute_descriptor(
         allocator: &StandardDescriptorSetAllocator,
         pipeline: &Arc<ComputePipeline>,
         vertex_buffers: &ParticleBuffersTriplet,
+        range: std::ops::Range<u64>,
         config_constants: Subbuffer<ConfigConstants>,
+        runtime_constants: Subbuffer<RuntimeConstants>,
     ) -> Arc<PersistentDescriptorSet> {
         PersistentDescriptorSet::new(
             allocator,
@@ -386,30 +1227,490 @@ impl Particles {
                 .unwrap()
                 .clone(),
             [
-                WriteDescriptorSet::buffer(0, vertex_buffers.vertex.clone()), // 0 is the binding of the data in this set
-                WriteDescriptorSet::buffer(1, vertex_buffers.fixed_square.clone()),
-                WriteDescriptorSet::buffer(2, vertex_buffers.fixed_cube.clone()),
+                WriteDescriptorSet::buffer(0, vertex_buffers.vertex.clone().slice(range.clone())), // 0 is the binding of the data in this set
+                WriteDescriptorSet::buffer(
+                    1,
+                    vertex_buffers.fixed_square.clone().slice(range.clone()),
+                ),
+                WriteDescriptorSet::buffer(
+                    2,
+                    vertex_buffers.fixed_cube.clone().slice(range.clone()),
+                ),
                 WriteDescriptorSet::buffer(3, config_constants),
+                WriteDescriptorSet::buffer(
+                    4,
+                    vertex_buffers.previous_positions.clone().slice(range),
+                ),
+                WriteDescriptorSet::buffer(5, runtime_constants),
             ],
             [],
         )
         .expect("Failed to create particle compute descriptor set")
     }
+
+    fn new_surface_descriptor(
+        allocator: &StandardDescriptorSetAllocator,
+        pipeline: &Arc<ComputePipeline>,
+        vertex_buffers: &ParticleBuffersTriplet,
+        range: std::ops::Range<u64>,
+        runtime_constants: Subbuffer<RuntimeConstants>,
+    ) -> Arc<PersistentDescriptorSet> {
+        PersistentDescriptorSet::new(
+            allocator,
+            pipeline.layout().set_layouts().get(0).unwrap().clone(),
+            [
+                WriteDescriptorSet::buffer(0, vertex_buffers.vertex.clone().slice(range.clone())),
+                WriteDescriptorSet::buffer(
+                    1,
+                    vertex_buffers.previous_positions.clone().slice(range),
+                ),
+                WriteDescriptorSet::buffer(2, runtime_constants),
+            ],
+            [],
+        )
+        .expect("Failed to create fractal-surface descriptor set")
+    }
+}
+
+impl ParticleGrid {
+    fn new(
+        allocators: &Allocators,
+        device: &Arc<Device>,
+        pipeline_cache: &Arc<PipelineCache>,
+        vertex_buffer: &Subbuffer<[PointParticle]>,
+    ) -> Self {
+        // Helper for allocating the device-local index buffers of the grid.
+        let new_index_buffer = |len: u64| {
+            Buffer::new_slice::<u32>(
+                allocators.memory.clone(),
+                BufferCreateInfo {
+                    usage: BufferUsage::STORAGE_BUFFER | BufferUsage::TRANSFER_DST,
+                    ..Default::default()
+                },
+                AllocationCreateInfo {
+                    memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
+                    ..Default::default()
+                },
+                len,
+            )
+            .expect("Failed to create particle-grid buffer")
+        };
+        let particle_count = vertex_buffer.len();
+        let cell_counts = new_index_buffer(MAX_GRID_CELL_COUNT);
+        let cell_offsets = new_index_buffer(MAX_GRID_CELL_COUNT);
+        let particle_cells = new_index_buffer(particle_count);
+        let particle_slots = new_index_buffer(particle_count);
+        let sorted_indices = new_index_buffer(particle_count);
+
+        // Helper for creating a stage from its shader and the buffers it binds.
+        let new_stage = |shader: Arc<ShaderModule>, writes: Vec<WriteDescriptorSet>| {
+            let pipeline = pipeline::create_compute(device.clone(), pipeline_cache, &shader);
+            let descriptor_set = PersistentDescriptorSet::new(
+                &allocators.descriptor_set,
+                pipeline.layout().set_layouts().get(0).unwrap().clone(),
+                writes,
+                [],
+            )
+            .expect("Failed to create particle-grid descriptor set");
+            GridStage {
+                pipeline,
+                descriptor_set,
+            }
+        };
+
+        let count = new_stage(
+            particle_grid_shaders::count::load(device.clone())
+                .expect("Failed to load particle-grid count shader"),
+            vec![
+                WriteDescriptorSet::buffer(0, vertex_buffer.clone()),
+                WriteDescriptorSet::buffer(1, cell_counts.clone()),
+                WriteDescriptorSet::buffer(3, particle_cells.clone()),
+                WriteDescriptorSet::buffer(4, particle_slots.clone()),
+            ],
+        );
+        let scan = new_stage(
+            particle_grid_shaders::scan::load(device.clone())
+                .expect("Failed to load particle-grid scan shader"),
+            vec![
+                WriteDescriptorSet::buffer(1, cell_counts.clone()),
+                WriteDescriptorSet::buffer(2, cell_offsets.clone()),
+            ],
+        );
+        let scatter = new_stage(
+            particle_grid_shaders::scatter::load(device.clone())
+                .expect("Failed to load particle-grid scatter shader"),
+            vec![
+                WriteDescriptorSet::buffer(2, cell_offsets.clone()),
+                WriteDescriptorSet::buffer(3, particle_cells),
+                WriteDescriptorSet::buffer(4, particle_slots),
+                WriteDescriptorSet::buffer(5, sorted_indices.clone()),
+            ],
+        );
+        let repel = new_stage(
+            particle_grid_shaders::repel::load(device.clone())
+                .expect("Failed to load particle repulsion shader"),
+            vec![
+                WriteDescriptorSet::buffer(0, vertex_buffer.clone()),
+                WriteDescriptorSet::buffer(1, cell_counts.clone()),
+                WriteDescriptorSet::buffer(2, cell_offsets),
+                WriteDescriptorSet::buffer(5, sorted_indices),
+            ],
+        );
+
+        Self {
+            cell_counts,
+            count,
+            scan,
+            scatter,
+            repel,
+        }
+    }
+
+    // Determine the grid layout and push constants for this frame's repulsion pass.
+    // Cells are at least as wide as the repulsion radius so that only adjacent cells must be searched
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    pub fn push_constants(
+        repulsion: ParticleRepulsion,
+        delta_time: f32,
+        use_third_dimension: bool,
+        particle_count: u64,
+    ) -> ParticleGridPushConstants {
+        let max_grid_size = if use_third_dimension {
+            MAX_GRID_SIZE_3D
+        } else {
+            MAX_GRID_SIZE_2D
+        };
+        let grid_size = ((2. / repulsion.radius) as u32).clamp(1, max_grid_size);
+        let cell_count = if use_third_dimension {
+            grid_size * grid_size * grid_size
+        } else {
+            grid_size * grid_size
+        };
+
+        ParticleGridPushConstants {
+            strength: repulsion.strength,
+            radius: repulsion.radius,
+            delta_time,
+            grid_size,
+            cell_count,
+            particle_count: particle_count as u32,
+            use_third_dimension: u32::from(use_third_dimension),
+        }
+    }
+}
+
+impl ParticleSort {
+    fn new(
+        allocators: &Allocators,
+        device: &Arc<Device>,
+        pipeline_cache: &Arc<PipelineCache>,
+        vertex_buffer: &Subbuffer<[PointParticle]>,
+        sorted_count: u32,
+    ) -> Self {
+        // Helper for allocating the device-local buffers of the sort.
+        let new_sort_buffer = |usage: BufferUsage| {
+            Buffer::new_slice::<u32>(
+                allocators.memory.clone(),
+                BufferCreateInfo {
+                    usage: BufferUsage::STORAGE_BUFFER | usage,
+                    ..Default::default()
+                },
+                AllocationCreateInfo {
+                    memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
+                    ..Default::default()
+                },
+                u64::from(sorted_count),
+            )
+            .expect("Failed to create particle-sort buffer")
+        };
+        let sort_keys = new_sort_buffer(BufferUsage::empty());
+        let indices = new_sort_buffer(BufferUsage::INDEX_BUFFER);
+
+        // Helper for creating a stage from its shader and the buffers it binds.
+        let new_stage = |shader: Arc<ShaderModule>, writes: Vec<WriteDescriptorSet>| {
+            let pipeline = pipeline::create_compute(device.clone(), pipeline_cache, &shader);
+            let descriptor_set = PersistentDescriptorSet::new(
+                &allocators.descriptor_set,
+                pipeline.layout().set_layouts().get(0).unwrap().clone(),
+                writes,
+                [],
+            )
+            .expect("Failed to create particle-sort descriptor set");
+            GridStage {
+                pipeline,
+                descriptor_set,
+            }
+        };
+
+        let keys = new_stage(
+            particle_sort_shaders::keys::load(device.clone())
+                .expect("Failed to load particle-sort key shader"),
+            vec![
+                WriteDescriptorSet::buffer(0, vertex_buffer.clone()),
+                WriteDescriptorSet::buffer(1, sort_keys.clone()),
+                WriteDescriptorSet::buffer(2, indices.clone()),
+            ],
+        );
+        let sort = new_stage(
+            particle_sort_shaders::sort::load(device.clone())
+                .expect("Failed to load particle-sort shader"),
+            vec![
+                WriteDescriptorSet::buffer(1, sort_keys),
+                WriteDescriptorSet::buffer(2, indices.clone()),
+            ],
+        );
+
+        Self {
+            indices,
+            keys,
+            sort,
+            sorted_count,
+        }
+    }
 }
 
 impl Fractal {
-    pub fn new(device: &Arc<Device>, render_pass: &Arc<RenderPass>, viewport: Viewport) -> Self {
+    pub fn new(
+        device: &Arc<Device>,
+        pipeline_cache: &Arc<PipelineCache>,
+        render_pass: &Arc<RenderPass>,
+        viewport: Viewport,
+        particle_viewport: Viewport,
+        shader_directory: Option<&Path>,
+    ) -> Self {
         // Load fractal shaders
-        let frag_shader = fractal_shaders::fs::load(device.clone())
-            .expect("Failed to load fractal fragment shader");
+        let frag_shader = FRACTAL_FRAGMENT_SHADER.load(device, shader_directory);
+        let vert_shader = FRACTAL_VERTEX_SHADER.load(device, shader_directory);
+        let passthrough_frag_shader = fractal_shaders::particle_passthrough::load(device.clone())
+            .expect("Failed to load particle passthrough fragment shader");
+
+        let subpass =
+            Subpass::from(render_pass.clone(), 1).expect("Failed to create fractal subpass");
+        let pipeline = pipeline::create_entire_view(
+            device.clone(),
+            pipeline_cache,
+            &vert_shader,
+            &frag_shader,
+            subpass.clone(),
+            viewport,
+        );
+        let passthrough_pipeline = pipeline::create_entire_view(
+            device.clone(),
+            pipeline_cache,
+            &vert_shader,
+            &passthrough_frag_shader,
+            subpass,
+            particle_viewport,
+        );
+
+        Self {
+            frag_shader,
+            passthrough_frag_shader,
+            passthrough_pipeline,
+            pipeline,
+            vert_shader,
+        }
+    }
+}
+
+impl DepthOfField {
+    pub fn new(
+        device: &Arc<Device>,
+        pipeline_cache: &Arc<PipelineCache>,
+        particle_render_pass: &Arc<RenderPass>,
+        render_pass: &Arc<RenderPass>,
+        viewport: Viewport,
+    ) -> Self {
+        // Load post-processing shaders, resolving depth according to whether the particles are multisampled
+        let particle_subpass = Subpass::from(particle_render_pass.clone(), 0)
+            .expect("Failed to create particle subpass");
+        let resolve_frag_shader = match particle_subpass.num_samples() {
+            Some(SampleCount::Sample2) => {
+                post_process_shaders::depth_resolve_2_samples::load(device.clone())
+            }
+            Some(SampleCount::Sample4) => {
+                post_process_shaders::depth_resolve_4_samples::load(device.clone())
+            }
+            Some(SampleCount::Sample8) => {
+                post_process_shaders::depth_resolve_8_samples::load(device.clone())
+            }
+            _ => post_process_shaders::depth_resolve_single_sample::load(device.clone()),
+        }
+        .expect("Failed to load depth-resolve fragment shader");
+        let frag_shader = post_process_shaders::depth_of_field::load(device.clone())
+            .expect("Failed to load depth-of-field fragment shader");
+        let vert_shader = fractal_shaders::vs::load(device.clone())
+            .expect("Failed to load depth-of-field vertex shader");
+
+        // Depth is resolved in the subpass following the particles, and blurring begins the app pass
+        let resolve_pipeline = pipeline::create_entire_view(
+            device.clone(),
+            pipeline_cache,
+            &vert_shader,
+            &resolve_frag_shader,
+            Subpass::from(particle_render_pass.clone(), 1)
+                .expect("Failed to create depth-resolve subpass"),
+            viewport.clone(),
+        );
+        let pipeline = pipeline::create_entire_view(
+            device.clone(),
+            pipeline_cache,
+            &vert_shader,
+            &frag_shader,
+            Subpass::from(render_pass.clone(), 0).expect("Failed to create depth-of-field subpass"),
+            viewport,
+        );
+
+        // Pixels are fetched directly, so filtering is unused
+        let sampler = Sampler::new(
+            device.clone(),
+            SamplerCreateInfo {
+                mag_filter: Filter::Nearest,
+                min_filter: Filter::Nearest,
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..Default::default()
+            },
+        )
+        .expect("Failed to create depth-of-field sampler");
+
+        Self {
+            frag_shader,
+            pipeline,
+            resolve_frag_shader,
+            resolve_pipeline,
+            sampler,
+            vert_shader,
+        }
+    }
+}
+
+impl TemporalResolve {
+    pub fn new(
+        device: &Arc<Device>,
+        pipeline_cache: &Arc<PipelineCache>,
+        present_render_pass: &Arc<RenderPass>,
+        present_passthrough_render_pass: &Arc<RenderPass>,
+        viewport: Viewport,
+    ) -> Self {
+        // Load temporal-resolve shaders
+        let frag_shader = post_process_shaders::temporal_resolve::load(device.clone())
+            .expect("Failed to load temporal-resolve fragment shader");
+        let passthrough_frag_shader =
+            post_process_shaders::temporal_passthrough::load(device.clone())
+                .expect("Failed to load temporal passthrough fragment shader");
+        let vert_shader = fractal_shaders::vs::load(device.clone())
+            .expect("Failed to load temporal-resolve vertex shader");
+
+        let pipeline = pipeline::create_entire_view(
+            device.clone(),
+            pipeline_cache,
+            &vert_shader,
+            &frag_shader,
+            Subpass::from(present_render_pass.clone(), 0)
+                .expect("Failed to create temporal-resolve subpass"),
+            viewport.clone(),
+        );
+        let passthrough_pipeline = pipeline::create_entire_view(
+            device.clone(),
+            pipeline_cache,
+            &vert_shader,
+            &passthrough_frag_shader,
+            Subpass::from(present_passthrough_render_pass.clone(), 0)
+                .expect("Failed to create temporal passthrough subpass"),
+            viewport,
+        );
+
+        // Reprojected history lands between pixels, so it is filtered
+        let sampler = Sampler::new(
+            device.clone(),
+            SamplerCreateInfo {
+                mag_filter: Filter::Linear,
+                min_filter: Filter::Linear,
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..Default::default()
+            },
+        )
+        .expect("Failed to create temporal-resolve sampler");
+
+        Self {
+            frag_shader,
+            passthrough_frag_shader,
+            passthrough_pipeline,
+            pipeline,
+            sampler,
+            vert_shader,
+        }
+    }
+}
+
+impl Bloom {
+    pub fn new(
+        device: &Arc<Device>,
+        pipeline_cache: &Arc<PipelineCache>,
+        bloom_render_pass: &Arc<RenderPass>,
+        viewport: Viewport,
+    ) -> Self {
+        // Load bloom shaders
+        let frag_shader = post_process_shaders::bloom::load(device.clone())
+            .expect("Failed to load bloom fragment shader");
+        let vert_shader =
+            fractal_shaders::vs::load(device.clone()).expect("Failed to load bloom vertex shader");
+
+        // Both blur directions share a pipeline, differing only by their push constants
+        let pipeline = pipeline::create_entire_view(
+            device.clone(),
+            pipeline_cache,
+            &vert_shader,
+            &frag_shader,
+            Subpass::from(bloom_render_pass.clone(), 0).expect("Failed to create bloom subpass"),
+            viewport,
+        );
+
+        // Taps land between pixels, so they are filtered
+        let sampler = Sampler::new(
+            device.clone(),
+            SamplerCreateInfo {
+                mag_filter: Filter::Linear,
+                min_filter: Filter::Linear,
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..Default::default()
+            },
+        )
+        .expect("Failed to create bloom sampler");
+
+        Self {
+            frag_shader,
+            pipeline,
+            sampler,
+            vert_shader,
+        }
+    }
+}
+
+impl ToneMap {
+    pub fn new(
+        device: &Arc<Device>,
+        pipeline_cache: &Arc<PipelineCache>,
+        render_pass: &Arc<RenderPass>,
+        viewport: Viewport,
+    ) -> Self {
+        // Load tone-mapping shaders
+        let frag_shader = post_process_shaders::tone_map::load(device.clone())
+            .expect("Failed to load tone-mapping fragment shader");
         let vert_shader = fractal_shaders::vs::load(device.clone())
-            .expect("Failed to load fractal vertex shader");
+            .expect("Failed to load tone-mapping vertex shader");
 
-        let pipeline = pipeline::create_fractal(
+        // Colors are mapped in the subpass following the fractal
+        let pipeline = pipeline::create_entire_view(
             device.clone(),
+            pipeline_cache,
             &vert_shader,
             &frag_shader,
-            Subpass::from(render_pass.clone(), 1).expect("Failed to create fractal subpass"),
+            Subpass::from(render_pass.clone(), 2).expect("Failed to create tone-mapping subpass"),
             viewport,
         );
 
@@ -420,3 +1721,86 @@ impl Fractal {
         }
     }
 }
+
+impl DirectPass {
+    // The shaders of each object are reused, so only the pipelines are created for the subpasses of the direct render pass.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        device: &Arc<Device>,
+        pipeline_cache: &Arc<PipelineCache>,
+        render_pass: &Arc<RenderPass>,
+        particles: &[Particles],
+        depth_of_field: &DepthOfField,
+        fractal: &Fractal,
+        tone_map: &ToneMap,
+        viewport: &Viewport,
+        display_layout: DisplayLayout,
+    ) -> Self {
+        let (particle_viewport, fractal_viewport) =
+            super::layout_viewports(viewport, display_layout);
+        let subpass = |index| {
+            Subpass::from(render_pass.clone(), index).expect("Failed to create direct subpass")
+        };
+
+        // Particles are drawn first, then their depth is resolved for the fractal, which the colors are mapped from last
+        let particle_pipelines = particles
+            .iter()
+            .map(|particles| {
+                let create_particle_pipeline = if particles.sprites {
+                    pipeline::create_particle_sprites
+                } else {
+                    pipeline::create_particle
+                };
+                create_particle_pipeline(
+                    device.clone(),
+                    pipeline_cache,
+                    &particles.vert_shader,
+                    &particles.frag_shader,
+                    subpass(0),
+                    particle_viewport.clone(),
+                    particles.alpha_blended,
+                )
+            })
+            .collect();
+        let resolve_pipeline = pipeline::create_entire_view(
+            device.clone(),
+            pipeline_cache,
+            &depth_of_field.vert_shader,
+            &depth_of_field.resolve_frag_shader,
+            subpass(1),
+            viewport.clone(),
+        );
+        let fractal_pipeline = pipeline::create_entire_view(
+            device.clone(),
+            pipeline_cache,
+            &fractal.vert_shader,
+            &fractal.frag_shader,
+            subpass(2),
+            fractal_viewport,
+        );
+        let passthrough_pipeline = pipeline::create_entire_view(
+            device.clone(),
+            pipeline_cache,
+            &fractal.vert_shader,
+            &fractal.passthrough_frag_shader,
+            subpass(2),
+            particle_viewport,
+        );
+        let tone_map_pipeline = pipeline::create_entire_view(
+            device.clone(),
+            pipeline_cache,
+            &tone_map.vert_shader,
+            &tone_map.frag_shader,
+            subpass(3),
+            viewport.clone(),
+        );
+
+        Self {
+            fractal_pipeline,
+            particle_pipelines,
+            passthrough_pipeline,
+            resolve_pipeline,
+            tone_map_pipeline,
+        }
+    }
+}
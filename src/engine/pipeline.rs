@@ -20,31 +20,170 @@ use std::sync::Arc;
 
 use smallvec::smallvec;
 use vulkano::device::Device;
-use vulkano::pipeline::graphics::color_blend::{ColorBlendAttachmentState, ColorBlendState};
+use vulkano::pipeline::cache::{PipelineCache, PipelineCacheCreateInfo};
+use vulkano::pipeline::compute::ComputePipelineCreateInfo;
+use vulkano::pipeline::graphics::color_blend::{
+    AttachmentBlend, BlendFactor, BlendOp, ColorBlendAttachmentState, ColorBlendState,
+};
 use vulkano::pipeline::graphics::depth_stencil::{DepthState, DepthStencilState};
 use vulkano::pipeline::graphics::input_assembly::{InputAssemblyState, PrimitiveTopology};
 use vulkano::pipeline::graphics::multisample::MultisampleState;
 use vulkano::pipeline::graphics::rasterization::RasterizationState;
-use vulkano::pipeline::graphics::vertex_input::VertexInputState;
-use vulkano::pipeline::graphics::viewport::{Viewport, ViewportState};
+use vulkano::pipeline::graphics::vertex_input::{VertexBufferDescription, VertexInputState};
+use vulkano::pipeline::graphics::viewport::{Scissor, Viewport, ViewportState};
 use vulkano::pipeline::graphics::GraphicsPipelineCreateInfo;
 use vulkano::pipeline::layout::PipelineDescriptorSetLayoutCreateInfo;
-use vulkano::pipeline::{GraphicsPipeline, PipelineLayout, PipelineShaderStageCreateInfo};
+use vulkano::pipeline::{
+    ComputePipeline, GraphicsPipeline, PipelineLayout, PipelineShaderStageCreateInfo,
+};
 use vulkano::render_pass::Subpass;
 use vulkano::shader::ShaderModule;
 
 use super::vertex::PointParticle;
 
-// Create a graphics pipeline for displaying a list of particles.
+const PIPELINE_CACHE_FILE: &str = "pipeline_cache.bin";
+
+// Size of the header Vulkan prepends to pipeline cache data, identifying the device which created it.
+const PIPELINE_CACHE_HEADER_SIZE: usize = 32;
+
+// Whether the cache data was created by this device and driver, so that it may be safely reused.
+fn is_compatible_cache_data(device: &Device, data: &[u8]) -> bool {
+    if data.len() < PIPELINE_CACHE_HEADER_SIZE {
+        return false;
+    }
+    let read_u32 = |offset: usize| u32::from_ne_bytes(data[offset..offset + 4].try_into().unwrap());
+    let properties = device.physical_device().properties();
+    read_u32(0) as usize == PIPELINE_CACHE_HEADER_SIZE
+        && read_u32(4) == 1 // `VK_PIPELINE_CACHE_HEADER_VERSION_ONE`
+        && read_u32(8) == properties.vendor_id
+        && read_u32(12) == properties.device_id
+        && data[16..PIPELINE_CACHE_HEADER_SIZE] == properties.pipeline_cache_uuid
+}
+
+// Create a pipeline cache, pre-warmed with the data saved by a previous launch when available.
+pub fn load_cache(device: &Arc<Device>) -> Arc<PipelineCache> {
+    let initial_data = crate::persistence::cache_directory()
+        .and_then(|dir| std::fs::read(dir.join(PIPELINE_CACHE_FILE)).ok())
+        .filter(|data| {
+            let compatible = is_compatible_cache_data(device, data);
+            if !compatible {
+                log!("Ignoring pipeline cache created by a different device or driver");
+            }
+            compatible
+        })
+        .unwrap_or_default();
+
+    // Safety: The data was written by `save_cache` and its header matches this device.
+    // Drivers are required to validate the remaining contents
+    unsafe {
+        PipelineCache::new(
+            device.clone(),
+            PipelineCacheCreateInfo {
+                initial_data,
+                ..Default::default()
+            },
+        )
+    }
+    .expect("Failed to create pipeline cache")
+}
+
+// Persist the contents of the pipeline cache so that the next launch may skip shader compilation.
+pub fn save_cache(cache: &PipelineCache) {
+    let Some(dir) = crate::persistence::cache_directory() else {
+        return;
+    };
+    let result = cache
+        .get_data()
+        .map_err(|e| format!("{e:?}"))
+        .and_then(|data| {
+            std::fs::create_dir_all(&dir)
+                .and_then(|()| std::fs::write(dir.join(PIPELINE_CACHE_FILE), data))
+                .map_err(|e| format!("{e:?}"))
+        });
+    if let Err(e) = result {
+        log!("Failed to save pipeline cache: {e}");
+    }
+}
+
+// A fixed viewport, scissored to its bounds so that nothing is drawn outside of it, such as by large points.
+// Layouts draw separate pipelines to each half of the view, which must not overlap
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn fixed_viewport_state(viewport: Viewport) -> ViewportState {
+    let scissor = Scissor {
+        offset: [viewport.offset[0] as u32, viewport.offset[1] as u32],
+        extent: [viewport.extent[0] as u32, viewport.extent[1] as u32],
+    };
+    ViewportState {
+        viewports: smallvec![viewport],
+        scissors: smallvec![scissor],
+        ..Default::default()
+    }
+}
+
+// Create a graphics pipeline for displaying a list of particles, optionally alpha-blending each over those drawn before it.
 pub fn create_particle(
     device: Arc<Device>,
+    cache: &Arc<PipelineCache>,
+    vert_shader: &Arc<ShaderModule>,
+    frag_shader: &Arc<ShaderModule>,
+    subpass: Subpass,
+    viewport: Viewport,
+    alpha_blended: bool,
+) -> Arc<GraphicsPipeline> {
+    // Needed for `PointParticle::per_vertex()`.
+    use vulkano::pipeline::graphics::vertex_input::Vertex;
+
+    create_particle_pipeline(
+        device,
+        cache,
+        vert_shader,
+        frag_shader,
+        subpass,
+        viewport,
+        alpha_blended,
+        Some(PointParticle::per_vertex()),
+        PrimitiveTopology::PointList,
+    )
+}
+
+// Create a graphics pipeline for displaying particles as instanced quads, each a strip of four vertices.
+// There is no vertex input since the shader pulls each instance's particle from a storage buffer
+pub fn create_particle_sprites(
+    device: Arc<Device>,
+    cache: &Arc<PipelineCache>,
+    vert_shader: &Arc<ShaderModule>,
+    frag_shader: &Arc<ShaderModule>,
+    subpass: Subpass,
+    viewport: Viewport,
+    alpha_blended: bool,
+) -> Arc<GraphicsPipeline> {
+    create_particle_pipeline(
+        device,
+        cache,
+        vert_shader,
+        frag_shader,
+        subpass,
+        viewport,
+        alpha_blended,
+        None,
+        PrimitiveTopology::TriangleStrip,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_particle_pipeline(
+    device: Arc<Device>,
+    cache: &Arc<PipelineCache>,
     vert_shader: &Arc<ShaderModule>,
     frag_shader: &Arc<ShaderModule>,
     subpass: Subpass,
     viewport: Viewport,
+    alpha_blended: bool,
+    vertex_buffer: Option<VertexBufferDescription>,
+    topology: PrimitiveTopology,
 ) -> Arc<GraphicsPipeline> {
-    // Needed for `PointParticle::per_vertex()` and `VertexBufferDescription::definition()` respectively.
-    use vulkano::pipeline::graphics::vertex_input::{Vertex, VertexDefinition};
+    // Needed for `VertexBufferDescription::definition()`.
+    use vulkano::pipeline::graphics::vertex_input::VertexDefinition;
 
     // Setup relevant context for creating the pipeline from these shaders.
     let vs = vert_shader.entry_point("main").unwrap();
@@ -63,22 +202,17 @@ pub fn create_particle(
 
     GraphicsPipeline::new(
         device,
-        None,
+        Some(cache.clone()),
         GraphicsPipelineCreateInfo {
             stages,
-            vertex_input_state: Some(
-                PointParticle::per_vertex()
-                    .definition(&vs.info().input_interface)
-                    .unwrap(),
-            ), // Describes the layout of the vertex input.
+            vertex_input_state: Some(vertex_buffer.map_or_else(VertexInputState::default, |v| {
+                v.definition(&vs.info().input_interface).unwrap()
+            })), // Describes the layout of the vertex input.
             input_assembly_state: Some(InputAssemblyState {
-                topology: PrimitiveTopology::PointList,
+                topology,
                 ..InputAssemblyState::default()
             }), // Indicate the type of the primitives (the default is a list of triangles).
-            viewport_state: Some(ViewportState {
-                viewports: smallvec![viewport],
-                ..Default::default()
-            }), // Set the fixed viewport.
+            viewport_state: Some(fixed_viewport_state(viewport)), // Set the fixed viewport.
             multisample_state: Some(MultisampleState {
                 rasterization_samples: subpass.num_samples().unwrap(),
                 ..Default::default()
@@ -88,15 +222,28 @@ pub fn create_particle(
                 ..DepthStencilState::default()
             }), // Explicitly enable depth testing.
 
-            // Necessary defaults.
-            rasterization_state: Some(RasterizationState::default()),
+            // Translucent particles are drawn back-to-front, so still write depth for the passes which follow.
+            // Particle colors are output with premultiplied alpha so that sprite shapes fade smoothly at their edges.
             color_blend_state: Some(ColorBlendState {
                 attachments: (0..subpass.num_color_attachments())
-                    .map(|_| ColorBlendAttachmentState::default())
+                    .map(|_| ColorBlendAttachmentState {
+                        blend: alpha_blended.then_some(AttachmentBlend {
+                            src_color_blend_factor: BlendFactor::One,
+                            dst_color_blend_factor: BlendFactor::OneMinusSrcAlpha,
+                            color_blend_op: BlendOp::Add,
+                            src_alpha_blend_factor: BlendFactor::One,
+                            dst_alpha_blend_factor: BlendFactor::OneMinusSrcAlpha,
+                            alpha_blend_op: BlendOp::Add,
+                        }),
+                        ..Default::default()
+                    })
                     .collect(),
                 ..Default::default()
             }),
 
+            // Necessary defaults.
+            rasterization_state: Some(RasterizationState::default()),
+
             // Specify the subpass that this pipeline will be used in.
             subpass: Some(subpass.into()),
             ..GraphicsPipelineCreateInfo::layout(layout)
@@ -105,9 +252,10 @@ pub fn create_particle(
     .expect("Failed to construct particle graphics pipeline")
 }
 
-// Create a graphics pipeline for displaying fractals.
-pub fn create_fractal(
+// Create a graphics pipeline drawing a single quad over the entire view, as used by fractals and post-processing.
+pub fn create_entire_view(
     device: Arc<Device>,
+    cache: &Arc<PipelineCache>,
     vert_shader: &Arc<ShaderModule>,
     frag_shader: &Arc<ShaderModule>,
     subpass: Subpass,
@@ -130,7 +278,7 @@ pub fn create_fractal(
 
     GraphicsPipeline::new(
         device,
-        None,
+        Some(cache.clone()),
         GraphicsPipelineCreateInfo {
             stages,
             vertex_input_state: Some(VertexInputState::default()),
@@ -141,10 +289,7 @@ pub fn create_fractal(
                 ..InputAssemblyState::default()
             }),
             // Set the fixed viewport.
-            viewport_state: Some(ViewportState {
-                viewports: smallvec![viewport],
-                ..Default::default()
-            }),
+            viewport_state: Some(fixed_viewport_state(viewport)),
 
             // Necessary defaults.
             rasterization_state: Some(RasterizationState::default()),
@@ -161,5 +306,27 @@ pub fn create_fractal(
             ..GraphicsPipelineCreateInfo::layout(layout)
         },
     )
-    .expect("Failed to construct fractal graphics pipeline")
+    .expect("Failed to construct entire-view graphics pipeline")
+}
+
+// Create a compute pipeline from the `main` entry point of the given shader.
+pub fn create_compute(
+    device: Arc<Device>,
+    cache: &Arc<PipelineCache>,
+    shader: &Arc<ShaderModule>,
+) -> Arc<ComputePipeline> {
+    let stage = PipelineShaderStageCreateInfo::new(shader.entry_point("main").unwrap());
+    let layout = PipelineLayout::new(
+        device.clone(),
+        PipelineDescriptorSetLayoutCreateInfo::from_stages([&stage])
+            .into_pipeline_layout_create_info(device.clone())
+            .unwrap(),
+    )
+    .unwrap();
+    ComputePipeline::new(
+        device,
+        Some(cache.clone()),
+        ComputePipelineCreateInfo::stage_layout(stage, layout),
+    )
+    .expect("Failed to create compute pipeline")
 }
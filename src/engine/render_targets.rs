@@ -0,0 +1,129 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::sync::Arc;
+
+use vulkano::format::Format;
+use vulkano::image::view::ImageView;
+use vulkano::image::{Image, ImageCreateInfo, ImageUsage, SampleCount};
+use vulkano::memory::allocator::{AllocationCreateInfo, StandardMemoryAllocator};
+
+// Idle attachments are kept for this many of the most recently used extents, such as both the windowed and fullscreen sizes.
+const MAX_CACHED_EXTENTS: usize = 3;
+
+// The properties an attachment must share to be reused in place of another.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct TargetKey {
+    format: Format,
+    extent: [u32; 3],
+    samples: SampleCount,
+    usage: ImageUsage,
+}
+
+struct CachedTarget {
+    key: TargetKey,
+    view: Arc<ImageView>,
+    last_used: u64,
+}
+
+// Pool of attachment images, reused when the frame targets are recreated rather than reallocated.
+// Reallocating the large attachments on every resize fragments device memory over long sessions on some drivers
+#[derive(Default)]
+pub struct RenderTargetCache {
+    targets: Vec<CachedTarget>,
+    generation: u64,
+}
+
+impl RenderTargetCache {
+    // Begin a new set of frame targets, marking the attachments used from now on as the most recent.
+    pub fn begin_generation(&mut self) {
+        self.generation += 1;
+    }
+
+    // Get the view of an attachment with the given properties, reusing an idle one if possible.
+    pub fn attachment(
+        &mut self,
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+        format: Format,
+        extent: [u32; 3],
+        samples: SampleCount,
+        usage: ImageUsage,
+    ) -> Arc<ImageView> {
+        let key = TargetKey {
+            format,
+            extent,
+            samples,
+            usage,
+        };
+
+        // An attachment is idle once the cache holds its only reference, so no framebuffer or frame in flight uses it.
+        if let Some(target) = self
+            .targets
+            .iter_mut()
+            .find(|target| target.key == key && Arc::strong_count(&target.view) == 1)
+        {
+            target.last_used = self.generation;
+            return target.view.clone();
+        }
+
+        let view = ImageView::new_default(
+            Image::new(
+                memory_allocator.clone(),
+                ImageCreateInfo {
+                    format,
+                    extent,
+                    samples,
+                    usage,
+                    ..Default::default()
+                },
+                AllocationCreateInfo::default(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        self.targets.push(CachedTarget {
+            key,
+            view: view.clone(),
+            last_used: self.generation,
+        });
+        view
+    }
+
+    // Drop the idle attachments of all but the most recently used extents.
+    pub fn evict(&mut self) {
+        let mut recent_extents: Vec<([u32; 3], u64)> = Vec::new();
+        for target in &self.targets {
+            match recent_extents
+                .iter_mut()
+                .find(|(extent, _)| *extent == target.key.extent)
+            {
+                Some((_, last_used)) => *last_used = (*last_used).max(target.last_used),
+                None => recent_extents.push((target.key.extent, target.last_used)),
+            }
+        }
+        recent_extents.sort_by(|a, b| b.1.cmp(&a.1));
+        recent_extents.truncate(MAX_CACHED_EXTENTS);
+
+        self.targets.retain(|target| {
+            Arc::strong_count(&target.view) > 1
+                || recent_extents
+                    .iter()
+                    .any(|(extent, _)| *extent == target.key.extent)
+        });
+    }
+}
@@ -20,32 +20,40 @@ use std::sync::Arc;
 
 use vulkano::buffer::Subbuffer;
 use vulkano::command_buffer::{
-    AutoCommandBufferBuilder, CommandBufferUsage, PrimaryAutoCommandBuffer, RenderPassBeginInfo,
-    SecondaryAutoCommandBuffer, SubpassBeginInfo, SubpassContents, SubpassEndInfo,
+    AutoCommandBufferBuilder, CommandBufferUsage, CopyBufferInfo, PrimaryAutoCommandBuffer,
+    RenderPassBeginInfo, SecondaryAutoCommandBuffer, SubpassBeginInfo, SubpassContents,
+    SubpassEndInfo,
 };
+use vulkano::descriptor_set::allocator::StandardDescriptorSetAllocator;
 use vulkano::descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet};
 use vulkano::format::ClearValue;
 use vulkano::image::view::ImageView;
 use vulkano::pipeline::{GraphicsPipeline, Pipeline, PipelineBindPoint};
 use vulkano::render_pass::Framebuffer;
 
+use super::object::{
+    BloomPushConstants, GridStage, ParticleChunk, ParticleGrid, ParticleGridPushConstants,
+    ParticleSort, ParticleSortPushConstants, Particles, LIVE_UNIFORMS_FRACTAL_BINDING,
+    PARTICLE_WORK_GROUP_SIZE,
+};
 use super::vertex::PointParticle;
-use super::{DrawData, Engine, FractalPushConstants, ParticleVertexPushConstants};
+use super::{
+    DepthOfFieldPushConstants, DrawData, Engine, FractalPushConstants, FractalSurfacePushConstants,
+    FrameTargets, ParticleAttractors, ParticleComputePushConstants, ParticleVertexPushConstants,
+    TemporalResolvePushConstants, ToneMapPushConstants,
+};
+use crate::app_config::{DisplayLayout, ParticleRepulsion};
 
-// Helper for initializing the rendering of a frame. Must specify clear value of each subpass
+// Helper for initializing a render pass of a frame. Must specify clear value of each attachment
 fn begin_render_pass(
     builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
     framebuffer: &Arc<Framebuffer>,
+    clear_values: Vec<Option<ClearValue>>,
 ) {
     builder
         .begin_render_pass(
             RenderPassBeginInfo {
-                clear_values: vec![
-                    Some([0., 0., 0., 1.].into()),
-                    None,
-                    Some(ClearValue::Depth(1.)),
-                    None,
-                ], // Clear values for attachments
+                clear_values, // Clear values for attachments
                 ..RenderPassBeginInfo::framebuffer(framebuffer.clone())
             },
             SubpassBeginInfo {
@@ -56,9 +64,27 @@ fn begin_render_pass(
         .unwrap();
 }
 
+// Helper for moving to the next subpass of the current render pass
+fn next_subpass(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    contents: SubpassContents,
+) {
+    builder
+        .next_subpass(
+            SubpassEndInfo::default(),
+            SubpassBeginInfo {
+                contents,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+}
+
 pub fn create_render_commands(
     engine: &mut Engine,
-    framebuffer: &Arc<Framebuffer>,
+    frame_targets: &FrameTargets,
+    history: Option<Arc<ImageView>>,
+    previous_trails: Option<Arc<ImageView>>,
     draw_data: &DrawData,
     gui_command_buffer: Option<Arc<SecondaryAutoCommandBuffer>>,
 ) -> Arc<PrimaryAutoCommandBuffer> {
@@ -70,77 +96,123 @@ pub fn create_render_commands(
     )
     .unwrap();
 
-    // Allow toggling of particle effects and avoid unnecesary computation
-    if let Some((compute_push_constants, vertex_push_constants)) = draw_data.particle_data {
-        let compute_pipeline = engine.compute_pipeline();
-        let descriptor_set = engine.compute_descriptor_set().clone();
-        let vertex_buffer = engine.particles.vertex_buffers.vertex.clone();
-        let buffer_count = engine.particle_count() as u32;
+    // Measure the time the GPU spends on this frame
+    if let Some(gpu_timer) = &mut engine.gpu_timer {
+        gpu_timer.begin(&mut builder);
+    }
 
-        // Build compute commands
-        builder
-            // Push constants for compute shader
-            .push_constants(compute_pipeline.layout().clone(), 0, compute_push_constants)
-            .unwrap()
-            // Perform compute operation to update particle positions
-            .bind_pipeline_compute(compute_pipeline.clone())
-            .unwrap()
-            .bind_descriptor_sets(
-                PipelineBindPoint::Compute,
-                compute_pipeline.layout().clone(),
-                0, // Start binding descriptor sets at index 0
-                descriptor_set,
-            )
-            .unwrap()
-            .dispatch([buffer_count / 128, 1, 1])
-            .unwrap();
+    // Clear the particle color and depth, the other attachments are always overwritten.
+    // Particles are only drawn to the intermediary when multisampling
+    let black = Some([0., 0., 0., 1.].into());
+    let (intermediary_clear, particle_clear) = if engine.particle_samples > 1 {
+        (black, None)
+    } else {
+        (None, black)
+    };
+    let particle_clear_values = vec![
+        intermediary_clear,
+        particle_clear,
+        Some(ClearValue::Depth(1.)),
+        None,
+    ];
 
-        // Start render pass
-        begin_render_pass(&mut builder, framebuffer);
+    // Upload this frame's attractors once, to be shared by every particle system
+    let attractors = draw_data.particle_attractors.map(|attractors| {
+        let buffer = engine
+            .allocators
+            .uniform_buffer
+            .allocate_sized::<ParticleAttractors>()
+            .expect("Allocation of particle attractors buffer failed");
+        *buffer
+            .write()
+            .expect("Initialization of particle attractors failed") = attractors;
+        buffer
+    });
+
+    // Simulate each shown particle system, skipping the hidden ones to avoid unnecesary computation
+    for (particles, data) in engine.particles.iter_mut().zip(&draw_data.particle_data) {
+        if let (Some((compute_push_constants, vertex_push_constants)), Some(attractors)) =
+            (data, &attractors)
+        {
+            particle_compute_cmds(
+                &mut builder,
+                &engine.allocators.descriptor_set,
+                particles,
+                *compute_push_constants,
+                attractors.clone(),
+                vertex_push_constants,
+                draw_data.fractal_surface,
+                draw_data.particle_repulsion,
+            );
+        }
+    }
+    if let Some(gpu_timer) = &mut engine.gpu_timer {
+        gpu_timer.end_pass(&mut builder, 0);
+    }
 
-        // Add inline commands to render particles
-        inline_particles_cmds(
+    // Without post-processing, the particles are drawn straight into the fractal within a single render pass
+    if draw_data.post_processes_particles() {
+        post_processed_render_cmds(
             &mut builder,
-            engine.particle_pipeline().clone(),
-            &vertex_buffer,
-            vertex_push_constants,
-            engine.particle_descriptor_set().clone(),
+            engine,
+            frame_targets,
+            previous_trails,
+            draw_data,
+            particle_clear_values,
         );
     } else {
-        // Begin the same render pass as with particles, but skip commands to draw particles
-        begin_render_pass(&mut builder, framebuffer);
+        direct_render_cmds(
+            &mut builder,
+            engine,
+            frame_targets,
+            draw_data,
+            particle_clear_values,
+        );
     }
 
-    // Move to next subpass, fractal rendering
-    builder
-        .next_subpass(
-            SubpassEndInfo::default(),
-            SubpassBeginInfo {
-                contents: SubpassContents::Inline,
-                ..Default::default()
-            },
-        )
-        .unwrap();
-
-    // Add inline commands to render fractal
-    inline_fractal_cmds(
-        &mut builder,
-        engine,
-        draw_data.fractal_data,
-        framebuffer.attachments()[1].clone(),
-        framebuffer.attachments()[2].clone(),
-    );
+    // Start the present render pass, beginning with the temporal resolve of the fractal.
+    // Without a previous frame to reproject, the fractal is passed through unchanged
+    let fractal_color = frame_targets.app.attachments()[4].clone();
+    if engine.resolves_history(draw_data) {
+        let (history, temporal_resolve_data) = match history {
+            Some(history) => (history, draw_data.temporal_resolve),
+            None => (
+                fractal_color.clone(),
+                TemporalResolvePushConstants {
+                    history_weight: 0.,
+                    ..draw_data.temporal_resolve
+                },
+            ),
+        };
+        begin_render_pass(
+            &mut builder,
+            &frame_targets.present,
+            vec![Some([0., 0., 0., 1.].into()), None],
+        );
+        inline_temporal_resolve_cmds(
+            &mut builder,
+            engine,
+            temporal_resolve_data,
+            fractal_color,
+            history,
+        );
+    } else {
+        // Without a history to blend, hold, or capture, the fractal is only faded and no history is written
+        begin_render_pass(
+            &mut builder,
+            &frame_targets.present_passthrough,
+            vec![Some([0., 0., 0., 1.].into()), None],
+        );
+        inline_temporal_passthrough_cmds(
+            &mut builder,
+            engine,
+            draw_data.temporal_resolve,
+            fractal_color,
+        );
+    }
 
     // Move to next subpass, GUI rendering
-    builder
-        .next_subpass(
-            SubpassEndInfo::default(),
-            SubpassBeginInfo {
-                contents: SubpassContents::SecondaryCommandBuffers,
-                ..Default::default()
-            },
-        )
-        .unwrap();
+    next_subpass(&mut builder, SubpassContents::SecondaryCommandBuffers);
 
     // Add optional GUI command buffer to primary command buffer.
     if let Some(command_buffer) = gui_command_buffer {
@@ -150,18 +222,476 @@ pub fn create_render_commands(
     // Mark completion of frame rendering (for this pass)
     builder.end_render_pass(SubpassEndInfo::default()).unwrap();
 
+    // Copy the finished frame for any requested capture, using the history which excludes the GUI
+    if let Some(frame_capture) = &mut engine.frame_capture {
+        frame_capture.record(
+            &mut builder,
+            frame_targets.present.attachments()[1].image().clone(),
+            &engine.viewport,
+        );
+    }
+
+    if let Some(gpu_timer) = &mut engine.gpu_timer {
+        gpu_timer.end(&mut builder);
+    }
+
     // Return new command buffer for this framebuffer
     builder.build().unwrap()
 }
 
+// Record the render passes drawing the particles to be sampled by the depth-of-field and bloom passes, then the fractal over them.
+fn post_processed_render_cmds(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    engine: &mut Engine,
+    frame_targets: &FrameTargets,
+    previous_trails: Option<Arc<ImageView>>,
+    draw_data: &DrawData,
+    particle_clear_values: Vec<Option<ClearValue>>,
+) {
+    // Start render pass
+    begin_render_pass(builder, &frame_targets.particles, particle_clear_values);
+
+    // Add inline commands to render each shown particle system in turn
+    inline_particle_systems_cmds(builder, engine, draw_data, false);
+
+    // Move to next subpass, resolving the nearest particle depth of each pixel
+    next_subpass(builder, SubpassContents::Inline);
+    let resolve_pipeline = engine.depth_of_field.resolve_pipeline.clone();
+    inline_depth_resolve_cmds(
+        builder,
+        engine,
+        resolve_pipeline,
+        frame_targets.particles.attachments()[2].clone(),
+    );
+    builder.end_render_pass(SubpassEndInfo::default()).unwrap();
+    if let Some(gpu_timer) = &mut engine.gpu_timer {
+        gpu_timer.end_pass(builder, 1);
+    }
+
+    // Blur the brightest particles into a glow, which the depth-of-field pass adds to them.
+    // Without bloom, the particles are bound in place of the glow, which is then unused
+    let particle_color = frame_targets.particles.attachments()[1].clone();
+    let bloom_color = if draw_data.bloom.enabled {
+        let [horizontal, vertical] = &frame_targets.bloom;
+        bloom_cmds(
+            builder,
+            engine,
+            horizontal,
+            particle_color.clone(),
+            BloomPushConstants {
+                direction: [1.5, 0.],
+                threshold: draw_data.bloom.threshold,
+            },
+        );
+        bloom_cmds(
+            builder,
+            engine,
+            vertical,
+            horizontal.attachments()[0].clone(),
+            BloomPushConstants {
+                direction: [0., 1.5],
+                threshold: 0.,
+            },
+        );
+        vertical.attachments()[0].clone()
+    } else {
+        particle_color.clone()
+    };
+
+    // Start the app render pass, beginning with depth-of-field on the rendered particles.
+    // Without a previous frame, the particles begin without trails
+    let (previous_trails, depth_of_field_data) = match previous_trails {
+        Some(previous_trails) => (previous_trails, draw_data.depth_of_field),
+        None => (
+            particle_color.clone(),
+            DepthOfFieldPushConstants {
+                trail_decay: 0.,
+                ..draw_data.depth_of_field
+            },
+        ),
+    };
+    begin_render_pass(
+        builder,
+        &frame_targets.app,
+        vec![None, None, None, None, None],
+    );
+    inline_depth_of_field_cmds(
+        builder,
+        engine,
+        depth_of_field_data,
+        particle_color,
+        frame_targets.particles.attachments()[3].clone(),
+        previous_trails,
+        bloom_color,
+    );
+
+    // Move to next subpass, fractal rendering
+    next_subpass(builder, SubpassContents::Inline);
+
+    // Add inline commands to render fractal, passing the particles through wherever it is not shown
+    let scene_color = frame_targets.app.attachments()[0].clone();
+    let layout = engine.display_layout;
+    if matches!(
+        layout,
+        DisplayLayout::ParticlesOnly | DisplayLayout::SplitScreen
+    ) {
+        let pipeline = engine.fractal.passthrough_pipeline.clone();
+        inline_particle_passthrough_cmds(builder, engine, pipeline, scene_color.clone());
+    }
+    if layout != DisplayLayout::ParticlesOnly {
+        let pipeline = engine.fractal_pipeline().clone();
+        inline_fractal_cmds(
+            builder,
+            engine,
+            pipeline,
+            draw_data.fractal_data,
+            scene_color,
+            frame_targets.app.attachments()[1].clone(),
+        );
+    }
+
+    // Move to next subpass, mapping the unbounded colors to the display
+    next_subpass(builder, SubpassContents::Inline);
+    let pipeline = engine.tone_map.pipeline.clone();
+    inline_tone_map_cmds(
+        builder,
+        engine,
+        pipeline,
+        draw_data.tone_map,
+        frame_targets.app.attachments()[2].clone(),
+    );
+    builder.end_render_pass(SubpassEndInfo::default()).unwrap();
+    if let Some(gpu_timer) = &mut engine.gpu_timer {
+        gpu_timer.end_pass(builder, 2);
+    }
+}
+
+// Record the single render pass drawing the particles straight into the fractal, skipping the depth-of-field and bloom passes.
+fn direct_render_cmds(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    engine: &mut Engine,
+    frame_targets: &FrameTargets,
+    draw_data: &DrawData,
+    mut clear_values: Vec<Option<ClearValue>>,
+) {
+    // The fractal and display colors are always overwritten
+    clear_values.extend([None, None]);
+    begin_render_pass(builder, &frame_targets.direct, clear_values);
+    inline_particle_systems_cmds(builder, engine, draw_data, true);
+
+    // Move to next subpass, resolving the nearest particle depth of each pixel
+    next_subpass(builder, SubpassContents::Inline);
+    let resolve_pipeline = engine.direct.resolve_pipeline.clone();
+    inline_depth_resolve_cmds(
+        builder,
+        engine,
+        resolve_pipeline,
+        frame_targets.direct.attachments()[2].clone(),
+    );
+    if let Some(gpu_timer) = &mut engine.gpu_timer {
+        gpu_timer.end_pass(builder, 1);
+    }
+
+    // Move to next subpass, rendering the fractal over the unprocessed particles
+    next_subpass(builder, SubpassContents::Inline);
+    let particle_color = frame_targets.direct.attachments()[1].clone();
+    let layout = engine.display_layout;
+    if matches!(
+        layout,
+        DisplayLayout::ParticlesOnly | DisplayLayout::SplitScreen
+    ) {
+        let pipeline = engine.direct.passthrough_pipeline.clone();
+        inline_particle_passthrough_cmds(builder, engine, pipeline, particle_color.clone());
+    }
+    if layout != DisplayLayout::ParticlesOnly {
+        let pipeline = engine.direct.fractal_pipeline.clone();
+        inline_fractal_cmds(
+            builder,
+            engine,
+            pipeline,
+            draw_data.fractal_data,
+            particle_color,
+            frame_targets.direct.attachments()[3].clone(),
+        );
+    }
+
+    // Move to next subpass, mapping the unbounded colors to the display
+    next_subpass(builder, SubpassContents::Inline);
+    let pipeline = engine.direct.tone_map_pipeline.clone();
+    inline_tone_map_cmds(
+        builder,
+        engine,
+        pipeline,
+        draw_data.tone_map,
+        frame_targets.direct.attachments()[4].clone(),
+    );
+    builder.end_render_pass(SubpassEndInfo::default()).unwrap();
+    if let Some(gpu_timer) = &mut engine.gpu_timer {
+        gpu_timer.end_pass(builder, 2);
+    }
+}
+
+// Add inline commands to render each shown particle system in turn, with the pipelines of the direct render pass if requested.
+fn inline_particle_systems_cmds(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    engine: &Engine,
+    draw_data: &DrawData,
+    direct: bool,
+) {
+    let systems = engine.particles.iter().zip(&draw_data.particle_data);
+    for (index, (particles, data)) in systems.enumerate() {
+        if let Some((_, vertex_push_constants)) = data {
+            let pipeline = if direct {
+                engine.direct.particle_pipelines[index].clone()
+            } else {
+                particles.graphics_pipeline.clone()
+            };
+
+            // Sprites pull their particles from the storage buffer rather than binding it as vertices
+            let vertex_buffer =
+                (!particles.sprites).then(|| particles.vertex_buffers.vertex.clone());
+            inline_particles_cmds(
+                builder,
+                pipeline,
+                vertex_buffer,
+                &particles.chunks,
+                particles.sort.as_ref(),
+                *vertex_push_constants,
+                particles.graphics_descriptor_set.clone(),
+            );
+        }
+    }
+}
+
+// Helper for simulating a particle system, then optionally repelling and sorting its particles
+#[allow(clippy::too_many_arguments)]
+fn particle_compute_cmds(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    descriptor_pool: &StandardDescriptorSetAllocator,
+    particles: &mut Particles,
+    compute_push_constants: ParticleComputePushConstants,
+    attractors: Subbuffer<ParticleAttractors>,
+    vertex_push_constants: &ParticleVertexPushConstants,
+    fractal_surface: Option<FractalSurfacePushConstants>,
+    particle_repulsion: ParticleRepulsion,
+) {
+    let compute_pipeline = particles.compute_pipeline.clone();
+    let particle_count = particles.vertex_buffers.vertex.len();
+
+    // Return the particles to their initial positions before simulating them, if a reset was requested
+    if let Some((vertices, previous_positions)) = particles.pending_reset.take() {
+        let buffers = &particles.vertex_buffers;
+        builder
+            .copy_buffer(CopyBufferInfo::buffers(vertices, buffers.vertex.clone()))
+            .unwrap()
+            .copy_buffer(CopyBufferInfo::buffers(
+                previous_positions,
+                buffers.previous_positions.clone(),
+            ))
+            .unwrap();
+    }
+
+    // The attractors are bound once as the second set, while each chunk binds its own first set
+    let attractor_descriptor_set = PersistentDescriptorSet::new(
+        descriptor_pool,
+        compute_pipeline
+            .layout()
+            .set_layouts()
+            .get(1)
+            .expect("Failed to get particle attractors descriptor set layout")
+            .clone(),
+        [WriteDescriptorSet::buffer(0, attractors)],
+        [],
+    )
+    .expect("Failed to create particle attractors descriptor set");
+
+    // Build compute commands
+    builder
+        // Push constants for compute shader
+        .push_constants(compute_pipeline.layout().clone(), 0, compute_push_constants)
+        .unwrap()
+        // Perform compute operation to update particle positions
+        .bind_pipeline_compute(compute_pipeline.clone())
+        .unwrap()
+        .bind_descriptor_sets(
+            PipelineBindPoint::Compute,
+            compute_pipeline.layout().clone(),
+            1,
+            attractor_descriptor_set,
+        )
+        .unwrap();
+
+    // Dispatch each chunk separately, binding only its slice of the particle buffers
+    for chunk in &particles.chunks {
+        builder
+            .bind_descriptor_sets(
+                PipelineBindPoint::Compute,
+                compute_pipeline.layout().clone(),
+                0, // Start binding descriptor sets at index 0
+                chunk.compute_descriptor_set.clone(),
+            )
+            .unwrap()
+            .dispatch([chunk.count.div_ceil(PARTICLE_WORK_GROUP_SIZE), 1, 1])
+            .unwrap();
+    }
+
+    // Respawn some of the particles on the fractal's surface, which only a 3D projection shows them on
+    let fractal_surface =
+        fractal_surface.filter(|_| compute_push_constants.use_third_dimension != 0);
+    if let Some(push_constants) = fractal_surface {
+        let surface_pipeline = particles.surface_pipeline.clone();
+        builder
+            .bind_pipeline_compute(surface_pipeline.clone())
+            .unwrap();
+        for chunk in &particles.chunks {
+            builder
+                .push_constants(
+                    surface_pipeline.layout().clone(),
+                    0,
+                    FractalSurfacePushConstants {
+                        first: chunk.first,
+                        ..push_constants
+                    },
+                )
+                .unwrap()
+                .bind_descriptor_sets(
+                    PipelineBindPoint::Compute,
+                    surface_pipeline.layout().clone(),
+                    0,
+                    chunk.surface_descriptor_set.clone(),
+                )
+                .unwrap()
+                .dispatch([chunk.count.div_ceil(PARTICLE_WORK_GROUP_SIZE), 1, 1])
+                .unwrap();
+        }
+    }
+
+    // Optionally push neighboring particles apart, using a grid rebuilt from the updated positions
+    let grid = particles
+        .grid
+        .as_ref()
+        .filter(|_| particle_repulsion.enabled);
+    if let Some(grid) = grid {
+        let push_constants = ParticleGrid::push_constants(
+            particle_repulsion,
+            compute_push_constants.delta_time,
+            compute_push_constants.use_third_dimension != 0,
+            particle_count,
+        );
+        particle_grid_cmds(builder, grid, push_constants);
+    }
+
+    // Order translucent particles back-to-front from the updated positions
+    if let Some(sort) = &particles.sort {
+        particle_sort_cmds(builder, sort, vertex_push_constants, particle_count);
+    }
+}
+
+// Record the commands to bin particles into the grid and apply the repulsive forces between neighbors.
+// Buffer accesses between the dispatches are synchronized automatically by the command buffer builder
+fn particle_grid_cmds(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    grid: &ParticleGrid,
+    push_constants: ParticleGridPushConstants,
+) {
+    // Reset the cell counts before particles are binned
+    builder
+        .fill_buffer(grid.cell_counts.clone(), 0)
+        .expect("Failed to clear particle-grid cell counts");
+
+    // Helper to dispatch a single stage of the grid.
+    let mut dispatch = |stage: &GridStage, group_counts: [u32; 3]| {
+        let layout = stage.pipeline.layout().clone();
+        builder
+            .push_constants(layout.clone(), 0, push_constants)
+            .unwrap()
+            .bind_pipeline_compute(stage.pipeline.clone())
+            .unwrap()
+            .bind_descriptor_sets(
+                PipelineBindPoint::Compute,
+                layout,
+                0,
+                stage.descriptor_set.clone(),
+            )
+            .unwrap()
+            .dispatch(group_counts)
+            .expect("Failed to dispatch particle-grid stage");
+    };
+    let particle_groups = [
+        push_constants
+            .particle_count
+            .div_ceil(PARTICLE_WORK_GROUP_SIZE),
+        1,
+        1,
+    ];
+
+    dispatch(&grid.count, particle_groups);
+    dispatch(&grid.scan, [1, 1, 1]); // The scan is performed by a single work group
+    dispatch(&grid.scatter, particle_groups);
+    dispatch(&grid.repel, particle_groups);
+}
+
+// Key the particles by their depth, then sort them from the farthest to the nearest.
+// Only 3D particles have depth, so 2D particles are drawn in the order the keys are reset to
+fn particle_sort_cmds(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    sort: &ParticleSort,
+    vertex_push_constants: &ParticleVertexPushConstants,
+    particle_count: u64,
+) {
+    // Helper to dispatch a single stage of the sort over every sorted element.
+    let mut dispatch = |stage: &GridStage, block_size: u32, compare_distance: u32| {
+        let layout = stage.pipeline.layout().clone();
+        #[allow(clippy::cast_possible_truncation)]
+        let push_constants = ParticleSortPushConstants {
+            quaternion: vertex_push_constants.quaternion,
+            camera_position: vertex_push_constants.camera_position,
+            particle_count: particle_count as u32,
+            block_size,
+            compare_distance,
+        };
+        builder
+            .push_constants(layout.clone(), 0, push_constants)
+            .unwrap()
+            .bind_pipeline_compute(stage.pipeline.clone())
+            .unwrap()
+            .bind_descriptor_sets(
+                PipelineBindPoint::Compute,
+                layout,
+                0,
+                stage.descriptor_set.clone(),
+            )
+            .unwrap()
+            .dispatch([sort.sorted_count / PARTICLE_WORK_GROUP_SIZE, 1, 1])
+            .expect("Failed to dispatch particle-sort stage");
+    };
+
+    dispatch(&sort.keys, 0, 0);
+    if vertex_push_constants.use_third_dimension == 0 {
+        return;
+    }
+
+    // Each block is merged by comparing elements at halving distances, doubling the blocks until one spans every element
+    let mut block_size = 2;
+    while block_size <= sort.sorted_count {
+        let mut compare_distance = block_size / 2;
+        while compare_distance > 0 {
+            dispatch(&sort.sort, block_size, compare_distance);
+            compare_distance /= 2;
+        }
+        block_size *= 2;
+    }
+}
+
 fn inline_particles_cmds(
     builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
     pipeline: Arc<GraphicsPipeline>,
-    vertex_buffer: &Subbuffer<[PointParticle]>,
+    vertex_buffer: Option<Subbuffer<[PointParticle]>>,
+    chunks: &[ParticleChunk],
+    sort: Option<&ParticleSort>,
     push_constants: ParticleVertexPushConstants,
     descriptor_set: Arc<PersistentDescriptorSet>,
 ) {
-    let buffer_count = vertex_buffer.len() as u32;
     let layout = pipeline.layout().clone();
 
     // Build render pass commands
@@ -171,17 +701,164 @@ fn inline_particles_cmds(
         .unwrap()
         .push_constants(layout.clone(), 0, push_constants)
         .unwrap()
-        .bind_vertex_buffers(0, vertex_buffer.clone())
+        .bind_descriptor_sets(PipelineBindPoint::Graphics, layout, 0, descriptor_set)
+        .unwrap();
+
+    // Sorted particles are drawn in a single draw, in the order of their sorted indices.
+    // Points index the vertex buffer by them, while sprites read them from the sorted storage buffer
+    if let Some(sort) = sort {
+        let particle_count = chunks.iter().map(|chunk| chunk.count).sum();
+        if let Some(vertex_buffer) = vertex_buffer {
+            builder
+                .bind_vertex_buffers(0, vertex_buffer)
+                .unwrap()
+                .bind_index_buffer(sort.indices.clone().slice(..u64::from(particle_count)))
+                .unwrap()
+                .draw_indexed(particle_count, 1, 0, 0, 0)
+                .expect("Failed to draw particle subpass");
+        } else {
+            builder
+                .draw(4, particle_count, 0, 0)
+                .expect("Failed to draw particle subpass");
+        }
+        return;
+    }
+
+    // Draw in the same chunks as the particles are updated, so no single draw is excessively long.
+    // Without a vertex buffer, each particle is an instance of a four-vertex quad
+    if let Some(vertex_buffer) = vertex_buffer {
+        builder.bind_vertex_buffers(0, vertex_buffer).unwrap();
+        for chunk in chunks {
+            builder
+                .draw(chunk.count, 1, chunk.first, 0)
+                .expect("Failed to draw particle subpass");
+        }
+    } else {
+        for chunk in chunks {
+            builder
+                .draw(4, chunk.count, 0, chunk.first)
+                .expect("Failed to draw particle subpass");
+        }
+    }
+}
+
+fn inline_depth_resolve_cmds(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    engine: &mut Engine,
+    pipeline: Arc<GraphicsPipeline>,
+    particle_depth: Arc<ImageView>,
+) {
+    let layout = pipeline.layout().clone();
+    let descriptor_set = PersistentDescriptorSet::new(
+        engine.descriptor_pool(),
+        layout
+            .set_layouts()
+            .get(0) // 0 is the index of the descriptor set layout we want
+            .expect("Failed to get depth-resolve descriptor set layout")
+            .clone(),
+        [WriteDescriptorSet::image_view(0, particle_depth)],
+        [],
+    )
+    .expect("Failed to create depth-resolve descriptor set");
+
+    // Build render pass commands
+    builder
+        .bind_pipeline_graphics(pipeline)
         .unwrap()
         .bind_descriptor_sets(PipelineBindPoint::Graphics, layout, 0, descriptor_set)
         .unwrap()
-        .draw(buffer_count, 1, 0, 0)
-        .expect("Failed to draw particle subpass");
+        // Draw 4 static vertices (entire view quad)
+        .draw(4, 1, 0, 0)
+        .expect("Failed to draw depth-resolve subpass");
+}
+
+// Record a render pass blurring the given image along a single axis into the given framebuffer.
+fn bloom_cmds(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    engine: &mut Engine,
+    framebuffer: &Arc<Framebuffer>,
+    source_color: Arc<ImageView>,
+    push_constants: BloomPushConstants,
+) {
+    let sampler = engine.bloom.sampler.clone();
+    let pipeline = engine.bloom.pipeline.clone();
+    let layout = pipeline.layout().clone();
+    let descriptor_set = PersistentDescriptorSet::new(
+        engine.descriptor_pool(),
+        layout
+            .set_layouts()
+            .get(0) // 0 is the index of the descriptor set layout we want
+            .expect("Failed to get bloom descriptor set layout")
+            .clone(),
+        [WriteDescriptorSet::image_view_sampler(
+            0,
+            source_color,
+            sampler,
+        )],
+        [],
+    )
+    .expect("Failed to create bloom descriptor set");
+
+    begin_render_pass(builder, framebuffer, vec![Some([0., 0., 0., 1.].into())]);
+    builder
+        .bind_pipeline_graphics(pipeline)
+        .unwrap()
+        .push_constants(layout.clone(), 0, push_constants)
+        .unwrap()
+        .bind_descriptor_sets(PipelineBindPoint::Graphics, layout, 0, descriptor_set)
+        .unwrap()
+        // Draw 4 static vertices (entire view quad)
+        .draw(4, 1, 0, 0)
+        .expect("Failed to draw bloom pass");
+    builder.end_render_pass(SubpassEndInfo::default()).unwrap();
+}
+
+fn inline_depth_of_field_cmds(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    engine: &mut Engine,
+    push_constants: DepthOfFieldPushConstants,
+    particle_color: Arc<ImageView>,
+    particle_depth: Arc<ImageView>,
+    previous_trails: Arc<ImageView>,
+    bloom_color: Arc<ImageView>,
+) {
+    let sampler = engine.depth_of_field.sampler.clone();
+    let pipeline = engine.depth_of_field.pipeline.clone();
+    let layout = pipeline.layout().clone();
+    let descriptor_set = PersistentDescriptorSet::new(
+        engine.descriptor_pool(),
+        layout
+            .set_layouts()
+            .get(0) // 0 is the index of the descriptor set layout we want
+            .expect("Failed to get depth-of-field descriptor set layout")
+            .clone(),
+        [
+            WriteDescriptorSet::image_view_sampler(0, particle_color, sampler.clone()),
+            WriteDescriptorSet::image_view_sampler(1, particle_depth, sampler.clone()),
+            WriteDescriptorSet::image_view_sampler(2, previous_trails, sampler.clone()),
+            WriteDescriptorSet::image_view_sampler(3, bloom_color, sampler),
+        ],
+        [],
+    )
+    .expect("Failed to create depth-of-field descriptor set");
+
+    // Build render pass commands
+    builder
+        .bind_pipeline_graphics(pipeline)
+        .unwrap()
+        .push_constants(layout.clone(), 0, push_constants)
+        .unwrap()
+        .bind_descriptor_sets(PipelineBindPoint::Graphics, layout, 0, descriptor_set)
+        .unwrap()
+        // Draw 4 static vertices (entire view quad)
+        .draw(4, 1, 0, 0)
+        .expect("Failed to draw depth-of-field subpass");
 }
 
 fn inline_fractal_cmds(
     builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
     engine: &mut Engine,
+    pipeline: Arc<GraphicsPipeline>,
     push_constants: FractalPushConstants,
     particle_input: Arc<ImageView>,
     particle_depth: Arc<ImageView>,
@@ -189,35 +866,182 @@ fn inline_fractal_cmds(
     let config_constants = engine.app_constants.clone();
     let runtime_constants = engine.runtime_constants.clone();
 
-    let pipeline = engine.fractal_pipeline().clone();
+    let layout = pipeline.layout().clone();
+    let set_layout = layout
+        .set_layouts()
+        .get(0) // 0 is the index of the descriptor set layout we want
+        .expect("Failed to get fractal descriptor set layout")
+        .clone();
+    let mut writes = vec![
+        WriteDescriptorSet::image_view(0, particle_input),
+        WriteDescriptorSet::image_view(1, particle_depth),
+        WriteDescriptorSet::buffer(2, config_constants),
+        WriteDescriptorSet::buffer(3, runtime_constants),
+    ];
+
+    // The live uniforms are absent from the layout until the shader reads them.
+    if set_layout
+        .bindings()
+        .contains_key(&LIVE_UNIFORMS_FRACTAL_BINDING)
+    {
+        writes.push(WriteDescriptorSet::buffer(
+            LIVE_UNIFORMS_FRACTAL_BINDING,
+            engine.live_uniforms.clone(),
+        ));
+    }
+    let descriptor_set =
+        PersistentDescriptorSet::new(engine.descriptor_pool(), set_layout, writes, [])
+            .expect("Failed to create fractal descriptor set");
+
+    // Build render pass commands
+    builder
+        .bind_pipeline_graphics(pipeline)
+        .unwrap()
+        // Push constants
+        .push_constants(layout.clone(), 0, push_constants)
+        .unwrap()
+        .bind_descriptor_sets(PipelineBindPoint::Graphics, layout, 0, descriptor_set)
+        .unwrap()
+        // Draw 4 static vertices (entire view quad)
+        .draw(4, 1, 0, 0)
+        .expect("Failed to draw fractal subpass");
+}
+
+fn inline_particle_passthrough_cmds(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    engine: &mut Engine,
+    pipeline: Arc<GraphicsPipeline>,
+    particle_input: Arc<ImageView>,
+) {
+    let layout = pipeline.layout().clone();
+    let descriptor_set = PersistentDescriptorSet::new(
+        engine.descriptor_pool(),
+        layout
+            .set_layouts()
+            .get(0) // 0 is the index of the descriptor set layout we want
+            .expect("Failed to get particle passthrough descriptor set layout")
+            .clone(),
+        [WriteDescriptorSet::image_view(0, particle_input)],
+        [],
+    )
+    .expect("Failed to create particle passthrough descriptor set");
+
+    // Build render pass commands
+    builder
+        .bind_pipeline_graphics(pipeline)
+        .unwrap()
+        .bind_descriptor_sets(PipelineBindPoint::Graphics, layout, 0, descriptor_set)
+        .unwrap()
+        // Draw 4 static vertices (entire view quad)
+        .draw(4, 1, 0, 0)
+        .expect("Failed to draw particle passthrough");
+}
+
+fn inline_tone_map_cmds(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    engine: &mut Engine,
+    pipeline: Arc<GraphicsPipeline>,
+    push_constants: ToneMapPushConstants,
+    hdr_color: Arc<ImageView>,
+) {
+    let layout = pipeline.layout().clone();
+    let descriptor_set = PersistentDescriptorSet::new(
+        engine.descriptor_pool(),
+        layout
+            .set_layouts()
+            .get(0) // 0 is the index of the descriptor set layout we want
+            .expect("Failed to get tone-mapping descriptor set layout")
+            .clone(),
+        [WriteDescriptorSet::image_view(0, hdr_color)],
+        [],
+    )
+    .expect("Failed to create tone-mapping descriptor set");
+
+    // Build render pass commands
+    builder
+        .bind_pipeline_graphics(pipeline)
+        .unwrap()
+        .push_constants(layout.clone(), 0, push_constants)
+        .unwrap()
+        .bind_descriptor_sets(PipelineBindPoint::Graphics, layout, 0, descriptor_set)
+        .unwrap()
+        // Draw 4 static vertices (entire view quad)
+        .draw(4, 1, 0, 0)
+        .expect("Failed to draw tone-mapping subpass");
+}
+
+fn inline_temporal_resolve_cmds(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    engine: &mut Engine,
+    push_constants: TemporalResolvePushConstants,
+    fractal_color: Arc<ImageView>,
+    history: Arc<ImageView>,
+) {
+    let sampler = engine.temporal_resolve.sampler.clone();
+    let pipeline = engine.temporal_resolve.pipeline.clone();
     let layout = pipeline.layout().clone();
     let descriptor_set = PersistentDescriptorSet::new(
         engine.descriptor_pool(),
         layout
             .set_layouts()
             .get(0) // 0 is the index of the descriptor set layout we want
-            .expect("Failed to get fractal descriptor set layout")
+            .expect("Failed to get temporal-resolve descriptor set layout")
             .clone(),
         [
-            WriteDescriptorSet::image_view(0, particle_input),
-            WriteDescriptorSet::image_view(1, particle_depth),
-            WriteDescriptorSet::buffer(2, config_constants),
-            WriteDescriptorSet::buffer(3, runtime_constants),
+            WriteDescriptorSet::image_view_sampler(0, fractal_color, sampler.clone()),
+            WriteDescriptorSet::image_view_sampler(1, history, sampler),
         ],
         [],
     )
-    .expect("Failed to create fractal descriptor set");
+    .expect("Failed to create temporal-resolve descriptor set");
 
     // Build render pass commands
     builder
         .bind_pipeline_graphics(pipeline)
         .unwrap()
-        // Push constants
         .push_constants(layout.clone(), 0, push_constants)
         .unwrap()
         .bind_descriptor_sets(PipelineBindPoint::Graphics, layout, 0, descriptor_set)
         .unwrap()
         // Draw 4 static vertices (entire view quad)
         .draw(4, 1, 0, 0)
-        .expect("Failed to draw fractal subpass");
+        .expect("Failed to draw temporal-resolve subpass");
+}
+
+fn inline_temporal_passthrough_cmds(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    engine: &mut Engine,
+    push_constants: TemporalResolvePushConstants,
+    fractal_color: Arc<ImageView>,
+) {
+    let sampler = engine.temporal_resolve.sampler.clone();
+    let pipeline = engine.temporal_resolve.passthrough_pipeline.clone();
+    let layout = pipeline.layout().clone();
+    let descriptor_set = PersistentDescriptorSet::new(
+        engine.descriptor_pool(),
+        layout
+            .set_layouts()
+            .get(0) // 0 is the index of the descriptor set layout we want
+            .expect("Failed to get temporal passthrough descriptor set layout")
+            .clone(),
+        [WriteDescriptorSet::image_view_sampler(
+            0,
+            fractal_color,
+            sampler,
+        )],
+        [],
+    )
+    .expect("Failed to create temporal passthrough descriptor set");
+
+    // Build render pass commands
+    builder
+        .bind_pipeline_graphics(pipeline)
+        .unwrap()
+        .push_constants(layout.clone(), 0, push_constants)
+        .unwrap()
+        .bind_descriptor_sets(PipelineBindPoint::Graphics, layout, 0, descriptor_set)
+        .unwrap()
+        // Draw 4 static vertices (entire view quad)
+        .draw(4, 1, 0, 0)
+        .expect("Failed to draw temporal passthrough subpass");
 }
@@ -0,0 +1,162 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::sync::Arc;
+
+use vulkano::command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer};
+use vulkano::device::Queue;
+use vulkano::query::{QueryPool, QueryPoolCreateInfo, QueryResultFlags, QueryType};
+use vulkano::sync::PipelineStage;
+
+// The spans of each frame which are measured separately, in the order they are recorded.
+pub const GPU_PASS_NAMES: [&str; GPU_PASS_COUNT] = [
+    "Particle compute",
+    "Particles",
+    "Bloom, fractal, and tone mapping",
+    "Resolve and overlay",
+];
+pub const GPU_PASS_COUNT: usize = 4;
+
+// A timestamp begins the frame and ends each pass.
+#[allow(clippy::cast_possible_truncation)]
+const QUERY_COUNT: u32 = GPU_PASS_COUNT as u32 + 1;
+
+// Measures the time the GPU spends executing each frame's commands, and each of its passes, using timestamp queries.
+pub struct GpuTimer {
+    query_pool: Arc<QueryPool>,
+    nanoseconds_per_tick: f64,
+    valid_bits_mask: u64,
+    pending: bool,
+    last_frame_ms: Option<f32>,
+    last_pass_ms: Option<[f32; GPU_PASS_COUNT]>,
+}
+
+impl GpuTimer {
+    // Create a timer for commands submitted to the given queue. Returns `None` if the queue does not support timestamps.
+    pub fn new(queue: &Arc<Queue>) -> Option<Self> {
+        let device = queue.device();
+        let physical_device = device.physical_device();
+        let valid_bits = physical_device.queue_family_properties()
+            [queue.queue_family_index() as usize]
+            .timestamp_valid_bits?;
+
+        let query_pool = QueryPool::new(
+            device.clone(),
+            QueryPoolCreateInfo {
+                query_count: QUERY_COUNT,
+                ..QueryPoolCreateInfo::query_type(QueryType::Timestamp)
+            },
+        )
+        .map_err(|e| log!("Failed to create timestamp query pool: {e:?}"))
+        .ok()?;
+
+        Some(Self {
+            query_pool,
+            nanoseconds_per_tick: f64::from(physical_device.properties().timestamp_period),
+            valid_bits_mask: if valid_bits >= 64 {
+                u64::MAX
+            } else {
+                (1 << valid_bits) - 1
+            },
+            pending: false,
+            last_frame_ms: None,
+            last_pass_ms: None,
+        })
+    }
+
+    // Record the timestamp marking the start of the frame's commands.
+    pub fn begin(&mut self, builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>) {
+        unsafe {
+            builder
+                .reset_query_pool(self.query_pool.clone(), 0..QUERY_COUNT)
+                .unwrap()
+                .write_timestamp(self.query_pool.clone(), 0, PipelineStage::TopOfPipe)
+                .unwrap();
+        }
+    }
+
+    // Record the timestamp marking the end of the pass, at the given index of `GPU_PASS_NAMES`.
+    // Passes must be ended in order, outside of any render pass
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn end_pass(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        pass: usize,
+    ) {
+        unsafe {
+            builder
+                .write_timestamp(
+                    self.query_pool.clone(),
+                    pass as u32 + 1,
+                    PipelineStage::BottomOfPipe,
+                )
+                .unwrap();
+        }
+    }
+
+    // Record the timestamp marking the end of the frame's commands, which also ends the last pass.
+    pub fn end(&mut self, builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>) {
+        self.end_pass(builder, GPU_PASS_COUNT - 1);
+        self.pending = true;
+    }
+
+    // Read the timestamps of the last submitted frame, if they are available.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn read_results(&mut self) {
+        if !self.pending {
+            return;
+        }
+
+        let mut timestamps = [0u64; QUERY_COUNT as usize];
+        match self.query_pool.get_results(
+            0..QUERY_COUNT,
+            &mut timestamps,
+            QueryResultFlags::empty(),
+        ) {
+            Ok(true) => {
+                let milliseconds = |from: u64, to: u64| {
+                    let ticks = to.wrapping_sub(from) & self.valid_bits_mask;
+                    (ticks as f64 * self.nanoseconds_per_tick / 1e6) as f32
+                };
+                self.last_frame_ms = Some(milliseconds(timestamps[0], timestamps[GPU_PASS_COUNT]));
+                self.last_pass_ms = Some(std::array::from_fn(|i| {
+                    milliseconds(timestamps[i], timestamps[i + 1])
+                }));
+                self.pending = false;
+            }
+
+            // The results are not yet available, try again after the next frame.
+            Ok(false) => {}
+
+            Err(e) => {
+                log!("Failed to read GPU timestamps: {e:?}");
+                self.pending = false;
+            }
+        }
+    }
+
+    // The GPU time of the most recently measured frame, in milliseconds.
+    pub fn last_frame_ms(&self) -> Option<f32> {
+        self.last_frame_ms
+    }
+
+    // The GPU time of each pass of the most recently measured frame, in milliseconds.
+    pub fn last_pass_ms(&self) -> Option<[f32; GPU_PASS_COUNT]> {
+        self.last_pass_ms
+    }
+}
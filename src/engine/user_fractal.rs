@@ -0,0 +1,132 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use vulkano::device::Device;
+use vulkano::shader::ShaderModule;
+
+use super::object::{self, FRACTAL_FRAGMENT_SHADER};
+
+// How often the distance estimator's file is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// The name the fractal shader includes the user's distance estimator by.
+const INCLUDE_NAME: &str = "user_fractal.glsl";
+
+// Compiles the fractal shader with the distance estimator of the `user_fractal` file, and again whenever it is saved.
+pub struct UserFractal {
+    path: PathBuf,
+    shader_directory: Option<PathBuf>, // Where a distribution may provide a patched fractal shader
+    modified: Option<SystemTime>,
+    last_poll: Option<Instant>,
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl UserFractal {
+    pub fn new(path: &str, shader_directory: Option<PathBuf>) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            shader_directory,
+            modified: None,
+            last_poll: None,
+        }
+    }
+
+    // Check whether the file has changed since it was last compiled, returning the recompiled fractal shader if so.
+    // The first check compiles the shader immediately
+    pub fn poll(&mut self, device: &Arc<Device>) -> Option<Arc<ShaderModule>> {
+        let first_poll = match self.last_poll {
+            Some(last_poll) if last_poll.elapsed() < POLL_INTERVAL => return None,
+            last_poll => last_poll.is_none(),
+        };
+        self.last_poll = Some(Instant::now());
+
+        // A missing file is waited on, since it may yet be created.
+        let modified = modified_time(&self.path);
+        if modified.is_none() {
+            if first_poll {
+                log!(
+                    "The user fractal `{}` does not exist, it is compiled once created",
+                    self.path.display()
+                );
+            }
+            return None;
+        }
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        // An editor may save a partially written file, which is compiled again on its next change.
+        match self.compile(device) {
+            Ok(module) => {
+                log!("Compiled the user fractal `{}`", self.path.display());
+                Some(module)
+            }
+            Err(e) => {
+                log!(
+                    "Failed to compile the user fractal `{}`: {e}",
+                    self.path.display()
+                );
+                None
+            }
+        }
+    }
+
+    // Compile the fractal shader, preferring any a distribution provides, with the user's distance estimator included.
+    fn compile(&self, device: &Arc<Device>) -> anyhow::Result<Arc<ShaderModule>> {
+        let snippet = std::fs::read_to_string(&self.path)?;
+        let source = self
+            .shader_directory
+            .as_ref()
+            .map(|directory| directory.join(FRACTAL_FRAGMENT_SHADER.file_name))
+            .filter(|path| path.is_file())
+            .map_or_else(
+                || Ok(String::from(FRACTAL_FRAGMENT_SHADER.glsl)),
+                std::fs::read_to_string,
+            )?;
+
+        let mut options = object::compile_options(self.shader_directory.as_deref())?;
+        options.add_macro_definition("USER_FRACTAL", None);
+        let resolved_name = self.path.display().to_string();
+        let shader_directory = self.shader_directory.clone();
+        options.set_include_callback(move |name, _, _, _| {
+            if name == INCLUDE_NAME {
+                Ok(shaderc::ResolvedInclude {
+                    resolved_name: resolved_name.clone(),
+                    content: snippet.clone(),
+                })
+            } else {
+                object::resolve_shared_include(name, shader_directory.as_deref())
+            }
+        });
+        object::compile_glsl(
+            device,
+            &source,
+            FRACTAL_FRAGMENT_SHADER.kind,
+            FRACTAL_FRAGMENT_SHADER.file_name,
+            &options,
+        )
+    }
+}
@@ -0,0 +1,290 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crossbeam_channel::{Sender, TrySendError};
+use serde::Serialize;
+
+use crate::app_config::EventHooks;
+
+// Events waiting to be delivered. Beyond this, new events are dropped rather than delaying the frame.
+const MAX_QUEUED_EVENTS: usize = 64;
+
+// Bound how long a slow or absent receiver may hold up the delivery of later events.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(1);
+
+// The prefix of the OSC address of each event, which is followed by the event's name.
+pub const OSC_ADDRESS_PREFIX: &str = "/fractal_sugar/";
+
+// Notable moments which external tools, such as streaming software or lighting controllers, may react to.
+#[derive(Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    AppStarted,
+    ColorSchemeChanged { index: usize, name: String },
+    FractalChanged { id: u32 },
+    Kick { strength: f32 }, // Only kicks at least as strong as the configured threshold
+    RecordingStarted,
+    RecordingStopped,
+}
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AppStarted => "app_started",
+            Self::ColorSchemeChanged { .. } => "color_scheme_changed",
+            Self::FractalChanged { .. } => "fractal_changed",
+            Self::Kick { .. } => "kick",
+            Self::RecordingStarted => "recording_started",
+            Self::RecordingStopped => "recording_stopped",
+        }
+    }
+}
+
+// A destination which events are delivered to.
+trait EventSink: Send {
+    fn description(&self) -> String;
+    fn send(&mut self, event: &Event) -> anyhow::Result<()>;
+}
+
+// Delivers each event as a JSON body POSTed to a plain `http://` URL.
+struct WebhookSink {
+    url: String,
+    host: String, // The `HOST[:PORT]` of the URL, as sent in the `Host` header
+    path: String,
+}
+
+impl WebhookSink {
+    fn new(url: &str) -> anyhow::Result<Self> {
+        let Some(rest) = url.strip_prefix("http://") else {
+            anyhow::bail!("Webhook URL `{url}` must begin with `http://`");
+        };
+        let (host, path) = rest.find('/').map_or((rest, "/"), |i| rest.split_at(i));
+        if host.is_empty() {
+            anyhow::bail!("Webhook URL `{url}` has no host");
+        }
+        Ok(Self {
+            url: String::from(url),
+            host: String::from(host),
+            path: String::from(path),
+        })
+    }
+}
+
+impl EventSink for WebhookSink {
+    fn description(&self) -> String {
+        format!("webhook {}", self.url)
+    }
+
+    fn send(&mut self, event: &Event) -> anyhow::Result<()> {
+        let address = if self.host.contains(':') {
+            self.host.clone()
+        } else {
+            format!("{}:80", self.host)
+        };
+        let Some(address) = address.to_socket_addrs()?.next() else {
+            anyhow::bail!("Failed to resolve `{}`", self.host);
+        };
+        let mut stream = TcpStream::connect_timeout(&address, WEBHOOK_TIMEOUT)?;
+        stream.set_read_timeout(Some(WEBHOOK_TIMEOUT))?;
+        stream.set_write_timeout(Some(WEBHOOK_TIMEOUT))?;
+
+        let body = serde_json::to_string(event)?;
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            self.path,
+            self.host,
+            body.len(),
+        )?;
+
+        // Only the status line matters, e.g. `HTTP/1.1 204 No Content`.
+        let mut response = [0; 32];
+        let size = stream.read(&mut response)?;
+        let status = String::from_utf8_lossy(&response[..size]);
+        match status.split_whitespace().nth(1) {
+            Some(code) if code.starts_with('2') => Ok(()),
+            _ => anyhow::bail!(
+                "Unexpected response `{}`",
+                status.lines().next().unwrap_or("")
+            ),
+        }
+    }
+}
+
+// Delivers each event as an OSC message over UDP, addressed `/fractal_sugar/<event name>`.
+struct OscSink {
+    target: String,
+    socket: UdpSocket,
+}
+
+impl OscSink {
+    fn new(target: &str) -> anyhow::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.connect(target)?;
+        Ok(Self {
+            target: String::from(target),
+            socket,
+        })
+    }
+}
+
+// An argument of an OSC message.
+enum OscArgument<'a> {
+    Int(i32),
+    Float(f32),
+    String(&'a str),
+}
+
+// Append an OSC string, which is null-terminated and padded to a multiple of four bytes.
+fn push_osc_string(packet: &mut Vec<u8>, s: &str) {
+    packet.extend_from_slice(s.as_bytes());
+    let padding = 4 - s.len() % 4;
+    packet.resize(packet.len() + padding, 0);
+}
+
+// Encode an OSC message with the given address and arguments.
+fn encode_osc_message(address: &str, arguments: &[OscArgument]) -> Vec<u8> {
+    let mut packet = Vec::new();
+    push_osc_string(&mut packet, address);
+
+    let type_tags: String = std::iter::once(',')
+        .chain(arguments.iter().map(|argument| match argument {
+            OscArgument::Int(_) => 'i',
+            OscArgument::Float(_) => 'f',
+            OscArgument::String(_) => 's',
+        }))
+        .collect();
+    push_osc_string(&mut packet, &type_tags);
+
+    for argument in arguments {
+        match argument {
+            OscArgument::Int(i) => packet.extend_from_slice(&i.to_be_bytes()),
+            OscArgument::Float(f) => packet.extend_from_slice(&f.to_be_bytes()),
+            OscArgument::String(s) => push_osc_string(&mut packet, s),
+        }
+    }
+    packet
+}
+
+impl EventSink for OscSink {
+    fn description(&self) -> String {
+        format!("OSC target {}", self.target)
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    fn send(&mut self, event: &Event) -> anyhow::Result<()> {
+        let arguments = match event {
+            Event::ColorSchemeChanged { index, name } => {
+                vec![OscArgument::Int(*index as i32), OscArgument::String(name)]
+            }
+            Event::FractalChanged { id } => vec![OscArgument::Int(*id as i32)],
+            Event::Kick { strength } => vec![OscArgument::Float(*strength)],
+            Event::AppStarted | Event::RecordingStarted | Event::RecordingStopped => Vec::new(),
+        };
+        let address = format!("{OSC_ADDRESS_PREFIX}{}", event.name());
+        self.socket
+            .send(&encode_osc_message(&address, &arguments))?;
+        Ok(())
+    }
+}
+
+// Publishes events to the configured sinks from a background thread, so slow receivers never stall rendering.
+pub struct EventBus {
+    tx: Option<Sender<Event>>,
+    thread: Option<JoinHandle<()>>,
+    kick_threshold: f32,
+}
+
+impl EventBus {
+    pub fn new(hooks: &EventHooks) -> Self {
+        let mut sinks: Vec<Box<dyn EventSink>> = Vec::new();
+        for url in &hooks.webhooks {
+            match WebhookSink::new(url) {
+                Ok(sink) => sinks.push(Box::new(sink)),
+                Err(e) => log!("Ignoring event webhook: {e:?}"),
+            }
+        }
+        for target in &hooks.osc_targets {
+            match OscSink::new(target) {
+                Ok(sink) => sinks.push(Box::new(sink)),
+                Err(e) => log!("Ignoring event OSC target `{target}`: {e:?}"),
+            }
+        }
+
+        // Without any sinks there is nothing to deliver, so no thread is needed.
+        if sinks.is_empty() {
+            return Self {
+                tx: None,
+                thread: None,
+                kick_threshold: hooks.kick_threshold,
+            };
+        }
+
+        let (tx, rx) = crossbeam_channel::bounded::<Event>(MAX_QUEUED_EVENTS);
+        let thread = std::thread::spawn(move || {
+            for event in rx {
+                for sink in &mut sinks {
+                    if let Err(e) = sink.send(&event) {
+                        log!(
+                            "Failed to deliver the `{}` event to {}: {e:?}",
+                            event.name(),
+                            sink.description()
+                        );
+                    }
+                }
+            }
+        });
+        Self {
+            tx: Some(tx),
+            thread: Some(thread),
+            kick_threshold: hooks.kick_threshold,
+        }
+    }
+
+    pub fn publish(&self, event: Event) {
+        let Some(tx) = &self.tx else {
+            return;
+        };
+        if let Err(TrySendError::Full(event)) = tx.try_send(event) {
+            log!(
+                "Dropped the `{}` event, the event queue is full",
+                event.name()
+            );
+        }
+    }
+
+    // Publish a kick only if it is at least as strong as the configured threshold.
+    pub fn publish_kick(&self, strength: f32) {
+        if strength >= self.kick_threshold {
+            self.publish(Event::Kick { strength });
+        }
+    }
+
+    // Stop accepting events and wait for those already queued to be delivered.
+    pub fn shutdown(&mut self) {
+        self.tx = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
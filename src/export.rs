@@ -0,0 +1,203 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::path::PathBuf;
+
+use crossbeam_channel::Sender;
+
+use crate::audio::{self, SharedAnalysisConfig, SourceKind, State};
+use crate::timelapse;
+
+// Frames rendered per second of the video when no rate is given.
+pub const DEFAULT_FRAME_RATE: f32 = 60.;
+
+// Frames waiting to be saved. Rendering waits for the disk rather than skipping frames of the video.
+const MAX_QUEUED_FRAMES: usize = 8;
+
+// Frames between each log of the export's progress.
+const PROGRESS_FRAMES: usize = 300;
+
+// The frame sequence to render, as given on the command line.
+pub struct ExportOptions {
+    pub directory: PathBuf,
+    pub frame_rate: f32,
+    pub duration: Option<f32>, // Seconds to render, or the whole audio file if not given
+    pub audio_file: Option<PathBuf>, // An audio file to analyze, rather than generating the audio
+    pub size: [u32; 2],
+}
+
+// Renders a session at a fixed frame rate, saving every frame as a numbered PNG for assembling into a video.
+pub struct Export {
+    tx: Option<Sender<(PathBuf, Vec<u8>)>>,
+    writer: Option<std::thread::JoinHandle<bool>>,
+    directory: PathBuf,
+    frame_rate: f32,
+    frame_count: usize, // Frames of the video, including the one being rendered
+    total_frames: usize,
+    awaiting_capture: bool,
+    states: std::vec::IntoIter<State>,
+    state_interval: f32, // Seconds between consecutive audio states
+    next_state_time: f32,
+    time: f32,
+    size: [u32; 2],
+}
+
+impl Export {
+    // Analyze all of the audio to be rendered, then spawn the thread saving frames into the export directory.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    pub fn new(
+        options: ExportOptions,
+        source_kind: SourceKind,
+        analysis_config: SharedAnalysisConfig,
+    ) -> anyhow::Result<Self> {
+        let (sample_rate, samples) = match (&options.audio_file, source_kind) {
+            (Some(path), _) => audio::file_source::decode(path, options.duration)?,
+            (None, SourceKind::Generator(signal)) => {
+                let duration = options.duration.ok_or_else(|| {
+                    anyhow::anyhow!("Exporting a test signal requires `--duration`")
+                })?;
+                let rate = audio::generator::SAMPLE_RATE;
+                let samples = audio::generator::samples(signal)
+                    .take((duration * rate as f32).ceil() as usize)
+                    .map(|sample| (sample, sample))
+                    .collect();
+                (rate, samples)
+            }
+            (None, _) => anyhow::bail!(
+                "An export is rendered offline, so requires `--audio-file` or a test signal for `--audio-source`"
+            ),
+        };
+        let duration = options
+            .duration
+            .unwrap_or(samples.len() as f32 / sample_rate as f32);
+        let total_frames = (duration * options.frame_rate).ceil() as usize;
+
+        let states = audio::analyze_offline(sample_rate, samples.into_iter(), analysis_config);
+        let state_interval = audio::analysis_interval(sample_rate);
+        log!(
+            "Analyzed {} audio states for an export of {total_frames} frames",
+            states.len()
+        );
+
+        std::fs::create_dir_all(&options.directory)?;
+        let (tx, rx) = crossbeam_channel::bounded::<(PathBuf, Vec<u8>)>(MAX_QUEUED_FRAMES);
+        let size = options.size;
+        let writer = std::thread::spawn(move || {
+            let mut saved = true;
+            for (path, rgba) in rx {
+                if let Err(e) = timelapse::write_png(&path, size, &rgba) {
+                    log!("Failed to save exported frame `{}`: {e:?}", path.display());
+                    saved = false;
+                }
+            }
+            saved
+        });
+
+        Ok(Self {
+            tx: Some(tx),
+            writer: Some(writer),
+            directory: options.directory,
+            frame_rate: options.frame_rate,
+            frame_count: 0,
+            total_frames,
+            awaiting_capture: false,
+            states: states.into_iter(),
+            state_interval,
+            next_state_time: state_interval,
+            time: 0.,
+            size,
+        })
+    }
+
+    // Advance to the next frame of the video, returning the seconds it simulates.
+    // Time only passes once the particles are ready, so that the video begins with the visualization
+    pub fn step(&mut self, particles_ready: bool) -> f32 {
+        if !particles_ready || self.frame_count >= self.total_frames {
+            return 0.;
+        }
+        let delta_time = 1. / self.frame_rate;
+        self.time += delta_time;
+        self.frame_count += 1;
+        self.awaiting_capture = true;
+        delta_time
+    }
+
+    // The audio states analyzed by the current time which have not yet been applied, in order.
+    pub fn take_due_states(&mut self) -> Vec<State> {
+        let mut due = Vec::new();
+        while self.next_state_time <= self.time {
+            let Some(state) = self.states.next() else {
+                break;
+            };
+            due.push(state);
+            self.next_state_time += self.state_interval;
+        }
+        due
+    }
+
+    // Whether this frame should be captured, since it advanced the video.
+    pub fn capture_due(&self) -> bool {
+        self.awaiting_capture
+    }
+
+    // Queue the captured frame, given as tightly packed RGBA rows, to be saved as the latest frame of the video.
+    pub fn save(&mut self, rgba: &[u8]) {
+        if !std::mem::take(&mut self.awaiting_capture) {
+            return;
+        }
+        let Some(tx) = &self.tx else {
+            return;
+        };
+        let path = self
+            .directory
+            .join(format!("frame-{:06}.png", self.frame_count - 1));
+        if tx.send((path, rgba.to_vec())).is_err() {
+            self.tx = None;
+        }
+        if self.frame_count % PROGRESS_FRAMES == 0 {
+            log!(
+                "Exported {} of {} frames",
+                self.frame_count,
+                self.total_frames
+            );
+        }
+    }
+
+    // Whether every frame was saved, once the last frame has been captured and every frame written.
+    pub fn finished(&mut self) -> Option<bool> {
+        if self.frame_count < self.total_frames || self.awaiting_capture {
+            return None;
+        }
+        self.tx = None;
+        let saved = self.writer.take()?.join().unwrap_or(false);
+        log!(
+            "Exported {} frames to `{}`",
+            self.frame_count,
+            self.directory.display()
+        );
+        Some(saved)
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        self.size
+    }
+}
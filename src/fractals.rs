@@ -0,0 +1,113 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+// The tunables of a fractal's distance estimator, written to the runtime constants while it is selected.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Tunables {
+    pub iterations: u32,
+    pub bailout: f32,        // The radius beyond which a point escapes
+    pub fold_scale: f32,     // The scale applied by each fold of the space
+    pub color_exponent: f32, // How sharply the color darkens with the steps taken to reach the surface
+}
+
+// A fractal selectable by its distance estimator ID, and which tunables its distance estimator uses.
+#[derive(Clone, Copy)]
+pub struct Fractal {
+    pub name: &'static str,
+    pub defaults: Tunables,
+    pub bailout: bool,
+    pub fold_scale: bool,
+}
+
+pub const FRACTAL_COUNT: usize = 12;
+pub const MAX_ITERATIONS: u32 = 24;
+pub const MAX_BAILOUT: f32 = 64.;
+pub const MAX_FOLD_SCALE: f32 = 4.;
+pub const MAX_COLOR_EXPONENT: f32 = 8.;
+
+// Most fractals darken with the steps taken alike.
+const COLOR_EXPONENT: f32 = 2.75;
+
+const fn fractal(
+    name: &'static str,
+    iterations: u32,
+    bailout: Option<f32>,
+    fold_scale: Option<f32>,
+    color_exponent: f32,
+) -> Fractal {
+    Fractal {
+        name,
+        defaults: Tunables {
+            iterations,
+            bailout: match bailout {
+                Some(bailout) => bailout,
+                None => 1.,
+            },
+            fold_scale: match fold_scale {
+                Some(fold_scale) => fold_scale,
+                None => 1.,
+            },
+            color_exponent,
+        },
+        bailout: bailout.is_some(),
+        fold_scale: fold_scale.is_some(),
+    }
+}
+
+// Every fractal, indexed by its distance estimator ID, with the values built into `ray_march.frag`.
+pub static FRACTALS: [Fractal; FRACTAL_COUNT] = [
+    fractal("Empty", 1, None, None, COLOR_EXPONENT),
+    fractal(
+        "Mandelbox-inspired",
+        5,
+        Some(3.4641),
+        Some(2.1),
+        COLOR_EXPONENT,
+    ),
+    fractal("Mandelbulb", 3, Some(1.5), None, COLOR_EXPONENT),
+    fractal("Klein-inspired", 3, None, None, COLOR_EXPONENT),
+    fractal("Menger-inspired", 4, None, Some(3.), COLOR_EXPONENT),
+    fractal(
+        "Sierpiński-inspired",
+        8,
+        Some(31.623),
+        Some(2.),
+        COLOR_EXPONENT,
+    ),
+    fractal("Quaternion Julia", 6, Some(1.5), None, 1.75),
+    fractal("Mandelbox", 10, None, Some(2.), COLOR_EXPONENT),
+    fractal("Menger sponge", 5, None, Some(3.), COLOR_EXPONENT),
+    fractal(
+        "Sierpiński tetrahedron",
+        9,
+        Some(31.623),
+        Some(2.),
+        COLOR_EXPONENT,
+    ),
+    fractal("Pseudo-Kleinian", 7, None, None, COLOR_EXPONENT),
+    fractal("User fractal", 8, Some(2.), Some(2.), COLOR_EXPONENT),
+];
+
+pub fn default_tunables() -> [Tunables; FRACTAL_COUNT] {
+    FRACTALS.map(|fractal| fractal.defaults)
+}
+
+// The built-in fractal of the given ID, if any.
+pub fn fractal_info(id: u32) -> Option<&'static Fractal> {
+    FRACTALS.get(id as usize)
+}
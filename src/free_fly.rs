@@ -0,0 +1,126 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use winit::event::{ModifiersState, VirtualKeyCode};
+
+use crate::my_math::{Quaternion, Vector3, Vector4};
+
+// Speed of the camera in fractal units per second, and the multipliers applied while holding Shift or Ctrl.
+const FLY_SPEED: f32 = 0.4;
+const FAST_MULTIPLIER: f32 = 4.;
+const SLOW_MULTIPLIER: f32 = 0.2;
+
+// Radians the view turns for each unit of raw mouse motion.
+const LOOK_SENSITIVITY: f32 = 0.0025;
+
+// The keys steering the camera, by their position on a US QWERTY keyboard, and their direction in view space.
+// The view looks down its negative z-axis, as in `ray_march.frag`
+const MOVEMENT_KEYS: [(VirtualKeyCode, Vector3); 6] = [
+    (VirtualKeyCode::W, Vector3::new(0., 0., -1.)),
+    (VirtualKeyCode::S, Vector3::new(0., 0., 1.)),
+    (VirtualKeyCode::A, Vector3::new(-1., 0., 0.)),
+    (VirtualKeyCode::D, Vector3::new(1., 0., 0.)),
+    (VirtualKeyCode::E, Vector3::new(0., 1., 0.)),
+    (VirtualKeyCode::Q, Vector3::new(0., -1., 0.)),
+];
+
+// A camera flown freely through the fractal, in place of orbiting its center.
+// The view turns about its own axes, like a spacecraft, so that any orientation it starts from is kept without a jump
+pub struct FreeFly {
+    position: Vector3,
+    orientation: Quaternion,
+    held: [bool; 6], // Whether each of the movement keys is held down
+}
+
+impl FreeFly {
+    // Begin flying from the given position and orientation, such as those of the orbiting camera.
+    pub fn new(position: Vector3, orientation: Quaternion) -> Self {
+        Self {
+            position,
+            orientation,
+            held: [false; 6],
+        }
+    }
+
+    pub fn position(&self) -> Vector3 {
+        self.position
+    }
+    pub fn orientation(&self) -> Quaternion {
+        self.orientation
+    }
+
+    // Press or release the key at the given position, returning whether it is one of the movement keys.
+    pub fn key(&mut self, position: Option<VirtualKeyCode>, pressed: bool) -> bool {
+        match MOVEMENT_KEYS
+            .iter()
+            .position(|(keycode, _)| Some(*keycode) == position)
+        {
+            Some(index) => {
+                self.held[index] = pressed;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Stop moving, such as when focus is lost and key releases would go unreported.
+    pub fn release_keys(&mut self) {
+        self.held = [false; 6];
+    }
+
+    // Turn the view by raw mouse motion, where positive motion is rightward and downward.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn look(&mut self, delta: (f64, f64)) {
+        // Quaternions are built from half of the angle they rotate by.
+        let yaw = -0.5 * LOOK_SENSITIVITY * delta.0 as f32;
+        let pitch = -0.5 * LOOK_SENSITIVITY * delta.1 as f32;
+        self.orientation
+            .rotate_by(Quaternion::build(Vector3::new(0., 1., 0.), yaw));
+        self.orientation
+            .rotate_by(Quaternion::build(Vector3::new(1., 0., 0.), pitch));
+
+        // Renormalize so that rounding errors do not accumulate into a scaling of the view.
+        let v = self.orientation.v;
+        self.orientation.v = v.scale(1. / Vector4::dot(v, v).sqrt());
+    }
+
+    // Move the camera along the held directions, faster while holding Shift and slower while holding Ctrl.
+    pub fn update(&mut self, delta_time: f32, modifiers: ModifiersState) {
+        let direction = MOVEMENT_KEYS
+            .iter()
+            .zip(self.held)
+            .filter(|(_, held)| *held)
+            .fold(Vector3::default(), |sum, ((_, direction), _)| {
+                sum + *direction
+            });
+
+        // Opposing keys cancel, leaving no direction to move in.
+        if Vector3::dot(direction, direction) < 0.5 {
+            return;
+        }
+
+        let mut speed = FLY_SPEED;
+        if modifiers.shift() {
+            speed *= FAST_MULTIPLIER;
+        }
+        if modifiers.ctrl() {
+            speed *= SLOW_MULTIPLIER;
+        }
+        self.position += (speed * delta_time) * self.orientation.rotate_point(direction.norm());
+    }
+}
@@ -0,0 +1,984 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::sync::OnceLock;
+
+use winit::event::{ModifiersState, ScanCode, VirtualKeyCode};
+
+// The actions which may be triggered by keyboard shortcuts.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    ToggleFullscreen,
+    Escape,
+    ToggleConsole,
+    ToggleHelp,
+    ToggleConfig,
+    ToggleVariables,
+    ToggleSpectrum,
+    SaveConfig,
+    ToggleTimelapse,
+    Screenshot,
+    RecordClip,
+    ToggleInspect,
+    StepFrame,
+    ToggleAudioResponsive,
+    ToggleCaptureMode,
+    ToggleKaleidoscope,
+    FewerKaleidoscopeSegments,
+    MoreKaleidoscopeSegments,
+    ToggleJello,
+    ToggleParticles,
+    ToggleHideStationary,
+    ToggleAlternateColors,
+    Toggle3dParticles,
+    ResetParticles,
+    ToggleSurfaceEmission,
+    NextColorScheme,
+    PreviousColorScheme,
+    SelectFractal(u32),
+    NextColoringMode,
+    NextPhysicsMode,
+    CycleDisplayLayout,
+    ToggleFreeFly,
+    PlayCameraPath(usize),
+    ToggleBlackout,
+    ToggleFreeze,
+    SaveSnapshot(usize),
+    RecallSnapshot(usize),
+    PlayPauseShow,
+    NextScene,
+}
+
+// The groups in which actions are listed by the Help window, in display order.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    AppWindow,
+    Overlay,
+    Debugging,
+    Audio,
+    Visuals,
+    Performance,
+}
+pub const CATEGORIES: [Category; 6] = [
+    Category::AppWindow,
+    Category::Overlay,
+    Category::Debugging,
+    Category::Audio,
+    Category::Visuals,
+    Category::Performance,
+];
+
+impl Category {
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::AppWindow => "App-Window Management",
+            Self::Overlay => "Overlay-Window Management",
+            Self::Debugging => "Debugging",
+            Self::Audio => "Audio",
+            Self::Visuals => "Visuals",
+            Self::Performance => "Live Performance",
+        }
+    }
+}
+
+impl Action {
+    // A stable identifier for the action.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ToggleFullscreen => "toggle_fullscreen",
+            Self::Escape => "escape",
+            Self::ToggleConsole => "toggle_console",
+            Self::ToggleHelp => "toggle_help",
+            Self::ToggleConfig => "toggle_config",
+            Self::ToggleVariables => "toggle_variables",
+            Self::ToggleSpectrum => "toggle_spectrum",
+            Self::SaveConfig => "save_config",
+            Self::ToggleTimelapse => "toggle_timelapse",
+            Self::Screenshot => "screenshot",
+            Self::RecordClip => "record_clip",
+            Self::ToggleInspect => "toggle_inspect",
+            Self::StepFrame => "step_frame",
+            Self::ToggleAudioResponsive => "toggle_audio_responsive",
+            Self::ToggleCaptureMode => "toggle_capture_mode",
+            Self::ToggleKaleidoscope => "toggle_kaleidoscope",
+            Self::FewerKaleidoscopeSegments => "fewer_kaleidoscope_segments",
+            Self::MoreKaleidoscopeSegments => "more_kaleidoscope_segments",
+            Self::ToggleJello => "toggle_jello",
+            Self::ToggleParticles => "toggle_particles",
+            Self::ToggleHideStationary => "toggle_hide_stationary",
+            Self::ToggleAlternateColors => "toggle_alternate_colors",
+            Self::Toggle3dParticles => "toggle_3d_particles",
+            Self::ResetParticles => "reset_particles",
+            Self::ToggleSurfaceEmission => "toggle_surface_emission",
+            Self::NextColorScheme => "next_color_scheme",
+            Self::PreviousColorScheme => "previous_color_scheme",
+            Self::SelectFractal(_) => "select_fractal",
+            Self::NextColoringMode => "next_coloring_mode",
+            Self::NextPhysicsMode => "next_physics_mode",
+            Self::CycleDisplayLayout => "cycle_display_layout",
+            Self::ToggleFreeFly => "toggle_free_fly",
+            Self::PlayCameraPath(_) => "play_camera_path",
+            Self::ToggleBlackout => "toggle_blackout",
+            Self::ToggleFreeze => "toggle_freeze",
+            Self::SaveSnapshot(_) => "save_snapshot",
+            Self::RecallSnapshot(_) => "recall_snapshot",
+            Self::PlayPauseShow => "play_pause_show",
+            Self::NextScene => "next_scene",
+        }
+    }
+
+    // The name the action is bound by in the `[keybindings]` table of the config file.
+    // Actions for each of a set of keys are numbered as their default keys are
+    pub fn config_name(self) -> String {
+        match self {
+            Self::SelectFractal(index) => format!("{}_{index}", self.name()),
+            Self::PlayCameraPath(index)
+            | Self::SaveSnapshot(index)
+            | Self::RecallSnapshot(index) => {
+                format!("{}_{}", self.name(), index + 1)
+            }
+            _ => String::from(self.name()),
+        }
+    }
+
+    // The action bound by the given name in the config file, if any.
+    pub fn from_config_name(name: &str) -> Option<Self> {
+        BINDINGS
+            .iter()
+            .map(|(_, action)| *action)
+            .find(|action| action.config_name() == name)
+    }
+
+    // The explanation shown in the Help window.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::ToggleFullscreen => "Toggle window fullscreen",
+            Self::Escape => "If fullscreen, then enter windowed mode. Else, close the application",
+            Self::ToggleConsole => "Toggle the visibility of the output command prompt",
+            Self::ToggleHelp => "Toggle visibility of this Help window",
+            Self::ToggleConfig => "Toggle visibility of the App Config window",
+            Self::ToggleVariables => "Toggle visibility of the Variables console, listing every runtime variable by name",
+            Self::ToggleSpectrum => "Toggle visibility of the Spectrum window, showing the live frequency spectrum, the loudest notes of each band, and how near the bass is to a kick",
+            Self::SaveConfig => "Save the applied configuration to the config file",
+            Self::ToggleTimelapse => "Start or stop a timelapse, saving a frame every configured interval into a new folder",
+            Self::Screenshot => "Save a screenshot of the window as a PNG",
+            Self::RecordClip => "Record a short clip as an animated GIF, or stop recording early",
+            Self::ToggleInspect => "Toggle pause-and-inspect mode, freezing the simulation and showing the submitted push constants",
+            Self::StepFrame => "While paused, advance the simulation by exactly one frame",
+            Self::ToggleAudioResponsive => "Toggle the application's responsiveness to system audio",
+            Self::ToggleCaptureMode => "Switch between capturing the audio out (loopback) and the audio in, such as a microphone",
+            Self::ToggleKaleidoscope => "Toggle kaleidoscope effect on fractals",
+            Self::FewerKaleidoscopeSegments => "Fold the kaleidoscope into fewer mirrored segments",
+            Self::MoreKaleidoscopeSegments => "Fold the kaleidoscope into more mirrored segments",
+            Self::ToggleJello => "Toggle 'jello' effect on particles (i.e., the fixing of particles to a position with spring tension)",
+            Self::ToggleParticles => "Toggle the rendering and updating of particles",
+            Self::ToggleHideStationary => "Toggles whether to hide stationary particles",
+            Self::ToggleAlternateColors => "Toggle negative-color effect for particles",
+            Self::Toggle3dParticles => "Toggle between 2D and 3D projections of the particles",
+            Self::ResetParticles => "Return the particles to their initial positions at rest, such as to recover from a chaotic state",
+            Self::ToggleSurfaceEmission => "Toggle respawning particles on the surface of the fractal, so that the 3D swarm outlines its shape",
+            Self::NextColorScheme => "Cycle through particle color schemes. *Requires that all overlay windows are closed*",
+            Self::PreviousColorScheme => "Cycle backwards through particle color schemes",
+            Self::SelectFractal(0) => "Select the 'empty' fractal",
+            Self::SelectFractal(_) => "Select the fractal corresponding to the respective key, where - and = follow 9",
+            Self::NextColoringMode => "Cycle through the ways fractals are colored, independent of the selected fractal",
+            Self::NextPhysicsMode => "Cycle through the forces acting on the particles alongside the attractors: none, gravity, a vortex, and a flow field",
+            Self::CycleDisplayLayout => "Cycle between showing particles and fractal together, the fractal only, the particles only, and the two split side-by-side",
+            Self::ToggleFreeFly => "Toggle flying the camera freely through the fractal, steering with the mouse and moving with W, A, S, D, Q, and E. Hold SHIFT to fly faster or CTRL to fly slower",
+            Self::PlayCameraPath(_) => "Animate the fractal camera along the camera path configured for the respective key, in order of definition",
+            Self::ToggleBlackout => "Fade the output to black until pressed again, as an emergency blackout",
+            Self::ToggleFreeze => "Hold the current frame until pressed again, while the visualization keeps running behind it",
+            Self::SaveSnapshot(_) => "Save the current look, from the color scheme to the modulation, to the snapshot slot of the respective key",
+            Self::RecallSnapshot(_) => "Recall the look saved to the snapshot slot of the respective key, blending into it like a lighting cue",
+            Self::PlayPauseShow => "Start the show of configured scenes from its first scene, or pause or resume it",
+            Self::NextScene => "Skip to the next scene of the show, starting the show if it was stopped",
+        }
+    }
+
+    pub fn category(self) -> Category {
+        match self {
+            Self::ToggleFullscreen
+            | Self::Escape
+            | Self::ToggleConsole
+            | Self::ToggleTimelapse
+            | Self::Screenshot
+            | Self::RecordClip => Category::AppWindow,
+            Self::ToggleHelp
+            | Self::ToggleConfig
+            | Self::ToggleVariables
+            | Self::ToggleSpectrum
+            | Self::SaveConfig => Category::Overlay,
+            Self::ToggleInspect | Self::StepFrame => Category::Debugging,
+            Self::ToggleAudioResponsive | Self::ToggleCaptureMode => Category::Audio,
+            Self::ToggleKaleidoscope
+            | Self::FewerKaleidoscopeSegments
+            | Self::MoreKaleidoscopeSegments
+            | Self::ToggleJello
+            | Self::ToggleParticles
+            | Self::ToggleHideStationary
+            | Self::ToggleAlternateColors
+            | Self::Toggle3dParticles
+            | Self::ResetParticles
+            | Self::ToggleSurfaceEmission
+            | Self::NextColorScheme
+            | Self::PreviousColorScheme
+            | Self::SelectFractal(_)
+            | Self::NextColoringMode
+            | Self::NextPhysicsMode
+            | Self::CycleDisplayLayout
+            | Self::ToggleFreeFly
+            | Self::PlayCameraPath(_) => Category::Visuals,
+            Self::ToggleBlackout
+            | Self::ToggleFreeze
+            | Self::SaveSnapshot(_)
+            | Self::RecallSnapshot(_)
+            | Self::PlayPauseShow
+            | Self::NextScene => Category::Performance,
+        }
+    }
+
+    // Whether the action does anything in this build, since some only exist on certain platforms.
+    pub fn available(self) -> bool {
+        match self {
+            Self::ToggleConsole => cfg!(all(not(debug_assertions), target_os = "windows")),
+            _ => true,
+        }
+    }
+}
+
+// How a chord identifies its key. Logical keys follow the symbol printed on the key in the user's layout,
+// while physical keys follow the position of the named key on a US QWERTY keyboard, regardless of layout
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Logical(VirtualKeyCode),
+    Physical(VirtualKeyCode),
+}
+
+// A key pressed, identified both by the symbol it produces and by its position on the keyboard.
+#[derive(Clone, Copy)]
+pub struct KeyPress {
+    pub logical: Option<VirtualKeyCode>,
+    pub scancode: ScanCode,
+}
+
+impl Key {
+    // The named key, either as printed or at whose US QWERTY position the key is.
+    pub fn keycode(self) -> VirtualKeyCode {
+        match self {
+            Self::Logical(keycode) | Self::Physical(keycode) => keycode,
+        }
+    }
+
+    fn matches(self, press: KeyPress) -> bool {
+        match self {
+            Self::Logical(keycode) => press.logical == Some(keycode),
+
+            // Platforms whose scancodes are unknown fall back to the symbol produced.
+            Self::Physical(keycode) => match physical_key(press.scancode) {
+                Some(position) => position == keycode,
+                None => press.logical == Some(keycode),
+            },
+        }
+    }
+}
+
+// The key at the position of the given scancode on a US QWERTY keyboard.
+// Windows and Linux both report the scancodes of the PC keyboard's set 1 for these keys
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn physical_key(scancode: ScanCode) -> Option<VirtualKeyCode> {
+    use VirtualKeyCode as K;
+    Some(match scancode {
+        1 => K::Escape,
+        2 => K::Key1,
+        3 => K::Key2,
+        4 => K::Key3,
+        5 => K::Key4,
+        6 => K::Key5,
+        7 => K::Key6,
+        8 => K::Key7,
+        9 => K::Key8,
+        10 => K::Key9,
+        11 => K::Key0,
+        12 => K::Minus,
+        13 => K::Equals,
+        14 => K::Back,
+        15 => K::Tab,
+        16 => K::Q,
+        17 => K::W,
+        18 => K::E,
+        19 => K::R,
+        20 => K::T,
+        21 => K::Y,
+        22 => K::U,
+        23 => K::I,
+        24 => K::O,
+        25 => K::P,
+        26 => K::LBracket,
+        27 => K::RBracket,
+        28 => K::Return,
+        30 => K::A,
+        31 => K::S,
+        32 => K::D,
+        33 => K::F,
+        34 => K::G,
+        35 => K::H,
+        36 => K::J,
+        37 => K::K,
+        38 => K::L,
+        39 => K::Semicolon,
+        40 => K::Apostrophe,
+        41 => K::Grave,
+        43 => K::Backslash,
+        44 => K::Z,
+        45 => K::X,
+        46 => K::C,
+        47 => K::V,
+        48 => K::B,
+        49 => K::N,
+        50 => K::M,
+        51 => K::Comma,
+        52 => K::Period,
+        53 => K::Slash,
+        57 => K::Space,
+        58 => K::Capital,
+        59 => K::F1,
+        60 => K::F2,
+        61 => K::F3,
+        62 => K::F4,
+        63 => K::F5,
+        64 => K::F6,
+        65 => K::F7,
+        66 => K::F8,
+        67 => K::F9,
+        68 => K::F10,
+        87 => K::F11,
+        88 => K::F12,
+        _ => return None,
+    })
+}
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn physical_key(_scancode: ScanCode) -> Option<VirtualKeyCode> {
+    None
+}
+
+// The key at the position pressed on a US QWERTY keyboard, for controls laid out by position such as WASD.
+pub fn key_position(press: KeyPress) -> Option<VirtualKeyCode> {
+    physical_key(press.scancode).or(press.logical)
+}
+
+// A key pressed while holding an exact set of modifiers.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Chord {
+    pub key: Key,
+    pub modifiers: ModifiersState,
+}
+
+impl Chord {
+    const fn key(keycode: VirtualKeyCode) -> Self {
+        Self {
+            key: Key::Logical(keycode),
+            modifiers: ModifiersState::empty(),
+        }
+    }
+    const fn ctrl(keycode: VirtualKeyCode) -> Self {
+        Self {
+            key: Key::Logical(keycode),
+            modifiers: ModifiersState::CTRL,
+        }
+    }
+    const fn shift(keycode: VirtualKeyCode) -> Self {
+        Self {
+            key: Key::Logical(keycode),
+            modifiers: ModifiersState::SHIFT,
+        }
+    }
+
+    // Chords of keys chosen for their position rather than their symbol, such as the number row,
+    // which needs Shift to type digits on some layouts
+    const fn position(keycode: VirtualKeyCode) -> Self {
+        Self {
+            key: Key::Physical(keycode),
+            modifiers: ModifiersState::empty(),
+        }
+    }
+    const fn ctrl_position(keycode: VirtualKeyCode) -> Self {
+        Self {
+            key: Key::Physical(keycode),
+            modifiers: ModifiersState::CTRL,
+        }
+    }
+    const fn shift_position(keycode: VirtualKeyCode) -> Self {
+        Self {
+            key: Key::Physical(keycode),
+            modifiers: ModifiersState::SHIFT,
+        }
+    }
+    const fn alt_position(keycode: VirtualKeyCode) -> Self {
+        Self {
+            key: Key::Physical(keycode),
+            modifiers: ModifiersState::ALT,
+        }
+    }
+}
+
+impl std::fmt::Display for Chord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (modifier, name) in [
+            (ModifiersState::CTRL, "CTRL+"),
+            (ModifiersState::ALT, "ALT+"),
+            (ModifiersState::SHIFT, "SHIFT+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+
+        // Keys matched differently than they are by default are marked, so that the chord parses back as shown.
+        let keycode = self.key.keycode();
+        match self.key {
+            Key::Physical(_) if !positional_by_default(keycode) => f.write_str(PHYSICAL_PREFIX)?,
+            Key::Logical(_) if positional_by_default(keycode) => f.write_str(LOGICAL_PREFIX)?,
+            _ => {}
+        }
+        f.write_str(&key_name(keycode))
+    }
+}
+
+// The name of the key as shown in the Help window, without any prefix.
+fn key_name(keycode: VirtualKeyCode) -> String {
+    match keycode {
+        VirtualKeyCode::Escape => String::from("ESC"),
+        VirtualKeyCode::Return => String::from("ENTER"),
+        VirtualKeyCode::Back => String::from("BACKSPACE"),
+        VirtualKeyCode::Capital => String::from("CAPS"),
+        VirtualKeyCode::Period => String::from("."),
+        VirtualKeyCode::Grave => String::from("`"),
+        VirtualKeyCode::Key0 => String::from("0"),
+        VirtualKeyCode::Key1 => String::from("1"),
+        VirtualKeyCode::Key2 => String::from("2"),
+        VirtualKeyCode::Key3 => String::from("3"),
+        VirtualKeyCode::Key4 => String::from("4"),
+        VirtualKeyCode::Key5 => String::from("5"),
+        VirtualKeyCode::Key6 => String::from("6"),
+        VirtualKeyCode::Key7 => String::from("7"),
+        VirtualKeyCode::Key8 => String::from("8"),
+        VirtualKeyCode::Key9 => String::from("9"),
+        keycode => format!("{keycode:?}").to_uppercase(),
+    }
+}
+
+// Every key which may be named in the config file.
+const NAMEABLE_KEYS: [VirtualKeyCode; 75] = [
+    VirtualKeyCode::A,
+    VirtualKeyCode::B,
+    VirtualKeyCode::C,
+    VirtualKeyCode::D,
+    VirtualKeyCode::E,
+    VirtualKeyCode::F,
+    VirtualKeyCode::G,
+    VirtualKeyCode::H,
+    VirtualKeyCode::I,
+    VirtualKeyCode::J,
+    VirtualKeyCode::K,
+    VirtualKeyCode::L,
+    VirtualKeyCode::M,
+    VirtualKeyCode::N,
+    VirtualKeyCode::O,
+    VirtualKeyCode::P,
+    VirtualKeyCode::Q,
+    VirtualKeyCode::R,
+    VirtualKeyCode::S,
+    VirtualKeyCode::T,
+    VirtualKeyCode::U,
+    VirtualKeyCode::V,
+    VirtualKeyCode::W,
+    VirtualKeyCode::X,
+    VirtualKeyCode::Y,
+    VirtualKeyCode::Z,
+    VirtualKeyCode::Key0,
+    VirtualKeyCode::Key1,
+    VirtualKeyCode::Key2,
+    VirtualKeyCode::Key3,
+    VirtualKeyCode::Key4,
+    VirtualKeyCode::Key5,
+    VirtualKeyCode::Key6,
+    VirtualKeyCode::Key7,
+    VirtualKeyCode::Key8,
+    VirtualKeyCode::Key9,
+    VirtualKeyCode::F1,
+    VirtualKeyCode::F2,
+    VirtualKeyCode::F3,
+    VirtualKeyCode::F4,
+    VirtualKeyCode::F5,
+    VirtualKeyCode::F6,
+    VirtualKeyCode::F7,
+    VirtualKeyCode::F8,
+    VirtualKeyCode::F9,
+    VirtualKeyCode::F10,
+    VirtualKeyCode::F11,
+    VirtualKeyCode::F12,
+    VirtualKeyCode::Escape,
+    VirtualKeyCode::Return,
+    VirtualKeyCode::Space,
+    VirtualKeyCode::Tab,
+    VirtualKeyCode::Back,
+    VirtualKeyCode::Capital,
+    VirtualKeyCode::Insert,
+    VirtualKeyCode::Delete,
+    VirtualKeyCode::Home,
+    VirtualKeyCode::End,
+    VirtualKeyCode::PageUp,
+    VirtualKeyCode::PageDown,
+    VirtualKeyCode::Up,
+    VirtualKeyCode::Down,
+    VirtualKeyCode::Left,
+    VirtualKeyCode::Right,
+    VirtualKeyCode::Grave,
+    VirtualKeyCode::Minus,
+    VirtualKeyCode::Equals,
+    VirtualKeyCode::LBracket,
+    VirtualKeyCode::RBracket,
+    VirtualKeyCode::Backslash,
+    VirtualKeyCode::Semicolon,
+    VirtualKeyCode::Apostrophe,
+    VirtualKeyCode::Comma,
+    VirtualKeyCode::Period,
+    VirtualKeyCode::Slash,
+];
+
+// Prefixes of a key's name which choose whether it is matched by position or by symbol, such as `CTRL+phys:Z`.
+const PHYSICAL_PREFIX: &str = "phys:";
+const LOGICAL_PREFIX: &str = "sym:";
+
+// Whether the key is matched by position unless chosen otherwise.
+// As with the defaults, the number row and punctuation are matched by position and all other keys by symbol
+fn positional_by_default(keycode: VirtualKeyCode) -> bool {
+    use VirtualKeyCode as K;
+    matches!(
+        keycode,
+        K::Key0
+            | K::Key1
+            | K::Key2
+            | K::Key3
+            | K::Key4
+            | K::Key5
+            | K::Key6
+            | K::Key7
+            | K::Key8
+            | K::Key9
+            | K::Grave
+            | K::Minus
+            | K::Equals
+            | K::LBracket
+            | K::RBracket
+            | K::Backslash
+            | K::Semicolon
+            | K::Apostrophe
+            | K::Comma
+            | K::Period
+            | K::Slash
+    )
+}
+
+// Parse a chord as it is displayed in the Help window, such as `CTRL+SHIFT+T`.
+// The key's name may be prefixed by `phys:` or `sym:` to match it by position or by symbol, instead of by default
+impl std::str::FromStr for Chord {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let name = match parts.pop() {
+            Some(name) if !name.is_empty() => name,
+            _ => anyhow::bail!("Keybinding `{s}` does not name a key"),
+        };
+
+        let mut modifiers = ModifiersState::empty();
+        for modifier in parts {
+            modifiers |= match modifier.to_uppercase().as_str() {
+                "CTRL" => ModifiersState::CTRL,
+                "ALT" => ModifiersState::ALT,
+                "SHIFT" => ModifiersState::SHIFT,
+                _ => anyhow::bail!(
+                    "Keybinding `{s}` has the unknown modifier `{modifier}`, expected CTRL, ALT, or SHIFT"
+                ),
+            };
+        }
+
+        let strip_prefix = |prefix: &str| {
+            name.get(..prefix.len())
+                .filter(|start| start.eq_ignore_ascii_case(prefix))
+                .map(|_| &name[prefix.len()..])
+        };
+        let (name, positional) = if let Some(name) = strip_prefix(PHYSICAL_PREFIX) {
+            (name, Some(true))
+        } else if let Some(name) = strip_prefix(LOGICAL_PREFIX) {
+            (name, Some(false))
+        } else {
+            (name, None)
+        };
+
+        let name = name.to_uppercase();
+        let keycode = NAMEABLE_KEYS
+            .into_iter()
+            .find(|keycode| key_name(*keycode) == name)
+            .ok_or_else(|| anyhow::anyhow!("Keybinding `{s}` names the unknown key `{name}`"))?;
+        let positional = positional.unwrap_or_else(|| positional_by_default(keycode));
+        Ok(Self {
+            key: if positional {
+                Key::Physical(keycode)
+            } else {
+                Key::Logical(keycode)
+            },
+            modifiers,
+        })
+    }
+}
+
+// The default bindings of chords to actions.
+const BINDINGS: [(Chord, Action); 71] = [
+    (Chord::key(VirtualKeyCode::F11), Action::ToggleFullscreen),
+    (Chord::key(VirtualKeyCode::Escape), Action::Escape),
+    (Chord::key(VirtualKeyCode::Return), Action::ToggleConsole),
+    (Chord::key(VirtualKeyCode::F1), Action::ToggleHelp),
+    (Chord::key(VirtualKeyCode::C), Action::ToggleConfig),
+    (
+        Chord::position(VirtualKeyCode::Grave),
+        Action::ToggleVariables,
+    ),
+    (Chord::key(VirtualKeyCode::F2), Action::ToggleSpectrum),
+    (Chord::ctrl(VirtualKeyCode::S), Action::SaveConfig),
+    (Chord::ctrl(VirtualKeyCode::T), Action::ToggleTimelapse),
+    (Chord::key(VirtualKeyCode::F12), Action::Screenshot),
+    (Chord::ctrl(VirtualKeyCode::G), Action::RecordClip),
+    (Chord::key(VirtualKeyCode::F8), Action::ToggleInspect),
+    (Chord::position(VirtualKeyCode::Period), Action::StepFrame),
+    (Chord::key(VirtualKeyCode::R), Action::ToggleAudioResponsive),
+    (Chord::key(VirtualKeyCode::I), Action::ToggleCaptureMode),
+    (
+        Chord::key(VirtualKeyCode::Space),
+        Action::ToggleKaleidoscope,
+    ),
+    (
+        Chord::position(VirtualKeyCode::LBracket),
+        Action::FewerKaleidoscopeSegments,
+    ),
+    (
+        Chord::position(VirtualKeyCode::RBracket),
+        Action::MoreKaleidoscopeSegments,
+    ),
+    (Chord::key(VirtualKeyCode::J), Action::ToggleJello),
+    (Chord::key(VirtualKeyCode::P), Action::ToggleParticles),
+    (Chord::key(VirtualKeyCode::H), Action::ToggleHideStationary),
+    (
+        Chord::key(VirtualKeyCode::Capital),
+        Action::ToggleAlternateColors,
+    ),
+    (Chord::key(VirtualKeyCode::D), Action::Toggle3dParticles),
+    (Chord::key(VirtualKeyCode::Back), Action::ResetParticles),
+    (Chord::key(VirtualKeyCode::O), Action::ToggleSurfaceEmission),
+    (Chord::key(VirtualKeyCode::Tab), Action::NextColorScheme),
+    (
+        Chord::shift(VirtualKeyCode::Tab),
+        Action::PreviousColorScheme,
+    ),
+    (
+        Chord::position(VirtualKeyCode::Key0),
+        Action::SelectFractal(0),
+    ),
+    (
+        Chord::position(VirtualKeyCode::Key1),
+        Action::SelectFractal(1),
+    ),
+    (
+        Chord::position(VirtualKeyCode::Key2),
+        Action::SelectFractal(2),
+    ),
+    (
+        Chord::position(VirtualKeyCode::Key3),
+        Action::SelectFractal(3),
+    ),
+    (
+        Chord::position(VirtualKeyCode::Key4),
+        Action::SelectFractal(4),
+    ),
+    (
+        Chord::position(VirtualKeyCode::Key5),
+        Action::SelectFractal(5),
+    ),
+    (
+        Chord::position(VirtualKeyCode::Key6),
+        Action::SelectFractal(6),
+    ),
+    (
+        Chord::position(VirtualKeyCode::Key7),
+        Action::SelectFractal(7),
+    ),
+    (
+        Chord::position(VirtualKeyCode::Key8),
+        Action::SelectFractal(8),
+    ),
+    (
+        Chord::position(VirtualKeyCode::Key9),
+        Action::SelectFractal(9),
+    ),
+    (
+        Chord::position(VirtualKeyCode::Minus),
+        Action::SelectFractal(10),
+    ),
+    (
+        Chord::position(VirtualKeyCode::Equals),
+        Action::SelectFractal(11),
+    ),
+    (Chord::key(VirtualKeyCode::M), Action::NextColoringMode),
+    (Chord::key(VirtualKeyCode::N), Action::NextPhysicsMode),
+    (Chord::key(VirtualKeyCode::L), Action::CycleDisplayLayout),
+    (Chord::key(VirtualKeyCode::V), Action::ToggleFreeFly),
+    (
+        Chord::shift_position(VirtualKeyCode::Key1),
+        Action::PlayCameraPath(0),
+    ),
+    (
+        Chord::shift_position(VirtualKeyCode::Key2),
+        Action::PlayCameraPath(1),
+    ),
+    (
+        Chord::shift_position(VirtualKeyCode::Key3),
+        Action::PlayCameraPath(2),
+    ),
+    (
+        Chord::shift_position(VirtualKeyCode::Key4),
+        Action::PlayCameraPath(3),
+    ),
+    (
+        Chord::shift_position(VirtualKeyCode::Key5),
+        Action::PlayCameraPath(4),
+    ),
+    (
+        Chord::shift_position(VirtualKeyCode::Key6),
+        Action::PlayCameraPath(5),
+    ),
+    (
+        Chord::shift_position(VirtualKeyCode::Key7),
+        Action::PlayCameraPath(6),
+    ),
+    (
+        Chord::shift_position(VirtualKeyCode::Key8),
+        Action::PlayCameraPath(7),
+    ),
+    (
+        Chord::shift_position(VirtualKeyCode::Key9),
+        Action::PlayCameraPath(8),
+    ),
+    (Chord::key(VirtualKeyCode::B), Action::ToggleBlackout),
+    (Chord::key(VirtualKeyCode::F), Action::ToggleFreeze),
+    (
+        Chord::ctrl_position(VirtualKeyCode::Key1),
+        Action::SaveSnapshot(0),
+    ),
+    (
+        Chord::ctrl_position(VirtualKeyCode::Key2),
+        Action::SaveSnapshot(1),
+    ),
+    (
+        Chord::ctrl_position(VirtualKeyCode::Key3),
+        Action::SaveSnapshot(2),
+    ),
+    (
+        Chord::ctrl_position(VirtualKeyCode::Key4),
+        Action::SaveSnapshot(3),
+    ),
+    (
+        Chord::ctrl_position(VirtualKeyCode::Key5),
+        Action::SaveSnapshot(4),
+    ),
+    (
+        Chord::ctrl_position(VirtualKeyCode::Key6),
+        Action::SaveSnapshot(5),
+    ),
+    (
+        Chord::ctrl_position(VirtualKeyCode::Key7),
+        Action::SaveSnapshot(6),
+    ),
+    (
+        Chord::ctrl_position(VirtualKeyCode::Key8),
+        Action::SaveSnapshot(7),
+    ),
+    (
+        Chord::ctrl_position(VirtualKeyCode::Key9),
+        Action::SaveSnapshot(8),
+    ),
+    (
+        Chord::alt_position(VirtualKeyCode::Key1),
+        Action::RecallSnapshot(0),
+    ),
+    (
+        Chord::alt_position(VirtualKeyCode::Key2),
+        Action::RecallSnapshot(1),
+    ),
+    (
+        Chord::alt_position(VirtualKeyCode::Key3),
+        Action::RecallSnapshot(2),
+    ),
+    (
+        Chord::alt_position(VirtualKeyCode::Key4),
+        Action::RecallSnapshot(3),
+    ),
+    (
+        Chord::alt_position(VirtualKeyCode::Key5),
+        Action::RecallSnapshot(4),
+    ),
+    (
+        Chord::alt_position(VirtualKeyCode::Key6),
+        Action::RecallSnapshot(5),
+    ),
+    (
+        Chord::alt_position(VirtualKeyCode::Key7),
+        Action::RecallSnapshot(6),
+    ),
+    (
+        Chord::alt_position(VirtualKeyCode::Key8),
+        Action::RecallSnapshot(7),
+    ),
+    (
+        Chord::alt_position(VirtualKeyCode::Key9),
+        Action::RecallSnapshot(8),
+    ),
+    (Chord::ctrl(VirtualKeyCode::P), Action::PlayPauseShow),
+    (Chord::ctrl(VirtualKeyCode::N), Action::NextScene),
+];
+
+// The bindings configured at launch, replacing the defaults once set.
+static CONFIGURED_BINDINGS: OnceLock<Vec<(Chord, Action)>> = OnceLock::new();
+
+// The chords currently bound to actions.
+pub fn bindings() -> &'static [(Chord, Action)] {
+    CONFIGURED_BINDINGS.get().map_or(&BINDINGS, Vec::as_slice)
+}
+
+// Replace the default chords of each given action, where an empty list leaves the action unbound.
+// A chord given for one action is removed from any other action it was bound to by default.
+// Only the first configuration applies, since bindings are fixed once the app has launched
+pub fn configure_bindings(overrides: &[(Action, Vec<Chord>)]) {
+    let mut bindings: Vec<(Chord, Action)> = Vec::with_capacity(BINDINGS.len());
+    for (chord, action) in BINDINGS {
+        match overrides
+            .iter()
+            .find(|(overridden, _)| *overridden == action)
+        {
+            // Place the configured chords where the action's first default chord was, keeping the Help window's order.
+            Some((_, chords)) => {
+                if !bindings.iter().any(|(_, bound)| *bound == action) {
+                    bindings.extend(chords.iter().map(|chord| (*chord, action)));
+                }
+            }
+            None => {
+                let stolen = overrides.iter().any(|(_, chords)| chords.contains(&chord));
+                if stolen {
+                    log!(
+                        "`{chord}` is rebound, so no longer performs `{}`",
+                        action.config_name()
+                    );
+                } else {
+                    bindings.push((chord, action));
+                }
+            }
+        }
+    }
+    if CONFIGURED_BINDINGS.set(bindings).is_err() {
+        log!("Keybindings were already configured, ignoring the new configuration");
+    }
+}
+
+// Controls of the mouse, which are not bound to actions but are listed alongside them.
+const MOUSE_CONTROLS: [(&str, &str); 4] = [
+    ("MOUSE-BTTN", "Holding the primary or secondary mouse button applies a repulsive or attractive force, respectively, at the cursor's position"),
+    ("MOUSE-SCRL", "Scrolling up or down changes the strength of the cursor's applied force"),
+    ("CTRL+SCRL", "Zoom in or out by changing the vertical field-of-view"),
+    ("ALT+SCRL", "Raise or lower the intensity macro, calming or hyping the entire visual"),
+];
+
+// A row of the Help window, listing every chord which performs the same described action.
+pub struct HelpEntry {
+    pub keys: String,
+    pub name: String, // The names of the actions in the `[keybindings]` table of the config file
+    pub description: &'static str,
+}
+
+// Build the rows of the Help window for each category from the current bindings.
+// Actions not available in this build are skipped, and categories without any rows are omitted
+pub fn help_entries() -> Vec<(Category, Vec<HelpEntry>)> {
+    CATEGORIES
+        .into_iter()
+        .filter_map(|category| {
+            let mut entries: Vec<HelpEntry> = Vec::new();
+            for (chord, action) in bindings() {
+                if action.category() != category || !action.available() {
+                    continue;
+                }
+
+                // Merge chords sharing a description, such as the fractal selection keys.
+                let description = action.description();
+                let name = action.config_name();
+                if let Some(entry) = entries.iter_mut().find(|e| e.description == description) {
+                    entry.keys = format!("{}, {chord}", entry.keys);
+                    if !entry.name.split(", ").any(|n| n == name) {
+                        entry.name = format!("{}, {name}", entry.name);
+                    }
+                } else {
+                    entries.push(HelpEntry {
+                        keys: chord.to_string(),
+                        name,
+                        description,
+                    });
+                }
+            }
+            if category == Category::Visuals {
+                entries.extend(MOUSE_CONTROLS.map(|(keys, description)| HelpEntry {
+                    keys: String::from(keys),
+                    name: String::from("mouse"),
+                    description,
+                }));
+            }
+
+            (!entries.is_empty()).then_some((category, entries))
+        })
+        .collect()
+}
+
+// Find the action bound to a key pressed with the given modifiers.
+// A chord with the exact modifiers takes precedence, otherwise the modifiers are ignored so that
+// unmodified shortcuts still work while, for example, Shift is held
+pub fn action_for_key(press: KeyPress, modifiers: ModifiersState) -> Option<Action> {
+    // Only these modifiers distinguish chords.
+    let modifiers =
+        modifiers & (ModifiersState::CTRL | ModifiersState::SHIFT | ModifiersState::ALT);
+    let find = |modifiers: ModifiersState| {
+        bindings()
+            .iter()
+            .find(|(binding, _)| binding.modifiers == modifiers && binding.key.matches(press))
+            .map(|(_, action)| *action)
+    };
+
+    find(modifiers).or_else(|| {
+        // Avoid triggering plain shortcuts from chords intended for other applications.
+        if modifiers.intersects(ModifiersState::CTRL | ModifiersState::ALT) {
+            None
+        } else {
+            find(ModifiersState::empty())
+        }
+    })
+}
@@ -0,0 +1,147 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::fs::File;
+use std::io::BufReader;
+
+use crate::app_config::LogoIntro;
+use crate::my_math::helpers::halton;
+use crate::my_math::Vector2;
+
+// The largest fraction of the window's height or width which the logo may cover.
+const LOGO_EXTENT: f32 = 0.8;
+
+// Pixels with at least this alpha are part of the logo.
+const MASK_ALPHA: u8 = 128;
+
+// Strength and duration, in seconds, of the outward burst releasing the particles from the logo.
+const BURST_STRENGTH: f32 = 1.5;
+const BURST_DURATION: f32 = 0.3;
+
+// Arrange the given number of particles over the opaque pixels of a PNG logo, in 2D screen coordinates.
+// The logo is centered and keeps its shape in a window of the given aspect ratio
+#[allow(clippy::cast_precision_loss)]
+pub fn logo_positions(path: &str, count: usize, aspect_ratio: f32) -> anyhow::Result<Vec<Vector2>> {
+    let image = ico::IconImage::read_png(BufReader::new(File::open(path)?))?;
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let mask: Vec<usize> = image
+        .rgba_data()
+        .chunks_exact(4)
+        .enumerate()
+        .filter_map(|(i, pixel)| (pixel[3] >= MASK_ALPHA).then_some(i))
+        .collect();
+    if mask.is_empty() {
+        anyhow::bail!("The logo has no opaque pixels to arrange particles over");
+    }
+
+    // Find the half-extents of the logo, where screen coordinates are stretched horizontally by the aspect ratio.
+    let image_aspect = width as f32 / height as f32;
+    let (mut half_width, mut half_height) =
+        (LOGO_EXTENT * image_aspect / aspect_ratio, LOGO_EXTENT);
+    if half_width > LOGO_EXTENT {
+        half_height *= LOGO_EXTENT / half_width;
+        half_width = LOGO_EXTENT;
+    }
+
+    // Spread the particles evenly over the mask, scattering each within its pixel by a low-discrepancy sequence.
+    Ok((0..count)
+        .map(|i| {
+            let pixel = mask[i * mask.len() / count];
+            #[allow(clippy::cast_possible_truncation)]
+            let offset = i as u32;
+            let x = ((pixel % width) as f32 + halton(offset, 2)) / width as f32;
+            let y = ((pixel / width) as f32 + halton(offset, 3)) / height as f32;
+            Vector2::new(half_width * (2. * x - 1.), half_height * (2. * y - 1.))
+        })
+        .collect())
+}
+
+// The stages of the intro, beginning when the app launches.
+#[derive(Clone, Copy, Default)]
+enum Phase {
+    Holding {
+        elapsed: f32,
+    },
+    Ramping {
+        elapsed: f32,
+    },
+    #[default]
+    Finished,
+}
+
+// Startup state machine which holds the particles as a logo, bursts them, then ramps in the audio reactivity.
+#[derive(Default)]
+pub struct IntroSequence {
+    phase: Phase,
+    hold: f32,
+    ramp: f32,
+}
+
+impl IntroSequence {
+    // Begin holding the logo, which the particles must already be arranged as.
+    pub fn start(intro: &LogoIntro) -> Self {
+        Self {
+            phase: Phase::Holding { elapsed: 0. },
+            hold: intro.hold,
+            ramp: intro.ramp,
+        }
+    }
+
+    // Advance the intro by the seconds elapsed since the last frame.
+    pub fn update(&mut self, delta_time: f32) {
+        self.phase = match self.phase {
+            Phase::Holding { elapsed } if elapsed + delta_time < self.hold => Phase::Holding {
+                elapsed: elapsed + delta_time,
+            },
+            Phase::Holding { .. } => {
+                log!("Releasing the particles from the intro logo");
+                Phase::Ramping { elapsed: 0. }
+            }
+            Phase::Ramping { elapsed } if elapsed + delta_time < self.ramp.max(BURST_DURATION) => {
+                Phase::Ramping {
+                    elapsed: elapsed + delta_time,
+                }
+            }
+            Phase::Ramping { .. } | Phase::Finished => Phase::Finished,
+        };
+    }
+
+    // Whether the particles are still held in place as the logo.
+    pub fn holding(&self) -> bool {
+        matches!(self.phase, Phase::Holding { .. })
+    }
+
+    // Fraction of the audio's influence over the particles, ramping from zero to one after the burst.
+    pub fn audio_weight(&self) -> f32 {
+        match self.phase {
+            Phase::Holding { .. } => 0.,
+            Phase::Ramping { elapsed } if self.ramp > 0. => (elapsed / self.ramp).min(1.),
+            Phase::Ramping { .. } | Phase::Finished => 1.,
+        }
+    }
+
+    // Strength of the outward burst from the center of the screen, while it lasts.
+    pub fn burst(&self) -> Option<f32> {
+        match self.phase {
+            Phase::Ramping { elapsed } if elapsed < BURST_DURATION => {
+                Some(BURST_STRENGTH * (1. - elapsed / BURST_DURATION))
+            }
+            _ => None,
+        }
+    }
+}
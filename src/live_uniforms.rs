@@ -0,0 +1,90 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::app_config;
+use crate::engine::LiveUniforms;
+
+// How often the config file is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Pack the named constants into the uniform buffer's layout, in order of their names.
+pub fn pack(uniforms: &BTreeMap<String, f32>) -> LiveUniforms {
+    let mut packed = LiveUniforms::default();
+    for (i, value) in uniforms.values().enumerate() {
+        packed[i / 4][i % 4] = *value;
+    }
+    packed
+}
+
+// Watches the config file, reading back its live uniforms whenever it is saved.
+// Only the live uniforms are applied, the rest of the config still requires a restart
+pub struct Watcher {
+    path: String,
+    modified: Option<SystemTime>,
+    last_poll: Instant,
+    uniforms: BTreeMap<String, f32>,
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl Watcher {
+    pub fn new(path: &str, uniforms: BTreeMap<String, f32>) -> Self {
+        Self {
+            path: String::from(path),
+            modified: modified_time(path),
+            last_poll: Instant::now(),
+            uniforms,
+        }
+    }
+
+    // Check whether the file has changed, returning its live uniforms if they differ from those applied.
+    pub fn poll(&mut self) -> Option<LiveUniforms> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return None;
+        }
+        self.last_poll = Instant::now();
+
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        // An editor may save a partially written file, which is read again on its next change.
+        let uniforms = match app_config::parse_file(&self.path) {
+            Ok(config) => config.live_uniforms,
+            Err(e) => {
+                log!("Live uniforms were not reloaded, the config file is invalid: {e}");
+                return None;
+            }
+        };
+        if uniforms == self.uniforms {
+            return None;
+        }
+        for (i, (name, value)) in uniforms.iter().enumerate() {
+            log!("Live uniform {i} `{name}` = {value}");
+        }
+        self.uniforms = uniforms;
+        Some(pack(&self.uniforms))
+    }
+}
@@ -18,31 +18,72 @@
 
 // Ensure Windows release builds are not console apps.
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use app_overlay::AppOverlay;
+use app_overlay::{AppOverlay, ColorSchemeEdit, TourDemonstration};
 #[cfg(all(not(debug_assertions), target_os = "windows"))]
 use companion_console::ConsoleState;
+use input::Action;
 use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::event::{
-    ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
+    DeviceEvent, ElementState, Event, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta,
+    WindowEvent,
 };
 use winit::event_loop::{ControlFlow, EventLoop};
-use winit::window::Fullscreen;
+use winit::window::{CursorGrabMode, Fullscreen};
 
 use engine::core::{RecreateSwapchainResult, WindowSurface};
 use engine::{DrawData, Engine};
 
+// Declared first so that its logging macro is available to every other module.
+#[macro_use]
+mod crash;
+
+mod analysis_only;
 mod app_config;
 mod app_overlay;
+mod attract_mode;
 mod audio;
+mod auto_cycle;
+mod balance;
+mod camera_animation;
+mod cli;
+mod clip;
+mod config_editor;
+mod crossfade;
+mod cvars;
+mod doctor;
 mod engine;
+mod events;
+mod export;
+mod fractals;
+mod free_fly;
+mod input;
+mod intro;
+mod live_uniforms;
+mod metrics;
+mod midi;
 mod my_math;
+mod parameters;
+mod performer;
+mod persistence;
+mod player_sync;
+mod poster;
+mod refinement;
+mod scenes;
+mod screenshot;
+mod sidechain;
+mod snapshots;
 mod space_filling_curves;
+mod timelapse;
+mod virtual_camera;
 
-use app_config::{AppConfig, Scheme};
-use my_math::helpers::{interpolate_floats, interpolate_vec3};
-use my_math::{Quaternion, Vector3, Vector4};
+use app_config::{
+    AppConfig, ColorManagement, ColoringMode, DisplayLayout, ParticleBlending, ParticleBloom,
+    ParticleContainment, PhysicsMode, Scheme, MAX_DOF_FOCUS_DISTANCE, MIN_DOF_FOCUS_DISTANCE,
+};
+use my_math::helpers::{halton, interpolate_floats, interpolate_vec3};
+use my_math::{Quaternion, Vector2, Vector3, Vector4};
 
 // App constants
 const BASE_ANGULAR_VELOCITY: f32 = 0.02;
@@ -50,21 +91,38 @@ const CURSOR_LOOSE_STRENGTH: f32 = 0.75;
 const CURSOR_FIXED_STRENGTH: f32 = 1.75;
 const KALEIDOSCOPE_SPEED: f32 = 0.275;
 const SCROLL_SENSITIVITY: f32 = 0.15;
+const INTENSITY_SCROLL_STEP: f32 = 0.05;
+const INSPECT_STEP_DELTA_TIME: f32 = 1. / 60.;
+
+// The OSC controls of the performer's emergency switches, which messages address by name.
+const PERFORMER_CONTROLS: [&str; 2] = [crossfade::BLACKOUT_ADDRESS, crossfade::FREEZE_ADDRESS];
+const PARTICLE_CAMERA_ORBIT: Vector3 = Vector3::new(0., 0., 1.75); // Keep in sync with the depth scale of `ray_march.frag`.
+const KICK_ECHO_COUNT: usize = 4; // Keep in sync with `particles.vert`.
+const KICK_ECHO_LIFETIME: f32 = 3.;
+const TAA_HISTORY_WEIGHT: f32 = 0.9;
+const TAA_JITTER_SAMPLES: u32 = 8;
+const WINDOW_VELOCITY_SMOOTHING: f32 = 10.;
+const WINDOW_INERTIA_SCALE: f32 = 1.;
+const MAX_WINDOW_INERTIA: f32 = 40.;
 
 struct LocalAudioState {
     pub play_time: f32,
     pub latest_volume: f32,
 
-    // Particle forces to apply
+    // Particle forces to apply, as many of each band as the audio source sends
     pub big_boomer: Vector4,
-    pub curl_attractors: [Vector4; 2],
-    pub attractors: [Vector4; 2],
+    pub extra_big_boomers: Vec<Vector4>,
+    pub curl_attractors: Vec<Vector4>,
+    pub attractors: Vec<Vector4>,
 
     // Target vectors used for fractal coloring
     pub reactive_bass: Vector3,
     pub reactive_mids: Vector3,
     pub reactive_high: Vector3,
 
+    // The latest musical key detected by the audio thread
+    pub key: Option<audio::MusicalKey>,
+
     // Local values used for interpolating values between updates from audio thread
     pub local_volume: f32,
     pub local_angular_velocity: Vector4,
@@ -74,6 +132,27 @@ struct LocalAudioState {
     pub local_smooth_bass: Vector3,
     pub local_smooth_mids: Vector3,
     pub local_smooth_high: Vector3,
+
+    // Ring buffer of recent kicks, where the next kick replaces the oldest
+    pub kick_echoes: [KickEcho; KICK_ECHO_COUNT],
+    pub next_kick_echo: usize,
+
+    // The automatic balance of particles against the fractal
+    pub balance: balance::Balancer,
+
+    // The sidechain-style pump of the fractal after each kick
+    pub pump: sidechain::PumpEnvelope,
+
+    // The kicks and energy counted towards automatically switching color schemes
+    pub cycler: auto_cycle::SchemeCycler,
+}
+
+// A recent kick, shown as a ring expanding from the position of the big boomer at the time.
+#[derive(Clone, Copy, Default)]
+struct KickEcho {
+    pub position: Vector3,
+    pub strength: f32,
+    pub age: f32,
 }
 
 // Game-state enums
@@ -97,6 +176,15 @@ enum ParticleTension {
 pub struct RuntimeConstants {
     pub distance_estimator_id: u32,
     pub render_particles: bool,
+    pub coloring_mode: ColoringMode,
+    pub physics_mode: PhysicsMode,
+    pub particle_containment: ParticleContainment,
+    pub surface_emission: f32,
+    pub hue_rotation: f32,       // Radians to rotate the hue of the visuals by
+    pub point_scale: f32,        // Multiplies the configured point size
+    pub fractal_brightness: f32, // Multiplies the fractal's color, such as to duck it on kicks
+    pub color_management: ColorManagement,
+    pub fractal_tunables: fractals::Tunables, // The tunables of the selected fractal
 }
 
 #[allow(clippy::struct_excessive_bools)]
@@ -109,11 +197,41 @@ struct GameState {
     pub cursor_force_mult: f32,
     pub kaleidoscope: f32,
     pub kaleidoscope_dir: KaleidoscopeDirection,
+    pub kaleidoscope_rotation: f32, // Radians the kaleidoscope's folded view has turned by
     pub alternate_colors: AlternateColors,
     pub particles_are_3d: bool,
     pub color_scheme_index: usize,
     pub audio_responsive: bool,
     pub runtime_constants: RuntimeConstants,
+    pub inspect: InspectState,
+    pub focus_distance: f32,
+    pub previous_camera_quaternion: Quaternion,
+    pub taa_frame: u32,
+    pub refinement: refinement::StaticSceneDetector,
+    pub performer: performer::PerformerControls,
+    pub keyframe_time: f32,
+    pub follow_color_keyframes: bool,
+    pub window_center: Option<Vector2>,
+    pub window_velocity: Vector2,
+    pub window_inertia: Vector2,
+    pub camera_animation: camera_animation::CameraAnimation,
+    pub free_fly: Option<free_fly::FreeFly>, // Flies the camera in place of orbiting, while enabled
+    pub intro: intro::IntroSequence,
+    pub scheme_transition: Option<SchemeTransition>,
+}
+
+// A blend from the previously displayed colors to the selected color scheme.
+#[derive(Clone, Copy)]
+struct SchemeTransition {
+    pub from: Scheme,
+    pub elapsed: f32,
+}
+
+// State of the pause-and-inspect debug mode.
+#[derive(Default)]
+struct InspectState {
+    pub paused: bool,
+    pub step_requested: bool,
 }
 
 #[allow(clippy::struct_excessive_bools)]
@@ -122,21 +240,46 @@ struct WindowState {
     pub recreate_swapchain: bool,
     pub is_fullscreen: bool,
     pub is_focused: bool,
+    pub cursor_grabbed: bool,
     pub last_frame_time: Instant,
     pub last_mouse_movement: Instant,
+    pub modifiers: ModifiersState,
+    pub position: Option<PhysicalPosition<i32>>,
+    pub size: PhysicalSize<u32>,
 }
 
 // A helper for managing the audio input stream and the resulting audio-based state.
 struct AudioManager {
     pub receiver: crossbeam_channel::Receiver<audio::State>,
-    pub capture_stream: cpal::Stream,
+    pub source: Box<dyn audio::AudioSource>,
+    pub source_kind: audio::SourceKind,
+    pub device: Option<audio::AudioDevice>, // Captured by loopback sources in place of the default audio out
+    pub stems: Vec<audio::Stem>, // Analyzed separately in place of the single source, when any are configured
+    pub file_input: Option<audio::FileInput>, // Played back in place of any source
     pub state: LocalAudioState,
+    pub analysis_config: audio::SharedAnalysisConfig,
+
+    // The latest audio state received while the simulation was paused.
+    pub held_state: Option<audio::State>,
+
+    // The warning about the latest status of the source, if any.
+    pub warning: Option<&'static str>,
+
+    // When to recreate a lost source, and when it was last recreated for being lost.
+    pub reconnect_at: Option<Instant>,
+    pub last_reconnect: Option<Instant>,
 }
 
 struct FractalSugar {
     color_schemes: Vec<Scheme>,
     color_scheme_names: Vec<String>,
 
+    // The configuration applied at launch, and the file it was read from.
+    // In safe mode the file was ignored, so it is never overwritten
+    app_config: AppConfig,
+    config_path: String,
+    safe_mode: bool,
+
     app_overlay: AppOverlay,
     engine: Engine,
     event_loop: Option<EventLoop<()>>,
@@ -146,12 +289,47 @@ struct FractalSugar {
 
     audio: AudioManager,
     game_state: GameState,
+    events: events::EventBus,
+    metrics: metrics::MetricsCollector,
+    virtual_camera: Option<virtual_camera::VirtualCamera>,
+    timelapse: Option<timelapse::Timelapse>,
+    screenshots: screenshot::Screenshots,
+    clip: Option<clip::ClipRecorder>,
+    recorder: Option<audio::SessionRecorder>,
+    live_uniforms: Option<live_uniforms::Watcher>,
+    crossfade_fader: Option<crossfade::OscFader>,
+    intensity_fader: Option<crossfade::OscFader>,
+    performer_controls: Option<crossfade::OscControls>, // A controller's blackout and freeze switches
+    midi: Option<midi::MidiController>,
+    snapshots: snapshots::Snapshots,
+    attract_mode: attract_mode::AttractMode,
+    show: scenes::Show,
+    keyframe_clock: Option<player_sync::PlayerClock>,
+    poster: Option<poster::Poster>, // Renders a single frame in place of the interactive session
+    export: Option<export::Export>, // Renders every frame to files in place of the interactive session
     window_state: WindowState,
 }
 
 fn main() {
+    // Suggest safe mode when initialization fails, such as when a device rejects some feature.
+    // A hook is used because release builds abort on panic rather than unwinding.
+    // Every panic, during initialization or after, also writes a crash report to be shown on the next launch
+    let default_hook: std::sync::Arc<_> = std::panic::take_hook().into();
+    let init_hook = default_hook.clone();
+    std::panic::set_hook(Box::new(move |info| {
+        crash::write_report(info);
+        init_hook(info);
+        eprintln!(
+            "fractal_sugar failed to start, try launching with `--safe-mode` to diagnose the issue"
+        );
+    }));
+
     // Initialize app instance
     let fractal_sugar = FractalSugar::new();
+    std::panic::set_hook(Box::new(move |info| {
+        crash::write_report(info);
+        default_hook(info);
+    }));
 
     // Endless app-loop
     fractal_sugar.run()
@@ -161,29 +339,94 @@ impl FractalSugar {
     pub fn new() -> Self {
         // Windows-specific console clean-up. Important that this occurs before print statements for debugging
         #[cfg(all(not(debug_assertions), target_os = "windows"))]
-        let console_state = ConsoleState::new(false);
+        let mut console_state = ConsoleState::new(false);
 
         // Fetch command-line arguments
-        let args: Vec<String> = std::env::args().collect();
-        assert!(args.len() <= 2, "fractal_sugar accepts at most one argument, the TOML app configuration file. The default path is 'app_config.toml'");
+        let args = match cli::parse(std::env::args().skip(1)) {
+            Ok(args) => args,
+            Err(e) => {
+                log!("{e}\n\n{}", cli::USAGE);
+                std::process::exit(2);
+            }
+        };
+
+        // Only diagnose the environment the app depends on, exiting with failure if any check failed
+        if args.doctor {
+            #[cfg(all(not(debug_assertions), target_os = "windows"))]
+            if let Some(console_state) = &mut console_state {
+                console_state.show();
+            }
+            let passed = doctor::run(&args.config_path);
+            std::process::exit(i32::from(!passed));
+        }
+
+        // Only report the displays which could be driven directly
+        if args.list_displays {
+            #[cfg(all(not(debug_assertions), target_os = "windows"))]
+            if let Some(console_state) = &mut console_state {
+                console_state.show();
+            }
+            engine::display::print_displays();
+            std::process::exit(0);
+        }
+
+        // Without a window, only feed analyzed audio to another process or machine
+        if let Some(target) = &args.analysis_only {
+            #[cfg(all(not(debug_assertions), target_os = "windows"))]
+            if let Some(console_state) = &mut console_state {
+                console_state.show();
+            }
+            if let Err(e) = analysis_only::run(args.audio_source, target, &args.config_path) {
+                log!("Analysis-only mode failed: {e:?}");
+                std::process::exit(1);
+            }
+            std::process::exit(0);
+        }
+
+        // Only edit the config file, without initializing Vulkan
+        if args.edit_config {
+            if let Err(e) = config_editor::run(&args.config_path) {
+                log!("{e:?}");
+                std::process::exit(1);
+            }
+            std::process::exit(0);
+        }
 
         // Determine the runtime app configuration
-        let app_config = {
-            let filepath = match args.get(1) {
-                Some(path) => path.as_str(),
-                None => "app_config.toml",
-            };
+        let app_config = if args.safe_mode {
+            log!(
+                "Launching in safe mode, ignoring the config file `{}`",
+                args.config_path
+            );
+            AppConfig::safe_mode()
+        } else {
+            let filepath = args.config_path.as_str();
             match app_config::parse_file(filepath) {
                 Ok(config) => config,
                 Err(e) => {
-                    println!("Failed to process custom color schemes file `{filepath}`: {e:?}");
+                    log!("Failed to process custom color schemes file `{filepath}`: {e:?}");
                     AppConfig::default()
                 }
             }
         };
 
-        // Load icon from file resources
-        let icon = {
+        // Apply any configured keybindings before the Help window lists them.
+        input::configure_bindings(&app_config.keybindings);
+
+        let audio_source = args
+            .audio_source
+            .unwrap_or_else(|| app_config.capture_mode.source_kind());
+        crash::set_config_summary(format!(
+            "file `{}`, safe mode {}, {} particles, {}x MSAA, audio source {}",
+            args.config_path,
+            args.safe_mode,
+            app_config.particle_count,
+            app_config.msaa_samples,
+            audio_source.name(),
+        ));
+
+        // Load icon from file resources, which the startup splash also shows as its logo
+        let icon_image = {
             let icon_bytes = std::include_bytes!("../res/fractal_sugar.ico");
             let ico_reader = std::io::Cursor::<&[u8]>::new(icon_bytes);
             let ico_list = ico::IconDir::read(ico_reader).unwrap();
@@ -191,30 +434,136 @@ impl FractalSugar {
                 .entries()
                 .get(0)
                 .expect("Icon doesn't have any layers");
-            let image = ico.decode().unwrap();
-
-            match winit::window::Icon::from_rgba(
-                image.rgba_data().to_vec(),
-                image.width(),
-                image.height(),
-            ) {
-                Ok(icon) => Some(icon),
-                Err(e) => {
-                    println!("Failed to parse icon: {e:?}");
-                    None
-                }
+            ico.decode().unwrap()
+        };
+        let icon = match winit::window::Icon::from_rgba(
+            icon_image.rgba_data().to_vec(),
+            icon_image.width(),
+            icon_image.height(),
+        ) {
+            Ok(icon) => Some(icon),
+            Err(e) => {
+                log!("Failed to parse icon: {e:?}");
+                None
             }
         };
 
+        // Begin collecting metrics, optionally serving them to external tools.
+        let metrics = metrics::MetricsCollector::new();
+        if let Some(port) = args.metrics_port {
+            if let Err(e) = metrics::spawn_json_endpoint(port, metrics.shared().clone()) {
+                log!("Failed to serve metrics on port {port}: {e:?}");
+            }
+        }
+
         // Create global event loop to manage window events
         let event_loop = EventLoop::new();
 
+        // Create the audio-analysis parameters shared between the overlay and the audio thread.
+        let analysis_config = audio::new_shared_analysis_config(app_config.launch_analysis());
+
+        // A poster or export analyzes all of its audio before the window is created, so that the window never waits on it.
+        let poster = args.poster.map(|options| {
+            #[cfg(all(not(debug_assertions), target_os = "windows"))]
+            if let Some(console_state) = &mut console_state {
+                console_state.show();
+            }
+            match poster::Poster::new(options, audio_source, analysis_config.clone()) {
+                Ok(poster) => poster,
+                Err(e) => {
+                    log!("Failed to render the poster: {e:?}");
+                    std::process::exit(1);
+                }
+            }
+        });
+        let export = args.export.map(|options| {
+            #[cfg(all(not(debug_assertions), target_os = "windows"))]
+            if let Some(console_state) = &mut console_state {
+                console_state.show();
+            }
+            match export::Export::new(options, audio_source, analysis_config.clone()) {
+                Ok(export) => export,
+                Err(e) => {
+                    log!("Failed to render the export: {e:?}");
+                    std::process::exit(1);
+                }
+            }
+        });
+
         // Initialize game state so that the engine can leverage default values.
-        let game_state = GameState::default();
+        // Safe mode does not capture audio until the user toggles audio-responsiveness
+        let mut game_state = GameState {
+            audio_responsive: !args.safe_mode,
+            runtime_constants: RuntimeConstants {
+                coloring_mode: app_config.coloring_mode,
+                physics_mode: app_config.physics_mode,
+                particle_containment: app_config.particle_containment,
+                surface_emission: if app_config.surface_emission {
+                    app_config.surface_emission_rate
+                } else {
+                    0.
+                },
+                color_management: app_config.color_management,
+                render_particles: app_config.display_layout != DisplayLayout::FractalOnly,
+                ..RuntimeConstants::default()
+            },
+            ..GameState::default()
+        };
+        game_state.runtime_constants.fractal_tunables = app_config.fractal_tunables
+            [game_state.runtime_constants.distance_estimator_id as usize];
 
         // Use Engine helper to initialize Vulkan instance
-        let engine =
-            engine::Engine::new(&event_loop, &app_config, game_state.runtime_constants, icon);
+        let mut engine = engine::Engine::new(
+            &event_loop,
+            &app_config,
+            game_state.runtime_constants,
+            icon,
+            poster
+                .as_ref()
+                .map(poster::Poster::size)
+                .or_else(|| export.as_ref().map(export::Export::size)),
+        );
+
+        // Begin with the particles arranged as the intro logo, if one is configured.
+        if let Some(path) = &app_config.logo_intro.image {
+            match intro::logo_positions(
+                path,
+                app_config.particle_count,
+                engine.content_aspect_ratio(),
+            ) {
+                Ok(positions) => {
+                    engine.arrange_particles(&positions);
+                    game_state.intro = intro::IntroSequence::start(&app_config.logo_intro);
+                }
+                Err(e) => log!("Skipping the intro, failed to load the logo `{path}`: {e:?}"),
+            }
+        }
+
+        // Optionally publish the rendered frames as a virtual webcam, reading them back at the camera's resolution.
+        // A poster or export captures its own frames instead.
+        let virtual_camera = if let Some(poster) = &poster {
+            engine.enable_frame_capture(poster.size());
+            None
+        } else if let Some(export) = &export {
+            engine.enable_frame_capture(export.size());
+            None
+        } else if app_config.virtual_camera.enabled {
+            match virtual_camera::VirtualCamera::open(&app_config.virtual_camera) {
+                Ok(camera) => {
+                    engine.enable_frame_capture([
+                        app_config.virtual_camera.width,
+                        app_config.virtual_camera.height,
+                    ]);
+                    Some(camera)
+                }
+                Err(e) => {
+                    log!("Failed to start the virtual camera: {e:?}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
 
         // State vars
         engine.window().focus_window();
@@ -223,45 +572,202 @@ impl FractalSugar {
             resized: false,
             recreate_swapchain: false,
             is_focused: true,
+            cursor_grabbed: false,
             last_frame_time: Instant::now(),
             last_mouse_movement: Instant::now(),
+            modifiers: ModifiersState::empty(),
+            position: engine.window().outer_position().ok(),
+            size: engine.window().inner_size(),
         };
 
-        let config_window = AppOverlay::new(
+        let mut config_window = AppOverlay::new(
             engine.surface().clone(),
             engine.swapchain(),
             engine.queue().clone(),
             &event_loop,
             engine.gui_pass(),
             &app_config,
+            analysis_config.clone(),
+            audio_source,
+            persistence::is_first_launch(),
+            crash::take_pending_report(),
+            &icon_image,
         );
 
-        Self {
-            color_schemes: app_config.color_schemes,
-            color_scheme_names: app_config.color_scheme_names,
+        // Notify any external tools of notable events.
+        let events = events::EventBus::new(&app_config.event_hooks);
+
+        // Watch the config file for edits of the live uniforms, unless it was ignored.
+        let live_uniforms = (!args.safe_mode).then(|| {
+            live_uniforms::Watcher::new(&args.config_path, app_config.live_uniforms.clone())
+        });
+
+        // Listen for an external controller riding the scheme crossfade, if configured.
+        let crossfade_fader = app_config.scheme_crossfade.osc_port.and_then(|port| {
+            match crossfade::OscFader::new(port, crossfade::CROSSFADE_ADDRESS) {
+                Ok(fader) => Some(fader),
+                Err(e) => {
+                    log!("Failed to listen for the crossfade fader on UDP port {port}: {e:?}");
+                    None
+                }
+            }
+        });
+
+        // Likewise for the intensity macro, which is applied to the launch constants immediately.
+        config_window.set_intensity(&mut engine, app_config.intensity_macro.intensity);
+        let intensity_fader = app_config.intensity_macro.osc_port.and_then(|port| {
+            match crossfade::OscFader::new(port, crossfade::INTENSITY_ADDRESS) {
+                Ok(fader) => Some(fader),
+                Err(e) => {
+                    log!("Failed to listen for the intensity fader on UDP port {port}: {e:?}");
+                    None
+                }
+            }
+        });
+
+        // Listen for a controller's emergency switches, in the order of `PERFORMER_CONTROLS`.
+        let performer_controls = app_config.performer_osc_port.and_then(|port| {
+            match crossfade::OscControls::new(port, &PERFORMER_CONTROLS) {
+                Ok(controls) => Some(controls),
+                Err(e) => {
+                    log!("Failed to listen for the performer's controls on UDP port {port}: {e:?}");
+                    None
+                }
+            }
+        });
+
+        // Open a MIDI controller for any mappings, warning of those naming no runtime variable.
+        let midi = if app_config.midi.mappings.is_empty() {
+            None
+        } else {
+            match midi::MidiController::new(app_config.midi.clone()) {
+                Ok(midi) => {
+                    for name in midi.variable_names().filter(|name| {
+                        *name != midi::KALEIDOSCOPE_VARIABLE && !config_window.has_variable(name)
+                    }) {
+                        log!("MIDI mapping names the unknown variable `{name}`, which is ignored");
+                    }
+                    Some(midi)
+                }
+                Err(e) => {
+                    log!("Failed to open a MIDI controller: {e:?}");
+                    None
+                }
+            }
+        };
+
+        // Follow an external player's track position for any color keyframes, if configured.
+        let keyframe_clock = if app_config.color_keyframes.is_empty() {
+            None
+        } else {
+            player_sync::PlayerClock::new(&app_config.keyframe_clock)
+        };
+
+        // Saved looks are neither loaded nor persisted in safe mode.
+        let snapshots = snapshots::Snapshots::new(
+            app_config::Snapshots {
+                persist: app_config.snapshots.persist && !args.safe_mode,
+                ..app_config.snapshots
+            },
+            &app_config.color_scheme_names,
+        );
+
+        let scale_factor = engine.window().scale_factor();
+        // Record the states which drive the visuals, so that a session may be replayed exactly.
+        let recorder =
+            args.record
+                .as_deref()
+                .and_then(|path| match audio::SessionRecorder::create(path) {
+                    Ok(recorder) => Some(recorder),
+                    Err(e) => {
+                        log!(
+                            "Failed to record the audio session `{}`: {e:?}",
+                            path.display()
+                        );
+                        None
+                    }
+                });
+
+        let stems = app_config.stems.clone();
+        let audio_disabled = args.safe_mode || poster.is_some() || export.is_some();
+        let screenshots = screenshot::Screenshots::new(app_config.timelapse.directory.clone());
+        let mut fractal_sugar = Self {
+            color_schemes: app_config.color_schemes.clone(),
+            color_scheme_names: app_config.color_scheme_names.clone(),
+            app_config,
+            config_path: args.config_path,
+            safe_mode: args.safe_mode,
             app_overlay: config_window,
             engine,
             event_loop: Some(event_loop),
-            audio: AudioManager::default(),
+            audio: AudioManager::new(audio_source, stems, args.file_input, analysis_config),
             game_state,
+            events,
+            metrics,
+            virtual_camera,
+            timelapse: None,
+            screenshots,
+            clip: None,
+            recorder,
+            live_uniforms,
+            crossfade_fader,
+            intensity_fader,
+            performer_controls,
+            midi,
+            snapshots,
+            attract_mode: attract_mode::AttractMode::default(),
+            show: scenes::Show::default(),
+            keyframe_clock,
+            poster,
+            export,
             window_state,
 
             #[cfg(all(not(debug_assertions), target_os = "windows"))]
             console_state,
+        };
+        fractal_sugar.apply_scale_factor(scale_factor);
+        if audio_disabled {
+            log!("Audio capture is disabled until audio-responsiveness is toggled");
+        } else {
+            fractal_sugar.restart_audio();
         }
+        fractal_sugar
     }
 
     pub fn run(mut self) -> ! {
         // Run window loop
-        println!("Begin window loop...");
+        log!("Begin window loop...");
+        self.events.publish(events::Event::AppStarted);
+        if self.virtual_camera.is_some() {
+            self.events.publish(events::Event::RecordingStarted);
+        }
         self.event_loop
             .take()
             .unwrap()
             .run(move |event, _, control_flow| match event {
                 // All UI events have been handled (i.e., executes once per frame).
-                Event::MainEventsCleared => self.tock_frame(),
+                Event::MainEventsCleared => {
+                    self.apply_midi_controls(control_flow);
+                    self.tock_frame();
+                }
+
+                // Persist state which should outlive this launch.
+                Event::LoopDestroyed => {
+                    self.engine.save_pipeline_cache();
+                    if let Some(recorder) = &mut self.recorder {
+                        recorder.finish();
+                    }
+                    if self.virtual_camera.is_some() {
+                        self.events.publish(events::Event::RecordingStopped);
+                    }
+                    self.events.shutdown();
+                }
 
                 Event::WindowEvent { event, .. } => {
+                    if attract_mode::is_interaction(&event) && self.attract_mode.interact() {
+                        log!("Attract mode stopped");
+                    }
+
                     let mut handle_event = true;
                     if self.app_overlay.visible() {
                         // Determine if this event should be handled by the config window.
@@ -272,24 +778,115 @@ impl FractalSugar {
                         self.handle_window_event(&event, control_flow);
                     }
                 }
+
+                // Raw mouse motion turns the free-flying camera, unhindered by the edges of the window.
+                Event::DeviceEvent {
+                    event: DeviceEvent::MouseMotion { delta },
+                    ..
+                } => {
+                    if self.window_state.cursor_grabbed {
+                        if let Some(free_fly) = &mut self.game_state.free_fly {
+                            free_fly.look(delta);
+                        }
+                    }
+                }
                 _ => {}
             })
     }
 
     // Update per-frame state and draw to window
     fn tock_frame(&mut self) {
-        // Handle per-frame timing
+        // Handle per-frame timing, which a poster or export simulates at a fixed rate instead
         let now = Instant::now();
-        let delta_time = now
-            .duration_since(self.window_state.last_frame_time)
-            .as_secs_f32();
+        let delta_time = match (&mut self.poster, &mut self.export) {
+            (Some(poster), _) => poster.step(self.engine.particles_ready()),
+            (None, Some(export)) => export.step(self.engine.particles_ready()),
+            (None, None) => now
+                .duration_since(self.window_state.last_frame_time)
+                .as_secs_f32(),
+        };
         self.window_state.last_frame_time = now;
 
-        // Handle any changes to audio state from the input stream
-        self.update_audio_state_from_stream(delta_time);
+        // Fade any blackout in real time, even while the simulation is paused.
+        self.game_state.performer.update(delta_time);
+
+        // While inspecting, freeze the simulation unless a single step was requested.
+        let delta_time = if self.game_state.inspect.paused {
+            if std::mem::take(&mut self.game_state.inspect.step_requested) {
+                INSPECT_STEP_DELTA_TIME
+            } else {
+                0.
+            }
+        } else {
+            delta_time
+        };
+
+        if delta_time > 0. {
+            // Handle any changes to audio state from the input stream, or those a poster or export analyzed by this time
+            if let Some(poster) = &mut self.poster {
+                for state in poster.take_due_states() {
+                    self.apply_audio_state(state, delta_time);
+                }
+            } else if let Some(export) = &mut self.export {
+                for state in export.take_due_states() {
+                    self.apply_audio_state(state, delta_time);
+                }
+            } else {
+                self.update_audio_state_from_stream(delta_time);
+            }
+
+            // Update per-frame state
+            self.interpolate_frames(delta_time);
+        } else {
+            // Keep the audio threads flowing without applying their results.
+            self.hold_audio_state_from_stream();
+        }
+
+        // Upload any live uniforms edited since the last check.
+        if let Some(live_uniforms) = self
+            .live_uniforms
+            .as_mut()
+            .and_then(live_uniforms::Watcher::poll)
+        {
+            self.engine.update_live_uniforms(live_uniforms);
+        }
+
+        // Recompile the user's fractal whenever its file is saved.
+        self.engine.poll_user_fractal();
+
+        // Follow the crossfade fader of any external controller.
+        if let Some(position) = self
+            .crossfade_fader
+            .as_ref()
+            .and_then(crossfade::OscFader::take)
+        {
+            self.app_overlay.set_crossfade_position(position);
+            self.apply_scheme_crossfade(position);
+        }
+        if let Some(intensity) = self
+            .intensity_fader
+            .as_ref()
+            .and_then(crossfade::OscFader::take)
+        {
+            self.app_overlay.set_intensity(&mut self.engine, intensity);
+        }
+        if let Some(controls) = &self.performer_controls {
+            for (index, position) in controls.take() {
+                let on = position >= 0.5;
+                match index {
+                    0 => self.game_state.performer.blackout = on,
+                    _ => self.game_state.performer.freeze = on,
+                }
+            }
+        }
 
-        // Update per-frame state
-        self.interpolate_frames(delta_time);
+        // Grab the cursor for mouse-look while free-flying, releasing it to the overlay or when focus is lost.
+        let grab = self.game_state.free_fly.is_some()
+            && self.window_state.is_focused
+            && !self.app_overlay.visible();
+        if grab != self.window_state.cursor_grabbed {
+            self.set_cursor_grab(grab);
+        }
 
         let surface = self.engine.surface();
 
@@ -319,26 +916,161 @@ impl FractalSugar {
                 RecreateSwapchainResult::Ok => {
                     self.window_state.recreate_swapchain = false;
                     self.window_state.resized = false;
+                    self.game_state.refinement.reset();
                 }
                 RecreateSwapchainResult::ExtentNotSupported => return,
             }
         }
 
+        // Show the splash until the particles are initialized in the background, then fade into the visualization.
+        let particles_ready = self.engine.poll_particles_ready();
+        self.app_overlay.update_splash(particles_ready, delta_time);
+
         // Create per-frame data for particle compute-shader
-        let draw_data = self.next_shader_data(delta_time, self.engine.window().inner_size());
-
-        // Get an optional command buffer to render the GUI
-        let gui_command_buffer = if self.app_overlay.visible() {
-            // Render the config as an overlay
-            self.app_overlay.draw(
-                &mut self.engine,
-                &self.color_scheme_names,
-                &mut self.color_schemes,
-                &mut self.game_state.color_scheme_index,
-            )
+        let mut draw_data = self.next_shader_data(delta_time);
+
+        // Supersample the fractal over the following frames while the scene holds still, as a poster always does.
+        if self.app_overlay.progressive_refinement() || self.poster.is_some() {
+            self.game_state.refinement.refine(
+                &mut draw_data.fractal_data,
+                &mut draw_data.temporal_resolve,
+                self.engine.fractal_viewport().extent,
+            );
         } else {
-            None
-        };
+            self.game_state.refinement.reset();
+        }
+
+        // Get an optional command buffer to render the GUI, which is skipped while nothing is shown
+        self.app_overlay
+            .set_audio_warning(self.audio.poll_warning());
+        if self.app_overlay.visible() {
+            self.app_overlay.set_audio_stream(
+                self.audio.source.description(),
+                self.audio.source.stream_info(),
+            );
+        }
+        self.app_overlay.set_timelapse_frames(
+            self.timelapse
+                .as_ref()
+                .map(timelapse::Timelapse::frame_count),
+        );
+        if self.clip.as_ref().is_some_and(clip::ClipRecorder::finished) {
+            self.clip = None;
+        }
+        self.app_overlay
+            .set_clip_status(self.clip.as_ref().map(clip::ClipRecorder::status));
+        let gui_command_buffer = self.app_overlay.draw(
+            &mut self.engine,
+            &mut self.color_scheme_names,
+            &mut self.color_schemes,
+            &mut self.game_state.color_scheme_index,
+            &mut self.game_state.runtime_constants.coloring_mode,
+            &mut self.game_state.runtime_constants.physics_mode,
+            &mut self.game_state.runtime_constants.particle_containment,
+            &mut self.game_state.runtime_constants.color_management,
+            &draw_data,
+            self.metrics.latest(),
+            self.metrics.frame_times(),
+        );
+
+        // Apply any keybinding the onboarding tour is demonstrating.
+        match self.app_overlay.take_tour_demonstration() {
+            Some(TourDemonstration::ToggleKaleidoscope) => self.toggle_kaleidoscope(),
+            Some(TourDemonstration::NextColorScheme) => self.next_color_scheme(),
+            None => {}
+        }
+
+        // Apply any crossfade the user moved through the overlay.
+        if let Some(position) = self.app_overlay.take_requested_crossfade() {
+            self.apply_scheme_crossfade(position);
+        }
+
+        // Apply any present mode the user selected through the overlay, by recreating the swapchain.
+        if let Some(present_mode) = self.app_overlay.take_requested_present_mode() {
+            self.engine.set_present_mode(present_mode);
+            self.window_state.recreate_swapchain = true;
+        }
+
+        // Follow any color scheme the user renamed or deleted through the overlay.
+        if let Some(edit) = self.app_overlay.take_color_scheme_edit() {
+            self.apply_color_scheme_edit(edit);
+        }
+
+        // Save the configuration if the user requested it through the overlay.
+        if self.app_overlay.take_requested_save() {
+            self.save_config();
+        }
+
+        // Apply any audio source the user selected through the overlay.
+        if let Some(source_kind) = self.app_overlay.take_requested_audio_source() {
+            // Only recreate the stream immediately if the app is responding to audio.
+            if self.game_state.audio_responsive {
+                if let Err(e) = self.audio.set_source(source_kind) {
+                    self.audio_unavailable(&e);
+                }
+            } else {
+                self.audio.source_kind = source_kind;
+            }
+        }
+
+        // Apply any audio device the user selected through the overlay, likewise.
+        if let Some(device) = self.app_overlay.take_requested_audio_device() {
+            if self.game_state.audio_responsive {
+                if let Err(e) = self.audio.set_device(&device.to_string()) {
+                    self.audio_unavailable(&e);
+                }
+            } else {
+                self.audio.select_device(&device.to_string());
+            }
+        }
+
+        // Capture this frame as the poster once it has been supersampled.
+        if self
+            .poster
+            .as_ref()
+            .is_some_and(poster::Poster::capture_due)
+        {
+            self.engine.request_frame_capture();
+        }
+
+        // Capture every frame which advances an export.
+        if self
+            .export
+            .as_ref()
+            .is_some_and(export::Export::capture_due)
+        {
+            self.engine.request_frame_capture();
+        }
+
+        // Capture this frame for the virtual camera when the next is due.
+        if self
+            .virtual_camera
+            .as_mut()
+            .is_some_and(virtual_camera::VirtualCamera::frame_due)
+        {
+            self.engine.request_frame_capture();
+        }
+
+        // Also when the next frame of any timelapse is due, sharing the capture with the virtual camera.
+        if self
+            .timelapse
+            .as_mut()
+            .is_some_and(timelapse::Timelapse::frame_due)
+        {
+            self.engine.request_frame_capture();
+        }
+
+        // Capture the frames of a clip while one is recording.
+        if self
+            .clip
+            .as_mut()
+            .is_some_and(clip::ClipRecorder::frame_due)
+        {
+            self.engine.request_frame_capture();
+        }
+
+        // The time spent updating the frame, before waiting on the swapchain and recording the commands.
+        let cpu_ms = now.elapsed().as_secs_f32() * 1000.;
 
         // Draw frame and return whether a swapchain recreation was deemed necessary
         let (future, suboptimal) = match self.engine.render(&draw_data, gui_command_buffer) {
@@ -351,100 +1083,275 @@ impl FractalSugar {
         };
 
         self.window_state.recreate_swapchain |= self.engine.present(future) || suboptimal;
+
+        // Presenting waits for the frame to complete, so any capture can now be published.
+        let capture_extent = self.engine.frame_capture_extent();
+        let (camera, timelapse, screenshots, clip, poster, export) = (
+            &self.virtual_camera,
+            &mut self.timelapse,
+            &mut self.screenshots,
+            &mut self.clip,
+            &mut self.poster,
+            &mut self.export,
+        );
+        self.engine.read_captured_frame(|rgba| {
+            if let Some(extent) = capture_extent {
+                screenshots.save(extent, rgba);
+            }
+            if let Some(clip) = clip {
+                clip.save_frame(rgba);
+            }
+            if let Some(camera) = camera {
+                camera.send_frame(rgba);
+            }
+            if let Some(timelapse) = timelapse {
+                timelapse.save_frame(rgba);
+            }
+            if let Some(poster) = poster {
+                poster.save(rgba);
+            }
+            if let Some(export) = export {
+                export.save(rgba);
+            }
+        });
+
+        // Exit once the poster is saved, reporting whether it could be.
+        if let Some(saved) = self.poster.as_ref().and_then(poster::Poster::finished) {
+            self.engine.save_pipeline_cache();
+            std::process::exit(i32::from(!saved));
+        }
+
+        // Likewise once every frame of an export is saved.
+        if let Some(saved) = self.export.as_mut().and_then(export::Export::finished) {
+            self.engine.save_pipeline_cache();
+            std::process::exit(i32::from(!saved));
+        }
+
+        self.metrics.record_frame(
+            cpu_ms,
+            self.engine.gpu_frame_ms(),
+            self.engine.particle_count(),
+        );
+
+        // Advance the temporal anti-aliasing state, so the next frame reprojects from this one.
+        self.game_state.previous_camera_quaternion = self.game_state.camera_quaternion;
+        self.game_state.taa_frame = (self.game_state.taa_frame + 1) % TAA_JITTER_SAMPLES;
+    }
+
+    // Recreate the audio input stream, ceasing to respond to audio if no source can be created.
+    fn restart_audio(&mut self) {
+        if let Err(e) = self.audio.recreate_stream() {
+            self.audio_unavailable(&e);
+        }
+    }
+
+    // Fall back to not responding to audio after the source failed, telling the user why.
+    fn audio_unavailable(&mut self, e: &anyhow::Error) {
+        log!("Audio is unavailable: {e:?}");
+        self.app_overlay.show_toast(format!(
+            "Audio is unavailable, so the visuals are no longer responding to it: {e}"
+        ));
+        self.stop_responding_to_audio();
+    }
+
+    // Bring the audio-state to a rest and pause the audio stream.
+    fn stop_responding_to_audio(&mut self) {
+        self.game_state.audio_responsive = false;
+        self.audio.state.latest_volume = 0.;
+        self.audio.state.big_boomer = Vector4::default();
+        self.audio.state.extra_big_boomers.clear();
+        self.audio.state.curl_attractors.clear();
+        self.audio.state.attractors.clear();
+        self.audio.pause();
     }
 
     // Helper for receiving the latest audio state from the input stream
     fn update_audio_state_from_stream(&mut self, delta_time: f32) {
-        // Allow user to toggle audio-responsiveness
+        // Allow user to toggle audio-responsiveness.
+        // A paused source may end its thread, so a disconnected stream is expected until it is recreated
         if !self.game_state.audio_responsive {
-            match self.audio.receiver.try_recv() {
-                Ok(_) | Err(crossbeam_channel::TryRecvError::Empty) => {}
-
-                // Unexpected error, bail
-                Err(e) => panic!("Failed to receive data from audio thread: {e:?}"),
+            if self.audio.receiver.try_recv().is_ok() {
+                self.metrics.record_audio_update();
             }
             return;
         }
 
+        // Apply any state held while paused before checking for newer changes.
+        if let Some(state) = self.audio.held_state.take() {
+            self.apply_audio_state(state, delta_time);
+        }
+
         // Handle any changes to audio state
         match self.audio.receiver.try_recv() {
             // Update audio state vars
-            Ok(audio::State {
-                volume,
-
-                bass_note,
-                mids_notes,
-                high_notes,
-
-                reactive_bass,
-                reactive_mids,
-                reactive_high,
+            Ok(state) => {
+                self.metrics.record_audio_update();
+                self.apply_audio_state(state, delta_time);
+            }
 
-                kick_angular_velocity,
-            }) => {
-                // Update volume
-                self.audio.state.latest_volume = volume;
+            // No new data, continue on
+            Err(crossbeam_channel::TryRecvError::Empty) => {}
 
-                let (big_boomer, curl_attractors, attractors) = if self.game_state.particles_are_3d
-                {
-                    (
-                        audio::map_note_to_cube(bass_note, audio::BASS_POW),
-                        mids_notes.map(|n| audio::map_note_to_cube(n, audio::MIDS_POW)),
-                        high_notes.map(|n| audio::map_note_to_cube(n, audio::HIGH_POW)),
-                    )
-                } else {
-                    (
-                        audio::map_note_to_square(bass_note, audio::BASS_POW),
-                        mids_notes.map(|n| audio::map_note_to_square(n, audio::MIDS_POW)),
-                        high_notes.map(|n| audio::map_note_to_square(n, audio::HIGH_POW)),
-                    )
-                };
+            // The source ended without being paused
+            Err(crossbeam_channel::TryRecvError::Disconnected) => {
+                self.audio_unavailable(&anyhow::anyhow!("The audio source stopped unexpectedly"));
+            }
+        }
+    }
 
-                // Update 2D big boomers
-                match self.game_state.fix_particles {
-                    ParticleTension::Spring => {
-                        let smooth = 1. - (-7.25 * big_boomer.w * delta_time).exp();
-                        self.audio.state.big_boomer.x +=
-                            smooth * (big_boomer.x - self.audio.state.big_boomer.x);
-                        self.audio.state.big_boomer.y +=
-                            smooth * (big_boomer.y - self.audio.state.big_boomer.y);
-                        self.audio.state.big_boomer.z +=
-                            smooth * (big_boomer.z - self.audio.state.big_boomer.z);
-                        self.audio.state.big_boomer.w = big_boomer.w;
+    // Helper for receiving audio state without applying it, retaining only the latest.
+    fn hold_audio_state_from_stream(&mut self) {
+        loop {
+            match self.audio.receiver.try_recv() {
+                Ok(state) => {
+                    self.metrics.record_audio_update();
+                    if self.game_state.audio_responsive {
+                        self.audio.held_state = Some(state);
                     }
-                    ParticleTension::None => self.audio.state.big_boomer = big_boomer,
                 }
 
-                // Update 2D (curl)attractors
-                let c_len = curl_attractors.len();
-                let a_len = attractors.len();
-                self.audio.state.curl_attractors[..c_len]
-                    .copy_from_slice(&curl_attractors[..c_len]);
-                self.audio.state.attractors[..a_len].copy_from_slice(&attractors[..a_len]);
-
-                // Update fractal state
-                if let Some(omega) = kick_angular_velocity {
-                    self.audio.state.local_angular_velocity = omega;
-                }
-                self.audio.state.reactive_bass = reactive_bass;
-                self.audio.state.reactive_mids = reactive_mids;
-                self.audio.state.reactive_high = reactive_high;
+                // No new data, continue on. A disconnected source is handled once the simulation resumes
+                Err(_) => return,
             }
-
-            // No new data, continue on
-            Err(crossbeam_channel::TryRecvError::Empty) => {}
-
-            // Unexpected error, bail
-            Err(e) => panic!("Failed to receive data from audio thread: {e:?}"),
         }
     }
 
-    // Update the window and game state from keyboard inputs
-    fn handle_keyboard_input(&mut self, keycode: VirtualKeyCode, control_flow: &mut ControlFlow) {
-        match keycode {
-            // Handle fullscreen toggle (F11)
-            VirtualKeyCode::F11 => {
-                if self.window_state.is_fullscreen {
+    // Helper for applying a new audio state to the local state.
+    fn apply_audio_state(&mut self, state: audio::State, delta_time: f32) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(&state);
+        }
+        self.app_overlay.set_audio_spectrum(&state);
+
+        let audio::State {
+            volume,
+
+            bass_note,
+            extra_bass_notes,
+            mids_notes,
+            high_notes,
+
+            reactive_bass,
+            reactive_mids,
+            reactive_high,
+
+            kick_angular_velocity,
+
+            key,
+            beat,
+
+            bass_direction,
+            mids_direction,
+            high_direction,
+
+            mids_channel_notes,
+            high_channel_notes,
+            ..
+        } = state;
+
+        // Announce each newly detected key.
+        if let Some(key) = key.filter(|key| self.audio.state.key != Some(*key)) {
+            log!("Detected musical key: {key}");
+        }
+        if key.is_some() {
+            self.audio.state.key = key;
+        }
+
+        // Show the estimated tempo alongside the other details of the audio.
+        self.app_overlay.set_audio_beat(beat);
+
+        // Update volume
+        self.audio.state.latest_volume = volume;
+
+        let map_note: fn(audio::Note, f32) -> Vector4 = if self.game_state.particles_are_3d {
+            audio::map_note_to_cube
+        } else {
+            audio::map_note_to_square
+        };
+
+        // Move each attractor toward where its band is heard in the stereo field.
+        // Stereo attractors instead follow the notes of each channel, mirrored onto the side of their channel
+        let placement = self.app_overlay.stereo_placement();
+        let placed = |notes: &[audio::Note], pow: f32, direction: audio::Direction| {
+            notes
+                .iter()
+                .map(|&note| audio::place_toward(map_note(note, pow), direction, placement))
+                .collect::<Vec<_>>()
+        };
+        let big_boomer = audio::place_toward(
+            map_note(bass_note, audio::BASS_POW),
+            bass_direction,
+            placement,
+        );
+        let extra_big_boomers = placed(&extra_bass_notes, audio::BASS_POW, bass_direction);
+        let (curl_attractors, attractors) = if self.app_overlay.stereo_attractors() {
+            let mapped = |notes: &[audio::Note], pow: f32| {
+                let attractors: Vec<_> = notes.iter().map(|&note| map_note(note, pow)).collect();
+                audio::mirror_to_sides(&attractors)
+            };
+            (
+                mapped(&mids_channel_notes, audio::MIDS_POW),
+                mapped(&high_channel_notes, audio::HIGH_POW),
+            )
+        } else {
+            (
+                placed(&mids_notes, audio::MIDS_POW, mids_direction),
+                placed(&high_notes, audio::HIGH_POW, high_direction),
+            )
+        };
+
+        // Update 2D big boomers
+        match self.game_state.fix_particles {
+            ParticleTension::Spring => {
+                let smooth = 1. - (-7.25 * big_boomer.w * delta_time).exp();
+                self.audio.state.big_boomer.x +=
+                    smooth * (big_boomer.x - self.audio.state.big_boomer.x);
+                self.audio.state.big_boomer.y +=
+                    smooth * (big_boomer.y - self.audio.state.big_boomer.y);
+                self.audio.state.big_boomer.z +=
+                    smooth * (big_boomer.z - self.audio.state.big_boomer.z);
+                self.audio.state.big_boomer.w = big_boomer.w;
+            }
+            ParticleTension::None => self.audio.state.big_boomer = big_boomer,
+        }
+
+        // Update the remaining big boomers and (curl)attractors
+        self.audio.state.extra_big_boomers = extra_big_boomers;
+        self.audio.state.curl_attractors = curl_attractors;
+        self.audio.state.attractors = attractors;
+
+        // Update fractal state
+        if let Some(omega) = kick_angular_velocity {
+            self.audio.state.local_angular_velocity = omega;
+
+            // Remember where the kick occurred so that its echo may expand from there.
+            let state = &mut self.audio.state;
+            let strength = (0.8 * state.big_boomer.w.sqrt()).min(1.);
+            state.kick_echoes[state.next_kick_echo] = KickEcho {
+                position: state.big_boomer.xyz(),
+                strength,
+                age: 0.,
+            };
+            state.next_kick_echo = (state.next_kick_echo + 1) % KICK_ECHO_COUNT;
+            state.balance.record_kick();
+            state.pump.trigger();
+            state.cycler.record_kick();
+            self.events.publish_kick(strength);
+            if let Some(timelapse) = &mut self.timelapse {
+                timelapse.record_kick();
+            }
+        }
+        self.audio.state.reactive_bass = reactive_bass;
+        self.audio.state.reactive_mids = reactive_mids;
+        self.audio.state.reactive_high = reactive_high;
+    }
+
+    // Update the window and game state from the action bound to a keyboard input
+    fn handle_action(&mut self, action: Action, control_flow: &mut ControlFlow) {
+        match action {
+            // Handle fullscreen toggle (F11)
+            Action::ToggleFullscreen => {
+                if self.window_state.is_fullscreen {
                     self.engine.window().set_fullscreen(None);
                     self.window_state.is_fullscreen = false;
                 } else {
@@ -453,32 +1360,38 @@ impl FractalSugar {
                         .set_fullscreen(Some(Fullscreen::Borderless(None)));
                     self.window_state.is_fullscreen = true;
                 }
+
+                // The window jumps in and out of fullscreen, which should not fling the particles.
+                self.game_state.window_center = None;
             }
 
             // Handle Escape key
-            VirtualKeyCode::Escape => {
+            Action::Escape => {
                 if self.window_state.is_fullscreen {
                     // Leave fullscreen
                     self.engine.window().set_fullscreen(None);
                     self.window_state.is_fullscreen = false;
+                    self.game_state.window_center = None;
                 } else {
                     // Exit window loop
-                    println!("The Escape key was pressed, exiting");
+                    log!("The Escape key was pressed, exiting");
                     *control_flow = ControlFlow::Exit;
                 }
             }
 
             // Handle Space bar for toggling Kaleidoscope effect
-            VirtualKeyCode::Space => {
-                use KaleidoscopeDirection::{Backward, BackwardComplete, Forward, ForwardComplete};
-                self.game_state.kaleidoscope_dir = match self.game_state.kaleidoscope_dir {
-                    Forward | ForwardComplete => Backward,
-                    Backward | BackwardComplete => Forward,
-                }
+            Action::ToggleKaleidoscope => self.toggle_kaleidoscope(),
+            Action::FewerKaleidoscopeSegments => {
+                let segments = self.app_overlay.step_kaleidoscope_segments(-1);
+                log!("Kaleidoscope segments: {segments}");
+            }
+            Action::MoreKaleidoscopeSegments => {
+                let segments = self.app_overlay.step_kaleidoscope_segments(1);
+                log!("Kaleidoscope segments: {segments}");
             }
 
             // Handle toggling of Jello mode (i.e., fixing particles to positions)
-            VirtualKeyCode::J => {
+            Action::ToggleJello => {
                 self.game_state.fix_particles = match self.game_state.fix_particles {
                     ParticleTension::None => ParticleTension::Spring,
                     ParticleTension::Spring => ParticleTension::None,
@@ -486,7 +1399,7 @@ impl FractalSugar {
             }
 
             // Handle toggling of particle rendering.
-            VirtualKeyCode::P => {
+            Action::ToggleParticles => {
                 // Toggle value stored in CPU memory.
                 self.game_state.runtime_constants.render_particles =
                     !self.game_state.runtime_constants.render_particles;
@@ -501,14 +1414,14 @@ impl FractalSugar {
             }
 
             // Handle toggling of stationary particle visibility.
-            VirtualKeyCode::H => {
+            Action::ToggleHideStationary => {
                 // Tell overlay to update the state.
                 self.app_overlay
                     .toggle_hide_stationary_particles(&mut self.engine);
             }
 
             // Handle toggling of alternate colors
-            VirtualKeyCode::Capital => {
+            Action::ToggleAlternateColors => {
                 self.game_state.alternate_colors = match self.game_state.alternate_colors {
                     AlternateColors::Inverse => AlternateColors::Normal,
                     AlternateColors::Normal => AlternateColors::Inverse,
@@ -516,46 +1429,94 @@ impl FractalSugar {
             }
 
             // Handle toggling of 3D particles
-            VirtualKeyCode::D => {
+            Action::Toggle3dParticles => {
                 self.game_state.particles_are_3d = !self.game_state.particles_are_3d;
             }
+            Action::ResetParticles => self.engine.reset_particles(),
 
-            // Tab through different color schemes / palattes ?
-            VirtualKeyCode::Tab => {
-                self.game_state.color_scheme_index =
-                    (self.game_state.color_scheme_index + 1) % self.color_schemes.len();
-                self.engine
-                    .update_color_scheme(self.color_schemes[self.game_state.color_scheme_index]);
+            // Toggle respawning particles on the fractal's surface, at the configured rate.
+            Action::ToggleSurfaceEmission => {
+                let emitting = self.game_state.runtime_constants.surface_emission > 0.;
+                let surface_emission = if emitting {
+                    0.
+                } else {
+                    self.app_config.surface_emission_rate
+                };
+                self.game_state.runtime_constants.surface_emission = surface_emission;
+                self.engine.set_surface_emission(surface_emission);
             }
 
+            // Tab through different color schemes / palattes ?
+            Action::NextColorScheme => self.next_color_scheme(),
+            Action::PreviousColorScheme => self.previous_color_scheme(),
+
             // Toggle display of config window
-            VirtualKeyCode::C => self.app_overlay.toggle_config(),
+            Action::ToggleConfig => self.app_overlay.toggle_config(),
+
+            // Toggle display of the console of runtime variables
+            Action::ToggleVariables => self.app_overlay.toggle_variables(),
+            Action::ToggleSpectrum => self.app_overlay.toggle_spectrum(),
+
+            // Save the currently applied configuration to the config file
+            Action::SaveConfig => self.save_config(),
+
+            // Start or stop saving frames for a timelapse
+            Action::ToggleTimelapse => self.toggle_timelapse(),
+
+            // Save the next frame as a screenshot
+            Action::Screenshot => self.take_screenshot(),
+
+            // Start recording a clip, or stop the recording one early
+            Action::RecordClip => self.toggle_clip(),
 
             // Toggle display of help window
-            VirtualKeyCode::F1 => self.app_overlay.toggle_help(),
+            Action::ToggleHelp => self.app_overlay.toggle_help(),
 
-            // Toggle audio-responsiveness
-            VirtualKeyCode::R => {
-                use cpal::traits::StreamTrait;
-                self.game_state.audio_responsive = !self.game_state.audio_responsive;
+            // Toggle the pause-and-inspect debug mode.
+            Action::ToggleInspect => {
+                self.game_state.inspect.paused = !self.game_state.inspect.paused;
+                self.game_state.inspect.step_requested = false;
+                self.app_overlay
+                    .set_inspector_visible(self.game_state.inspect.paused);
+            }
 
+            // While inspecting, advance the simulation by exactly one frame.
+            Action::StepFrame => {
+                if self.game_state.inspect.paused {
+                    self.game_state.inspect.step_requested = true;
+                }
+            }
+
+            // Toggle audio-responsiveness
+            Action::ToggleAudioResponsive => {
                 if self.game_state.audio_responsive {
-                    self.audio.recreate_stream();
+                    self.stop_responding_to_audio();
                 } else {
-                    // Ensure audio-state comes to a rest
-                    self.audio.state.latest_volume = 0.;
-                    self.audio.state.big_boomer = Vector4::default();
-                    self.audio.state.curl_attractors = [Vector4::default(); 2];
-                    self.audio.state.attractors = [Vector4::default(); 2];
+                    self.game_state.audio_responsive = true;
+                    self.restart_audio();
+                }
+            }
 
-                    // Pause audio stream
-                    self.audio.capture_stream.pause().unwrap();
+            // Switch between capturing the audio out and the audio in, recreating the stream if responsive to audio.
+            Action::ToggleCaptureMode => {
+                let source_kind = if self.audio.source_kind == audio::SourceKind::Microphone {
+                    audio::SourceKind::Loopback
+                } else {
+                    audio::SourceKind::Microphone
+                };
+                if self.game_state.audio_responsive {
+                    if let Err(e) = self.audio.set_source(source_kind) {
+                        self.audio_unavailable(&e);
+                    }
+                } else {
+                    self.audio.source_kind = source_kind;
                 }
+                self.app_overlay.set_audio_source(source_kind);
             }
 
             // Handle toggling the companion-console.
             #[cfg(all(not(debug_assertions), target_os = "windows"))]
-            VirtualKeyCode::Return => {
+            Action::ToggleConsole => {
                 if let Some(console_state) = &mut self.console_state {
                     if console_state.visible {
                         console_state.hide();
@@ -566,15 +1527,88 @@ impl FractalSugar {
             }
 
             // Set different fractal types.
-            VirtualKeyCode::Key0 => self.set_distance_estimate_id(0),
-            VirtualKeyCode::Key1 => self.set_distance_estimate_id(1),
-            VirtualKeyCode::Key2 => self.set_distance_estimate_id(2),
-            VirtualKeyCode::Key3 => self.set_distance_estimate_id(3),
-            VirtualKeyCode::Key4 => self.set_distance_estimate_id(4),
-            VirtualKeyCode::Key5 => self.set_distance_estimate_id(5),
-            VirtualKeyCode::Key6 => self.set_distance_estimate_id(6),
+            Action::SelectFractal(id) => self.set_distance_estimate_id(id),
+            Action::NextColoringMode => {
+                let mode = self.game_state.runtime_constants.coloring_mode.next();
+                self.set_coloring_mode(mode);
+                log!("Fractal coloring mode: {}", mode.name());
+            }
+            Action::NextPhysicsMode => {
+                let mode = self.game_state.runtime_constants.physics_mode.next();
+                self.game_state.runtime_constants.physics_mode = mode;
+                log!("Particle physics mode: {}", mode.name());
+            }
+            Action::CycleDisplayLayout => {
+                let layout = self.engine.display_layout().next();
+                self.set_display_layout(layout);
+                log!("Display layout: {}", layout.name());
+            }
+            Action::ToggleFreeFly => self.toggle_free_fly(),
+
+            // Animate the fractal camera along the respective configured path.
+            Action::PlayCameraPath(index) => {
+                if let Some(path) = self.app_config.camera_paths.get(index) {
+                    log!("Playing camera path: {}", path.name);
+                    self.game_state.free_fly = None;
+                    self.game_state
+                        .camera_animation
+                        .play(path, self.game_state.camera_quaternion);
+                }
+            }
+
+            // Emergency controls for live operation.
+            Action::ToggleBlackout => {
+                let performer = &mut self.game_state.performer;
+                performer.blackout = !performer.blackout;
+                log!("Blackout {}", if performer.blackout { "on" } else { "off" });
+            }
+            Action::ToggleFreeze => {
+                let performer = &mut self.game_state.performer;
+                performer.freeze = !performer.freeze;
+                log!("Freeze {}", if performer.freeze { "on" } else { "off" });
+            }
+
+            // Save or recall the looks of the snapshot slots.
+            Action::SaveSnapshot(slot) => {
+                let look = self.current_look();
+                self.snapshots.save(slot, look, &self.color_scheme_names);
+                log!("Saved the look to snapshot {}", slot + 1);
+            }
+            Action::RecallSnapshot(slot) => {
+                if self.snapshots.recall(slot, self.current_look()) {
+                    self.stop_color_keyframes();
+                    log!("Recalling snapshot {}", slot + 1);
+                } else {
+                    log!("Snapshot {} has not been saved", slot + 1);
+                }
+            }
+
+            // Play the configured show of scenes.
+            Action::PlayPauseShow => {
+                if self.app_config.scenes.is_empty() {
+                    log!("No scenes are configured for the show");
+                } else if let Some(index) = self.show.toggle_play(&self.app_config.scenes) {
+                    log!("Started the show");
+                    self.begin_scene(index);
+                } else {
+                    log!(
+                        "Show {}",
+                        if self.show.paused() {
+                            "paused"
+                        } else {
+                            "resumed"
+                        }
+                    );
+                }
+            }
+            Action::NextScene => {
+                if let Some(index) = self.show.next(&self.app_config.scenes) {
+                    self.begin_scene(index);
+                }
+            }
 
             // No-op
+            #[allow(unreachable_patterns)]
             _ => {}
         }
     }
@@ -583,23 +1617,58 @@ impl FractalSugar {
         match *event {
             // Handle window close
             WindowEvent::CloseRequested => {
-                println!("The close button was pressed, exiting");
+                log!("The close button was pressed, exiting");
                 *control_flow = ControlFlow::Exit;
             }
 
             // Handle resize
-            WindowEvent::Resized(_) => self.window_state.resized = true,
+            WindowEvent::Resized(size) => {
+                self.window_state.resized = true;
+                self.window_state.size = size;
+            }
+
+            // Follow the pixel density of the monitor the window is on.
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.apply_scale_factor(scale_factor);
+            }
+
+            // Track the window being dragged, which the particles respond to.
+            WindowEvent::Moved(position) => self.window_state.position = Some(position),
 
             // Handle some keyboard input
             WindowEvent::KeyboardInput {
                 input:
                     KeyboardInput {
-                        state: ElementState::Pressed,
-                        virtual_keycode: Some(keycode),
+                        state,
+                        virtual_keycode,
+                        scancode,
                         ..
                     },
                 ..
-            } => self.handle_keyboard_input(keycode, control_flow),
+            } => {
+                let press = input::KeyPress {
+                    logical: virtual_keycode,
+                    scancode,
+                };
+                let pressed = state == ElementState::Pressed;
+
+                // While free-flying, the movement keys steer the camera in place of their bindings.
+                if let Some(free_fly) = &mut self.game_state.free_fly {
+                    if free_fly.key(input::key_position(press), pressed) {
+                        return;
+                    }
+                }
+
+                if pressed {
+                    if let Some(action) = input::action_for_key(press, self.window_state.modifiers)
+                    {
+                        self.handle_action(action, control_flow);
+                    }
+                }
+            }
+
+            // Track the held modifier keys so that chords may be recognized.
+            WindowEvent::ModifiersChanged(modifiers) => self.window_state.modifiers = modifiers,
 
             // Track window focus in a state var.
             WindowEvent::Focused(focused) => {
@@ -607,6 +1676,12 @@ impl FractalSugar {
                     // Force cursor visibility when focus is lost
                     self.engine.window().set_cursor_visible(true);
                     self.game_state.is_cursor_visible = true;
+
+                    // Modifier releases are not reported while unfocused, nor are those of the movement keys
+                    self.window_state.modifiers = ModifiersState::empty();
+                    if let Some(free_fly) = &mut self.game_state.free_fly {
+                        free_fly.release_keys();
+                    }
                 }
                 self.window_state.is_focused = focused;
             }
@@ -614,8 +1689,10 @@ impl FractalSugar {
             // Handle mouse movement.
             WindowEvent::CursorMoved { position, .. } => {
                 self.window_state.last_mouse_movement = Instant::now();
-                self.engine.window().set_cursor_visible(true);
-                self.game_state.is_cursor_visible = true;
+                if !self.window_state.cursor_grabbed {
+                    self.engine.window().set_cursor_visible(true);
+                    self.game_state.is_cursor_visible = true;
+                }
 
                 self.game_state.cursor_position = position;
             }
@@ -640,13 +1717,22 @@ impl FractalSugar {
                 }
             }
 
-            // Handle mouse scroll wheel to change strength of cursor-applied forces.
+            // Handle mouse scroll wheel to change strength of cursor-applied forces, zoom while holding Ctrl,
+            // or ride the intensity macro while holding Alt.
             WindowEvent::MouseWheel { delta, .. } => {
                 let delta = match delta {
                     MouseScrollDelta::LineDelta(_, y) => y,
                     MouseScrollDelta::PixelDelta(p) => p.y as f32,
                 };
-                self.game_state.cursor_force_mult *= (SCROLL_SENSITIVITY * delta).exp();
+                if self.window_state.modifiers.ctrl() {
+                    self.app_overlay
+                        .zoom(&mut self.engine, (-SCROLL_SENSITIVITY * delta).exp());
+                } else if self.window_state.modifiers.alt() {
+                    let intensity = self.app_overlay.intensity() + INTENSITY_SCROLL_STEP * delta;
+                    self.app_overlay.set_intensity(&mut self.engine, intensity);
+                } else {
+                    self.game_state.cursor_force_mult *= (SCROLL_SENSITIVITY * delta).exp();
+                }
             }
 
             _ => {}
@@ -655,24 +1741,112 @@ impl FractalSugar {
 
     // Helper for interpolating data on a per-frame basis.
     fn interpolate_frames(&mut self, delta_time: f32) {
+        let responsiveness = self.app_overlay.responsiveness();
+
         // Interpolate the volume towards the latest.
         interpolate_floats(
             &mut self.audio.state.local_volume,
             self.audio.state.latest_volume,
-            delta_time * -1.8,
+            delta_time * -responsiveness.volume,
         );
 
         // Use a volume-scaled delta-time to allow volume to control the speed of some actions.
         let audio_scaled_delta_time = delta_time * self.audio.state.local_volume.sqrt();
         self.audio.state.play_time += audio_scaled_delta_time;
 
-        // Rotate the camera according to its angular velocity.
-        self.game_state
-            .camera_quaternion
-            .rotate_by(Quaternion::build(
-                self.audio.state.local_angular_velocity.xyz(),
-                delta_time * self.audio.state.local_angular_velocity.w,
-            ));
+        // Rotate the camera according to its angular velocity, unless it is following a camera path or flown freely.
+        // The rotation is weighted so that it blends back in after a path ends
+        if let Some(free_fly) = &mut self.game_state.free_fly {
+            free_fly.update(delta_time, self.window_state.modifiers);
+            self.game_state.camera_quaternion = free_fly.orientation();
+        } else {
+            self.game_state
+                .camera_quaternion
+                .rotate_by(Quaternion::build(
+                    self.audio.state.local_angular_velocity.xyz(),
+                    delta_time
+                        * self.audio.state.local_angular_velocity.w
+                        * self
+                            .app_overlay
+                            .intensity_multipliers()
+                            .angular_velocity_max
+                        * self.game_state.camera_animation.audio_weight,
+                ));
+            self.game_state
+                .camera_animation
+                .update(delta_time, &mut self.game_state.camera_quaternion);
+        }
+
+        // Advance any intro holding the particles as a logo, once they are shown.
+        if self.engine.particles_ready() {
+            self.game_state.intro.update(delta_time);
+        }
+
+        // Transition between any keyframed color schemes, measured by time since launch or by the followed player.
+        self.game_state.keyframe_time = match &mut self.keyframe_clock {
+            Some(clock) => clock.advance(delta_time),
+            None => self.game_state.keyframe_time + delta_time,
+        };
+        if self.game_state.follow_color_keyframes {
+            if let Some(scheme) = app_config::keyframed_scheme(
+                &self.app_config.color_keyframes,
+                &self.color_schemes,
+                self.game_state.keyframe_time,
+            ) {
+                self.engine.update_color_scheme(scheme);
+            }
+        }
+
+        // Blend into any color scheme switched to.
+        self.update_scheme_transition(delta_time);
+
+        // Particles lag behind the window as it is dragged or resized.
+        self.update_window_inertia(delta_time);
+
+        // Move on through the scenes of any show being played.
+        if let Some(index) = self.show.update(&self.app_config.scenes, delta_time) {
+            self.begin_scene(index);
+        }
+
+        // Cycle through the scenes of the attract mode while no one is interacting.
+        self.update_attract_mode(delta_time);
+
+        // Blend into any recalled snapshot.
+        self.update_snapshot_recall(delta_time);
+
+        // Tint the visuals by the musical key of the audio.
+        self.update_key_hue(delta_time);
+
+        // Balance the particles against the fractal by the character of the music.
+        let previous_rule = self.audio.state.balance.rule;
+        self.audio.state.balance.update(
+            &self.app_overlay.auto_balance(),
+            self.audio.state.local_volume,
+            delta_time,
+        );
+        if self.audio.state.balance.rule != previous_rule {
+            log!(
+                "Automatic balance: {}",
+                self.audio.state.balance.rule.name()
+            );
+        }
+
+        // Switch color schemes with the music, when enabled.
+        if self.audio.state.cycler.update(
+            &self.app_overlay.auto_cycle(),
+            self.audio.state.local_volume,
+            delta_time,
+        ) {
+            self.next_color_scheme();
+        }
+
+        // Duck the fractal after each kick.
+        self.update_sidechain_pump(delta_time);
+
+        // Age the echoes of recent kicks.
+        for echo in &mut self.audio.state.kick_echoes {
+            echo.age += delta_time;
+        }
 
         // Interpolate the magnitude of the angular velocity towards the base value.
         interpolate_floats(
@@ -682,37 +1856,42 @@ impl FractalSugar {
         );
 
         // Interpolate the reactive vectors towards the latest.
+        let loudest = |attractors: &[Vector4]| attractors.first().map_or(0., |a| a.w);
+        let mids_strength = loudest(&self.audio.state.curl_attractors);
+        let high_strength = loudest(&self.audio.state.attractors);
         interpolate_vec3(
             &mut self.audio.state.local_reactive_bass,
             &self.audio.state.reactive_bass,
-            delta_time * (0.8 * self.audio.state.big_boomer.w.sqrt()).min(1.) * -0.36,
+            delta_time
+                * (0.8 * self.audio.state.big_boomer.w.sqrt()).min(1.)
+                * -responsiveness.reactive,
         );
         interpolate_vec3(
             &mut self.audio.state.local_reactive_mids,
             &self.audio.state.reactive_mids,
-            delta_time * (0.8 * self.audio.state.curl_attractors[0].w.sqrt()).min(1.) * -0.36,
+            delta_time * (0.8 * mids_strength.sqrt()).min(1.) * -responsiveness.reactive,
         );
         interpolate_vec3(
             &mut self.audio.state.local_reactive_high,
             &self.audio.state.reactive_high,
-            delta_time * (0.8 * self.audio.state.attractors[0].w.sqrt()).min(1.) * -0.36,
+            delta_time * (0.8 * high_strength.sqrt()).min(1.) * -responsiveness.reactive,
         );
 
         // Interpolate the smooth vectors towards the reactive vectors.
         interpolate_vec3(
             &mut self.audio.state.local_smooth_bass,
             &self.audio.state.local_reactive_bass,
-            delta_time * -0.15,
+            delta_time * -responsiveness.smooth,
         );
         interpolate_vec3(
             &mut self.audio.state.local_smooth_mids,
             &self.audio.state.local_reactive_mids,
-            delta_time * -0.15,
+            delta_time * -responsiveness.smooth,
         );
         interpolate_vec3(
             &mut self.audio.state.local_smooth_high,
             &self.audio.state.local_reactive_high,
-            delta_time * -0.15,
+            delta_time * -responsiveness.smooth,
         );
 
         // Check, and possibly update, the kaleidoscope animation state.
@@ -733,17 +1912,47 @@ impl FractalSugar {
             }
             _ => {}
         };
+
+        // Turn the kaleidoscope's folded view, faster with the volume like the rest of the animation.
+        self.game_state.kaleidoscope_rotation = (self.game_state.kaleidoscope_rotation
+            + self.app_overlay.kaleidoscope().spin * audio_scaled_delta_time)
+            .rem_euclid(std::f32::consts::TAU);
+
+        // Smoothly focus on either the depth of the big boomer or the configured distance.
+        let depth_of_field = self.app_overlay.depth_of_field();
+        let focus_target = if depth_of_field.track_big_boomer {
+            let camera_position = self.particle_camera_position(self.orbit_distance());
+            let view_position = self
+                .game_state
+                .camera_quaternion
+                .inv()
+                .rotate_point(self.audio.state.big_boomer.xyz() - camera_position);
+            (-view_position.z).clamp(MIN_DOF_FOCUS_DISTANCE, MAX_DOF_FOCUS_DISTANCE)
+        } else {
+            depth_of_field.focus_distance
+        };
+        interpolate_floats(
+            &mut self.game_state.focus_distance,
+            focus_target,
+            delta_time * -2.5,
+        );
     }
 
     // Create the push-constant data for the respective shaders from the current game state.
-    #[allow(clippy::cast_precision_loss)]
-    fn next_shader_data(&self, delta_time: f32, dimensions: PhysicalSize<u32>) -> DrawData {
-        let width = dimensions.width as f32;
-        let height = dimensions.height as f32;
-        let aspect_ratio = width / height;
-
-        // Create per-frame data for the particle compute-shader.
-        let particle_data = if self.game_state.runtime_constants.render_particles {
+    fn next_shader_data(&self, delta_time: f32) -> DrawData {
+        // Content may be letterboxed or stretched, so the shaders see the content's shape rather than the window's.
+        // The fractal is jittered and reprojected within its own region of the view, which may be half of it
+        let viewport = self.engine.fractal_viewport();
+        let aspect_ratio = self.engine.content_aspect_ratio();
+        let orbit_distance = self.orbit_distance();
+
+        // Create per-frame data for the particle compute-shader, once the particles are initialized.
+        let (particle_data, particle_attractors) = if self
+            .game_state
+            .runtime_constants
+            .render_particles
+            && self.engine.particles_ready()
+        {
             // Create a unique attractor based on the mouse position.
             let cursor_attractor = {
                 let strength = if self.game_state.fix_particles == ParticleTension::Spring {
@@ -753,52 +1962,133 @@ impl FractalSugar {
                 } * self.game_state.cursor_force_mult
                     * self.game_state.cursor_force;
 
-                let Vector3 { x, y, z, .. } =
-                    self.screen_position_to_world(dimensions, aspect_ratio);
+                let Vector3 { x, y, z, .. } = self.screen_position_to_world(aspect_ratio);
                 [x, y, z, strength]
             };
 
-            let compute = engine::ParticleComputePushConstants {
-                big_boomer: self.audio.state.big_boomer.into(),
-
-                curl_attractors: self
-                    .audio
-                    .state
-                    .curl_attractors
-                    .map(std::convert::Into::into),
-
-                attractors: [
-                    self.audio.state.attractors[0].into(),
-                    self.audio.state.attractors[1].into(),
-                    cursor_attractor,
-                ],
+            // Any intro holds the particles still, then bursts them outward as the audio ramps in.
+            let intro = &self.game_state.intro;
+            let audio_weight =
+                intro.audio_weight() * self.app_overlay.intensity_multipliers().attractor_strength;
+            let strengths = self.app_overlay.attractor_strengths();
+            let weighted = |attractor: Vector4, strength: f32| -> [f32; 4] {
+                Vector4::new(
+                    attractor.x,
+                    attractor.y,
+                    attractor.z,
+                    attractor.w * audio_weight * strength,
+                )
+                .into()
+            };
+            let big_boomer = match intro.burst() {
+                Some(strength) => [0., 0., 0., strength],
+                None => weighted(self.audio.state.big_boomer, strengths.bass),
+            };
+            let weigh_all = |attractors: &[Vector4], strength: f32| {
+                attractors
+                    .iter()
+                    .map(|&attractor| weighted(attractor, strength))
+                    .collect::<Vec<_>>()
+            };
+            let big_boomers = [
+                vec![big_boomer],
+                weigh_all(&self.audio.state.extra_big_boomers, strengths.bass),
+            ]
+            .concat();
+            let curl_attractors = weigh_all(&self.audio.state.curl_attractors, strengths.mids);
+            let mut attractors = weigh_all(&self.audio.state.attractors, strengths.high);
+            attractors.truncate(audio::MAX_HIGH_ATTRACTORS);
+            attractors.push(cursor_attractor);
+
+            // Fill the leading slots of each of the shader's arrays, which only reads as many as are counted
+            #[allow(clippy::cast_possible_truncation)]
+            let fill = |slots: &mut [[f32; 4]], values: &[[f32; 4]]| {
+                let count = values.len().min(slots.len());
+                slots[..count].copy_from_slice(&values[..count]);
+                count as u32
+            };
+            let mut particle_attractors = engine::ParticleAttractors {
+                big_boomers: [[0.; 4]; audio::MAX_BASS_ATTRACTORS],
+                curl_attractors: [[0.; 4]; audio::MAX_MIDS_ATTRACTORS],
+                attractors: [[0.; 4]; audio::MAX_HIGH_ATTRACTORS + 1],
+                big_boomer_count: 0,
+                curl_attractor_count: 0,
+                attractor_count: 0,
+            };
+            particle_attractors.big_boomer_count =
+                fill(&mut particle_attractors.big_boomers, &big_boomers);
+            particle_attractors.curl_attractor_count =
+                fill(&mut particle_attractors.curl_attractors, &curl_attractors);
+            particle_attractors.attractor_count =
+                fill(&mut particle_attractors.attractors, &attractors);
 
+            let compute = engine::ParticleComputePushConstants {
                 time: self.audio.state.play_time,
-                delta_time,
-                width,
-                height,
+                delta_time: if intro.holding() { 0. } else { delta_time },
+                aspect_ratio,
                 fix_particles: u32::from(self.game_state.fix_particles == ParticleTension::Spring),
+                inertia: self.window_inertia_to_world(aspect_ratio).into(),
                 use_third_dimension: u32::from(self.game_state.particles_are_3d),
+                physics_mode: self.game_state.runtime_constants.physics_mode as u32,
             };
 
+            // Only send the echoes which are still visible.
+            let show_echoes = self.app_overlay.kick_echoes();
+            let echoes = self.audio.state.kick_echoes;
             let vertex = engine::ParticleVertexPushConstants {
                 quaternion: self.game_state.camera_quaternion.inv().into(),
+                camera_position: self.particle_camera_position(orbit_distance).into(),
+                kick_echoes: echoes.map(|echo| {
+                    let Vector3 { x, y, z, .. } = echo.position;
+                    let visible = show_echoes && echo.age < KICK_ECHO_LIFETIME;
+                    [x, y, z, if visible { echo.strength } else { 0. }]
+                }),
+                kick_ages: echoes.map(|echo| echo.age),
                 time: self.audio.state.play_time,
                 alternate_colors: match self.game_state.alternate_colors {
                     AlternateColors::Inverse => 1,
                     AlternateColors::Normal => 0,
                 },
                 use_third_dimension: u32::from(self.game_state.particles_are_3d),
+                opacity: self.audio.state.balance.particle_opacity,
+                alpha: if self.app_config.particle_blending == ParticleBlending::Alpha {
+                    self.app_config.particle_alpha
+                } else {
+                    1.
+                },
+                sprite_shape: self.app_config.sprite_shape as u32,
             };
 
-            Some((compute, vertex))
+            // Additional particle systems feel the same attractors, but keep their own projection and physics.
+            let particle_data = std::iter::once(Some((compute, vertex)))
+                .chain(self.app_overlay.particle_systems().iter().map(|system| {
+                    (!system.hidden).then(|| {
+                        let use_third_dimension = u32::from(system.third_dimension);
+                        (
+                            engine::ParticleComputePushConstants {
+                                fix_particles: u32::from(system.jello),
+                                use_third_dimension,
+                                physics_mode: system.physics_mode as u32,
+                                ..compute
+                            },
+                            engine::ParticleVertexPushConstants {
+                                use_third_dimension,
+                                ..vertex
+                            },
+                        )
+                    })
+                }))
+                .collect();
+            (particle_data, Some(particle_attractors))
         } else {
-            None
+            (Vec::new(), None)
         };
 
         // Create fractal data.
-        let fractal_data = engine::FractalPushConstants {
+        let kaleidoscope = self.app_overlay.kaleidoscope();
+        let mut fractal_data = engine::FractalPushConstants {
             quaternion: self.game_state.camera_quaternion.into(),
+            camera_position: self.camera_position(orbit_distance).into(),
 
             reactive_bass: self.audio.state.local_reactive_bass.into(),
             reactive_mids: self.audio.state.local_reactive_mids.into(),
@@ -809,38 +2099,248 @@ impl FractalSugar {
             smooth_high: self.audio.state.local_smooth_high.into(),
 
             time: self.audio.state.play_time,
-            kaleidoscope: self.game_state.kaleidoscope.powf(0.65),
-            orbit_distance: if self.game_state.runtime_constants.render_particles
+            kaleidoscope: (self.game_state.kaleidoscope.powf(0.65)
+                * self.app_overlay.intensity_multipliers().kaleidoscope_max)
+                .min(1.),
+            kaleidoscope_segments: kaleidoscope.segments,
+            kaleidoscope_rotation: self.game_state.kaleidoscope_rotation,
+            kaleidoscope_axis: kaleidoscope.mirror_axis.to_radians(),
+            orbit_distance,
+            jitter_x: 0.,
+            jitter_y: 0.,
+        };
+
+        // Offset the fractal by a sub-pixel amount each frame, cycling through a low-discrepancy sequence.
+        let temporal_antialiasing = self.app_overlay.temporal_antialiasing();
+        if temporal_antialiasing {
+            let index = self.game_state.taa_frame + 1;
+            fractal_data.jitter_x = (halton(index, 2) - 0.5) * 2. / viewport.extent[0];
+            fractal_data.jitter_y = (halton(index, 3) - 0.5) * 2. / viewport.extent[1];
+        }
+
+        // Describe the camera motion since the last frame, allowing the fractal history to be reprojected.
+        let temporal_resolve = {
+            let fov_y = self
+                .engine
+                .app_constants()
+                .read()
+                .unwrap()
+                .vertical_fov
+                .tan();
+            engine::TemporalResolvePushConstants {
+                quaternion: self.game_state.camera_quaternion.into(),
+                previous_quaternion: self.game_state.previous_camera_quaternion.into(),
+                viewport: [
+                    viewport.offset[0],
+                    viewport.offset[1],
+                    viewport.extent[0],
+                    viewport.extent[1],
+                ],
+                orbit_distance,
+                fov_x: fov_y * aspect_ratio,
+                fov_y,
+
+                // The history is reprojected by assuming the camera orbits the origin, which a free-flying camera does not.
+                history_weight: if temporal_antialiasing && self.game_state.free_fly.is_none() {
+                    TAA_HISTORY_WEIGHT
+                } else {
+                    0.
+                },
+                fade: self.game_state.performer.fade(),
+                freeze: u32::from(self.game_state.performer.freeze),
+            }
+        };
+
+        // Only visible particles may glow.
+        let bloom = {
+            let config = self.app_overlay.bloom();
+            ParticleBloom {
+                enabled: config.enabled && self.game_state.runtime_constants.render_particles,
+                ..config
+            }
+        };
+
+        // Depth-of-field only applies to visible particles in a 3D projection.
+        // Trails fade exponentially with time, so that their length is the same at any frame rate
+        let depth_of_field = {
+            let config = self.app_overlay.depth_of_field();
+            let enabled = config.enabled
+                && self.game_state.runtime_constants.render_particles
+                && self.game_state.particles_are_3d;
+            let trails = self.app_overlay.particle_trails();
+            engine::DepthOfFieldPushConstants {
+                aperture: if enabled { config.aperture } else { 0. },
+                focus_distance: self.game_state.focus_distance,
+                trail_decay: if trails.enabled && self.game_state.runtime_constants.render_particles
+                {
+                    (-delta_time / trails.length).exp()
+                } else {
+                    0.
+                },
+                bloom_intensity: if bloom.enabled { bloom.intensity } else { 0. },
+            }
+        };
+
+        // Colors are encoded as sRGB unless the surface encodes them, so must be decoded to be mapped.
+        let tone_map = {
+            let config = self.app_overlay.tone_mapping();
+            engine::ToneMapPushConstants {
+                exposure: config.exposure,
+                operator_id: config.operator as u32,
+                srgb_encoded: u32::from(!self.engine.srgb_surface()),
+            }
+        };
+
+        // Particles are respawned on the surface of the fractal being drawn, which the empty fractal has none of.
+        // The emission follows the main particles' simulation time, so that it pauses with them
+        let runtime_constants = &self.game_state.runtime_constants;
+        let fractal_surface = particle_data
+            .first()
+            .and_then(Option::as_ref)
+            .filter(|_| {
+                runtime_constants.surface_emission > 0.
+                    && runtime_constants.distance_estimator_id != 0
+            })
+            .map(|(compute, _)| engine::FractalSurfacePushConstants {
+                reactive_bass: fractal_data.reactive_bass,
+                reactive_mids: fractal_data.reactive_mids,
+                reactive_high: fractal_data.reactive_high,
+                smooth_bass: fractal_data.smooth_bass,
+                smooth_mids: fractal_data.smooth_mids,
+                smooth_high: fractal_data.smooth_high,
+                time: fractal_data.time,
+                delta_time: compute.delta_time,
+                fractal_scale: orbit_distance / PARTICLE_CAMERA_ORBIT.z,
+                seed: fractal_data.time.to_bits(),
+                first: 0,
+            });
+
+        DrawData {
+            particle_data,
+            particle_attractors,
+            fractal_data,
+            fractal_surface,
+            particle_repulsion: self.app_overlay.particle_repulsion(),
+            bloom,
+            depth_of_field,
+            tone_map,
+            temporal_resolve,
+        }
+    }
+
+    // The distance of the fractal camera from the origin while orbiting, which also scales the fractal against the particles.
+    fn orbit_distance(&self) -> f32 {
+        let pump = self.app_overlay.sidechain_pump();
+        let pump_level = if pump.enabled {
+            self.audio.state.pump.level
+        } else {
+            0.
+        };
+        self.audio.state.balance.orbit_scale
+            * (1. - pump.orbit * pump_level)
+            * self.game_state.camera_animation.orbit_scale
+            * if self.game_state.runtime_constants.render_particles
                 && self.game_state.particles_are_3d
             {
                 1.385
             } else {
                 1.
-            },
-        };
+            }
+    }
 
-        DrawData {
-            particle_data,
-            fractal_data,
+    // The position of the fractal camera, either flown freely or on its orbit about the origin.
+    fn camera_position(&self, orbit_distance: f32) -> Vector3 {
+        match &self.game_state.free_fly {
+            Some(free_fly) => free_fly.position(),
+            None => {
+                self.game_state
+                    .camera_quaternion
+                    .rotate_point(Vector3::new(0., 0., orbit_distance))
+            }
         }
     }
 
+    // The position of the particle camera, which follows the fractal camera at the scale of the particles' orbit.
+    fn particle_camera_position(&self, orbit_distance: f32) -> Vector3 {
+        (PARTICLE_CAMERA_ORBIT.z / orbit_distance) * self.camera_position(orbit_distance)
+    }
+
     // Use game state to correctly map positions from screen space to world.
-    fn screen_position_to_world(
-        &self,
-        dimensions: PhysicalSize<u32>,
-        aspect_ratio: f32,
-    ) -> Vector3 {
-        #[allow(clippy::cast_lossless)]
+    // The cursor is measured relative to the viewport, so letterboxed content maps the same as a full window.
+    // Measure the acceleration of the window's center across the screen, in content coordinates, so that
+    // the particles slosh against it like water in a carried bowl.
+    #[allow(clippy::cast_precision_loss)]
+    fn update_window_inertia(&mut self, delta_time: f32) {
+        let size = self.window_state.size;
+        let center = self.window_state.position.map(|p| {
+            Vector2::new(
+                p.x as f32 + 0.5 * size.width as f32,
+                p.y as f32 + 0.5 * size.height as f32,
+            )
+        });
+        let extent = self.engine.particle_viewport().extent;
+        let velocity = match (center, self.game_state.window_center) {
+            (Some(center), Some(previous)) => {
+                let d = center - previous;
+                Vector2::new(2. * d.x / extent[0], 2. * d.y / extent[1]).scale(1. / delta_time)
+            }
+            _ => Vector2::default(),
+        };
+        self.game_state.window_center = center;
+
+        // Smooth the velocity since window managers deliver motion in uneven bursts.
+        let previous_velocity = self.game_state.window_velocity;
+        let smooth = 1. - (-WINDOW_VELOCITY_SMOOTHING * delta_time).exp();
+        self.game_state.window_velocity += smooth * (velocity - previous_velocity);
+
+        // Particles feel the opposite of the window's acceleration, limited so a window snapping into place does not scatter them.
+        let mut inertia = (-WINDOW_INERTIA_SCALE / delta_time)
+            * (self.game_state.window_velocity - previous_velocity);
+        let magnitude = (inertia.x * inertia.x + inertia.y * inertia.y).sqrt();
+        if magnitude > MAX_WINDOW_INERTIA {
+            inertia.scale_self(MAX_WINDOW_INERTIA / magnitude);
+        }
+        self.game_state.window_inertia = inertia;
+    }
+
+    // Map the window's inertia onto the plane facing the camera when particles are 3D.
+    fn window_inertia_to_world(&self, aspect_ratio: f32) -> Vector3 {
+        let Vector2 { x, y } = self.game_state.window_inertia;
+        if self.game_state.particles_are_3d {
+            let fov_y = self
+                .engine
+                .app_constants()
+                .read()
+                .unwrap()
+                .vertical_fov
+                .tan();
+            let fov_x = fov_y * aspect_ratio;
+            self.game_state
+                .camera_quaternion
+                .rotate_point(PARTICLE_CAMERA_ORBIT.z * Vector3::new(x * fov_x, y * fov_y, 0.))
+        } else {
+            Vector3::new(x, y, 0.)
+        }
+    }
+
+    fn screen_position_to_world(&self, aspect_ratio: f32) -> Vector3 {
         #[allow(clippy::cast_possible_truncation)]
-        fn normalize_cursor(p: f64, max: u32) -> f32 {
-            (2. * (p / max as f64) - 1.) as f32
+        fn normalize_cursor(p: f64, offset: f32, extent: f32) -> f32 {
+            (2. * ((p - f64::from(offset)) / f64::from(extent)) - 1.) as f32
         }
-        let x_norm = normalize_cursor(self.game_state.cursor_position.x, dimensions.width);
-        let y_norm = normalize_cursor(self.game_state.cursor_position.y, dimensions.height);
+        let viewport = self.engine.particle_viewport();
+        let x_norm = normalize_cursor(
+            self.game_state.cursor_position.x,
+            viewport.offset[0],
+            viewport.extent[0],
+        );
+        let y_norm = normalize_cursor(
+            self.game_state.cursor_position.y,
+            viewport.offset[1],
+            viewport.extent[1],
+        );
 
         if self.game_state.particles_are_3d && self.game_state.cursor_force != 0. {
-            const PARTICLE_CAMERA_ORBIT: Vector3 = Vector3::new(0., 0., 1.75); // Keep in sync with orbit of `particles.vert`.
             const PERSPECTIVE_DISTANCE: f32 = 1.35;
             let fov_y = self
                 .engine
@@ -855,16 +2355,435 @@ impl FractalSugar {
             let mut v = self.game_state.camera_quaternion.rotate_point(
                 PERSPECTIVE_DISTANCE * Vector3::new(x_norm * fov_x, y_norm * fov_y, -1.),
             );
-            v += self
-                .game_state
-                .camera_quaternion
-                .rotate_point(PARTICLE_CAMERA_ORBIT);
+            v += self.particle_camera_position(self.orbit_distance());
             Vector3::new(v.x, v.y, v.z)
         } else {
             Vector3::new(x_norm, y_norm, 0.)
         }
     }
 
+    // Helper to toggle the direction the kaleidoscope effect is animating towards.
+    fn toggle_kaleidoscope(&mut self) {
+        use KaleidoscopeDirection::{Backward, BackwardComplete, Forward, ForwardComplete};
+        self.game_state.kaleidoscope_dir = match self.game_state.kaleidoscope_dir {
+            Forward | ForwardComplete => Backward,
+            Backward | BackwardComplete => Forward,
+        }
+    }
+
+    // Whether the kaleidoscope effect is on, or turning on.
+    fn kaleidoscope_enabled(&self) -> bool {
+        matches!(
+            self.game_state.kaleidoscope_dir,
+            KaleidoscopeDirection::Forward | KaleidoscopeDirection::ForwardComplete
+        )
+    }
+
+    // Helper to capture the complete visual state, as saved to a snapshot.
+    fn current_look(&self) -> snapshots::Look {
+        snapshots::Look {
+            color_scheme: self.game_state.color_scheme_index,
+            fractal: self.game_state.runtime_constants.distance_estimator_id,
+            parameters: self.app_overlay.parameters(),
+            kaleidoscope: self.kaleidoscope_enabled(),
+            particles_are_3d: self.game_state.particles_are_3d,
+            intensity: self.app_overlay.intensity(),
+            key_hue: self.app_overlay.key_hue().enabled,
+            sidechain_pump: self.app_overlay.sidechain_pump().enabled,
+        }
+    }
+
+    // Blend into the next scene of the attract mode whenever it is due, as though it were a recalled snapshot.
+    // Rendering a poster or export is not interactive, and a show is left to play, so it never begins during either
+    fn update_attract_mode(&mut self, delta_time: f32) {
+        if self.poster.is_some() || self.export.is_some() {
+            return;
+        }
+        if self.show.started() {
+            self.attract_mode.interact();
+            return;
+        }
+        let was_active = self.attract_mode.active();
+        let current = self.current_look();
+        let Some(scene) = self.attract_mode.update(
+            &self.app_config.attract_mode,
+            current,
+            self.color_schemes.len(),
+            delta_time,
+        ) else {
+            return;
+        };
+        if !was_active {
+            log!(
+                "Attract mode started after {} idle seconds",
+                self.app_config.attract_mode.idle_seconds
+            );
+        }
+        self.stop_color_keyframes();
+        self.snapshots.blend(
+            current,
+            scene,
+            self.app_config.attract_mode.transition_seconds,
+        );
+    }
+
+    // Blend into the scene of the show, as though it were a recalled snapshot, and begin its camera path.
+    // Settings the scene leaves unset keep their current state
+    fn begin_scene(&mut self, index: usize) {
+        let scene = &self.app_config.scenes[index];
+        log!("Scene {}: {}", index + 1, scene.name);
+
+        // Color schemes may have been renamed or removed through the overlay since the show was configured.
+        let current = self.current_look();
+        let color_scheme = match &scene.color_scheme {
+            Some(name) => self
+                .color_scheme_names
+                .iter()
+                .position(|n| n == name)
+                .unwrap_or_else(|| {
+                    log!(
+                        "Scene `{}` keeps the color scheme, since `{name}` no longer exists",
+                        scene.name
+                    );
+                    current.color_scheme
+                }),
+            None => current.color_scheme,
+        };
+        let look = snapshots::Look {
+            color_scheme,
+            fractal: scene.fractal.unwrap_or(current.fractal),
+            kaleidoscope: scene.kaleidoscope.unwrap_or(current.kaleidoscope),
+            particles_are_3d: scene.third_dimension.unwrap_or(current.particles_are_3d),
+            ..current
+        };
+        if let Some(mode) = scene.physics_mode {
+            self.game_state.runtime_constants.physics_mode = mode;
+        }
+        if let Some(path) = scene.camera_path.as_ref().and_then(|name| {
+            self.app_config
+                .camera_paths
+                .iter()
+                .find(|p| p.name == *name)
+        }) {
+            self.game_state.free_fly = None;
+            self.game_state
+                .camera_animation
+                .play(path, self.game_state.camera_quaternion);
+        }
+
+        let transition = scene.transition();
+        self.stop_color_keyframes();
+        self.snapshots.blend(current, look, transition);
+    }
+
+    // Apply the blend of any snapshot being recalled, switching the settings which cannot be blended halfway through.
+    // The color schemes are blended throughout, and continue from the nearer one as with the crossfader
+    fn update_snapshot_recall(&mut self, delta_time: f32) {
+        let Some((from, to, t)) = self.snapshots.update(delta_time) else {
+            return;
+        };
+        self.game_state.scheme_transition = None;
+        let look = snapshots::Look::lerp(&from, &to, t);
+        self.app_overlay.set_look(&mut self.engine, &look);
+        if look.fractal != self.game_state.runtime_constants.distance_estimator_id {
+            self.set_distance_estimate_id(look.fractal);
+        }
+        if look.kaleidoscope != self.kaleidoscope_enabled() {
+            self.toggle_kaleidoscope();
+        }
+        self.game_state.particles_are_3d = look.particles_are_3d;
+
+        self.engine.update_color_scheme(Scheme::lerp(
+            &self.color_schemes[from.color_scheme],
+            &self.color_schemes[to.color_scheme],
+            t,
+        ));
+        if look.color_scheme != self.game_state.color_scheme_index {
+            self.game_state.color_scheme_index = look.color_scheme;
+            self.publish_color_scheme_changed();
+        }
+    }
+
+    // Helper to start a timelapse, or to stop the running one.
+    fn toggle_timelapse(&mut self) {
+        if let Some(timelapse) = self.timelapse.take() {
+            log!(
+                "Stopped the timelapse after saving {} frames to `{}`",
+                timelapse.frame_count(),
+                timelapse.directory().display()
+            );
+            return;
+        }
+
+        // Frames are read back at the virtual camera's resolution while it is enabled.
+        let config = &self.app_config.timelapse;
+        let extent = self.engine.frame_capture_extent().unwrap_or_else(|| {
+            let extent = [config.width, config.height];
+            self.engine.enable_frame_capture(extent);
+            extent
+        });
+        match timelapse::Timelapse::start(config, extent) {
+            Ok(timelapse) => self.timelapse = Some(timelapse),
+            Err(e) => log!("Failed to start a timelapse: {e:?}"),
+        }
+    }
+
+    // Helper to save the next rendered frame as a screenshot.
+    fn take_screenshot(&mut self) {
+        // Capture the whole window, unless frames are already captured for the virtual camera or a timelapse.
+        if self.engine.frame_capture_extent().is_none() {
+            let size = self.engine.window().inner_size();
+            if size.width == 0 || size.height == 0 {
+                return;
+            }
+            self.engine.enable_frame_capture([size.width, size.height]);
+        }
+        self.engine.request_frame_capture();
+        self.screenshots.request();
+    }
+
+    // Helper to start recording a clip, or to stop the recording one and encode what was recorded.
+    fn toggle_clip(&mut self) {
+        if let Some(clip) = &mut self.clip {
+            clip.stop();
+            return;
+        }
+
+        // Clips are downsampled from the captured frames, which span the whole window unless already enabled.
+        let extent = match self.engine.frame_capture_extent() {
+            Some(extent) => extent,
+            None => {
+                let size = self.engine.window().inner_size();
+                if size.width == 0 || size.height == 0 {
+                    return;
+                }
+                let extent = [size.width, size.height];
+                self.engine.enable_frame_capture(extent);
+                extent
+            }
+        };
+        match clip::ClipRecorder::start(
+            &self.app_config.clip,
+            self.app_config.timelapse.directory.as_deref(),
+            extent,
+        ) {
+            Ok(clip) => self.clip = Some(clip),
+            Err(e) => log!("Failed to start recording a clip: {e:?}"),
+        }
+    }
+
+    // Apply the knobs, faders, and pads of any MIDI controller moved since the last frame.
+    fn apply_midi_controls(&mut self, control_flow: &mut ControlFlow) {
+        let Some(controls) = self.midi.as_mut().map(midi::MidiController::take) else {
+            return;
+        };
+        for control in controls {
+            match control {
+                midi::Control::Action(action) => self.handle_action(action, control_flow),
+
+                // The kaleidoscope is held where it is set, rather than animating to either extreme.
+                midi::Control::Variable {
+                    name,
+                    position,
+                    range,
+                } if name == midi::KALEIDOSCOPE_VARIABLE => {
+                    let amount = range
+                        .map_or(position, |(min, max)| min + position * (max - min))
+                        .clamp(0., 1.);
+                    self.game_state.kaleidoscope = amount;
+                    self.game_state.kaleidoscope_dir = if amount > 0. {
+                        KaleidoscopeDirection::ForwardComplete
+                    } else {
+                        KaleidoscopeDirection::BackwardComplete
+                    };
+                }
+                midi::Control::Variable {
+                    name,
+                    position,
+                    range,
+                } => {
+                    self.app_overlay.set_variable_position(
+                        &mut self.engine,
+                        &name,
+                        position,
+                        range,
+                    );
+                }
+            }
+        }
+    }
+
+    // Helper to fly the camera freely from wherever it is orbiting, or to return it to its orbit.
+    fn toggle_free_fly(&mut self) {
+        if self.game_state.free_fly.take().is_some() {
+            log!("Free-fly camera disabled");
+            return;
+        }
+
+        // Take off from wherever any camera path has reached, which then stops rather than later resuming.
+        let position = self.camera_position(self.orbit_distance());
+        self.game_state.camera_animation = camera_animation::CameraAnimation::default();
+        self.game_state.free_fly = Some(free_fly::FreeFly::new(
+            position,
+            self.game_state.camera_quaternion,
+        ));
+        log!("Free-fly camera enabled");
+    }
+
+    // Helper to lock the cursor in place and hide it for mouse-look, or to release it.
+    // Platforms which cannot lock the cursor confine it to the window instead
+    fn set_cursor_grab(&mut self, grab: bool) {
+        let window = self.engine.window();
+        let result = if grab {
+            window
+                .set_cursor_grab(CursorGrabMode::Locked)
+                .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+        } else {
+            window.set_cursor_grab(CursorGrabMode::None)
+        };
+        if let Err(e) = result {
+            log!("Failed to change the cursor grab: {e}");
+        }
+        window.set_cursor_visible(!grab);
+        self.game_state.is_cursor_visible = !grab;
+        self.window_state.cursor_grabbed = grab;
+    }
+
+    // Helper to display the next color scheme in the list.
+    fn next_color_scheme(&mut self) {
+        self.stop_color_keyframes();
+        self.game_state.color_scheme_index =
+            (self.game_state.color_scheme_index + 1) % self.color_schemes.len();
+        self.start_scheme_transition();
+        self.publish_color_scheme_changed();
+    }
+
+    // Helper to display the previous color scheme in the list.
+    fn previous_color_scheme(&mut self) {
+        self.stop_color_keyframes();
+        let count = self.color_schemes.len();
+        self.game_state.color_scheme_index =
+            (self.game_state.color_scheme_index + count - 1) % count;
+        self.start_scheme_transition();
+        self.publish_color_scheme_changed();
+    }
+
+    // Helper to begin blending from the displayed colors to the selected color scheme.
+    // A transition already in progress continues from wherever its blend has reached
+    fn start_scheme_transition(&mut self) {
+        if self.app_config.scheme_transition > 0. {
+            self.game_state.scheme_transition = Some(SchemeTransition {
+                from: self.engine.color_scheme(),
+                elapsed: 0.,
+            });
+        } else {
+            self.engine
+                .update_color_scheme(self.color_schemes[self.game_state.color_scheme_index]);
+        }
+    }
+
+    // Helper to advance any transition between color schemes, easing in and out of the blend.
+    fn update_scheme_transition(&mut self, delta_time: f32) {
+        let Some(transition) = &mut self.game_state.scheme_transition else {
+            return;
+        };
+        transition.elapsed += delta_time;
+        let t = (transition.elapsed / self.app_config.scheme_transition).min(1.);
+        self.engine.update_color_scheme(Scheme::lerp(
+            &transition.from,
+            &self.color_schemes[self.game_state.color_scheme_index],
+            t * t * (3. - 2. * t),
+        ));
+        if t >= 1. {
+            self.game_state.scheme_transition = None;
+        }
+    }
+
+    // Helper to display a blend of the two crossfaded schemes, where a position of zero is entirely scheme A.
+    fn apply_scheme_crossfade(&mut self, position: f32) {
+        self.stop_color_keyframes();
+        self.game_state.scheme_transition = None;
+        let crossfade = self.app_overlay.scheme_crossfade();
+        self.engine.update_color_scheme(Scheme::lerp(
+            &self.color_schemes[crossfade.scheme_a],
+            &self.color_schemes[crossfade.scheme_b],
+            position,
+        ));
+
+        // Switching schemes continues from whichever the blend is nearer to.
+        let nearest = if position < 0.5 {
+            crossfade.scheme_a
+        } else {
+            crossfade.scheme_b
+        };
+        if nearest != self.game_state.color_scheme_index {
+            self.game_state.color_scheme_index = nearest;
+            self.publish_color_scheme_changed();
+        }
+    }
+
+    // Helper to update the references to color schemes after the list was edited through the overlay.
+    fn apply_color_scheme_edit(&mut self, edit: ColorSchemeEdit) {
+        match edit {
+            ColorSchemeEdit::Renamed => self
+                .snapshots
+                .rename_color_schemes(&self.color_scheme_names),
+            ColorSchemeEdit::Removed(index) => {
+                let keyframes = &mut self.app_config.color_keyframes;
+                keyframes.retain(|keyframe| keyframe.scheme_index != index);
+                for keyframe in keyframes {
+                    if keyframe.scheme_index > index {
+                        keyframe.scheme_index -= 1;
+                    }
+                }
+                self.snapshots
+                    .remove_color_scheme(index, &self.color_scheme_names);
+            }
+        }
+        self.publish_color_scheme_changed();
+    }
+
+    // Helper to notify external tools of the newly displayed color scheme.
+    fn publish_color_scheme_changed(&self) {
+        let index = self.game_state.color_scheme_index;
+        self.events.publish(events::Event::ColorSchemeChanged {
+            index,
+            name: self.color_scheme_names[index].clone(),
+        });
+    }
+
+    // Helper to give manual control of the color scheme back to the user.
+    fn stop_color_keyframes(&mut self) {
+        if self.game_state.follow_color_keyframes && !self.app_config.color_keyframes.is_empty() {
+            log!("Stopped following the color keyframes");
+        }
+        self.game_state.follow_color_keyframes = false;
+    }
+
+    // Helper to write the currently applied configuration back to the config file.
+    fn save_config(&self) {
+        if self.safe_mode {
+            log!("The app config is not saved in safe mode");
+            return;
+        }
+        let config = AppConfig {
+            coloring_mode: self.game_state.runtime_constants.coloring_mode,
+            physics_mode: self.game_state.runtime_constants.physics_mode,
+            particle_containment: self.game_state.runtime_constants.particle_containment,
+            surface_emission: self.game_state.runtime_constants.surface_emission > 0.,
+            display_layout: self.engine.display_layout(),
+            color_scheme_names: self.color_scheme_names.clone(),
+            ..self
+                .app_overlay
+                .applied_config(&self.app_config, &self.color_schemes)
+        };
+        let filepath = self.config_path.as_str();
+        match app_config::save_file(filepath, &config) {
+            Ok(()) => log!("Saved the app config to `{filepath}`"),
+            Err(e) => log!("Failed to save the app config to `{filepath}`: {e:?}"),
+        }
+    }
+
     // Helper to set a new distance estimator ID on CPU and GPU memory.
     fn set_distance_estimate_id(&mut self, id: u32) {
         self.game_state.runtime_constants.distance_estimator_id = id;
@@ -873,6 +2792,103 @@ impl FractalSugar {
             .write()
             .unwrap()
             .distance_estimator_id = id;
+
+        // Each fractal keeps its own tunables, as last edited through the overlay.
+        let tunables = self.app_overlay.fractal_tunables(id);
+        self.game_state.runtime_constants.fractal_tunables = tunables;
+        self.engine.set_fractal_tunables(tunables);
+        self.events.publish(events::Event::FractalChanged { id });
+    }
+
+    // Rotate the hue of the visuals towards the hue mapped to the latest detected key, or back to none if disabled.
+    // The tint is held through silence, when no key can be detected
+    fn update_key_hue(&mut self, delta_time: f32) {
+        use std::f32::consts::{PI, TAU};
+
+        let key_hue = self.app_overlay.key_hue();
+        let target = match (key_hue.enabled, self.audio.state.key) {
+            (true, Some(key)) => key_hue.hues[key.relative_major()].to_radians(),
+            (true, None) => return,
+            (false, _) => 0.,
+        };
+
+        // Turn the shortest way around the hue wheel, skipping the GPU write once settled.
+        let current = self.game_state.runtime_constants.hue_rotation;
+        let difference = (target - current + PI).rem_euclid(TAU) - PI;
+        if difference.abs() < 0.000_1 {
+            return;
+        }
+        let smooth = 1. - (-key_hue.response * delta_time).exp();
+        let hue = (current + smooth * difference).rem_euclid(TAU);
+
+        self.game_state.runtime_constants.hue_rotation = hue;
+        self.engine
+            .runtime_constants_mut()
+            .write()
+            .unwrap()
+            .hue_rotation = hue;
+    }
+
+    // Scale the particles and overlay by the given monitor scale factor, unless the config overrides them.
+    #[allow(clippy::cast_possible_truncation)]
+    fn apply_scale_factor(&mut self, scale_factor: f64) {
+        let scale_factor = scale_factor as f32;
+        let display_scale = self.app_config.display_scale;
+        let point_scale = display_scale.point_size.unwrap_or(scale_factor);
+        self.game_state.runtime_constants.point_scale = point_scale;
+        self.engine
+            .runtime_constants_mut()
+            .write()
+            .unwrap()
+            .point_scale = point_scale;
+        self.app_overlay
+            .set_pixels_per_point(display_scale.overlay.unwrap_or(scale_factor));
+        log!("Display scale factor: {scale_factor}");
+    }
+
+    // Advance the sidechain pump, ducking the fractal's brightness by its level and through any recall switching fractals.
+    // The GPU write is skipped while the brightness is unchanged
+    fn update_sidechain_pump(&mut self, delta_time: f32) {
+        let pump = self.app_overlay.sidechain_pump();
+        self.audio.state.pump.update(&pump, delta_time);
+
+        let pumped = if pump.enabled {
+            1. - pump.brightness * self.audio.state.pump.level
+        } else {
+            1.
+        };
+        let brightness = pumped * self.snapshots.fractal_fade();
+        if brightness == self.game_state.runtime_constants.fractal_brightness {
+            return;
+        }
+        self.game_state.runtime_constants.fractal_brightness = brightness;
+        self.engine
+            .runtime_constants_mut()
+            .write()
+            .unwrap()
+            .fractal_brightness = brightness;
+    }
+
+    // Helper to set a new fractal coloring mode on CPU and GPU memory.
+    fn set_coloring_mode(&mut self, mode: ColoringMode) {
+        self.game_state.runtime_constants.coloring_mode = mode;
+        self.engine
+            .runtime_constants_mut()
+            .write()
+            .unwrap()
+            .coloring_mode = mode as u32;
+    }
+
+    // Particles are neither simulated nor drawn while only the fractal is shown.
+    fn set_display_layout(&mut self, layout: DisplayLayout) {
+        self.engine.set_display_layout(layout);
+        let render_particles = layout != DisplayLayout::FractalOnly;
+        self.game_state.runtime_constants.render_particles = render_particles;
+        self.engine
+            .runtime_constants_mut()
+            .write()
+            .unwrap()
+            .render_particles = u32::from(render_particles);
     }
 }
 
@@ -884,14 +2900,17 @@ impl Default for LocalAudioState {
             latest_volume: 0.,
 
             big_boomer: Vector4::default(),
-            curl_attractors: [Vector4::default(); 2],
-            attractors: [Vector4::default(); 2],
+            extra_big_boomers: Vec::new(),
+            curl_attractors: Vec::new(),
+            attractors: Vec::new(),
 
             // 3D (Fractals).
             reactive_bass: Vector3::default(),
             reactive_mids: Vector3::default(),
             reactive_high: Vector3::default(),
 
+            key: None,
+
             local_volume: 0.,
             local_angular_velocity: Vector4::new(0., 1., 0., 0.),
             local_reactive_bass: Vector3::default(),
@@ -900,6 +2919,13 @@ impl Default for LocalAudioState {
             local_smooth_bass: Vector3::default(),
             local_smooth_mids: Vector3::default(),
             local_smooth_high: Vector3::default(),
+
+            kick_echoes: [KickEcho::default(); KICK_ECHO_COUNT],
+            next_kick_echo: 0,
+
+            balance: balance::Balancer::default(),
+            pump: sidechain::PumpEnvelope::default(),
+            cycler: auto_cycle::SchemeCycler::default(),
         }
     }
 }
@@ -916,11 +2942,27 @@ impl Default for GameState {
             cursor_force_mult: 1.5,
             kaleidoscope: 0.,
             kaleidoscope_dir: KaleidoscopeDirection::BackwardComplete,
+            kaleidoscope_rotation: 0.,
             alternate_colors: AlternateColors::Normal,
             particles_are_3d: false,
             color_scheme_index: 0,
             audio_responsive: true,
             runtime_constants: RuntimeConstants::default(),
+            inspect: InspectState::default(),
+            focus_distance: PARTICLE_CAMERA_ORBIT.z,
+            previous_camera_quaternion: Quaternion::default(),
+            taa_frame: 0,
+            refinement: refinement::StaticSceneDetector::default(),
+            performer: performer::PerformerControls::default(),
+            keyframe_time: 0.,
+            follow_color_keyframes: true,
+            window_center: None,
+            window_velocity: Vector2::default(),
+            window_inertia: Vector2::default(),
+            camera_animation: camera_animation::CameraAnimation::default(),
+            free_fly: None,
+            intro: intro::IntroSequence::default(),
+            scheme_transition: None,
         }
     }
 }
@@ -931,6 +2973,15 @@ impl Default for RuntimeConstants {
         Self {
             render_particles: true,
             distance_estimator_id: 4,
+            coloring_mode: ColoringMode::default(),
+            physics_mode: PhysicsMode::default(),
+            particle_containment: ParticleContainment::default(),
+            surface_emission: 0.,
+            hue_rotation: 0.,
+            point_scale: 1.,
+            fractal_brightness: 1.,
+            color_management: ColorManagement::default(),
+            fractal_tunables: fractals::FRACTALS[4].defaults,
         }
     }
 }
@@ -938,31 +2989,175 @@ impl Default for RuntimeConstants {
 impl RuntimeConstants {
     // Convert from CPU-side runtime constants to GPU-side runtime constants.
     #[must_use]
-    pub fn to_engine_constants(&self, aspect_ratio: f32) -> engine::RuntimeConstants {
+    pub fn to_engine_constants(
+        &self,
+        aspect_ratio: f32,
+        viewport_height: f32,
+        srgb_output: bool,
+    ) -> engine::RuntimeConstants {
         engine::RuntimeConstants {
             aspect_ratio,
             render_particles: u32::from(self.render_particles),
             distance_estimator_id: self.distance_estimator_id,
+            coloring_mode: self.coloring_mode as u32,
+            hue_rotation: self.hue_rotation,
+            point_scale: self.point_scale,
+            fractal_brightness: self.fractal_brightness,
+            viewport_height,
+            srgb_output: u32::from(srgb_output),
+            unmanaged_split: self.color_management.unmanaged_split(),
+            fractal_iterations: self.fractal_tunables.iterations,
+            fractal_bailout: self.fractal_tunables.bailout,
+            fractal_fold_scale: self.fractal_tunables.fold_scale,
+            fractal_color_exponent: self.fractal_tunables.color_exponent,
+            particle_containment: self.particle_containment as u32,
+            surface_emission: self.surface_emission,
         }
     }
 }
 
 const MAX_MESSAGE_BUFFER_COUNT: usize = 4;
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(2);
 impl AudioManager {
-    // Create a default audio input stream and begin processing.
-    pub fn default() -> Self {
+    // Recreate the audio input stream.
+    // A source which cannot be created is left unavailable, reporting itself lost until it is retried or paused
+    pub fn recreate_stream(&mut self) -> anyhow::Result<()> {
+        let (tx, receiver) = crossbeam_channel::bounded(MAX_MESSAGE_BUFFER_COUNT);
+        self.receiver = receiver;
+        self.held_state = None;
+        self.warning = None;
+        self.reconnect_at = None;
+        match Self::create_source(
+            self.source_kind,
+            self.device.as_ref(),
+            &self.stems,
+            self.file_input.as_ref(),
+            tx.clone(),
+            &self.analysis_config,
+        ) {
+            Ok(source) => {
+                self.source = source;
+                log!("Audio source: {}", self.source.description());
+                Ok(())
+            }
+            Err(e) => {
+                self.source = Box::new(audio::SilentSource::unavailable(tx));
+                Err(e)
+            }
+        }
+    }
+
+    // Helper for creating the source of a file if one is given, otherwise the stems if any are configured,
+    // otherwise a single source of the given kind
+    fn create_source(
+        source_kind: audio::SourceKind,
+        device: Option<&audio::AudioDevice>,
+        stems: &[audio::Stem],
+        file_input: Option<&audio::FileInput>,
+        tx: crossbeam_channel::Sender<audio::State>,
+        analysis_config: &audio::SharedAnalysisConfig,
+    ) -> anyhow::Result<Box<dyn audio::AudioSource>> {
+        Ok(if let Some(input) = file_input {
+            audio::create_file_source_and_send(input, tx, analysis_config.clone())
+        } else if stems.is_empty() {
+            audio::create_source_and_send(source_kind, tx, analysis_config.clone(), device)?
+        } else {
+            Box::new(audio::StemSources::new(stems, tx, analysis_config)?)
+        })
+    }
+
+    // Begin without capturing audio, creating the requested kind of source once the stream is recreated.
+    pub fn new(
+        source_kind: audio::SourceKind,
+        stems: Vec<audio::Stem>,
+        file_input: Option<audio::FileInput>,
+        analysis_config: audio::SharedAnalysisConfig,
+    ) -> Self {
         let (tx, receiver) = crossbeam_channel::bounded(MAX_MESSAGE_BUFFER_COUNT);
         Self {
             receiver,
-            capture_stream: audio::process_loopback_audio_and_send(tx),
+            source: Box::new(audio::SilentSource::new(tx)),
+            source_kind,
+            device: None,
+            stems,
+            file_input,
             state: LocalAudioState::default(),
+            analysis_config,
+            held_state: None,
+            warning: None,
+            reconnect_at: None,
+            last_reconnect: None,
         }
     }
 
-    // Recreate the audio input stream.
-    pub fn recreate_stream(&mut self) {
-        let (tx, receiver) = crossbeam_channel::bounded(MAX_MESSAGE_BUFFER_COUNT);
-        self.receiver = receiver;
-        self.capture_stream = audio::process_loopback_audio_and_send(tx);
+    // Replace the current audio input with a new kind of source, which also replaces any stems or file.
+    pub fn set_source(&mut self, source_kind: audio::SourceKind) -> anyhow::Result<()> {
+        self.source_kind = source_kind;
+        self.stems.clear();
+        self.file_input = None;
+        self.recreate_stream()
+    }
+
+    // Select the named device to capture the next time the stream is recreated, returning whether it exists.
+    // The name may also be given as listed, such as `default (in)`, to choose between devices sharing a name.
+    // Selecting an input device switches to microphone capture, and any other device to loopback
+    pub fn select_device(&mut self, device_name: &str) -> bool {
+        let devices = audio::list_devices();
+        let Some(device) = devices
+            .iter()
+            .find(|device| device.to_string() == device_name)
+            .or_else(|| devices.iter().find(|device| device.name == device_name))
+        else {
+            log!("There is no audio device named {device_name:?}");
+            return false;
+        };
+        self.source_kind = if device.is_input() {
+            audio::SourceKind::Microphone
+        } else {
+            audio::SourceKind::Loopback
+        };
+        self.device = Some(device.clone());
+        self.stems.clear();
+        self.file_input = None;
+        true
+    }
+
+    // Replace the current audio input with a capture of the named device, if it exists.
+    pub fn set_device(&mut self, device_name: &str) -> anyhow::Result<()> {
+        if self.select_device(device_name) {
+            self.recreate_stream()?;
+        }
+        Ok(())
+    }
+
+    // Pause the audio input stream, abandoning any pending reconnection.
+    pub fn pause(&mut self) {
+        self.reconnect_at = None;
+        self.source.pause();
+    }
+
+    // Check for any change in the status of the source, returning the warning to show for it, if any.
+    // A lost source is recreated, at most once per interval so that a missing device is not retried every frame
+    pub fn poll_warning(&mut self) -> Option<&'static str> {
+        if let Some(status) = self.source.poll_status() {
+            self.warning = status.warning();
+            if let Some(warning) = self.warning {
+                log!("Audio warning: {warning}");
+            }
+            if status == audio::SourceStatus::Lost && self.reconnect_at.is_none() {
+                let now = Instant::now();
+                self.reconnect_at = Some(
+                    self.last_reconnect
+                        .map_or(now, |last| (last + RECONNECT_INTERVAL).max(now)),
+                );
+            }
+        }
+        if self.reconnect_at.is_some_and(|at| Instant::now() >= at) {
+            self.last_reconnect = Some(Instant::now());
+            if let Err(e) = self.recreate_stream() {
+                log!("Failed to reconnect the audio source: {e:?}");
+            }
+        }
+        self.warning
     }
 }
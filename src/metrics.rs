@@ -0,0 +1,232 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpListener};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use arc_swap::ArcSwap;
+use serde::Serialize;
+
+use crate::engine::GPU_PASS_COUNT;
+
+// The period over which per-frame counters are accumulated before publishing a new snapshot.
+const SAMPLE_PERIOD_SECONDS: f32 = 1.;
+
+// The longest a metrics client may take to send its request or receive the response.
+// Connections are served one at a time, so an idle client must not hold up the others
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+// The number of recent frames whose times are kept for graphing.
+const FRAME_HISTORY_LENGTH: usize = 300;
+
+// A snapshot of the health of the running application.
+#[derive(Clone, Copy, Default, Serialize)]
+pub struct Metrics {
+    pub fps: f32,
+    pub cpu_ms: f32, // The time spent updating and recording each frame before it is submitted
+    pub gpu_ms: Option<f32>,
+    pub gpu_pass_ms: Option<[f32; GPU_PASS_COUNT]>, // In the order of `GPU_PASS_NAMES`
+    pub particle_count: u64,
+    pub audio_update_hz: f32,
+    pub dropped_audio_chunks: u64,
+    pub audio_chunk_frames: usize,
+    pub audio_analysis_size: usize,
+}
+
+// Lock-free handle to the latest metrics snapshot, shared with any readers such as the JSON endpoint.
+pub type SharedMetrics = Arc<ArcSwap<Metrics>>;
+
+// Accumulates per-frame counters and periodically publishes a new metrics snapshot.
+pub struct MetricsCollector {
+    shared: SharedMetrics,
+    period_start: Instant,
+    frame_count: u32,
+    audio_update_count: u32,
+    cpu_ms_total: f32,
+    gpu_ms_total: f32,
+    gpu_pass_ms_total: [f32; GPU_PASS_COUNT],
+    gpu_ms_count: u32,
+    frame_times: FrameHistory,
+}
+
+// A ring buffer of the times between the most recent frames, in milliseconds.
+pub struct FrameHistory {
+    times: VecDeque<f32>,
+    last_frame: Instant,
+}
+
+impl FrameHistory {
+    fn record(&mut self) {
+        let now = Instant::now();
+        if self.times.len() == FRAME_HISTORY_LENGTH {
+            self.times.pop_front();
+        }
+        self.times
+            .push_back(now.duration_since(self.last_frame).as_secs_f32() * 1000.);
+        self.last_frame = now;
+    }
+
+    // The frame times from oldest to newest.
+    pub fn times(&self) -> impl ExactSizeIterator<Item = f32> + '_ {
+        self.times.iter().copied()
+    }
+
+    pub fn max(&self) -> f32 {
+        self.times.iter().copied().fold(0., f32::max)
+    }
+}
+
+impl MetricsCollector {
+    pub fn new() -> Self {
+        Self {
+            shared: Arc::new(ArcSwap::from_pointee(Metrics::default())),
+            period_start: Instant::now(),
+            frame_count: 0,
+            audio_update_count: 0,
+            cpu_ms_total: 0.,
+            gpu_ms_total: 0.,
+            gpu_pass_ms_total: [0.; GPU_PASS_COUNT],
+            gpu_ms_count: 0,
+            frame_times: FrameHistory {
+                times: VecDeque::with_capacity(FRAME_HISTORY_LENGTH),
+                last_frame: Instant::now(),
+            },
+        }
+    }
+
+    // Record that a new audio state was received from the audio thread.
+    pub fn record_audio_update(&mut self) {
+        self.audio_update_count += 1;
+    }
+
+    // Record a presented frame, publishing a new snapshot at the end of each sample period.
+    // The GPU times are those of the latest measured frame, along with each of its passes
+    #[allow(clippy::cast_precision_loss)]
+    pub fn record_frame(
+        &mut self,
+        cpu_ms: f32,
+        gpu_ms: Option<(f32, [f32; GPU_PASS_COUNT])>,
+        particle_count: u64,
+    ) {
+        self.frame_count += 1;
+        self.frame_times.record();
+        self.cpu_ms_total += cpu_ms;
+        if let Some((gpu_ms, gpu_pass_ms)) = gpu_ms {
+            self.gpu_ms_total += gpu_ms;
+            for (total, ms) in self.gpu_pass_ms_total.iter_mut().zip(gpu_pass_ms) {
+                *total += ms;
+            }
+            self.gpu_ms_count += 1;
+        }
+
+        let elapsed = self.period_start.elapsed().as_secs_f32();
+        if elapsed < SAMPLE_PERIOD_SECONDS {
+            return;
+        }
+
+        self.shared.store(Arc::new(Metrics {
+            fps: self.frame_count as f32 / elapsed,
+            cpu_ms: self.cpu_ms_total / self.frame_count as f32,
+            gpu_ms: if self.gpu_ms_count > 0 {
+                Some(self.gpu_ms_total / self.gpu_ms_count as f32)
+            } else {
+                None
+            },
+            gpu_pass_ms: if self.gpu_ms_count > 0 {
+                Some(
+                    self.gpu_pass_ms_total
+                        .map(|total| total / self.gpu_ms_count as f32),
+                )
+            } else {
+                None
+            },
+            particle_count,
+            audio_update_hz: self.audio_update_count as f32 / elapsed,
+            dropped_audio_chunks: crate::audio::dropped_chunk_count(),
+            audio_chunk_frames: crate::audio::latest_chunk_frames(),
+            audio_analysis_size: crate::audio::analysis_size(),
+        }));
+
+        self.period_start = Instant::now();
+        self.frame_count = 0;
+        self.audio_update_count = 0;
+        self.cpu_ms_total = 0.;
+        self.gpu_ms_total = 0.;
+        self.gpu_pass_ms_total = [0.; GPU_PASS_COUNT];
+        self.gpu_ms_count = 0;
+    }
+
+    // The most recently published snapshot.
+    pub fn latest(&self) -> Metrics {
+        **self.shared.load()
+    }
+
+    pub fn shared(&self) -> &SharedMetrics {
+        &self.shared
+    }
+
+    pub fn frame_times(&self) -> &FrameHistory {
+        &self.frame_times
+    }
+}
+
+// Serve the latest metrics as JSON in response to any HTTP request on the given localhost port.
+pub fn spawn_json_endpoint(port: u16, metrics: SharedMetrics) -> std::io::Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    log!("Serving metrics as JSON on http://127.0.0.1:{port}/");
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log!("Failed to accept metrics connection: {e:?}");
+                    continue;
+                }
+            };
+
+            // The request itself is ignored, every request receives the latest metrics.
+            let timeouts = stream
+                .set_read_timeout(Some(CLIENT_TIMEOUT))
+                .and_then(|()| stream.set_write_timeout(Some(CLIENT_TIMEOUT)));
+            if let Err(e) = timeouts {
+                log!("Failed to set metrics connection timeouts: {e:?}");
+                continue;
+            }
+            let mut request = [0u8; 1024];
+            if stream.read(&mut request).is_err() {
+                continue;
+            }
+
+            let body = serde_json::to_string(&**metrics.load())
+                .expect("Metrics must always be serializable");
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            if let Err(e) = stream.write_all(response.as_bytes()) {
+                log!("Failed to send metrics response: {e:?}");
+            }
+        }
+    });
+
+    Ok(())
+}
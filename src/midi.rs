@@ -0,0 +1,190 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crossbeam_channel::Receiver;
+use midir::{Ignore, MidiInputConnection};
+
+use crate::app_config::{Midi, MidiInput, MidiTarget};
+use crate::input::Action;
+
+// Messages waiting to be applied. Knobs send many per second, and the frames apply only the latest of each.
+const MAX_QUEUED_MESSAGES: usize = 256;
+
+// The name this app is known by to the MIDI system.
+const CLIENT_NAME: &str = "fractal_sugar";
+
+// Control changes at or past this value are on, as sent by buttons.
+const CONTROL_ON: u8 = 64;
+
+// The name by which mappings set the kaleidoscope amount, which is not otherwise a runtime variable.
+pub const KALEIDOSCOPE_VARIABLE: &str = "kaleidoscope";
+
+// A MIDI message relevant to the mappings, decoded from its raw bytes.
+#[derive(Clone, Copy)]
+struct Message {
+    channel: u8, // In [1, 16]
+    input: MidiInput,
+    value: u8, // The control's value, or the note's velocity
+}
+
+// A change for the main loop to apply on behalf of the controller.
+pub enum Control {
+    Variable {
+        name: String,
+        position: f32, // In [0, 1]
+        range: Option<(f32, f32)>,
+    },
+    Action(Action),
+}
+
+// Receives the knobs, faders, and pads of a MIDI controller, mapping them to runtime variables and actions.
+pub struct MidiController {
+    rx: Receiver<Message>,
+    config: Midi,
+    values: Vec<Option<u8>>, // The last value of each mapping, detecting buttons being pressed
+    _connection: MidiInputConnection<()>, // Closed when dropped
+}
+
+// Decode a control change or a note on, ignoring other messages. Notes on with zero velocity are notes off.
+fn decode_message(bytes: &[u8]) -> Option<Message> {
+    let [status, number, value] = *bytes else {
+        return None;
+    };
+    let channel = (status & 0x0F) + 1;
+    let input = match status & 0xF0 {
+        0xB0 => MidiInput::Control(number),
+        0x90 if value > 0 => MidiInput::Note(number),
+        _ => return None,
+    };
+    Some(Message {
+        channel,
+        input,
+        value,
+    })
+}
+
+impl MidiController {
+    // Open the input port whose name contains the configured device, or the first port if none is configured.
+    pub fn new(config: Midi) -> anyhow::Result<Self> {
+        let mut midi_in = midir::MidiInput::new(CLIENT_NAME)?;
+        midi_in.ignore(Ignore::All);
+
+        let ports = midi_in.ports();
+        let port = match &config.device {
+            Some(device) => {
+                let device = device.to_lowercase();
+                ports.iter().find(|port| {
+                    midi_in
+                        .port_name(port)
+                        .is_ok_and(|name| name.to_lowercase().contains(&device))
+                })
+            }
+            None => ports.first(),
+        }
+        .ok_or_else(|| match &config.device {
+            Some(device) => anyhow::anyhow!("No MIDI input port is named like `{device}`"),
+            None => anyhow::anyhow!("No MIDI input ports are available"),
+        })?;
+        let port_name = midi_in.port_name(port)?;
+
+        let (tx, rx) = crossbeam_channel::bounded(MAX_QUEUED_MESSAGES);
+        let connection = midi_in
+            .connect(
+                port,
+                CLIENT_NAME,
+                move |_, bytes, _| {
+                    // A full queue means the frames are behind, and later values will follow anyway.
+                    if let Some(message) = decode_message(bytes) {
+                        let _ = tx.try_send(message);
+                    }
+                },
+                (),
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to open MIDI input `{port_name}`: {e}"))?;
+
+        log!("Listening for MIDI input from {port_name}");
+        Ok(Self {
+            rx,
+            values: vec![None; config.mappings.len()],
+            config,
+            _connection: connection,
+        })
+    }
+
+    // The names of the variables the mappings set, to be checked against those which exist.
+    pub fn variable_names(&self) -> impl Iterator<Item = &str> {
+        self.config
+            .mappings
+            .iter()
+            .filter_map(|mapping| match &mapping.target {
+                MidiTarget::Variable { name, .. } => Some(name.as_str()),
+                MidiTarget::Action(_) => None,
+            })
+    }
+
+    // Get the changes received since the last call. Actions are in the order received, followed by the latest
+    // position of each variable
+    pub fn take(&mut self) -> Vec<Control> {
+        let mut controls = Vec::new();
+        let mut positions: Vec<Option<f32>> = vec![None; self.config.mappings.len()];
+        for message in self.rx.try_iter() {
+            for (index, mapping) in self.config.mappings.iter().enumerate() {
+                if mapping.input != message.input
+                    || mapping
+                        .channel
+                        .is_some_and(|channel| channel != message.channel)
+                {
+                    continue;
+                }
+
+                let previous = self.values[index].replace(message.value);
+                match &mapping.target {
+                    MidiTarget::Variable { .. } => {
+                        positions[index] = Some(f32::from(message.value) / 127.);
+                    }
+
+                    // Notes trigger on every press, while controls trigger as a button is pressed down.
+                    MidiTarget::Action(action) => {
+                        let pressed = match message.input {
+                            MidiInput::Note(_) => true,
+                            MidiInput::Control(_) => {
+                                message.value >= CONTROL_ON
+                                    && previous.map_or(true, |value| value < CONTROL_ON)
+                            }
+                        };
+                        if pressed {
+                            controls.push(Control::Action(*action));
+                        }
+                    }
+                }
+            }
+        }
+
+        controls.extend(self.config.mappings.iter().zip(positions).filter_map(
+            |(mapping, position)| match (&mapping.target, position) {
+                (MidiTarget::Variable { name, range }, Some(position)) => Some(Control::Variable {
+                    name: name.clone(),
+                    position,
+                    range: *range,
+                }),
+                _ => None,
+            },
+        ));
+        controls
+    }
+}
@@ -19,9 +19,10 @@
 use std::ops::{Add, AddAssign, Mul, Neg, Sub};
 
 use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
 
 #[repr(C)]
-#[derive(Clone, Copy, Default, Pod, Zeroable)]
+#[derive(Clone, Copy, Default, Pod, Zeroable, Serialize, Deserialize)]
 pub struct Vector4 {
     pub x: f32,
     pub y: f32,
@@ -35,6 +36,12 @@ impl Vector4 {
     pub fn xyz(self) -> Vector3 {
         Vector3::new(self.x, self.y, self.z)
     }
+    pub fn scale(self, s: f32) -> Self {
+        Self::new(self.x * s, self.y * s, self.z * s, self.w * s)
+    }
+    pub fn dot(a: Self, b: Self) -> f32 {
+        a.x * b.x + a.y * b.y + a.z * b.z + a.w * b.w
+    }
 }
 impl Add for Vector4 {
     type Output = Self;
@@ -115,6 +122,28 @@ impl Quaternion {
             self.v.w * q.v.w - Vector3::dot(p_prime, q_prime),
         );
     }
+    // Spherically interpolate between two rotations along the shorter arc, where `t` of zero is entirely `a`.
+    pub fn slerp(a: Self, b: Self, t: f32) -> Self {
+        let mut cos = Vector4::dot(a.v, b.v);
+        let mut b = b.v;
+        if cos < 0. {
+            cos = -cos;
+            b = -b;
+        }
+
+        // Nearly parallel rotations are linearly interpolated to avoid dividing by a vanishing sine.
+        let (weight_a, weight_b) = if cos > 0.9995 {
+            (1. - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((1. - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+        let v = a.v.scale(weight_a) + b.scale(weight_b);
+        Self {
+            v: v.scale(1. / Vector4::dot(v, v).sqrt()),
+        }
+    }
     pub fn rotate_point(&self, p: Vector3) -> Vector3 {
         let q = Vector3::new(self.v.x, self.v.y, self.v.z);
         let temp = Vector3::cross(q, Vector3::cross(q, p) + self.v.w * p);
@@ -140,11 +169,12 @@ impl From<Quaternion> for [f32; 4] {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, Default, Pod, Zeroable)]
+#[derive(Clone, Copy, Default, Pod, Zeroable, Serialize, Deserialize)]
 pub struct Vector3 {
     pub x: f32,
     pub y: f32,
     pub z: f32,
+    #[serde(skip)]
     _w: f32,
 }
 impl Vector3 {
@@ -304,4 +334,17 @@ pub mod helpers {
         let smooth = 1. - (scale).exp();
         *source += smooth * (*target - *source);
     }
+
+    // The `index`-th element of the Halton sequence with the given base, in [0, 1)
+    #[allow(clippy::cast_precision_loss)]
+    pub fn halton(mut index: u32, base: u32) -> f32 {
+        let mut fraction = 1.;
+        let mut result = 0.;
+        while index > 0 {
+            fraction /= base as f32;
+            result += fraction * (index % base) as f32;
+            index /= base;
+        }
+        result
+    }
 }
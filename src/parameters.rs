@@ -0,0 +1,187 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::ops::RangeInclusive;
+
+use crate::engine::ConfigConstants;
+
+const DECIBEL_SCALE: f32 = std::f32::consts::LN_10 / 10.;
+
+// How a parameter is presented, relative to the value used by the shaders.
+#[derive(Clone, Copy)]
+pub enum Unit {
+    Linear,
+    Decibels,         // Presented in decibels, used as a linear scale
+    HalfAngleDegrees, // Presented as an angle in degrees, used as half the angle in radians
+}
+
+impl Unit {
+    pub fn to_shader(self, value: f32) -> f32 {
+        match self {
+            Self::Linear => value,
+            Self::Decibels => (DECIBEL_SCALE * value).exp(),
+            Self::HalfAngleDegrees => value * std::f32::consts::PI / 360.,
+        }
+    }
+    pub fn from_shader(self, value: f32) -> f32 {
+        match self {
+            Self::Linear => value,
+            Self::Decibels => value.ln() / DECIBEL_SCALE,
+            Self::HalfAngleDegrees => value * 360. / std::f32::consts::PI,
+        }
+    }
+}
+
+// The values of a parameter accepted from the config file.
+#[derive(Clone, Copy)]
+pub enum Limits {
+    Finite,
+    Positive,
+    Within(f32, f32), // Inclusive of both bounds
+}
+
+impl Limits {
+    pub fn contains(self, value: f32) -> bool {
+        match self {
+            Self::Finite => value.is_finite(),
+            Self::Positive => value.is_finite() && value > 0.,
+            Self::Within(min, max) => (min..=max).contains(&value),
+        }
+    }
+
+    // Describes the accepted values, as completing "must be".
+    pub fn describe(self) -> String {
+        match self {
+            Self::Finite => String::from("a finite number"),
+            Self::Positive => String::from("a positive number"),
+            Self::Within(min, max) => format!("in [{min}, {max}]"),
+        }
+    }
+}
+
+// A tunable used by the shaders. Each is declared once here, which defines its TOML key,
+// the overlay slider which edits it, and the field of the config constants it is written to
+pub struct Parameter {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub default: f32,
+    pub limits: Limits,
+    pub slider_range: RangeInclusive<f32>, // Within the limits
+    pub unit: Unit,
+    pub destination: fn(&mut ConfigConstants) -> &mut f32,
+}
+
+pub const PARAMETER_COUNT: usize = 6;
+
+// The presented value of each parameter, in the order of `PARAMETERS`.
+pub type ParameterValues = [f32; PARAMETER_COUNT];
+
+// The registry of parameters, in the order they are shown in the overlay.
+pub const PARAMETERS: [Parameter; PARAMETER_COUNT] = [
+    Parameter {
+        key: "audio_scale",
+        label: "audio scale (dB)",
+        default: -20.,
+        limits: Limits::Finite,
+        slider_range: -30.0..=5.,
+        unit: Unit::Decibels,
+        destination: |constants| &mut constants.audio_scale,
+    },
+    Parameter {
+        key: "max_speed",
+        label: "max speed",
+        default: 7.,
+        limits: Limits::Positive,
+        slider_range: 0.1..=10.,
+        unit: Unit::Linear,
+        destination: |constants| &mut constants.max_speed,
+    },
+    Parameter {
+        key: "point_size",
+        label: "point size",
+        default: 2.,
+        limits: Limits::Within(0., 16.),
+        slider_range: 0.0..=8.,
+        unit: Unit::Linear,
+        destination: |constants| &mut constants.point_size,
+    },
+    Parameter {
+        key: "friction_scale",
+        label: "friction scale",
+        default: 1.,
+        limits: Limits::Finite,
+        slider_range: 0.0..=5.,
+        unit: Unit::Linear,
+        destination: |constants| &mut constants.friction_scale,
+    },
+    Parameter {
+        key: "spring_coefficient",
+        label: "spring coefficient",
+        default: 75.,
+        limits: Limits::Finite,
+        slider_range: 0.0..=200.,
+        unit: Unit::Linear,
+        destination: |constants| &mut constants.spring_coefficient,
+    },
+    Parameter {
+        key: "vertical_fov",
+        label: "vertical fov",
+        default: 72.,
+        limits: Limits::Within(-180., 180.),
+        slider_range: 30.0..=105.,
+        unit: Unit::HalfAngleDegrees,
+        destination: |constants| &mut constants.vertical_fov,
+    },
+];
+
+pub fn default_values() -> ParameterValues {
+    PARAMETERS.map(|parameter| parameter.default)
+}
+
+// Write the presented values to the constants used by the shaders.
+pub fn write_constants(values: &ParameterValues, constants: &mut ConfigConstants) {
+    for (parameter, value) in PARAMETERS.iter().zip(values) {
+        *(parameter.destination)(constants) = parameter.unit.to_shader(*value);
+    }
+}
+
+// Read the presented values back from the constants used by the shaders.
+pub fn read_constants(constants: &ConfigConstants) -> ParameterValues {
+    let mut constants = *constants;
+    PARAMETERS.map(|parameter| {
+        parameter
+            .unit
+            .from_shader(*(parameter.destination)(&mut constants))
+    })
+}
+
+// Convert the parameters of the given constants to or from their presented units, as edited by the overlay.
+pub fn constants_to_presentable(mut constants: ConfigConstants) -> ConfigConstants {
+    for parameter in &PARAMETERS {
+        let value = (parameter.destination)(&mut constants);
+        *value = parameter.unit.from_shader(*value);
+    }
+    constants
+}
+pub fn constants_from_presentable(mut constants: ConfigConstants) -> ConfigConstants {
+    for parameter in &PARAMETERS {
+        let value = (parameter.destination)(&mut constants);
+        *value = parameter.unit.to_shader(*value);
+    }
+    constants
+}
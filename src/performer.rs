@@ -0,0 +1,56 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+// Seconds a blackout takes to fade the output to black, or back again once released.
+const BLACKOUT_FADE_SECONDS: f32 = 0.2;
+
+// Emergency controls for live operation, applied at the very end of the render path so that they override any visual.
+// The visualization keeps running behind both, so releasing them returns to the live output
+pub struct PerformerControls {
+    pub blackout: bool, // Fade the output to black and hold it there
+    pub freeze: bool,   // Hold the last presented frame
+    fade: f32,          // The brightness of the output, eased towards the blackout's target
+}
+
+impl Default for PerformerControls {
+    fn default() -> Self {
+        Self {
+            blackout: false,
+            freeze: false,
+            fade: 1.,
+        }
+    }
+}
+
+impl PerformerControls {
+    // Ease the output towards black or full brightness, at a constant rate in real time.
+    pub fn update(&mut self, delta_time: f32) {
+        let target = if self.blackout { 0. } else { 1. };
+        let step = delta_time / BLACKOUT_FADE_SECONDS;
+        self.fade = if self.fade < target {
+            (self.fade + step).min(target)
+        } else {
+            (self.fade - step).max(target)
+        };
+    }
+
+    // The brightness the output is multiplied by.
+    pub fn fade(&self) -> f32 {
+        self.fade
+    }
+}
@@ -0,0 +1,54 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::path::PathBuf;
+
+const APP_DIRECTORY: &str = "fractal_sugar";
+const TOUR_COMPLETED_FILE: &str = "tour_completed";
+
+// The per-user directory for state persisted between launches, if the platform provides one.
+pub fn state_directory() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIRECTORY))
+}
+
+// The per-user directory for data which may be safely discarded, such as compiled pipelines.
+pub fn cache_directory() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join(APP_DIRECTORY))
+}
+
+// The per-user directory for images saved by the app, such as the frames of a timelapse.
+pub fn pictures_directory() -> Option<PathBuf> {
+    dirs::picture_dir().map(|dir| dir.join(APP_DIRECTORY))
+}
+
+// Whether the onboarding tour should be shown, i.e., this is the first launch with persisted state available.
+pub fn is_first_launch() -> bool {
+    state_directory().map_or(false, |dir| !dir.join(TOUR_COMPLETED_FILE).exists())
+}
+
+// Record that the onboarding tour was completed or skipped so that it is not shown again.
+pub fn mark_tour_completed() {
+    let Some(dir) = state_directory() else {
+        return;
+    };
+    if let Err(e) = std::fs::create_dir_all(&dir)
+        .and_then(|()| std::fs::write(dir.join(TOUR_COMPLETED_FILE), b""))
+    {
+        log!("Failed to persist onboarding-tour completion: {e:?}");
+    }
+}
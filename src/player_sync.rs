@@ -0,0 +1,252 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+
+use crate::app_config::{KeyframeClock, KeyframeClockSource};
+
+// How often the player is asked for its position.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// How long to wait for the player to connect or respond.
+const PLAYER_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Drift beyond this many seconds is from a seek or a change of track, so the clock jumps rather than slews.
+const MAX_SLEW_DRIFT: f32 = 1.;
+
+// Rate, per second, at which smaller drift is corrected.
+const DRIFT_CORRECTION_RATE: f32 = 2.;
+
+// The playback state of the external player at the moment it was queried.
+#[derive(Clone, Copy)]
+struct Report {
+    position: f32, // Seconds into the current track
+    playing: bool,
+    received: Instant,
+}
+
+// A clock following the track position reported by an external player, rather than the time since launch.
+// Between reports the clock advances with the frames, and drift from the player is corrected gradually
+pub struct PlayerClock {
+    rx: Receiver<Report>,
+    running: Arc<AtomicBool>,
+    time: f32,
+    playing: bool,
+    drift: f32, // Seconds of drift from the player which have yet to be corrected
+}
+
+impl PlayerClock {
+    // Spawn a thread polling the configured player, or return `None` if the clock follows the time since launch.
+    pub fn new(config: &KeyframeClock) -> Option<Self> {
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+        match config.source {
+            KeyframeClockSource::Launch => return None,
+            KeyframeClockSource::Mpd => {
+                let address = config.mpd_address.clone();
+                log!("Following the track position of MPD at {address}");
+                std::thread::spawn(move || poll_mpd(&address, &tx, &thread_running));
+            }
+            KeyframeClockSource::Mpris => {
+                let player = config.mpris_player.clone();
+                log!(
+                    "Following the track position of MPRIS player {}",
+                    player.as_deref().unwrap_or("(any)")
+                );
+                std::thread::spawn(move || poll_mpris(player.as_deref(), &tx, &thread_running));
+            }
+        }
+
+        Some(Self {
+            rx,
+            running,
+            time: 0.,
+            playing: false,
+            drift: 0.,
+        })
+    }
+
+    // Advance the clock by the frame's duration and correct it towards the latest report, returning the time.
+    pub fn advance(&mut self, delta_time: f32) -> f32 {
+        if self.playing {
+            self.time += delta_time;
+        }
+
+        if let Some(report) = self.rx.try_iter().last() {
+            // Account for the time since the player was queried.
+            self.playing = report.playing;
+            let position = if report.playing {
+                report.position + report.received.elapsed().as_secs_f32()
+            } else {
+                report.position
+            };
+            let drift = position - self.time;
+            if drift.abs() > MAX_SLEW_DRIFT {
+                self.time = position;
+                self.drift = 0.;
+            } else {
+                self.drift = drift;
+            }
+        }
+
+        let correction = self.drift * (1. - (-DRIFT_CORRECTION_RATE * delta_time).exp());
+        self.time += correction;
+        self.drift -= correction;
+        self.time
+    }
+}
+
+impl Drop for PlayerClock {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+// Send the report for the main thread, returning whether polling should continue.
+fn send_report(tx: &Sender<Report>, report: Report) -> bool {
+    !matches!(tx.try_send(report), Err(TrySendError::Disconnected(_)))
+}
+
+// Repeatedly query MPD for its status, reconnecting whenever the connection is lost.
+fn poll_mpd(address: &str, tx: &Sender<Report>, running: &AtomicBool) {
+    let mut failed = false;
+    while running.load(Ordering::Relaxed) {
+        let result = connect_mpd(address).and_then(|mut connection| loop {
+            if !running.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            let report = query_mpd(&mut connection)?;
+            failed = false;
+            if !send_report(tx, report) {
+                return Ok(());
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        });
+        if let Err(e) = result {
+            if !failed {
+                log!("Failed to query MPD at {address}, will keep trying: {e:?}");
+            }
+            failed = true;
+            std::thread::sleep(PLAYER_TIMEOUT);
+        }
+    }
+}
+
+// Connect to MPD and read its greeting.
+fn connect_mpd(address: &str) -> anyhow::Result<BufReader<TcpStream>> {
+    let Some(address) = address.to_socket_addrs()?.next() else {
+        anyhow::bail!("The address did not resolve");
+    };
+    let stream = TcpStream::connect_timeout(&address, PLAYER_TIMEOUT)?;
+    stream.set_read_timeout(Some(PLAYER_TIMEOUT))?;
+    let mut connection = BufReader::new(stream);
+    let mut greeting = String::new();
+    connection.read_line(&mut greeting)?;
+    if !greeting.starts_with("OK MPD") {
+        anyhow::bail!("Unexpected greeting `{}`", greeting.trim_end());
+    }
+    Ok(connection)
+}
+
+// Ask MPD for the playback state and the elapsed time of the current song.
+fn query_mpd(connection: &mut BufReader<TcpStream>) -> anyhow::Result<Report> {
+    connection.get_mut().write_all(b"status\n")?;
+    let mut position = 0.;
+    let mut playing = false;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if connection.read_line(&mut line)? == 0 {
+            anyhow::bail!("The connection was closed");
+        }
+        let line = line.trim_end();
+        if line == "OK" {
+            break;
+        }
+        if let Some(error) = line.strip_prefix("ACK ") {
+            anyhow::bail!("MPD responded with an error: {error}");
+        }
+        match line.split_once(": ") {
+            Some(("state", state)) => playing = state == "play",
+            Some(("elapsed", elapsed)) => position = elapsed.parse()?,
+            _ => {}
+        }
+    }
+    Ok(Report {
+        position,
+        playing,
+        received: Instant::now(),
+    })
+}
+
+// Repeatedly query an MPRIS player, such as Spotify, through `playerctl`.
+// D-Bus is only spoken through the command-line tool, which must be installed
+fn poll_mpris(player: Option<&str>, tx: &Sender<Report>, running: &AtomicBool) {
+    let mut failed = false;
+    while running.load(Ordering::Relaxed) {
+        match query_mpris(player) {
+            Ok(report) => {
+                failed = false;
+                if !send_report(tx, report) {
+                    return;
+                }
+            }
+            Err(e) => {
+                if !failed {
+                    log!("Failed to query the MPRIS player, will keep trying: {e:?}");
+                }
+                failed = true;
+            }
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+// Ask the player for its status and position, the latter of which MPRIS reports in microseconds.
+#[allow(clippy::cast_precision_loss)]
+fn query_mpris(player: Option<&str>) -> anyhow::Result<Report> {
+    let mut command = std::process::Command::new("playerctl");
+    if let Some(player) = player {
+        command.args(["--player", player]);
+    }
+    let output = command
+        .args(["metadata", "--format", "{{status}} {{position}}"])
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "`playerctl` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        );
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let Some((status, position)) = stdout.trim_end().split_once(' ') else {
+        anyhow::bail!("Unexpected output from `playerctl`: {stdout}");
+    };
+    Ok(Report {
+        position: position.parse::<u64>()? as f32 / 1_000_000.,
+        playing: status == "Playing",
+        received: Instant::now(),
+    })
+}
@@ -0,0 +1,179 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::path::PathBuf;
+
+use crate::audio::{self, SharedAnalysisConfig, SourceKind, State};
+use crate::{refinement, timelapse};
+
+// The resolution of a poster when none is given.
+pub const DEFAULT_SIZE: [u32; 2] = [3840, 2160];
+
+// Frames simulated per second of the session, regardless of how long each takes to render.
+const SIMULATION_FRAME_RATE: f32 = 60.;
+
+// The still frame to render, as given on the command line.
+pub struct PosterOptions {
+    pub time: f32, // Seconds into the session
+    pub out: PathBuf,
+    pub audio_file: Option<PathBuf>, // An audio file to analyze, rather than generating the audio
+    pub size: [u32; 2],
+}
+
+enum Phase {
+    Simulating,     // Advancing the session to the poster's time
+    Refining(u32),  // Holding still while the fractal is supersampled, counting the frames held
+    Finished(bool), // Whether the poster was saved
+}
+
+// Renders a session up to a given time with a fixed frame rate, then saves a supersampled frame as a PNG.
+pub struct Poster {
+    options: PosterOptions,
+    states: std::vec::IntoIter<State>,
+    state_interval: f32, // Seconds between consecutive audio states
+    next_state_time: f32,
+    time: f32,
+    phase: Phase,
+}
+
+impl Poster {
+    // Analyze all of the audio leading up to the poster's time, from the audio file if given or else the generated signal.
+    pub fn new(
+        options: PosterOptions,
+        source_kind: SourceKind,
+        analysis_config: SharedAnalysisConfig,
+    ) -> anyhow::Result<Self> {
+        let (sample_rate, samples) = match (&options.audio_file, source_kind) {
+            (Some(path), _) => {
+                let (rate, samples) = audio::file_source::decode(path, Some(options.time))?;
+                if samples.len() < sample_count(options.time, rate) {
+                    log!(
+                        "The audio file `{}` ends before the poster's time, so its last state is held",
+                        path.display()
+                    );
+                }
+                (rate, samples)
+            }
+            (None, SourceKind::Generator(signal)) => {
+                let rate = audio::generator::SAMPLE_RATE;
+                let samples = audio::generator::samples(signal)
+                    .take(sample_count(options.time, rate))
+                    .map(|sample| (sample, sample))
+                    .collect();
+                (rate, samples)
+            }
+            (None, _) => anyhow::bail!(
+                "A poster is rendered offline, so requires `--audio-file` or a test signal for `--audio-source`"
+            ),
+        };
+
+        let states = audio::analyze_offline(sample_rate, samples.into_iter(), analysis_config);
+        let state_interval = audio::analysis_interval(sample_rate);
+        log!(
+            "Analyzed {} audio states for a poster at {} seconds",
+            states.len(),
+            options.time
+        );
+        Ok(Self {
+            options,
+            states: states.into_iter(),
+            state_interval,
+            next_state_time: state_interval,
+            time: 0.,
+            phase: Phase::Simulating,
+        })
+    }
+
+    // Advance to the next frame, returning the seconds it simulates.
+    // Time only passes once the particles are ready, then holds at the poster's time while the fractal is refined
+    pub fn step(&mut self, particles_ready: bool) -> f32 {
+        let frame_time = 1. / SIMULATION_FRAME_RATE;
+        match &mut self.phase {
+            Phase::Simulating if particles_ready => {
+                let remaining = self.options.time - self.time;
+                let delta_time = remaining.min(frame_time);
+                self.time += delta_time;
+                if remaining <= frame_time {
+                    self.phase = Phase::Refining(0);
+                }
+                delta_time
+            }
+            Phase::Refining(frames) => {
+                *frames += 1;
+                0.
+            }
+            _ => 0.,
+        }
+    }
+
+    // The audio states analyzed by the current time which have not yet been applied, in order.
+    pub fn take_due_states(&mut self) -> Vec<State> {
+        let mut due = Vec::new();
+        while self.next_state_time <= self.time {
+            let Some(state) = self.states.next() else {
+                break;
+            };
+            due.push(state);
+            self.next_state_time += self.state_interval;
+        }
+        due
+    }
+
+    // Whether this frame should be captured, since every jitter sample of the fractal has been accumulated.
+    pub fn capture_due(&self) -> bool {
+        matches!(self.phase, Phase::Refining(frames) if frames >= refinement::FRAMES_UNTIL_REFINED)
+    }
+
+    // Save the captured frame, given as tightly packed RGBA rows, as the poster.
+    pub fn save(&mut self, rgba: &[u8]) {
+        let path = &self.options.out;
+        let saved = match timelapse::write_png(path, self.options.size, rgba) {
+            Ok(()) => {
+                log!("Saved the poster to `{}`", path.display());
+                true
+            }
+            Err(e) => {
+                log!("Failed to save the poster `{}`: {e:?}", path.display());
+                false
+            }
+        };
+        self.phase = Phase::Finished(saved);
+    }
+
+    // Whether the poster was saved, once it is finished.
+    pub fn finished(&self) -> Option<bool> {
+        match self.phase {
+            Phase::Finished(saved) => Some(saved),
+            _ => None,
+        }
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        self.options.size
+    }
+}
+
+// The number of samples covering the given time at the given rate.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn sample_count(seconds: f32, sample_rate: u32) -> usize {
+    (seconds * sample_rate as f32).ceil() as usize
+}
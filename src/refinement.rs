@@ -0,0 +1,108 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::engine::{FractalPushConstants, TemporalResolvePushConstants};
+use crate::my_math::helpers::halton;
+
+// The largest change of any fractal parameter between frames for the scene to still be considered still.
+const STILL_TOLERANCE: f32 = 1e-3;
+
+// Frames the scene must stay still before refinement begins, so that brief pauses in motion do not flicker.
+const STILL_FRAMES_BEFORE_REFINING: u32 = 6;
+
+// The length of the jitter sequence cycled through while refining, spreading samples across each pixel.
+const REFINEMENT_JITTER_SAMPLES: u32 = 64;
+
+// Frames the scene must stay still for every jitter sample to have been accumulated once.
+pub const FRAMES_UNTIL_REFINED: u32 = STILL_FRAMES_BEFORE_REFINING + REFINEMENT_JITTER_SAMPLES;
+
+// Limit on the weight of the accumulated history, so that a refined image still responds to gradual changes.
+const MAX_REFINEMENT_WEIGHT: f32 = 0.985;
+
+// Detects when the fractal is nearly unchanged between frames, counting the samples accumulated since it last moved.
+#[derive(Default)]
+pub struct StaticSceneDetector {
+    previous: Option<FractalPushConstants>,
+    still_frames: u32,
+}
+
+// The largest change of any parameter affecting the fractal, ignoring the jitter.
+fn max_difference(a: &FractalPushConstants, b: &FractalPushConstants) -> f32 {
+    // Changing the kaleidoscope's segments refolds the entire view.
+    if a.kaleidoscope_segments != b.kaleidoscope_segments {
+        return f32::INFINITY;
+    }
+
+    let vectors = [
+        (a.quaternion, b.quaternion),
+        (a.camera_position, b.camera_position),
+        (a.reactive_bass, b.reactive_bass),
+        (a.reactive_mids, b.reactive_mids),
+        (a.reactive_high, b.reactive_high),
+        (a.smooth_bass, b.smooth_bass),
+        (a.smooth_mids, b.smooth_mids),
+        (a.smooth_high, b.smooth_high),
+    ];
+    let scalars = [
+        (a.time, b.time),
+        (a.kaleidoscope, b.kaleidoscope),
+        (a.kaleidoscope_rotation, b.kaleidoscope_rotation),
+        (a.kaleidoscope_axis, b.kaleidoscope_axis),
+        (a.orbit_distance, b.orbit_distance),
+    ];
+    vectors
+        .iter()
+        .flat_map(|(a, b)| a.iter().zip(b))
+        .chain(scalars.iter().map(|(a, b)| (a, b)))
+        .map(|(a, b)| (a - b).abs())
+        .fold(0., f32::max)
+}
+
+impl StaticSceneDetector {
+    // Forget the accumulated samples, such as when the history is lost with the swapchain.
+    pub fn reset(&mut self) {
+        self.previous = None;
+        self.still_frames = 0;
+    }
+
+    // Compare this frame's fractal against the last, and while still, jitter it and accumulate it onto the history.
+    // Each refining frame weighs the history by the samples it holds, so the result converges on their average
+    pub fn refine(
+        &mut self,
+        fractal: &mut FractalPushConstants,
+        temporal_resolve: &mut TemporalResolvePushConstants,
+        viewport_extent: [f32; 2],
+    ) {
+        let still = self
+            .previous
+            .is_some_and(|previous| max_difference(&previous, fractal) <= STILL_TOLERANCE);
+        self.still_frames = if still { self.still_frames + 1 } else { 0 };
+        self.previous = Some(*fractal);
+
+        let Some(samples) = self.still_frames.checked_sub(STILL_FRAMES_BEFORE_REFINING) else {
+            return;
+        };
+        #[allow(clippy::cast_precision_loss)]
+        let weight = (samples + 1) as f32 / (samples + 2) as f32;
+        temporal_resolve.history_weight = weight.min(MAX_REFINEMENT_WEIGHT);
+
+        let index = samples % REFINEMENT_JITTER_SAMPLES + 1;
+        fractal.jitter_x = (halton(index, 2) - 0.5) * 2. / viewport_extent[0];
+        fractal.jitter_y = (halton(index, 3) - 0.5) * 2. / viewport_extent[1];
+    }
+}
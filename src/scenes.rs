@@ -0,0 +1,96 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::app_config::Scene;
+
+// The position of the show being played.
+struct Playback {
+    index: usize, // The scene being shown
+    elapsed: f32, // Seconds the scene has been shown, not counting while paused
+    paused: bool,
+}
+
+// Plays the configured scenes in order, looping back to the first scene after the last.
+#[derive(Default)]
+pub struct Show {
+    playback: Option<Playback>,
+}
+
+impl Show {
+    // Whether the show is playing or paused, rather than stopped.
+    pub fn started(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    // Whether the show is paused on its current scene.
+    pub fn paused(&self) -> bool {
+        self.playback
+            .as_ref()
+            .is_some_and(|playback| playback.paused)
+    }
+
+    // Start the show from its first scene, or pause or resume the show already started.
+    // Returns the index of the scene to begin, if any
+    pub fn toggle_play(&mut self, scenes: &[Scene]) -> Option<usize> {
+        if scenes.is_empty() {
+            return None;
+        }
+        match &mut self.playback {
+            Some(playback) => {
+                playback.paused = !playback.paused;
+                None
+            }
+            None => Some(self.begin(0)),
+        }
+    }
+
+    // Skip to the scene after the current one, starting the show if it was stopped.
+    // Returns the index of the scene to begin, if any
+    pub fn next(&mut self, scenes: &[Scene]) -> Option<usize> {
+        if scenes.is_empty() {
+            return None;
+        }
+        let index = self
+            .playback
+            .as_ref()
+            .map_or(0, |playback| (playback.index + 1) % scenes.len());
+        Some(self.begin(index))
+    }
+
+    // Advance the current scene, returning the index of the next scene once the current one has been shown long enough.
+    pub fn update(&mut self, scenes: &[Scene], delta_time: f32) -> Option<usize> {
+        let playback = self.playback.as_mut().filter(|playback| !playback.paused)?;
+        playback.elapsed += delta_time;
+        if playback.elapsed < scenes[playback.index].duration {
+            return None;
+        }
+        let index = (playback.index + 1) % scenes.len();
+        Some(self.begin(index))
+    }
+
+    // Begin showing the scene, keeping any pause in effect.
+    fn begin(&mut self, index: usize) -> usize {
+        let paused = self.paused();
+        self.playback = Some(Playback {
+            index,
+            elapsed: 0.,
+            paused,
+        });
+        index
+    }
+}
@@ -0,0 +1,93 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crossbeam_channel::{Sender, TrySendError};
+
+use crate::timelapse::write_png;
+
+// Screenshots waiting to be saved. Taking more in quick succession than this drops the extras.
+const MAX_QUEUED_SCREENSHOTS: usize = 4;
+
+// Saves single captured frames as timestamped PNGs, encoding them on a separate thread so the frame loop doesn't hitch.
+pub struct Screenshots {
+    tx: Sender<(PathBuf, [u32; 2], Vec<u8>)>,
+    directory: Option<String>,
+    awaiting_capture: bool,
+}
+
+impl Screenshots {
+    // Spawn the thread saving screenshots, into the given directory or else the user's pictures.
+    pub fn new(directory: Option<String>) -> Self {
+        let (tx, rx) =
+            crossbeam_channel::bounded::<(PathBuf, [u32; 2], Vec<u8>)>(MAX_QUEUED_SCREENSHOTS);
+        std::thread::spawn(move || {
+            for (path, extent, rgba) in rx {
+                let saved = path
+                    .parent()
+                    .map_or(Ok(()), std::fs::create_dir_all)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|()| write_png(&path, extent, &rgba));
+                match saved {
+                    Ok(()) => log!("Saved a screenshot to `{}`", path.display()),
+                    Err(e) => log!("Failed to save screenshot `{}`: {e:?}", path.display()),
+                }
+            }
+        });
+
+        Self {
+            tx,
+            directory,
+            awaiting_capture: false,
+        }
+    }
+
+    // Save the next captured frame as a screenshot.
+    pub fn request(&mut self) {
+        self.awaiting_capture = true;
+    }
+
+    // Queue a captured frame of the given size, given as tightly packed RGBA rows, if a screenshot was requested.
+    pub fn save(&mut self, extent: [u32; 2], rgba: &[u8]) {
+        if !std::mem::take(&mut self.awaiting_capture) {
+            return;
+        }
+        let parent = match &self.directory {
+            Some(directory) => PathBuf::from(directory),
+            None => {
+                let Some(directory) = crate::persistence::pictures_directory() else {
+                    log!("There is no pictures directory to save the screenshot to, set `timelapse_directory`");
+                    return;
+                };
+                directory
+            }
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis());
+        let path = parent.join(format!("screenshot-{timestamp}.png"));
+        match self.tx.try_send((path, extent, rgba.to_vec())) {
+            Ok(()) | Err(TrySendError::Disconnected(_)) => {}
+            Err(TrySendError::Full(_)) => {
+                log!("Skipping a screenshot, the previous screenshots are still being saved");
+            }
+        }
+    }
+}
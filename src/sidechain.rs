@@ -0,0 +1,55 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::app_config::SidechainPump;
+
+// Envelope of the sidechain-style pump, which rises to one over the attack after each kick and then releases to zero.
+#[derive(Default)]
+pub struct PumpEnvelope {
+    pub level: f32,
+    attack_from: f32,        // The level when the latest kick occurred
+    since_kick: Option<f32>, // Seconds since the latest kick, until the release completes
+}
+
+impl PumpEnvelope {
+    // Restart the envelope from its current level, so that rapid kicks do not snap it back to zero.
+    pub fn trigger(&mut self) {
+        self.attack_from = self.level;
+        self.since_kick = Some(0.);
+    }
+
+    pub fn update(&mut self, pump: &SidechainPump, delta_time: f32) {
+        let Some(since_kick) = &mut self.since_kick else {
+            return;
+        };
+        *since_kick += delta_time;
+
+        self.level = if *since_kick < pump.attack {
+            let t = *since_kick / pump.attack;
+            self.attack_from + t * (1. - self.attack_from)
+        } else {
+            let t = (*since_kick - pump.attack) / pump.release;
+            if t >= 1. {
+                self.since_kick = None;
+                0.
+            } else {
+                (1. - t).powf(pump.release_curve)
+            }
+        };
+    }
+}
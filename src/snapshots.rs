@@ -0,0 +1,258 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_config;
+use crate::parameters::ParameterValues;
+use crate::persistence;
+
+// The number of snapshot slots, one for each of the keys 1 through 9.
+const SLOT_COUNT: usize = 9;
+
+const SNAPSHOTS_FILE: &str = "snapshots.toml";
+
+// The complete visual state captured by a snapshot, such that a look can be recalled like a lighting cue.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct Look {
+    #[serde(skip)]
+    pub color_scheme: usize, // Persisted by the name of the scheme, since indices change with the config file
+    pub fractal: u32,                // The distance estimator ID
+    pub parameters: ParameterValues, // The presented values of the shader parameters
+    pub kaleidoscope: bool,          // Whether the kaleidoscope effect is on, or turning on
+    pub particles_are_3d: bool,
+    pub intensity: f32,
+    pub key_hue: bool,
+    pub sidechain_pump: bool,
+}
+
+impl Look {
+    // Interpolate between two looks, where `t` of zero is entirely `a`.
+    // Settings which cannot be blended switch halfway through
+    pub fn lerp(a: &Self, b: &Self, t: f32) -> Self {
+        let nearest = if t < 0.5 { a } else { b };
+        let mut parameters = a.parameters;
+        for (value, target) in parameters.iter_mut().zip(b.parameters) {
+            *value += t * (target - *value);
+        }
+        Self {
+            parameters,
+            intensity: a.intensity + t * (b.intensity - a.intensity),
+            ..*nearest
+        }
+    }
+}
+
+// A look as stored in the snapshots file.
+#[derive(Deserialize, Serialize)]
+struct StoredSnapshot {
+    slot: usize, // Numbered as the keys, beginning from one
+    scheme: String,
+    #[serde(flatten)]
+    look: Look,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct SnapshotsFile {
+    #[serde(default)]
+    snapshot: Vec<StoredSnapshot>,
+}
+
+// The progress of recalling a snapshot, blending from the look shown when it was recalled.
+struct Recall {
+    from: Look,
+    to: Look,
+    elapsed: f32,
+    seconds: f32, // The duration of the blend
+}
+
+impl Recall {
+    // The fraction of the recall completed, where a duration of zero recalls immediately.
+    fn progress(&self) -> f32 {
+        if self.seconds > 0. {
+            (self.elapsed / self.seconds).min(1.)
+        } else {
+            1.
+        }
+    }
+}
+
+// Slots of saved looks, kept in memory and optionally persisted between launches.
+pub struct Snapshots {
+    slots: [Option<Look>; SLOT_COUNT],
+    settings: app_config::Snapshots,
+    recall: Option<Recall>,
+}
+
+impl Snapshots {
+    // Create the slots, loading any persisted snapshots whose color scheme still exists.
+    pub fn new(settings: app_config::Snapshots, scheme_names: &[String]) -> Self {
+        let mut slots = [None; SLOT_COUNT];
+        if settings.persist {
+            for stored in load_file().snapshot {
+                if !(1..=SLOT_COUNT).contains(&stored.slot) {
+                    log!("Ignoring snapshot of unknown slot {}", stored.slot);
+                    continue;
+                }
+                let Some(color_scheme) = scheme_names.iter().position(|n| *n == stored.scheme)
+                else {
+                    log!(
+                        "Ignoring snapshot {} of unknown color scheme `{}`",
+                        stored.slot,
+                        stored.scheme
+                    );
+                    continue;
+                };
+                slots[stored.slot - 1] = Some(Look {
+                    color_scheme,
+                    ..stored.look
+                });
+            }
+        }
+
+        Self {
+            slots,
+            settings,
+            recall: None,
+        }
+    }
+
+    // Save the look to the slot, persisting every slot if configured.
+    pub fn save(&mut self, slot: usize, look: Look, scheme_names: &[String]) {
+        self.slots[slot] = Some(look);
+        if self.settings.persist {
+            self.save_file(scheme_names);
+        }
+    }
+
+    // Forget the slots of a removed color scheme, shifting down the schemes after it.
+    // Any recall in progress is stopped, since it may blend from the removed scheme
+    pub fn remove_color_scheme(&mut self, removed: usize, scheme_names: &[String]) {
+        for slot in &mut self.slots {
+            let Some(look) = slot else {
+                continue;
+            };
+            if look.color_scheme == removed {
+                *slot = None;
+            } else if look.color_scheme > removed {
+                look.color_scheme -= 1;
+            }
+        }
+        self.recall = None;
+        if self.settings.persist {
+            self.save_file(scheme_names);
+        }
+    }
+
+    // Persist the slots again under the new names of their color schemes.
+    pub fn rename_color_schemes(&self, scheme_names: &[String]) {
+        if self.settings.persist {
+            self.save_file(scheme_names);
+        }
+    }
+
+    // Begin blending from the current look to the one saved in the slot, returning whether the slot was saved.
+    // The look being recalled replaces any recall already in progress
+    pub fn recall(&mut self, slot: usize, current: Look) -> bool {
+        let Some(to) = self.slots[slot] else {
+            return false;
+        };
+        self.blend(current, to, self.settings.recall_seconds);
+        true
+    }
+
+    // Begin blending from the current look to any other over the given seconds, as though it were recalled.
+    pub fn blend(&mut self, current: Look, to: Look, seconds: f32) {
+        self.recall = Some(Recall {
+            from: current,
+            to,
+            elapsed: 0.,
+            seconds,
+        });
+    }
+
+    // Advance any recall in progress, returning the looks it blends between and the eased position of the blend.
+    pub fn update(&mut self, delta_time: f32) -> Option<(Look, Look, f32)> {
+        let recall = self.recall.as_mut()?;
+        recall.elapsed += delta_time;
+        let t = recall.progress();
+        let step = (recall.from, recall.to, t * t * (3. - 2. * t));
+        if t >= 1. {
+            self.recall = None;
+        }
+        Some(step)
+    }
+
+    // The multiplier of the fractal's brightness, dipping to black as a recall switches between fractals.
+    pub fn fractal_fade(&self) -> f32 {
+        match &self.recall {
+            Some(recall) if recall.from.fractal != recall.to.fractal => {
+                (2. * recall.progress() - 1.).abs()
+            }
+            _ => 1.,
+        }
+    }
+
+    fn save_file(&self, scheme_names: &[String]) {
+        let Some(dir) = persistence::state_directory() else {
+            return;
+        };
+        let file = SnapshotsFile {
+            snapshot: self
+                .slots
+                .iter()
+                .enumerate()
+                .filter_map(|(i, look)| {
+                    look.map(|look| StoredSnapshot {
+                        slot: i + 1,
+                        scheme: scheme_names[look.color_scheme].clone(),
+                        look,
+                    })
+                })
+                .collect(),
+        };
+        let result = toml::to_string(&file)
+            .map_err(anyhow::Error::from)
+            .and_then(|contents| {
+                std::fs::create_dir_all(&dir)?;
+                std::fs::write(dir.join(SNAPSHOTS_FILE), contents)?;
+                Ok(())
+            });
+        if let Err(e) = result {
+            log!("Failed to persist the snapshots: {e:?}");
+        }
+    }
+}
+
+// Read the persisted snapshots, if any.
+fn load_file() -> SnapshotsFile {
+    let Some(path) = persistence::state_directory().map(|dir| dir.join(SNAPSHOTS_FILE)) else {
+        return SnapshotsFile::default();
+    };
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return SnapshotsFile::default();
+    };
+    toml::from_str(&contents).unwrap_or_else(|e| {
+        log!(
+            "Failed to parse the snapshots file `{}`: {e}",
+            path.display()
+        );
+        SnapshotsFile::default()
+    })
+}
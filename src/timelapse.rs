@@ -0,0 +1,145 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crossbeam_channel::{Sender, TrySendError};
+
+use crate::app_config;
+
+// Frames waiting to be saved. Encoding takes far less than any interval, so a full queue means the disk has stalled.
+const MAX_QUEUED_FRAMES: usize = 4;
+
+// When each frame of a timelapse is due.
+enum Schedule {
+    Interval(Duration),
+    Beats(u32),
+}
+
+// Saves a rendered frame into a new session folder every so often, encoding the frames on a separate thread.
+pub struct Timelapse {
+    tx: Sender<(PathBuf, Vec<u8>)>,
+    directory: PathBuf,
+    schedule: Schedule,
+    last_frame: Option<Instant>,
+    kicks: u32, // Kicks since the last frame
+    awaiting_capture: bool,
+    frame_count: usize,
+}
+
+impl Timelapse {
+    // Create the session folder and spawn the thread saving captured frames of the given size into it.
+    pub fn start(config: &app_config::Timelapse, extent: [u32; 2]) -> anyhow::Result<Self> {
+        let parent = match &config.directory {
+            Some(directory) => PathBuf::from(directory),
+            None => crate::persistence::pictures_directory().ok_or_else(|| {
+                anyhow::anyhow!("There is no pictures directory, set `timelapse_directory`")
+            })?,
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let directory = parent.join(format!("timelapse-{timestamp}"));
+        std::fs::create_dir_all(&directory)?;
+
+        let (tx, rx) = crossbeam_channel::bounded::<(PathBuf, Vec<u8>)>(MAX_QUEUED_FRAMES);
+        std::thread::spawn(move || {
+            for (path, rgba) in rx {
+                if let Err(e) = write_png(&path, extent, &rgba) {
+                    log!("Failed to save timelapse frame `{}`: {e:?}", path.display());
+                }
+            }
+        });
+
+        log!("Saving a timelapse to `{}`", directory.display());
+        Ok(Self {
+            tx,
+            directory,
+            schedule: match config.beats {
+                Some(beats) => Schedule::Beats(beats),
+                None => Schedule::Interval(Duration::from_secs_f32(config.interval)),
+            },
+            last_frame: None,
+            kicks: 0,
+            awaiting_capture: false,
+            frame_count: 0,
+        })
+    }
+
+    // Count a kick towards the next frame, when frames are saved every few beats.
+    pub fn record_kick(&mut self) {
+        self.kicks += 1;
+    }
+
+    // Whether the next rendered frame should be captured for the timelapse. The first frame is due immediately
+    pub fn frame_due(&mut self) -> bool {
+        let due = match (&self.schedule, self.last_frame) {
+            (_, None) => true,
+            (Schedule::Interval(interval), Some(last_frame)) => last_frame.elapsed() >= *interval,
+            (Schedule::Beats(beats), Some(_)) => self.kicks >= *beats,
+        };
+        if due {
+            self.last_frame = Some(Instant::now());
+            self.kicks = 0;
+            self.awaiting_capture = true;
+        }
+        due
+    }
+
+    // Queue a captured frame, given as tightly packed RGBA rows, to be saved if the timelapse requested it.
+    pub fn save_frame(&mut self, rgba: &[u8]) {
+        if !std::mem::take(&mut self.awaiting_capture) {
+            return;
+        }
+        let path = self
+            .directory
+            .join(format!("frame-{:05}.png", self.frame_count));
+        match self.tx.try_send((path, rgba.to_vec())) {
+            Ok(()) => self.frame_count += 1,
+            Err(TrySendError::Full(_)) => {
+                log!("Skipping a timelapse frame, the previous frames are still being saved");
+            }
+            Err(TrySendError::Disconnected(_)) => {}
+        }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+}
+
+// Write the frame as an opaque PNG, since the alpha of rendered frames is not meaningful.
+pub fn write_png(path: &Path, [width, height]: [u32; 2], rgba: &[u8]) -> anyhow::Result<()> {
+    let rgb: Vec<u8> = rgba
+        .chunks_exact(4)
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect();
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&rgb)?;
+    writer.finish()?;
+    Ok(())
+}
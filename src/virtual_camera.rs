@@ -0,0 +1,472 @@
+/*
+    fractal_sugar - An experimental audio visualizer combining fractals and particle simulations.
+    Copyright (C) 2022,2023  Ryan Andersen
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::Sender;
+
+use crate::app_config;
+
+#[cfg(target_os = "windows")]
+use obs::Output;
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+use unsupported::Output;
+#[cfg(target_os = "linux")]
+use v4l2::Output;
+
+// Publishes rendered frames as a virtual webcam, converting and writing them on a separate thread.
+pub struct VirtualCamera {
+    tx: Sender<Vec<u8>>,
+    frame_interval: Duration,
+    last_frame: Option<Instant>,
+}
+
+impl VirtualCamera {
+    // Open the configured output device and spawn the thread writing frames to it.
+    pub fn open(config: &app_config::VirtualCamera) -> anyhow::Result<Self> {
+        let mut output = Output::open(config)?;
+        log!(
+            "Publishing a {}x{} virtual camera at {} fps to {}",
+            config.width,
+            config.height,
+            config.fps,
+            output.destination()
+        );
+
+        // Only the latest frame is kept, so a slow consumer skips frames rather than delaying the render loop.
+        let (tx, rx) = crossbeam_channel::bounded::<Vec<u8>>(1);
+        std::thread::spawn(move || {
+            for rgba in rx {
+                if let Err(e) = output.write_frame(&rgba) {
+                    log!("Failed to write to the virtual camera, it has been stopped: {e:?}");
+                    return;
+                }
+            }
+        });
+
+        Ok(Self {
+            tx,
+            frame_interval: Duration::from_secs_f64(1. / f64::from(config.fps)),
+            last_frame: None,
+        })
+    }
+
+    // Whether enough time has passed since the last frame that another should be captured.
+    pub fn frame_due(&mut self) -> bool {
+        let now = Instant::now();
+        match self.last_frame {
+            Some(last_frame) if now.duration_since(last_frame) < self.frame_interval => false,
+            _ => {
+                self.last_frame = Some(now);
+                true
+            }
+        }
+    }
+
+    // Queue a captured frame, given as tightly packed RGBA rows, to be published.
+    pub fn send_frame(&self, rgba: &[u8]) {
+        // A full channel skips this frame, while a disconnected one means writing has already failed.
+        let _ = self.tx.try_send(rgba.to_vec());
+    }
+}
+
+// The BT.601 limited-range luma of an RGBA pixel, as expected by most webcam consumers.
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+fn luma(p: &[u8]) -> f32 {
+    16. + 0.257 * f32::from(p[0]) + 0.504 * f32::from(p[1]) + 0.098 * f32::from(p[2])
+}
+
+// The BT.601 limited-range chroma, U then V, of the given color.
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+fn chroma([r, g, b]: [f32; 3]) -> [f32; 2] {
+    [
+        128. - 0.148 * r - 0.291 * g + 0.439 * b,
+        128. + 0.439 * r - 0.368 * g - 0.071 * b,
+    ]
+}
+
+// Convert RGBA pixels to the packed YUYV format, where each pair of horizontal pixels shares the average of their chroma.
+#[cfg(target_os = "linux")]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn rgba_to_yuyv(rgba: &[u8], width: usize, yuyv: &mut Vec<u8>) {
+    yuyv.clear();
+    yuyv.reserve(rgba.len() / 2);
+    for row in rgba.chunks_exact(4 * width) {
+        for pair in row.chunks_exact(8) {
+            let (left, right) = pair.split_at(4);
+            let [u, v] = chroma([0, 1, 2].map(|i| (f32::from(left[i]) + f32::from(right[i])) / 2.));
+            yuyv.extend_from_slice(&[luma(left) as u8, u as u8, luma(right) as u8, v as u8]);
+        }
+    }
+}
+
+// Convert RGBA pixels to the planar NV12 format, a plane of luma followed by the interleaved chroma of each 2x2 block.
+#[cfg(target_os = "windows")]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn rgba_to_nv12(rgba: &[u8], width: usize, nv12: &mut [u8]) {
+    let (luma_plane, chroma_plane) = nv12.split_at_mut(rgba.len() / 4);
+    for (pixel, y) in rgba.chunks_exact(4).zip(luma_plane) {
+        *y = luma(pixel) as u8;
+    }
+    for (rows, uv_row) in rgba
+        .chunks_exact(8 * width)
+        .zip(chroma_plane.chunks_exact_mut(width))
+    {
+        let (top, bottom) = rows.split_at(4 * width);
+        let blocks = top.chunks_exact(8).zip(bottom.chunks_exact(8));
+        for ((top, bottom), uv) in blocks.zip(uv_row.chunks_exact_mut(2)) {
+            let [u, v] = chroma([0, 1, 2].map(|i| {
+                (f32::from(top[i])
+                    + f32::from(top[i + 4])
+                    + f32::from(bottom[i])
+                    + f32::from(bottom[i + 4]))
+                    / 4.
+            }));
+            uv.copy_from_slice(&[u as u8, v as u8]);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod v4l2 {
+    use std::fs::{File, OpenOptions};
+    use std::io::Write;
+    use std::os::fd::AsRawFd;
+
+    use crate::app_config;
+
+    // Definitions from the kernel's `videodev2.h`.
+    const BUF_TYPE_VIDEO_OUTPUT: u32 = 2;
+    const FIELD_NONE: u32 = 1;
+    const COLORSPACE_SRGB: u32 = 8;
+    const PIX_FMT_YUYV: u32 = u32::from_le_bytes(*b"YUYV");
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct PixFormat {
+        width: u32,
+        height: u32,
+        pixel_format: u32,
+        field: u32,
+        bytes_per_line: u32,
+        size_image: u32,
+        colorspace: u32,
+        private: u32,
+        flags: u32,
+        ycbcr_encoding: u32,
+        quantization: u32,
+        transfer_function: u32,
+    }
+
+    // The kernel reserves space for larger formats, some of which contain pointers.
+    #[repr(C)]
+    union FormatData {
+        pix: PixFormat,
+        raw_data: [u8; 200],
+        _pointer_alignment: [*const u8; 0],
+    }
+
+    #[repr(C)]
+    struct Format {
+        kind: u32,
+        data: FormatData,
+    }
+
+    // The `VIDIOC_S_FMT` request code, i.e. `_IOWR('V', 5, struct v4l2_format)`.
+    #[allow(clippy::cast_lossless, clippy::cast_possible_truncation)]
+    const VIDIOC_S_FMT: u64 =
+        (3 << 30) | ((std::mem::size_of::<Format>() as u64) << 16) | ((b'V' as u64) << 8) | 5;
+
+    // A `v4l2loopback` device accepting frames in the YUYV format.
+    pub struct Output {
+        file: File,
+        device: String,
+        width: usize,
+        yuyv: Vec<u8>,
+    }
+
+    impl Output {
+        // Open the configured device for writing frames of the configured size.
+        pub fn open(config: &app_config::VirtualCamera) -> anyhow::Result<Self> {
+            let (device, width, height) = (config.device.as_str(), config.width, config.height);
+            let file = OpenOptions::new().write(true).open(device).map_err(|e| {
+                anyhow::anyhow!("Failed to open virtual camera device `{device}`: {e}")
+            })?;
+
+            let mut format = Format {
+                kind: BUF_TYPE_VIDEO_OUTPUT,
+                data: FormatData { raw_data: [0; 200] },
+            };
+            format.data.pix = PixFormat {
+                width,
+                height,
+                pixel_format: PIX_FMT_YUYV,
+                field: FIELD_NONE,
+                bytes_per_line: 2 * width,
+                size_image: 2 * width * height,
+                colorspace: COLORSPACE_SRGB,
+                private: 0,
+                flags: 0,
+                ycbcr_encoding: 0,
+                quantization: 0,
+                transfer_function: 0,
+            };
+
+            // Safety: the format matches the layout the kernel expects for this request.
+            // The type of the request code differs between C libraries
+            #[allow(clippy::cast_possible_truncation)]
+            let result = unsafe {
+                libc::ioctl(
+                    file.as_raw_fd(),
+                    VIDIOC_S_FMT as _,
+                    std::ptr::addr_of_mut!(format),
+                )
+            };
+            if result < 0 {
+                anyhow::bail!(
+                    "Device `{device}` does not accept {width}x{height} YUYV output, is it a `v4l2loopback` device? {}",
+                    std::io::Error::last_os_error()
+                );
+            }
+            Ok(Self {
+                file,
+                device: device.to_owned(),
+                width: width as usize,
+                yuyv: Vec::new(),
+            })
+        }
+
+        pub fn destination(&self) -> &str {
+            &self.device
+        }
+
+        pub fn write_frame(&mut self, rgba: &[u8]) -> std::io::Result<()> {
+            super::rgba_to_yuyv(rgba, self.width, &mut self.yuyv);
+            self.file.write_all(&self.yuyv)
+        }
+    }
+}
+
+// Windows has no virtual camera without a registered DirectShow or Media Foundation driver, so frames are given to the one
+// installed with OBS Studio. It reads from a shared-memory queue, as defined by OBS's `shared-memory-queue.c`
+#[cfg(target_os = "windows")]
+mod obs {
+    use std::ffi::c_void;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Instant;
+
+    use crate::app_config;
+
+    const QUEUE_NAME: &str = "OBSVirtualCamVideo";
+    const QUEUE_TYPE_VIDEO: u32 = 0;
+    const QUEUE_STATE_STARTING: u32 = 1;
+    const QUEUE_STATE_READY: u32 = 2;
+    const QUEUE_STATE_STOPPING: u32 = 3;
+    const QUEUE_FRAME_COUNT: usize = 3;
+    const FRAME_HEADER_SIZE: usize = 32; // Begins with the timestamp of the frame
+
+    #[repr(C)]
+    #[allow(dead_code)] // Read by the camera
+    struct QueueHeader {
+        write_index: AtomicU32,
+        read_index: AtomicU32,
+        state: AtomicU32,
+        offsets: [u32; QUEUE_FRAME_COUNT],
+        kind: u32,
+        width: u32,
+        height: u32,
+        interval: u64, // In units of 100 nanoseconds
+        reserved: [u32; 8],
+    }
+
+    // Definitions from the Windows API.
+    const INVALID_HANDLE_VALUE: isize = -1;
+    const PAGE_READWRITE: u32 = 0x04;
+    const FILE_MAP_ALL_ACCESS: u32 = 0x000F_001F;
+    const ERROR_ALREADY_EXISTS: u32 = 183;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateFileMappingW(
+            file: isize,
+            attributes: *const c_void,
+            protect: u32,
+            maximum_size_high: u32,
+            maximum_size_low: u32,
+            name: *const u16,
+        ) -> isize;
+        fn MapViewOfFile(
+            mapping: isize,
+            desired_access: u32,
+            offset_high: u32,
+            offset_low: u32,
+            bytes_to_map: usize,
+        ) -> *mut c_void;
+        fn UnmapViewOfFile(base_address: *const c_void) -> i32;
+        fn CloseHandle(handle: isize) -> i32;
+        fn GetLastError() -> u32;
+    }
+
+    // The shared memory of the OBS Virtual Camera, holding a queue of frames in the NV12 format.
+    pub struct Output {
+        mapping: isize,
+        header: *mut QueueHeader, // The start of the mapped view
+        frame_size: usize,
+        width: usize,
+        start: Instant,
+    }
+
+    // Safety: the mapped view is only accessed by the thread which owns the output.
+    unsafe impl Send for Output {}
+
+    impl Output {
+        // Create the shared memory the camera reads from, which fails while another application publishes to it.
+        #[allow(clippy::cast_possible_truncation)]
+        pub fn open(config: &app_config::VirtualCamera) -> anyhow::Result<Self> {
+            let width = config.width as usize;
+            let frame_size = width * config.height as usize * 3 / 2;
+
+            // Each frame, following its header, is aligned to 32 bytes
+            let align = |size: usize| (size + 31) & !31;
+            let mut offsets = [0; QUEUE_FRAME_COUNT];
+            let mut size = align(std::mem::size_of::<QueueHeader>());
+            for offset in &mut offsets {
+                *offset = size as u32;
+                size = align(size + FRAME_HEADER_SIZE + frame_size);
+            }
+
+            let name: Vec<u16> = QUEUE_NAME.encode_utf16().chain([0]).collect();
+            // Safety: the name is null-terminated, and the mapping is backed by the paging file.
+            let mapping = unsafe {
+                CreateFileMappingW(
+                    INVALID_HANDLE_VALUE,
+                    std::ptr::null(),
+                    PAGE_READWRITE,
+                    0,
+                    size as u32,
+                    name.as_ptr(),
+                )
+            };
+            if mapping == 0 {
+                anyhow::bail!(
+                    "Failed to create the shared memory of the OBS Virtual Camera: {}",
+                    std::io::Error::last_os_error()
+                );
+            }
+            // Safety: the handle was just created, and is closed once.
+            unsafe {
+                if GetLastError() == ERROR_ALREADY_EXISTS {
+                    CloseHandle(mapping);
+                    anyhow::bail!(
+                        "The OBS Virtual Camera is already in use by another application"
+                    );
+                }
+            }
+
+            // Safety: the entire mapping is viewed, which is page-aligned and larger than the header.
+            let header = unsafe { MapViewOfFile(mapping, FILE_MAP_ALL_ACCESS, 0, 0, 0) }
+                .cast::<QueueHeader>();
+            if header.is_null() {
+                let error = std::io::Error::last_os_error();
+                unsafe { CloseHandle(mapping) };
+                anyhow::bail!("Failed to map the shared memory of the OBS Virtual Camera: {error}");
+            }
+            unsafe {
+                header.write(QueueHeader {
+                    write_index: AtomicU32::new(0),
+                    read_index: AtomicU32::new(0),
+                    state: AtomicU32::new(QUEUE_STATE_STARTING),
+                    offsets,
+                    kind: QUEUE_TYPE_VIDEO,
+                    width: config.width,
+                    height: config.height,
+                    interval: 10_000_000 / u64::from(config.fps),
+                    reserved: [0; 8],
+                });
+            }
+
+            Ok(Self {
+                mapping,
+                header,
+                frame_size,
+                width,
+                start: Instant::now(),
+            })
+        }
+
+        #[allow(clippy::unused_self)]
+        pub fn destination(&self) -> &'static str {
+            "the OBS Virtual Camera"
+        }
+
+        // Write the frame to the next slot of the queue, then mark it as the latest to be read.
+        #[allow(clippy::cast_possible_truncation, clippy::unnecessary_wraps)]
+        pub fn write_frame(&mut self, rgba: &[u8]) -> std::io::Result<()> {
+            // Safety: the header was written when the queue was created, and is only modified atomically.
+            let header = unsafe { &*self.header };
+            let index = header.write_index.fetch_add(1, Ordering::AcqRel) + 1;
+            let offset = header.offsets[index as usize % QUEUE_FRAME_COUNT] as usize;
+
+            // Safety: each slot of the mapping holds a frame header followed by a frame.
+            unsafe {
+                let slot = self.header.cast::<u8>().add(offset);
+                slot.cast::<[u8; 8]>()
+                    .write((self.start.elapsed().as_nanos() as u64).to_ne_bytes());
+                let frame =
+                    std::slice::from_raw_parts_mut(slot.add(FRAME_HEADER_SIZE), self.frame_size);
+                super::rgba_to_nv12(rgba, self.width, frame);
+            }
+            header.read_index.store(index, Ordering::Release);
+            header.state.store(QUEUE_STATE_READY, Ordering::Release);
+            Ok(())
+        }
+    }
+
+    impl Drop for Output {
+        fn drop(&mut self) {
+            // Safety: the view and handle are valid until released here.
+            unsafe {
+                (*self.header)
+                    .state
+                    .store(QUEUE_STATE_STOPPING, Ordering::Release);
+                UnmapViewOfFile(self.header.cast());
+                CloseHandle(self.mapping);
+            }
+        }
+    }
+}
+
+// Other platforms have no supported virtual camera.
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+mod unsupported {
+    use crate::app_config;
+
+    pub enum Output {}
+
+    impl Output {
+        pub fn open(_config: &app_config::VirtualCamera) -> anyhow::Result<Self> {
+            anyhow::bail!("Virtual cameras are only supported on Linux and Windows")
+        }
+        pub fn destination(&self) -> &str {
+            match *self {}
+        }
+        pub fn write_frame(&mut self, _rgba: &[u8]) -> std::io::Result<()> {
+            match *self {}
+        }
+    }
+}